@@ -63,12 +63,19 @@ fn format_func_type<'a>(name: String, t: FunctionType<'a>) -> String {
         .flatten()
         .collect::<Vec<_>>()
         .join(", ");
+    let func_sig = if t.variadic {
+        format!("{}, ...", func_sig)
+    } else {
+        func_sig
+    };
     let ret = &format_var_type(String::from(""), t.signature.1)[0].signatures[0];
     format!("{}({}) -> {}", name, func_sig, ret)
 }
 
 fn format_func_types<'a>(name: String, t: Rc<FunctionTypes<'a>>) -> Vec<String> {
-    t.0.iter()
+    t.instances()
+        .0
+        .iter()
         .map(|t| format_func_type(name.clone(), t.clone()))
         .collect()
 }
@@ -97,6 +104,7 @@ fn format_var_type<'a>(name: String, t: SyntaxType<'a>) -> Vec<NameInfo> {
         SyntaxType::Simple(SimpleSyntaxType::Na) => vec![NameInfo::simple_var(name, "na")],
         SyntaxType::Simple(SimpleSyntaxType::Color) => vec![NameInfo::simple_var(name, "color")],
         SyntaxType::Simple(SimpleSyntaxType::String) => vec![NameInfo::simple_var(name, "string")],
+        SyntaxType::Simple(SimpleSyntaxType::Template) => vec![NameInfo::simple_var(name, "T")],
 
         SyntaxType::Series(SimpleSyntaxType::Int) => {
             vec![NameInfo::simple_var(name, "series(int)")]
@@ -114,6 +122,9 @@ fn format_var_type<'a>(name: String, t: SyntaxType<'a>) -> Vec<NameInfo> {
         SyntaxType::Series(SimpleSyntaxType::String) => {
             vec![NameInfo::simple_var(name, "series(string)")]
         }
+        SyntaxType::Series(SimpleSyntaxType::Template) => {
+            vec![NameInfo::simple_var(name, "series(T)")]
+        }
 
         SyntaxType::List(sub_t) => vec![NameInfo::simple_var2(
             name,
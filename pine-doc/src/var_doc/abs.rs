@@ -8,7 +8,7 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: "",
         returns: "The absolute value of x",
         arguments: "",
-        remarks: "",
+        remarks: "The result is int if x is int. It is also available as `math.abs(number)`.",
         links: "",
     };
     vec![fn_doc]
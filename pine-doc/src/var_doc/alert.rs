@@ -0,0 +1,73 @@
+use crate::{DocBase, VarType};
+
+const DESCRIPTION: &'static str = r#"
+Creates an alert event when called during the bar. The event is sent to the host by the `on_alert` method of the output callback. The frequency limits how often the call sends the event.
+"#;
+
+const EXAMPLE: &'static str = r#"
+```pine
+//@version=4
+study("`alert()` example")
+xUp = crossover(close, sma(close, 14))
+if xUp
+    alert("Price crossed above the MA")
+```
+"#;
+
+const ARGUMENT: &'static str = r#"
+**message (series(string))** Message sent when the alert triggers.
+**freq (string)** The triggering frequency. Possible values are: [alert.freq_all](#var-alert-freq_all) (all calls trigger the alert), [alert.freq_once_per_bar](#var-alert-freq_once_per_bar) (the first call during the bar triggers the alert), [alert.freq_once_per_bar_close](#var-alert-freq_once_per_bar_close) (the call triggers the alert only when the bar is closed). The default is [alert.freq_once_per_bar](#var-alert-freq_once_per_bar).
+"#;
+
+const REMARKS: &'static str = r#"
+The alert of the bar is not sent again when the bar is calculated again by the update. The last bar of the data may still be updated, so the alert with [alert.freq_once_per_bar_close](#var-alert-freq_once_per_bar_close) on it is sent by the update that adds the next bar.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Function,
+            name: "alert",
+            signatures: vec![],
+            description: DESCRIPTION,
+            example: EXAMPLE,
+            returns: "",
+            arguments: ARGUMENT,
+            remarks: REMARKS,
+            links: "",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "alert.freq_all",
+            signatures: vec![],
+            description: "A named constant for use with the `freq` parameter of the [alert](#fun-alert) function. All function calls trigger the alert.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: "[alert](#fun-alert)",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "alert.freq_once_per_bar",
+            signatures: vec![],
+            description: "A named constant for use with the `freq` parameter of the [alert](#fun-alert) function. The first function call during the bar triggers the alert.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: "[alert](#fun-alert)",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "alert.freq_once_per_bar_close",
+            signatures: vec![],
+            description: "A named constant for use with the `freq` parameter of the [alert](#fun-alert) function. The function call triggers the alert only when it occurs during the last script iteration of the bar, when the bar closes.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: "[alert](#fun-alert)",
+        },
+    ]
+}
@@ -0,0 +1,63 @@
+use crate::{DocBase, VarType};
+
+const GAPS_LINKS: &'static str = r#"
+[barmerge.gaps_on](#var-barmerge-gaps_on) [barmerge.gaps_off](#var-barmerge-gaps_off)
+"#;
+
+const LOOKAHEAD_LINKS: &'static str = r#"
+[barmerge.lookahead_on](#var-barmerge-lookahead_on) [barmerge.lookahead_off](#var-barmerge-lookahead_off)
+"#;
+
+const LOOKAHEAD_ON_REMARK: &'static str = r#"
+On the historical bars, the requested value is the value of the higher timeframe bar that contains the current bar, which is only known in the future. Use the previous value like `close[1]` to avoid repainting.
+In the realtime, the higher timeframe bar is not completed yet, so the latest available value is returned.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "barmerge.gaps_off",
+            signatures: vec![],
+            description: "Merge strategy for requested data. Data is merged continuously without gaps, all the gaps are filled with the previous nearest existing value.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: GAPS_LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "barmerge.gaps_on",
+            signatures: vec![],
+            description: "Merge strategy for requested data. Data is merged with possible gaps (na values).",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: GAPS_LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "barmerge.lookahead_off",
+            signatures: vec![],
+            description: "Merge strategy for the requested data position. Requested barset is merged with current barset in the order of sorting bars by their close time. This merge strategy disables effect of getting data from \"future\" on calculation on history.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LOOKAHEAD_LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "barmerge.lookahead_on",
+            signatures: vec![],
+            description: "Merge strategy for the requested data position. Requested barset is merged with current barset in the order of sorting bars by their opening time. This merge strategy can lead to undesirable effect of getting data from \"future\" on calculation on history.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: LOOKAHEAD_ON_REMARK,
+            links: LOOKAHEAD_LINKS,
+        },
+    ]
+}
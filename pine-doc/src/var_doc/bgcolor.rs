@@ -0,0 +1,64 @@
+use crate::{DocBase, VarType};
+
+const BGCOLOR_DESCRIPTION: &'static str = r#"
+Fill background of bars with specified color.
+"#;
+
+const BGCOLOR_EXAMPLES: &'static str = r#"
+```pine
+bgcolor(close < open ? color.red : color.green, transp=70)
+```
+"#;
+
+const BGCOLOR_ARGUMENTS: &'static str = r#"
+color (color) Color of the filled background. You can use constants like 'red' or '#ff001a' as well as complex expressions like 'close >= open ? color.green : color.red'. Required argument.
+transp (int) Transparency of the filled background. Possible values are from 0 (not transparent) to 100 (invisible). Optional argument.
+offset (int) Shifts the color series to the left or to the right on the given number of bars. Default is 0.
+editable (bool) If true then bgcolor style will be editable in Format dialog. Default is true.
+show_last (int) If set, defines the number of bars (from the last bar back to the past) to fill on chart.
+title (string) Title of the bgcolor. Optional argument.
+"#;
+
+const BARCOLOR_DESCRIPTION: &'static str = r#"
+Set color of bars.
+"#;
+
+const BARCOLOR_EXAMPLES: &'static str = r#"
+```pine
+barcolor(close < open ? color.black : color.white)
+```
+"#;
+
+const BARCOLOR_ARGUMENTS: &'static str = r#"
+color (color) Color of bars. You can use constants like 'red' or '#ff001a' as well as complex expressions like 'close >= open ? color.green : color.red'. Required argument.
+offset (int) Shifts the color series to the left or to the right on the given number of bars. Default is 0.
+editable (bool) If true then barcolor style will be editable in Format dialog. Default is true.
+show_last (int) If set, defines the number of bars (from the last bar back to the past) to fill on chart.
+title (string) Title of the barcolor. Optional argument.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let bgcolor_doc = DocBase {
+        var_type: VarType::Function,
+        name: "bgcolor",
+        signatures: vec![],
+        description: BGCOLOR_DESCRIPTION,
+        example: BGCOLOR_EXAMPLES,
+        returns: "",
+        arguments: BGCOLOR_ARGUMENTS,
+        remarks: "Multiple bgcolor calls are painted in the order of the calls, the later background is blended over the earlier ones with the alpha of its color and the `transp` argument.",
+        links: "[barcolor](#fun-barcolor)",
+    };
+    let barcolor_doc = DocBase {
+        var_type: VarType::Function,
+        name: "barcolor",
+        signatures: vec![],
+        description: BARCOLOR_DESCRIPTION,
+        example: BARCOLOR_EXAMPLES,
+        returns: "",
+        arguments: BARCOLOR_ARGUMENTS,
+        remarks: "",
+        links: "[bgcolor](#fun-bgcolor)",
+    };
+    vec![bgcolor_doc, barcolor_doc]
+}
@@ -18,7 +18,8 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: "",
         returns: "Commodity channel index of x for y bars back.",
         arguments: PINE_FN_ARGUMENTS,
-        remarks: "",
+        remarks: "Two args version: x is a series and y is a length.
+        One arg version: x is a length. Algorithm uses the typical price hlc3 as a source series.",
         links: "",
     };
     vec![fn_doc]
@@ -0,0 +1,142 @@
+use crate::{DocBase, VarType};
+
+const NEW_ARGUMENT: &'static str = r#"
+**time (series(int))** The x-coordinate of the point, expressed as a UNIX time value.
+**index (series(int))** The x-coordinate of the point, expressed as a bar index value.
+**price (series(float))** The y-coordinate of the point.
+"#;
+
+const FROM_INDEX_ARGUMENT: &'static str = r#"
+**index (series(int))** The x-coordinate of the point, expressed as a bar index value.
+**price (series(float))** The y-coordinate of the point.
+"#;
+
+const FROM_TIME_ARGUMENT: &'static str = r#"
+**time (series(int))** The x-coordinate of the point, expressed as a UNIX time value.
+**price (series(float))** The y-coordinate of the point.
+"#;
+
+const NOW_ARGUMENT: &'static str = r#"
+**price (series(float))** The y-coordinate of the point.
+"#;
+
+const COPY_ARGUMENT: &'static str = r#"
+**id (chart.point)** The point to copy.
+"#;
+
+const EXAMPLE: &'static str = r#"
+```pine
+//@version=5
+indicator("`chart.point` example", overlay = true)
+points = [chart.point.now(high), chart.point.from_index(bar_index - 10, low)]
+polyline.new(points)
+```
+"#;
+
+const REMARKS: &'static str = r#"
+The value is provided by the host application and it is constant during the run. If the host provides no chart information, the value is na.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "chart.bg_color",
+            signatures: vec![],
+            description: "Returns the color of the chart's background.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: REMARKS,
+            links: "[chart.fg_color](#var_chart.fg_color)",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "chart.fg_color",
+            signatures: vec![],
+            description: "Returns a color providing optimal contrast with chart.bg_color.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: REMARKS,
+            links: "[chart.bg_color](#var_chart.bg_color)",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "chart.left_visible_bar_time",
+            signatures: vec![],
+            description: "The time of the leftmost bar currently visible on the chart.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: REMARKS,
+            links: "[chart.right_visible_bar_time](#var_chart.right_visible_bar_time)",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "chart.right_visible_bar_time",
+            signatures: vec![],
+            description: "The time of the rightmost bar currently visible on the chart.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: REMARKS,
+            links: "[chart.left_visible_bar_time](#var_chart.left_visible_bar_time)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "chart.point.new",
+            signatures: vec![],
+            description: "Creates a new point with the time, the bar index and the price.",
+            example: EXAMPLE,
+            returns: "The chart.point object.",
+            arguments: NEW_ARGUMENT,
+            remarks: "",
+            links: "[chart.point.from_index](#fun_chart.point.from_index) [chart.point.from_time](#fun_chart.point.from_time)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "chart.point.from_index",
+            signatures: vec![],
+            description: "Creates a new point with the bar index and the price, the time of the point is na.",
+            example: "",
+            returns: "The chart.point object.",
+            arguments: FROM_INDEX_ARGUMENT,
+            remarks: "",
+            links: "[chart.point.new](#fun_chart.point.new)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "chart.point.from_time",
+            signatures: vec![],
+            description: "Creates a new point with the time and the price, the bar index of the point is na.",
+            example: "",
+            returns: "The chart.point object.",
+            arguments: FROM_TIME_ARGUMENT,
+            remarks: "",
+            links: "[chart.point.new](#fun_chart.point.new)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "chart.point.now",
+            signatures: vec![],
+            description: "Creates a new point with the time and the bar index of the current bar.",
+            example: "",
+            returns: "The chart.point object.",
+            arguments: NOW_ARGUMENT,
+            remarks: "",
+            links: "[chart.point.new](#fun_chart.point.new)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "chart.point.copy",
+            signatures: vec![],
+            description: "Creates a copy of the point.",
+            example: "",
+            returns: "The new chart.point object.",
+            arguments: COPY_ARGUMENT,
+            remarks: "",
+            links: "",
+        },
+    ]
+}
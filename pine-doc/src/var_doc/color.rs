@@ -1,5 +1,16 @@
 use crate::{DocBase, VarType};
 
+const NEW_EXAMPLES: &'static str = r#"
+```pine
+plot(close, color=color.new(color.red, 50))
+```
+"#;
+
+const NEW_ARGUMENTS: &'static str = r#"
+color (color) The color to apply the transparency to.
+transp (float) Transparency of the color. Possible values are from 0 (not transparent) to 100 (invisible).
+"#;
+
 pub fn gen_doc() -> Vec<DocBase> {
     vec![
         DocBase {
@@ -189,5 +200,16 @@ pub fn gen_doc() -> Vec<DocBase> {
             remarks: "",
             links: "",
         },
+        DocBase {
+            var_type: VarType::Function,
+            name: "color.new",
+            signatures: vec![],
+            description: "Function color applies the specified transparency to the given color.",
+            example: NEW_EXAMPLES,
+            returns: "Color with the specified transparency.",
+            arguments: NEW_ARGUMENTS,
+            remarks: "The transparency replaces the alpha of the color. It's the v5 replacement of the `transp` argument of the plot functions.",
+            links: "",
+        },
     ]
 }
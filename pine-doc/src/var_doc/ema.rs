@@ -12,7 +12,7 @@ plot(ema(close, 15))
 pine_ema(x, y) =>
     alpha = 2.0 / (y + 1)
     sum = 0.0
-    sum := alpha * x + (1 - alpha) * nz(sum[1])
+    sum := na(sum[1]) ? sma(x, y) : alpha * x + (1 - alpha) * nz(sum[1])
 plot(pine_ema(close, 15))
 ```
 "#;
@@ -31,7 +31,7 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: EXAMPLE,
         returns: "Exponential moving average of x with `alpha = 2 / (y + 1)`",
         arguments: ARGUMENTS,
-        remarks: "",
+        remarks: "The result is na until `length` values of the source are available, then it is seeded by the sma of these values. The runner can start the recursion on the first bar instead with `EmaWarmup::Recursive`.",
         links:
             "[sma](#fun-sma) [rma](#fun-rma) [wma](#fun-wma) [vwma](#fun-vwma) [swma](#fun-swma)",
     };
@@ -0,0 +1,32 @@
+use crate::{DocBase, VarType};
+
+const LINKS: &'static str = r#"
+[label.new](#fun_label-new) [label.set_text_font_family](#fun_label-set_text_font_family)
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "font.family_default",
+            signatures: vec![],
+            description: "Default text font for the drawing text, such as the `text_font_family` argument of label.new and label.set_text_font_family.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "font.family_monospace",
+            signatures: vec![],
+            description: "Monospace text font for the drawing text, such as the `text_font_family` argument of label.new and label.set_text_font_family.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+    ]
+}
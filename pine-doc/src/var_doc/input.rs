@@ -23,12 +23,15 @@ plot(sin(f) > 0 ? close : open)
 const PINE_FN_ARGUMENTS: &'static str = "
 **defval (Depends on 'type' argument)** Default value of the input variable. Note, that input value that will be ACTUALLY USED by the script is set by user on the Chart Setting dialog.
 **title (string)** Title of the input
-**type (string)** Input type. Possible values are [input.bool](#var-input-bool), [input.integer](#var-input-integer), [input.float](#var-input-float), [input.string](#var-input-string), [input.symbol](#var_input-symbol), [input.resolution](#var-input-resolution), [input.session](#var-input-session), [input.source](#var-input-source).
+**type (string)** Input type. Possible values are [input.bool](#var-input-bool), [input.integer](#var-input-integer), [input.float](#var-input-float), [input.price](#var-input-price), [input.string](#var-input-string), [input.symbol](#var-input-symbol), [input.resolution](#var-input-resolution), [input.session](#var-input-session), [input.source](#var-input-source), [input.color](#var-input-color).
 **minval (integer, float)** Minimal possible value of the input variable. This argument is used only when input type is [input.integer](#var-input-integer) or [input.float](#var-input-float).
 **maxval (integer, float)** Maximum possible value of the input variable. This argument is used only when input type is [input.integer](#var-input-integer) or [input.float](#var-input-float).
-**confirm (bool)** If true, then user will be asked to confirm input value before indicator is added to chart. Default value is false. This argument not used when input type is [input.source](#var-input-source).
+**confirm (bool)** If true, then user will be asked to confirm input value before indicator is added to chart. Default value is false.
 **step (integer, float)** Step value to use for incrementing/decrementing input from format dialog. Default value is 1. This argument is used only for input types [input.integer](#var-input-integer) and [input.float](#var-input-float).
-**options (List of constants: [<type>...])** A list of options to choose from. This argument is used only for input types [input.integer](#var-input-integer), [input.float](#var-input-float) and [input.string](#var-input-string).
+**options (List of constants: [<type>...])** A list of options to choose from. This argument is used only for input types [input.integer](#var-input-integer), [input.float](#var-input-float) and [input.string](#var-input-string). The default value and the value chosen by user must be one of the options.
+**inline (string)** Combines all the input calls using the same argument in one line. The string used as an argument is not displayed.
+**group (string)** Creates a header above all inputs using the same group argument string. The string is also used as the header's text.
+**tooltip (string)** The string that will be shown to the user when hovering over the tooltip icon.
 ";
 
 pub fn gen_doc() -> Vec<DocBase> {
@@ -40,7 +43,7 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: PINE_FN_EXAMPLE,
         returns: "Value of input variable.",
         arguments: PINE_FN_ARGUMENTS,
-        remarks: "",
+        remarks: "The value of the [input.symbol](#var-input-symbol) input can be passed to [security](#fun-security) as the symbol. The chart symbol is used if the default value is na. The [input.color](#var-input-color) input returns a simple color, its default opacity is given by the alpha of the default color like `#FF000080`.",
         links: "",
     };
     vec![fn_doc]
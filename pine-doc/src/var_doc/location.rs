@@ -0,0 +1,65 @@
+use crate::{DocBase, VarType};
+
+const LINKS: &'static str = r#"
+[plotshape](#fun_plotshape) [plotchar](#fun_plotchar)
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "location.abovebar",
+            signatures: vec![],
+            description: "Location value for plotshape and plotchar functions. Shape is plotted above main series bars.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "location.absolute",
+            signatures: vec![],
+            description: "Location value for plotshape and plotchar functions. Shape is plotted on chart using indicator value as a price coordinate.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "location.belowbar",
+            signatures: vec![],
+            description: "Location value for plotshape and plotchar functions. Shape is plotted below main series bars.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "location.bottom",
+            signatures: vec![],
+            description: "Location value for plotshape and plotchar functions. Shape is plotted near the bottom chart border.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "location.top",
+            signatures: vec![],
+            description: "Location value for plotshape and plotchar functions. Shape is plotted near the top chart border.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+    ]
+}
@@ -0,0 +1,31 @@
+use crate::{DocBase, VarType};
+
+const DESCRIPTION: &'static str = r#"
+Returns the median of the series for y bars back.
+"#;
+
+const EXAMPLE: &'static str = r#"
+```pine
+plot(median(close, 15))
+```
+"#;
+
+const ARGUMENTS: &'static str = r#"
+source (series(float)) Series of values to process.
+length (int) Number of bars (length).
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let fn_doc = DocBase {
+        var_type: VarType::Function,
+        name: "median",
+        signatures: vec![],
+        description: DESCRIPTION,
+        example: EXAMPLE,
+        returns: "The median of x for y bars back.",
+        arguments: ARGUMENTS,
+        remarks: "The median of the even length is the mean of the two middle values. The result is na if any value of the last y bars is na.",
+        links: "[sma](#fun-sma)",
+    };
+    vec![fn_doc]
+}
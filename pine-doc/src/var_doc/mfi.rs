@@ -39,7 +39,8 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: EXAMPLES,
         returns: "Money Flow Index.",
         arguments: ARGUMENTS,
-        remarks: "",
+        remarks: "Two args version: x is a series and y is a length.
+        One arg version: x is a length. Algorithm uses the typical price hlc3 as a source series.",
         links: "[rsi](#fun-rsi) [sum](#fun-sum)",
     };
     vec![fn_doc]
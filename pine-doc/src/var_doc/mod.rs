@@ -1,17 +1,21 @@
 mod abs;
 mod accdist;
 mod acos;
+mod alert;
 mod alma;
 mod asin;
 mod atan;
 mod atr;
 mod avg;
+mod barmerge;
 mod barstate;
 mod bb;
 mod bbw;
+mod bgcolor;
 mod cci;
 mod ceil;
 mod change;
+mod chart;
 mod close;
 mod cmo;
 mod cog;
@@ -29,6 +33,7 @@ mod falling;
 mod fill;
 mod fixnan;
 mod floor;
+mod font;
 mod high;
 mod highest;
 mod highestbars;
@@ -41,6 +46,7 @@ mod iff;
 mod input;
 mod kc;
 mod kcw;
+mod location;
 mod log;
 mod log10;
 mod low;
@@ -48,6 +54,7 @@ mod lowest;
 mod lowestbars;
 mod macd;
 mod max;
+mod median;
 mod mfi;
 mod min;
 mod minute;
@@ -58,13 +65,19 @@ mod nz;
 mod ohlc4;
 mod open;
 mod plot;
+mod plotarrow;
+mod plotchar;
+mod plotshape;
+mod polyline;
 mod pow;
+mod request;
 mod rising;
 mod rma;
 mod round;
 mod rsi;
 mod sign;
 mod sin;
+mod size;
 mod sma;
 mod sqrt;
 mod stdev;
@@ -73,23 +86,36 @@ mod study;
 mod sum;
 mod swma;
 mod tan;
+mod text;
+mod ticker;
 mod time;
 mod timenow;
 mod timestamp;
 mod tr;
 mod tsi;
 mod variance;
+mod vwap;
 mod vwma;
 mod weekofyear;
 mod wma;
+mod wpr;
 mod year;
 use super::DocBase;
 
 pub fn declare_vars() -> Vec<DocBase> {
     vec![
         plot::gen_doc(),
+        plotarrow::gen_doc(),
+        plotchar::gen_doc(),
+        plotshape::gen_doc(),
+        polyline::gen_doc(),
+        size::gen_doc(),
+        location::gen_doc(),
+        text::gen_doc(),
+        font::gen_doc(),
         input::gen_doc(),
         accdist::gen_doc(),
+        alert::gen_doc(),
         abs::gen_doc(),
         acos::gen_doc(),
         alma::gen_doc(),
@@ -97,6 +123,7 @@ pub fn declare_vars() -> Vec<DocBase> {
         atan::gen_doc(),
         atr::gen_doc(),
         avg::gen_doc(),
+        barmerge::gen_doc(),
         barstate::gen_doc(),
         color::gen_doc(),
         dayofmonth::gen_doc(),
@@ -114,9 +141,11 @@ pub fn declare_vars() -> Vec<DocBase> {
         tr::gen_doc(),
         bb::gen_doc(),
         bbw::gen_doc(),
+        bgcolor::gen_doc(),
         cci::gen_doc(),
         ceil::gen_doc(),
         change::gen_doc(),
+        chart::gen_doc(),
         cmo::gen_doc(),
         cog::gen_doc(),
         correlation::gen_doc(),
@@ -143,12 +172,15 @@ pub fn declare_vars() -> Vec<DocBase> {
         lowestbars::gen_doc(),
         macd::gen_doc(),
         max::gen_doc(),
+        median::gen_doc(),
         mfi::gen_doc(),
         min::gen_doc(),
         mom::gen_doc(),
         na::gen_doc(),
         nz::gen_doc(),
         pow::gen_doc(),
+        request::gen_doc(),
+        ticker::gen_doc(),
         rising::gen_doc(),
         rma::gen_doc(),
         round::gen_doc(),
@@ -166,8 +198,10 @@ pub fn declare_vars() -> Vec<DocBase> {
         timestamp::gen_doc(),
         tsi::gen_doc(),
         variance::gen_doc(),
+        vwap::gen_doc(),
         vwma::gen_doc(),
         wma::gen_doc(),
+        wpr::gen_doc(),
         close::gen_doc(),
         open::gen_doc(),
         high::gen_doc(),
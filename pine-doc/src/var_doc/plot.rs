@@ -14,6 +14,17 @@ fill(p1, p2, color=color.green)
 const PINE_FN_ARGUMENTS: &'static str = "
 **series (series(float))** Series of data to be plotted. Required argument.
 **title (string)** Title of the plot.
+**color (color)** Color of the plot. You can use constants like 'color=color.red' or 'color=#ff001a' as well as complex expressions like 'color = close >= open ? color.green : color.red'. Optional argument.
+**linewidth (int)** Width of the plotted line, use values from 1 to 4. Default value is 1. Not applicable to every style.
+**style (string)** Type of the plot. Possible values are: [plot.style_line](#var_plot-style_line), [plot.style_stepline](#var_plot-style_stepline), [plot.style_histogram](#var_plot-style_histogram), [plot.style_cross](#var_plot-style_cross), [plot.style_area](#var_plot-style_area), [plot.style_columns](#var_plot-style_columns), [plot.style_circles](#var_plot-style_circles), [plot.style_linebr](#var_plot-style_linebr), [plot.style_areabr](#var_plot-style_areabr). Default value is plot.style_line.
+**trackprice (bool)** If true then a horizontal price line will be shown at the level of the last indicator value. Default is false.
+**opacity (int)** Transparency of the plot, applicable only to the plot.style_area style. Possible values are from 0 (not transparent) to 100 (invisible). Optional argument.
+**histbase (float)** Price value which will be considered as a start base point when rendering plot with plot.style_histogram, plot.style_columns or plot.style_area style. Default is 0.0.
+**offset (int)** Shifts the plot to the left or to the right on the given number of bars. Default is 0. The values shifted after the last bar are returned as the projections of the outputs.
+**join (bool)** If true then plot points will be joined with line, applicable only to plot.style_cross and plot.style_circles styles. Default is false.
+**editable (bool)** If true then plot style will be editable in Format dialog. Default is true.
+**show_last (int)** If set, defines the number of bars (from the last bar back to the past) to plot on chart.
+**display (int)** Controls where the plot is displayed. Possible values are: [display.none](#var_display-none), [display.all](#var_display-all), [display.pane](#var_display-pane). The plot with display.none is hidden from the chart but its values are still generated for the alerts and exports. Default is display.all.
 ";
 
 pub fn gen_doc() -> Vec<DocBase> {
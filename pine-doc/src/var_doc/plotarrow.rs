@@ -0,0 +1,42 @@
+use crate::{DocBase, VarType};
+
+const EXAMPLE: &'static str = r#"
+```pine
+study("plotarrow example", overlay=true)
+codiff = close - open
+plotarrow(codiff, colorup=#00ff00, colordown=#ff0000, opacity=30)
+```
+"#;
+
+const ARGUMENTS: &'static str = r#"
+**series (series(float))** Series of data to be plotted as arrows. Required argument.
+**title (string)** Title of the plot.
+**colorup (color)** Color of the up arrows. Optional argument.
+**colordown (color)** Color of the down arrows. Optional argument.
+**opacity (int)** Transparency of the arrows. Possible values are from 0 (not transparent) to 100 (invisible). Optional argument.
+**offset (int)** Shifts arrows to the left or to the right on the given number of bars. Default is 0.
+**minheight (int)** Minimal possible arrow height in pixels. Default is 5.
+**maxheight (int)** Maximum possible arrow height in pixels. Default is 100.
+**editable (bool)** If true then plotarrow style will be editable in Format dialog. Default is true.
+**show_last (int)** If set, defines the number of arrows (from the last bar back to the past) to plot on chart.
+**display (int)** Controls where the plot is displayed. Possible values are: [display.none](#var_display-none), [display.all](#var_display-all), [display.pane](#var_display-pane). Default is display.all.
+"#;
+
+const REMARKS: &'static str = r#"
+The positive values of the series are plotted as the up arrows and the negative values as the down arrows. The na and zero values are not plotted. The height of the arrow is relative to the absolute value of the series.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let fn_doc = DocBase {
+        var_type: VarType::Function,
+        name: "plotarrow",
+        signatures: vec![],
+        description: "Plots up and down arrows on the chart.",
+        example: EXAMPLE,
+        returns: "",
+        arguments: ARGUMENTS,
+        remarks: REMARKS,
+        links: "[plot](#fun_plot) [plotshape](#fun_plotshape) [plotchar](#fun_plotchar)",
+    };
+    vec![fn_doc]
+}
@@ -0,0 +1,40 @@
+use crate::{DocBase, VarType};
+
+const EXAMPLE: &'static str = r#"
+```pine
+study("plotchar example", overlay=true)
+data = close >= open
+plotchar(data, char='❄', location=location.abovebar, size=size.small)
+```
+"#;
+
+const ARGUMENTS: &'static str = r#"
+**series (series(float))** Series of data to be plotted as characters. Required argument.
+**title (string)** Title of the plot.
+**char (string)** Character to use as a visual shape. The value must be exactly one character.
+**location (string)** Location of characters on the chart. Possible values are: [location.abovebar](#var_location-abovebar), [location.belowbar](#var_location-belowbar), [location.top](#var_location-top), [location.bottom](#var_location-bottom), [location.absolute](#var_location-absolute). Default value is location.abovebar.
+**color (color)** Color of the characters. Optional argument.
+**opacity (int)** Transparency of the characters. Possible values are from 0 (not transparent) to 100 (invisible). Optional argument.
+**offset (int)** Shifts characters to the left or to the right on the given number of bars. Default is 0.
+**text (series string)** Text to display with the character, it can change on every bar. Optional argument.
+**textcolor (color)** Color of the text. Optional argument.
+**editable (bool)** If true then plotchar style will be editable in Format dialog. Default is true.
+**size (string)** Size of characters on the chart. Possible values are: [size.auto](#var_size-auto), [size.tiny](#var_size-tiny), [size.small](#var_size-small), [size.normal](#var_size-normal), [size.large](#var_size-large), [size.huge](#var_size-huge). Default is size.auto.
+**show_last (int)** If set, defines the number of characters (from the last bar back to the past) to plot on chart.
+**display (int)** Controls where the plot is displayed. Possible values are: [display.none](#var_display-none), [display.all](#var_display-all), [display.pane](#var_display-pane). Default is display.all.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let fn_doc = DocBase {
+        var_type: VarType::Function,
+        name: "plotchar",
+        signatures: vec![],
+        description: "Plots visual shapes using any given one Unicode character on the chart.",
+        example: EXAMPLE,
+        returns: "",
+        arguments: ARGUMENTS,
+        remarks: "",
+        links: "[plot](#fun_plot) [plotshape](#fun_plotshape) [plotarrow](#fun_plotarrow)",
+    };
+    vec![fn_doc]
+}
@@ -0,0 +1,40 @@
+use crate::{DocBase, VarType};
+
+const EXAMPLE: &'static str = r#"
+```pine
+study("plotshape example", overlay=true)
+data = close >= open
+plotshape(data, style='xcross', location=location.belowbar, size=size.tiny)
+```
+"#;
+
+const ARGUMENTS: &'static str = r#"
+**series (series(float))** Series of data to be plotted as shapes. Required argument.
+**title (string)** Title of the plot.
+**style (string)** Type of the plot.
+**location (string)** Location of shapes on the chart. Possible values are: [location.abovebar](#var_location-abovebar), [location.belowbar](#var_location-belowbar), [location.top](#var_location-top), [location.bottom](#var_location-bottom), [location.absolute](#var_location-absolute). Default value is location.abovebar.
+**color (color)** Color of the shapes. Optional argument.
+**opacity (int)** Transparency of the shapes. Possible values are from 0 (not transparent) to 100 (invisible). Optional argument.
+**offset (int)** Shifts shapes to the left or to the right on the given number of bars. Default is 0.
+**text (series string)** Text to display with the shape, it can change on every bar. Optional argument.
+**textcolor (color)** Color of the text. Optional argument.
+**editable (bool)** If true then plotshape style will be editable in Format dialog. Default is true.
+**size (string)** Size of shapes on the chart. Possible values are: [size.auto](#var_size-auto), [size.tiny](#var_size-tiny), [size.small](#var_size-small), [size.normal](#var_size-normal), [size.large](#var_size-large), [size.huge](#var_size-huge). Default is size.auto.
+**show_last (int)** If set, defines the number of shapes (from the last bar back to the past) to plot on chart.
+**display (int)** Controls where the plot is displayed. Possible values are: [display.none](#var_display-none), [display.all](#var_display-all), [display.pane](#var_display-pane). Default is display.all.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let fn_doc = DocBase {
+        var_type: VarType::Function,
+        name: "plotshape",
+        signatures: vec![],
+        description: "Plots visual shapes on the chart.",
+        example: EXAMPLE,
+        returns: "",
+        arguments: ARGUMENTS,
+        remarks: "",
+        links: "[plot](#fun_plot) [plotchar](#fun_plotchar) [plotarrow](#fun_plotarrow)",
+    };
+    vec![fn_doc]
+}
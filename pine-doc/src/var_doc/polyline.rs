@@ -0,0 +1,57 @@
+use crate::{DocBase, VarType};
+
+const NEW_ARGUMENT: &'static str = r#"
+**points (chart.point[])** The array of the points of the polyline. The na points are skipped.
+**curved (bool)** If true, the polyline is drawn with the curved segments. Default is false.
+**closed (bool)** If true, the polyline connects the last point to the first one. Default is false.
+**xloc (series(string))** Determines whether the bar index or the time of the points is used. Possible values are `xloc.bar_index` and `xloc.bar_time`. Default is `xloc.bar_index`.
+**line_color (series(color))** Color of the polyline.
+**fill_color (series(color))** Fill color of the area enclosed by the polyline.
+**line_style (series(string))** Style of the polyline. Possible values are `line.style_solid`, `line.style_dotted`, `line.style_dashed`, `line.style_arrow_left`, `line.style_arrow_right` and `line.style_arrow_both`.
+**line_width (series(int))** Width of the polyline in pixels.
+"#;
+
+const NEW_EXAMPLE: &'static str = r#"
+```pine
+//@version=5
+indicator("`polyline.new()` example", overlay = true)
+if barstate.islast
+    points = [chart.point.from_index(bar_index - 20, low), chart.point.from_index(bar_index - 10, high), chart.point.now(close)]
+    polyline.new(points, curved = true, line_color = color.blue)
+```
+"#;
+
+const NEW_REMARKS: &'static str = r#"
+The oldest polylines are deleted when the count of the polylines exceeds the `max_polylines_count` of the script declaration.
+"#;
+
+const DELETE_ARGUMENT: &'static str = r#"
+**id (polyline)** Polyline object to delete.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Function,
+            name: "polyline.new",
+            signatures: vec![],
+            description: "Creates a new polyline connecting the points.",
+            example: NEW_EXAMPLE,
+            returns: "Polyline ID object which may be passed to polyline.delete function.",
+            arguments: NEW_ARGUMENT,
+            remarks: NEW_REMARKS,
+            links: "[polyline.delete](#fun_polyline.delete) [chart.point.new](#fun_chart.point.new)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "polyline.delete",
+            signatures: vec![],
+            description: "Deletes the specified polyline object. If it has already been deleted, does nothing.",
+            example: "",
+            returns: "",
+            arguments: DELETE_ARGUMENT,
+            remarks: "",
+            links: "[polyline.new](#fun_polyline.new)",
+        },
+    ]
+}
@@ -0,0 +1,109 @@
+use crate::{DocBase, VarType};
+
+const FINANCIAL_ARGUMENT: &'static str = r#"
+**symbol (string)** Symbol. Note that the symbol should be passed with a prefix. For example: "NASDAQ:AAPL" instead of "AAPL".
+**financial_id (string)** Financial identifier.
+**period (string)** Reporting period. Possible values are "TTM", "FY", "FQ".
+**gaps (bool)** Merge strategy for the requested data. If true, the function returns na for the bars that have no new data point. Default is false.
+"#;
+
+const DIVIDENDS_ARGUMENT: &'static str = r#"
+**ticker (string)** Symbol. Note that the symbol should be passed with a prefix. For example: "NASDAQ:AAPL" instead of "AAPL".
+**field (string)** The requested field, e.g. "net" or "gross".
+**gaps (bool)** Merge strategy for the requested data. If true, the function returns na for the bars that have no new data point. Default is false.
+"#;
+
+const SPLITS_ARGUMENT: &'static str = r#"
+**ticker (string)** Symbol. Note that the symbol should be passed with a prefix. For example: "NASDAQ:AAPL" instead of "AAPL".
+**field (string)** The requested field, "numerator" or "denominator".
+**gaps (bool)** Merge strategy for the requested data. If true, the function returns na for the bars that have no new data point. Default is false.
+"#;
+
+const ECONOMIC_ARGUMENT: &'static str = r#"
+**country_code (string)** Country code, e.g. "US" or "EU".
+**field (string)** The economic indicator, e.g. "GDP" or "IRSTCB1".
+**gaps (bool)** Merge strategy for the requested data. If true, the function returns na for the bars that have no new data point. Default is false.
+"#;
+
+const ECONOMIC_EXAMPLE: &'static str = r#"
+```pine
+gdp = request.economic("US", "GDP")
+plot(gdp)
+```
+"#;
+
+const EXTERNAL_ARGUMENT: &'static str = r#"
+**id (string)** The id of the series registered by the host, e.g. "BINANCE:BTCUSDT.FUNDING".
+**timeframe (string)** The timeframe of the series. If the host registered no series for the timeframe, the series registered without the timeframe is used.
+**gaps (bool)** Merge strategy for the requested data. If true, the function returns na for the bars that have no new data point. Default is false.
+"#;
+
+const REGISTRY_REMARKS: &'static str = r#"
+The series registered by the host application are used first, otherwise the data is requested from the host. If the host provides no data, the function returns na.
+"#;
+
+const REMARKS: &'static str = r#"
+The data is provided by the host application. If the host provides no data, the function returns na.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Function,
+            name: "request.financial",
+            signatures: vec![],
+            description: "Requests financial series for symbol.",
+            example: "",
+            returns: "Requested series.",
+            arguments: FINANCIAL_ARGUMENT,
+            remarks: REMARKS,
+            links: "[request.dividends](#fun_request.dividends)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "request.dividends",
+            signatures: vec![],
+            description: "Requests dividends data for the specified symbol.",
+            example: "",
+            returns: "Requested series.",
+            arguments: DIVIDENDS_ARGUMENT,
+            remarks: REMARKS,
+            links:
+                "[request.financial](#fun_request.financial) [request.splits](#fun_request.splits)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "request.splits",
+            signatures: vec![],
+            description: "Requests splits data for the specified symbol.",
+            example: "",
+            returns: "Requested series.",
+            arguments: SPLITS_ARGUMENT,
+            remarks: REMARKS,
+            links: "[request.dividends](#fun_request.dividends)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "request.economic",
+            signatures: vec![],
+            description: "Requests economic data for the specified country.",
+            example: ECONOMIC_EXAMPLE,
+            returns: "Requested series.",
+            arguments: ECONOMIC_ARGUMENT,
+            remarks: REGISTRY_REMARKS,
+            links: "[request.external](#fun_request.external)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "request.external",
+            signatures: vec![],
+            description:
+                "Requests the external series registered by the host, like the funding rates.",
+            example: "",
+            returns: "Requested series.",
+            arguments: EXTERNAL_ARGUMENT,
+            remarks: REGISTRY_REMARKS,
+            links: "[request.economic](#fun_request.economic)",
+        },
+    ]
+}
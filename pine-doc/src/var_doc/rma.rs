@@ -12,7 +12,7 @@ plot(rma(close, 15))
 pine_rma(x, y) =>
 	alpha = y
     sum = 0.0
-    sum := (x + (alpha - 1) * nz(sum[1])) / alpha
+    sum := na(sum[1]) ? sma(x, y) : (x + (alpha - 1) * nz(sum[1])) / alpha
 plot(pine_rma(close, 15))
 ```
 "#;
@@ -31,7 +31,7 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: EXAMPLE,
         returns: "Exponential moving average of x with `alpha = 1 / y`.",
         arguments: ARGUMENTS,
-        remarks: "",
+        remarks: "The result is na until `length` values of the source are available, then it is seeded by the sma of these values. The runner can start the recursion on the first bar instead with `EmaWarmup::Recursive`.",
         links: "[sma](#fun-sma)",
     };
     vec![fn_doc]
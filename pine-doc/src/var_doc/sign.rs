@@ -13,7 +13,7 @@ pub fn gen_doc() -> Vec<DocBase> {
         example: "",
         returns: "The sign of the argument.",
         arguments: "",
-        remarks: "",
+        remarks: "The result is int if x is int. It is also available as `math.sign(number)`.",
         links: "",
     };
     vec![fn_doc]
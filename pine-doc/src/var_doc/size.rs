@@ -0,0 +1,76 @@
+use crate::{DocBase, VarType};
+
+const LINKS: &'static str = r#"
+[plotshape](#fun_plotshape) [plotchar](#fun_plotchar)
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.auto",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions. The size of the shape automatically adapts to the size of the bars.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.huge",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.large",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.normal",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.small",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "size.tiny",
+            signatures: vec![],
+            description: "Size value for plotshape and plotchar functions.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+    ]
+}
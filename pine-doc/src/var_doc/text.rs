@@ -0,0 +1,87 @@
+use crate::{DocBase, VarType};
+
+const LINKS: &'static str = r#"
+[label.new](#fun_label-new) [label.set_textalign](#fun_label-set_textalign)
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.align_bottom",
+            signatures: vec![],
+            description: "Vertical text alignment for the drawing text.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.align_center",
+            signatures: vec![],
+            description: "Text alignment for the drawing text, such as the `textalign` argument of label.new and label.set_textalign.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.align_left",
+            signatures: vec![],
+            description: "Horizontal text alignment for the drawing text, such as the `textalign` argument of label.new and label.set_textalign.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.align_right",
+            signatures: vec![],
+            description: "Horizontal text alignment for the drawing text, such as the `textalign` argument of label.new and label.set_textalign.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.align_top",
+            signatures: vec![],
+            description: "Vertical text alignment for the drawing text.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.wrap_auto",
+            signatures: vec![],
+            description: "Automatic wrapping mode for the drawing text. The text is wrapped to fit the width of the drawing.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "text.wrap_none",
+            signatures: vec![],
+            description: "Disabled wrapping mode for the drawing text.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: LINKS,
+        },
+    ]
+}
@@ -0,0 +1,98 @@
+use crate::{DocBase, VarType};
+
+const NEW_ARGUMENT: &'static str = r#"
+**prefix (string)** Exchange prefix. For example: "BATS".
+**ticker (string)** Ticker name without the exchange prefix. For example: "MSFT".
+**session (string)** Session type. Optional argument. Possible values: [session.regular](#var_session.regular), [session.extended](#var_session.extended).
+**adjustment (string)** Adjustment type. Optional argument. Possible values: [adjustment.none](#var_adjustment.none), [adjustment.splits](#var_adjustment.splits), [adjustment.dividends](#var_adjustment.dividends).
+"#;
+
+const MODIFY_ARGUMENT: &'static str = r#"
+**tickerid (string)** The ticker id created by [ticker.new](#fun_ticker.new) or a symbol with the exchange prefix.
+**session (string)** Session type. Optional argument.
+**adjustment (string)** Adjustment type. Optional argument.
+"#;
+
+const TRANSFORM_REMARKS: &'static str = r#"
+The returned ticker id can be passed to [security](#fun_security), the host application transforms the bars of the symbol.
+"#;
+
+fn gen_fun_doc(
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static str,
+    remarks: &'static str,
+) -> DocBase {
+    DocBase {
+        var_type: VarType::Function,
+        name,
+        signatures: vec![],
+        description,
+        example: "",
+        returns:
+            "String value of ticker id, that can be supplied to [security](#fun_security) function.",
+        arguments,
+        remarks,
+        links: "[security](#fun_security)",
+    }
+}
+
+fn gen_var_doc(name: &'static str, description: &'static str) -> DocBase {
+    DocBase {
+        var_type: VarType::Variable,
+        name,
+        signatures: vec![],
+        description,
+        example: "",
+        returns: "",
+        arguments: "",
+        remarks: "",
+        links: "",
+    }
+}
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        gen_fun_doc(
+            "ticker.new",
+            "Creates a ticker identifier for requesting additional data for the script.",
+            NEW_ARGUMENT,
+            "",
+        ),
+        gen_fun_doc(
+            "ticker.modify",
+            "Creates a ticker identifier for requesting additional data for the script from the existing ticker id.",
+            MODIFY_ARGUMENT,
+            "",
+        ),
+        gen_fun_doc(
+            "ticker.heikinashi",
+            "Creates a ticker identifier for requesting Heikin Ashi bar values.",
+            "**symbol (string)** Symbol ticker identifier.",
+            TRANSFORM_REMARKS,
+        ),
+        gen_fun_doc(
+            "ticker.renko",
+            "Creates a ticker identifier for requesting Renko values.",
+            "**symbol (string)** Symbol ticker identifier.\n**style (string)** Box size assignment method: \"ATR\" or \"Traditional\". Default is \"ATR\".\n**param (float)** ATR length if style is \"ATR\", or box size if style is \"Traditional\". Default is 14.",
+            TRANSFORM_REMARKS,
+        ),
+        gen_fun_doc(
+            "ticker.kagi",
+            "Creates a ticker identifier for requesting Kagi values.",
+            "**symbol (string)** Symbol ticker identifier.\n**reversal (float)** Reversal amount (absolute price value). Default is 1.",
+            TRANSFORM_REMARKS,
+        ),
+        gen_fun_doc(
+            "ticker.linebreak",
+            "Creates a ticker identifier for requesting Line Break values.",
+            "**symbol (string)** Symbol ticker identifier.\n**number_of_lines (int)** The number of line for calculation. Default is 3.",
+            TRANSFORM_REMARKS,
+        ),
+        gen_var_doc("session.regular", "Constant for regular session type (with no extended hours data)."),
+        gen_var_doc("session.extended", "Constant for extended session type (with extended hours data)."),
+        gen_var_doc("adjustment.none", "Constant for none adjustment type (no adjustment is applied)."),
+        gen_var_doc("adjustment.splits", "Constant for splits adjustment type (splits adjustment is applied). The raw bars are back-adjusted by the splits supplied by the host."),
+        gen_var_doc("adjustment.dividends", "Constant for dividends adjustment type (dividends adjustment is applied). The raw bars are back-adjusted by the splits and dividends supplied by the host."),
+    ]
+}
@@ -22,7 +22,7 @@ For example, on a symbol that is traded seven days a week with the 24-hour tradi
 
 const TIME_ARGUMENTS: &'static str = r#"
 **resolution (string)** Resolution.
-**session (string)** Session specification. Optional argument, session of the symbol used by default.
+**session (string)** Session specification. Optional argument, session of the symbol used by default. It can also be [session.regular](#var_session.regular) for the trade time of the symbol or [session.extended](#var_session.extended) for all the bars.
 "#;
 
 pub fn gen_doc() -> Vec<DocBase> {
@@ -49,5 +49,27 @@ pub fn gen_doc() -> Vec<DocBase> {
             remarks: "UNIX time is the number of milliseconds that have elapsed since 00:00:00 UTC, 1 January 1970.",
             links: "",
         },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "time_close",
+            signatures: vec![],
+            description: "Current bar close time in UNIX format. It is the number of milliseconds that have elapsed since 00:00:00 UTC, 1 January 1970.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "If the data source does not provide it, the bar closes when the next bar opens, or one bar interval after its open time before a session break and on the last bar.",
+            links: "",
+        },
+        DocBase {
+            var_type: VarType::Variable,
+            name: "time_tradingday",
+            signatures: vec![],
+            description: "The beginning time of the trading day the current bar belongs to, in UNIX format (the number of milliseconds that have elapsed since 00:00:00 UTC, 1 January 1970).",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "The variable is useful for overnight sessions, where the current day's session can start on the previous calendar day (e.g., on FXCM:EURUSD the Monday session will start on Sunday, 17:00). Unlike `time`, which would return the timestamp for Sunday at 17:00 for the Daily bar, `time_tradingday` will return the timestamp for Monday, 00:00 UTC.",
+            links: "",
+        },
     ]
 }
@@ -10,7 +10,7 @@ pub fn gen_doc() -> Vec<DocBase> {
             example: "",
             returns: "",
             arguments: "",
-            remarks: "",
+            remarks: "The time is read from the clock of the host every time `timenow` is evaluated, so it changes on the realtime updates. Compare it with `time` to get how old the bar is, e.g. `timenow - time`.",
             links: "",
         },
     ]
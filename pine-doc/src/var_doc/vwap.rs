@@ -0,0 +1,42 @@
+use crate::{DocBase, VarType};
+
+const ARGUMENTS: &'static str = r#"
+**source (series(float))** Source used for the VWAP calculation.
+**anchor (series(bool))** The condition that triggers the reset of VWAP calculations. When true, calculations restart from the current bar. If it is not specified, the calculations restart at the beginning of every trading day.
+"#;
+
+const EXAMPLE: &'static str = r#"
+```pine
+//@version=4
+study("Anchored VWAP", overlay=true)
+newMonth = change(month) != 0
+plot(vwap(hlc3, newMonth))
+```
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    vec![
+        DocBase {
+            var_type: VarType::Variable,
+            name: "vwap",
+            signatures: vec![],
+            description: "Volume Weighted Average Price. It uses hlc3 as a source series and restarts at the beginning of every trading day.",
+            example: "",
+            returns: "",
+            arguments: "",
+            remarks: "",
+            links: "[vwap](#fun-vwap) [vwma](#fun-vwma)",
+        },
+        DocBase {
+            var_type: VarType::Function,
+            name: "vwap",
+            signatures: vec![],
+            description: "Volume weighted average price of the source from the start of the period.",
+            example: EXAMPLE,
+            returns: "Volume weighted average. It is na until the volume of the period is greater than 0.",
+            arguments: ARGUMENTS,
+            remarks: "The na anchor does not reset the calculations.",
+            links: "[vwap](#var-vwap) [vwma](#fun-vwma)",
+        },
+    ]
+}
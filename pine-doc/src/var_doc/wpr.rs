@@ -0,0 +1,37 @@
+use crate::{DocBase, VarType};
+
+const DESCRIPTION: &'static str = r#"
+Williams %R. The oscillator shows the current closing price in relation to the high and low of the past 'length' bars.
+"#;
+
+const EXAMPLES: &'static str = r#"
+```pine
+study("Williams %R", shorttitle="%R", format=format.price, precision=2)
+plot(wpr(14), title="%R", color=#ff6d00)
+
+// the same on pine
+f_wpr(length) =>
+    100 * (close - highest(high, length)) / (highest(high, length) - lowest(low, length))
+
+plot(f_wpr(14))
+```
+"#;
+
+const ARGUMENTS: &'static str = r#"
+**length (int)** Number of bars.
+"#;
+
+pub fn gen_doc() -> Vec<DocBase> {
+    let fn_doc = DocBase {
+        var_type: VarType::Function,
+        name: "wpr",
+        signatures: vec![],
+        description: DESCRIPTION,
+        example: EXAMPLES,
+        returns: "Williams %R.",
+        arguments: ARGUMENTS,
+        remarks: "",
+        links: "[mfi](#fun-mfi) [cmo](#fun-cmo)",
+    };
+    vec![fn_doc]
+}
@@ -2,7 +2,9 @@ use super::text_doc::TextDoc;
 use jsonrpc_core::request::Notification;
 use jsonrpc_core::Params;
 use lsp_types::*;
-use pine::ast::input::StrRange;
+use pine::ast::input::{Position as StrPos, StrRange};
+use pine::runtime::error_format::ErrorFormater;
+use pine::syntax::quick_fix::range_overlap;
 use std::collections::HashMap;
 use std::sync::mpsc::Sender;
 
@@ -25,12 +27,12 @@ fn from_str_range(range: StrRange) -> Range {
     )
 }
 
-// fn to_str_range(range: Range) -> StrRange {
-//     StrRange::new(
-//         StrPos::new(range.start.line as u32, range.start.character as u32),
-//         StrPos::new(range.end.line as u32, range.end.character as u32),
-//     )
-// }
+fn to_str_range(range: Range) -> StrRange {
+    StrRange::new(
+        StrPos::new(range.start.line as u32, range.start.character as u32),
+        StrPos::new(range.end.line as u32, range.end.character as u32),
+    )
+}
 
 impl<'a> PineServer<'a> {
     pub fn new(sender: Sender<String>) -> PineServer<'a> {
@@ -149,15 +151,185 @@ impl<'a> PineServer<'a> {
             // info!("publish errors {:?}", publish_diagnostics);
             self.send_notification("textDocument/publishDiagnostics", publish_diagnostics);
         } else {
+            let formatter = ErrorFormater::new();
+            let diagnostics: Vec<_> = doc
+                .get_lint_warnings()
+                .iter()
+                .map(|warning| {
+                    let severity = if warning.code.is_hint() {
+                        DiagnosticSeverity::Hint
+                    } else {
+                        DiagnosticSeverity::Warning
+                    };
+                    Diagnostic::new(
+                        from_str_range(warning.range),
+                        Some(severity),
+                        None,
+                        Some(String::from("pine ls")),
+                        formatter.format_lint(warning.code.clone()),
+                        None,
+                        None,
+                    )
+                })
+                .collect();
             let publish_diagnostics = PublishDiagnosticsParams {
                 uri: doc.get_uri().clone(),
-                diagnostics: vec![],
+                diagnostics: diagnostics,
                 version: None,
             };
             self.send_notification("textDocument/publishDiagnostics", publish_diagnostics);
         }
     }
 
+    // Get the quick fixes whose ranges overlap the requested range, the edits of each fix are
+    // applied together by the workspace edit.
+    pub fn code_action(&self, params: CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = params.text_document.uri;
+        let doc = match self.text_docs.get(&uri) {
+            Some(doc) => doc,
+            None => return vec![],
+        };
+        let range = to_str_range(params.range);
+        doc.get_quick_fixes()
+            .iter()
+            .filter(|fix| range_overlap(fix.range, range))
+            .map(|fix| {
+                let edits = fix
+                    .edits
+                    .iter()
+                    .map(|edit| TextEdit::new(from_str_range(edit.range), edit.new_text.clone()))
+                    .collect();
+                let mut changes = HashMap::new();
+                changes.insert(uri.clone(), edits);
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: fix.title.clone(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: None,
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(changes),
+                        document_changes: None,
+                    }),
+                    command: None,
+                    is_preferred: None,
+                })
+            })
+            .collect()
+    }
+
+    // Rename the variable or function at the position, the error message is returned if the
+    // new name is invalid or collides with other names.
+    pub fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let doc = match self.text_docs.get(&text_document.uri) {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+        let pos = StrPos::new(position.line as u32, position.character as u32);
+        let edits = doc
+            .rename(pos, &params.new_name)
+            .map_err(|err| err.message)?;
+        let edits = edits
+            .into_iter()
+            .map(|edit| TextEdit::new(from_str_range(edit.range), edit.new_text))
+            .collect();
+        let mut changes = HashMap::new();
+        changes.insert(text_document.uri, edits);
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+        }))
+    }
+
+    // Show the documentation of the annotations above the declaration that the name refers to.
+    pub fn hover(&self, params: TextDocumentPositionParams) -> Option<Hover> {
+        let doc = self.text_docs.get(&params.text_document.uri)?;
+        let pos = StrPos::new(
+            params.position.line as u32,
+            params.position.character as u32,
+        );
+        doc.hover_doc(pos).map(|value| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        })
+    }
+
+    // Complete the named arguments of the call at the position by the snippets.
+    pub fn completion(&self, params: CompletionParams) -> Vec<CompletionItem> {
+        let TextDocumentPositionParams {
+            text_document,
+            position,
+        } = params.text_document_position;
+        let doc = match self.text_docs.get(&text_document.uri) {
+            Some(doc) => doc,
+            None => return vec![],
+        };
+        let pos = StrPos::new(position.line as u32, position.character as u32);
+        doc.param_completions(pos)
+            .into_iter()
+            .map(|completion| CompletionItem {
+                label: format!("{}=", completion.name),
+                kind: Some(CompletionItemKind::Field),
+                detail: Some(completion.detail),
+                insert_text: Some(completion.snippet),
+                insert_text_format: Some(InsertTextFormat::Snippet),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    pub fn folding_range(&self, params: FoldingRangeParams) -> Vec<FoldingRange> {
+        match self.text_docs.get(&params.text_document.uri) {
+            Some(doc) => doc
+                .folding_ranges()
+                .into_iter()
+                .map(|r| FoldingRange {
+                    start_line: r.start_line as u64,
+                    start_character: None,
+                    end_line: r.end_line as u64,
+                    end_character: None,
+                    kind: Some(FoldingRangeKind::Region),
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // The selection range of each position is linked to the parent ranges from the innermost
+    // node to the whole script.
+    pub fn selection_range(&self, params: SelectionRangeParams) -> Vec<SelectionRange> {
+        let doc = match self.text_docs.get(&params.text_document.uri) {
+            Some(doc) => doc,
+            None => return vec![],
+        };
+        params
+            .positions
+            .into_iter()
+            .map(|position| {
+                let pos = StrPos::new(position.line as u32, position.character as u32);
+                let ranges = doc.selection_ranges(pos);
+                let parent = ranges.iter().rev().fold(None, |parent, range| {
+                    Some(Box::new(SelectionRange {
+                        range: from_str_range(*range),
+                        parent,
+                    }))
+                });
+                match parent {
+                    Some(selection) => *selection,
+                    None => SelectionRange {
+                        range: Range::new(position, position),
+                        parent: None,
+                    },
+                }
+            })
+            .collect()
+    }
+
     pub fn send_notification(
         &self,
         method: impl Into<String>,
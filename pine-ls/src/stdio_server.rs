@@ -4,7 +4,6 @@ use jsonrpc_core;
 use lsp_types::*;
 use serde;
 use serde_json;
-use serde_json::json;
 use std::io::{self, BufRead, Read, Write};
 use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
@@ -31,6 +30,18 @@ pub fn start() {
         // capabilities.definition_provider = Some(true);
         // capabilities.references_provider = Some(true);
         capabilities.hover_provider = Some(true);
+        capabilities.code_action_provider = Some(CodeActionProviderCapability::Simple(true));
+        capabilities.rename_provider = Some(RenameProviderCapability::Simple(true));
+        capabilities.folding_range_provider = Some(FoldingRangeProviderCapability::Simple(true));
+        capabilities.selection_range_provider =
+            Some(SelectionRangeProviderCapability::Simple(true));
+        capabilities.completion_provider = Some(CompletionOptions {
+            resolve_provider: None,
+            trigger_characters: Some(vec![String::from("("), String::from(",")]),
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        });
         capabilities.signature_help_provider = Some(SignatureHelpOptions {
             trigger_characters: Some(vec![String::from("("), String::from(".")]),
             retrigger_characters: None,
@@ -91,9 +102,50 @@ pub fn start() {
         info!("Close text document {:?}", params);
     });
 
+    let server = Arc::clone(&pine_server);
     io.add_method("textDocument/hover", move |params: Params| {
         info!("hover text document {:?}", params);
-        Ok(json!(null))
+        let hover = server.lock().unwrap().hover(params.parse()?);
+        serde_json::to_value(hover).map_err(|_| jsonrpc_core::Error::internal_error())
+    });
+
+    let server = Arc::clone(&pine_server);
+    io.add_method("textDocument/codeAction", move |params: Params| {
+        info!("code action {:?}", params);
+        let actions = server.lock().unwrap().code_action(params.parse()?);
+        serde_json::to_value(actions).map_err(|_| jsonrpc_core::Error::internal_error())
+    });
+
+    let server = Arc::clone(&pine_server);
+    io.add_method("textDocument/rename", move |params: Params| {
+        info!("rename {:?}", params);
+        match server.lock().unwrap().rename(params.parse()?) {
+            Ok(edit) => {
+                serde_json::to_value(edit).map_err(|_| jsonrpc_core::Error::internal_error())
+            }
+            Err(message) => Err(jsonrpc_core::Error::invalid_params(message)),
+        }
+    });
+
+    let server = Arc::clone(&pine_server);
+    io.add_method("textDocument/completion", move |params: Params| {
+        info!("completion {:?}", params);
+        let items = server.lock().unwrap().completion(params.parse()?);
+        serde_json::to_value(items).map_err(|_| jsonrpc_core::Error::internal_error())
+    });
+
+    let server = Arc::clone(&pine_server);
+    io.add_method("textDocument/foldingRange", move |params: Params| {
+        info!("folding range {:?}", params);
+        let ranges = server.lock().unwrap().folding_range(params.parse()?);
+        serde_json::to_value(ranges).map_err(|_| jsonrpc_core::Error::internal_error())
+    });
+
+    let server = Arc::clone(&pine_server);
+    io.add_method("textDocument/selectionRange", move |params: Params| {
+        info!("selection range {:?}", params);
+        let ranges = server.lock().unwrap().selection_range(params.parse()?);
+        serde_json::to_value(ranges).map_err(|_| jsonrpc_core::Error::internal_error())
     });
 
     // Spawn thread to read requests from stdin
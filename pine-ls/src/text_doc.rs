@@ -1,7 +1,12 @@
 use lsp_types::Url;
 use lsp_types::*;
 // use pine::ast::input::{Position as StrPos, StrRange};
+use pine::ast::input::{Position as StrPos, StrRange};
 use pine::runtime::error_format::PineFormatError;
+use pine::syntax::completion::ParamCompletion;
+use pine::syntax::lint::LintWarning;
+use pine::syntax::quick_fix::{QuickFix, TextEdit as StrTextEdit};
+use pine::syntax::structure::FoldingRange as StrFoldingRange;
 use pine::syntax::{SyntaxContext, SyntaxParser};
 use pine::PineScript;
 use std::mem;
@@ -11,6 +16,10 @@ pub struct TextDoc<'a> {
     uri: Url,
     line_lens: Vec<usize>,
     syntax_ctx: Option<Box<SyntaxContext<'a>>>,
+    lint_warnings: Vec<LintWarning>,
+    quick_fixes: Vec<QuickFix>,
+    // The script is kept between the changes to reuse the AST of the unchanged statements.
+    pine_script: PineScript<'a, 'a, 'a>,
 }
 
 fn get_line_lens(text: &str) -> Vec<usize> {
@@ -33,11 +42,16 @@ fn get_line_lens(text: &str) -> Vec<usize> {
 impl<'a> TextDoc<'a> {
     pub fn new(text: String, uri: Url) -> TextDoc<'a> {
         let line_lens = get_line_lens(&text);
+        let mut pine_script = PineScript::new(None);
+        pine_script.set_incremental_parse(true);
         TextDoc {
             text,
             uri,
             line_lens,
             syntax_ctx: None,
+            lint_warnings: vec![],
+            quick_fixes: vec![],
+            pine_script,
         }
     }
 
@@ -83,8 +97,13 @@ impl<'a> TextDoc<'a> {
     // }
 
     pub fn parse_src(&mut self) -> Result<(), Vec<PineFormatError>> {
-        let mut pine_script = PineScript::new(None);
-        let result = pine_script.parse_src(&self.text);
+        let pine_script = &mut self.pine_script;
+        let result = pine_script.parse_src(self.text.clone());
+        self.lint_warnings = match result {
+            Ok(_) => pine_script.lint_warnings(),
+            Err(_) => vec![],
+        };
+        self.quick_fixes = pine_script.quick_fixes();
         let parser = pine_script.move_parser();
         if parser.is_some() {
             unsafe {
@@ -96,6 +115,35 @@ impl<'a> TextDoc<'a> {
         }
         result
     }
+
+    pub fn get_lint_warnings(&self) -> &Vec<LintWarning> {
+        &self.lint_warnings
+    }
+
+    pub fn get_quick_fixes(&self) -> &Vec<QuickFix> {
+        &self.quick_fixes
+    }
+
+    // The script keeps the source of the last change even if it fails to parse.
+    pub fn param_completions(&self, pos: StrPos) -> Vec<ParamCompletion> {
+        self.pine_script.param_completions(pos)
+    }
+
+    pub fn hover_doc(&self, pos: StrPos) -> Option<String> {
+        self.pine_script.hover_doc(pos)
+    }
+
+    pub fn rename(&self, pos: StrPos, new_name: &str) -> Result<Vec<StrTextEdit>, PineFormatError> {
+        self.pine_script.rename(pos, new_name)
+    }
+
+    pub fn folding_ranges(&self) -> Vec<StrFoldingRange> {
+        self.pine_script.folding_ranges()
+    }
+
+    pub fn selection_ranges(&self, pos: StrPos) -> Vec<StrRange> {
+        self.pine_script.selection_ranges(pos)
+    }
 }
 
 #[cfg(test)]
@@ -42,8 +42,8 @@ macro_rules! log {
 // }
 
 use pine::runtime::{
-    AnySeries, InputVal, NoneCallback, OutputData, OutputDataCollect, OutputInfo, PineFormatError,
-    PlotInfo, StrOptionsData, SymbolInfo,
+    AnySeries, ChartInfo, HostEvent, InputVal, NoneCallback, OutputData, OutputDataCollect,
+    OutputInfo, PineFormatError, PlotInfo, StrOptionsData, SymbolInfo,
 };
 use pine::PineScript;
 use std::convert::TryInto;
@@ -81,6 +81,83 @@ pub fn parse_src(runner: &mut ExportPineRunner, src: String) -> Result<(), JsVal
     }
 }
 
+#[wasm_bindgen]
+pub fn set_fixed_inputs(runner: &mut ExportPineRunner, input_val: JsValue) {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    let input: Option<Vec<Option<InputVal>>> = input_val.into_serde().unwrap();
+    runner_ins.set_fixed_inputs(input);
+}
+
+#[wasm_bindgen]
+pub fn set_chart_info(runner: &mut ExportPineRunner, chart_info: JsValue) {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    let info: Option<Rc<ChartInfo>> = match chart_info.into_serde() {
+        Ok(info) => Some(Rc::new(info)),
+        Err(_) => None,
+    };
+    runner_ins.set_chart_info(info);
+}
+
+#[wasm_bindgen]
+pub fn get_warnings(runner: &mut ExportPineRunner) -> JsValue {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    JsValue::from_serde(&runner_ins.get_warnings()).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn lint(runner: &mut ExportPineRunner) -> JsValue {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    JsValue::from_serde(&runner_ins.lint()).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn audit_repaint(runner: &mut ExportPineRunner) -> JsValue {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    JsValue::from_serde(&runner_ins.audit_repaint()).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn get_ast_dot(runner: &mut ExportPineRunner, with_scopes: bool) -> String {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    runner_ins.ast_dot(with_scopes)
+}
+
+#[wasm_bindgen]
+pub fn get_dependencies(runner: &mut ExportPineRunner) -> JsValue {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    JsValue::from_serde(&runner_ins.dependencies()).unwrap()
+}
+
+#[wasm_bindgen]
+pub fn get_strategy_result(runner: &mut ExportPineRunner) -> JsValue {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    JsValue::from_serde(&runner_ins.get_strategy_result()).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn gen_io_info(runner: &mut ExportPineRunner) -> Result<JsValue, JsValue> {
     let runner_ins = unsafe {
@@ -144,6 +221,16 @@ pub fn output_options(output: &mut ExportOutputData) -> JsValue {
     }
 }
 
+// The interned texts of every bar given by the series string arguments, e.g. the plotchar text.
+#[wasm_bindgen]
+pub fn output_texts(output: &mut ExportOutputData) -> JsValue {
+    let output_d = unsafe { transmute::<_, &mut Option<OutputData>>(output.output_data) };
+    match output_d {
+        None => JsValue::from_serde(&Vec::<StrOptionsData>::new()).unwrap(),
+        Some(output_d) => JsValue::from_serde(&output_d.texts).unwrap(),
+    }
+}
+
 #[wasm_bindgen]
 pub fn output_colors(output: &mut ExportOutputData) -> *mut i32 {
     let mut res: Vec<i32> = Vec::new();
@@ -190,6 +277,20 @@ pub fn output_array(array: &ExportOutputArray) -> JsValue {
     JsValue::from_serde(&vec![output.from, output.to, output.data_list.len() as i32]).unwrap()
 }
 
+// The blended background color of every bar, null if the script doesn't call bgcolor.
+#[wasm_bindgen]
+pub fn output_array_bgcolors(array: &ExportOutputArray) -> JsValue {
+    let output = unsafe { transmute::<_, &mut OutputDataCollect>(array.outputs) };
+    JsValue::from_serde(&output.bgcolors).unwrap()
+}
+
+// The values of the plots with the positive offsets after the last bar, see `OutputDataCollect`.
+#[wasm_bindgen]
+pub fn output_array_projections(array: &ExportOutputArray) -> JsValue {
+    let output = unsafe { transmute::<_, &mut OutputDataCollect>(array.outputs) };
+    JsValue::from_serde(&output.projections).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn output_array_get(array: &ExportOutputArray, i: usize) -> ExportOutputData {
     let output = unsafe { transmute::<_, &mut OutputDataCollect>(array.outputs) };
@@ -221,6 +322,24 @@ pub fn run_with_input(
     }
 }
 
+// Push the event of the host like the changed input or the picked chart point, the outputs are
+// undefined if no input is changed by the event.
+#[wasm_bindgen]
+pub fn push_event(
+    runner: &mut ExportPineRunner,
+    event: JsValue,
+) -> Result<Option<ExportOutputArray>, JsValue> {
+    let runner_ins = unsafe {
+        let script = transmute::<*mut (), *mut PineScript>(runner.script);
+        script.as_mut().unwrap()
+    };
+    let event: HostEvent = event.into_serde().unwrap();
+    match runner_ins.push_event(&event) {
+        Ok(output) => Ok(output.map(output_data_to_slice)),
+        Err(err) => Err(JsValue::from_serde(&err).unwrap()),
+    }
+}
+
 fn slice_input_data(origin_data: &[f64], index: usize, count: usize) -> Vec<Option<f64>> {
     origin_data[index * count..(index + 1) * count]
         .iter()
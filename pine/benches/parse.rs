@@ -0,0 +1,59 @@
+extern crate pine;
+use pine::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+use pine::libs::declare_vars;
+use pine::runtime::data_src::NoneCallback;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// The block repeated with renamed variables to build the large script.
+const BLOCK: &'static str = r#"
+len{i} = input({i} + 1, title="Length {i}", minval=1)
+fast{i} = ema(close, len{i})
+slow{i} = sma(close, len{i} * 2)
+[mid{i}, upper{i}, lower{i}] = bb(close, len{i}, 2)
+diff{i} = if fast{i} > slow{i}
+    fast{i} - slow{i}
+else
+    slow{i} - fast{i}
+f{i}(x, y) => x > y ? x - y : y - x
+plot(f{i}(upper{i}, lower{i}) + diff{i}, title="Plot {i}", color=#3BB3E4)
+"#;
+
+fn gen_script(blocks: usize) -> String {
+    let mut script = String::from("study(title=\"Large Script\")\n");
+    for i in 0..blocks {
+        script.push_str(&BLOCK.replace("{i}", &i.to_string()));
+    }
+    script
+}
+
+fn parse_script(script: &str) {
+    let lib_info = pine::LibInfo::new(
+        declare_vars(),
+        vec![
+            ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("open", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("high", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("low", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("volume", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ("bar_index", SyntaxType::Series(SimpleSyntaxType::Int)),
+        ],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    assert!(parser.parse_src(String::from(script)).is_ok());
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for blocks in [10usize, 100, 500].iter() {
+        let script = gen_script(*blocks);
+        group.bench_function(format!("{} lines", script.lines().count()), |b| {
+            b.iter(|| parse_script(black_box(&script)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
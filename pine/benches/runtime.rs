@@ -0,0 +1,188 @@
+extern crate pine;
+use pine::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+use pine::libs::declare_vars;
+use pine::runtime::data_src::NoneCallback;
+use pine::runtime::output::InputVal;
+use pine::runtime::AnySeries;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const INDICATOR_SCRIPT: &'static str = r#"
+study(title="Bench Indicator")
+len = input(14, title="Length", minval=1)
+fast = ema(close, 12)
+slow = ema(close, 26)
+[macd_line, signal, hist] = macd(close, 12, 26, 9)
+r = rsi(close, len)
+[mid, upper, lower] = bb(close, 20, 2)
+hh = highest(high, len)
+ll = lowest(low, len)
+k = 100 * (close - ll) / (hh - ll)
+trend = fast > slow ? 1 : -1
+plot(r, title="RSI")
+plot(k, title="K")
+plot(hist, title="Histogram")
+plot(upper - lower, title="Width")
+plot(trend * atr(len), title="Trend")
+"#;
+
+const WINDOW_SCRIPT: &'static str = r#"
+study(title="Bench Window")
+len = LENGTH
+hh = highest(high, len)
+ll = lowest(low, len)
+hb = highestbars(high, len)
+lb = lowestbars(low, len)
+plot((close - ll) / (hh - ll), title="Position")
+plot(hb - lb, title="Distance")
+"#;
+
+const FUNCTION_SCRIPT: &'static str = r#"
+study(title="Bench Functions")
+len = input(10, title="Length", minval=1)
+scale(x, k) => x * k
+diff(x) =>
+    d = x - x[1]
+    na(d) ? 0 : d
+smooth(x) =>
+    s = 0.0
+    s := scale(x, 0.5) + scale(nz(s[1]), 0.5)
+    s
+band(x, n) =>
+    if x > 0
+        scale(x, n)
+    else
+        scale(-x, n)
+v = 0.0
+for i = 1 to 5
+    v := v + smooth(diff(close)) * i
+plot(v, title="Value")
+plot(band(smooth(close - open), len), title="Band")
+"#;
+
+fn new_lib_info<'a>() -> pine::LibInfo<'a> {
+    pine::LibInfo::new(
+        declare_vars(),
+        vec![
+            ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("open", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("high", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("low", SyntaxType::Series(SimpleSyntaxType::Float)),
+            ("volume", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ("bar_index", SyntaxType::Series(SimpleSyntaxType::Int)),
+        ],
+    )
+}
+
+// Generate the deterministic OHLC bars.
+fn gen_data(len: usize) -> Vec<(&'static str, AnySeries)> {
+    let closes: Vec<f64> = (0..len)
+        .map(|i| 100f64 + (i as f64 / 10f64).sin() * 10f64 + (i % 7) as f64)
+        .collect();
+    let series =
+        |offset: f64| AnySeries::from_float_vec(closes.iter().map(|c| Some(c + offset)).collect());
+    vec![
+        ("close", series(0f64)),
+        ("open", series(-0.5f64)),
+        ("high", series(1f64)),
+        ("low", series(-1f64)),
+    ]
+}
+
+fn run_bars(c: &mut Criterion) {
+    let data = gen_data(100_000);
+    let mut group = c.benchmark_group("runtime");
+    group.sample_size(10);
+    group.bench_function("run 100k bars", |b| {
+        b.iter_batched(
+            || data.clone(),
+            |data| {
+                let lib_info = new_lib_info();
+                let mut script =
+                    pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+                script.parse_src(String::from(INDICATOR_SCRIPT)).unwrap();
+                assert!(script.run_with_data(data, None).is_ok());
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+// The window extremes with the short and the long length, the cost of each bar should not grow
+// with the length.
+fn window_extremes(c: &mut Criterion) {
+    let data = gen_data(100_000);
+    let mut group = c.benchmark_group("runtime");
+    group.sample_size(10);
+    for length in [5, 500].iter() {
+        let src = WINDOW_SCRIPT.replace("LENGTH", &length.to_string());
+        group.bench_function(format!("highest/lowest {} on 100k bars", length), |b| {
+            b.iter_batched(
+                || data.clone(),
+                |data| {
+                    let lib_info = new_lib_info();
+                    let mut script =
+                        pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+                    script.parse_src(src.clone()).unwrap();
+                    assert!(script.run_with_data(data, None).is_ok());
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// Rerun the script with many user-function calls after the input is changed, the contexts of the
+// function calls are reused by the reruns.
+fn rerun_functions(c: &mut Criterion) {
+    let lib_info = new_lib_info();
+    let mut script = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    script.parse_src(String::from(FUNCTION_SCRIPT)).unwrap();
+    script.run_with_data(gen_data(10_000), None).unwrap();
+
+    let mut length = 10;
+    let mut group = c.benchmark_group("runtime");
+    group.sample_size(10);
+    group.bench_function("rerun user functions on 10k bars", |b| {
+        b.iter(|| {
+            length = length % 20 + 1;
+            let input = vec![Some(InputVal::Int(length))];
+            assert!(script.run_with_input(input).is_ok());
+        })
+    });
+    group.finish();
+}
+
+fn update_bar(c: &mut Criterion) {
+    let lib_info = new_lib_info();
+    let mut script = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    script.parse_src(String::from(INDICATOR_SCRIPT)).unwrap();
+    script.run_with_data(gen_data(10_000), None).unwrap();
+
+    // Update the last bar with the new realtime price.
+    let mut price = 100f64;
+    c.bench_function("update last bar", |b| {
+        b.iter(|| {
+            price += 0.01f64;
+            let tick = vec![
+                ("close", AnySeries::from_float_vec(vec![Some(price)])),
+                ("open", AnySeries::from_float_vec(vec![Some(100f64)])),
+                ("high", AnySeries::from_float_vec(vec![Some(price + 1f64)])),
+                ("low", AnySeries::from_float_vec(vec![Some(99f64)])),
+            ];
+            assert!(script.update(tick).is_ok());
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    run_bars,
+    window_extremes,
+    rerun_functions,
+    update_bar
+);
+criterion_main!(benches);
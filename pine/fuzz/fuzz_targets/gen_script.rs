@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pine::testing::{GenConfig, ScriptGen};
+
+// Use the input as the seed of the grammar-aware generator, so the generated scripts
+// are always near the valid grammar.
+fuzz_target!(|data: &[u8]| {
+    let mut seed = 0u64;
+    for (i, b) in data.iter().take(8).enumerate() {
+        seed |= (*b as u64) << (i * 8);
+    }
+    let mut gen = ScriptGen::new(seed, GenConfig::default());
+    pine::testing::check_script(&gen.gen_script());
+    pine::testing::check_script(&gen.gen_mutated_script());
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use pine::testing::check_script;
+
+// Feed the raw bytes as the script source.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(src) = std::str::from_utf8(data) {
+        check_script(src);
+    }
+});
@@ -1,5 +1,5 @@
 use super::input::Input;
-use super::syntax_type::SimpleSyntaxType;
+use super::syntax_type::{SimpleSyntaxType, TypeQualifier};
 use nom::error::{ErrorKind, ParseError};
 use nom::Err;
 use nom::IResult;
@@ -42,6 +42,7 @@ pub enum PineErrorKind {
     ExpReturnNa,                  // The block in expression return na
     TypeMismatch,                 // The return type is not match
     ForRangeIndexNotInt,          // The index of for-range expression is not int
+    ForInNotIterable,             // The iterated expression of for-in is not an array
     UnaryTypeNotNum,              // The destination type is not num for unary operator.
     BinaryTypeNotNum,             // The destination type is not num for binary operator.
     BoolExpTypeNotBool,           // The type of bool expression is not bool
@@ -49,6 +50,23 @@ pub enum PineErrorKind {
     BreakNotInForStmt,            // Use break in non for-range statement.
     ContinueNotInForStmt,         // Use break in non for-range statement.
     NonRecongnizeStmt,            // This statement is not recongnized.
+    ScriptDeclNotTopLevel,        // The script declaration is not a statement in the top level.
+    ScriptDeclDuplicate,          // The script declaration is called more than once.
+    ArgQualifierNotMatch {
+        param: String,
+        qualifier: TypeQualifier,
+        found: TypeQualifier,
+    }, // The argument is passed to the parameter requiring the stronger qualifier.
+    ShadowBuiltinVar,             // The declared variable shadows the builtin variable or function.
+    TypeArgNotMatch,              // The function doesn't accept the type argument like `<float>`.
+    BinaryOpTypeNotMatch {
+        op: String,
+        left: String,
+        right: String,
+    }, // The operand types are not valid for the binary operator.
+    TupleVarAssign,               // The tuple like `[a, b] := f()` can't be reassigned.
+    InvalidVarAssignTarget,       // The target of `:=` is not a variable name like `a.b := 1`.
+    InputDefvalNotInOptions,      // The default value of the input is not one of the options.
     UnknownErr,                   // Unknown error.
 }
 
@@ -0,0 +1,232 @@
+use super::input::{Position, StrRange};
+use super::stat_expr_types::Block;
+use super::state::PineInputError;
+use crate::parse_ast_from;
+use std::collections::HashMap;
+use std::mem;
+
+// The source lines of one top-level statement with the following comments and empty lines.
+struct AstChunk {
+    start_line: u32,
+    // The block borrows from the text, so the text must be dropped after the block.
+    blk: Option<Block<'static>>,
+    errors: Vec<PineInputError>,
+    text: Box<str>,
+}
+
+impl AstChunk {
+    fn parse(start_line: u32, text: &str) -> AstChunk {
+        let text: Box<str> = Box::from(text);
+        // The text is moved with the box, so the parsed nodes keep pointing to the same heap data.
+        let src = unsafe { mem::transmute::<&str, &'static str>(&text) };
+        let (blk, errors) = if is_blank(src) {
+            (None, vec![])
+        } else {
+            match parse_ast_from(src, Position::new(start_line, 0)) {
+                Ok(blk) => (Some(blk), vec![]),
+                Err((blk, errors)) => (blk, errors),
+            }
+        };
+        AstChunk {
+            start_line,
+            blk,
+            errors,
+            text,
+        }
+    }
+}
+
+fn is_blank(text: &str) -> bool {
+    text.lines().all(|line| {
+        let line = line.trim();
+        line.is_empty() || line.starts_with("//")
+    })
+}
+
+// The line starts a top-level statement if it's not indented, the comments and the `else` of
+// the if statement at the line start continue the previous statement.
+fn is_stmt_start(line: &str) -> bool {
+    match line.chars().next() {
+        Some(c) if !c.is_whitespace() => {
+            if line.starts_with("//") {
+                return false;
+            }
+            match line.strip_prefix("else") {
+                Some(rest) => rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+                None => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+// Split the source into the chunks of the top-level statements, the first chunk starts at the first line.
+fn split_chunks(src: &str) -> Vec<(u32, &str)> {
+    let mut starts: Vec<(u32, usize)> = vec![(0, 0)];
+    let mut offset = 0;
+    for (i, line) in src.split('\n').enumerate() {
+        if i > 0 && is_stmt_start(line) {
+            starts.push((i as u32, offset));
+        }
+        offset += line.len() + 1;
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (line, start))| match starts.get(i + 1) {
+            Some((_, end)) => (*line, &src[*start..*end]),
+            None => (*line, &src[*start..]),
+        })
+        .collect()
+}
+
+// The block parsed with the errors like `parse_ast`, it's boxed since it's much larger than the block.
+pub type IncrementalErr<'a> = Box<(Option<Block<'a>>, Vec<PineInputError>)>;
+
+// Parse the source for the editors on every change. The top-level statements whose lines and
+// positions are the same as the last parse reuse the last nodes, so typing in a line only
+// reparses the statement of that line. The statements after the inserted or deleted lines are
+// parsed again since their positions are moved.
+#[derive(Default)]
+pub struct IncrementalParser {
+    chunks: Vec<AstChunk>,
+    reused_count: usize,
+}
+
+impl IncrementalParser {
+    pub fn new() -> IncrementalParser {
+        IncrementalParser {
+            chunks: vec![],
+            reused_count: 0,
+        }
+    }
+
+    // The count of the chunks reused by the last parse.
+    pub fn get_reused_count(&self) -> usize {
+        self.reused_count
+    }
+
+    // Parse the source like `parse_ast`. Unlike `parse_ast`, the statements after an invalid
+    // statement are still parsed, so the editor can check them.
+    pub fn parse<'a>(&'a mut self, src: &str) -> Result<Block<'a>, IncrementalErr<'a>> {
+        let mut old_chunks: HashMap<u32, AstChunk> = self
+            .chunks
+            .drain(..)
+            .map(|chunk| (chunk.start_line, chunk))
+            .collect();
+        self.reused_count = 0;
+        for (start_line, text) in split_chunks(src) {
+            match old_chunks.remove(&start_line) {
+                Some(chunk) if &*chunk.text == text => {
+                    self.reused_count += 1;
+                    self.chunks.push(chunk);
+                }
+                _ => self.chunks.push(AstChunk::parse(start_line, text)),
+            }
+        }
+
+        // The expression without the statement end is only valid at the end of the script,
+        // the whole source is parsed in one chunk to report it like `parse_ast`.
+        let blks: Vec<&Block> = self.chunks.iter().filter_map(|c| c.blk.as_ref()).collect();
+        let ret_in_middle = blks.iter().rev().skip(1).any(|b| b.ret_stmt.is_some());
+        if blks.is_empty() || ret_in_middle {
+            self.reused_count = 0;
+            self.chunks = vec![AstChunk::parse(0, src)];
+        }
+
+        let mut stmts = vec![];
+        let mut ret_stmt = None;
+        let mut errors = vec![];
+        for chunk in self.chunks.iter() {
+            errors.extend(chunk.errors.iter().cloned());
+            if let Some(blk) = &chunk.blk {
+                stmts.extend(blk.stmts.iter().cloned());
+                ret_stmt = blk.ret_stmt.clone();
+            }
+        }
+        let start = match (stmts.first(), &ret_stmt) {
+            (Some(stmt), _) => Some(stmt.range().start),
+            (None, Some(exp)) => Some(exp.range().start),
+            (None, None) => None,
+        };
+        let end = match (&ret_stmt, stmts.last()) {
+            (Some(exp), _) => Some(exp.range().end),
+            (None, Some(stmt)) => Some(stmt.range().end),
+            (None, None) => None,
+        };
+        let blk = match (start, end) {
+            (Some(start), Some(end)) => {
+                Some(Block::new(stmts, ret_stmt, StrRange::new(start, end)))
+            }
+            // The whole source is parsed in one chunk.
+            _ => self.chunks[0].blk.clone(),
+        };
+        match blk {
+            Some(blk) if errors.is_empty() => Ok(blk),
+            blk => Err(Box::new((blk, errors))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    const SRC: &str = "// @version=4
+m = 1
+f(a) =>
+    b = a + 1
+    b
+
+// the branches
+if close > 1
+    m := 2
+else
+    m := 3
+plot(f(m))
+";
+
+    #[test]
+    fn split_chunks_test() {
+        let starts: Vec<u32> = split_chunks(SRC).iter().map(|c| c.0).collect();
+        assert_eq!(starts, vec![0, 1, 2, 7, 11]);
+        assert_eq!(
+            split_chunks(SRC)[3].1,
+            "if close > 1\n    m := 2\nelse\n    m := 3\n"
+        );
+    }
+
+    #[test]
+    fn same_ast_test() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.parse(SRC).map_err(|e| *e), parse_ast(SRC));
+
+        let src = "m = 1\nplot(m)";
+        assert_eq!(parser.parse(src).map_err(|e| *e), parse_ast(src));
+
+        let src = "m = (1\nplot(m)";
+        assert!(parser.parse(src).is_err());
+
+        // The expression in the middle is an error.
+        let src = "m = 1\nm\nplot(m)";
+        assert_eq!(parser.parse(src).map_err(|e| *e), parse_ast(src));
+    }
+
+    #[test]
+    fn reuse_test() {
+        let mut parser = IncrementalParser::new();
+        assert!(parser.parse(SRC).is_ok());
+        assert_eq!(parser.get_reused_count(), 0);
+
+        // Edit the else branch.
+        let src = SRC.replace("m := 3", "m := 4");
+        assert_eq!(parser.parse(&src).map_err(|e| *e), parse_ast(&src));
+        assert_eq!(parser.get_reused_count(), 4);
+
+        // The statements after the inserted line are parsed again.
+        let src = src.replace("m = 1\n", "m = 1\nn = 2\n");
+        assert_eq!(parser.parse(&src).map_err(|e| *e), parse_ast(&src));
+        assert_eq!(parser.get_reused_count(), 2);
+    }
+}
@@ -3,6 +3,7 @@ pub mod color;
 pub mod comment;
 pub mod error;
 pub mod func_call;
+pub mod incremental;
 pub mod input;
 pub mod name;
 pub mod num;
@@ -14,3 +15,4 @@ pub mod string;
 pub mod syntax_type;
 pub mod trans;
 pub mod utils;
+pub mod visit;
@@ -26,32 +26,56 @@ impl IntNode {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct FloatNode {
+pub struct FloatNode<'a> {
     pub value: f64,
+    // The literal text in the source, e.g. `0.10` or `1e3`. It is None for the literals created
+    // by the syntax parser, e.g. the folded constants.
+    pub text: Option<&'a str>,
     pub range: StrRange,
 }
 
-impl FloatNode {
-    pub fn new(value: f64, range: StrRange) -> FloatNode {
-        FloatNode { value, range }
+impl<'a> FloatNode<'a> {
+    pub fn new(value: f64, range: StrRange) -> FloatNode<'a> {
+        FloatNode {
+            value,
+            text: None,
+            range,
+        }
+    }
+
+    pub fn new_with_text(value: f64, text: &'a str, range: StrRange) -> FloatNode<'a> {
+        FloatNode {
+            value,
+            text: Some(text),
+            range,
+        }
+    }
+
+    // The literal as it is written in the source, or the shortest text that parses back to the
+    // same value.
+    pub fn to_literal(&self) -> String {
+        match self.text {
+            Some(text) => String::from(text),
+            None => format!("{:?}", self.value),
+        }
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Numeral {
-    Float(FloatNode),
+pub enum Numeral<'a> {
+    Float(FloatNode<'a>),
     Int(IntNode),
 }
 
-impl Numeral {
-    pub fn from_i64(val: i64) -> Numeral {
+impl<'a> Numeral<'a> {
+    pub fn from_i64(val: i64) -> Numeral<'a> {
         Numeral::Int(IntNode::new(
             val,
             StrRange::new(Position::new(0, 0), Position::max()),
         ))
     }
 
-    pub fn from_f64(val: f64) -> Numeral {
+    pub fn from_f64(val: f64) -> Numeral<'a> {
         Numeral::Float(FloatNode::new(
             val,
             StrRange::new(Position::new(0, 0), Position::max()),
@@ -124,7 +148,7 @@ pub fn float_sgn_suffix(input: Input) -> PineResult<i64> {
     }
 }
 
-pub fn num_lit(input: Input) -> PineResult<Numeral> {
+pub fn num_lit<'a>(input: Input<'a>) -> PineResult<'a, Numeral<'a>> {
     let (input, out) = recognize(tuple((
         opt(decimal),
         opt(preceded(tag("."), decimal)),
@@ -138,7 +162,11 @@ pub fn num_lit(input: Input) -> PineResult<Numeral> {
     } else if let Ok(f) = f64::from_str(out.src) {
         Ok((
             input,
-            Numeral::Float(FloatNode::new(f, StrRange::from_input(&out))),
+            Numeral::Float(FloatNode::new_with_text(
+                f,
+                out.src,
+                StrRange::from_input(&out),
+            )),
         ))
     } else {
         Err(Err::Error(PineError::from_pine_kind(
@@ -149,7 +177,7 @@ pub fn num_lit(input: Input) -> PineResult<Numeral> {
 }
 
 // match float or int. e.g. 2.12 2.12e121 .111e11  1221
-pub fn num_lit_ws(input: Input) -> PineResult<Numeral> {
+pub fn num_lit_ws<'a>(input: Input<'a>) -> PineResult<'a, Numeral<'a>> {
     let (input, _) = skip_ws(input)?;
     num_lit(input)
 }
@@ -169,8 +197,9 @@ mod tests {
                 num_lit_ws(test_input),
                 Ok((
                     Input::new("", Position::new(0, input_len), Position::max()),
-                    Numeral::Float(FloatNode::new(
+                    Numeral::Float(FloatNode::new_with_text(
                         res,
+                        s,
                         StrRange::new(Position::new(0, 0), Position::new(0, input_len))
                     ))
                 ))
@@ -180,6 +209,28 @@ mod tests {
         test_lit_ws("121.1", 121.1);
         test_lit_ws("121e1", 121e1);
         test_lit_ws("121.1e1", 121.1e1);
+        test_lit_ws("0.10", 0.1);
+    }
+
+    #[test]
+    fn float_literal_test() {
+        let lit = |s| match num_lit(Input::new_with_str(s)) {
+            Ok((_, Numeral::Float(n))) => n,
+            _ => unreachable!(),
+        };
+        assert_eq!(lit("0.10").to_literal(), "0.10");
+        assert_eq!(lit("1e3").to_literal(), "1e3");
+        assert_eq!(lit("0.10").value, lit("0.1").value);
+
+        // The nodes without the source text fall back to the value.
+        assert_eq!(
+            FloatNode::new(0.1, StrRange::new_empty()).to_literal(),
+            "0.1"
+        );
+        assert_eq!(
+            FloatNode::new(-2.0, StrRange::new_empty()).to_literal(),
+            "-2.0"
+        );
     }
 
     #[test]
@@ -3,6 +3,7 @@ use super::error::PineResult;
 use super::input::{Input, StrRange};
 use super::utils::skip_ws;
 use nom::{branch::alt, bytes::complete::tag, combinator::map};
+use std::fmt;
 
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum BinaryOp {
@@ -21,6 +22,27 @@ pub enum BinaryOp {
     BoolOr,
 }
 
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            BinaryOp::Plus => "+",
+            BinaryOp::Minus => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Lt => "<",
+            BinaryOp::Leq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Geq => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::Neq => "!=",
+            BinaryOp::BoolAnd => "and",
+            BinaryOp::BoolOr => "or",
+        };
+        write!(f, "{}", op)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct BinaryOpNode {
     pub op: BinaryOp,
@@ -3,18 +3,19 @@ use super::color::color_lit;
 use super::error::{PineError, PineErrorKind, PineResult};
 use super::func_call::{func_call, func_call_args, func_call_ws};
 use super::input::{Input, StrRange};
-use super::name::{varname, varname_only, varname_ws};
+use super::name::{varname, varname_only, varname_ws, VarName};
 use super::num::num_lit_ws;
 use super::op::*;
 use super::stat_expr_types::*;
 use super::state::{AstState, PineInputError};
 use super::string::string_lit_ws;
+use super::syntax_type::TypeQualifier;
 use super::trans::flatexp_from_components;
 use super::utils::{eat_sep, eat_space, eat_statement, statement_end, statement_indent};
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    combinator::{map, opt},
+    combinator::{map, opt, verify},
     multi::{many0, separated_list},
     sequence::{delimited, preceded, terminated, tuple},
     Err,
@@ -78,14 +79,13 @@ pub fn flatexp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, FlatExp
 
 pub fn exp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Exp<'a>> {
     alt((
-        map(|s| condition(s, state), |exp| Exp::Condition(Box::new(exp))), // match a ? b : c
         map(eat_sep(|s| assign_expr(s, state)), |s| {
             Exp::Assignment(Box::new(s))
         }), // a = b
         map(eat_sep(|s| var_assign_expr(s, state)), |s| {
             Exp::VarAssignment(Box::new(s))
         }), // a := b
-        map(|s| flatexp(s, state), Exp::from),
+        |s| condition_or_flatexp(s, state), // match a ? b : c or a
     ))(input)
 }
 
@@ -99,6 +99,9 @@ pub fn all_exp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Exp<'a>
         map(eat_sep(|s| for_range_exp(s, state)), |s| {
             Exp::ForRange(Box::new(s))
         }),
+        map(eat_sep(|s| for_in_exp(s, state)), |s| {
+            Exp::ForIn(Box::new(s))
+        }),
     ))(input)
 }
 
@@ -112,6 +115,9 @@ pub fn exp_with_stmt_end<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
         map(eat_sep(|s| for_range_exp(s, state)), |s| {
             Exp::ForRange(Box::new(s))
         }),
+        map(eat_sep(|s| for_in_exp(s, state)), |s| {
+            Exp::ForIn(Box::new(s))
+        }),
     ))(input)
 }
 
@@ -204,11 +210,13 @@ fn type_cast<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, TypeCast<
 //     Ok((input, RefCall::new(name, arg, range)))
 // }
 
+// Apply the member access, reference or function call to the expression, the expression is
+// given back if none of them follows so the caller need not clone it for every attempt.
 fn search_prefix_ref_func<'a>(
     input: Input<'a>,
     state: &AstState,
     exp: Exp<'a>,
-) -> PineResult<'a, Exp<'a>> {
+) -> Result<(Input<'a>, Exp<'a>), Exp<'a>> {
     match eat_sep(tag("."))(input) {
         Err(_) => (),
         Ok((input, _)) => match eat_sep(|s| varname_only(s))(input) {
@@ -236,31 +244,43 @@ fn search_prefix_ref_func<'a>(
         }
     };
 
+    // The namespace function can be called with the type argument, e.g. `array.new<float>()`.
+    let (call_input, type_arg) = match exp {
+        Exp::PrefixExp(_) => match type_arg(input, state) {
+            Ok((input, data_type)) => (input, Some(data_type.value)),
+            Err(_) => (input, None),
+        },
+        _ => (input, None),
+    };
     match tuple((
         // eat_sep(tag("(")),
         eat_space(tag("(")), // The function call cannot cross multi lines
         |s| func_call_args(s, state),
         eat_sep(tag(")")),
-    ))(input)
+    ))(call_input)
     {
         Err(_) => (),
         Ok((input, (_, (pos_args, dict_args), paren_r))) => {
             let start = exp.range().start;
-            return Ok((
-                input,
-                Exp::FuncCall(Box::new(FunctionCall::new_no_ctxid(
-                    exp,
-                    pos_args,
-                    dict_args,
-                    StrRange::new(start, paren_r.end),
-                ))),
-            ));
+            let mut func_call = FunctionCall::new_no_ctxid(
+                exp,
+                pos_args,
+                dict_args,
+                StrRange::new(start, paren_r.end),
+            );
+            func_call.type_arg = type_arg;
+            return Ok((input, Exp::FuncCall(Box::new(func_call))));
         }
     };
-    Err(Err::Error(PineError::from_pine_kind(
-        input,
-        PineErrorKind::UnknownErr,
-    )))
+    Err(exp)
+}
+
+fn type_arg<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, DataTypeNode<'a>> {
+    delimited(
+        eat_space(tag("<")),
+        |s| datatype(s, state),
+        eat_space(tag(">")),
+    )(input)
 }
 
 fn prefix_ref_func_call<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Exp<'a>> {
@@ -273,12 +293,15 @@ fn prefix_ref_func_call<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a
     )))(input)?;
     let mut cur_input = input;
     loop {
-        match search_prefix_ref_func(cur_input, state, var_exp.clone()) {
+        match search_prefix_ref_func(cur_input, state, var_exp) {
             Ok((input, exp)) => {
                 cur_input = input;
                 var_exp = exp;
             }
-            Err(_) => break,
+            Err(exp) => {
+                var_exp = exp;
+                break;
+            }
         };
     }
     if let Exp::VarName(RVVarName { name, .. }) = var_exp {
@@ -293,17 +316,27 @@ fn bracket_expr<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Exp<'a
     delimited(eat_sep(tag("(")), |s| all_exp(s, state), eat_sep(tag(")")))(input)
 }
 
-fn condition<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Condition<'a>> {
-    let (input, (cond, _, exp1, _, exp2)) = tuple((
-        map(|s| flatexp(s, state), |s| Exp::from(s)),
+// Parse the flat expression only once and then check if it is the condition of `a ? b : c`,
+// the flat expression is returned alone if the condition branches do not follow.
+fn condition_or_flatexp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Exp<'a>> {
+    let (input, cond) = map(|s| flatexp(s, state), Exp::from)(input)?;
+    match tuple((
         eat_sep(tag("?")),
         |s| all_exp(s, state),
         eat_sep(tag(":")),
         |s| all_exp(s, state),
-    ))(input)?;
-
-    let range = StrRange::new(cond.range().start, exp2.range().end);
-    Ok((input, Condition::new(cond, exp1, exp2, range)))
+    ))(input)
+    {
+        Ok((input, (_, exp1, _, exp2))) => {
+            let range = StrRange::new(cond.range().start, exp2.range().end);
+            Ok((
+                input,
+                Exp::Condition(Box::new(Condition::new(cond, exp1, exp2, range))),
+            ))
+        }
+        Err(Err::Error(_)) => Ok((input, cond)),
+        Err(e) => Err(e),
+    }
 }
 
 // fn prefix_exp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, PrefixExp<'a>> {
@@ -423,6 +456,50 @@ fn for_range_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
     })(input)
 }
 
+fn for_in<'a, F>(block_parser: F) -> impl Fn(Input<'a>, &AstState) -> PineResult<'a, ForIn<'a>>
+where
+    F: Fn(Input<'a>, &AstState) -> PineResult<'a, Block<'a>>,
+{
+    move |input: Input<'a>, state| {
+        let (input, (for_tag, (index_var, var), _, iter, _, do_blk)) = tuple((
+            atom_val("for"),
+            alt((
+                map(
+                    tuple((
+                        eat_sep(tag("[")),
+                        |s| varname_ws(s, state),
+                        eat_sep(tag(",")),
+                        |s| varname_ws(s, state),
+                        eat_sep(tag("]")),
+                    )),
+                    |(_, index_var, _, var, _)| (Some(index_var), var),
+                ),
+                map(|s| varname_ws(s, state), |var| (None, var)),
+            )),
+            eat_sep(atom_val("in")),
+            |s| all_exp(s, state),
+            statement_end,
+            |s| block_parser(s, state),
+        ))(input)?;
+
+        let range = StrRange::new(for_tag.start, do_blk.range.end);
+        Ok((
+            input,
+            ForIn::new_no_ctxid(index_var, var, iter, do_blk, range),
+        ))
+    }
+}
+
+pub fn for_in_exp<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, ForIn<'a>> {
+    for_in(inner_block_for_exp)(input, state)
+}
+
+fn for_in_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, ForIn<'a>> {
+    preceded(statement_indent(state.get_indent()), |s| {
+        for_in(inner_block_for_stmt)(s, state)
+    })(input)
+}
+
 pub fn function_def_with_indent<'a>(
     input: Input<'a>,
     state: &AstState,
@@ -431,7 +508,7 @@ pub fn function_def_with_indent<'a>(
         statement_indent(state.get_indent()),
         |s| varname(s, state),
         eat_sep(tag("(")),
-        separated_list(eat_sep(tag(",")), |s| varname_ws(s, state)),
+        separated_list(eat_sep(tag(",")), |s| func_param(s, state)),
         eat_sep(tag(")")),
         eat_sep(tag("=>")),
         alt((
@@ -447,7 +524,51 @@ pub fn function_def_with_indent<'a>(
     ))(input)?;
 
     let range = StrRange::new(name.range.start, body.range.end);
-    Ok((input, FunctionDef::new(name, params, body, range)))
+    let (param_types, params) = params.into_iter().unzip();
+    Ok((
+        input,
+        FunctionDef::new_with_param_types(name, params, param_types, body, range),
+    ))
+}
+
+fn type_qualifier<'a>(
+    input: Input<'a>,
+    _state: &AstState,
+) -> PineResult<'a, (TypeQualifier, StrRange)> {
+    let (input, label) = atom_vals(&["const", "input", "simple", "series"])(input)?;
+    let qualifier = match label.src {
+        "const" => TypeQualifier::Const,
+        "input" => TypeQualifier::Input,
+        "simple" => TypeQualifier::Simple,
+        _ => TypeQualifier::Series,
+    };
+    Ok((input, (qualifier, StrRange::from_input(&label))))
+}
+
+// The function parameter with the optional type annotation, e.g. `x`, `float x`, `simple int len`.
+fn func_param<'a>(
+    input: Input<'a>,
+    state: &AstState,
+) -> PineResult<'a, (Option<ParamType<'a>>, VarName<'a>)> {
+    alt((
+        map(
+            tuple((
+                opt(eat_sep(|s| type_qualifier(s, state))),
+                eat_sep(|s| datatype(s, state)),
+                |s| varname_ws(s, state),
+            )),
+            |(qualifier, data_type, name)| {
+                let start = match qualifier {
+                    Some((_, range)) => range.start,
+                    None => data_type.range.start,
+                };
+                let range = StrRange::new(start, data_type.range.end);
+                let param_type = ParamType::new(qualifier.map(|q| q.0), data_type.value, range);
+                (Some(param_type), name)
+            },
+        ),
+        map(|s| varname_ws(s, state), |name| (None, name)),
+    ))(input)
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -463,8 +584,9 @@ impl<'a> DataTypeNode<'a> {
 }
 
 fn datatype<'a>(input: Input<'a>, _state: &AstState) -> PineResult<'a, DataTypeNode<'a>> {
-    let (input, label) =
-        atom_vals(&["float", "int", "bool", "color", "string", "line", "label"])(input)?;
+    let (input, label) = atom_vals(&[
+        "float", "int", "bool", "color", "string", "line", "label", "polyline",
+    ])(input)?;
     let data_type = match label.src {
         "float" => DataType::Float,
         "int" => DataType::Int,
@@ -598,6 +720,39 @@ pub fn var_assign_stmt<'a>(
     })(input)
 }
 
+// The reassignment with the invalid target like `[a, b] := f()` or `a.b := 1`. The error is caught
+// and the statement is skipped, so the following statements are still parsed.
+fn invalid_var_assign_stmt<'a>(
+    input: Input<'a>,
+    state: &AstState,
+) -> PineResult<'a, Statement<'a>> {
+    let (input, (target, _, val)) = preceded(
+        statement_indent(state.get_indent()),
+        tuple((
+            alt((
+                map(
+                    |s| rettupledef(s, state),
+                    |s| (PineErrorKind::TupleVarAssign, s.range),
+                ),
+                map(
+                    verify(
+                        |s| condition_or_flatexp(s, state),
+                        |s| !matches!(s, Exp::VarName(_)),
+                    ),
+                    |s| (PineErrorKind::InvalidVarAssignTarget, s.range()),
+                ),
+            )),
+            eat_sep(tag(":=")),
+            |s| exp_with_stmt_end(s, state),
+        )),
+    )(input)?;
+    state.catch(PineInputError::new(target.0, target.1));
+    Ok((
+        input,
+        Statement::None(StrRange::new(target.1.start, val.range().end)),
+    ))
+}
+
 fn block_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'a, Block<'a>> {
     let gen_indent = statement_indent(state.get_indent());
 
@@ -648,6 +803,7 @@ fn transfer_block_ret<'a>(mut blk: Block<'a>) -> Block<'a> {
     match blk.stmts.last() {
         Some(&Statement::Ite(_))
         | Some(&Statement::ForRange(_))
+        | Some(&Statement::ForIn(_))
         | Some(&Statement::Assignment(_))
         | Some(&Statement::VarAssignment(_))
         | Some(&Statement::Exp(_)) => match blk.stmts.pop().unwrap() {
@@ -662,6 +818,10 @@ fn transfer_block_ret<'a>(mut blk: Block<'a>) -> Block<'a> {
                 s.do_blk = transfer_block_ret(s.do_blk);
                 Block::new(blk.stmts, Some(Exp::ForRange(s)), blk.range)
             }
+            Statement::ForIn(mut s) => {
+                s.do_blk = transfer_block_ret(s.do_blk);
+                Block::new(blk.stmts, Some(Exp::ForIn(s)), blk.range)
+            }
             Statement::Assignment(assign) => {
                 Block::new(blk.stmts, Some(Exp::Assignment(assign)), blk.range)
             }
@@ -708,6 +868,10 @@ fn statement_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
             |input| for_range_with_indent(input, state),
             |s| Statement::ForRange(Box::new(s)),
         ),
+        map(
+            |input| for_in_with_indent(input, state),
+            |s| Statement::ForIn(Box::new(s)),
+        ),
         map(statement_end, |s| Statement::None(StrRange::from_input(&s))),
         map(
             |input| function_def_with_indent(input, state),
@@ -724,6 +888,7 @@ fn statement_with_indent<'a>(input: Input<'a>, state: &AstState) -> PineResult<'
             |input| var_assign_stmt(input, state),
             |s| Statement::VarAssignment(Box::new(s)),
         ),
+        |input| invalid_var_assign_stmt(input, state),
         map(
             preceded(gen_indent(), |input| exp_with_stmt_end(input, state)),
             |s| Statement::Exp(s),
@@ -864,13 +1029,21 @@ mod tests {
     fn condition_test() {
         check_res(
             "a ? b : c",
-            condition,
-            Condition::new(
+            condition_or_flatexp,
+            Exp::Condition(Box::new(Condition::new(
                 Exp::VarName(RVVarName::new_with_start("a", Position::new(0, 0))),
                 Exp::VarName(RVVarName::new_with_start("b", Position::new(0, 4))),
                 Exp::VarName(RVVarName::new_with_start("c", Position::new(0, 8))),
                 StrRange::from_start("a ? b : c", Position::new(0, 0)),
-            ),
+            ))),
+        );
+
+        // The flat expression is returned if the condition branches are incomplete.
+        check_res_input(
+            "a ? b",
+            condition_or_flatexp,
+            Exp::VarName(RVVarName::new_with_start("a", Position::new(0, 0))),
+            " ? b",
         );
     }
 
@@ -1223,6 +1396,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn for_in_test() {
+        check_res(
+            "for a in b \n    continue\n    a  \n",
+            for_in_exp,
+            ForIn::new_no_ctxid(
+                None,
+                VarName::new_with_start("a", Position::new(0, 4)),
+                Exp::VarName(RVVarName::new_with_start("b", Position::new(0, 9))),
+                Block::new(
+                    vec![Statement::Continue(StrRange::from_start(
+                        "continue",
+                        Position::new(1, 4),
+                    ))],
+                    Some(Exp::VarName(RVVarName::new_with_start(
+                        "a",
+                        Position::new(2, 4),
+                    ))),
+                    StrRange::new(Position::new(1, 4), Position::new(2, 5)),
+                ),
+                StrRange::new(Position::new(0, 0), Position::new(2, 5)),
+            ),
+        );
+        check_res(
+            "for [i, a] in b \n    a  \n",
+            for_in_exp,
+            ForIn::new_no_ctxid(
+                Some(VarName::new_with_start("i", Position::new(0, 5))),
+                VarName::new_with_start("a", Position::new(0, 8)),
+                Exp::VarName(RVVarName::new_with_start("b", Position::new(0, 14))),
+                Block::new(
+                    vec![],
+                    Some(Exp::VarName(RVVarName::new_with_start(
+                        "a",
+                        Position::new(1, 4),
+                    ))),
+                    StrRange::from_start("a", Position::new(1, 4)),
+                ),
+                StrRange::new(Position::new(0, 0), Position::new(1, 5)),
+            ),
+        );
+    }
+
     #[test]
     fn func_def_test() {
         check_res(
@@ -1269,6 +1485,41 @@ mod tests {
                 StrRange::new(Position::new(0, 0), Position::new(1, 8)),
             ),
         );
+
+        check_res(
+            "a(float x, simple int len, color) => x",
+            function_def_with_indent,
+            FunctionDef::new_with_param_types(
+                VarName::new_with_start("a", Position::new(0, 0)),
+                vec![
+                    VarName::new_with_start("x", Position::new(0, 8)),
+                    VarName::new_with_start("len", Position::new(0, 22)),
+                    VarName::new_with_start("color", Position::new(0, 27)),
+                ],
+                vec![
+                    Some(ParamType::new(
+                        None,
+                        DataType::Float,
+                        StrRange::from_start("float", Position::new(0, 2)),
+                    )),
+                    Some(ParamType::new(
+                        Some(TypeQualifier::Simple),
+                        DataType::Int,
+                        StrRange::from_start("simple int", Position::new(0, 11)),
+                    )),
+                    None,
+                ],
+                Block::new(
+                    vec![],
+                    Some(Exp::VarName(RVVarName::new_with_start(
+                        "x",
+                        Position::new(0, 37),
+                    ))),
+                    StrRange::from_start("x", Position::new(0, 37)),
+                ),
+                StrRange::new(Position::new(0, 0), Position::new(0, 38)),
+            ),
+        );
     }
 
     #[test]
@@ -1399,6 +1650,26 @@ mod tests {
         assert_eq!(input.src, "");
     }
 
+    #[test]
+    fn type_arg_func_call_test() {
+        let test_input = Input::new_with_str("array.new<float>(3, 1)");
+        let (input, output) = prefix_ref_func_call(test_input, &AstState::new()).unwrap();
+        assert_eq!(input.src, "");
+        match output {
+            Exp::FuncCall(func_call) => {
+                assert_eq!(func_call.type_arg, Some(DataType::Float));
+                assert_eq!(func_call.pos_args.len(), 2);
+            }
+            _ => unreachable!(),
+        }
+
+        // The comparison of the namespace variable is not a type argument.
+        let test_input = Input::new_with_str("a.b < c");
+        let (input, output) = prefix_ref_func_call(test_input, &AstState::new()).unwrap();
+        assert_eq!(input.src, " < c");
+        assert!(matches!(output, Exp::PrefixExp(_)));
+    }
+
     #[test]
     fn expr_stmt_test() {
         let test_input = Input::new_with_str("hello\nprint(ma)\n");
@@ -1416,7 +1687,7 @@ mod tests {
         assert_eq!(input.src, "");
 
         let test_input = Input::new_with_str("na(myVar) ? 0 : close");
-        let (input, _output) = condition(test_input, &AstState::new()).unwrap();
+        let (input, _output) = condition_or_flatexp(test_input, &AstState::new()).unwrap();
         assert_eq!(input.src, "");
 
         let test_input = Input::new_with_str("myClose = na(myVar) ? 0 : close");
@@ -4,7 +4,7 @@ use super::name::VarName;
 use super::num::Numeral;
 use super::op::{BinaryOp, BinaryOpNode, UnaryOp, UnaryOpNode};
 use super::string::StringNode;
-use super::syntax_type::{FunctionType, SyntaxType};
+use super::syntax_type::{FunctionType, SyntaxType, TypeQualifier};
 
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub struct VarIndex {
@@ -28,6 +28,10 @@ pub struct FunctionCall<'a> {
     pub func_type: Option<FunctionType<'a>>,
     // The index in function definition's specfic definitions
     pub spec_index: i32,
+    // The parameter positions of the dict arguments, they are resolved by the syntax pass
+    pub dict_arg_indexs: Option<Vec<usize>>,
+    // The type argument of the generic function call like `array.new<float>()`
+    pub type_arg: Option<DataType<'a>>,
 }
 
 impl<'a> FunctionCall<'a> {
@@ -47,6 +51,8 @@ impl<'a> FunctionCall<'a> {
             range,
             func_type: None,
             spec_index: 0,
+            dict_arg_indexs: None,
+            type_arg: None,
         }
     }
 
@@ -64,6 +70,8 @@ impl<'a> FunctionCall<'a> {
             range,
             func_type: None,
             spec_index: 0,
+            dict_arg_indexs: None,
+            type_arg: None,
         }
     }
 }
@@ -267,7 +275,7 @@ impl<'a> RVVarName<'a> {
 pub enum Exp<'a> {
     Na(NaNode),
     Bool(BoolNode),
-    Num(Numeral),
+    Num(Numeral<'a>),
     Str(StringNode),
     Color(ColorNode<'a>),
     VarName(RVVarName<'a>),
@@ -280,6 +288,7 @@ pub enum Exp<'a> {
     Condition(Box<Condition<'a>>),
     Ite(Box<IfThenElse<'a>>),
     ForRange(Box<ForRange<'a>>),
+    ForIn(Box<ForIn<'a>>),
     Assignment(Box<Assignment<'a>>),
     VarAssignment(Box<VarAssignment<'a>>),
     UnaryExp(Box<UnaryExp<'a>>),
@@ -303,6 +312,7 @@ impl<'a> Exp<'a> {
             Exp::Condition(node) => node.range,
             Exp::Ite(node) => node.range,
             Exp::ForRange(node) => node.range,
+            Exp::ForIn(node) => node.range,
             Exp::Assignment(node) => node.range,
             Exp::VarAssignment(node) => node.range,
             Exp::UnaryExp(node) => node.range,
@@ -369,7 +379,7 @@ impl<'a> FlatExp<'a> {
 pub enum Exp2<'a> {
     Na(NaNode),
     Bool(BoolNode),
-    Num(Numeral),
+    Num(Numeral<'a>),
     Str(StringNode),
     Color(ColorNode<'a>),
     VarName(VarName<'a>),
@@ -806,11 +816,97 @@ impl<'a> ForRange<'a> {
     }
 }
 
+// The for-in statement iterates the elements of the array, e.g. `for x in arr` or
+// `for [i, x] in arr` that also binds the element index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForIn<'a> {
+    pub index_var: Option<VarName<'a>>,
+    pub var: VarName<'a>,
+    pub iter: Exp<'a>,
+    pub do_blk: Block<'a>,
+    pub ctxid: i32,
+    pub index_varid: i32,
+    pub varid: i32,
+    pub range: StrRange,
+    pub item_type: SyntaxType<'a>,
+    pub result_type: SyntaxType<'a>,
+}
+
+impl<'a> ForIn<'a> {
+    pub fn new_no_ctxid(
+        index_var: Option<VarName<'a>>,
+        var: VarName<'a>,
+        iter: Exp<'a>,
+        do_blk: Block<'a>,
+        range: StrRange,
+    ) -> Self {
+        ForIn {
+            index_var,
+            var,
+            iter,
+            do_blk,
+            ctxid: 0,
+            index_varid: 0,
+            varid: 0,
+            range,
+            item_type: SyntaxType::Any,
+            result_type: SyntaxType::Any,
+        }
+    }
+
+    pub fn get_var_count(&self) -> i32 {
+        self.do_blk.var_count
+    }
+
+    pub fn get_libfun_count(&self) -> i32 {
+        self.do_blk.libfun_count
+    }
+
+    pub fn get_subctx_count(&self) -> i32 {
+        self.do_blk.subctx_count
+    }
+}
+
+// The type annotation of the function parameter, e.g. `series float x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamType<'a> {
+    pub qualifier: Option<TypeQualifier>,
+    pub data_type: DataType<'a>,
+    pub range: StrRange,
+}
+
+impl<'a> ParamType<'a> {
+    pub fn new(
+        qualifier: Option<TypeQualifier>,
+        data_type: DataType<'a>,
+        range: StrRange,
+    ) -> ParamType<'a> {
+        ParamType {
+            qualifier,
+            data_type,
+            range,
+        }
+    }
+
+    pub fn new_no_input(
+        qualifier: Option<TypeQualifier>,
+        data_type: DataType<'a>,
+    ) -> ParamType<'a> {
+        ParamType {
+            qualifier,
+            data_type,
+            range: StrRange::new_empty(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FunctionDef<'a> {
     pub name: VarName<'a>,
     pub gen_name: Option<String>, // The method name generated by the system.
     pub params: Vec<VarName<'a>>,
+    // The optional type annotations of the parameters.
+    pub param_types: Vec<Option<ParamType<'a>>>,
     pub body: Block<'a>,
     pub range: StrRange,
     // The index in global name context
@@ -827,11 +923,23 @@ impl<'a> FunctionDef<'a> {
         params: Vec<VarName<'a>>,
         body: Block<'a>,
         range: StrRange,
+    ) -> FunctionDef<'a> {
+        let param_types = vec![None; params.len()];
+        FunctionDef::new_with_param_types(name, params, param_types, body, range)
+    }
+
+    pub fn new_with_param_types(
+        name: VarName<'a>,
+        params: Vec<VarName<'a>>,
+        param_types: Vec<Option<ParamType<'a>>>,
+        body: Block<'a>,
+        range: StrRange,
     ) -> FunctionDef<'a> {
         FunctionDef {
             name,
             gen_name: None,
             params,
+            param_types,
             body,
             range,
             name_varid: 0,
@@ -849,6 +957,7 @@ impl<'a> FunctionDef<'a> {
         FunctionDef {
             name: VarName::new_with_start("", Position::new(0, 0)),
             gen_name: Some(name),
+            param_types: vec![None; params.len()],
             params,
             body,
             range,
@@ -863,6 +972,7 @@ impl<'a> FunctionDef<'a> {
             name: self.name.clone(),
             gen_name: None,
             params: self.params.clone(),
+            param_types: self.param_types.clone(),
             body: self.body.clone(),
             range: self.range.clone(),
             name_varid: 0,
@@ -893,6 +1003,7 @@ pub enum Statement<'a> {
     VarAssignment(Box<VarAssignment<'a>>),
     Ite(Box<IfThenElse<'a>>),
     ForRange(Box<ForRange<'a>>),
+    ForIn(Box<ForIn<'a>>),
     FuncCall(Box<FunctionCall<'a>>),
     FuncDef(Box<FunctionDef<'a>>),
     Exp(Exp<'a>),
@@ -908,6 +1019,7 @@ impl<'a> Statement<'a> {
             Statement::VarAssignment(assign) => assign.range,
             Statement::Ite(ite) => ite.range,
             Statement::ForRange(for_range) => for_range.range,
+            Statement::ForIn(for_in) => for_in.range,
             Statement::FuncCall(func_call) => func_call.range,
             Statement::FuncDef(func_def) => func_def.range,
             Statement::Exp(exp) => exp.range(),
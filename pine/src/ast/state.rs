@@ -6,11 +6,29 @@ use std::cell::{Cell, RefCell};
 pub struct PineInputError {
     pub code: PineErrorKind,
     pub range: StrRange,
+    // The secondary ranges pointed by the error, e.g. the operands of the binary expression.
+    pub related_ranges: Vec<StrRange>,
 }
 
 impl PineInputError {
     pub fn new(code: PineErrorKind, range: StrRange) -> PineInputError {
-        PineInputError { code, range }
+        PineInputError {
+            code,
+            range,
+            related_ranges: vec![],
+        }
+    }
+
+    pub fn new_with_related(
+        code: PineErrorKind,
+        range: StrRange,
+        related_ranges: Vec<StrRange>,
+    ) -> PineInputError {
+        PineInputError {
+            code,
+            range,
+            related_ranges,
+        }
     }
 }
 
@@ -1,17 +1,88 @@
 use super::stat_expr_types::DataType;
 use std::collections::BTreeMap;
 use std::convert::From;
+use std::fmt;
 use std::rc::Rc;
 use std::string::ToString;
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct FunctionType<'a> {
     pub signature: (Vec<(&'a str, SyntaxType<'a>)>, SyntaxType<'a>),
+    // The candidate types of the type variable `SimpleSyntaxType::Template` in the signature.
+    pub templates: Vec<SimpleSyntaxType>,
+    // The last parameter can be repeated any number of times, e.g. `max(x1, x2, ...)`.
+    pub variadic: bool,
 }
 
 impl<'a> FunctionType<'a> {
     pub fn new(signature: (Vec<(&'a str, SyntaxType<'a>)>, SyntaxType<'a>)) -> FunctionType<'a> {
-        FunctionType { signature }
+        FunctionType {
+            signature,
+            templates: vec![],
+            variadic: false,
+        }
+    }
+
+    // Create the generic signature, e.g. `nz(x: T, y: series T) -> T` with the candidates `[int, float]`.
+    // The candidates are tried in order when matching the arguments.
+    pub fn new_with_templates(
+        templates: Vec<SimpleSyntaxType>,
+        signature: (Vec<(&'a str, SyntaxType<'a>)>, SyntaxType<'a>),
+    ) -> FunctionType<'a> {
+        FunctionType {
+            signature,
+            templates,
+            variadic: false,
+        }
+    }
+
+    pub fn into_variadic(mut self) -> FunctionType<'a> {
+        self.variadic = true;
+        self
+    }
+
+    pub fn is_template(&self) -> bool {
+        !self.templates.is_empty()
+    }
+
+    // Replace the type variable with every candidate type, the non-generic signature is returned as is.
+    pub fn instances(&self) -> Vec<FunctionType<'a>> {
+        if !self.is_template() {
+            return vec![self.clone()];
+        }
+        self.templates
+            .iter()
+            .map(|t| FunctionType {
+                signature: (
+                    self.signature
+                        .0
+                        .iter()
+                        .map(|(name, arg)| (*name, arg.instantiate(t)))
+                        .collect(),
+                    self.signature.1.instantiate(t),
+                ),
+                templates: vec![],
+                variadic: self.variadic,
+            })
+            .collect()
+    }
+
+    // Repeat the last parameter of the variadic signature to accept `len` arguments,
+    // the result is the fixed signature.
+    pub fn expand_args(&self, len: usize) -> FunctionType<'a> {
+        let mut args = self.signature.0.clone();
+        if self.variadic {
+            if let Some(last) = args.last().cloned() {
+                while args.len() < len {
+                    args.push(last.clone());
+                }
+            }
+        }
+        FunctionType {
+            signature: (args, self.signature.1.clone()),
+            templates: self.templates.clone(),
+            variadic: false,
+        }
     }
 
     pub fn arg_names(&self) -> Vec<&'a str> {
@@ -36,6 +107,26 @@ impl<'a> FunctionType<'a> {
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct FunctionTypes<'a>(pub Vec<FunctionType<'a>>);
 
+impl<'a> FunctionTypes<'a> {
+    pub fn has_template(&self) -> bool {
+        self.0.iter().any(|func| func.is_template())
+    }
+
+    // Get the concrete signatures by instantiating the generic ones in place.
+    pub fn instances(&self) -> FunctionTypes<'a> {
+        FunctionTypes(self.0.iter().flat_map(|func| func.instances()).collect())
+    }
+
+    pub fn has_variadic(&self) -> bool {
+        self.0.iter().any(|func| func.variadic)
+    }
+
+    // Get the fixed signatures that accept `len` positional arguments.
+    pub fn expand_args(&self, len: usize) -> FunctionTypes<'a> {
+        FunctionTypes(self.0.iter().map(|func| func.expand_args(len)).collect())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub enum SimpleSyntaxType {
     Int,
@@ -44,6 +135,8 @@ pub enum SimpleSyntaxType {
     Na,
     String,
     Color,
+    // The type variable of the generic function signature.
+    Template,
 }
 
 impl<'a> From<DataType<'a>> for SimpleSyntaxType {
@@ -78,6 +171,28 @@ impl ToString for SimpleSyntaxType {
             SimpleSyntaxType::Na => String::from("na"),
             SimpleSyntaxType::String => String::from("string"),
             SimpleSyntaxType::Color => String::from("color"),
+            SimpleSyntaxType::Template => String::from("T"),
+        }
+    }
+}
+
+// The type qualifiers ordered from the strongest to the weakest. The value with the stronger
+// qualifier can be passed to the parameter that requires the weaker one.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash, PartialOrd, Ord)]
+pub enum TypeQualifier {
+    Const,
+    Input,
+    Simple,
+    Series,
+}
+
+impl fmt::Display for TypeQualifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeQualifier::Const => write!(f, "const"),
+            TypeQualifier::Input => write!(f, "input"),
+            TypeQualifier::Simple => write!(f, "simple"),
+            TypeQualifier::Series => write!(f, "series"),
         }
     }
 }
@@ -105,6 +220,30 @@ pub enum SyntaxType<'a> {
     Any,
 }
 
+// Format the value type like the type declaration in the script, e.g. `series float`, `int[]`.
+impl<'a> fmt::Display for SyntaxType<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyntaxType::Void => write!(f, "void"),
+            SyntaxType::Simple(t) => write!(f, "{}", t.to_string()),
+            SyntaxType::Series(t) => write!(f, "series {}", t.to_string()),
+            SyntaxType::List(t) => write!(f, "{}[]", t.to_string()),
+            SyntaxType::Tuple(types) => {
+                let types: Vec<_> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "[{}]", types.join(", "))
+            }
+            SyntaxType::ObjectClass(name) => write!(f, "{}", name),
+            SyntaxType::Val(t) | SyntaxType::DynamicExpr(t) => t.fmt(f),
+            SyntaxType::ValFunction(t, _) | SyntaxType::ValObjectFunction(t, _, _) => t.fmt(f),
+            SyntaxType::Object(_) => write!(f, "object"),
+            SyntaxType::Function(_)
+            | SyntaxType::ObjectFunction(_, _)
+            | SyntaxType::UserFunction(_) => write!(f, "function"),
+            SyntaxType::Any => write!(f, "any"),
+        }
+    }
+}
+
 impl<'a> SyntaxType<'a> {
     // Get the value type from ValFunction type.
     pub fn get_v_for_vf(&self) -> &Self {
@@ -142,6 +281,39 @@ impl<'a> SyntaxType<'a> {
         }
     }
 
+    // Get the weakest qualifier of the value type. The const and input values have the simple type,
+    // their qualifiers are tracked next to the types by the syntax parser.
+    pub fn get_qualifier(&self) -> Option<TypeQualifier> {
+        match self.get_v_for_vf().get_v_for_de() {
+            SyntaxType::Simple(_) | SyntaxType::List(_) => Some(TypeQualifier::Simple),
+            SyntaxType::Series(_) => Some(TypeQualifier::Series),
+            _ => None,
+        }
+    }
+
+    // Get the simple type of the series type, the other types are returned as is.
+    pub fn to_simple(&self) -> SyntaxType<'a> {
+        match self.get_v_for_vf() {
+            SyntaxType::Series(t) => SyntaxType::Simple(t.clone()),
+            t => t.clone(),
+        }
+    }
+
+    // Replace the type variable with the concrete type.
+    pub fn instantiate(&self, t: &SimpleSyntaxType) -> SyntaxType<'a> {
+        match self {
+            SyntaxType::Simple(SimpleSyntaxType::Template) => SyntaxType::Simple(t.clone()),
+            SyntaxType::Series(SimpleSyntaxType::Template) => SyntaxType::Series(t.clone()),
+            SyntaxType::List(SimpleSyntaxType::Template) => SyntaxType::List(t.clone()),
+            SyntaxType::Tuple(types) => {
+                SyntaxType::Tuple(Rc::new(types.iter().map(|v| v.instantiate(t)).collect()))
+            }
+            SyntaxType::Val(v) => SyntaxType::Val(Box::new(v.instantiate(t))),
+            SyntaxType::DynamicExpr(v) => SyntaxType::DynamicExpr(Box::new(v.instantiate(t))),
+            v => v.clone(),
+        }
+    }
+
     pub fn is_void(&self) -> bool {
         self == &SyntaxType::Void
     }
@@ -212,3 +384,115 @@ impl<'a> SyntaxType<'a> {
         SyntaxType::Series(SimpleSyntaxType::String)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualifier_test() {
+        assert!(TypeQualifier::Const < TypeQualifier::Input);
+        assert!(TypeQualifier::Input < TypeQualifier::Simple);
+        assert!(TypeQualifier::Simple < TypeQualifier::Series);
+
+        assert_eq!(
+            SyntaxType::int().get_qualifier(),
+            Some(TypeQualifier::Simple)
+        );
+        assert_eq!(
+            SyntaxType::Val(Box::new(SyntaxType::float_series())).get_qualifier(),
+            Some(TypeQualifier::Series)
+        );
+        assert_eq!(SyntaxType::Void.get_qualifier(), None);
+
+        assert_eq!(SyntaxType::int_series().to_simple(), SyntaxType::int());
+        assert_eq!(SyntaxType::string().to_simple(), SyntaxType::string());
+    }
+
+    #[test]
+    fn display_test() {
+        assert_eq!(SyntaxType::float_series().to_string(), "series float");
+        assert_eq!(SyntaxType::int().to_string(), "int");
+        assert_eq!(
+            SyntaxType::List(SimpleSyntaxType::String).to_string(),
+            "string[]"
+        );
+        assert_eq!(
+            SyntaxType::Tuple(Rc::new(vec![
+                SyntaxType::bool(),
+                SyntaxType::color_series()
+            ]))
+            .to_string(),
+            "[bool, series color]"
+        );
+        assert_eq!(
+            SyntaxType::Val(Box::new(SyntaxType::string())).to_string(),
+            "string"
+        );
+    }
+
+    #[test]
+    fn template_test() {
+        let func = FunctionType::new_with_templates(
+            vec![SimpleSyntaxType::Int, SimpleSyntaxType::Float],
+            (
+                vec![
+                    ("id", SyntaxType::List(SimpleSyntaxType::Template)),
+                    ("value", SyntaxType::Series(SimpleSyntaxType::Template)),
+                    ("length", SyntaxType::int()),
+                ],
+                SyntaxType::Simple(SimpleSyntaxType::Template),
+            ),
+        );
+        let types = FunctionTypes(vec![func]);
+        assert!(types.has_template());
+        assert_eq!(
+            types.instances(),
+            FunctionTypes(vec![
+                FunctionType::new((
+                    vec![
+                        ("id", SyntaxType::List(SimpleSyntaxType::Int)),
+                        ("value", SyntaxType::int_series()),
+                        ("length", SyntaxType::int()),
+                    ],
+                    SyntaxType::int(),
+                )),
+                FunctionType::new((
+                    vec![
+                        ("id", SyntaxType::List(SimpleSyntaxType::Float)),
+                        ("value", SyntaxType::float_series()),
+                        ("length", SyntaxType::int()),
+                    ],
+                    SyntaxType::float(),
+                )),
+            ])
+        );
+        assert!(!types.instances().has_template());
+    }
+
+    #[test]
+    fn variadic_test() {
+        let func = FunctionType::new((
+            vec![("x1", SyntaxType::int()), ("x2", SyntaxType::float())],
+            SyntaxType::float(),
+        ))
+        .into_variadic();
+        let types = FunctionTypes(vec![func.clone()]);
+        assert!(types.has_variadic());
+        assert_eq!(
+            types.expand_args(4),
+            FunctionTypes(vec![FunctionType::new((
+                vec![
+                    ("x1", SyntaxType::int()),
+                    ("x2", SyntaxType::float()),
+                    ("x2", SyntaxType::float()),
+                    ("x2", SyntaxType::float())
+                ],
+                SyntaxType::float(),
+            ))])
+        );
+        // The parameters are not removed if there are less arguments.
+        assert_eq!(func.expand_args(1).signature, func.signature);
+        assert!(!func.expand_args(1).variadic);
+    }
+}
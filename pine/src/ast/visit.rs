@@ -0,0 +1,252 @@
+// The shared traversal of the syntax tree for the analysis passes. The pass overrides the
+// `visit_*` methods of the nodes it cares about, and calls the `walk_*` function in the override
+// to keep visiting the children. The children are visited in the source order.
+use super::name::VarName;
+use super::stat_expr_types::*;
+
+pub trait Visitor<'a, 'b> {
+    fn visit_blk(&mut self, blk: &'b Block<'a>) {
+        walk_blk(self, blk);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'b Statement<'a>) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        walk_exp(self, exp);
+    }
+
+    fn visit_func_call(&mut self, call: &'b FunctionCall<'a>) {
+        walk_func_call(self, call);
+    }
+
+    fn visit_ite(&mut self, ite: &'b IfThenElse<'a>) {
+        walk_ite(self, ite);
+    }
+
+    fn visit_for_range(&mut self, fr: &'b ForRange<'a>) {
+        walk_for_range(self, fr);
+    }
+
+    fn visit_for_in(&mut self, fi: &'b ForIn<'a>) {
+        walk_for_in(self, fi);
+    }
+
+    fn visit_assign(&mut self, assign: &'b Assignment<'a>) {
+        walk_assign(self, assign);
+    }
+
+    fn visit_var_assign(&mut self, assign: &'b VarAssignment<'a>) {
+        walk_var_assign(self, assign);
+    }
+
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        walk_func_def(self, def);
+    }
+
+    // Every name in the tree, the declared names, the variables, the argument names and the
+    // field names after the dot.
+    fn visit_var_name(&mut self, _name: &'b VarName<'a>) {}
+}
+
+pub fn walk_blk<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, blk: &'b Block<'a>) {
+    blk.stmts.iter().for_each(|s| v.visit_stmt(s));
+    if let Some(exp) = &blk.ret_stmt {
+        v.visit_exp(exp);
+    }
+}
+
+pub fn walk_stmt<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, stmt: &'b Statement<'a>) {
+    match stmt {
+        Statement::Break(_) | Statement::Continue(_) | Statement::None(_) => {}
+        Statement::Assignment(assign) => v.visit_assign(assign),
+        Statement::VarAssignment(assign) => v.visit_var_assign(assign),
+        Statement::Ite(ite) => v.visit_ite(ite),
+        Statement::ForRange(fr) => v.visit_for_range(fr),
+        Statement::ForIn(fi) => v.visit_for_in(fi),
+        Statement::FuncCall(call) => v.visit_func_call(call),
+        Statement::FuncDef(def) => v.visit_func_def(def),
+        Statement::Exp(exp) => v.visit_exp(exp),
+    }
+}
+
+pub fn walk_exp<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, exp: &'b Exp<'a>) {
+    match exp {
+        Exp::Na(_) | Exp::Bool(_) | Exp::Num(_) | Exp::Str(_) | Exp::Color(_) => {}
+        Exp::VarName(name) => v.visit_var_name(&name.name),
+        Exp::Tuple(t) => t.exps.iter().for_each(|e| v.visit_exp(e)),
+        Exp::TypeCast(t) => v.visit_exp(&t.exp),
+        Exp::FuncCall(c) => v.visit_func_call(c),
+        Exp::RefCall(r) => {
+            v.visit_exp(&r.name);
+            v.visit_exp(&r.arg);
+        }
+        Exp::PrefixExp(p) => {
+            v.visit_exp(&p.left_exp);
+            v.visit_var_name(&p.right_name);
+        }
+        Exp::Condition(c) => {
+            v.visit_exp(&c.cond);
+            v.visit_exp(&c.exp1);
+            v.visit_exp(&c.exp2);
+        }
+        Exp::Ite(ite) => v.visit_ite(ite),
+        Exp::ForRange(fr) => v.visit_for_range(fr),
+        Exp::ForIn(fi) => v.visit_for_in(fi),
+        Exp::Assignment(assign) => v.visit_assign(assign),
+        Exp::VarAssignment(assign) => v.visit_var_assign(assign),
+        Exp::UnaryExp(node) => v.visit_exp(&node.exp),
+        Exp::BinaryExp(node) => {
+            v.visit_exp(&node.exp1);
+            v.visit_exp(&node.exp2);
+        }
+    }
+}
+
+pub fn walk_func_call<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, call: &'b FunctionCall<'a>) {
+    v.visit_exp(&call.method);
+    call.pos_args.iter().for_each(|e| v.visit_exp(e));
+    call.dict_args.iter().for_each(|(n, e)| {
+        v.visit_var_name(n);
+        v.visit_exp(e);
+    });
+}
+
+pub fn walk_ite<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, ite: &'b IfThenElse<'a>) {
+    v.visit_exp(&ite.cond);
+    v.visit_blk(&ite.then_blk);
+    if let Some(blk) = &ite.else_blk {
+        v.visit_blk(blk);
+    }
+}
+
+pub fn walk_for_range<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, fr: &'b ForRange<'a>) {
+    v.visit_var_name(&fr.var);
+    v.visit_exp(&fr.start);
+    v.visit_exp(&fr.end);
+    if let Some(step) = &fr.step {
+        v.visit_exp(step);
+    }
+    v.visit_blk(&fr.do_blk);
+}
+
+pub fn walk_for_in<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, fi: &'b ForIn<'a>) {
+    if let Some(index) = &fi.index_var {
+        v.visit_var_name(index);
+    }
+    v.visit_var_name(&fi.var);
+    v.visit_exp(&fi.iter);
+    v.visit_blk(&fi.do_blk);
+}
+
+pub fn walk_assign<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, assign: &'b Assignment<'a>) {
+    assign.names.iter().for_each(|n| v.visit_var_name(n));
+    v.visit_exp(&assign.val);
+}
+
+pub fn walk_var_assign<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(
+    v: &mut V,
+    assign: &'b VarAssignment<'a>,
+) {
+    v.visit_var_name(&assign.name);
+    v.visit_exp(&assign.val);
+}
+
+pub fn walk_func_def<'a, 'b, V: Visitor<'a, 'b> + ?Sized>(v: &mut V, def: &'b FunctionDef<'a>) {
+    v.visit_var_name(&def.name);
+    def.params.iter().for_each(|n| v.visit_var_name(n));
+    v.visit_blk(&def.body);
+}
+
+// The traversal that rewrites the syntax tree, the children are mapped before the parent.
+// The variable names are kept, so the history references and the fields after the dot are not
+// mapped, and neither is the method of the function call.
+pub trait AstMapper<'a> {
+    fn map_exp(&mut self, _exp: &mut Exp<'a>) {}
+
+    fn map_stmt(&mut self, _stmt: &mut Statement<'a>) {}
+}
+
+pub fn walk_blk_mut<'a>(m: &mut dyn AstMapper<'a>, blk: &mut Block<'a>) {
+    blk.stmts.iter_mut().for_each(|s| walk_stmt_mut(m, s));
+    if let Some(exp) = &mut blk.ret_stmt {
+        walk_exp_mut(m, exp);
+    }
+}
+
+fn walk_func_call_mut<'a>(m: &mut dyn AstMapper<'a>, call: &mut FunctionCall<'a>) {
+    call.pos_args.iter_mut().for_each(|e| walk_exp_mut(m, e));
+    call.dict_args
+        .iter_mut()
+        .for_each(|(_, e)| walk_exp_mut(m, e));
+}
+
+pub fn walk_ite_mut<'a>(m: &mut dyn AstMapper<'a>, ite: &mut IfThenElse<'a>) {
+    walk_exp_mut(m, &mut ite.cond);
+    walk_blk_mut(m, &mut ite.then_blk);
+    if let Some(blk) = &mut ite.else_blk {
+        walk_blk_mut(m, blk);
+    }
+}
+
+pub fn walk_for_range_mut<'a>(m: &mut dyn AstMapper<'a>, fr: &mut ForRange<'a>) {
+    walk_exp_mut(m, &mut fr.start);
+    walk_exp_mut(m, &mut fr.end);
+    if let Some(step) = &mut fr.step {
+        walk_exp_mut(m, step);
+    }
+    walk_blk_mut(m, &mut fr.do_blk);
+}
+
+pub fn walk_for_in_mut<'a>(m: &mut dyn AstMapper<'a>, fi: &mut ForIn<'a>) {
+    walk_exp_mut(m, &mut fi.iter);
+    walk_blk_mut(m, &mut fi.do_blk);
+}
+
+pub fn walk_exp_mut<'a>(m: &mut dyn AstMapper<'a>, exp: &mut Exp<'a>) {
+    match exp {
+        Exp::Na(_)
+        | Exp::Bool(_)
+        | Exp::Num(_)
+        | Exp::Str(_)
+        | Exp::Color(_)
+        | Exp::VarName(_)
+        | Exp::PrefixExp(_) => {}
+        Exp::Tuple(t) => t.exps.iter_mut().for_each(|e| walk_exp_mut(m, e)),
+        Exp::TypeCast(t) => walk_exp_mut(m, &mut t.exp),
+        Exp::FuncCall(c) => walk_func_call_mut(m, c),
+        Exp::RefCall(r) => walk_exp_mut(m, &mut r.arg),
+        Exp::Condition(c) => {
+            walk_exp_mut(m, &mut c.cond);
+            walk_exp_mut(m, &mut c.exp1);
+            walk_exp_mut(m, &mut c.exp2);
+        }
+        Exp::Ite(ite) => walk_ite_mut(m, ite),
+        Exp::ForRange(fr) => walk_for_range_mut(m, fr),
+        Exp::ForIn(fi) => walk_for_in_mut(m, fi),
+        Exp::Assignment(assign) => walk_exp_mut(m, &mut assign.val),
+        Exp::VarAssignment(assign) => walk_exp_mut(m, &mut assign.val),
+        Exp::UnaryExp(node) => walk_exp_mut(m, &mut node.exp),
+        Exp::BinaryExp(node) => {
+            walk_exp_mut(m, &mut node.exp1);
+            walk_exp_mut(m, &mut node.exp2);
+        }
+    }
+    m.map_exp(exp);
+}
+
+pub fn walk_stmt_mut<'a>(m: &mut dyn AstMapper<'a>, stmt: &mut Statement<'a>) {
+    match stmt {
+        Statement::Break(_) | Statement::Continue(_) | Statement::None(_) => {}
+        Statement::Assignment(assign) => walk_exp_mut(m, &mut assign.val),
+        Statement::VarAssignment(assign) => walk_exp_mut(m, &mut assign.val),
+        Statement::Ite(ite) => walk_ite_mut(m, ite),
+        Statement::ForRange(fr) => walk_for_range_mut(m, fr),
+        Statement::ForIn(fi) => walk_for_in_mut(m, fi),
+        Statement::FuncCall(call) => walk_func_call_mut(m, call),
+        Statement::FuncDef(def) => walk_blk_mut(m, &mut def.body),
+        Statement::Exp(exp) => walk_exp_mut(m, exp),
+    }
+    m.map_stmt(stmt);
+}
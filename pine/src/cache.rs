@@ -0,0 +1,184 @@
+use crate::ast::stat_expr_types::Block;
+use crate::ast::state::PineInputError;
+use crate::{LibInfo, PineParser};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+// The syntax-checked script that can be shared by many runners.
+#[derive(Debug)]
+pub struct CachedScript<'a> {
+    blk: Block<'a>,
+    input_names: Vec<&'a str>,
+    source: &'a str,
+}
+
+impl<'a> CachedScript<'a> {
+    pub fn get_blk(&self) -> &Block<'a> {
+        &self.blk
+    }
+
+    // The input sources(close, open, etc) used by this script.
+    pub fn get_input_names(&self) -> &Vec<&'a str> {
+        &self.input_names
+    }
+
+    pub fn get_source(&self) -> &'a str {
+        self.source
+    }
+}
+
+// Cache the parsed scripts by the hash of the source, so hosts that evaluate many identical
+// scripts can skip the parsing and syntax checking.
+// All the cached scripts are invalidated when the library info changes.
+pub struct ScriptCache<'a> {
+    lib_hash: u64,
+    scripts: HashMap<u64, Rc<CachedScript<'a>>>,
+}
+
+pub fn hash_source(src: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    src.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn hash_lib_info<'a>(lib_info: &LibInfo<'a>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    lib_info.var_types.hash(&mut hasher);
+    for (name, _) in lib_info.input_names.iter() {
+        name.hash(&mut hasher);
+    }
+    lib_info.client_input_names.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<'a> ScriptCache<'a> {
+    pub fn new(lib_info: &LibInfo<'a>) -> ScriptCache<'a> {
+        ScriptCache {
+            lib_hash: hash_lib_info(lib_info),
+            scripts: HashMap::new(),
+        }
+    }
+
+    // Get the parsed script from the cache or parse and cache it.
+    // The script that contains errors will not be cached. The cached block borrows the source,
+    // so the source must outlive the cache.
+    pub fn get_or_parse(
+        &mut self,
+        src: &'a str,
+        lib_info: &LibInfo<'a>,
+    ) -> Result<Rc<CachedScript<'a>>, Vec<PineInputError>> {
+        let lib_hash = hash_lib_info(lib_info);
+        if lib_hash != self.lib_hash {
+            self.invalidate();
+            self.lib_hash = lib_hash;
+        }
+
+        let src_hash = hash_source(src);
+        if let Some(script) = self.scripts.get(&src_hash) {
+            if script.source == src {
+                return Ok(Rc::clone(script));
+            }
+        }
+
+        match PineParser::new(src, lib_info).parse() {
+            Ok((blk, parser, errs)) => {
+                if !errs.is_empty() {
+                    return Err(errs);
+                }
+                let script = Rc::new(CachedScript {
+                    blk,
+                    input_names: parser.get_inputnames(),
+                    source: src,
+                });
+                self.scripts.insert(src_hash, Rc::clone(&script));
+                Ok(script)
+            }
+            Err(errs) => Err(errs),
+        }
+    }
+
+    pub fn contains(&self, src: &str) -> bool {
+        match self.scripts.get(&hash_source(src)) {
+            Some(script) => script.source == src,
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, src: &str) -> Option<Rc<CachedScript<'a>>> {
+        if self.contains(src) {
+            self.scripts.remove(&hash_source(src))
+        } else {
+            None
+        }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.scripts.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::syntax_type::SyntaxType;
+    use crate::libs::{hl2, plot};
+    use crate::runtime::{AnySeries, NoneCallback, OutputData};
+    use crate::PineRunner;
+
+    #[test]
+    fn script_cache_test() {
+        let callback = NoneCallback();
+        let lib_info = LibInfo::new(
+            vec![plot::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let mut cache = ScriptCache::new(&lib_info);
+
+        let script = cache.get_or_parse("plot(close)", &lib_info).unwrap();
+        let script2 = cache.get_or_parse("plot(close)", &lib_info).unwrap();
+        assert!(Rc::ptr_eq(&script, &script2));
+        assert_eq!(script.get_input_names(), &vec!["close"]);
+        assert_eq!(cache.len(), 1);
+
+        // The scripts with errors are not cached.
+        assert!(cache.get_or_parse("plot(m)", &lib_info).is_err());
+        assert_eq!(cache.len(), 1);
+        assert!(!cache.contains("plot(m)"));
+
+        for i in 1..3 {
+            let mut runner = PineRunner::new(&lib_info, script.get_blk(), &callback);
+            runner
+                .run(
+                    &vec![("close", AnySeries::from_float_vec(vec![Some(i as f64)]))],
+                    None,
+                )
+                .unwrap();
+            assert_eq!(
+                runner.move_output_data(),
+                vec![Some(OutputData::new(vec![vec![Some(i as f64)]]))]
+            );
+        }
+
+        // Changing the library info invalidates the cache.
+        let lib_info2 = LibInfo::new(
+            vec![plot::declare_var(), hl2::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let script3 = cache.get_or_parse("plot(close)", &lib_info2).unwrap();
+        assert!(!Rc::ptr_eq(&script, &script3));
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.remove("plot(close)").is_some());
+        assert!(cache.is_empty());
+    }
+}
@@ -5,4 +5,8 @@ pub const UNRECONGNIZED_TZ: &'static str = "Unrecognized timezone {}.";
 pub const UNRECONGNIZED_RES: &'static str = "Unrecognized resolution {}.";
 pub const GE_1: &'static str = "The value of {} should be greater than or equal to 1.";
 pub const INPUT_SRCS: &'static str = "The input source should be one of {}.";
+pub const INPUT_OPTIONS: &'static str = "The input value {} should be one of the options {}.";
 pub const INVALID_VALS: &'static str = "The input value is invalid for property named {}.";
+pub const STRATEGY_NOT_DECLARED: &'static str = "The {} requires the strategy declaration.";
+pub const ARRAY_SIZE_NOT_MATCH: &'static str = "The arrays {} and {} should have the same size.";
+pub const INPUT_INDEX: &'static str = "The input index {} is out of the {} inputs of the script.";
@@ -48,3 +48,18 @@ pub fn float_add(val1: Float, val2: Float) -> Float {
         _ => None,
     }
 }
+
+// Round the price to the nearest multiple of the min tick. The division by the reciprocal is used
+// when the min tick is like 0.01, so the result has no error like 1.2300000000000002.
+pub fn round_to_mintick(val: f64, mintick: f64) -> f64 {
+    if mintick.is_nan() || mintick <= 0f64 {
+        return val;
+    }
+    let ticks = (val / mintick).round();
+    let recip = (1f64 / mintick).round();
+    if recip >= 1f64 && (recip * mintick - 1f64).abs() < 1e-9 {
+        ticks / recip
+    } else {
+        ticks * mintick
+    }
+}
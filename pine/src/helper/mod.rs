@@ -5,6 +5,7 @@ pub mod node_finder;
 pub mod param_checker;
 pub mod pine_ref;
 pub mod resolution;
+pub mod rolling_window;
 pub mod session;
 pub mod str_replace;
 
@@ -16,6 +17,7 @@ pub use float_ops::*;
 pub use param_checker::*;
 pub use pine_ref::*;
 pub use resolution::*;
+pub use rolling_window::*;
 pub use session::*;
 pub use str_replace::*;
 pub use vec::*;
@@ -185,6 +185,26 @@ impl<'a> NodeFinder<'a> for ForRange<'a> {
     }
 }
 
+impl<'a> NodeFinder<'a> for ForIn<'a> {
+    fn find(&self, pos: Position, state: &mut FindState) -> Option<FindVal<'a>> {
+        if let Some(index_var) = &self.index_var {
+            if index_var.range.contain(pos) {
+                return Some(FindVal::new(index_var.value, self.ctxid));
+            }
+        }
+        if self.var.range.contain(pos) {
+            return Some(FindVal::new(self.var.value, self.ctxid));
+        }
+        if let Some(res) = self.iter.find(pos, state) {
+            return Some(res);
+        }
+        if let Some(e) = self.do_blk.find(pos, state) {
+            return Some(e);
+        }
+        None
+    }
+}
+
 impl<'a> NodeFinder<'a> for UnaryExp<'a> {
     fn find(&self, pos: Position, state: &mut FindState) -> Option<FindVal<'a>> {
         if let Some(v) = self.exp.find(pos, state) {
@@ -254,6 +274,7 @@ impl<'a> NodeFinder<'a> for Exp<'a> {
             Exp::Condition(cond) => cond.find(pos, state),
             Exp::Ite(ite) => ite.find(pos, state),
             Exp::ForRange(for_range) => for_range.find(pos, state),
+            Exp::ForIn(for_in) => for_in.find(pos, state),
             Exp::Assignment(assign) => assign.find(pos, state),
             Exp::VarAssignment(assign) => assign.find(pos, state),
             Exp::UnaryExp(exp) => exp.find(pos, state),
@@ -272,6 +293,7 @@ impl<'a> NodeFinder<'a> for Statement<'a> {
             Statement::VarAssignment(assign) => assign.find(pos, state),
             Statement::Ite(ite) => ite.find(pos, state),
             Statement::ForRange(for_range) => for_range.find(pos, state),
+            Statement::ForIn(for_in) => for_in.find(pos, state),
             Statement::FuncCall(call) => call.find(pos, state),
             Statement::FuncDef(def) => def.find(pos, state),
             Statement::Exp(exp) => exp.find(pos, state),
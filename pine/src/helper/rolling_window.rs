@@ -0,0 +1,439 @@
+use crate::types::Float;
+use std::collections::VecDeque;
+
+// The aggregate of the values in the rolling window. Each value is identified by its position,
+// the positions are increasing and the values leave the window in the order they entered.
+pub trait WindowAgg<T> {
+    // Recalculate the aggregate from the window values once per `length` values, it is needed by
+    // the aggregates that accumulate the rounding errors of the removed values.
+    const RESYNC: bool = false;
+
+    fn push(&mut self, pos: usize, val: &T);
+
+    fn pop(&mut self, pos: usize, val: &T);
+
+    fn clear(&mut self);
+}
+
+// The values of the last `length` bars kept in the ring buffer with the incremental aggregate,
+// so the windowed builtins take O(1) amortized time on each bar instead of O(length).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollingWindow<T, A> {
+    length: usize,
+    values: VecDeque<T>,
+    agg: A,
+    // The position of the next pushed value.
+    next: usize,
+    // The bar of the last update. The window is rebuilt from the source history if the update
+    // is not on the next bar, e.g. the bar is rolled back or the call is not run on every bar.
+    last_bar: Option<i32>,
+}
+
+impl<T, A: WindowAgg<T>> RollingWindow<T, A> {
+    pub fn new(agg: A) -> RollingWindow<T, A> {
+        RollingWindow {
+            length: 0,
+            values: VecDeque::new(),
+            agg,
+            next: 0,
+            last_bar: None,
+        }
+    }
+
+    pub fn get_agg(&self) -> &A {
+        &self.agg
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+
+    // Slide the window to the current bar. `source(i)` gives the value `i` bars ago, the values
+    // before the first bar are expected to be the default value (na) like the series index.
+    pub fn update<F: Fn(usize) -> T>(&mut self, bar: i32, length: usize, source: F) -> &A {
+        if self.last_bar.is_none_or(|last| last + 1 != bar) || self.length != length {
+            self.length = length;
+            self.values.clear();
+            self.agg.clear();
+            self.next = 0;
+            for i in (1..length).rev() {
+                self.push(source(i));
+            }
+        }
+        self.push(source(0));
+        self.last_bar = Some(bar);
+        &self.agg
+    }
+
+    // Rebuild the window on the next update, e.g. after the current bar is rolled back.
+    pub fn invalidate(&mut self) {
+        self.last_bar = None;
+    }
+
+    fn push(&mut self, val: T) {
+        if self.values.len() == self.length {
+            if let Some(old) = self.values.pop_front() {
+                self.agg.pop(self.next - self.length, &old);
+            }
+        }
+        self.agg.push(self.next, &val);
+        self.values.push_back(val);
+        self.next += 1;
+
+        if A::RESYNC && self.next.is_multiple_of(self.length) {
+            self.agg.clear();
+            let start = self.next - self.values.len();
+            for (i, v) in self.values.iter().enumerate() {
+                self.agg.push(start + i, v);
+            }
+        }
+    }
+}
+
+// The count, sum, mean and sum of squared deviations (Welford) of the non-na values in the
+// window. The sum, mean and variance are na if any value in the window is na.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Moments {
+    count: usize,
+    nas: usize,
+    sum: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    pub fn new() -> Moments {
+        Moments::default()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.nas == 0 && self.count > 0
+    }
+
+    pub fn sum(&self) -> Float {
+        if self.is_valid() {
+            Some(self.sum)
+        } else {
+            None
+        }
+    }
+
+    pub fn mean(&self) -> Float {
+        if self.is_valid() {
+            Some(self.sum / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    // The population variance, the removed values can leave a tiny negative error so it is
+    // clamped to zero.
+    pub fn variance(&self) -> Float {
+        if self.is_valid() {
+            Some(self.m2.max(0f64) / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    pub fn stdev(&self) -> Float {
+        self.variance().map(f64::sqrt)
+    }
+}
+
+impl WindowAgg<Float> for Moments {
+    const RESYNC: bool = true;
+
+    fn push(&mut self, _pos: usize, val: &Float) {
+        match *val {
+            Some(val) => {
+                self.count += 1;
+                self.sum += val;
+                let delta = val - self.mean;
+                self.mean += delta / self.count as f64;
+                self.m2 += delta * (val - self.mean);
+            }
+            None => self.nas += 1,
+        }
+    }
+
+    fn pop(&mut self, _pos: usize, val: &Float) {
+        match *val {
+            Some(val) => {
+                self.count -= 1;
+                if self.count == 0 {
+                    self.sum = 0f64;
+                    self.mean = 0f64;
+                    self.m2 = 0f64;
+                } else {
+                    self.sum -= val;
+                    let delta = val - self.mean;
+                    self.mean -= delta / self.count as f64;
+                    self.m2 -= delta * (val - self.mean);
+                }
+            }
+            None => self.nas -= 1,
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Moments::default();
+    }
+}
+
+// The maximum (or minimum) of the window kept by a monotonic deque. The values that can never be
+// the extreme again are dropped when a new value is pushed, so the front is always the extreme
+// and the ties are resolved to the most recent value. The na values are never the extreme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extreme {
+    is_max: bool,
+    // The (position, value) pairs with increasing positions and strictly decreasing values
+    // (increasing for the minimum).
+    items: VecDeque<(usize, f64)>,
+    last: usize,
+}
+
+impl Extreme {
+    pub fn new(is_max: bool) -> Extreme {
+        Extreme {
+            is_max,
+            items: VecDeque::new(),
+            last: 0,
+        }
+    }
+
+    // The extreme value with its offset from the last pushed value, None if all values are na.
+    pub fn get(&self) -> Option<(f64, usize)> {
+        self.items.front().map(|&(pos, val)| (val, self.last - pos))
+    }
+}
+
+impl WindowAgg<Float> for Extreme {
+    fn push(&mut self, pos: usize, val: &Float) {
+        self.last = pos;
+        if let Some(val) = *val {
+            while let Some(&(_, last)) = self.items.back() {
+                if (self.is_max && last <= val) || (!self.is_max && last >= val) {
+                    self.items.pop_back();
+                } else {
+                    break;
+                }
+            }
+            self.items.push_back((pos, val));
+        }
+    }
+
+    fn pop(&mut self, pos: usize, _val: &Float) {
+        if self.items.front().is_some_and(|&(p, _)| p == pos) {
+            self.items.pop_front();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+        self.last = 0;
+    }
+}
+
+// The median of the window kept by the sorted values, each value is inserted and removed by the
+// binary search. The median of the even count is the mean of the two middle values, it is na if
+// any value in the window is na.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Median {
+    sorted: Vec<f64>,
+    nas: usize,
+}
+
+impl Median {
+    pub fn new() -> Median {
+        Median::default()
+    }
+
+    pub fn get(&self) -> Float {
+        let len = self.sorted.len();
+        if self.nas > 0 || len == 0 {
+            return None;
+        }
+        match len % 2 {
+            1 => Some(self.sorted[len / 2]),
+            _ => Some((self.sorted[len / 2 - 1] + self.sorted[len / 2]) / 2f64),
+        }
+    }
+}
+
+impl WindowAgg<Float> for Median {
+    fn push(&mut self, _pos: usize, val: &Float) {
+        match *val {
+            Some(val) if !val.is_nan() => {
+                let index = self.sorted.partition_point(|v| *v < val);
+                self.sorted.insert(index, val);
+            }
+            _ => self.nas += 1,
+        }
+    }
+
+    fn pop(&mut self, _pos: usize, val: &Float) {
+        match *val {
+            Some(val) if !val.is_nan() => {
+                let index = self.sorted.partition_point(|v| *v < val);
+                if self.sorted.get(index) == Some(&val) {
+                    self.sorted.remove(index);
+                }
+            }
+            _ => self.nas -= 1,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.sorted.clear();
+        self.nas = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generate the deterministic values in [-11, 10] with some na values.
+    fn gen_vals(len: usize) -> Vec<Float> {
+        let mut seed = 7u64;
+        (0..len)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                match (seed >> 33) % 23 {
+                    0 => None,
+                    n => Some(n as f64 - 12f64),
+                }
+            })
+            .collect()
+    }
+
+    fn window_of(vals: &[Float], bar: usize, length: usize) -> Vec<Float> {
+        (0..length)
+            .rev()
+            .map(|i| if i > bar { None } else { vals[bar - i] })
+            .collect()
+    }
+
+    #[test]
+    fn moments_test() {
+        let vals: Vec<Float> = gen_vals(300)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| if i < 200 { v.or(Some(1f64)) } else { v })
+            .collect();
+        for &length in [1usize, 2, 5, 17].iter() {
+            let mut window = RollingWindow::new(Moments::new());
+            for bar in 0..vals.len() {
+                let agg = window.update(bar as i32, length, |i| {
+                    if i > bar {
+                        None
+                    } else {
+                        vals[bar - i]
+                    }
+                });
+                let items = window_of(&vals, bar, length);
+                if items.iter().any(|v| v.is_none()) {
+                    assert_eq!(agg.mean(), None);
+                    assert_eq!(agg.variance(), None);
+                    continue;
+                }
+                let items: Vec<f64> = items.into_iter().map(|v| v.unwrap()).collect();
+                let sum: f64 = items.iter().sum();
+                let mean = sum / length as f64;
+                let var = items.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / length as f64;
+                assert!((agg.sum().unwrap() - sum).abs() < 1e-9);
+                assert!((agg.mean().unwrap() - mean).abs() < 1e-9);
+                assert!((agg.variance().unwrap() - var).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_test() {
+        let vals = gen_vals(300);
+        for &is_max in [true, false].iter() {
+            for &length in [1usize, 2, 5, 17, 500].iter() {
+                let mut window = RollingWindow::new(Extreme::new(is_max));
+                for bar in 0..vals.len() {
+                    let agg = window.update(bar as i32, length, |i| {
+                        if i > bar {
+                            None
+                        } else {
+                            vals[bar - i]
+                        }
+                    });
+                    // The reference scans the window from the most recent value.
+                    let mut res: Option<(f64, usize)> = None;
+                    for (i, v) in window_of(&vals, bar, length).into_iter().rev().enumerate() {
+                        if let Some(v) = v {
+                            let better = match res {
+                                None => true,
+                                Some((r, _)) if is_max => v > r,
+                                Some((r, _)) => v < r,
+                            };
+                            if better {
+                                res = Some((v, i));
+                            }
+                        }
+                    }
+                    assert_eq!(agg.get(), res);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn median_test() {
+        let vals = gen_vals(300);
+        for &length in [1usize, 2, 5, 16].iter() {
+            let mut window = RollingWindow::new(Median::new());
+            for bar in 0..vals.len() {
+                let agg = window.update(bar as i32, length, |i| {
+                    if i > bar {
+                        None
+                    } else {
+                        vals[bar - i]
+                    }
+                });
+                let items: Option<Vec<f64>> = window_of(&vals, bar, length).into_iter().collect();
+                let res = items.map(|mut items| {
+                    items.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                    match length % 2 {
+                        1 => items[length / 2],
+                        _ => (items[length / 2 - 1] + items[length / 2]) / 2f64,
+                    }
+                });
+                assert_eq!(agg.get(), res);
+            }
+        }
+    }
+
+    #[test]
+    fn rebuild_test() {
+        let vals = [Some(1f64), Some(5f64), Some(3f64), Some(2f64)];
+        let mut window = RollingWindow::new(Moments::new());
+        for bar in 0..3 {
+            window.update(
+                bar as i32,
+                2,
+                |i| if i > bar { None } else { vals[bar - i] },
+            );
+        }
+        assert_eq!(window.get_agg().sum(), Some(8f64));
+
+        // Update the same bar again with the new value, the window is rebuilt from the source.
+        window.update(2, 2, |i| if i == 0 { Some(4f64) } else { vals[2 - i] });
+        assert_eq!(window.get_agg().sum(), Some(9f64));
+
+        window.invalidate();
+        window.update(3, 3, |i| vals[3 - i]);
+        assert_eq!(window.get_agg().sum(), Some(10f64));
+        assert_eq!(
+            window.values().cloned().collect::<Vec<_>>(),
+            vec![Some(5f64), Some(3f64), Some(2f64)]
+        );
+    }
+}
@@ -88,21 +88,24 @@ impl TradeTimeSpan {
 
     // parse string like 9:10
     pub fn parse_str(start: &str, end: &str) -> TradeTimeSpan {
-        let h1: i32;
-        let m1: i32;
-        let h2: i32;
-        let m2: i32;
+        match TradeTimeSpan::try_parse_str(start, end) {
+            Some(span) => span,
+            None => unreachable!(),
+        }
+    }
+
+    // parse string like 9:10, return None if the strings are not valid times.
+    pub fn try_parse_str(start: &str, end: &str) -> Option<TradeTimeSpan> {
         let re = Regex::new(r"(\d+):(\d+)").unwrap();
         match (re.captures(start), re.captures(end)) {
-            (Some(caps), Some(caps2)) => {
-                h1 = i32::from_str(&caps[1]).unwrap();
-                m1 = i32::from_str(&caps[2]).unwrap();
-                h2 = i32::from_str(&caps2[1]).unwrap();
-                m2 = i32::from_str(&caps2[2]).unwrap();
-            }
-            _ => unreachable!(),
+            (Some(caps), Some(caps2)) => Some(TradeTimeSpan::parse(
+                i32::from_str(&caps[1]).ok()?,
+                i32::from_str(&caps[2]).ok()?,
+                i32::from_str(&caps2[1]).ok()?,
+                i32::from_str(&caps2[2]).ok()?,
+            )),
+            _ => None,
         }
-        TradeTimeSpan::parse(h1, m1, h2, m2)
     }
 
     pub fn is_between(&self, time: &DayTime) -> bool {
@@ -259,6 +262,14 @@ mod tests {
             TradeTimeSpan::parse_str("9:10", "12:00").is_in(ts, &tz),
             true
         );
+        assert_eq!(TradeTimeSpan::try_parse_str("", ""), None);
+        assert_eq!(
+            TradeTimeSpan::try_parse_str("17:00", "16:00"),
+            Some(TradeTimeSpan {
+                start: DayTime::new(-7, 0),
+                end: DayTime::new(16, 0),
+            })
+        );
     }
 
     #[test]
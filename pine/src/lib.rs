@@ -11,38 +11,90 @@ extern crate assert_matches;
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(feature = "runtime")]
 extern crate regex;
 
 pub mod ast;
+#[cfg(feature = "runtime")]
+pub mod cache;
 
+#[cfg(feature = "runtime")]
 #[macro_use]
 pub mod helper;
 
+#[cfg(feature = "runtime")]
 pub mod libs;
+#[cfg(feature = "runtime")]
 pub mod runtime;
 pub mod syntax;
+#[cfg(all(feature = "runtime", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "runtime")]
 pub mod types;
 
 use ast::error::PineErrorKind;
+#[cfg(feature = "runtime")]
+use ast::incremental::IncrementalParser;
 use ast::input::{Input, Position, StrRange};
 use ast::stat_expr::block;
-use ast::stat_expr_types::{Block, VarIndex};
+use ast::stat_expr_types::Block;
+#[cfg(feature = "runtime")]
+use ast::stat_expr_types::VarIndex;
 use ast::state::{AstState, PineInputError};
-use ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+#[cfg(feature = "runtime")]
+use ast::syntax_type::SimpleSyntaxType;
+use ast::syntax_type::SyntaxType;
 
-use syntax::SyntaxParser;
+use syntax::{ShadowSeverity, SyntaxParser};
 
+pub use syntax::migrate::{migrate, PineVersion};
+
+#[cfg(feature = "runtime")]
+use libs::ema::EmaWarmup;
+#[cfg(feature = "runtime")]
 use libs::{declare_vars, VarResult};
+#[cfg(feature = "runtime")]
+use runtime::cancel::CancelToken;
+#[cfg(feature = "runtime")]
+use runtime::clock::Clock;
+#[cfg(feature = "runtime")]
 use runtime::context::{downcast_ctx, Ctx, PineRuntimeError, VarOperate};
-use runtime::data_src::{parse_datalen, Callback, DataSrc};
+#[cfg(feature = "runtime")]
+use runtime::data_src::{parse_datalen, Callback, DataSrc, TIME_CLOSE_NAME, TIME_TRADINGDAY_NAME};
+#[cfg(feature = "runtime")]
+use runtime::drawing::DrawingStats;
+#[cfg(feature = "runtime")]
 use runtime::error_format::{ErrorFormater, PineFormatError};
-use runtime::output::{IOInfo, InputVal, OutputData, OutputDataCollect, SymbolInfo};
+#[cfg(feature = "runtime")]
+use runtime::exp::HistoryIndexCheck;
+#[cfg(feature = "runtime")]
+use runtime::external::ExternalSeries;
+#[cfg(feature = "runtime")]
+use runtime::host_event::HostEvent;
+#[cfg(feature = "runtime")]
+use runtime::memory::{LeakWarning, MemoryReport};
+#[cfg(feature = "runtime")]
+use runtime::op::NaComparison;
+#[cfg(feature = "runtime")]
+use runtime::output::{
+    blend_bgcolors, ChartInfo, IOInfo, InputVal, OutputData, OutputDataCollect, PlotOffsets,
+    SymbolInfo,
+};
+#[cfg(feature = "runtime")]
+use runtime::profile::Profile;
+#[cfg(feature = "runtime")]
+use runtime::strategy::StrategyResult;
+#[cfg(feature = "runtime")]
 use runtime::{AnySeries, AnySeriesType};
+#[cfg(feature = "runtime")]
 use std::mem;
+#[cfg(feature = "runtime")]
 use std::rc::Rc;
 use syntax::InputSrcDetector;
+#[cfg(feature = "runtime")]
 use types::{Float, Int, PineRef};
 
+#[cfg(feature = "runtime")]
 #[derive(Debug, Clone)]
 pub struct LibInfo<'a> {
     var_types: Vec<(&'a str, SyntaxType<'a>)>,
@@ -51,8 +103,10 @@ pub struct LibInfo<'a> {
     client_input_names: Vec<&'a str>,           // The input varnames user client should pass in
 }
 
+#[cfg(feature = "runtime")]
 const BAR_INDEX: &'static str = "bar_index";
 
+#[cfg(feature = "runtime")]
 impl<'a> LibInfo<'a> {
     pub fn new(
         lib_vars: Vec<VarResult<'a>>,
@@ -69,6 +123,7 @@ impl<'a> LibInfo<'a> {
     }
 }
 
+#[cfg(feature = "runtime")]
 impl<'a> InputSrcDetector<'a> for LibInfo<'a> {
     fn get_client_srcs(&self) -> Vec<&'a str> {
         self.client_input_names.clone()
@@ -105,13 +160,17 @@ impl<'a> InputSrcDetector<'a> for LibInfo<'a> {
     }
 }
 
+#[cfg(feature = "runtime")]
 pub struct PineParser<'a, 'b> {
     src: &'a str,
     var_types: &'b Vec<(&'a str, SyntaxType<'a>)>,
     // client_input_names: &'b Vec<&'a str>,
     lib_info: &'b LibInfo<'a>,
+    shadow_severity: ShadowSeverity,
+    fixed_inputs: Option<Vec<Option<InputVal>>>,
 }
 
+#[cfg(feature = "runtime")]
 impl<'a, 'b> PineParser<'a, 'b> {
     pub fn new(src: &'a str, lib_info: &'b LibInfo<'a>) -> PineParser<'a, 'b> {
         PineParser {
@@ -119,14 +178,33 @@ impl<'a, 'b> PineParser<'a, 'b> {
             var_types: &lib_info.var_types,
             // client_input_names: &lib_info.client_input_names,
             lib_info,
+            shadow_severity: ShadowSeverity::Warning,
+            fixed_inputs: None,
         }
     }
 
+    pub fn set_shadow_severity(&mut self, severity: ShadowSeverity) {
+        self.shadow_severity = severity;
+    }
+
+    // Specialize the script with the fixed input values before the syntax pass.
+    pub fn set_fixed_inputs(&mut self, inputs: Option<Vec<Option<InputVal>>>) {
+        self.fixed_inputs = inputs;
+    }
+
     pub fn parse(
         &mut self,
+    ) -> Result<(Block<'a>, SyntaxParser<'a>, Vec<PineInputError>), Vec<PineInputError>> {
+        self.parse_with_ast(parse_ast(self.src))
+    }
+
+    // Run the syntax pass on the AST parsed ahead, e.g. by the incremental parser.
+    pub fn parse_with_ast(
+        &mut self,
+        ast: Result<Block<'a>, (Option<Block<'a>>, Vec<PineInputError>)>,
     ) -> Result<(Block<'a>, SyntaxParser<'a>, Vec<PineInputError>), Vec<PineInputError>> {
         let mut all_errs = vec![];
-        let mut blk = match parse_ast(self.src) {
+        let mut blk = match ast {
             Ok(blk) => blk,
             Err((Some(blk), errs)) => {
                 all_errs = errs;
@@ -134,12 +212,20 @@ impl<'a, 'b> PineParser<'a, 'b> {
             }
             Err((None, errs)) => return Err(errs),
         };
+        if let Some(inputs) = &self.fixed_inputs {
+            syntax::specialize::specialize(&mut blk, inputs);
+        }
         let syntax_parser;
 
-        match parse_syntax(&mut blk, &self.var_types, unsafe {
-            let s: *const (dyn InputSrcDetector<'a> + 'b) = self.lib_info;
-            mem::transmute::<_, *const (dyn InputSrcDetector<'a>)>(s)
-        }) {
+        match parse_syntax(
+            &mut blk,
+            &self.var_types,
+            unsafe {
+                let s: *const (dyn InputSrcDetector<'a> + 'b) = self.lib_info;
+                mem::transmute::<_, *const (dyn InputSrcDetector<'a>)>(s)
+            },
+            self.shadow_severity,
+        ) {
             Ok(parser) => syntax_parser = parser,
             Err((parser, errs)) => {
                 all_errs.extend(errs);
@@ -166,10 +252,12 @@ impl<'a, 'b> PineParser<'a, 'b> {
     }
 }
 
+#[cfg(feature = "runtime")]
 pub struct PineRunner<'a> {
     datasrc: DataSrc<'a>,
 }
 
+#[cfg(feature = "runtime")]
 impl<'a> PineRunner<'a> {
     pub fn new(
         lib_info: &LibInfo<'a>,
@@ -201,6 +289,23 @@ impl<'a> PineRunner<'a> {
         self.datasrc.runl(data, len, syminfo)
     }
 
+    // Run the bars before `from_index`, then replay the following bars one by one by the
+    // returned iterator, every step returns the outputs of the bar.
+    pub fn replay<'r>(
+        &'r mut self,
+        data: &'r Vec<(&'static str, AnySeries)>,
+        from_index: usize,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<Replay<'r, 'a>, PineRuntimeError> {
+        let len = self.datasrc.replay_from(data, from_index, syminfo)?;
+        Ok(Replay {
+            runner: self,
+            data,
+            index: from_index.min(len),
+            len,
+        })
+    }
+
     pub fn update(
         &mut self,
         data: &Vec<(&'static str, AnySeries)>,
@@ -237,6 +342,63 @@ impl<'a> PineRunner<'a> {
         self.datasrc.set_input_srcs(srcs);
     }
 
+    // Apply the event pushed by the host, e.g. the input changed by the user or the point picked
+    // for the interactive inputs, and run all the bars again with the new inputs because the
+    // inputs are read from the first bar. Return false and run nothing if no input is changed.
+    pub fn push_event(
+        &mut self,
+        data: &Vec<(&'static str, AnySeries)>,
+        event: &HostEvent,
+    ) -> Result<bool, PineRuntimeError> {
+        if !self.datasrc.apply_event(event)? {
+            return Ok(false);
+        }
+        let syminfo = downcast_ctx(self.get_context()).get_syminfo().clone();
+        self.datasrc.run(data, syminfo)?;
+        Ok(true)
+    }
+
+    // Set the token to abort the long runs, the cancelled run returns the `RuntimeErr::Cancelled` error.
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.datasrc.set_cancel_token(token);
+    }
+
+    // Set how the comparison operators treat na, see `NaComparison`.
+    pub fn set_na_comparison(&mut self, na_cmp: NaComparison) {
+        self.datasrc.set_na_comparison(na_cmp);
+    }
+
+    // Set how ema and rma start before enough bars are calculated, see `EmaWarmup`.
+    pub fn set_ema_warmup(&mut self, warmup: EmaWarmup) {
+        self.datasrc.set_ema_warmup(warmup);
+    }
+
+    // Evaluate the top-level statements by the dependency levels, see `DataSrc::set_level_order`.
+    pub fn set_level_order(&mut self, enabled: bool) {
+        self.datasrc.set_level_order(enabled);
+    }
+
+    // Set how the negative history references like `close[-1]` are handled, see `HistoryIndexCheck`.
+    pub fn set_history_index_check(&mut self, check: HistoryIndexCheck) {
+        self.datasrc.set_history_index_check(check);
+    }
+
+    // Inject the wall clock read by `timenow`, e.g. the `FixedClock` for the deterministic runs.
+    pub fn set_clock(&mut self, clock: Option<Rc<dyn Clock>>) {
+        self.datasrc.set_clock(clock);
+    }
+
+    // Set the chart environment like the colors and the visible range read by `chart.*`.
+    pub fn set_chart_info(&mut self, chart_info: Option<Rc<ChartInfo>>) {
+        self.datasrc.set_chart_info(chart_info);
+    }
+
+    // Register the named external series like the economic indicators, the `request.economic`
+    // and `request.external` calls read them before asking the data request of the callback.
+    pub fn set_external_series(&mut self, series: Option<Rc<ExternalSeries>>) {
+        self.datasrc.set_external_series(series);
+    }
+
     pub fn change_inputs(&mut self, inputs: Vec<Option<InputVal>>) {
         self.datasrc.change_inputs(inputs);
     }
@@ -252,8 +414,113 @@ impl<'a> PineRunner<'a> {
     pub fn move_output_data(&mut self) -> Vec<Option<OutputData>> {
         downcast_ctx(self.get_context()).move_output_data()
     }
+
+    // The performance of the strategy, None if the script is not a strategy.
+    pub fn get_strategy_result(&mut self) -> Option<StrategyResult> {
+        downcast_ctx(self.get_context())
+            .get_broker()
+            .map(|broker| broker.gen_result())
+    }
+
+    // The count of the alive drawings(line, label, box) created by the script.
+    pub fn get_drawing_stats(&mut self) -> DrawingStats {
+        downcast_ctx(self.get_context()).get_drawing_stats()
+    }
+
+    // The runtime statistics of the runs so far, the timing requires the `profile` feature.
+    pub fn profile(&mut self) -> Profile {
+        downcast_ctx(self.get_context()).gen_profile()
+    }
+
+    // The live series, arrays and drawings with their sizes and declarations.
+    pub fn memory_report(&mut self) -> MemoryReport {
+        self.datasrc.gen_memory_report()
+    }
+
+    // Warn about the arrays and drawings that grow on `min_bars` bars without shrinking,
+    // e.g. the array that is extended on every bar. None disables the check.
+    pub fn set_leak_check(&mut self, min_bars: Option<usize>) {
+        self.datasrc.set_leak_check(min_bars);
+    }
+
+    pub fn get_leak_warnings(&self) -> Vec<LeakWarning> {
+        self.datasrc.get_leak_warnings()
+    }
+}
+
+// The outputs of one replayed bar.
+#[cfg(feature = "runtime")]
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReplayStep {
+    pub bar_index: usize,
+    pub outputs: Vec<Option<OutputData>>,
+}
+
+// The iterator that runs the bars after the replay start one by one.
+#[cfg(feature = "runtime")]
+pub struct Replay<'r, 'a> {
+    runner: &'r mut PineRunner<'a>,
+    data: &'r Vec<(&'static str, AnySeries)>,
+    index: usize,
+    len: usize,
+}
+
+#[cfg(feature = "runtime")]
+impl<'r, 'a> Replay<'r, 'a> {
+    // The outputs of the bars that have been run and not moved, e.g. the bars before the replay start.
+    pub fn move_output_data(&mut self) -> Vec<Option<OutputData>> {
+        self.runner.move_output_data()
+    }
+
+    pub fn get_runner(&mut self) -> &mut PineRunner<'a> {
+        self.runner
+    }
+
+    // The index of the next bar to be replayed.
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    // Apply the event pushed by the host and run the replayed bars again with the new inputs, the
+    // replay continues from the same bar. Return false and run nothing if no input is changed.
+    pub fn push_event(&mut self, event: &HostEvent) -> Result<bool, PineRuntimeError> {
+        let datasrc = &mut self.runner.datasrc;
+        if !datasrc.apply_event(event)? {
+            return Ok(false);
+        }
+        let syminfo = downcast_ctx(datasrc.get_context()).get_syminfo().clone();
+        datasrc.replay_from(self.data, self.index, syminfo)?;
+        Ok(true)
+    }
 }
 
+#[cfg(feature = "runtime")]
+impl<'r, 'a> Iterator for Replay<'r, 'a> {
+    type Item = Result<ReplayStep, PineRuntimeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let bar_index = self.index;
+        match self.runner.datasrc.replay_step(self.data, bar_index) {
+            Ok(()) => {
+                self.index += 1;
+                Some(Ok(ReplayStep {
+                    bar_index,
+                    outputs: self.runner.move_output_data(),
+                }))
+            }
+            Err(err) => {
+                // Stop the replay after the error.
+                self.index = self.len;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
 pub struct PineScript<'pa, 'li, 'ra> {
     source: String,
     lib_info: LibInfo<'li>,
@@ -265,11 +532,24 @@ pub struct PineScript<'pa, 'li, 'ra> {
     datalen: usize,
     syminfo: Option<Rc<SymbolInfo>>,
     error_format: ErrorFormater,
+    shadow_severity: ShadowSeverity,
+    fixed_inputs: Option<Vec<Option<InputVal>>>,
+    // The errors of the last parsed source, they are kept for the quick fixes.
+    input_errors: Vec<PineInputError>,
+    plot_offsets: PlotOffsets,
+    // The AST of the unchanged statements is reused from the last parse if it's set.
+    incremental: Option<IncrementalParser>,
+    // The AST of the source before the syntax pass, it's parsed once per change and shared by
+    // the editor services that refer to the original source.
+    src_ast: Result<Block<'pa>, (Option<Block<'pa>>, Vec<PineInputError>)>,
 }
 
+#[cfg(feature = "runtime")]
 const SERIES_FLOAT: SyntaxType = SyntaxType::Series(SimpleSyntaxType::Float);
+#[cfg(feature = "runtime")]
 const SERIES_INT: SyntaxType = SyntaxType::Series(SimpleSyntaxType::Int);
 
+#[cfg(feature = "runtime")]
 impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
     pub fn new(callback: Option<&'ra dyn Callback>) -> PineScript<'pa, 'li, 'ra> {
         let lib_info = LibInfo::new(
@@ -282,6 +562,8 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
                 ("volume", SERIES_INT.clone()),
                 ("_time", SERIES_INT.clone()),
                 (BAR_INDEX, SERIES_INT.clone()),
+                (TIME_CLOSE_NAME, SERIES_INT.clone()),
+                (TIME_TRADINGDAY_NAME, SERIES_INT.clone()),
             ],
         );
         PineScript {
@@ -295,6 +577,12 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
             datalen: 0,
             syminfo: None,
             error_format: ErrorFormater::new(),
+            shadow_severity: ShadowSeverity::Warning,
+            fixed_inputs: None,
+            input_errors: vec![],
+            plot_offsets: PlotOffsets::new(),
+            incremental: None,
+            src_ast: Ok(Block::new_no_input(vec![], None)),
         }
     }
 
@@ -313,6 +601,12 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
             datalen: 0,
             syminfo: None,
             error_format: ErrorFormater::new(),
+            shadow_severity: ShadowSeverity::Warning,
+            fixed_inputs: None,
+            input_errors: vec![],
+            plot_offsets: PlotOffsets::new(),
+            incremental: None,
+            src_ast: Ok(Block::new_no_input(vec![], None)),
         }
     }
 
@@ -323,19 +617,33 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         'a: 'pa,
     {
         let mut parser: PineParser<'pa, 'pb>;
+        let src_ref: &'pa str;
         unsafe {
             self.source = src;
             let src: &'a str = mem::transmute::<_, &'a str>(self.source.as_str());
             let lib_ref = mem::transmute::<&LibInfo<'li>, &'pb LibInfo<'pa>>(&self.lib_info);
-            let src_ref = mem::transmute::<&'a str, &'pa str>(src);
+            src_ref = mem::transmute::<&'a str, &'pa str>(src);
             parser = PineParser::new(src_ref, lib_ref);
         }
+        parser.set_shadow_severity(self.shadow_severity);
+        parser.set_fixed_inputs(self.fixed_inputs.clone());
         // parser = PineParser::new(src, &self.lib_info);
-        match parser.parse() {
+        let src_ast = match &mut self.incremental {
+            Some(incremental) => unsafe {
+                mem::transmute::<
+                    Result<Block, (Option<Block>, Vec<PineInputError>)>,
+                    Result<Block<'pa>, (Option<Block<'pa>>, Vec<PineInputError>)>,
+                >(incremental.parse(&self.source).map_err(|err| *err))
+            },
+            None => parse_ast(src_ref),
+        };
+        let result = parser.parse_with_ast(src_ast.clone());
+        let res = match result {
             Ok((blk, parser, errs)) => {
                 self.blk = blk;
                 self.syntax_parser = Some(parser);
                 self.runner = None;
+                self.input_errors = errs.clone();
                 if errs.is_empty() {
                     Ok(())
                 } else {
@@ -345,10 +653,203 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
                         .collect())
                 }
             }
-            Err(errs) => Err(errs
+            Err(errs) => {
+                // The last script borrows the replaced source and the dropped chunks.
+                self.clear_parsed();
+                self.input_errors = errs.clone();
+                Err(errs
+                    .into_iter()
+                    .map(|err| PineFormatError::from_input_error(&self.error_format, err))
+                    .collect())
+            }
+        };
+        self.src_ast = src_ast;
+        res
+    }
+
+    // Set the severity for the variables that shadow the builtin variables, it takes effect
+    // when the source is parsed next time.
+    pub fn set_shadow_severity(&mut self, severity: ShadowSeverity) {
+        self.shadow_severity = severity;
+    }
+
+    // Set the fixed input values to specialize the script, the input calls with the fixed values
+    // are replaced by the literals and the branches guarded by the constant conditions are removed.
+    // The inputs after the first input whose call order is unknown, e.g. the input in a function
+    // or a branch, are not replaced and are still reported as the inputs of the script.
+    // It takes effect when the source is parsed next time.
+    pub fn set_fixed_inputs(&mut self, inputs: Option<Vec<Option<InputVal>>>) {
+        self.fixed_inputs = inputs;
+    }
+
+    // Reuse the AST of the unchanged top-level statements when the source is parsed again,
+    // it's for the editors that parse the source on every change.
+    // The parsed script borrows the chunks of the incremental parser, so it's parsed again
+    // without them when it's disabled.
+    pub fn set_incremental_parse(&mut self, enable: bool)
+    where
+        'li: 'pa,
+    {
+        if !enable {
+            if self.incremental.is_some() {
+                self.clear_parsed();
+                self.incremental = None;
+                if !self.source.is_empty() {
+                    let _ = self.parse_src(self.source.clone());
+                }
+            }
+        } else if self.incremental.is_none() {
+            self.incremental = Some(IncrementalParser::new());
+        }
+    }
+
+    // Drop the runner, the syntax parser and the ASTs that borrow the source.
+    fn clear_parsed(&mut self) {
+        self.runner = None;
+        self.syntax_parser = None;
+        self.blk = Block::new_no_input(vec![], None);
+        self.src_ast = Ok(Block::new_no_input(vec![], None));
+    }
+
+    // The original AST of the source, or the statements that can be parsed if it has errors.
+    fn partial_src_ast(&self) -> Option<&Block<'pa>> {
+        match &self.src_ast {
+            Ok(blk) | Err((Some(blk), _)) => Some(blk),
+            Err((None, _)) => None,
+        }
+    }
+
+    pub fn get_warnings(&self) -> Vec<PineFormatError> {
+        match &self.syntax_parser {
+            Some(parser) => parser
+                .get_warnings()
+                .iter()
+                .map(|err| PineFormatError::from_input_error(&self.error_format, err.clone()))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // Check the parsed script for the unused variables, unreachable code and constant conditions.
+    pub fn lint_warnings(&self) -> Vec<syntax::lint::LintWarning> {
+        syntax::lint::lint(&self.blk)
+    }
+
+    pub fn lint(&self) -> Vec<PineFormatError> {
+        self.lint_warnings()
+            .into_iter()
+            .map(|w| PineFormatError::from_lint_warning(&self.error_format, w))
+            .collect()
+    }
+
+    // Find the constructs that may repaint, they calculate differently on the historical and realtime bars.
+    // Like the dependencies, the original AST is audited to keep the security expressions.
+    pub fn audit_repaint(&self) -> Vec<PineFormatError> {
+        match &self.src_ast {
+            Ok(blk) => syntax::repaint::audit(blk)
                 .into_iter()
-                .map(|err| PineFormatError::from_input_error(&self.error_format, err))
-                .collect()),
+                .map(|w| PineFormatError::from_lint_warning(&self.error_format, w))
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    // Get the quick fixes for the lint warnings and the errors of the parsed script, the ranges
+    // and the edits refer to the original source.
+    pub fn quick_fixes(&self) -> Vec<syntax::quick_fix::QuickFix> {
+        let blk = match self.partial_src_ast() {
+            Some(blk) => blk,
+            None => return vec![],
+        };
+        let warnings = syntax::lint::lint(blk);
+        syntax::quick_fix::quick_fixes(&self.source, blk, &warnings, &self.input_errors)
+    }
+
+    // Rename the variable or function at the position of the parsed script, the edits replace
+    // the declaration and the references in the original source.
+    pub fn rename(
+        &self,
+        pos: Position,
+        new_name: &str,
+    ) -> Result<Vec<syntax::quick_fix::TextEdit>, PineFormatError> {
+        let blk = match &self.src_ast {
+            Ok(blk) | Err((Some(blk), _)) => blk,
+            Err((None, errs)) => {
+                return Err(PineFormatError::from_input_error(
+                    &self.error_format,
+                    errs[0].clone(),
+                ))
+            }
+        };
+        let index = syntax::references::ReferenceIndex::new(blk);
+        let builtin_names: Vec<_> = self.lib_info.var_types.iter().map(|v| v.0).collect();
+        syntax::references::rename(&index, pos, new_name, &builtin_names)
+            .map_err(|err| PineFormatError::from_input_error(&self.error_format, err))
+    }
+
+    // Render the AST to the DOT graph for debugging. With the scopes, the AST checked by the
+    // syntax parser is rendered with its contexts and variable indexes, otherwise the original AST.
+    pub fn ast_dot(&self, with_scopes: bool) -> String {
+        if with_scopes {
+            return syntax::dot::to_dot(&self.blk, true);
+        }
+        match self.partial_src_ast() {
+            Some(blk) => syntax::dot::to_dot(blk, false),
+            None => syntax::dot::to_dot(&Block::new(vec![], None, StrRange::new_empty()), false),
+        }
+    }
+
+    // Get the folding ranges of the if, else, for and function bodies of the parsed script.
+    pub fn folding_ranges(&self) -> Vec<syntax::structure::FoldingRange> {
+        match self.partial_src_ast() {
+            Some(blk) => syntax::structure::folding_ranges(blk),
+            None => vec![],
+        }
+    }
+
+    // Get the ranges to expand the selection at the position, from the innermost node to the whole script.
+    pub fn selection_ranges(&self, pos: Position) -> Vec<StrRange> {
+        match self.partial_src_ast() {
+            Some(blk) => syntax::structure::selection_ranges(blk, pos),
+            None => vec![],
+        }
+    }
+
+    // Collect the `//@` annotations and the documentation of the declarations.
+    pub fn annotations(&self) -> syntax::annotation::ScriptAnnotations {
+        syntax::annotation::parse_annotations(&self.source, self.partial_src_ast())
+    }
+
+    // Get the documentation of the function, variable or parameter referenced at the position
+    // as the markdown for the hover.
+    pub fn hover_doc(&self, pos: Position) -> Option<String> {
+        let blk = self.partial_src_ast()?;
+        let index = syntax::references::ReferenceIndex::new(blk);
+        let symbol = index.symbol_at(pos)?;
+        syntax::annotation::parse_annotations(&self.source, Some(blk)).doc_of(symbol.decl)
+    }
+
+    // Get the named arguments to complete at the position, the source may be incomplete while
+    // typing, so the declarations come from the statements that can be parsed.
+    pub fn param_completions(&self, pos: Position) -> Vec<syntax::completion::ParamCompletion> {
+        syntax::completion::param_completions(
+            &self.source,
+            self.partial_src_ast(),
+            pos,
+            &self.lib_info.var_types,
+        )
+    }
+
+    // Get the sources, securities, inputs and builtin libraries required by the parsed script.
+    // The syntax pass replaces the security expressions with the generated functions,
+    // so the dependencies are collected from the original AST.
+    pub fn dependencies(&self) -> syntax::dependency::ScriptDeps {
+        match &self.src_ast {
+            Ok(blk) => {
+                let lib_names: Vec<_> = self.lib_info.var_types.iter().map(|v| v.0).collect();
+                syntax::dependency::collect_deps(blk, &self.lib_info.get_client_srcs(), &lib_names)
+            }
+            Err(_) => syntax::dependency::ScriptDeps::default(),
         }
     }
 
@@ -384,23 +885,93 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
             Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
             Ok(_) => {
                 self.move_output_data();
-                Ok(downcast_ctx(self.get_runner().get_context())
+                let mut io_info = downcast_ctx(self.get_runner().get_context())
                     .get_io_info()
-                    .clone())
+                    .clone();
+                // The script meta comes from the declaration call, the annotations complete it.
+                if let Some(mut meta) = io_info.get_script_meta().clone() {
+                    let annotations = self.annotations();
+                    meta.description = annotations.description;
+                    meta.strategy_alert_message = annotations.strategy_alert_message;
+                    io_info.set_script_meta(meta);
+                }
+                Ok(io_info)
             }
         }
     }
 
     pub fn move_output_data(&mut self) -> OutputDataCollect {
-        let context = downcast_ctx(self.get_runner().get_context());
+        self.get_runner();
+        let context = downcast_ctx(self.runner.as_mut().unwrap().get_context());
         let (start, end) = context.get_data_range();
-        OutputDataCollect::new(start.unwrap(), end.unwrap(), context.move_output_data())
+        let (start, end) = (start.unwrap(), end.unwrap());
+        let mut collect = OutputDataCollect::new(start, end, context.move_output_data());
+        collect.bgcolors = blend_bgcolors(
+            context.get_io_info().get_outputs(),
+            &collect.data_list,
+            (end - start).max(0) as usize,
+        );
+        self.plot_offsets
+            .apply(context.get_io_info().get_outputs(), &mut collect);
+        collect
     }
 
     pub fn change_inputs(&mut self, inputs: Vec<Option<InputVal>>) {
         self.get_runner().change_inputs(inputs);
     }
 
+    pub fn get_strategy_result(&mut self) -> Option<StrategyResult> {
+        self.get_runner().get_strategy_result()
+    }
+
+    pub fn get_drawing_stats(&mut self) -> DrawingStats {
+        self.get_runner().get_drawing_stats()
+    }
+
+    pub fn memory_report(&mut self) -> MemoryReport {
+        self.get_runner().memory_report()
+    }
+
+    pub fn set_leak_check(&mut self, min_bars: Option<usize>) {
+        self.get_runner().set_leak_check(min_bars);
+    }
+
+    pub fn get_leak_warnings(&mut self) -> Vec<LeakWarning> {
+        self.get_runner().get_leak_warnings()
+    }
+
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.get_runner().set_cancel_token(token);
+    }
+
+    pub fn set_na_comparison(&mut self, na_cmp: NaComparison) {
+        self.get_runner().set_na_comparison(na_cmp);
+    }
+
+    pub fn set_ema_warmup(&mut self, warmup: EmaWarmup) {
+        self.get_runner().set_ema_warmup(warmup);
+    }
+
+    pub fn set_level_order(&mut self, enabled: bool) {
+        self.get_runner().set_level_order(enabled);
+    }
+
+    pub fn set_history_index_check(&mut self, check: HistoryIndexCheck) {
+        self.get_runner().set_history_index_check(check);
+    }
+
+    pub fn set_clock(&mut self, clock: Option<Rc<dyn Clock>>) {
+        self.get_runner().set_clock(clock);
+    }
+
+    pub fn set_chart_info(&mut self, chart_info: Option<Rc<ChartInfo>>) {
+        self.get_runner().set_chart_info(chart_info);
+    }
+
+    pub fn set_external_series(&mut self, series: Option<Rc<ExternalSeries>>) {
+        self.get_runner().set_external_series(series);
+    }
+
     // Run the script with new input settings and old data
     pub fn run_with_input(
         &mut self,
@@ -419,6 +990,30 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
         }
     }
 
+    // Apply the event pushed by the host and run the script with the old data and the new inputs.
+    // Return None if no input is changed by the event.
+    pub fn push_event(
+        &mut self,
+        event: &HostEvent,
+    ) -> Result<Option<OutputDataCollect>, PineFormatError> {
+        let syminfo = self.syminfo.clone();
+        let runner = self.get_runner();
+        match runner.datasrc.apply_event(event) {
+            Ok(false) => return Ok(None),
+            Ok(true) => (),
+            Err(err) => return Err(PineFormatError::from_runtime_error(&self.error_format, err)),
+        }
+        match self
+            .runner
+            .as_mut()
+            .unwrap()
+            .runl(&self.data, self.datalen, syminfo)
+        {
+            Ok(_) => Ok(Some(self.move_output_data())),
+            Err(err) => Err(PineFormatError::from_runtime_error(&self.error_format, err)),
+        }
+    }
+
     fn transform_data(data: &mut Vec<(&'static str, AnySeries)>) {
         for item in data.iter_mut() {
             if item.0 == "time" {
@@ -615,7 +1210,16 @@ impl<'pa, 'li, 'ra> PineScript<'pa, 'li, 'ra> {
 }
 
 pub fn parse_ast(in_str: &str) -> Result<Block, (Option<Block>, Vec<PineInputError>)> {
-    let input = Input::new(in_str, Position::new(0, 0), Position::max());
+    parse_ast_from(in_str, Position::new(0, 0))
+}
+
+// Parse the part of the source that starts at the position, the ranges of the nodes refer to
+// the whole source.
+pub fn parse_ast_from(
+    in_str: &str,
+    start: Position,
+) -> Result<Block, (Option<Block>, Vec<PineInputError>)> {
+    let input = Input::new(in_str, start, Position::max());
     let state = AstState::new();
     match block(input.clone(), &state) {
         Ok((input, parsed)) => {
@@ -652,8 +1256,10 @@ pub fn parse_syntax<'a>(
     blk: &mut Block<'a>,
     vars: &Vec<(&'a str, SyntaxType<'a>)>,
     lib_info: *const dyn InputSrcDetector<'a>,
+    shadow_severity: ShadowSeverity,
 ) -> Result<SyntaxParser<'a>, (Option<SyntaxParser<'a>>, Vec<PineInputError>)> {
     let mut syntax_parser = SyntaxParser::new_with_libvars(vars);
+    syntax_parser.set_shadow_severity(shadow_severity);
     // syntax_parser.init_input_options(input_names.clone());
     // syntax_parser.set_input_name_mapper(map_input_name);
     syntax_parser.init_input_detector(lib_info);
@@ -673,6 +1279,7 @@ pub fn parse_syntax<'a>(
     }
 }
 
+#[cfg(feature = "runtime")]
 pub fn extract_vars<'a>(
     vars: Vec<VarResult<'a>>,
     input_vars: Vec<(&'static str, SyntaxType<'a>)>,
@@ -692,7 +1299,7 @@ pub fn extract_vars<'a>(
     }
     for (n, t) in input_vars {
         match n {
-            BAR_INDEX | "volume" | "_time" => {
+            BAR_INDEX | "volume" | "_time" | TIME_CLOSE_NAME | TIME_TRADINGDAY_NAME => {
                 input_names.push((n, AnySeriesType::Int));
             }
             _ => {
@@ -700,8 +1307,9 @@ pub fn extract_vars<'a>(
             }
         }
         types.push((n, t));
+        // The client can omit the series that are derived from the bar times.
         match n {
-            BAR_INDEX => {}
+            BAR_INDEX | TIME_CLOSE_NAME | TIME_TRADINGDAY_NAME => {}
             "_time" => user_input_names.push("time"),
             n => user_input_names.push(n),
         }
@@ -712,13 +1320,15 @@ pub fn extract_vars<'a>(
     (types, values, input_names, user_input_names)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "runtime"))]
 mod tests {
     use super::*;
     use crate::libs::input;
     use crate::libs::plot;
     use crate::runtime::data_src::NoneCallback;
-    use crate::runtime::output::{InputInfo, InputSrc, IntInputInfo, OutputInfo, PlotInfo};
+    use crate::runtime::output::{
+        InputInfo, InputMeta, InputSrc, IntInputInfo, OutputInfo, PlotInfo, StrOptionsData,
+    };
 
     #[test]
     fn lib_info_test() {
@@ -761,6 +1371,123 @@ mod tests {
         assert_eq!(lib_info.map_input_src("time"), Some("_time"));
     }
 
+    #[test]
+    fn replay_test() {
+        let lib_info = LibInfo::new(
+            vec![plot::declare_var()],
+            vec![("close", SERIES_FLOAT.clone())],
+        );
+        let blk = PineParser::new("plot(close[1])", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+        )];
+
+        let mut replay = runner.replay(&data, 2, None).unwrap();
+        assert_eq!(
+            replay.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![None, Some(1f64)]]))]
+        );
+        assert_eq!(replay.get_index(), 2);
+        assert_eq!(
+            replay.collect::<Result<Vec<_>, _>>(),
+            Ok(vec![
+                ReplayStep {
+                    bar_index: 2,
+                    outputs: vec![Some(OutputData::new(vec![vec![Some(2f64)]]))]
+                },
+                ReplayStep {
+                    bar_index: 3,
+                    outputs: vec![Some(OutputData::new(vec![vec![Some(3f64)]]))]
+                },
+            ])
+        );
+
+        // Replay from the first bar.
+        let mut replay = runner.replay(&data, 0, None).unwrap();
+        assert_eq!(replay.move_output_data(), vec![]);
+        let outputs: Vec<_> = replay.map(|step| step.unwrap().outputs).collect();
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(
+            outputs[1],
+            vec![Some(OutputData::new(vec![vec![Some(1f64)]]))]
+        );
+    }
+
+    #[test]
+    fn push_event_test() {
+        let lib_info = LibInfo::new(
+            vec![input::declare_var(), plot::declare_var()],
+            vec![
+                ("close", SERIES_FLOAT.clone()),
+                (BAR_INDEX, SERIES_INT.clone()),
+            ],
+        );
+        let src = r#"
+a = input(1, "Anchor")
+p = input(1.5, "Price", input.price, confirm=true)
+plot(bar_index >= a ? close + p : na)
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+            ),
+            (
+                BAR_INDEX,
+                AnySeries::from_int_vec(vec![Some(0), Some(1), Some(2)]),
+            ),
+        ];
+        runner.run(&data, None).unwrap();
+        runner.move_output_data();
+
+        let event = HostEvent::PointSelected {
+            time_input: Some(0),
+            price_input: Some(1),
+            time: 2,
+            price: 10f64,
+        };
+        assert_eq!(runner.push_event(&data, &event), Ok(true));
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![None, None, Some(13f64)]]))]
+        );
+        assert_eq!(runner.push_event(&data, &event), Ok(false));
+        assert!(runner
+            .push_event(
+                &data,
+                &HostEvent::InputChanged {
+                    index: 2,
+                    value: InputVal::Int(0)
+                }
+            )
+            .is_err());
+
+        // The replayed bars run again and the replay continues from the same bar.
+        let mut replay = runner.replay(&data, 2, None).unwrap();
+        replay.move_output_data();
+        let event = HostEvent::InputChanged {
+            index: 0,
+            value: InputVal::Int(0),
+        };
+        assert_eq!(replay.push_event(&event), Ok(true));
+        assert_eq!(
+            replay.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![Some(11f64), Some(12f64)]]))]
+        );
+        assert_eq!(replay.get_index(), 2);
+        let outputs: Vec<_> = replay.map(|step| step.unwrap().outputs).collect();
+        assert_eq!(
+            outputs,
+            vec![vec![Some(OutputData::new(vec![vec![Some(13f64)]]))]]
+        );
+    }
+
     #[test]
     fn script_test() {
         let lib_info = LibInfo::new(
@@ -791,6 +1518,7 @@ mod tests {
                     maxval: None,
                     step: None,
                     options: None,
+                    meta: InputMeta::default(),
                 })],
                 vec![OutputInfo::Plot(PlotInfo {
                     title: None,
@@ -916,4 +1644,615 @@ mod tests {
         );
         assert_eq!(parser.datalen, 3);
     }
+
+    #[test]
+    fn binary_err_test() {
+        use crate::ast::input::{Position, StrRange};
+
+        let lib_info = LibInfo::new(vec![], vec![("close", SERIES_FLOAT.clone())]);
+        let mut parser = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+        let errs = parser
+            .parse_src(String::from("m = close + \"a\""))
+            .unwrap_err();
+        assert_eq!(
+            errs,
+            vec![PineFormatError {
+                message: String::from(
+                    "The operator + can't be applied to the operands of type series float and string."
+                ),
+                range: StrRange::new(Position::new(0, 4), Position::new(0, 14)),
+                related_ranges: vec![
+                    StrRange::new(Position::new(0, 4), Position::new(0, 9)),
+                    // The range of the string literal excludes the quotes.
+                    StrRange::new(Position::new(0, 13), Position::new(0, 14)),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn ast_dot_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from("m = 1\nif close > m\n    n = m + 1"))
+            .unwrap();
+        let dot = parser.ast_dot(false);
+        assert!(dot.contains("[label=\"m\"];"));
+        assert!(!dot.contains("subgraph"));
+
+        // The variables are labeled with the indexes of the checked AST.
+        let dot = parser.ast_dot(true);
+        assert!(dot.contains("label=\"ctx 0\";"));
+        assert!(dot.contains("m\\n(0, 0)"));
+        assert!(dot.contains("m\\n(0, 1)"));
+    }
+
+    #[test]
+    fn dependencies_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from(
+                "m = request.security_lower_tf(\"AAPL\", \"1\", close)\nplot(math.max(array.indexof(m, 1.0), 10) + volume)",
+            ))
+            .unwrap();
+        let deps = parser.dependencies();
+        assert_eq!(deps.srcs, vec![String::from("volume")]);
+        assert_eq!(deps.securities[0].ticker, Some(String::from("AAPL")));
+        assert_eq!(deps.securities[0].srcs, vec![String::from("close")]);
+        assert_eq!(
+            deps.libraries,
+            vec![
+                String::from("request"),
+                String::from("plot"),
+                String::from("math"),
+                String::from("array")
+            ]
+        );
+    }
+
+    #[test]
+    fn param_completions_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        assert!(parser.parse_src(String::from("m = sma(close, ")).is_err());
+        let completions = parser.param_completions(Position::new(0, 15));
+        let names: Vec<_> = completions.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["length"]);
+        assert_eq!(completions[0].snippet, "length=${1:length}");
+    }
+
+    #[test]
+    fn annotations_test() {
+        let src = "//@version=4\n//@description The test.\nstudy(\"Test\")\n//@function Double the value.\n//@param x The value.\nf(x) => x * 2\nplot(f(close))";
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser.parse_src(String::from(src)).unwrap();
+        assert_eq!(parser.annotations().version, Some(4));
+
+        let meta = parser
+            .gen_io_info()
+            .unwrap()
+            .get_script_meta()
+            .clone()
+            .unwrap();
+        assert_eq!(meta.description, Some(String::from("The test.")));
+        assert_eq!(meta.strategy_alert_message, None);
+
+        assert_eq!(
+            parser.hover_doc(Position::new(6, 5)),
+            Some(String::from(
+                "**f**\n\nDouble the value.\n\n- `x`: The value."
+            ))
+        );
+        assert_eq!(
+            parser.hover_doc(Position::new(5, 8)),
+            Some(String::from("**x**\n\nThe value."))
+        );
+        assert_eq!(parser.hover_doc(Position::new(6, 8)), None);
+    }
+
+    #[test]
+    fn fixed_inputs_test() {
+        let src = "show = input(true)\nlen = input(2)\nif show\n    plot(close * len)\nelse\n    plot(close)";
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+        )];
+
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser.set_fixed_inputs(Some(vec![Some(InputVal::Bool(false)), None]));
+        parser.parse_src(String::from(src)).unwrap();
+        let io_info = parser.gen_io_info().unwrap();
+        assert_eq!(io_info.get_inputs().len(), 1);
+        assert_eq!(io_info.get_outputs().len(), 1);
+        assert_eq!(
+            parser.run_with_data(data.clone(), None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(1f64), Some(2f64)]
+            ))
+        );
+
+        parser.set_fixed_inputs(Some(vec![
+            Some(InputVal::Bool(true)),
+            Some(InputVal::Int(3)),
+        ]));
+        parser.parse_src(String::from(src)).unwrap();
+        assert_eq!(
+            parser.run_with_data(data, None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(3f64), Some(6f64)]
+            ))
+        );
+    }
+
+    #[test]
+    fn fixed_inputs_func_test() {
+        // The inputs are numbered in the call order, the input in the function is called after
+        // the top-level input, so only the top-level input is replaced.
+        let src = "f() => input(1, \"inner\")\na = input(2, \"outer\")\nplot(a)\nplot(f())";
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser.parse_src(String::from(src)).unwrap();
+        let io_info = parser.gen_io_info().unwrap();
+        let titles: Vec<_> = io_info
+            .get_inputs()
+            .iter()
+            .map(|input| match input {
+                InputInfo::Int(info) => info.title.clone(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![Some(String::from("outer")), Some(String::from("inner"))]
+        );
+
+        parser.set_fixed_inputs(Some(vec![Some(InputVal::Int(10)), Some(InputVal::Int(20))]));
+        parser.parse_src(String::from(src)).unwrap();
+        let io_info = parser.gen_io_info().unwrap();
+        assert_eq!(io_info.get_inputs().len(), 1);
+        assert_eq!(
+            parser.run_with_data(data, None),
+            Ok(OutputDataCollect::new(
+                0,
+                1,
+                vec![
+                    Some(OutputData::new(vec![vec![Some(10f64)]])),
+                    Some(OutputData::new(vec![vec![Some(1f64)]])),
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn incremental_parse_test() {
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+        )];
+
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser.set_incremental_parse(true);
+        parser
+            .parse_src(String::from("m = 2\nn = close * m\nplot(n)"))
+            .unwrap();
+        assert!(parser
+            .parse_src(String::from("m = 2\nn = close * \nplot(n)"))
+            .is_err());
+        parser
+            .parse_src(String::from("m = 2\nn = close * m * 2\nplot(n)"))
+            .unwrap();
+        assert_eq!(
+            parser.run_with_data(data.clone(), None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(4f64), Some(8f64)]
+            ))
+        );
+
+        // The script is parsed again without the chunks of the incremental parser.
+        parser.set_incremental_parse(false);
+        assert_eq!(parser.lint_warnings().len(), 0);
+        assert_eq!(
+            parser.run_with_data(data, None),
+            Ok(OutputDataCollect::new_with_one(
+                0,
+                2,
+                vec![Some(4f64), Some(8f64)]
+            ))
+        );
+    }
+
+    #[test]
+    fn plot_offset_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from(
+                "plot(close, offset=2)\nplot(close, offset=-1)",
+            ))
+            .unwrap();
+        let output = parser
+            .run_with_data(
+                vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            output.data_list,
+            vec![
+                Some(OutputData::new(vec![vec![None, None, Some(1f64)]])),
+                Some(OutputData::new(vec![vec![Some(2f64), Some(3f64), None]])),
+            ]
+        );
+        assert_eq!(
+            output.projections,
+            vec![Some(vec![Some(2f64), Some(3f64)]), None]
+        );
+
+        let output = parser
+            .update(vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(4f64), Some(5f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            output.data_list,
+            vec![
+                Some(OutputData::new(vec![vec![Some(1f64), Some(2f64)]])),
+                Some(OutputData::new(vec![vec![Some(5f64), None]])),
+            ]
+        );
+        assert_eq!(
+            output.projections,
+            vec![Some(vec![Some(4f64), Some(5f64)]), None]
+        );
+    }
+
+    #[test]
+    fn bgcolor_blend_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from(
+                "bgcolor(#FF0000)\nbgcolor(close > 1 ? #0000FF80 : na)\nplot(close)",
+            ))
+            .unwrap();
+        let output = parser
+            .run_with_data(
+                vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            output.bgcolors,
+            Some(StrOptionsData {
+                options: vec![String::from("#FF0000"), String::from("#7F0080")],
+                values: vec![Some(0), Some(1)],
+            })
+        );
+
+        parser.parse_src(String::from("plot(close)")).unwrap();
+        let output = parser
+            .run_with_data(
+                vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        assert_eq!(output.bgcolors, None);
+    }
+
+    #[test]
+    fn audit_repaint_test() {
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from(
+                "plot(close, offset=-1)\nplot(close[1], offset=1)",
+            ))
+            .unwrap();
+        assert_eq!(
+            parser
+                .audit_repaint()
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>(),
+            vec![String::from(
+                "The negative offset references the future bars, so the values are repainted."
+            )]
+        );
+    }
+
+    #[test]
+    fn lint_test() {
+        use crate::ast::input::{Position, StrRange};
+
+        let lib_info = LibInfo::new(vec![], vec![("close", SERIES_FLOAT.clone())]);
+        let mut parser = PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+        parser
+            .parse_src(String::from(
+                "m = close
+n = m * 2",
+            ))
+            .unwrap();
+        assert_eq!(
+            parser.lint(),
+            vec![PineFormatError {
+                message: String::from("The variable n is declared but never used."),
+                range: StrRange::from_start("n", Position::new(1, 0)),
+                related_ranges: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn rename_test() {
+        use crate::ast::input::{Position, StrRange};
+        use crate::syntax::quick_fix::TextEdit;
+
+        let mut parser = PineScript::new(Some(&NoneCallback()));
+        parser
+            .parse_src(String::from("m = close\nplot(m * 2)"))
+            .unwrap();
+        assert_eq!(
+            parser.rename(Position::new(1, 5), "val"),
+            Ok(vec![
+                TextEdit::new(
+                    StrRange::from_start("m", Position::new(0, 0)),
+                    String::from("val")
+                ),
+                TextEdit::new(
+                    StrRange::from_start("m", Position::new(1, 5)),
+                    String::from("val")
+                ),
+            ])
+        );
+        assert_eq!(
+            parser
+                .rename(Position::new(0, 0), "sma")
+                .map_err(|e| e.message),
+            Err(String::from(
+                "This variable shadows the built-in variable or function with the same name."
+            ))
+        );
+
+        parser
+            .parse_src(String::from("n = close\nplot(n)"))
+            .unwrap();
+        assert_eq!(
+            parser.rename(Position::new(1, 5), "val"),
+            Ok(vec![
+                TextEdit::new(
+                    StrRange::from_start("n", Position::new(0, 0)),
+                    String::from("val")
+                ),
+                TextEdit::new(
+                    StrRange::from_start("n", Position::new(1, 5)),
+                    String::from("val")
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn history_index_test() {
+        use crate::types::{Float, RuntimeErr, Series};
+        use runtime::profile::HistoryIndexProfile;
+
+        let lib_info = LibInfo::new(vec![], vec![("close", SERIES_FLOAT.clone())]);
+        let blk = PineParser::new("m = close[2]\nn = close[-1]", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        )];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(1, 0)),
+            &Some(PineRef::new(Series::<Float>::from_vec(vec![
+                None, None, None
+            ])))
+        );
+        assert_eq!(
+            runner.profile().history_indexes,
+            vec![
+                HistoryIndexProfile {
+                    range: StrRange::new(Position::new(0, 4), Position::new(0, 12)),
+                    negative: 0,
+                    out_of_range: 2
+                },
+                HistoryIndexProfile {
+                    range: StrRange::new(Position::new(1, 4), Position::new(1, 13)),
+                    negative: 3,
+                    out_of_range: 0
+                }
+            ]
+        );
+
+        runner.set_history_index_check(HistoryIndexCheck::Error);
+        let err = runner.run(&data, None).unwrap_err();
+        assert_eq!(err.code, RuntimeErr::NegativeHistoryIndex(-1));
+    }
+
+    #[test]
+    fn profile_test() {
+        let lib_info = LibInfo::new(
+            vec![plot::declare_var()],
+            vec![("close", SERIES_FLOAT.clone())],
+        );
+        let blk = PineParser::new("m = close * 2\nplot(m)", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        )];
+        runner.run(&data, None).unwrap();
+
+        let profile = runner.profile();
+        // The series `m` keeps the values of the three bars.
+        assert!(profile.series_memory >= 3 * mem::size_of::<Float>());
+
+        if cfg!(feature = "profile") {
+            assert_eq!(
+                profile
+                    .calls
+                    .iter()
+                    .map(|c| (c.name.as_str(), c.count))
+                    .collect::<Vec<_>>(),
+                vec![("plot", 3)]
+            );
+            assert_eq!(profile.statements.len(), 2);
+            assert!(profile.statements.iter().all(|s| s.count == 3));
+        } else {
+            assert!(profile.calls.is_empty());
+            assert!(profile.statements.is_empty());
+        }
+    }
+
+    #[test]
+    fn cancel_test() {
+        use crate::libs::alert;
+        use crate::runtime::OutputCallback;
+        use crate::types::RuntimeErr;
+        use std::cell::Cell;
+
+        // Cancel the run when the bar or the alert reaches the index.
+        struct CancelCallback {
+            token: CancelToken,
+            bar_index: Cell<i32>,
+        }
+        impl OutputCallback for CancelCallback {
+            fn on_alert(&self, _bar_index: i32, _message: String) {
+                self.token.cancel();
+            }
+
+            fn on_bar_complete(&self, bar_index: i32) {
+                if bar_index == self.bar_index.get() {
+                    self.token.cancel();
+                }
+            }
+        }
+        impl Callback for CancelCallback {
+            fn get_output_callback(&self) -> Option<&dyn OutputCallback> {
+                Some(self)
+            }
+        }
+
+        let callback = CancelCallback {
+            token: CancelToken::new(),
+            bar_index: Cell::new(1),
+        };
+        let lib_info = LibInfo::new(
+            vec![plot::declare_var(), alert::declare_var()],
+            vec![("close", SERIES_FLOAT.clone())],
+        );
+        let src = "plot(close)\nif close > 3\n    for i = 0 to 10\n        alert('hello')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        runner.set_cancel_token(Some(callback.token.clone()));
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+        )];
+
+        // The run is aborted before the third bar.
+        let err = runner.run(&data, None).unwrap_err();
+        assert_eq!(err.code, RuntimeErr::Cancelled(1));
+
+        // The run is aborted inside the loop of the fourth bar.
+        callback.token.reset();
+        runner.set_cancel_token(Some(callback.token.clone()));
+        callback.bar_index.set(-1);
+        let err = runner.run(&data, None).unwrap_err();
+        assert_eq!(err.code, RuntimeErr::Cancelled(2));
+        assert_eq!(err.range.start, Position::new(2, 4));
+
+        // The cancelled token aborts the run before the first bar.
+        runner.set_cancel_token(Some(callback.token.clone()));
+        let err = runner.run(&data, None).unwrap_err();
+        assert_eq!(err.code, RuntimeErr::Cancelled(-1));
+
+        runner.set_cancel_token(None);
+        assert!(runner.run(&data, None).is_ok());
+    }
+
+    #[test]
+    fn level_order_test() {
+        use crate::runtime::OutputCallback;
+        use std::cell::RefCell;
+
+        struct AlertCallback {
+            alerts: RefCell<Vec<String>>,
+        }
+        impl OutputCallback for AlertCallback {
+            fn on_alert(&self, _bar_index: i32, message: String) {
+                self.alerts.borrow_mut().push(message);
+            }
+        }
+        impl Callback for AlertCallback {
+            fn get_output_callback(&self) -> Option<&dyn OutputCallback> {
+                Some(self)
+            }
+        }
+
+        // The statements without the dependencies are moved to the lower levels, while the
+        // plots and alerts keep their order.
+        let src = "len = input(2)
+s = sma(close, len)
+cnt = 0
+m = close * 2
+alert('first')
+if close > 1
+    m := m + 1
+    cnt := 1
+plot(s)
+plot(cnt)
+k = highest(close, 2)
+alert('second')
+plot(m)
+plot(k)";
+        let data = || {
+            vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(3f64), Some(2f64)]),
+            )]
+        };
+        let run = |level_order| {
+            let callback = AlertCallback {
+                alerts: RefCell::new(vec![]),
+            };
+            let mut parser = PineScript::new(Some(&callback));
+            parser.parse_src(String::from(src)).unwrap();
+            parser.set_level_order(level_order);
+            let output = parser.run_with_data(data(), None).unwrap();
+            let alerts = callback.alerts.borrow().clone();
+            (output, alerts)
+        };
+
+        let (output, alerts) = run(true);
+        assert_eq!((output.clone(), alerts.clone()), run(false));
+        assert_eq!(
+            alerts,
+            vec!["first", "second", "first", "second", "first", "second"]
+        );
+        assert_eq!(
+            output.data_list[2],
+            Some(OutputData::new(vec![vec![
+                Some(2f64),
+                Some(7f64),
+                Some(5f64)
+            ]]))
+        );
+    }
 }
@@ -1,80 +1,30 @@
+use super::cos::{gen_int_math_type, int_math_callable};
 use super::VarResult;
-use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::{pine_ref_to_f64, pine_ref_to_i64};
-use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, NA};
-use std::mem;
-use std::rc::Rc;
+use crate::types::{Float, Int, PineRef};
 
-fn int_abs<'a>(xval: Option<PineRef<'a>>) -> Int {
-    match pine_ref_to_i64(xval) {
-        None => None,
-        Some(v) => Some(v.abs()),
-    }
+pub fn int_abs<'a>(xval: Option<PineRef<'a>>) -> Int {
+    pine_ref_to_i64(xval).map(|v| v.abs())
 }
 
-fn float_abs<'a>(xval: Option<PineRef<'a>>) -> Float {
-    match pine_ref_to_f64(xval) {
-        None => None,
-        Some(v) => Some(v.abs()),
-    }
-}
-
-fn abs_func<'a>(
-    _context: &mut dyn Ctx<'a>,
-    mut param: Vec<Option<PineRef<'a>>>,
-    func_type: FunctionType<'a>,
-) -> Result<PineRef<'a>, RuntimeErr> {
-    let xval = mem::replace(&mut param[0], None);
-
-    match ((func_type.signature.0)[0]).1 {
-        SyntaxType::Simple(SimpleSyntaxType::Int) => {
-            let res = int_abs(xval);
-            Ok(PineRef::new_box(res))
-        }
-        SyntaxType::Series(SimpleSyntaxType::Int) => {
-            let res = int_abs(xval);
-            Ok(PineRef::new_rc(Series::from(res)))
-        }
-        SyntaxType::Simple(SimpleSyntaxType::Float) => {
-            let res = float_abs(xval);
-            Ok(PineRef::new_box(res))
-        }
-        SyntaxType::Series(SimpleSyntaxType::Float) => {
-            let res = float_abs(xval);
-            Ok(PineRef::new_rc(Series::from(res)))
-        }
-        _ => unreachable!(),
-    }
+pub fn float_abs<'a>(xval: Option<PineRef<'a>>) -> Float {
+    pine_ref_to_f64(xval).map(|v| v.abs())
 }
 
 pub const VAR_NAME: &'static str = "abs";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
-    let value = PineRef::new(Callable::new(Some(abs_func), None));
-
-    let func_type = FunctionTypes(vec![
-        FunctionType::new((vec![("x", SyntaxType::int())], SyntaxType::int())),
-        FunctionType::new((
-            vec![("x", SyntaxType::int_series())],
-            SyntaxType::int_series(),
-        )),
-        FunctionType::new((vec![("x", SyntaxType::float())], SyntaxType::float())),
-        FunctionType::new((
-            vec![("x", SyntaxType::float_series())],
-            SyntaxType::float_series(),
-        )),
-    ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
-    VarResult::new(value, syntax_type, VAR_NAME)
+    let value = PineRef::new(int_math_callable(int_abs, float_abs));
+    VarResult::new(value, gen_int_math_type("x"), VAR_NAME)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ast::stat_expr_types::VarIndex;
-    use crate::ast::syntax_type::SimpleSyntaxType;
+    use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
     use crate::runtime::{AnySeries, NoneCallback, VarOperate};
+    use crate::types::Series;
     use crate::{LibInfo, PineParser, PineRunner};
 
     #[test]
@@ -22,7 +22,6 @@ struct AccDistVal {
 
 impl AccDistVal {
     pub fn new() -> AccDistVal {
-        println!("init accdist");
         AccDistVal {
             close_index: VarIndex::new(0, 0),
             low_index: VarIndex::new(0, 0),
@@ -0,0 +1,347 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::{move_element, pine_ref_to_string, str_replace};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::types::{Callable, CallableObject, PineClass, PineRef, RuntimeErr, SeriesCall, NA};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+const FREQ_ALL: &str = "all";
+const FREQ_ONCE_PER_BAR: &str = "once_per_bar";
+const FREQ_ONCE_PER_BAR_CLOSE: &str = "once_per_bar_close";
+
+// The firing state of one alert call, it's kept when the bar is rolled back and calculated
+// again by the update, so the host doesn't receive the alerts of the same bar repeatedly.
+#[derive(Debug, Clone)]
+struct AlertVal {
+    fired_bar: Option<i32>,
+}
+
+impl AlertVal {
+    fn new() -> AlertVal {
+        AlertVal { fired_bar: None }
+    }
+}
+
+impl<'a> SeriesCall<'a> for AlertVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut p: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let message = pine_ref_to_string(move_element(&mut p, 0)).unwrap_or_default();
+        let freq = pine_ref_to_string(move_element(&mut p, 1));
+        let bar_index = context.get_iterindex();
+        let fire = match freq.as_deref().unwrap_or(FREQ_ONCE_PER_BAR) {
+            FREQ_ALL => true,
+            FREQ_ONCE_PER_BAR => self.fired_bar != Some(bar_index),
+            // The last bar of the data may be updated, so it's closed when the next bar comes.
+            FREQ_ONCE_PER_BAR_CLOSE => {
+                let (_, end) = downcast_ctx(context.get_main_ctx()).get_data_range();
+                self.fired_bar != Some(bar_index) && Some(bar_index + 1) != end
+            }
+            _ => {
+                return Err(RuntimeErr::InvalidParameters(str_replace(
+                    INVALID_VALS,
+                    vec![String::from("freq")],
+                )))
+            }
+        };
+        // The alert isn't fired while the outputs are muted, so it can be fired by the calculation
+        // of the bar.
+        if fire && !downcast_ctx(context).check_is_output_muted() {
+            self.fired_bar = Some(bar_index);
+            if let Some(callback) = downcast_ctx(context).get_output_callback() {
+                callback.on_alert(bar_index, message);
+            }
+        }
+        Ok(PineRef::new_box(NA))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+struct AlertProps;
+
+impl<'a> PineClass<'a> for AlertProps {
+    fn custom_type(&self) -> &str {
+        "alert"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "freq_all" => Ok(PineRef::new_rc(String::from(FREQ_ALL))),
+            "freq_once_per_bar" => Ok(PineRef::new_rc(String::from(FREQ_ONCE_PER_BAR))),
+            "freq_once_per_bar_close" => Ok(PineRef::new_rc(String::from(FREQ_ONCE_PER_BAR_CLOSE))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("alert")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(AlertProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "alert";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableObject::new(Box::new(AlertProps), || {
+        Callable::new(None, Some(Box::new(AlertVal::new())))
+    }));
+
+    // alert(message, freq) → void
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("message", SyntaxType::string_series()),
+            ("freq", SyntaxType::string()),
+        ],
+        SyntaxType::Void,
+    ))]);
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("freq_all", SyntaxType::string());
+    obj_type.insert("freq_once_per_bar", SyntaxType::string());
+    obj_type.insert("freq_once_per_bar_close", SyntaxType::string());
+    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::{bgcolor, plot, plotarrow, plotchar, plotshape};
+    use crate::runtime::{AnySeries, Callback, OutputCallback, OutputData};
+    use crate::{LibInfo, PineParser, PineRunner};
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct StreamCallback {
+        events: RefCell<Vec<String>>,
+    }
+
+    impl OutputCallback for StreamCallback {
+        fn on_plot_point(&self, output_index: usize, bar_index: i32, value: Option<f64>) {
+            self.events
+                .borrow_mut()
+                .push(format!("plot {} {} {:?}", output_index, bar_index, value));
+        }
+
+        fn on_plot_color(&self, output_index: usize, bar_index: i32, color: Option<String>) {
+            self.events
+                .borrow_mut()
+                .push(format!("color {} {} {:?}", output_index, bar_index, color));
+        }
+
+        fn on_alert(&self, bar_index: i32, message: String) {
+            self.events
+                .borrow_mut()
+                .push(format!("alert {} {}", bar_index, message));
+        }
+
+        fn on_bar_complete(&self, bar_index: i32) {
+            self.events
+                .borrow_mut()
+                .push(format!("complete {}", bar_index));
+        }
+    }
+
+    impl Callback for StreamCallback {
+        fn get_output_callback(&self) -> Option<&dyn OutputCallback> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn alert_stream_test() {
+        let callback = StreamCallback::default();
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"plot(close)
+plot(close, offset=1)
+if close > 1
+    alert('up')
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec![
+                "plot 0 0 Some(1.0)",
+                "plot 1 1 Some(1.0)",
+                "complete 0",
+                "plot 0 1 Some(2.0)",
+                "plot 1 2 Some(2.0)",
+                "alert 1 up",
+                "complete 1",
+            ]
+        );
+
+        // The updated bar is streamed again.
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(0f64), Some(3f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec![
+                "plot 0 1 Some(0.0)",
+                "plot 1 2 Some(0.0)",
+                "complete 1",
+                "plot 0 2 Some(3.0)",
+                "plot 1 3 Some(3.0)",
+                "alert 2 up",
+                "complete 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn alert_freq_test() {
+        let callback = StreamCallback::default();
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"for i = 1 to 2
+    alert('all', alert.freq_all)
+    alert('bar')
+if close > 1
+    alert('close', freq=alert.freq_once_per_bar_close)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        // The last bar may be updated, so the alert at the bar close is not sent.
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec![
+                "alert 0 all",
+                "alert 0 bar",
+                "alert 0 all",
+                "alert 0 close",
+                "complete 0",
+                "alert 1 all",
+                "alert 1 bar",
+                "alert 1 all",
+                "complete 1",
+            ]
+        );
+
+        // The bar 1 is closed by the new bar, the alert once per bar is not sent again.
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(3f64), Some(0f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec![
+                "alert 1 all",
+                "alert 1 all",
+                "alert 1 close",
+                "complete 1",
+                "alert 2 all",
+                "alert 2 bar",
+                "alert 2 all",
+                "complete 2",
+            ]
+        );
+
+        let src = "alert('bar', 'daily')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        assert!(runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn plot_like_stream_test() {
+        let callback = StreamCallback::default();
+        let lib_info = LibInfo::new(
+            vec![
+                plotshape::declare_var(),
+                plotchar::declare_var(),
+                plotarrow::declare_var(),
+                bgcolor::declare_bgcolor_var(),
+                bgcolor::declare_barcolor_var(),
+            ],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"plotshape(close, offset=1)
+plotchar(close * 2)
+plotarrow(close - 1)
+bgcolor(close > 1 ? #ff0000 : na)
+barcolor(#00ff00)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec![
+                "plot 0 1 Some(1.0)",
+                "plot 1 0 Some(2.0)",
+                "plot 2 0 Some(0.0)",
+                "color 3 0 None",
+                "color 4 0 Some(\"#00ff00\")",
+                "complete 0",
+                "plot 0 2 Some(2.0)",
+                "plot 1 1 Some(4.0)",
+                "plot 2 1 Some(1.0)",
+                "color 3 1 Some(\"#ff0000\")",
+                "color 4 1 Some(\"#00ff00\")",
+                "complete 1",
+            ]
+        );
+        // The output data is in the order of the output infos.
+        assert_eq!(runner.get_io_info().get_outputs().len(), 5);
+        assert_eq!(
+            runner.move_output_data()[..3],
+            [
+                Some(OutputData::new(vec![vec![Some(1f64), Some(2f64)]])),
+                Some(OutputData::new(vec![vec![Some(2f64), Some(4f64)]])),
+                Some(OutputData::new(vec![vec![Some(0f64), Some(1f64)]])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,909 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{
+    ge1_param_i64, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_f64_series,
+    pine_ref_to_i64, pine_ref_to_string, require_param,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{
+    downcast_pf, Bool, Callable, CallableFactory, DataType, Float, Int, Object, ParamCollectCall,
+    PineClass, PineRef, PineStaticType, RuntimeErr, SecondType, Series, SeriesCall, Tuple,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+// The values compared by the search functions.
+trait SearchItem: Clone {
+    fn is_equal(&self, other: &Self) -> bool;
+
+    // The `na` values are ordered before all the other values.
+    fn compare(&self, other: &Self) -> Ordering;
+}
+
+// The floats are equal if the difference is within the rounding error of the larger one,
+// so `0.1 + 0.2` can be found in the array `[0.3]`.
+fn float_eq(a: f64, b: f64) -> bool {
+    a == b || (a - b).abs() <= f64::EPSILON * a.abs().max(b.abs()).max(1f64)
+}
+
+impl SearchItem for Float {
+    fn is_equal(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => float_eq(*a, *b),
+            _ => false,
+        }
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Some(a), Some(b)) if float_eq(*a, *b) => Ordering::Equal,
+            (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+impl SearchItem for Int {
+    fn is_equal(&self, other: &Self) -> bool {
+        self.is_some() && self == other
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+impl SearchItem for Option<String> {
+    fn is_equal(&self, other: &Self) -> bool {
+        self.is_some() && self == other
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+// Convert the array argument to the vector. The array can be the tuple literal like `[1, 2]`
+// or the array value of the element type `D` returned by the functions like
+// `request.security_lower_tf`.
+pub fn pine_ref_to_array<'a, D, T>(
+    val: Option<PineRef<'a>>,
+    from_ref: fn(Option<PineRef<'a>>) -> T,
+    from_item: fn(D) -> T,
+) -> Vec<T>
+where
+    D: PineStaticType + Clone + PartialEq + Debug + 'a,
+{
+    let val = match val {
+        Some(val) => val,
+        None => return vec![],
+    };
+    match val.get_type() {
+        (DataType::Tuple, _) => downcast_pf::<Tuple>(val)
+            .unwrap()
+            .into_inner()
+            .0
+            .into_iter()
+            .map(|v| from_ref(Some(v)))
+            .collect(),
+        (t, SecondType::Array) if t == D::static_type().0 => downcast_pf::<Vec<D>>(val)
+            .unwrap()
+            .into_inner()
+            .into_iter()
+            .map(from_item)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn index_of<T: SearchItem>(items: &[T], val: &T) -> Int {
+    items.iter().position(|v| v.is_equal(val)).map(|i| i as i64)
+}
+
+fn last_index_of<T: SearchItem>(items: &[T], val: &T) -> Int {
+    items
+        .iter()
+        .rposition(|v| v.is_equal(val))
+        .map(|i| i as i64)
+}
+
+// The array must be sorted in ascending order. On the unsorted array the search still ends,
+// and the result is either the index of an element equal to the value or -1, but the value
+// may not be found even if the array contains it.
+fn binary_search<T: SearchItem>(items: &[T], val: &T) -> Int {
+    let (mut low, mut high) = (0, items.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match items[mid].compare(val) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal if items[mid].is_equal(val) => return Some(mid as i64),
+            // Found the `na` that never equals the `na` value.
+            Ordering::Equal => return None,
+        }
+    }
+    None
+}
+
+// The values that are not na.
+fn valid_values(items: &[Float]) -> Vec<f64> {
+    items.iter().filter_map(|v| *v).collect()
+}
+
+// The biased variance divides the sum of the squared deviations by n, the unbiased one by n - 1.
+// The corrected two-pass algorithm removes the rounding error of the mean from the sum.
+fn variance(items: &[Float], biased: bool) -> Float {
+    let vals = valid_values(items);
+    let n = vals.len();
+    let divisor = if biased { n } else { n.max(1) - 1 };
+    if n == 0 || divisor == 0 {
+        return None;
+    }
+    let mean = vals.iter().sum::<f64>() / n as f64;
+    let (sum_sq, sum) = vals.iter().fold((0f64, 0f64), |(sq, s), v| {
+        let d = v - mean;
+        (sq + d * d, s + d)
+    });
+    Some((sum_sq - sum * sum / n as f64).max(0f64) / divisor as f64)
+}
+
+// The covariance of the pairs at the same index, the pairs with any na value are skipped.
+fn covariance(items1: &[Float], items2: &[Float], biased: bool) -> Float {
+    let pairs: Vec<(f64, f64)> = items1
+        .iter()
+        .zip(items2.iter())
+        .filter_map(|(a, b)| Some(((*a)?, (*b)?)))
+        .collect();
+    let n = pairs.len();
+    let divisor = if biased { n } else { n.max(1) - 1 };
+    if n == 0 || divisor == 0 {
+        return None;
+    }
+    let mean1 = pairs.iter().map(|p| p.0).sum::<f64>() / n as f64;
+    let mean2 = pairs.iter().map(|p| p.1).sum::<f64>() / n as f64;
+    let (sum_prod, sum1, sum2) = pairs.iter().fold((0f64, 0f64, 0f64), |(sp, s1, s2), p| {
+        let (d1, d2) = (p.0 - mean1, p.1 - mean2);
+        (sp + d1 * d2, s1 + d1, s2 + d2)
+    });
+    Some((sum_prod - sum1 * sum2 / n as f64) / divisor as f64)
+}
+
+fn sorted_values(items: &[Float]) -> Vec<f64> {
+    let mut vals = valid_values(items);
+    vals.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    vals
+}
+
+// The middle value, or the average of the two middle values for the even count.
+fn median(items: &[Float]) -> Float {
+    let vals = sorted_values(items);
+    let n = vals.len();
+    match n {
+        0 => None,
+        _ if n % 2 == 1 => Some(vals[n / 2]),
+        _ => Some(vals[n / 2 - 1] + (vals[n / 2] - vals[n / 2 - 1]) / 2f64),
+    }
+}
+
+// The most frequent value, the smallest one is returned if several values have the same frequency.
+fn mode(items: &[Float]) -> Float {
+    let vals = sorted_values(items);
+    let mut res: Option<(f64, usize)> = None;
+    let mut i = 0;
+    while i < vals.len() {
+        let count = vals[i..].iter().take_while(|v| **v == vals[i]).count();
+        if res.is_none_or(|(_, c)| count > c) {
+            res = Some((vals[i], count));
+        }
+        i += count;
+    }
+    res.map(|(v, _)| v)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchKind {
+    BinarySearch,
+    IndexOf,
+    LastIndexOf,
+    Includes,
+}
+
+impl SearchKind {
+    fn search<'a, T: SearchItem>(&self, items: Vec<T>, val: T) -> PineRef<'a> {
+        let index = match self {
+            SearchKind::BinarySearch => binary_search(&items, &val),
+            SearchKind::IndexOf | SearchKind::Includes => index_of(&items, &val),
+            SearchKind::LastIndexOf => last_index_of(&items, &val),
+        };
+        match self {
+            SearchKind::Includes => PineRef::new_rc(Series::from(index.is_some() as Bool)),
+            _ => PineRef::new_rc(Series::from(Some(index.unwrap_or(-1)))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SearchCall {
+    kind: SearchKind,
+}
+
+impl<'a> SeriesCall<'a> for SearchCall {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let id = move_element(&mut param, 0);
+        let value = move_element(&mut param, 1);
+        match func_type.get_type(0).unwrap() {
+            SyntaxType::List(SimpleSyntaxType::Int) => Ok(self.kind.search(
+                pine_ref_to_array(id, pine_ref_to_i64, |v: Int| v),
+                pine_ref_to_i64(value),
+            )),
+            SyntaxType::List(SimpleSyntaxType::Float) => Ok(self.kind.search(
+                pine_ref_to_array(id, pine_ref_to_f64, |v: Float| v),
+                pine_ref_to_f64(value),
+            )),
+            SyntaxType::List(SimpleSyntaxType::String) => Ok(self.kind.search(
+                pine_ref_to_array(id, pine_ref_to_string, |v: String| Some(v)),
+                pine_ref_to_string(value),
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StatsKind {
+    Covariance,
+    Variance,
+    Median,
+    Mode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StatsCall {
+    kind: StatsKind,
+}
+
+impl<'a> SeriesCall<'a> for StatsCall {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let is_int = func_type.get_type(0) == Some(&SyntaxType::List(SimpleSyntaxType::Int));
+        let mut to_floats = |index: usize| -> Vec<Float> {
+            let id = move_element(&mut param, index);
+            if is_int {
+                pine_ref_to_array(id, pine_ref_to_i64, |v: Int| v)
+                    .into_iter()
+                    .map(|v| v.map(|i| i as f64))
+                    .collect()
+            } else {
+                pine_ref_to_array(id, pine_ref_to_f64, |v: Float| v)
+            }
+        };
+        let res = match self.kind {
+            StatsKind::Covariance => {
+                let (items1, items2) = (to_floats(0), to_floats(1));
+                if items1.len() != items2.len() {
+                    return Err(RuntimeErr::InvalidParameters(str_replace(
+                        ARRAY_SIZE_NOT_MATCH,
+                        vec![String::from("id1"), String::from("id2")],
+                    )));
+                }
+                let biased = pine_ref_to_bool(move_element(&mut param, 2)).unwrap_or(true);
+                covariance(&items1, &items2, biased)
+            }
+            StatsKind::Variance => {
+                let items = to_floats(0);
+                let biased = pine_ref_to_bool(move_element(&mut param, 1)).unwrap_or(true);
+                variance(&items, biased)
+            }
+            StatsKind::Median => median(&to_floats(0)),
+            StatsKind::Mode => {
+                let res = mode(&to_floats(0));
+                // The mode of the int array is one of the elements, so it keeps the int type.
+                if is_int {
+                    return Ok(PineRef::new_rc(Series::from(res.map(|v| v as i64))));
+                }
+                res
+            }
+        };
+        Ok(PineRef::new_rc(Series::from(res)))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+// Collect the latest `length` values of the series into the array, the element `i` is the
+// value `source[i]`, so the array functions can be combined with the rolling window.
+#[derive(Debug, Clone, PartialEq)]
+struct FromSeriesCall;
+
+impl<'a> SeriesCall<'a> for FromSeriesCall {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((source, length) = param);
+        let source = require_param("source", pine_ref_to_f64_series(source))?;
+        let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+        let items: Vec<Float> = (0..length as usize)
+            .map(|i| source.index_value(i).unwrap())
+            .collect();
+        Ok(PineRef::new_rc(items))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+// Create the array of `size` elements filled with `initial_value`, the element type is selected
+// by the type argument like `array.new<float>(size, initial_value)`.
+#[derive(Debug, Clone, PartialEq)]
+struct NewCall;
+
+impl<'a> SeriesCall<'a> for NewCall {
+    fn step(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((size, initial_value) = param);
+        let size = match pine_ref_to_i64(size) {
+            Some(v) if v < 0 => {
+                return Err(RuntimeErr::InvalidParameters(str_replace(
+                    INVALID_VALS,
+                    vec![String::from("size")],
+                )))
+            }
+            v => v.unwrap_or(0) as usize,
+        };
+        match func_type.signature.1 {
+            SyntaxType::List(SimpleSyntaxType::Int) => {
+                Ok(PineRef::new_rc(vec![pine_ref_to_i64(initial_value); size]))
+            }
+            SyntaxType::List(SimpleSyntaxType::Float) => {
+                Ok(PineRef::new_rc(vec![pine_ref_to_f64(initial_value); size]))
+            }
+            SyntaxType::List(SimpleSyntaxType::Bool) => {
+                Ok(PineRef::new_rc(vec![
+                    pine_ref_to_bool(initial_value)
+                        .unwrap_or(false);
+                    size
+                ]))
+            }
+            SyntaxType::List(SimpleSyntaxType::String) => Ok(PineRef::new_rc(vec![
+                pine_ref_to_string(initial_value)
+                    .unwrap_or_default();
+                size
+            ])),
+            _ => unreachable!(),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+struct ArrayProps;
+
+impl<'a> PineClass<'a> for ArrayProps {
+    fn custom_type(&self) -> &str {
+        "array"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "binary_search" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(SearchCall {
+                        kind: SearchKind::BinarySearch,
+                    })),
+                )
+            }))),
+            "indexof" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(SearchCall {
+                        kind: SearchKind::IndexOf,
+                    })),
+                )
+            }))),
+            "lastindexof" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(SearchCall {
+                        kind: SearchKind::LastIndexOf,
+                    })),
+                )
+            }))),
+            "includes" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(SearchCall {
+                        kind: SearchKind::Includes,
+                    })),
+                )
+            }))),
+            "covariance" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(StatsCall {
+                        kind: StatsKind::Covariance,
+                    })),
+                )
+            }))),
+            "variance" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(StatsCall {
+                        kind: StatsKind::Variance,
+                    })),
+                )
+            }))),
+            "median" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(StatsCall {
+                        kind: StatsKind::Median,
+                    })),
+                )
+            }))),
+            "mode" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(StatsCall {
+                        kind: StatsKind::Mode,
+                    })),
+                )
+            }))),
+            "from_series" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                        FromSeriesCall,
+                    )))),
+                )
+            }))),
+            "new" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                        NewCall,
+                    )))),
+                )
+            }))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("array")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(ArrayProps)
+    }
+}
+
+// The int array is matched first, so the int values are not compared as floats.
+fn gen_search_type<'a>(res_type: SyntaxType<'a>) -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        FunctionType::new_with_templates(
+            vec![
+                SimpleSyntaxType::Int,
+                SimpleSyntaxType::Float,
+                SimpleSyntaxType::String,
+            ],
+            (
+                vec![
+                    ("id", SyntaxType::List(SimpleSyntaxType::Template)),
+                    ("value", SyntaxType::Series(SimpleSyntaxType::Template)),
+                ],
+                res_type,
+            ),
+        ),
+    ])))
+}
+
+// The statistics of the int or float arrays, `extra` are the parameters after the arrays.
+fn gen_stats_type<'a>(
+    ids: &[&'static str],
+    extra: Vec<(&'static str, SyntaxType<'a>)>,
+    int_res: SyntaxType<'a>,
+) -> SyntaxType<'a> {
+    let gen_type = |t: SimpleSyntaxType, res: SyntaxType<'a>| {
+        let mut params: Vec<_> = ids
+            .iter()
+            .map(|id| (*id, SyntaxType::List(t.clone())))
+            .collect();
+        params.extend(extra.clone());
+        FunctionType::new((params, res))
+    };
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        gen_type(SimpleSyntaxType::Int, int_res),
+        gen_type(SimpleSyntaxType::Float, SyntaxType::float_series()),
+    ])))
+}
+
+pub const VAR_NAME: &'static str = "array";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(ArrayProps)));
+
+    let mut obj_type = BTreeMap::new();
+    // array.binary_search(id, value) → series[int]
+    obj_type.insert("binary_search", gen_search_type(SyntaxType::int_series()));
+    // array.indexof(id, value) → series[int]
+    obj_type.insert("indexof", gen_search_type(SyntaxType::int_series()));
+    // array.lastindexof(id, value) → series[int]
+    obj_type.insert("lastindexof", gen_search_type(SyntaxType::int_series()));
+    // array.includes(id, value) → series[bool]
+    obj_type.insert("includes", gen_search_type(SyntaxType::bool_series()));
+    // array.covariance(id1, id2, biased) → series[float]
+    obj_type.insert(
+        "covariance",
+        gen_stats_type(
+            &["id1", "id2"],
+            vec![("biased", SyntaxType::bool_series())],
+            SyntaxType::float_series(),
+        ),
+    );
+    // array.variance(id, biased) → series[float]
+    obj_type.insert(
+        "variance",
+        gen_stats_type(
+            &["id"],
+            vec![("biased", SyntaxType::bool_series())],
+            SyntaxType::float_series(),
+        ),
+    );
+    // array.median(id) → series[float]
+    obj_type.insert(
+        "median",
+        gen_stats_type(&["id"], vec![], SyntaxType::float_series()),
+    );
+    // array.mode(id) → series[int]
+    // array.mode(id) → series[float]
+    obj_type.insert(
+        "mode",
+        gen_stats_type(&["id"], vec![], SyntaxType::int_series()),
+    );
+    // array.from_series(source, length) → float[]
+    obj_type.insert(
+        "from_series",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("source", SyntaxType::float_series()),
+                ("length", SyntaxType::int()),
+            ],
+            SyntaxType::List(SimpleSyntaxType::Float),
+        ))]))),
+    );
+    // array.new<type>(size, initial_value) → type[]
+    obj_type.insert(
+        "new",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            FunctionType::new_with_templates(
+                vec![
+                    SimpleSyntaxType::Int,
+                    SimpleSyntaxType::Float,
+                    SimpleSyntaxType::Bool,
+                    SimpleSyntaxType::String,
+                ],
+                (
+                    vec![
+                        ("size", SyntaxType::int()),
+                        (
+                            "initial_value",
+                            SyntaxType::Series(SimpleSyntaxType::Template),
+                        ),
+                    ],
+                    SyntaxType::List(SimpleSyntaxType::Template),
+                ),
+            ),
+        ]))),
+    );
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn search_test() {
+        let items = vec![Some(1f64), Some(2f64), Some(2f64), Some(4f64)];
+        assert_eq!(index_of(&items, &Some(2f64)), Some(1));
+        assert_eq!(last_index_of(&items, &Some(2f64)), Some(2));
+        assert_eq!(index_of(&items, &Some(3f64)), None);
+        assert_eq!(binary_search(&items, &Some(4f64)), Some(3));
+        assert_eq!(binary_search(&items, &Some(0f64)), None);
+        assert_eq!(binary_search(&items, &Some(5f64)), None);
+        assert_eq!(binary_search::<Float>(&[], &Some(1f64)), None);
+
+        // The na value is never found.
+        let items = vec![None, Some(1f64)];
+        assert_eq!(index_of(&items, &None), None);
+        assert_eq!(binary_search(&items, &None), None);
+        assert_eq!(binary_search(&items, &Some(1f64)), Some(1));
+
+        // The search on the unsorted array may miss the value but never returns a wrong index.
+        let items = vec![Some(3), Some(1), Some(2)];
+        assert_eq!(binary_search(&items, &Some(1)), Some(1));
+        assert_eq!(binary_search(&items, &Some(3)), None);
+        assert_eq!(binary_search(&items, &Some(4)), None);
+    }
+
+    // The first data set of the Anscombe's quartet.
+    const ANSCOMBE_X: [f64; 11] = [10., 8., 13., 9., 11., 14., 6., 4., 12., 7., 5.];
+    const ANSCOMBE_Y: [f64; 11] = [
+        8.04, 6.95, 7.58, 8.81, 8.33, 9.96, 7.24, 4.26, 10.84, 4.82, 5.68,
+    ];
+
+    fn to_floats(vals: &[f64]) -> Vec<Float> {
+        vals.iter().map(|v| Some(*v)).collect()
+    }
+
+    fn assert_close(val: Float, expected: f64, tolerance: f64) {
+        let val = val.unwrap();
+        assert!(
+            (val - expected).abs() <= tolerance * expected.abs().max(1f64),
+            "{} != {}",
+            val,
+            expected
+        );
+    }
+
+    #[test]
+    fn stats_reference_test() {
+        let (x, y) = (to_floats(&ANSCOMBE_X), to_floats(&ANSCOMBE_Y));
+        assert_close(variance(&x, false), 11f64, 1e-12);
+        assert_close(variance(&x, true), 10f64, 1e-12);
+        assert_close(variance(&y, false), 4.127269090909091, 1e-12);
+        assert_close(covariance(&x, &y, false), 5.501, 1e-12);
+        assert_close(covariance(&x, &y, true), 55.01 / 11f64, 1e-12);
+        assert_eq!(median(&x), Some(9f64));
+        assert_eq!(median(&y), Some(7.58));
+
+        // The NIST StRD data set NumAcc3: the large values with the small deviations lose the
+        // precision with the naive sum of squares.
+        let mut vals = vec![Some(1000000.2f64)];
+        for _ in 0..500 {
+            vals.push(Some(1000000.1f64));
+            vals.push(Some(1000000.3f64));
+        }
+        assert_close(variance(&vals, false), 0.01, 1e-9);
+        assert_close(covariance(&vals, &vals, false), 0.01, 1e-9);
+        assert_eq!(median(&vals), Some(1000000.2f64));
+    }
+
+    #[test]
+    fn stats_na_test() {
+        let items = vec![Some(1f64), None, Some(3f64), Some(3f64), None, Some(1f64)];
+        assert_eq!(variance(&items, true), Some(1f64));
+        assert_eq!(median(&items), Some(2f64));
+        // Both 1 and 3 appear twice, the smaller one is the mode.
+        assert_eq!(mode(&items), Some(1f64));
+        assert_eq!(mode(&[Some(2f64), Some(5f64), Some(5f64)]), Some(5f64));
+
+        // The pairs with na are skipped, the pairs are (1, 2), (3, 6) and (1, 2).
+        let items2 = vec![
+            Some(2f64),
+            Some(1f64),
+            Some(6f64),
+            None,
+            Some(9f64),
+            Some(2f64),
+        ];
+        assert_close(covariance(&items, &items2, true), 16f64 / 9f64, 1e-12);
+
+        assert_eq!(variance(&[None], true), None);
+        assert_eq!(variance(&[Some(1f64)], false), None);
+        assert_eq!(covariance(&[], &[], true), None);
+        assert_eq!(median(&[]), None);
+        assert_eq!(mode(&[None]), None);
+    }
+
+    #[test]
+    fn array_stats_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+a = array.from_series(close, 4)
+v1 = array.variance(a)
+v2 = array.variance(a, false)
+c = array.covariance(a, array.from_series(close * 2, 4))
+m1 = array.median(a)
+m2 = array.median([1, 2, 3, 4])
+o1 = array.mode([3, 1, 3, 1, 2])
+o2 = array.mode(a)
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(4f64), Some(4f64), Some(6f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+
+        // The array of the last bar is [6, 4, 4, 2].
+        let ctx = runner.get_context();
+        let mut last = |i: i32| {
+            let val = ctx.move_var(VarIndex::new(i, 0)).unwrap();
+            match val.get_type().0 {
+                DataType::Int => downcast_pf::<Series<Int>>(val)
+                    .unwrap()
+                    .get_history()
+                    .last()
+                    .unwrap()
+                    .map(|v| v as f64),
+                _ => *downcast_pf::<Series<Float>>(val)
+                    .unwrap()
+                    .get_history()
+                    .last()
+                    .unwrap(),
+            }
+        };
+        assert_eq!(last(1), Some(2f64));
+        assert_eq!(last(2), Some(8f64 / 3f64));
+        assert_eq!(last(3), Some(4f64));
+        assert_eq!(last(4), Some(4f64));
+        assert_eq!(last(5), Some(2.5f64));
+        assert_eq!(last(6), Some(1f64));
+        assert_eq!(last(7), Some(4f64));
+    }
+
+    #[test]
+    fn array_covariance_size_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "c = array.covariance([1.0, 2.0], [1.0])";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert!(runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn float_tolerance_test() {
+        let items = vec![Some(0.1f64), Some(0.3f64), Some(1e20f64)];
+        assert_eq!(index_of(&items, &Some(0.1f64 + 0.2f64)), Some(1));
+        assert_eq!(binary_search(&items, &Some(0.1f64 + 0.2f64)), Some(1));
+        assert_eq!(binary_search(&items, &Some(1e20f64 + 1e4f64)), Some(2));
+        assert_eq!(index_of(&items, &Some(0.3f64 + 1e-10f64)), None);
+        assert_eq!(binary_search(&items, &Some(0.3f64 + 1e-10f64)), None);
+    }
+
+    #[test]
+    fn array_search_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+a = array.indexof([1, 2, 2], 2)
+b = array.lastindexof([1, 2, 2], 2)
+c = array.binary_search([1.0, 2.5, 4.0], close)
+d = array.includes(["a", "b"], "b")
+e = array.includes([1.0, 2.0], 0.1 + 0.2)
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(2.5f64)]))],
+                None,
+            )
+            .unwrap();
+
+        let int_res = |i| Some(PineRef::new_rc(Series::from_vec(vec![Some(i)])));
+        let bool_res = |b| Some(PineRef::new_rc(Series::from_vec(vec![b])));
+        let ctx = runner.get_context();
+        assert_eq!(ctx.move_var(VarIndex::new(0, 0)), int_res(1i64));
+        assert_eq!(ctx.move_var(VarIndex::new(1, 0)), int_res(2i64));
+        assert_eq!(ctx.move_var(VarIndex::new(2, 0)), int_res(1i64));
+        assert_eq!(ctx.move_var(VarIndex::new(3, 0)), bool_res(true));
+        assert_eq!(ctx.move_var(VarIndex::new(4, 0)), bool_res(false));
+    }
+
+    #[test]
+    fn array_new_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+a = array.new<float>(3, close)
+b = array.includes(a, 2.5)
+c = array.new<int>(2)
+d = array.indexof(array.new<string>(2, "x"), "x")
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(2.5f64)]))],
+                None,
+            )
+            .unwrap();
+
+        let ctx = runner.get_context();
+        assert_eq!(
+            downcast_pf::<Vec<Float>>(ctx.move_var(VarIndex::new(0, 0)).unwrap())
+                .unwrap()
+                .into_inner(),
+            vec![Some(2.5f64); 3]
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![true])))
+        );
+        assert_eq!(
+            downcast_pf::<Vec<Int>>(ctx.move_var(VarIndex::new(2, 0)).unwrap())
+                .unwrap()
+                .into_inner(),
+            vec![None; 2]
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(0i64)])))
+        );
+    }
+
+    #[test]
+    fn array_new_type_arg_test() {
+        use crate::ast::error::PineErrorKind;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        // The initial value must match the element type.
+        assert!(PineParser::new("a = array.new<int>(2, close)", &lib_info)
+            .parse_blk()
+            .is_err());
+
+        // The type argument is rejected by the functions without the generic signatures.
+        let errs = PineParser::new("a = array.indexof<int>([1], 1)", &lib_info)
+            .parse_blk()
+            .unwrap_err();
+        assert_eq!(errs[0].code, PineErrorKind::TypeArgNotMatch);
+    }
+}
@@ -1,4 +1,4 @@
-use super::ema::rma_func;
+use super::ema::{rma_func, MaState};
 use super::VarResult;
 use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
@@ -34,8 +34,7 @@ struct AtrVal {
     close_index: VarIndex,
     low_index: VarIndex,
     high_index: VarIndex,
-    val_history: Vec<Float>,
-    prev_val: Float,
+    rma: MaState,
 }
 
 impl AtrVal {
@@ -44,8 +43,7 @@ impl AtrVal {
             close_index: VarIndex::new(0, 0),
             low_index: VarIndex::new(0, 0),
             high_index: VarIndex::new(0, 0),
-            val_history: vec![],
-            prev_val: Some(0f64),
+            rma: MaState::new(),
         }
     }
 }
@@ -76,15 +74,15 @@ impl<'a> SeriesCall<'a> for AtrVal {
         let low = pine_ref_to_f64_series(ctx.get_var(self.low_index).clone());
         let high = pine_ref_to_f64_series(ctx.get_var(self.high_index).clone());
 
-        let result = rma_func(true_range(&close, &high, &low), length, self.prev_val)?;
-        self.prev_val = result;
-        self.val_history.push(result);
+        let warmup = downcast_ctx(ctx).get_ema_warmup();
+        let result = self
+            .rma
+            .next(warmup, rma_func, true_range(&close, &high, &low), length)?;
         Ok(PineRef::new(Series::from(result)))
     }
 
     fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
-        self.val_history.pop();
-        self.prev_val = *self.val_history.last().unwrap();
+        self.rma.back();
         Ok(())
     }
 
@@ -150,11 +148,10 @@ mod tests {
             )
             .unwrap();
 
-        let val1 = rma_func(Some(14f64), 2, None).unwrap();
-        let val2 = rma_func(Some(20f64), 2, val1).unwrap();
+        // The true ranges are 14 and 20, the rma is seeded by their sma.
         assert_eq!(
             runner.get_context().get_var(VarIndex::new(0, 0)),
-            &Some(PineRef::new(Series::from_vec(vec![val1, val2])))
+            &Some(PineRef::new(Series::from_vec(vec![None, Some(17f64)])))
         );
     }
 
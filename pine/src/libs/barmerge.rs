@@ -0,0 +1,93 @@
+use super::VarResult;
+use crate::ast::syntax_type::SyntaxType;
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::runtime::context::Ctx;
+use crate::types::{Object, PineClass, PineRef, RuntimeErr};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// The merge strategies are the values of the bool arguments `gaps` and `lookahead` of `security`.
+pub const GAPS_ON: bool = true;
+pub const GAPS_OFF: bool = false;
+pub const LOOKAHEAD_ON: bool = true;
+pub const LOOKAHEAD_OFF: bool = false;
+
+struct BarmergeProps;
+
+impl<'a> PineClass<'a> for BarmergeProps {
+    fn custom_type(&self) -> &str {
+        "barmerge"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "gaps_on" => Ok(PineRef::new_box(GAPS_ON)),
+            "gaps_off" => Ok(PineRef::new_box(GAPS_OFF)),
+            "lookahead_on" => Ok(PineRef::new_box(LOOKAHEAD_ON)),
+            "lookahead_off" => Ok(PineRef::new_box(LOOKAHEAD_OFF)),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("barmerge")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(BarmergeProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "barmerge";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(BarmergeProps)));
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("gaps_on", SyntaxType::bool());
+    obj_type.insert("gaps_off", SyntaxType::bool());
+    obj_type.insert("lookahead_on", SyntaxType::bool());
+    obj_type.insert("lookahead_off", SyntaxType::bool());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::{downcast_pf, Tuple};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn barmerge_fields_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"m = [
+            barmerge.gaps_on, barmerge.gaps_off, barmerge.lookahead_on, barmerge.lookahead_off
+        ]";
+
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let tuple_res =
+            downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(0, 0)).unwrap());
+        assert_eq!(
+            tuple_res.unwrap().into_inner().0,
+            vec![
+                PineRef::new_box(true),
+                PineRef::new_box(false),
+                PineRef::new_box(true),
+                PineRef::new_box(false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use super::plot::plot_color;
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::{
+    move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_i64, pine_ref_to_string,
+};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::output::{BarColorInfo, BgColorInfo, OutputData, OutputInfo};
+use crate::types::{
+    Callable, CallableFactory, ParamCollectCall, PineRef, RuntimeErr, SeriesCall, NA,
+};
+use std::rc::Rc;
+
+// Push the per-bar colors if the color is series, the simple color is saved in the output info.
+fn pine_color<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<(), RuntimeErr> {
+    let color = move_element(&mut param, 0);
+    match (func_type.get_type(0), color) {
+        (Some(SyntaxType::Series(_)), Some(color)) => {
+            let color = plot_color(color, context)?;
+            downcast_ctx(context)
+                .push_output_data(Some(OutputData::new_with_sc(vec![], vec![color])));
+            Ok(())
+        }
+        _ => {
+            downcast_ctx(context).push_output_data(None);
+            Ok(())
+        }
+    }
+}
+
+fn get_color<'a>(color: Option<PineRef<'a>>, func_type: &FunctionType<'a>) -> Option<String> {
+    match func_type.get_type(0) {
+        Some(SyntaxType::Simple(_)) => pine_ref_to_color(color),
+        _ => Some(String::from("")),
+    }
+}
+
+// Stream the color of the current bar to the host, the color is on the bar shifted by the offset.
+fn stream_plot_color<'a>(
+    context: &mut dyn Ctx<'a>,
+    output_id: i32,
+    p: &[Option<PineRef<'a>>],
+    offset_index: usize,
+) {
+    if let Some(callback) = downcast_ctx(context).get_output_callback() {
+        let offset = pine_ref_to_i64(p.get(offset_index).cloned().flatten()).unwrap_or(0);
+        let bar_index = context.get_iterindex() as i64 + offset;
+        if output_id >= 0 && bar_index >= 0 {
+            let color = pine_ref_to_color(p[0].clone()).filter(|c| !c.is_empty());
+            callback.on_plot_color(output_id as usize, bar_index as i32, color);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BgColorVal {
+    output_id: i32,
+}
+
+impl BgColorVal {
+    fn new() -> BgColorVal {
+        BgColorVal { output_id: -1 }
+    }
+}
+
+impl<'a> SeriesCall<'a> for BgColorVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        p: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
+            move_tuplet!((color, transp, offset, editable, show_last, title) = p.clone());
+            let info = BgColorInfo {
+                title: pine_ref_to_string(title),
+                color: get_color(color, &func_type),
+                transp: pine_ref_to_i64(transp),
+                offset: pine_ref_to_i64(offset),
+                editable: pine_ref_to_bool(editable),
+                show_last: pine_ref_to_i64(show_last),
+            };
+            self.output_id =
+                downcast_ctx(context).push_output_info_retindex(OutputInfo::BgColor(info));
+        }
+        stream_plot_color(context, self.output_id, &p, 2);
+        Ok(PineRef::new_box(NA))
+    }
+
+    fn run_with_cd(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        params: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<(), RuntimeErr> {
+        pine_color(context, params, func_type)
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BarColorVal {
+    output_id: i32,
+}
+
+impl BarColorVal {
+    fn new() -> BarColorVal {
+        BarColorVal { output_id: -1 }
+    }
+}
+
+impl<'a> SeriesCall<'a> for BarColorVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        p: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
+            move_tuplet!((color, offset, editable, show_last, title) = p.clone());
+            let info = BarColorInfo {
+                title: pine_ref_to_string(title),
+                color: get_color(color, &func_type),
+                offset: pine_ref_to_i64(offset),
+                editable: pine_ref_to_bool(editable),
+                show_last: pine_ref_to_i64(show_last),
+            };
+            self.output_id =
+                downcast_ctx(context).push_output_info_retindex(OutputInfo::BarColor(info));
+        }
+        stream_plot_color(context, self.output_id, &p, 1);
+        Ok(PineRef::new_box(NA))
+    }
+
+    fn run_with_cd(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        params: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<(), RuntimeErr> {
+        pine_color(context, params, func_type)
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub const BGCOLOR_NAME: &'static str = "bgcolor";
+
+pub fn declare_bgcolor_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                BgColorVal::new(),
+            )))),
+        )
+    }));
+
+    // bgcolor(color, transp, offset, editable, show_last, title) → void
+    let gen_type = |color_type| {
+        FunctionType::new((
+            vec![
+                ("color", color_type),
+                ("transp", SyntaxType::int()),
+                ("offset", SyntaxType::int()),
+                ("editable", SyntaxType::bool()),
+                ("show_last", SyntaxType::int()),
+                ("title", SyntaxType::string()),
+            ],
+            SyntaxType::Void,
+        ))
+    };
+    let func_type = FunctionTypes(vec![
+        gen_type(SyntaxType::color()),
+        gen_type(SyntaxType::Series(SimpleSyntaxType::Color)),
+    ]);
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, BGCOLOR_NAME)
+}
+
+pub const BARCOLOR_NAME: &'static str = "barcolor";
+
+pub fn declare_barcolor_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                BarColorVal::new(),
+            )))),
+        )
+    }));
+
+    // barcolor(color, offset, editable, show_last, title) → void
+    let gen_type = |color_type| {
+        FunctionType::new((
+            vec![
+                ("color", color_type),
+                ("offset", SyntaxType::int()),
+                ("editable", SyntaxType::bool()),
+                ("show_last", SyntaxType::int()),
+                ("title", SyntaxType::string()),
+            ],
+            SyntaxType::Void,
+        ))
+    };
+    let func_type = FunctionTypes(vec![
+        gen_type(SyntaxType::color()),
+        gen_type(SyntaxType::Series(SimpleSyntaxType::Color)),
+    ]);
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, BARCOLOR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{AnySeries, NoneCallback, StrOptionsData};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn bgcolor_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_bgcolor_var(), declare_barcolor_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "bgcolor(#111111, 50, 1, true, 10, 'bg')\n\
+        bgcolor(close > 1 ? #111111 : #222222)\n\
+        barcolor(#333333, title='bar')\n\
+        barcolor(close > 1 ? #111111 : #222222)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_io_info().get_outputs(),
+            &vec![
+                OutputInfo::BgColor(BgColorInfo {
+                    title: Some(String::from("bg")),
+                    color: Some(String::from("#111111")),
+                    transp: Some(50),
+                    offset: Some(1),
+                    editable: Some(true),
+                    show_last: Some(10),
+                }),
+                OutputInfo::BgColor(BgColorInfo {
+                    title: None,
+                    color: Some(String::from("")),
+                    transp: None,
+                    offset: None,
+                    editable: None,
+                    show_last: None,
+                }),
+                OutputInfo::BarColor(BarColorInfo {
+                    title: Some(String::from("bar")),
+                    color: Some(String::from("#333333")),
+                    offset: None,
+                    editable: None,
+                    show_last: None,
+                }),
+                OutputInfo::BarColor(BarColorInfo {
+                    title: None,
+                    color: Some(String::from("")),
+                    offset: None,
+                    editable: None,
+                    show_last: None,
+                }),
+            ]
+        );
+
+        let colors = Some(OutputData::new_with_sc(
+            vec![],
+            vec![StrOptionsData {
+                options: vec![String::from("#222222"), String::from("#111111")],
+                values: vec![Some(0), Some(1)],
+            }],
+        ));
+        assert_eq!(
+            runner.move_output_data(),
+            vec![None, colors.clone(), None, colors]
+        );
+    }
+}
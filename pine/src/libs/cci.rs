@@ -3,11 +3,9 @@ use super::sma::{series_dev, series_sma};
 use super::VarResult;
 use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
-use crate::helper::err_msgs::*;
-use crate::helper::str_replace;
 use crate::helper::{
-    ensure_srcs, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_f64_series,
-    pine_ref_to_i64, require_param, series_index,
+    ensure_srcs, ge1_param_i64, pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64,
+    require_param,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::InputSrc;
@@ -16,56 +14,73 @@ use crate::types::{
     ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use std::cmp;
-use std::mem;
 use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
-struct CciVal {
-    // close_index: VarIndex,
-    ma_history: Vec<Float>,
-    tp_history: Vec<Float>,
+struct CciVal<'a> {
+    close_index: VarIndex,
+    low_index: VarIndex,
+    high_index: VarIndex,
+    // The typical prices(hlc3) of the overload without the source.
+    tp_history: Series<'a, Float>,
 }
 
-impl CciVal {
-    pub fn new() -> CciVal {
+impl<'a> CciVal<'a> {
+    pub fn new() -> CciVal<'a> {
         CciVal {
-            ma_history: vec![],
-            tp_history: vec![],
+            close_index: VarIndex::new(0, 0),
+            low_index: VarIndex::new(0, 0),
+            high_index: VarIndex::new(0, 0),
+            tp_history: Series::new(),
         }
     }
+
+    fn handle_index(&mut self, ctx: &mut dyn Ctx<'a>) {
+        ensure_srcs(ctx, vec!["close", "low", "high"], |indexs| {
+            self.close_index = indexs[0];
+            self.low_index = indexs[1];
+            self.high_index = indexs[2];
+        });
+    }
+}
+
+fn calc_cci(series: &Series<Float>, length: i64) -> Result<Float, RuntimeErr> {
+    Ok(series
+        .at(0)
+        .minus(series_sma(series, length)?)
+        .div(Some(0.015f64).mul(series_dev(series, length)?)))
 }
 
 // cci = (src - sma(src, length)) / (0.015 * dev(src, length))
-impl<'a> SeriesCall<'a> for CciVal {
+impl<'a> SeriesCall<'a> for CciVal<'a> {
     fn step(
         &mut self,
         ctx: &mut dyn Ctx<'a>,
         mut param: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
-        let series = require_param(
-            "source",
-            pine_ref_to_f64_series(mem::replace(&mut param[0], None)),
-        )?;
-
-        let length = require_param("length", pine_ref_to_i64(mem::replace(&mut param[1], None)))?;
-        if length < 1i64 {
-            return Err(RuntimeErr::InvalidParameters(str_replace(
-                GE_1,
-                vec![String::from("length")],
-            )));
-        }
-
-        let cci_val = series
-            .at(0)
-            .minus(series_sma(&series, length)?)
-            .div(Some(0.015f64).mul(series_dev(&series, length)?));
+        let cci_val = if _func_type.signature.0.len() == 1 {
+            // The typical price is the source if it is omitted.
+            self.handle_index(ctx);
+            let length = ge1_param_i64("length", pine_ref_to_i64(param[0].take()))?;
+            let close = pine_ref_to_f64(ctx.get_var(self.close_index).clone());
+            let low = pine_ref_to_f64(ctx.get_var(self.low_index).clone());
+            let high = pine_ref_to_f64(ctx.get_var(self.high_index).clone());
+            self.tp_history
+                .update(high.add(low).add(close).div(Some(3f64)));
+            let res = calc_cci(&self.tp_history, length)?;
+            self.tp_history.commit();
+            res
+        } else {
+            let series = require_param("source", pine_ref_to_f64_series(param[0].take()))?;
+            let length = ge1_param_i64("length", pine_ref_to_i64(param[1].take()))?;
+            calc_cci(&series, length)?
+        };
         Ok(PineRef::new(Series::from(cci_val)))
     }
 
     fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
-        self.ma_history.pop();
-        self.tp_history.pop();
+        self.tp_history.roll_back();
         Ok(())
     }
 
@@ -85,13 +100,19 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         )
     }));
 
-    let func_type = FunctionTypes(vec![FunctionType::new((
-        vec![
-            ("source", SyntaxType::float_series()),
-            ("length", SyntaxType::int()),
-        ],
-        SyntaxType::float_series(),
-    ))]);
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((
+            vec![("length", SyntaxType::int())],
+            SyntaxType::float_series(),
+        )),
+        FunctionType::new((
+            vec![
+                ("source", SyntaxType::float_series()),
+                ("length", SyntaxType::int()),
+            ],
+            SyntaxType::float_series(),
+        )),
+    ]);
     let syntax_type = SyntaxType::Function(Rc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
@@ -102,6 +123,7 @@ mod tests {
     use crate::ast::syntax_type::SyntaxType;
     use crate::runtime::VarOperate;
     use crate::runtime::{AnySeries, NoneCallback};
+    use crate::testing::approx::{assert_series_approx_eq, DEFAULT_TOLERANCE};
     use crate::types::Series;
     use crate::{LibInfo, PineParser, PineRunner};
     use std::f64;
@@ -147,4 +169,58 @@ mod tests {
             ])))
         );
     }
+
+    #[test]
+    fn cci_tp_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+            ],
+        );
+        let src = "m = cci(3)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let gen_data = |close: Vec<f64>, high: Vec<f64>, low: Vec<f64>| {
+            let series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+            vec![
+                ("close", series(close)),
+                ("high", series(high)),
+                ("low", series(low)),
+            ]
+        };
+        let result = |runner: &mut PineRunner| {
+            let val = runner.get_context().get_var(VarIndex::new(0, 0)).clone();
+            pine_ref_to_f64_series(val).unwrap().get_history().clone()
+        };
+
+        // The typical prices are 10, 12 and 14.
+        runner
+            .run(
+                &gen_data(
+                    vec![10f64, 12f64, 15f64],
+                    vec![12f64, 14f64, 18f64],
+                    vec![8f64, 10f64, 9f64],
+                ),
+                None,
+            )
+            .unwrap();
+        assert_series_approx_eq(
+            &result(&mut runner),
+            &[None, None, Some(100f64)],
+            DEFAULT_TOLERANCE,
+        );
+
+        // The typical price of the last bar is rolled back by the update, it is 11 now.
+        runner
+            .update(&gen_data(vec![11f64], vec![12f64], vec![10f64]))
+            .unwrap();
+        assert_series_approx_eq(
+            &result(&mut runner),
+            &[None, None, Some((11f64 - 11f64) / (0.015f64 * 2f64 / 3f64))],
+            DEFAULT_TOLERANCE,
+        );
+    }
 }
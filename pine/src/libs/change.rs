@@ -115,7 +115,7 @@ mod tests {
                 ("low", SyntaxType::Series(SimpleSyntaxType::Float)),
             ],
         );
-        // 5 10 -10
+        // 5 10 -10, the change of the first bar is na, so the comparison is false.
         let src = "ao = high - low\nplot(ao, color = change(ao) <= 0 ? color.red : color.green, style=plot.style_histogram)";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
@@ -140,8 +140,8 @@ mod tests {
             vec![Some(OutputData::new_with_sc(
                 vec![vec![Some(5f64), Some(10f64), Some(-10f64)]],
                 vec![StrOptionsData {
-                    options: vec![String::from("#FF5252"), String::from("#4CAF50")],
-                    values: vec![Some(0), Some(1), Some(0)]
+                    options: vec![String::from("#4CAF50"), String::from("#FF5252")],
+                    values: vec![Some(0), Some(0), Some(1)]
                 }]
             )),]
         );
@@ -0,0 +1,384 @@
+use super::VarResult;
+use crate::ast::stat_expr_types::VarIndex;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{ensure_srcs, move_element, pine_ref_to_f64, pine_ref_to_i64};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::types::{
+    downcast_pf, Callable, CallableFactory, Color, DataType, Float, Int, Object, ParamCollectCall,
+    PineClass, PineFrom, PineRef, PineStaticType, PineType, RefData, RuntimeErr, SecondType,
+    SeriesCall, SimpleType,
+};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// The point on the chart used by the drawings like polylines. It has both the bar index and the
+// bar time, the drawing selects one of them by its `xloc`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChartPoint {
+    pub time: Int,
+    pub index: Int,
+    pub price: Float,
+}
+
+impl PineStaticType for ChartPoint {
+    fn static_type() -> (DataType, SecondType) {
+        (DataType::ChartPoint, SecondType::Simple)
+    }
+}
+
+impl<'a> PineFrom<'a, ChartPoint> for ChartPoint {
+    fn implicity_from(t: PineRef<'a>) -> Result<RefData<ChartPoint>, RuntimeErr> {
+        match t.get_type() {
+            (DataType::ChartPoint, SecondType::Simple) => Ok(downcast_pf::<ChartPoint>(t).unwrap()),
+            (DataType::NA, _) => Ok(RefData::new(ChartPoint::default())),
+            _ => Err(RuntimeErr::UnknownRuntimeErr),
+        }
+    }
+}
+
+impl<'a> PineType<'a> for ChartPoint {
+    fn get_type(&self) -> (DataType, SecondType) {
+        (DataType::ChartPoint, SecondType::Simple)
+    }
+    fn copy(&self) -> PineRef<'a> {
+        PineRef::new(self.clone())
+    }
+}
+
+impl SimpleType for ChartPoint {}
+
+// Convert the argument to the point, the na value and the other types are None.
+pub fn pine_ref_to_point<'a>(val: Option<PineRef<'a>>) -> Option<ChartPoint> {
+    match val {
+        Some(v) if v.get_type() == ChartPoint::static_type() => {
+            Some(downcast_pf::<ChartPoint>(v).unwrap().into_inner())
+        }
+        _ => None,
+    }
+}
+
+fn new_point<'a>(time: Int, index: Int, price: Float) -> Result<PineRef<'a>, RuntimeErr> {
+    Ok(PineRef::new(ChartPoint { time, index, price }))
+}
+
+fn new_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((time, index, price) = param);
+    new_point(
+        pine_ref_to_i64(time),
+        pine_ref_to_i64(index),
+        pine_ref_to_f64(price),
+    )
+}
+
+fn from_index_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((index, price) = param);
+    new_point(None, pine_ref_to_i64(index), pine_ref_to_f64(price))
+}
+
+fn from_time_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((time, price) = param);
+    new_point(pine_ref_to_i64(time), None, pine_ref_to_f64(price))
+}
+
+fn copy_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let id = param[0].take();
+    Ok(PineRef::new(pine_ref_to_point(id).unwrap_or_default()))
+}
+
+// The point at the time and the index of the current bar.
+#[derive(Debug, Clone, PartialEq)]
+struct NowCall {
+    time_index: Option<VarIndex>,
+}
+
+impl<'a> SeriesCall<'a> for NowCall {
+    fn step(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        ensure_srcs(ctx, vec!["_time"], |_| {});
+        if self.time_index.is_none() {
+            self.time_index = downcast_ctx(ctx).get_rel_varname_index("_time");
+        }
+        let price = param[0].take();
+        let time = match self.time_index {
+            Some(index) => pine_ref_to_i64(ctx.get_var(index).clone()),
+            None => None,
+        };
+        new_point(
+            time,
+            Some(ctx.get_iterindex() as i64),
+            pine_ref_to_f64(price),
+        )
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+struct PointProps;
+
+impl<'a> PineClass<'a> for PointProps {
+    fn custom_type(&self) -> &str {
+        "chart.point"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "new" => Ok(PineRef::new(Callable::new(Some(new_func), None))),
+            "from_index" => Ok(PineRef::new(Callable::new(Some(from_index_func), None))),
+            "from_time" => Ok(PineRef::new(Callable::new(Some(from_time_func), None))),
+            "copy" => Ok(PineRef::new(Callable::new(Some(copy_func), None))),
+            "now" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                        NowCall { time_index: None },
+                    )))),
+                )
+            }))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("chart.point")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(PointProps)
+    }
+}
+
+// The na color is the empty string.
+fn gen_color<'a>(color: &Option<String>) -> Color<'a> {
+    match color {
+        Some(s) => Color::new_owned(s.clone()),
+        None => Color::new(""),
+    }
+}
+
+struct ChartProps;
+
+impl<'a> PineClass<'a> for ChartProps {
+    fn custom_type(&self) -> &str {
+        "chart"
+    }
+
+    fn get(&self, ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        let info = downcast_ctx(ctx).get_chart_info().unwrap_or_default();
+        match name {
+            "point" => Ok(PineRef::new(Object::new(Box::new(PointProps)))),
+            "bg_color" => Ok(PineRef::new_box(gen_color(&info.bg_color))),
+            "fg_color" => Ok(PineRef::new_box(gen_color(&info.fg_color))),
+            "left_visible_bar_time" => Ok(PineRef::new_box(info.left_visible_bar_time)),
+            "right_visible_bar_time" => Ok(PineRef::new_box(info.right_visible_bar_time)),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("chart")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(ChartProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "chart";
+
+pub const POINT_CLASS: &'static str = "chart.point";
+
+// The array of the points like `[chart.point.now(high), chart.point.now(low)]`.
+pub const POINT_ARRAY_CLASS: &'static str = "chart.point[]";
+
+fn gen_point_func<'a>(params: Vec<(&'static str, SyntaxType<'a>)>) -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        params,
+        SyntaxType::ObjectClass(POINT_CLASS),
+    ))])))
+}
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(ChartProps)));
+
+    let mut point_type = BTreeMap::new();
+    point_type.insert(
+        "new",
+        gen_point_func(vec![
+            ("time", SyntaxType::int_series()),
+            ("index", SyntaxType::int_series()),
+            ("price", SyntaxType::float_series()),
+        ]),
+    );
+    point_type.insert(
+        "from_index",
+        gen_point_func(vec![
+            ("index", SyntaxType::int_series()),
+            ("price", SyntaxType::float_series()),
+        ]),
+    );
+    point_type.insert(
+        "from_time",
+        gen_point_func(vec![
+            ("time", SyntaxType::int_series()),
+            ("price", SyntaxType::float_series()),
+        ]),
+    );
+    point_type.insert(
+        "now",
+        gen_point_func(vec![("price", SyntaxType::float_series())]),
+    );
+    point_type.insert(
+        "copy",
+        gen_point_func(vec![("id", SyntaxType::ObjectClass(POINT_CLASS))]),
+    );
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("point", SyntaxType::Object(Rc::new(point_type)));
+    obj_type.insert("bg_color", SyntaxType::color());
+    obj_type.insert("fg_color", SyntaxType::color());
+    obj_type.insert("left_visible_bar_time", SyntaxType::int());
+    obj_type.insert("right_visible_bar_time", SyntaxType::int());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::Tuple;
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn chart_point_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::float_series()),
+                ("_time", SyntaxType::int_series()),
+            ],
+        );
+        let src = r"
+        p1 = chart.point.new(100, 1, close)
+        p2 = chart.point.from_index(2, close)
+        p3 = chart.point.from_time(300, close)
+        p4 = chart.point.copy(chart.point.now(close))
+        m = [p1, p2, p3, p4]
+        ";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                    ),
+                    ("_time", AnySeries::from_int_vec(vec![Some(10), Some(20)])),
+                ],
+                None,
+            )
+            .unwrap();
+        let point = |time, index, price| ChartPoint { time, index, price };
+        let tuple =
+            downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(4, 0)).unwrap());
+        let points: Vec<_> = tuple
+            .unwrap()
+            .into_inner()
+            .0
+            .into_iter()
+            .map(|v| pine_ref_to_point(Some(v)))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                Some(point(Some(100), Some(1), Some(2f64))),
+                Some(point(None, Some(2), Some(2f64))),
+                Some(point(Some(300), None, Some(2f64))),
+                Some(point(Some(20), Some(1), Some(2f64))),
+            ]
+        );
+    }
+
+    #[test]
+    fn chart_info_test() {
+        use crate::runtime::ChartInfo;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+        m = [chart.bg_color, chart.fg_color]
+        n = [chart.left_visible_bar_time, chart.right_visible_bar_time]
+        ";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        let get_vals = |runner: &mut PineRunner| {
+            let colors =
+                downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(0, 0)).unwrap())
+                    .unwrap()
+                    .into_inner()
+                    .0
+                    .into_iter()
+                    .map(|v| Color::implicity_from(v).unwrap().into_inner())
+                    .collect::<Vec<_>>();
+            let times =
+                downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(1, 0)).unwrap())
+                    .unwrap()
+                    .into_inner()
+                    .0
+                    .into_iter()
+                    .map(|v| Int::implicity_from(v).unwrap().into_inner())
+                    .collect::<Vec<_>>();
+            (colors, times)
+        };
+
+        // The values are na if the host does not provide the chart information.
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            get_vals(&mut runner),
+            (vec![Color::new(""), Color::new("")], vec![None, None])
+        );
+
+        runner.set_chart_info(Some(Rc::new(ChartInfo {
+            bg_color: Some(String::from("#131722")),
+            fg_color: Some(String::from("#D1D4DC")),
+            left_visible_bar_time: Some(100),
+            right_visible_bar_time: None,
+        })));
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            get_vals(&mut runner),
+            (
+                vec![Color::new("#131722"), Color::new("#D1D4DC")],
+                vec![Some(100), None]
+            )
+        );
+    }
+}
@@ -30,25 +30,11 @@ fn cmo_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, Ru
             .index_value(i as usize)
             .unwrap()
             .minus(source.index_value((i + 1) as usize).unwrap());
-        let cz1 = match cz_val.clone() {
-            Some(cz1) => {
-                if cz1 >= 0f64 {
-                    cz1
-                } else {
-                    0f64
-                }
-            }
-            None => 0f64,
-        };
-        let cz2 = match cz_val.clone() {
-            Some(cz_val) => {
-                if cz_val >= 0f64 {
-                    0f64
-                } else {
-                    -cz_val
-                }
-            }
-            None => 0f64,
+        // The na momentum is 0 for sm1 but na for sm2, so the result is na.
+        let (cz1, cz2) = match cz_val {
+            Some(cz_val) if cz_val >= 0f64 => (cz_val, 0f64),
+            Some(cz_val) => (0f64, -cz_val),
+            None => return Ok(None),
         };
         sm1 += cz1;
         sm2 += cz2;
@@ -98,11 +84,7 @@ mod tests {
 
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new(Series::from_vec(vec![
-                None,
-                Some(-100.0),
-                Some(0.0)
-            ])))
+            Some(PineRef::new(Series::from_vec(vec![None, None, Some(0.0)])))
         );
     }
 }
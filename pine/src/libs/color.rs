@@ -3,18 +3,138 @@ use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, Syn
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
-    move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_f64, pine_ref_to_i64,
-    pine_ref_to_string,
+    move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_color2, pine_ref_to_f64,
+    pine_ref_to_i64, pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::output::{OutputData, OutputInfo, PlotInfo};
 use crate::types::{
-    Bool, Callable, Color, DataType, Float, Int, Object, ParamCollectCall, PineClass, PineFrom,
-    PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
+    Bool, Callable, CallableFactory, Color, DataType, Float, Int, Object, ParamCollectCall,
+    PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
 };
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+// Interpolate the color between the bottom color and the top color by the position of the value
+// in the range of [bottom, top], the value out of the range is clamped.
+fn gradient<'a>(
+    value: f64,
+    bottom: f64,
+    top: f64,
+    bottom_color: [u8; 4],
+    top_color: [u8; 4],
+) -> Color<'a> {
+    let ratio = if top == bottom {
+        if value < bottom {
+            0f64
+        } else {
+            1f64
+        }
+    } else {
+        ((value - bottom) / (top - bottom)).clamp(0f64, 1f64)
+    };
+    let mut rgba = [0u8; 4];
+    for i in 0..4 {
+        let (b, t) = (bottom_color[i] as f64, top_color[i] as f64);
+        rgba[i] = (b + (t - b) * ratio).round() as u8;
+    }
+    Color::from_rgba(rgba)
+}
+
+fn from_gradient_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((value, bottom, top, bottom_color, top_color) = param);
+    let color = match (
+        pine_ref_to_f64(value),
+        pine_ref_to_f64(bottom),
+        pine_ref_to_f64(top),
+        pine_ref_to_color2(bottom_color).and_then(|c| c.to_rgba()),
+        pine_ref_to_color2(top_color).and_then(|c| c.to_rgba()),
+    ) {
+        (Some(v), Some(b), Some(t), Some(bc), Some(tc)) => gradient(v, b, t, bc, tc),
+        _ => Color::new(""),
+    };
+    Ok(PineRef::new_rc(Series::from(color)))
+}
+
+// Apply the transparency in the range of [0, 100] to the color, the alpha of the color is replaced.
+fn new_color<'a>(color: [u8; 4], transp: f64) -> Color<'a> {
+    let opacity = 1f64 - transp.clamp(0f64, 100f64) / 100f64;
+    let [r, g, b, _] = color;
+    Color::from_rgba([r, g, b, (255f64 * opacity).round() as u8])
+}
+
+fn new_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((color, transp) = param);
+    let color = match (
+        pine_ref_to_color2(color).and_then(|c| c.to_rgba()),
+        pine_ref_to_f64(transp),
+    ) {
+        (Some(c), Some(t)) => new_color(c, t),
+        _ => Color::new(""),
+    };
+    match &((func_type.signature.0)[0]).1 {
+        SyntaxType::Simple(_) => Ok(PineRef::new_box(color)),
+        _ => Ok(PineRef::new_rc(Series::from(color))),
+    }
+}
+
+// Get the channel value of the color, the index 3 is the transparency in the range of [0, 100].
+fn channel_val<'a>(
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+    index: usize,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let color = pine_ref_to_color2(move_element(&mut param, 0));
+    let val = color.and_then(|c| c.to_rgba()).map(|rgba| match index {
+        3 => (255 - rgba[3]) as f64 / 255f64 * 100f64,
+        _ => rgba[index] as f64,
+    });
+    match &((func_type.signature.0)[0]).1 {
+        SyntaxType::Simple(_) => Ok(PineRef::new(val)),
+        _ => Ok(PineRef::new_rc(Series::from(val))),
+    }
+}
+
+fn r_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    channel_val(param, func_type, 0)
+}
+
+fn g_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    channel_val(param, func_type, 1)
+}
+
+fn b_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    channel_val(param, func_type, 2)
+}
+
+fn t_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    channel_val(param, func_type, 3)
+}
+
 struct ColorProps;
 
 impl<'a> PineClass<'a> for ColorProps {
@@ -24,23 +144,41 @@ impl<'a> PineClass<'a> for ColorProps {
 
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
-            "aqua" => Ok(PineRef::new_box(Color("#00BCD4"))),
-            "black" => Ok(PineRef::new_box(Color("#363A45"))),
-            "blue" => Ok(PineRef::new_box(Color("#2196F3"))),
-            "fuchsia" => Ok(PineRef::new_box(Color("#E040FB"))),
-            "gray" => Ok(PineRef::new_box(Color("#787B86"))),
-            "green" => Ok(PineRef::new_box(Color("#4CAF50"))),
-            "lime" => Ok(PineRef::new_box(Color("#00E676"))),
-            "maroon" => Ok(PineRef::new_box(Color("#880E4F"))),
-            "navy" => Ok(PineRef::new_box(Color("#311B92"))),
-            "olive" => Ok(PineRef::new_box(Color("#808000"))),
-            "orange" => Ok(PineRef::new_box(Color("#FF9800"))),
-            "purple" => Ok(PineRef::new_box(Color("#9C27B0"))),
-            "red" => Ok(PineRef::new_box(Color("#FF5252"))),
-            "silver" => Ok(PineRef::new_box(Color("#B2B5BE"))),
-            "teal" => Ok(PineRef::new_box(Color("#00897B"))),
-            "white" => Ok(PineRef::new_box(Color("#FFFFFF"))),
-            "yellow" => Ok(PineRef::new_box(Color("#FFEB3B"))),
+            "aqua" => Ok(PineRef::new_box(Color::new("#00BCD4"))),
+            "black" => Ok(PineRef::new_box(Color::new("#363A45"))),
+            "blue" => Ok(PineRef::new_box(Color::new("#2196F3"))),
+            "fuchsia" => Ok(PineRef::new_box(Color::new("#E040FB"))),
+            "gray" => Ok(PineRef::new_box(Color::new("#787B86"))),
+            "green" => Ok(PineRef::new_box(Color::new("#4CAF50"))),
+            "lime" => Ok(PineRef::new_box(Color::new("#00E676"))),
+            "maroon" => Ok(PineRef::new_box(Color::new("#880E4F"))),
+            "navy" => Ok(PineRef::new_box(Color::new("#311B92"))),
+            "olive" => Ok(PineRef::new_box(Color::new("#808000"))),
+            "orange" => Ok(PineRef::new_box(Color::new("#FF9800"))),
+            "purple" => Ok(PineRef::new_box(Color::new("#9C27B0"))),
+            "red" => Ok(PineRef::new_box(Color::new("#FF5252"))),
+            "silver" => Ok(PineRef::new_box(Color::new("#B2B5BE"))),
+            "teal" => Ok(PineRef::new_box(Color::new("#00897B"))),
+            "white" => Ok(PineRef::new_box(Color::new("#FFFFFF"))),
+            "yellow" => Ok(PineRef::new_box(Color::new("#FFEB3B"))),
+            "from_gradient" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(from_gradient_func), None)
+            }))),
+            "new" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(new_func), None)
+            }))),
+            "r" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(r_func), None)
+            }))),
+            "g" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(g_func), None)
+            }))),
+            "b" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(b_func), None)
+            }))),
+            "t" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(t_func), None)
+            }))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
                 vec![String::from(name), String::from("color")],
@@ -76,6 +214,53 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("teal", SyntaxType::color());
     obj_type.insert("white", SyntaxType::color());
     obj_type.insert("yellow", SyntaxType::color());
+
+    // color.from_gradient(value, bottom_value, top_value, bottom_color, top_color) → series[color]
+    obj_type.insert(
+        "from_gradient",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("value", SyntaxType::float_series()),
+                ("bottom_value", SyntaxType::float_series()),
+                ("top_value", SyntaxType::float_series()),
+                ("bottom_color", SyntaxType::color_series()),
+                ("top_color", SyntaxType::color_series()),
+            ],
+            SyntaxType::color_series(),
+        ))]))),
+    );
+    // color.new(color, transp) → color
+    obj_type.insert(
+        "new",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            FunctionType::new((
+                vec![
+                    ("color", SyntaxType::color()),
+                    ("transp", SyntaxType::float()),
+                ],
+                SyntaxType::color(),
+            )),
+            FunctionType::new((
+                vec![
+                    ("color", SyntaxType::color_series()),
+                    ("transp", SyntaxType::float_series()),
+                ],
+                SyntaxType::color_series(),
+            )),
+        ]))),
+    );
+    // color.r(color) → float, color.g, color.b and color.t are the same.
+    let channel_type = SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        FunctionType::new((vec![("color", SyntaxType::color())], SyntaxType::float())),
+        FunctionType::new((
+            vec![("color", SyntaxType::color_series())],
+            SyntaxType::float_series(),
+        )),
+    ])));
+    obj_type.insert("r", channel_type.clone());
+    obj_type.insert("g", channel_type.clone());
+    obj_type.insert("b", channel_type.clone());
+    obj_type.insert("t", channel_type);
     let syntax_type = SyntaxType::Object(Rc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
@@ -117,24 +302,120 @@ mod tests {
         assert_eq!(
             tuple_vec,
             vec![
-                PineRef::new_box(Color("#00BCD4")),
-                PineRef::new_box(Color("#363A45")),
-                PineRef::new_box(Color("#2196F3")),
-                PineRef::new_box(Color("#E040FB")),
-                PineRef::new_box(Color("#787B86")),
-                PineRef::new_box(Color("#4CAF50")),
-                PineRef::new_box(Color("#00E676")),
-                PineRef::new_box(Color("#880E4F")),
-                PineRef::new_box(Color("#311B92")),
-                PineRef::new_box(Color("#808000")),
-                PineRef::new_box(Color("#FF9800")),
-                PineRef::new_box(Color("#9C27B0")),
-                PineRef::new_box(Color("#FF5252")),
-                PineRef::new_box(Color("#B2B5BE")),
-                PineRef::new_box(Color("#00897B")),
-                PineRef::new_box(Color("#FFFFFF")),
-                PineRef::new_box(Color("#FFEB3B")),
+                PineRef::new_box(Color::new("#00BCD4")),
+                PineRef::new_box(Color::new("#363A45")),
+                PineRef::new_box(Color::new("#2196F3")),
+                PineRef::new_box(Color::new("#E040FB")),
+                PineRef::new_box(Color::new("#787B86")),
+                PineRef::new_box(Color::new("#4CAF50")),
+                PineRef::new_box(Color::new("#00E676")),
+                PineRef::new_box(Color::new("#880E4F")),
+                PineRef::new_box(Color::new("#311B92")),
+                PineRef::new_box(Color::new("#808000")),
+                PineRef::new_box(Color::new("#FF9800")),
+                PineRef::new_box(Color::new("#9C27B0")),
+                PineRef::new_box(Color::new("#FF5252")),
+                PineRef::new_box(Color::new("#B2B5BE")),
+                PineRef::new_box(Color::new("#00897B")),
+                PineRef::new_box(Color::new("#FFFFFF")),
+                PineRef::new_box(Color::new("#FFEB3B")),
             ]
         );
     }
+
+    #[test]
+    fn gradient_test() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 0]);
+        assert_eq!(
+            gradient(0f64, 0f64, 10f64, black, white),
+            Color::new("#000000")
+        );
+        assert_eq!(
+            gradient(5f64, 0f64, 10f64, black, white),
+            Color::new("#80808080")
+        );
+        assert_eq!(
+            gradient(20f64, 0f64, 10f64, black, white),
+            Color::new("#FFFFFF00")
+        );
+        assert_eq!(
+            gradient(-1f64, 0f64, 10f64, black, white),
+            Color::new("#000000")
+        );
+        assert_eq!(
+            gradient(1f64, 1f64, 1f64, black, white),
+            Color::new("#FFFFFF00")
+        );
+    }
+
+    #[test]
+    fn color_func_test() {
+        use crate::ast::stat_expr_types::VarIndex;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+m1 = color.from_gradient(close, 0, 10, #000000, #FF8040)
+m2 = color.r(m1)
+m3 = color.t(#FF000080)
+m4 = color.g(close > 5 ? #00FF00 : na)
+m5 = color.new(#FF0000, 50)
+m6 = color.new(close > 5 ? #00FF00 : na, close * 10)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(10f64), None, Some(5f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        let ctx = runner.get_context();
+        assert_eq!(
+            ctx.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Color::new("#FF8040"),
+                Color::new(""),
+                Color::new("#804020"),
+            ])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(255f64),
+                None,
+                Some(128f64)
+            ])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new(Some(127f64 / 255f64 * 100f64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(255f64),
+                None,
+                None
+            ])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(4, 0)),
+            Some(PineRef::new_box(Color::new("#FF000080")))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(5, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Color::new("#00FF0000"),
+                Color::new(""),
+                Color::new(""),
+            ])))
+        );
+    }
 }
@@ -10,11 +10,13 @@ use std::rc::Rc;
 #[derive(Debug, Clone, PartialEq)]
 struct MathCallVal {
     func: *mut (),
+    // The handler of the int argument, the int argument is converted to float if it is None.
+    int_func: Option<*mut ()>,
 }
 
 impl MathCallVal {
-    pub fn new(func: *mut ()) -> MathCallVal {
-        MathCallVal { func }
+    pub fn new(func: *mut (), int_func: Option<*mut ()>) -> MathCallVal {
+        MathCallVal { func, int_func }
     }
 }
 
@@ -28,12 +30,21 @@ impl<'a> SeriesCall<'a> for MathCallVal {
         let xval = mem::replace(&mut param[0], None);
 
         let handler = unsafe { transmute::<_, fn(Option<PineRef<'a>>) -> Float>(self.func) };
-        match ((func_type.signature.0)[0]).1 {
-            SyntaxType::Simple(SimpleSyntaxType::Float) => {
+        let int_handler = self
+            .int_func
+            .map(|f| unsafe { transmute::<_, fn(Option<PineRef<'a>>) -> Int>(f) });
+        match (((func_type.signature.0)[0]).1.clone(), int_handler) {
+            (SyntaxType::Simple(SimpleSyntaxType::Int), Some(int_handler)) => {
+                Ok(PineRef::new_box(int_handler(xval)))
+            }
+            (SyntaxType::Series(SimpleSyntaxType::Int), Some(int_handler)) => {
+                Ok(PineRef::new_rc(Series::from(int_handler(xval))))
+            }
+            (SyntaxType::Simple(_), _) => {
                 let res = handler(xval);
                 Ok(PineRef::new_box(res))
             }
-            SyntaxType::Series(SimpleSyntaxType::Float) => {
+            (SyntaxType::Series(_), _) => {
                 let res = handler(xval);
                 Ok(PineRef::new_rc(Series::from(res)))
             }
@@ -47,24 +58,62 @@ impl<'a> SeriesCall<'a> for MathCallVal {
 }
 pub const VAR_NAME: &'static str = "cos";
 
-pub fn declare_math_var<'a>(
-    varname: &'static str,
+// The math function that takes a number and returns float, e.g. `sqrt(x)`.
+pub fn math_callable<'a>(func: fn(Option<PineRef<'a>>) -> Float) -> Callable<'a> {
+    Callable::new(
+        None,
+        Some(Box::new(MathCallVal::new(func as *mut (), None))),
+    )
+}
+
+// The math function that returns int for the int argument, e.g. `abs(x)`.
+pub fn int_math_callable<'a>(
+    int_func: fn(Option<PineRef<'a>>) -> Int,
     func: fn(Option<PineRef<'a>>) -> Float,
-) -> VarResult<'a> {
-    let value = PineRef::new(Callable::new(
+) -> Callable<'a> {
+    Callable::new(
         None,
-        Some(Box::new(MathCallVal::new(func as *mut ()))),
-    ));
+        Some(Box::new(MathCallVal::new(
+            func as *mut (),
+            Some(int_func as *mut ()),
+        ))),
+    )
+}
 
-    let func_type = FunctionTypes(vec![
-        FunctionType::new((vec![("x", SyntaxType::float())], SyntaxType::float())),
+// f(x) → float, f(series[x]) → series[float], the parameter is `number` in the math namespace.
+pub fn gen_math_type<'a>(name: &'a str) -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        FunctionType::new((vec![(name, SyntaxType::float())], SyntaxType::float())),
         FunctionType::new((
-            vec![("x", SyntaxType::float_series())],
+            vec![(name, SyntaxType::float_series())],
             SyntaxType::float_series(),
         )),
-    ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
-    VarResult::new(value, syntax_type, varname)
+    ])))
+}
+
+// f(T) → T, f(series[T]) → series[T], the type variable is int or float.
+pub fn gen_int_math_type<'a>(name: &'a str) -> SyntaxType<'a> {
+    let gen_type = |arg_type: SyntaxType<'a>| {
+        FunctionType::new_with_templates(
+            vec![SimpleSyntaxType::Int, SimpleSyntaxType::Float],
+            (vec![(name, arg_type.clone())], arg_type),
+        )
+    };
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        gen_type(SyntaxType::Simple(SimpleSyntaxType::Template)),
+        gen_type(SyntaxType::Series(SimpleSyntaxType::Template)),
+    ])))
+}
+
+pub fn declare_math_var<'a>(
+    varname: &'static str,
+    func: fn(Option<PineRef<'a>>) -> Float,
+) -> VarResult<'a> {
+    VarResult::new(
+        PineRef::new(math_callable(func)),
+        gen_math_type("x"),
+        varname,
+    )
 }
 
 fn float_cos<'a>(xval: Option<PineRef<'a>>) -> Float {
@@ -133,7 +182,7 @@ pub fn declare_atan_var<'a>() -> VarResult<'a> {
     declare_math_var("atan", float_atan)
 }
 
-fn float_sqrt<'a>(xval: Option<PineRef<'a>>) -> Float {
+pub fn float_sqrt<'a>(xval: Option<PineRef<'a>>) -> Float {
     match pine_ref_to_f64(xval) {
         None => None,
         Some(v) => Some(v.sqrt()),
@@ -144,7 +193,7 @@ pub fn declare_sqrt_var<'a>() -> VarResult<'a> {
     declare_math_var("sqrt", float_sqrt)
 }
 
-fn float_exp<'a>(xval: Option<PineRef<'a>>) -> Float {
+pub fn float_exp<'a>(xval: Option<PineRef<'a>>) -> Float {
     match pine_ref_to_f64(xval) {
         None => None,
         Some(v) => Some(v.exp()),
@@ -155,7 +204,7 @@ pub fn declare_exp_var<'a>() -> VarResult<'a> {
     declare_math_var("exp", float_exp)
 }
 
-fn float_log<'a>(xval: Option<PineRef<'a>>) -> Float {
+pub fn float_log<'a>(xval: Option<PineRef<'a>>) -> Float {
     match pine_ref_to_f64(xval) {
         None => None,
         Some(v) => Some(v.log(std::f64::consts::E)),
@@ -166,7 +215,7 @@ pub fn declare_log_var<'a>() -> VarResult<'a> {
     declare_math_var("log", float_log)
 }
 
-fn float_log10<'a>(xval: Option<PineRef<'a>>) -> Float {
+pub fn float_log10<'a>(xval: Option<PineRef<'a>>) -> Float {
     match pine_ref_to_f64(xval) {
         None => None,
         Some(v) => Some(v.log10()),
@@ -177,7 +226,7 @@ pub fn declare_log10_var<'a>() -> VarResult<'a> {
     declare_math_var("log10", float_log10)
 }
 
-fn float_sign<'a>(xval: Option<PineRef<'a>>) -> Float {
+pub fn float_sign<'a>(xval: Option<PineRef<'a>>) -> Float {
     match pine_ref_to_f64(xval) {
         None => None,
         Some(v) if v > 0f64 => Some(1f64),
@@ -187,8 +236,16 @@ fn float_sign<'a>(xval: Option<PineRef<'a>>) -> Float {
     }
 }
 
+pub fn int_sign<'a>(xval: Option<PineRef<'a>>) -> Int {
+    pine_ref_to_i64(xval).map(|v| v.signum())
+}
+
 pub fn declare_sign_var<'a>() -> VarResult<'a> {
-    declare_math_var("sign", float_sign)
+    VarResult::new(
+        PineRef::new(int_math_callable(int_sign, float_sign)),
+        gen_int_math_type("x"),
+        "sign",
+    )
 }
 
 #[cfg(test)]
@@ -223,7 +280,8 @@ mod tests {
         m5 = tan(0)\nm6 = atan(0)\n
         m7 = sqrt(16)\nm8 = exp(3)\n
         m9 = log(exp(3))\nm10 = log10(100)\n
-        m11 = sign(12)\n
+        m11 = sign(12)\nm12 = sign(close)\n
+        m13 = sign(int(close))\n
         ";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
@@ -283,9 +341,18 @@ mod tests {
             runner.get_context().move_var(VarIndex::new(starti + 9, 0)),
             Some(PineRef::new(Some(2f64)))
         );
+        // The sign of the int is int.
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(starti + 10, 0)),
-            Some(PineRef::new(Some(1f64)))
+            Some(PineRef::new(Some(1i64)))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(starti + 11, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(-1f64)])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(starti + 12, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(-1i64)])))
         );
     }
 }
@@ -1,9 +1,10 @@
 use super::VarResult;
 use crate::ast::syntax_type::SyntaxType;
 use crate::helper::err_msgs::*;
+use crate::helper::pine_ref_to_i64;
 use crate::helper::str_replace;
 use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::output::{OutputData, OutputInfo, PlotInfo, StrOptionsData};
+use crate::runtime::output::{DISPLAY_ALL, DISPLAY_NONE, DISPLAY_PANE};
 use crate::types::{
     Bool, Callable, CallableObject, Color, DataType, Float, Int, Object, ParamCollectCall,
     PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
@@ -12,6 +13,16 @@ use crate::types::{
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+// Get the display argument of the plot functions, the value must be one of the display constants.
+pub fn pine_ref_to_display<'a>(val: Option<PineRef<'a>>) -> Result<Option<i64>, RuntimeErr> {
+    match pine_ref_to_i64(val) {
+        Some(v) if v != DISPLAY_NONE && v != DISPLAY_ALL && v != DISPLAY_PANE => Err(
+            RuntimeErr::InvalidParameters(str_replace(INVALID_VALS, vec![String::from("display")])),
+        ),
+        v => Ok(v),
+    }
+}
+
 struct PlotProps;
 
 impl<'a> PineClass<'a> for PlotProps {
@@ -21,8 +32,9 @@ impl<'a> PineClass<'a> for PlotProps {
 
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
-            "none" => Ok(PineRef::new_box(Some(0i64))),
-            "all" => Ok(PineRef::new_box(Some(1i64))),
+            "none" => Ok(PineRef::new_box(Some(DISPLAY_NONE))),
+            "all" => Ok(PineRef::new_box(Some(DISPLAY_ALL))),
+            "pane" => Ok(PineRef::new_box(Some(DISPLAY_PANE))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
                 vec![String::from(name), String::from("plot")],
@@ -43,6 +55,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     let mut obj_type = BTreeMap::new();
     obj_type.insert("none", SyntaxType::int());
     obj_type.insert("all", SyntaxType::int());
+    obj_type.insert("pane", SyntaxType::int());
     let syntax_type = SyntaxType::Object(Rc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
@@ -62,7 +75,7 @@ mod tests {
             vec![("close", SyntaxType::float_series())],
         );
         let src = r"m = [
-            display.none, display.all
+            display.none, display.all, display.pane
         ]";
 
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
@@ -79,7 +92,55 @@ mod tests {
         let tuple_vec = tuple_res.unwrap().into_inner().0;
         assert_eq!(
             tuple_vec,
-            vec![PineRef::new_box(Some(0i64)), PineRef::new_box(Some(1i64))]
+            vec![
+                PineRef::new_box(Some(0i64)),
+                PineRef::new_box(Some(1i64)),
+                PineRef::new_box(Some(2i64))
+            ]
+        );
+    }
+
+    #[test]
+    fn plot_display_test() {
+        use crate::libs::{plot, plotshape};
+        use crate::runtime::output::OutputData;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var(), plotshape::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"plot(close, display=display.none)
+plot(close, display=display.pane)
+plot(close)
+plotshape(close, display=display.none)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+
+        let outputs = runner.get_io_info().get_outputs();
+        assert_eq!(
+            outputs.iter().map(|o| o.get_display()).collect::<Vec<_>>(),
+            vec![
+                Some(DISPLAY_NONE),
+                Some(DISPLAY_PANE),
+                None,
+                Some(DISPLAY_NONE)
+            ]
+        );
+        assert_eq!(
+            outputs.iter().map(|o| o.is_visible()).collect::<Vec<_>>(),
+            vec![false, true, true, false]
+        );
+
+        // The hidden outputs still generate the data.
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![Some(1f64)]])); 4]
         );
     }
 }
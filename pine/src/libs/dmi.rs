@@ -1,4 +1,4 @@
-use super::ema::rma_func;
+use super::ema::{rma_func, EmaWarmup, MaState};
 use super::VarResult;
 use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
@@ -7,7 +7,6 @@ use crate::helper::{
     pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index, series_index2,
 };
 use crate::libs::change::series_change;
-use crate::libs::ema::series_rma;
 use crate::libs::tr::series_tr;
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::InputSrc;
@@ -19,25 +18,25 @@ use std::mem;
 use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
-struct DirmovProps<'a> {
-    trs: Series<'a, Float>,
-    dm1s: Series<'a, Float>,
-    dm2s: Series<'a, Float>,
+struct DirmovProps {
+    trs: MaState,
+    dm1s: MaState,
+    dm2s: MaState,
 }
 
-impl<'a> DirmovProps<'a> {
-    fn new() -> DirmovProps<'a> {
+impl DirmovProps {
+    fn new() -> DirmovProps {
         DirmovProps {
-            trs: Series::new(),
-            dm1s: Series::new(),
-            dm2s: Series::new(),
+            trs: MaState::new(),
+            dm1s: MaState::new(),
+            dm2s: MaState::new(),
         }
     }
 
-    fn commit(&mut self) {
-        self.trs.commit();
-        self.dm1s.commit();
-        self.dm2s.commit();
+    fn back(&mut self) {
+        self.trs.back();
+        self.dm1s.back();
+        self.dm2s.back();
     }
 }
 
@@ -53,13 +52,14 @@ fn dirmov(
     low: &Series<Float>,
     close: &Series<Float>,
     props: &mut DirmovProps,
+    warmup: EmaWarmup,
     len: i64,
 ) -> Result<(Float, Float), RuntimeErr> {
     let up = series_change(high, 1);
     let down = series_change(low, 1).negative();
 
     let trv = series_tr(high.at(0), low.at(0), close);
-    let truerange = series_rma(trv, len, &mut props.trs)?;
+    let truerange = props.trs.next(warmup, rma_func, trv, len)?;
 
     let dm_plus = if up.gt(down) && up.gt(Some(0f64)) {
         up
@@ -67,7 +67,7 @@ fn dirmov(
         Some(0f64)
     };
     let plus = Some(100f64)
-        .mul(series_rma(dm_plus, len, &mut props.dm1s)?)
+        .mul(props.dm1s.next(warmup, rma_func, dm_plus, len)?)
         .div(truerange);
 
     let dm_minus = if down.gt(up) && down.gt(Some(0f64)) {
@@ -76,35 +76,35 @@ fn dirmov(
         Some(0f64)
     };
     let minus = Some(100f64)
-        .mul(series_rma(dm_minus, len, &mut props.dm2s)?)
+        .mul(props.dm2s.next(warmup, rma_func, dm_minus, len)?)
         .div(truerange);
 
     return Ok((plus, minus));
 }
 
 #[derive(Debug, Clone, PartialEq)]
-struct DmiVal<'a> {
+struct DmiVal {
     close_index: VarIndex,
     low_index: VarIndex,
     high_index: VarIndex,
 
-    dirmov_props: DirmovProps<'a>,
-    adxs: Series<'a, Float>,
+    dirmov_props: DirmovProps,
+    adxs: MaState,
 }
 
-impl<'a> DmiVal<'a> {
-    pub fn new() -> DmiVal<'a> {
+impl DmiVal {
+    pub fn new() -> DmiVal {
         DmiVal {
             close_index: VarIndex::new(0, 0),
             low_index: VarIndex::new(0, 0),
             high_index: VarIndex::new(0, 0),
             dirmov_props: DirmovProps::new(),
-            adxs: Series::new(),
+            adxs: MaState::new(),
         }
     }
 }
 
-impl<'a> SeriesCall<'a> for DmiVal<'a> {
+impl<'a> SeriesCall<'a> for DmiVal {
     fn step(
         &mut self,
         ctx: &mut dyn Ctx<'a>,
@@ -145,14 +145,22 @@ impl<'a> SeriesCall<'a> for DmiVal<'a> {
         // adx = 100 * rma(abs(plus - minus) / (sum == 0 ? 1 : sum), adxlen)
         // [adx, plus, minus]
 
-        let (plus, minus) = dirmov(&*high, &*low, &*close, &mut self.dirmov_props, di_len)?;
+        let warmup = downcast_ctx(ctx).get_ema_warmup();
+        let (plus, minus) = dirmov(
+            &*high,
+            &*low,
+            &*close,
+            &mut self.dirmov_props,
+            warmup,
+            di_len,
+        )?;
         let sum = plus.add(minus);
         let aval =
             float_abs(plus.minus(minus)).div(if sum == Some(0f64) { Some(1f64) } else { sum });
-        let adx = series_rma(aval, adx_len, &mut self.adxs)?.mul(Some(100f64));
-
-        self.dirmov_props.commit();
-        self.adxs.commit();
+        let adx = self
+            .adxs
+            .next(warmup, rma_func, aval, adx_len)?
+            .mul(Some(100f64));
 
         Ok(PineRef::new(Tuple(vec![
             PineRef::new_rc(Series::from(plus)),
@@ -162,6 +170,8 @@ impl<'a> SeriesCall<'a> for DmiVal<'a> {
     }
 
     fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.dirmov_props.back();
+        self.adxs.back();
         Ok(())
     }
 
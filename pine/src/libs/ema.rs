@@ -5,37 +5,17 @@ use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
     ge1_param_i64, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_f64_series,
-    pine_ref_to_i64,
+    pine_ref_to_i64, series_index, Moments, RollingWindow,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::InputSrc;
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, CallableFactory, Evaluate, EvaluateVal,
-    Float, Int, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
+    Float, Int, ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall, NA,
 };
 use std::mem;
 use std::rc::Rc;
 
-pub fn series_rma<'a>(
-    src: Float,
-    length: i64,
-    rma: &mut Series<Float>,
-) -> Result<Float, RuntimeErr> {
-    let val = rma_func(src, length, rma.at(1))?;
-    rma.update(val);
-    Ok(val)
-}
-
-pub fn series_ema<'a>(
-    src: Float,
-    length: i64,
-    ema: &mut Series<Float>,
-) -> Result<Float, RuntimeErr> {
-    let val = ema_func(src, length, ema.at(1))?;
-    ema.update(val);
-    Ok(val)
-}
-
 pub fn ema_func<'a>(source: Float, length: i64, prev_val: Float) -> Result<Float, RuntimeErr> {
     let mut sum = 0f64;
     let alpha = 2f64 / (length + 1) as f64;
@@ -53,7 +33,6 @@ pub fn ema_func<'a>(source: Float, length: i64, prev_val: Float) -> Result<Float
 pub fn rma_func<'a>(source: Float, length: i64, prev_val: Float) -> Result<Float, RuntimeErr> {
     let mut sum = 0f64;
     let alpha = length as f64;
-    println!("with rma {:?} {:?} {:?}", source, prev_val, length);
     match source {
         Some(val) => {
             sum = val + (alpha - 1f64) * prev_val.unwrap_or(0f64);
@@ -66,17 +45,85 @@ pub fn rma_func<'a>(source: Float, length: i64, prev_val: Float) -> Result<Float
     Ok(Some(sum))
 }
 
+// How `ema`, `rma` and the averages inside the builtin indicators start before enough bars are
+// calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EmaWarmup {
+    // Like TradingView: the average is na until `length` source values are available, then it is
+    // seeded by the sma of these values. It is seeded again after the na source values.
+    #[default]
+    SmaSeed,
+    // The behavior of the old versions of the runtime: the recursion starts on the first bar and
+    // the na previous average is treated as 0.
+    Recursive,
+}
+
+pub type MaFunc = fn(Float, i64, Float) -> Result<Float, RuntimeErr>;
+
+// The ema or rma calculated inside the builtin indicators like `macd`, `atr` and `rsi`. It
+// starts by the warm-up setting of the runner like the `ema` and `rma` builtins, so the
+// indicators agree with the averages written in the script.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MaState {
+    src_history: Vec<Float>,
+    val_history: Vec<Float>,
+}
+
+impl MaState {
+    pub fn new() -> MaState {
+        MaState::default()
+    }
+
+    pub fn next(
+        &mut self,
+        warmup: EmaWarmup,
+        func: MaFunc,
+        src: Float,
+        length: i64,
+    ) -> Result<Float, RuntimeErr> {
+        let prev_val = self.val_history.last().cloned().flatten();
+        self.src_history.push(src);
+        let val = match (warmup, prev_val) {
+            (EmaWarmup::SmaSeed, None) => self.sma_seed(length as usize),
+            _ => func(src, length, prev_val)?,
+        };
+        self.val_history.push(val);
+        Ok(val)
+    }
+
+    // The sma of the last `length` source values, na if any of them is na.
+    fn sma_seed(&self, length: usize) -> Float {
+        if self.src_history.len() < length {
+            return None;
+        }
+        let mut sum = 0f64;
+        for src in self.src_history[self.src_history.len() - length..].iter() {
+            sum += (*src)?;
+        }
+        Some(sum / length as f64)
+    }
+
+    // Roll back the value of the current bar.
+    pub fn back(&mut self) {
+        self.src_history.pop();
+        self.val_history.pop();
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct EmaVal {
-    prev_val: Float,
     ma_func: *mut (),
+    // The sma of the source that seeds the average.
+    window: RollingWindow<Float, Moments>,
+    val_history: Vec<Float>,
 }
 
 impl EmaVal {
     pub fn new(ma_func: *mut ()) -> EmaVal {
         EmaVal {
-            prev_val: None,
             ma_func,
+            window: RollingWindow::new(Moments::new()),
+            val_history: vec![],
         }
     }
 }
@@ -84,23 +131,41 @@ impl EmaVal {
 impl<'a> SeriesCall<'a> for EmaVal {
     fn step(
         &mut self,
-        _ctx: &mut dyn Ctx<'a>,
+        ctx: &mut dyn Ctx<'a>,
         mut param: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
         move_tuplet!((source, length) = param);
 
-        let source = pine_ref_to_f64(source);
+        let source = pine_ref_to_f64_series(source);
         let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
 
-        let func = unsafe {
-            mem::transmute::<_, fn(Float, i64, Float) -> Result<Float, RuntimeErr>>(self.ma_func)
+        let func = unsafe { mem::transmute::<_, MaFunc>(self.ma_func) };
+        let prev_val = self.val_history.last().cloned().flatten();
+        let val = match downcast_ctx(ctx).get_ema_warmup() {
+            EmaWarmup::SmaSeed => {
+                let bar = ctx.get_iterindex();
+                let seed = self
+                    .window
+                    .update(bar, length as usize, |i| series_index(&source, i))
+                    .mean();
+                match prev_val {
+                    Some(_) => func(series_index(&source, 0), length, prev_val)?,
+                    None => seed,
+                }
+            }
+            EmaWarmup::Recursive => func(series_index(&source, 0), length, prev_val)?,
         };
-        let val = func(source, length, mem::replace(&mut self.prev_val, None))?;
-        self.prev_val = val;
+        self.val_history.push(val);
         Ok(PineRef::new(Series::from(val)))
     }
 
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.val_history.pop();
+        self.window.invalidate();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -124,13 +189,23 @@ fn declare_ma_var<'a>(name: &'static str, factory: fn() -> Callable<'a>) -> VarR
 
 pub fn declare_ema_var<'a>() -> VarResult<'a> {
     declare_ma_var("ema", || {
-        Callable::new(None, Some(Box::new(EmaVal::new(ema_func as *mut ()))))
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                EmaVal::new(ema_func as *mut ()),
+            )))),
+        )
     })
 }
 
 pub fn declare_rma_var<'a>() -> VarResult<'a> {
     declare_ma_var("rma", || {
-        Callable::new(None, Some(Box::new(EmaVal::new(rma_func as *mut ()))))
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                EmaVal::new(rma_func as *mut ()),
+            )))),
+        )
     })
 }
 
@@ -153,6 +228,7 @@ mod tests {
         let src = "m1 = ema(close, 3)\nm2 = rma(close, 2)\n";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.set_ema_warmup(EmaWarmup::Recursive);
 
         runner
             .run(
@@ -181,4 +257,83 @@ mod tests {
             ])))
         );
     }
+
+    #[test]
+    fn sma_seed_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_ema_var(), declare_rma_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m1 = ema(close, 3)\nm2 = rma(close, 2)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![
+                        None,
+                        Some(10f64),
+                        Some(20f64),
+                        Some(30f64),
+                        Some(40f64),
+                    ]),
+                )],
+                None,
+            )
+            .unwrap();
+        // Replace the last bar with na, the averages are seeded again after it.
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![None, Some(20f64), Some(40f64)]),
+            )])
+            .unwrap();
+
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                None,
+                None,
+                Some(20f64),
+                None,
+                None,
+                None
+            ])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                None,
+                Some(15f64),
+                Some(22.5f64),
+                None,
+                None,
+                Some(30f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn ma_state_test() {
+        let mut state = MaState::new();
+        let next =
+            |state: &mut MaState, src| state.next(EmaWarmup::SmaSeed, rma_func, src, 2).unwrap();
+        assert_eq!(next(&mut state, Some(10f64)), None);
+        assert_eq!(next(&mut state, Some(20f64)), Some(15f64));
+        assert_eq!(next(&mut state, Some(25f64)), Some(20f64));
+
+        // The rolled back bar is calculated again from the previous average.
+        state.back();
+        assert_eq!(next(&mut state, Some(35f64)), Some(25f64));
+
+        let mut state = MaState::new();
+        assert_eq!(
+            state.next(EmaWarmup::Recursive, rma_func, Some(10f64), 2),
+            Ok(Some(5f64))
+        );
+    }
 }
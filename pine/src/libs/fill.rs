@@ -16,21 +16,48 @@ use crate::types::{
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+// Get the boundary values of the fill region from the referenced plot or hline output.
+fn fill_bound<'a>(context: &mut dyn Ctx<'a>, fill_type: &str, id: i64) -> Vec<Option<f64>> {
+    let ctx_ins = downcast_ctx(context.get_main_ctx());
+    match fill_type {
+        "hline" => {
+            let price = match ctx_ins.get_io_info().get_outputs().get(id as usize) {
+                Some(OutputInfo::HLine(info)) => info.price,
+                _ => None,
+            };
+            // No bar is run before the data range is set, so the region is empty.
+            match ctx_ins.get_data_range() {
+                (Some(start), Some(end)) => vec![price; end.saturating_sub(start).max(0) as usize],
+                _ => vec![],
+            }
+        }
+        _ => match ctx_ins.get_output_data(id as usize) {
+            Some(data) if !data.series.is_empty() => data.series[0].clone(),
+            _ => vec![],
+        },
+    }
+}
+
 fn pine_plot<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
     _func_type: FunctionType<'a>,
+    plot_val: &PlotVal,
 ) -> Result<(), RuntimeErr> {
     move_tuplet!((_plot1, _plot2, color) = param);
+    let region = vec![
+        fill_bound(context, plot_val.fill_type, plot_val.start_id),
+        fill_bound(context, plot_val.fill_type, plot_val.end_id),
+    ];
     match (_func_type.get_type(2), color) {
         (Some(SyntaxType::Series(_)), Some(color)) => {
             let color = plot_color(color, context)?;
             downcast_ctx(context)
-                .push_output_data(Some(OutputData::new_with_sc(vec![], vec![color])));
+                .push_output_data(Some(OutputData::new_with_sc(region, vec![color])));
             Ok(())
         }
         _ => {
-            downcast_ctx(context).push_output_data(None);
+            downcast_ctx(context).push_output_data(Some(OutputData::new(region)));
             Ok(())
         }
     }
@@ -38,6 +65,7 @@ fn pine_plot<'a>(
 
 #[derive(Debug, Clone)]
 struct PlotVal {
+    fill_type: &'static str,
     start_id: i64,
     end_id: i64,
     output_id: i32,
@@ -46,6 +74,7 @@ struct PlotVal {
 impl PlotVal {
     fn new() -> PlotVal {
         PlotVal {
+            fill_type: "plot",
             start_id: -1i64,
             end_id: -1i64,
             output_id: -1,
@@ -67,10 +96,13 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 Some(&SyntaxType::ObjectClass("hline")) => ("hline", "hline1", "hline2"),
                 _ => unreachable!(),
             };
+            self.fill_type = names.0;
+            self.start_id = require_param(names.1, pine_ref_to_i64(plot1))?;
+            self.end_id = require_param(names.2, pine_ref_to_i64(plot2))?;
             let plot_info = FillInfo {
                 fill_type: String::from(names.0),
-                start: require_param(names.1, pine_ref_to_i64(plot1))?,
-                end: require_param(names.2, pine_ref_to_i64(plot2))?,
+                start: self.start_id,
+                end: self.end_id,
                 title: pine_ref_to_string(title),
                 color: pine_ref_to_color(color),
                 opacity: pine_ref_to_i64(opacity),
@@ -90,7 +122,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
         params: Vec<Option<PineRef<'a>>>,
         func_type: FunctionType<'a>,
     ) -> Result<(), RuntimeErr> {
-        pine_plot(_context, params, func_type)
+        pine_plot(_context, params, func_type, self)
     }
 
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
@@ -179,7 +211,7 @@ mod tests {
             vec![declare_var(), plot::declare_var()],
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
-        let src = "p1 = plot(close)\np01 = plot(close)\np2 = plot(close)\n\
+        let src = "p1 = plot(close)\np01 = plot(close)\np2 = plot(close + 1)\n\
         fill(p1, p2, #111111, 1, 'fill', true, 1)\n\
         fill(p1, p2, close > close ? #111111 : #222222)";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
@@ -209,11 +241,17 @@ mod tests {
         );
 
         let output_data = runner.move_output_data();
-        assert_eq!(output_data[3], None);
+        assert_eq!(
+            output_data[3],
+            Some(OutputData::new(vec![
+                vec![Some(1f64), Some(2f64)],
+                vec![Some(2f64), Some(3f64)]
+            ]))
+        );
         assert_eq!(
             output_data[4],
             Some(OutputData::new_with_sc(
-                vec![],
+                vec![vec![Some(1f64), Some(2f64)], vec![Some(2f64), Some(3f64)]],
                 vec![StrOptionsData {
                     options: vec![String::from("#222222")],
                     values: vec![Some(0), Some(0)]
@@ -222,6 +260,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fill_bound_no_range_test() {
+        use crate::runtime::context::{Context, ContextType};
+
+        let mut context = Context::new(None, ContextType::Main);
+        assert_eq!(fill_bound(&mut context, "hline", 0), vec![]);
+    }
+
     #[test]
     fn fill_hline_test() {
         use super::super::hline;
@@ -258,5 +304,12 @@ mod tests {
                 show_last: Some(1i64),
             })
         );
+        assert_eq!(
+            runner.move_output_data()[2],
+            Some(OutputData::new(vec![
+                vec![Some(1f64), Some(1f64)],
+                vec![Some(2f64), Some(2f64)]
+            ]))
+        );
     }
 }
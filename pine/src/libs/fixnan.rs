@@ -99,24 +99,14 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         Callable::new(None, Some(Box::new(AtrVal::new())))
     }));
 
-    let func_type = FunctionTypes(vec![
-        FunctionType::new((
-            vec![("x", SyntaxType::float_series())],
-            SyntaxType::float_series(),
-        )),
-        FunctionType::new((
-            vec![("x", SyntaxType::int_series())],
-            SyntaxType::int_series(),
-        )),
-        // FunctionType::new((
-        //     vec![("x", SyntaxType::bool_series())],
-        //     SyntaxType::bool_series(),
-        // )),
-        // FunctionType::new((
-        //     vec![("x", SyntaxType::Series(SimpleSyntaxType::Color))],
-        //     SyntaxType::Series(SimpleSyntaxType::Color),
-        // )),
-    ]);
+    // The float series is matched first, so the int series is fixed as the float series.
+    let func_type = FunctionTypes(vec![FunctionType::new_with_templates(
+        vec![SimpleSyntaxType::Float, SimpleSyntaxType::Int],
+        (
+            vec![("x", SyntaxType::Series(SimpleSyntaxType::Template))],
+            SyntaxType::Series(SimpleSyntaxType::Template),
+        ),
+    )]);
     let syntax_type = SyntaxType::Function(Rc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
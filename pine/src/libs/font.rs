@@ -0,0 +1,82 @@
+use super::VarResult;
+use crate::ast::syntax_type::SyntaxType;
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::runtime::context::Ctx;
+use crate::types::{Object, PineClass, PineRef, RuntimeErr};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+pub const FONT_FAMILY_DEFAULT: &'static str = "default";
+pub const FONT_FAMILY_MONOSPACE: &'static str = "monospace";
+
+struct FontProps;
+
+impl<'a> PineClass<'a> for FontProps {
+    fn custom_type(&self) -> &str {
+        "font"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "family_default" => Ok(PineRef::new(String::from(FONT_FAMILY_DEFAULT))),
+            "family_monospace" => Ok(PineRef::new(String::from(FONT_FAMILY_MONOSPACE))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("font")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(FontProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "font";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(FontProps)));
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("family_default", SyntaxType::string());
+    obj_type.insert("family_monospace", SyntaxType::string());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::{downcast_pf, Tuple};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn font_fields_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = [font.family_default, font.family_monospace]";
+
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let tuple_res =
+            downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(0, 0)).unwrap());
+        assert_eq!(
+            tuple_res.unwrap().into_inner().0,
+            vec![
+                PineRef::new(String::from("default")),
+                PineRef::new(String::from("monospace")),
+            ]
+        );
+    }
+}
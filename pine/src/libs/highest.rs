@@ -4,7 +4,7 @@ use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::{
     ensure_srcs, float_abs, float_max, ge1_param_i64, move_element, pine_ref_to_bool,
-    pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64, series_index,
+    pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64, series_index, Extreme, RollingWindow,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::InputSrc;
@@ -16,7 +16,6 @@ use std::mem;
 use std::rc::Rc;
 
 type GenIndexFunc<'a> = fn(&mut dyn Ctx<'a>) -> VarIndex;
-type GetValFunc = fn(&Option<RefData<Series<Float>>>, i64) -> Float;
 
 fn gen_high_index<'a>(ctx: &mut dyn Ctx<'a>) -> VarIndex {
     VarIndex::new(*ctx.get_varname_index("high").unwrap(), 0)
@@ -36,15 +35,15 @@ pub fn get_max_val<'a>(source: &Option<RefData<Series<Float>>>, length: i64) ->
 #[derive(Debug, Clone, PartialEq)]
 struct AtrVal {
     src_name: &'static str,
-    run_func: *mut (),
+    window: RollingWindow<Float, Extreme>,
     dest_index: VarIndex,
 }
 
 impl AtrVal {
-    pub fn new(src_name: &'static str, run_func: *mut ()) -> AtrVal {
+    pub fn new(src_name: &'static str, is_max: bool) -> AtrVal {
         AtrVal {
             src_name,
-            run_func,
+            window: RollingWindow::new(Extreme::new(is_max)),
             dest_index: VarIndex::new(0, 0),
         }
     }
@@ -60,8 +59,6 @@ impl<'a> SeriesCall<'a> for AtrVal {
         let source;
         let length;
 
-        let runner = unsafe { mem::transmute::<_, GetValFunc>(self.run_func) };
-
         if _func_type.signature.0.len() == 1 {
             ensure_srcs(ctx, vec![self.src_name], |indexs| {
                 self.dest_index = indexs[0];
@@ -73,10 +70,20 @@ impl<'a> SeriesCall<'a> for AtrVal {
             source = pine_ref_to_f64_series(mem::replace(&mut param[0], None));
             length = ge1_param_i64("length", pine_ref_to_i64(mem::replace(&mut param[1], None)))?;
         }
-        let max_val = runner(&source, length);
+        let bar = ctx.get_iterindex();
+        let max_val = self
+            .window
+            .update(bar, length as usize, |i| series_index(&source, i))
+            .get()
+            .map(|(val, _)| val);
         Ok(PineRef::new_rc(Series::from(max_val)))
     }
 
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.window.invalidate();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -85,12 +92,12 @@ impl<'a> SeriesCall<'a> for AtrVal {
 #[derive(Debug, Clone, PartialEq)]
 struct SmaCreator {
     src_name: &'static str,
-    handle: *mut (),
+    is_max: bool,
 }
 
 impl SmaCreator {
-    pub fn new(src_name: &'static str, handle: *mut ()) -> SmaCreator {
-        SmaCreator { src_name, handle }
+    pub fn new(src_name: &'static str, is_max: bool) -> SmaCreator {
+        SmaCreator { src_name, is_max }
     }
 }
 
@@ -99,7 +106,7 @@ impl<'a> CallableCreator<'a> for SmaCreator {
         Callable::new(
             None,
             Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
-                AtrVal::new(self.src_name, self.handle),
+                AtrVal::new(self.src_name, self.is_max),
             )))),
         )
     }
@@ -112,10 +119,10 @@ impl<'a> CallableCreator<'a> for SmaCreator {
 pub fn declare_s_var<'a>(
     name: &'static str,
     src_name: &'static str,
-    run_func: GetValFunc,
+    is_max: bool,
 ) -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new_with_creator(Box::new(
-        SmaCreator::new(src_name, run_func as *mut ()),
+        SmaCreator::new(src_name, is_max),
     )));
 
     let func_type = FunctionTypes(vec![
@@ -136,7 +143,7 @@ pub fn declare_s_var<'a>(
 }
 
 pub fn declare_var<'a>() -> VarResult<'a> {
-    declare_s_var("highest", "high", get_max_val)
+    declare_s_var("highest", "high", true)
 }
 
 #[cfg(test)]
@@ -193,4 +200,53 @@ mod tests {
             ])))
         );
     }
+
+    #[test]
+    fn highest_update_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = highest(close, 2)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(-3f64), Some(-5f64), None, Some(-1f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(0, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![
+                Some(-3f64),
+                Some(-3f64),
+                Some(-5f64),
+                Some(-1f64)
+            ])))
+        );
+
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(-7f64)]),
+            )])
+            .unwrap();
+        runner
+            .update(&vec![("close", AnySeries::from_float_vec(vec![None]))])
+            .unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(0, 0)),
+            &Some(PineRef::new(Series::from_vec(vec![
+                Some(-3f64),
+                Some(-3f64),
+                Some(-5f64),
+                None
+            ])))
+        );
+    }
 }
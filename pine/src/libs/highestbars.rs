@@ -1,48 +1,31 @@
-use super::ema::rma_func;
 use super::VarResult;
 use crate::ast::stat_expr_types::VarIndex;
-use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
 use crate::helper::{
-    ensure_srcs, float_abs, float_max, ge1_param_i64, move_element, pine_ref_to_bool,
-    pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index,
+    ensure_srcs, ge1_param_i64, pine_ref_to_f64_series, pine_ref_to_i64, series_index, Extreme,
+    RollingWindow,
 };
-use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::InputSrc;
+use crate::runtime::context::Ctx;
 use crate::types::{
-    downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Float, Int,
-    ParamCollectCall, PineRef, RefData, RuntimeErr, Series, SeriesCall,
+    Callable, CallableCreator, CallableFactory, Float, ParamCollectCall, PineRef, RuntimeErr,
+    Series, SeriesCall,
 };
 use std::mem;
 use std::rc::Rc;
 
 type GenIndexFunc<'a> = fn(&mut dyn Ctx<'a>) -> VarIndex;
-type GetValFunc = fn(&Option<RefData<Series<Float>>>, i64) -> Int;
-
-fn get_max_val<'a>(source: &Option<RefData<Series<Float>>>, length: i64) -> Int {
-    let mut max_val = Some(0f64);
-    let mut max_i = Some(0);
-    for i in 0..length {
-        let cur_val = series_index(source, i as usize);
-        if cur_val > max_val {
-            max_i = Some(-i as i64);
-            max_val = cur_val;
-        }
-    }
-    max_i
-}
-
 #[derive(Debug, Clone, PartialEq)]
 struct AtrVal {
     src_name: &'static str,
-    run_func: *mut (),
+    window: RollingWindow<Float, Extreme>,
     dest_index: VarIndex,
 }
 
 impl AtrVal {
-    pub fn new(src_name: &'static str, run_func: *mut ()) -> AtrVal {
+    pub fn new(src_name: &'static str, is_max: bool) -> AtrVal {
         AtrVal {
             src_name,
-            run_func,
+            window: RollingWindow::new(Extreme::new(is_max)),
             dest_index: VarIndex::new(0, 0),
         }
     }
@@ -58,8 +41,6 @@ impl<'a> SeriesCall<'a> for AtrVal {
         let source;
         let length;
 
-        let runner = unsafe { mem::transmute::<_, GetValFunc>(self.run_func) };
-
         if _func_type.signature.0.len() == 1 {
             ensure_srcs(ctx, vec![self.src_name], |indexs| {
                 self.dest_index = indexs[0];
@@ -71,8 +52,18 @@ impl<'a> SeriesCall<'a> for AtrVal {
             source = pine_ref_to_f64_series(mem::replace(&mut param[0], None));
             length = ge1_param_i64("length", pine_ref_to_i64(mem::replace(&mut param[1], None)))?;
         }
-        let max_val = runner(&source, length);
-        Ok(PineRef::new_rc(Series::from(max_val)))
+        let bar = ctx.get_iterindex();
+        let max_i = self
+            .window
+            .update(bar, length as usize, |i| series_index(&source, i))
+            .get()
+            .map(|(_, i)| -(i as i64));
+        Ok(PineRef::new_rc(Series::from(max_i)))
+    }
+
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.window.invalidate();
+        Ok(())
     }
 
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
@@ -83,12 +74,12 @@ impl<'a> SeriesCall<'a> for AtrVal {
 #[derive(Debug, Clone, PartialEq)]
 struct SmaCreator {
     src_name: &'static str,
-    handle: *mut (),
+    is_max: bool,
 }
 
 impl SmaCreator {
-    pub fn new(src_name: &'static str, handle: *mut ()) -> SmaCreator {
-        SmaCreator { src_name, handle }
+    pub fn new(src_name: &'static str, is_max: bool) -> SmaCreator {
+        SmaCreator { src_name, is_max }
     }
 }
 
@@ -97,7 +88,7 @@ impl<'a> CallableCreator<'a> for SmaCreator {
         Callable::new(
             None,
             Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
-                AtrVal::new(self.src_name, self.handle),
+                AtrVal::new(self.src_name, self.is_max),
             )))),
         )
     }
@@ -110,10 +101,10 @@ impl<'a> CallableCreator<'a> for SmaCreator {
 pub fn declare_s_var<'a>(
     name: &'static str,
     src_name: &'static str,
-    run_func: GetValFunc,
+    is_max: bool,
 ) -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new_with_creator(Box::new(
-        SmaCreator::new(src_name, run_func as *mut ()),
+        SmaCreator::new(src_name, is_max),
     )));
 
     let func_type = FunctionTypes(vec![
@@ -134,7 +125,7 @@ pub fn declare_s_var<'a>(
 }
 
 pub fn declare_var<'a>() -> VarResult<'a> {
-    declare_s_var("highestbars", "high", get_max_val)
+    declare_s_var("highestbars", "high", true)
 }
 
 #[cfg(test)]
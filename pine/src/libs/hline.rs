@@ -33,10 +33,6 @@ impl<'a> SeriesCall<'a> for PlotVal {
         mut p: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
-        println!(
-            "is ready {:?}",
-            downcast_ctx(context).check_is_output_info_ready(),
-        );
         if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
             move_tuplet!((price, title, color, linestyle, linewidth, editable) = p);
             let plot_info = HLineInfo {
@@ -51,7 +47,6 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 downcast_ctx(context).push_output_info_retindex(OutputInfo::HLine(plot_info));
         }
 
-        println!("get id {:?}", self.output_id);
         Ok(PineRef::Box(Box::new(Some(self.output_id as i64))))
     }
 
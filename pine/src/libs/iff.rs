@@ -32,7 +32,7 @@ fn iff_func<'a>(
             Ok(PineRef::new_box(pine_ref_to_bool(val).unwrap_or(false)))
         }
         SyntaxType::Simple(SimpleSyntaxType::Color) => Ok(PineRef::new_box(
-            pine_ref_to_color2(val).unwrap_or(Color("")),
+            pine_ref_to_color2(val).unwrap_or(Color::new("")),
         )),
         SyntaxType::Simple(SimpleSyntaxType::String) => Ok(PineRef::new_rc(
             pine_ref_to_string(val).unwrap_or(String::from("")),
@@ -48,7 +48,7 @@ fn iff_func<'a>(
             pine_ref_to_bool(val).unwrap_or(false),
         ))),
         SyntaxType::Series(SimpleSyntaxType::Color) => Ok(PineRef::new_rc(Series::from(
-            pine_ref_to_color2(val).unwrap_or(Color("")),
+            pine_ref_to_color2(val).unwrap_or(Color::new("")),
         ))),
         SyntaxType::Series(SimpleSyntaxType::String) => Ok(PineRef::new_rc(Series::from(
             pine_ref_to_string(val).unwrap_or(String::from("")),
@@ -199,7 +199,7 @@ mod tests {
         );
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(starti + 4, 0)),
-            Some(PineRef::new(Color("#123456")))
+            Some(PineRef::new(Color::new("#123456")))
         );
 
         assert_eq!(
@@ -224,8 +224,8 @@ mod tests {
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(starti + 9, 0)),
             Some(PineRef::new(Series::from_vec(vec![
-                Color("#123456"),
-                Color("#654321")
+                Color::new("#123456"),
+                Color::new("#654321")
             ])))
         );
     }
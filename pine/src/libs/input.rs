@@ -4,20 +4,22 @@ use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, Syn
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
-    move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
+    move_element, pine_ref_to_bool, pine_ref_to_color2, pine_ref_to_f64, pine_ref_to_i64,
+    pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::output::{
-    BoolInputInfo, FloatInputInfo, InputInfo, InputVal, IntInputInfo, SourceInputInfo,
-    StringInputInfo,
+    BoolInputInfo, ColorInputInfo, FloatInputInfo, InputInfo, InputMeta, InputVal, IntInputInfo,
+    SourceInputInfo, StringInputInfo, SymbolInputInfo,
 };
 use crate::types::{
-    downcast_pf, Bool, Callable, CallableObject, DataType, Float, Int, ParamCollectCall, PineClass,
-    PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
+    downcast_pf, Bool, Callable, CallableObject, Color, DataType, Float, Int, ParamCollectCall,
+    PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
     SimpleCallableObject, Tuple, NA,
 };
 use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::fmt;
 use std::rc::Rc;
 
 const BOOL_TYPE_STR: &'static str = "bool";
@@ -25,6 +27,39 @@ const INT_TYPE_STR: &'static str = "int";
 const FLOAT_TYPE_STR: &'static str = "float";
 const STRING_TYPE_STR: &'static str = "string";
 const SOURCE_TYPE_STR: &'static str = "source";
+const PRICE_TYPE_STR: &'static str = "price";
+const SYMBOL_TYPE_STR: &'static str = "symbol";
+const COLOR_TYPE_STR: &'static str = "color";
+
+// Generate the layout metadata from the `inline`, `group` and `tooltip` arguments.
+fn input_meta<'a>(
+    inline: Option<PineRef<'a>>,
+    group: Option<PineRef<'a>>,
+    tooltip: Option<PineRef<'a>>,
+) -> InputMeta {
+    InputMeta {
+        inline: pine_ref_to_string(inline),
+        group: pine_ref_to_string(group),
+        tooltip: pine_ref_to_string(tooltip),
+    }
+}
+
+// The default value and the value given by the host must be one of the options if the input has
+// the options.
+fn check_options<T: PartialEq + fmt::Debug>(
+    val: Option<&T>,
+    options: &Option<Vec<T>>,
+) -> Result<(), RuntimeErr> {
+    match (val, options) {
+        (Some(val), Some(options)) if !options.contains(val) => {
+            Err(RuntimeErr::FuncCallParamNotValid(str_replace(
+                INPUT_OPTIONS,
+                vec![format!("{:?}", val), format!("{:?}", options)],
+            )))
+        }
+        _ => Ok(()),
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 struct InputCall<'a> {
@@ -91,6 +126,11 @@ fn input_for_bool<'a>(
             title: pine_ref_to_string(move_element(&mut param, 1)),
             input_type: String::from(BOOL_TYPE_STR),
             confirm: pine_ref_to_bool(move_element(&mut param, 3)),
+            meta: input_meta(
+                move_element(&mut param, 4),
+                move_element(&mut param, 5),
+                move_element(&mut param, 6),
+            ),
         }));
     }
 
@@ -108,6 +148,10 @@ fn input_for_string<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
+    if pine_ref_to_string(param[2].clone()).as_deref() == Some(SYMBOL_TYPE_STR) {
+        return input_for_symbol(context, param);
+    }
+    let options = pine_ref_to_str_list(move_element(&mut param, 4));
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
         let type_str = pine_ref_to_string(move_element(&mut param, 2));
@@ -122,18 +166,28 @@ fn input_for_string<'a>(
                 ],
             )));
         }
+        let defval = pine_ref_to_string(param[0].clone());
+        check_options(defval.as_ref(), &options)?;
         ctx_ins.push_input_info(InputInfo::String(StringInputInfo {
-            defval: pine_ref_to_string(param[0].clone()),
+            defval,
             title: pine_ref_to_string(move_element(&mut param, 1)),
             input_type: String::from(STRING_TYPE_STR),
             confirm: pine_ref_to_bool(move_element(&mut param, 3)),
-            options: pine_ref_to_str_list(move_element(&mut param, 4)),
+            options: options.clone(),
+            meta: input_meta(
+                move_element(&mut param, 5),
+                move_element(&mut param, 6),
+                move_element(&mut param, 7),
+            ),
         }));
     }
 
     let input_val = ctx_ins.copy_next_input();
     match input_val {
-        Some(InputVal::String(val)) => Ok(PineRef::new_rc(val)),
+        Some(InputVal::String(val)) => {
+            check_options(Some(&val), &options)?;
+            Ok(PineRef::new_rc(val))
+        }
         _ => match move_element(&mut param, 0) {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
@@ -141,6 +195,90 @@ fn input_for_string<'a>(
     }
 }
 
+// The symbol input falls back to the chart symbol if the default value is na or empty.
+fn input_for_symbol<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!(
+        (
+            defval,
+            title,
+            _input_type,
+            confirm,
+            _options,
+            inline,
+            group,
+            tooltip
+        ) = param
+    );
+    let ctx_ins = downcast_ctx(context);
+    let defval = match pine_ref_to_string(defval) {
+        Some(s) if !s.is_empty() => Some(s),
+        _ => ctx_ins
+            .get_syminfo()
+            .as_ref()
+            .map(|info| info.ticker.clone()),
+    };
+    if !ctx_ins.check_is_input_info_ready() {
+        let exchange = defval
+            .as_ref()
+            .and_then(|s| s.find(':').map(|i| String::from(&s[..i])));
+        ctx_ins.push_input_info(InputInfo::Symbol(SymbolInputInfo {
+            defval: defval.clone(),
+            title: pine_ref_to_string(title),
+            input_type: String::from(SYMBOL_TYPE_STR),
+            confirm: pine_ref_to_bool(confirm),
+            exchange,
+            meta: input_meta(inline, group, tooltip),
+        }));
+    }
+
+    match ctx_ins.copy_next_input() {
+        Some(InputVal::String(val)) | Some(InputVal::Symbol(val)) => Ok(PineRef::new_rc(val)),
+        _ => Ok(PineRef::new_rc(defval.unwrap_or_default())),
+    }
+}
+
+// The color input returns the color chosen by the host, or the default color if the host gives
+// nothing or an invalid color.
+fn input_for_color<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((defval, title, input_type, confirm, inline, group, tooltip) = param);
+    let ctx_ins = downcast_ctx(context);
+    if !ctx_ins.check_is_input_info_ready() {
+        if let Some(type_str) = pine_ref_to_string(input_type).filter(|s| s != COLOR_TYPE_STR) {
+            return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
+                EXP_VAL_BUT_GET_VAL,
+                vec![String::from(COLOR_TYPE_STR), type_str],
+            )));
+        }
+        let rgba = pine_ref_to_color2(defval.clone()).and_then(|c| c.to_rgba());
+        ctx_ins.push_input_info(InputInfo::Color(ColorInputInfo {
+            defval: rgba.map(|[r, g, b, _]| Color::from_rgba([r, g, b, 255]).0.into_owned()),
+            alpha: rgba.map(|[_, _, _, a]| a),
+            title: pine_ref_to_string(title),
+            input_type: String::from(COLOR_TYPE_STR),
+            confirm: pine_ref_to_bool(confirm),
+            meta: input_meta(inline, group, tooltip),
+        }));
+    }
+
+    match ctx_ins.copy_next_input() {
+        Some(InputVal::Color(val)) | Some(InputVal::String(val))
+            if Color::new(&val).to_rgba().is_some() =>
+        {
+            Ok(PineRef::new_box(Color::new_owned(val)))
+        }
+        _ => match defval {
+            Some(val) => Ok(val),
+            _ => Err(RuntimeErr::NotValidParam),
+        },
+    }
+}
+
 const SOURCES: &[&'static str] = &["close", "open", "high", "low"];
 fn get_name_from_source<'a>(
     context: &mut dyn Ctx<'a>,
@@ -200,11 +338,16 @@ fn input_for_source<'a>(
             defval: name,
             title: pine_ref_to_string(move_element(&mut param, 1)),
             input_type: String::from(SOURCE_TYPE_STR),
+            confirm: pine_ref_to_bool(move_element(&mut param, 3)),
+            meta: input_meta(
+                move_element(&mut param, 4),
+                move_element(&mut param, 5),
+                move_element(&mut param, 6),
+            ),
         }));
     }
 
     let input_val = downcast_ctx(context).copy_next_input();
-    println!("Get input val {:?}", input_val);
 
     match input_val {
         Some(InputVal::String(val)) | Some(InputVal::Source(val)) => {
@@ -252,10 +395,34 @@ fn input_for_int<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    if pine_ref_to_string(param[2].clone()) == Some(String::from("float")) {
-        return input_for_float(context, param);
+    match pine_ref_to_string(param[2].clone()).as_deref() {
+        Some(FLOAT_TYPE_STR) | Some(PRICE_TYPE_STR) => return input_for_float(context, param),
+        // The na default of the symbol input is matched as the int input.
+        Some(SYMBOL_TYPE_STR) => {
+            let sym_param = [
+                None,
+                Some(1),
+                Some(2),
+                Some(5),
+                None,
+                Some(8),
+                Some(9),
+                Some(10),
+            ]
+            .iter()
+            .map(|i| i.and_then(|i| move_element(&mut param, i)))
+            .collect();
+            return input_for_symbol(context, sym_param);
+        }
+        _ => {}
     }
-    move_tuplet!((defval, title, input_type, minval, maxval, confirm, step, options) = param);
+    move_tuplet!(
+        (
+            defval, title, input_type, minval, maxval, confirm, step, options, inline, group,
+            tooltip
+        ) = param
+    );
+    let options = pine_ref_to_i64_list(options);
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
         let type_str = pine_ref_to_string(input_type);
@@ -269,21 +436,27 @@ fn input_for_int<'a>(
                 ],
             )));
         }
+        let int_defval = pine_ref_to_i64(defval.clone());
+        check_options(int_defval.as_ref(), &options)?;
         ctx_ins.push_input_info(InputInfo::Int(IntInputInfo {
-            defval: pine_ref_to_i64(defval.clone()),
+            defval: int_defval,
             title: pine_ref_to_string(title),
             input_type: String::from(INT_TYPE_STR),
             minval: pine_ref_to_i64(minval),
             maxval: pine_ref_to_i64(maxval),
             confirm: pine_ref_to_bool(confirm),
             step: pine_ref_to_i64(step),
-            options: pine_ref_to_i64_list(options),
+            options: options.clone(),
+            meta: input_meta(inline, group, tooltip),
         }));
     }
 
     let input_val = ctx_ins.copy_next_input();
     match input_val {
-        Some(InputVal::Int(val)) => Ok(PineRef::new_box(Some(val))),
+        Some(InputVal::Int(val)) => {
+            check_options(Some(&val), &options)?;
+            Ok(PineRef::new_box(Some(val)))
+        }
         _ => match defval {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
@@ -295,11 +468,22 @@ fn input_for_float<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    move_tuplet!((defval, title, input_type, minval, maxval, confirm, step, options) = param);
+    move_tuplet!(
+        (
+            defval, title, input_type, minval, maxval, confirm, step, options, inline, group,
+            tooltip
+        ) = param
+    );
+    let options = pine_ref_to_f64_list(options);
     let ctx_ins = downcast_ctx(context);
     if !ctx_ins.check_is_input_info_ready() {
         let type_str = pine_ref_to_string(input_type);
-        if type_str.is_some() && type_str.as_ref().unwrap() != FLOAT_TYPE_STR {
+        // The price input is the float input that the host can pick the value on the chart.
+        let input_type = match type_str.as_deref() {
+            Some(PRICE_TYPE_STR) => PRICE_TYPE_STR,
+            _ => FLOAT_TYPE_STR,
+        };
+        if type_str.is_some() && type_str.as_deref() != Some(input_type) {
             // type must be BOOL_TYPE_STR
             return Err(RuntimeErr::FuncCallParamNotValid(str_replace(
                 EXP_VAL_BUT_GET_VAL,
@@ -309,21 +493,27 @@ fn input_for_float<'a>(
                 ],
             )));
         }
+        let float_defval = pine_ref_to_f64(defval.clone());
+        check_options(float_defval.as_ref(), &options)?;
         ctx_ins.push_input_info(InputInfo::Float(FloatInputInfo {
-            defval: pine_ref_to_f64(defval.clone()),
+            defval: float_defval,
             title: pine_ref_to_string(title),
-            input_type: String::from(FLOAT_TYPE_STR),
+            input_type: String::from(input_type),
             minval: pine_ref_to_f64(minval),
             maxval: pine_ref_to_f64(maxval),
             confirm: pine_ref_to_bool(confirm),
             step: pine_ref_to_f64(step),
-            options: pine_ref_to_f64_list(options),
+            options: options.clone(),
+            meta: input_meta(inline, group, tooltip),
         }));
     }
 
     let input_val = ctx_ins.copy_next_input();
     match input_val {
-        Some(InputVal::Float(val)) => Ok(PineRef::new_box(Some(val))),
+        Some(InputVal::Float(val)) => {
+            check_options(Some(&val), &options)?;
+            Ok(PineRef::new_box(Some(val)))
+        }
         _ => match defval {
             Some(val) => Ok(val),
             _ => Err(RuntimeErr::NotValidParam),
@@ -338,6 +528,9 @@ fn gen_bool_type<'a>() -> FunctionType<'a> {
             ("title", SyntaxType::string()),
             ("type", SyntaxType::string()),
             ("confirm", SyntaxType::bool()),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
         ],
         SyntaxType::bool(),
     ))
@@ -354,6 +547,9 @@ fn gen_int_type<'a>() -> FunctionType<'a> {
             ("confirm", SyntaxType::bool()),
             ("step", SyntaxType::int()),
             ("options", SyntaxType::List(SimpleSyntaxType::Int)),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
         ],
         SyntaxType::int(),
     ))
@@ -370,6 +566,9 @@ fn gen_float_type<'a>() -> FunctionType<'a> {
             ("confirm", SyntaxType::bool()),
             ("step", SyntaxType::float()),
             ("options", SyntaxType::List(SimpleSyntaxType::Float)),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
         ],
         SyntaxType::float(),
     ))
@@ -383,6 +582,9 @@ fn gen_string_type<'a>() -> FunctionType<'a> {
             ("type", SyntaxType::string()),
             ("confirm", SyntaxType::bool()),
             ("options", SyntaxType::List(SimpleSyntaxType::String)),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
         ],
         SyntaxType::string(),
     ))
@@ -394,30 +596,47 @@ fn gen_source_type<'a>() -> FunctionType<'a> {
             ("defval", SyntaxType::Series(SimpleSyntaxType::Float)),
             ("title", SyntaxType::string()),
             ("type", SyntaxType::string()),
+            ("confirm", SyntaxType::bool()),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
         ],
         SyntaxType::Series(SimpleSyntaxType::Float),
     ))
 }
 
+fn gen_color_type<'a>() -> FunctionType<'a> {
+    FunctionType::new((
+        vec![
+            ("defval", SyntaxType::color()),
+            ("title", SyntaxType::string()),
+            ("type", SyntaxType::string()),
+            ("confirm", SyntaxType::bool()),
+            ("inline", SyntaxType::string()),
+            ("group", SyntaxType::string()),
+            ("tooltip", SyntaxType::string()),
+        ],
+        SyntaxType::color(),
+    ))
+}
+
 fn pine_input<'a>(
     context: &mut dyn Ctx<'a>,
     param: Vec<Option<PineRef<'a>>>,
     func_type: FunctionType<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    if func_type.arg_names().len() == 4 {
+    if func_type == gen_bool_type() {
         input_for_bool(context, param)
-    } else if func_type.arg_names().len() == 8 {
-        if func_type == gen_int_type() {
-            input_for_int(context, param)
-        } else if func_type == gen_float_type() {
-            input_for_float(context, param)
-        } else {
-            unreachable!();
-        }
-    } else if func_type.arg_names().len() == 5 {
+    } else if func_type == gen_int_type() {
+        input_for_int(context, param)
+    } else if func_type == gen_float_type() {
+        input_for_float(context, param)
+    } else if func_type == gen_string_type() {
         input_for_string(context, param)
-    } else if func_type.arg_names().len() == 3 {
+    } else if func_type == gen_source_type() {
         input_for_source(context, param)
+    } else if func_type == gen_color_type() {
+        input_for_color(context, param)
     } else {
         unreachable!();
     }
@@ -433,13 +652,15 @@ impl<'a> PineClass<'a> for InputProps {
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
             "bool" => Ok(PineRef::new_rc(String::from(BOOL_TYPE_STR))),
+            "color" => Ok(PineRef::new_rc(String::from(COLOR_TYPE_STR))),
             "float" => Ok(PineRef::new_rc(String::from(FLOAT_TYPE_STR))),
             "integer" => Ok(PineRef::new_rc(String::from(INT_TYPE_STR))),
+            "price" => Ok(PineRef::new_rc(String::from(PRICE_TYPE_STR))),
             "resolution" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
             "session" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
             "source" => Ok(PineRef::new_rc(String::from(SOURCE_TYPE_STR))),
             "string" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
-            "symbol" => Ok(PineRef::new_rc(String::from(STRING_TYPE_STR))),
+            "symbol" => Ok(PineRef::new_rc(String::from(SYMBOL_TYPE_STR))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
                 vec![String::from(name), String::from("input")],
@@ -459,16 +680,19 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         Callable::new(None, Some(Box::new(InputCall::new())))
     }));
     /*
-        input(defval, title, type, confirm) → input bool
-        input(defval, title, type, minval, maxval, confirm, step, options) → input integer
-        input(defval, title, type, minval, maxval, confirm, step, options) → input float
-        input(defval, title, type, confirm, options) → input string
-        input(defval, title, type) → series[float]
+        input(defval, title, type, confirm, inline, group, tooltip) → input bool
+        input(defval, title, type, minval, maxval, confirm, step, options, inline, group, tooltip) → input integer
+        input(defval, title, type, minval, maxval, confirm, step, options, inline, group, tooltip) → input float
+        input(defval, title, type, confirm, options, inline, group, tooltip) → input string
+        input(defval, title, type, confirm, inline, group, tooltip) → series[float]
+        input(defval, title, type, confirm, inline, group, tooltip) → input color
     */
     let mut obj_type = BTreeMap::new();
     obj_type.insert("bool", SyntaxType::string());
+    obj_type.insert("color", SyntaxType::string());
     obj_type.insert("float", SyntaxType::string());
     obj_type.insert("integer", SyntaxType::string());
+    obj_type.insert("price", SyntaxType::string());
     obj_type.insert("resolution", SyntaxType::string());
     obj_type.insert("session", SyntaxType::string());
     obj_type.insert("source", SyntaxType::string());
@@ -482,6 +706,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             gen_bool_type(),
             gen_string_type(),
             gen_source_type(),
+            gen_color_type(),
         ])),
     );
     VarResult::new(value, syntax_type, VAR_NAME)
@@ -491,6 +716,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
 mod tests {
     use super::*;
     use crate::ast::stat_expr_types::VarIndex;
+    use crate::ast::syntax_type::TypeQualifier;
     use crate::runtime::{AnySeries, NoneCallback, VarOperate};
     use crate::types::PineRef;
     use crate::{LibInfo, PineParser, PineRunner};
@@ -566,6 +792,7 @@ mod tests {
                 title: Some(String::from("title")),
                 input_type: String::from("bool"),
                 confirm: Some(false),
+                meta: InputMeta::default(),
             })]
         )
     }
@@ -592,7 +819,7 @@ mod tests {
             Some(PineRef::new_box(Some(1)))
         );
 
-        runner.change_inputs(vec![Some(InputVal::Int(4))]);
+        runner.change_inputs(vec![Some(InputVal::Int(3))]);
         runner
             .run(
                 &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
@@ -601,7 +828,7 @@ mod tests {
             .unwrap();
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new_box(Some(4)))
+            Some(PineRef::new_box(Some(3)))
         );
         assert_eq!(
             runner.get_io_info().get_inputs(),
@@ -613,7 +840,8 @@ mod tests {
                 minval: Some(1),
                 maxval: Some(10),
                 step: Some(1),
-                options: Some(vec![1, 2, 3])
+                options: Some(vec![1, 2, 3]),
+                meta: InputMeta::default(),
             })]
         )
     }
@@ -647,7 +875,7 @@ mod tests {
             vec![declare_var()],
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
-        let src = "m = input(1.5, 'hello', 'float', 1, 10, true, 1, [1, 2, 3])";
+        let src = "m = input(1.5, 'hello', 'float', 1, 10, true, 1, [1, 1.5, 2])";
 
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
@@ -670,7 +898,7 @@ mod tests {
             vec![declare_var()],
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
-        let src = "m = input('defval', 'hello', 'string', options=['RMA', 'SMA', 'EMA'])";
+        let src = "m = input('SMA', 'hello', 'string', options=['RMA', 'SMA', 'EMA'])";
 
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
@@ -683,13 +911,13 @@ mod tests {
             .unwrap();
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new_rc(String::from("defval")))
+            Some(PineRef::new_rc(String::from("SMA")))
         );
 
         assert_eq!(
             runner.get_io_info().get_inputs(),
             &vec![InputInfo::String(StringInputInfo {
-                defval: Some(String::from("defval")),
+                defval: Some(String::from("SMA")),
                 title: Some(String::from("hello")),
                 input_type: (String::from("string")),
                 confirm: None,
@@ -698,10 +926,132 @@ mod tests {
                     String::from("SMA"),
                     String::from("EMA")
                 ]),
+                meta: InputMeta::default(),
             })]
         );
     }
 
+    #[test]
+    fn input_options_test() {
+        use crate::ast::error::PineErrorKind;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let get_errors = |src| match PineParser::new(src, &lib_info).parse_blk() {
+            Ok(_) => vec![],
+            Err(errs) => errs.into_iter().map(|e| e.code).collect(),
+        };
+        assert_eq!(get_errors("m = input(2, options=[1, 2.0])"), vec![]);
+        assert_eq!(
+            get_errors("m = input(5, 'len', 'int', 1, 10, true, 1, [1, 2])"),
+            vec![PineErrorKind::InputDefvalNotInOptions]
+        );
+        assert_eq!(
+            get_errors("m = input('a', 'ma', options=['b', 'c'])"),
+            vec![PineErrorKind::InputDefvalNotInOptions]
+        );
+        // The options input is const, so it can be passed to the const parameter.
+        assert_eq!(
+            get_errors("f(const int n) => n * 2\nm = f(input(2, options=[1, 2]))"),
+            vec![]
+        );
+        assert_eq!(
+            get_errors("f(const int n) => n * 2\nm = f(input(2))"),
+            vec![PineErrorKind::ArgQualifierNotMatch {
+                param: String::from("n"),
+                qualifier: TypeQualifier::Const,
+                found: TypeQualifier::Input,
+            }]
+        );
+
+        let src = "m = input('b', options=['b', 'c'])";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+        runner.change_inputs(vec![Some(InputVal::String(String::from("c")))]);
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(String::from("c")))
+        );
+
+        // The value given by the host must be one of the options.
+        runner.change_inputs(vec![Some(InputVal::String(String::from("d")))]);
+        assert!(runner.run(&data, None).is_err());
+    }
+
+    #[test]
+    fn input_qualifier_test() {
+        use crate::ast::error::PineErrorKind;
+        use crate::libs::{hline, plot};
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var(), hline::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let get_errors = |src| match PineParser::new(src, &lib_info).parse_blk() {
+            Ok(_) => vec![],
+            Err(errs) => errs.into_iter().map(|e| e.code).collect(),
+        };
+        let qualifier_err = |param: &str, qualifier, found| PineErrorKind::ArgQualifierNotMatch {
+            param: String::from(param),
+            qualifier,
+            found,
+        };
+
+        // The literals and the variables of them are const.
+        assert_eq!(
+            get_errors("t = 'a' + 'b'\nplot(close, title=t)\nhline(-1, 'h')"),
+            vec![]
+        );
+        assert_eq!(
+            get_errors("t = input('a')\nplot(close, title=t)"),
+            vec![qualifier_err(
+                "title",
+                TypeQualifier::Const,
+                TypeQualifier::Input
+            )]
+        );
+        assert_eq!(
+            get_errors("len = input(2)\nm = input(len)"),
+            vec![qualifier_err(
+                "defval",
+                TypeQualifier::Const,
+                TypeQualifier::Input
+            )]
+        );
+
+        // The price of the hline accepts the input but not the series.
+        assert_eq!(get_errors("p = input(1.0)\nhline(p * 2)"), vec![]);
+        assert_eq!(
+            get_errors("f(x) => x\nhline(f(1.0))"),
+            vec![qualifier_err(
+                "price",
+                TypeQualifier::Input,
+                TypeQualifier::Simple
+            )]
+        );
+
+        // The series parameter like the source of the input accepts any argument.
+        assert_eq!(get_errors("m = input(close, type=input.source)"), vec![]);
+
+        // The annotated parameters keep the qualifiers in the function body.
+        assert_eq!(
+            get_errors("f(input float x) => hline(x)\nf(input(1.0))"),
+            vec![]
+        );
+        assert_eq!(
+            get_errors("f(simple float x) => hline(x)\nf(1.0)"),
+            vec![qualifier_err(
+                "price",
+                TypeQualifier::Input,
+                TypeQualifier::Simple
+            )]
+        );
+    }
+
     #[test]
     fn source_input_test<'a>() {
         let lib_info = LibInfo::new(
@@ -743,6 +1093,134 @@ mod tests {
         );
     }
 
+    #[test]
+    fn symbol_input_test() {
+        use crate::runtime::SymbolInfo;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = input('NASDAQ:AAPL', 'Symbol', input.symbol, group='g')\n\
+        n = input(na, type=input.symbol)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let syminfo = Rc::new(SymbolInfo {
+            symbol_type: String::from("stock"),
+            timezone: String::from("America/New_York"),
+            ticker: String::from("NYSE:IBM"),
+            session: String::from("regular"),
+            trade_start: String::from(""),
+            trade_end: String::from(""),
+            root: None,
+            currency: String::from("USD"),
+            description: String::from(""),
+            mintick: 0.01,
+        });
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        runner.run(&data, Some(syminfo.clone())).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(String::from("NASDAQ:AAPL")))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(String::from("NYSE:IBM")))
+        );
+        assert_eq!(
+            runner.get_io_info().get_inputs(),
+            &vec![
+                InputInfo::Symbol(SymbolInputInfo {
+                    defval: Some(String::from("NASDAQ:AAPL")),
+                    title: Some(String::from("Symbol")),
+                    input_type: String::from(SYMBOL_TYPE_STR),
+                    confirm: None,
+                    exchange: Some(String::from("NASDAQ")),
+                    meta: InputMeta {
+                        group: Some(String::from("g")),
+                        ..InputMeta::default()
+                    },
+                }),
+                InputInfo::Symbol(SymbolInputInfo {
+                    defval: Some(String::from("NYSE:IBM")),
+                    title: None,
+                    input_type: String::from(SYMBOL_TYPE_STR),
+                    confirm: None,
+                    exchange: Some(String::from("NYSE")),
+                    meta: InputMeta::default(),
+                }),
+            ]
+        );
+
+        runner.change_inputs(vec![
+            Some(InputVal::Symbol(String::from("BINANCE:BTCUSDT"))),
+            None,
+        ]);
+        runner.run(&data, Some(syminfo)).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(String::from("BINANCE:BTCUSDT")))
+        );
+    }
+
+    #[test]
+    fn color_input_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = input(#FF000080, 'Color', input.color, group='g')\n\
+        n = input(defval=#00FF00, type=input.color)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Color::new("#FF000080")))
+        );
+        assert_eq!(
+            runner.get_io_info().get_inputs(),
+            &vec![
+                InputInfo::Color(ColorInputInfo {
+                    defval: Some(String::from("#FF0000")),
+                    alpha: Some(128),
+                    title: Some(String::from("Color")),
+                    input_type: String::from(COLOR_TYPE_STR),
+                    confirm: None,
+                    meta: InputMeta {
+                        group: Some(String::from("g")),
+                        ..InputMeta::default()
+                    },
+                }),
+                InputInfo::Color(ColorInputInfo {
+                    defval: Some(String::from("#00FF00")),
+                    alpha: Some(255),
+                    title: None,
+                    input_type: String::from(COLOR_TYPE_STR),
+                    confirm: None,
+                    meta: InputMeta::default(),
+                }),
+            ]
+        );
+
+        // The invalid color falls back to the default color.
+        runner.change_inputs(vec![
+            Some(InputVal::Color(String::from("#0000FF40"))),
+            Some(InputVal::Color(String::from("blue"))),
+        ]);
+        runner.run(&data, None).unwrap();
+        let m = runner.get_context().move_var(VarIndex::new(0, 0)).unwrap();
+        assert_eq!(m.get_type(), (DataType::Color, SecondType::Simple));
+        assert_eq!(m, PineRef::new_box(Color::new("#0000FF40")));
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_box(Color::new("#00FF00")))
+        );
+    }
+
     #[test]
     fn input_fields_test() {
         use crate::types::Tuple;
@@ -753,7 +1231,7 @@ mod tests {
         );
         let src = r"m = [
             input.bool, input.float, input.integer, input.resolution, 
-            input.session, input.source, input.string, input.symbol
+            input.session, input.source, input.string, input.symbol, input.color
         ]";
 
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
@@ -777,7 +1255,8 @@ mod tests {
                 PineRef::new_rc(String::from(STRING_TYPE_STR)),
                 PineRef::new_rc(String::from(SOURCE_TYPE_STR)),
                 PineRef::new_rc(String::from(STRING_TYPE_STR)),
-                PineRef::new_rc(String::from(STRING_TYPE_STR)),
+                PineRef::new_rc(String::from(SYMBOL_TYPE_STR)),
+                PineRef::new_rc(String::from(COLOR_TYPE_STR)),
             ])
         );
     }
@@ -804,4 +1283,83 @@ mod tests {
             Some(PineRef::new_box(Some(2f64)))
         );
     }
+
+    #[test]
+    fn input_meta_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+p = input(1.5, "Entry", input.price, confirm=true, inline="levels", group="Orders")
+s = input(close, "Source", input.source, tooltip="Price source")
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner.change_inputs(vec![Some(InputVal::Float(2f64))]);
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(2f64)))
+        );
+
+        let inputs = runner.get_io_info().get_inputs();
+        assert_eq!(
+            inputs,
+            &vec![
+                InputInfo::Float(FloatInputInfo {
+                    defval: Some(1.5f64),
+                    title: Some(String::from("Entry")),
+                    input_type: String::from("price"),
+                    minval: None,
+                    maxval: None,
+                    confirm: Some(true),
+                    step: None,
+                    options: None,
+                    meta: InputMeta {
+                        inline: Some(String::from("levels")),
+                        group: Some(String::from("Orders")),
+                        tooltip: None,
+                    },
+                }),
+                InputInfo::Source(SourceInputInfo {
+                    defval: Some(String::from("close")),
+                    title: Some(String::from("Source")),
+                    input_type: String::from("source"),
+                    confirm: None,
+                    meta: InputMeta {
+                        inline: None,
+                        group: None,
+                        tooltip: Some(String::from("Price source")),
+                    },
+                })
+            ]
+        );
+
+        // The metadata fields are flattened into the input descriptor.
+        let json = serde_json::to_value(&inputs[0]).unwrap();
+        assert_eq!(json["type"], "Float");
+        assert_eq!(json["inline"], "levels");
+        assert_eq!(json["group"], "Orders");
+        let source: InputInfo = serde_json::from_str(
+            r#"{"type": "Source", "defval": "close", "title": null, "input_type": "source"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            source,
+            InputInfo::Source(SourceInputInfo {
+                defval: Some(String::from("close")),
+                title: None,
+                input_type: String::from("source"),
+                confirm: None,
+                meta: InputMeta::default(),
+            })
+        );
+    }
 }
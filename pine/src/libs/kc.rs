@@ -1,4 +1,4 @@
-use super::ema::ema_func;
+use super::ema::{ema_func, MaState};
 use super::sma::{declare_ma_var, wma_func};
 use super::tr::tr_func;
 use super::VarResult;
@@ -37,8 +37,8 @@ pub struct KcVal {
     close_index: VarIndex,
     low_index: VarIndex,
     high_index: VarIndex,
-    prev_basis: Float,
-    prev_range_ema: Float,
+    basis: MaState,
+    range_ema: MaState,
     val_gen: *mut (),
 }
 
@@ -48,8 +48,8 @@ impl KcVal {
             close_index: VarIndex::new(0, 0),
             low_index: VarIndex::new(0, 0),
             high_index: VarIndex::new(0, 0),
-            prev_basis: None,
-            prev_range_ema: None,
+            basis: MaState::new(),
+            range_ema: MaState::new(),
             val_gen: val_gen,
         }
     }
@@ -77,7 +77,8 @@ impl KcVal {
         let multi = require_param("multi", pine_ref_to_f64(multi))?;
         let use_true_range = pine_ref_to_bool(use_true_range).unwrap_or(true);
 
-        let basis = ema_func(sval, length, self.prev_basis)?;
+        let warmup = downcast_ctx(_ctx).get_ema_warmup();
+        let basis = self.basis.next(warmup, ema_func, sval, length)?;
 
         let high = pine_ref_to_f64(_ctx.get_var(self.high_index).clone());
         let low = pine_ref_to_f64(_ctx.get_var(self.low_index).clone());
@@ -90,10 +91,7 @@ impl KcVal {
             high.minus(low)
         };
 
-        let range_ema = ema_func(range, length, self.prev_range_ema)?;
-
-        self.prev_basis = basis;
-        self.prev_range_ema = range_ema;
+        let range_ema = self.range_ema.next(warmup, ema_func, range, length)?;
 
         let multi_ema = range_ema.mul(Some(multi));
         Ok((basis, basis.add(multi_ema), basis.minus(multi_ema)))
@@ -111,6 +109,12 @@ impl<'a> SeriesCall<'a> for KcVal {
         Ok(func(self.process_kc(_ctx, param, _func_type)?))
     }
 
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.basis.back();
+        self.range_ema.back();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -172,15 +176,30 @@ mod tests {
                 &vec![
                     (
                         "close",
-                        AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+                        AnySeries::from_float_vec(vec![
+                            Some(10f64),
+                            Some(20f64),
+                            Some(30f64),
+                            Some(40f64),
+                        ]),
                     ),
                     (
                         "high",
-                        AnySeries::from_float_vec(vec![Some(6f64), Some(20f64)]),
+                        AnySeries::from_float_vec(vec![
+                            Some(6f64),
+                            Some(20f64),
+                            Some(30f64),
+                            Some(40f64),
+                        ]),
                     ),
                     (
                         "low",
-                        AnySeries::from_float_vec(vec![Some(6f64), Some(20f64)]),
+                        AnySeries::from_float_vec(vec![
+                            Some(6f64),
+                            Some(20f64),
+                            Some(30f64),
+                            Some(40f64),
+                        ]),
                     ),
                 ],
                 None,
@@ -190,8 +209,10 @@ mod tests {
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
             Some(PineRef::new(Series::from_vec(vec![
-                Some(5f64),
-                Some(12.5f64)
+                None,
+                None,
+                Some(20f64),
+                Some(30f64)
             ])))
         );
     }
@@ -67,7 +67,7 @@ mod tests {
                 ("low", SyntaxType::float_series()),
             ],
         );
-        let src = "m = kcw(close, 3, 1)\n";
+        let src = "m = kcw(close, 2, 1)\n";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
 
@@ -76,7 +76,7 @@ mod tests {
                 &vec![
                     (
                         "close",
-                        AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+                        AnySeries::from_float_vec(vec![Some(10f64), Some(30f64)]),
                     ),
                     (
                         "high",
@@ -93,10 +93,7 @@ mod tests {
 
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new(Series::from_vec(vec![
-                Some(0f64),
-                Some(0.8f64)
-            ])))
+            Some(PineRef::new(Series::from_vec(vec![None, Some(0.5f64)])))
         );
     }
 }
@@ -1,3 +1,5 @@
+use super::font::*;
+use super::text::*;
 use super::xloc::*;
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
@@ -8,6 +10,7 @@ use crate::helper::{
     pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::drawing::DrawingKind;
 use crate::runtime::output::{OutputData, OutputInfo, PlotInfo, StrOptionsData};
 use crate::types::{
     downcast_pf, Bool, Callable, CallableFactory, CallableObject, Category, Color, ComplexType,
@@ -192,9 +195,9 @@ pub enum TextAlignEnum {
 impl TextAlignEnum {
     fn from_str(s: &str) -> Result<TextAlignEnum, RuntimeErr> {
         match s {
-            "left" => Ok(TextAlignEnum::Left),
-            "center" => Ok(TextAlignEnum::Center),
-            "right" => Ok(TextAlignEnum::Right),
+            TEXT_ALIGN_LEFT => Ok(TextAlignEnum::Left),
+            TEXT_ALIGN_CENTER => Ok(TextAlignEnum::Center),
+            TEXT_ALIGN_RIGHT => Ok(TextAlignEnum::Right),
             _ => Err(RuntimeErr::InvalidParameters(str_replace(
                 INVALID_VALS,
                 vec![String::from("textalign")],
@@ -210,6 +213,32 @@ impl TextAlignEnum {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum FontFamilyEnum {
+    Default = 0,
+    Monospace = 1,
+}
+
+impl FontFamilyEnum {
+    fn from_str(s: &str) -> Result<FontFamilyEnum, RuntimeErr> {
+        match s {
+            FONT_FAMILY_DEFAULT => Ok(FontFamilyEnum::Default),
+            FONT_FAMILY_MONOSPACE => Ok(FontFamilyEnum::Monospace),
+            _ => Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("text_font_family")],
+            ))),
+        }
+    }
+
+    fn from_pf<'a>(s: Option<PineRef<'a>>) -> Result<FontFamilyEnum, RuntimeErr> {
+        match pine_ref_to_string(s) {
+            None => Ok(FontFamilyEnum::Default),
+            Some(s) => FontFamilyEnum::from_str(&s[..]),
+        }
+    }
+}
+
 fn is_label_na<'a>(val: Option<PineRef<'a>>) -> bool {
     if val.is_none() {
         return true;
@@ -236,7 +265,7 @@ fn pine_ref_to_label<'a>(val: Option<PineRef<'a>>) -> PerLabelItem {
 // The label definition that represent every label object.
 #[derive(Debug, Clone, PartialEq)]
 pub struct PerLabel {
-    // x, y, text, xloc, yloc, color, style, textcolor, size, textalign
+    // x, y, text, xloc, yloc, color, style, textcolor, size, textalign, text_font_family
     x: Int,
     y: Float,
     text: Option<String>,
@@ -248,6 +277,7 @@ pub struct PerLabel {
     textcolor: Option<String>,
     size: i32,
     textalign: i32,
+    text_font_family: i32,
 }
 
 impl PerLabel {
@@ -263,6 +293,7 @@ impl PerLabel {
             textcolor: None,
             size: 0,
             textalign: 0,
+            text_font_family: 0,
         }
     }
 }
@@ -324,7 +355,7 @@ impl<'a> LineFromNaVal<'a> {
 impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
     fn step(
         &mut self,
-        _context: &mut dyn Ctx<'a>,
+        context: &mut dyn Ctx<'a>,
         mut p: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
@@ -337,7 +368,21 @@ impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
                     .into_pf()),
             }
         } else {
-            move_tuplet!((x, y, text, xloc, yloc, color, style, textcolor, size, textalign) = p);
+            move_tuplet!(
+                (
+                    x,
+                    y,
+                    text,
+                    xloc,
+                    yloc,
+                    color,
+                    style,
+                    textcolor,
+                    size,
+                    textalign,
+                    text_font_family
+                ) = p
+            );
             let label = PerLabel {
                 x: pine_ref_to_i64(x),
                 y: pine_ref_to_f64(y),
@@ -349,8 +394,11 @@ impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
                 textcolor: pine_ref_to_color(textcolor),
                 size: SizeEnum::from_pf(size)? as i32,
                 textalign: TextAlignEnum::from_pf(textalign)? as i32,
+                text_font_family: FontFamilyEnum::from_pf(text_font_family)? as i32,
             };
-            self.labels.update(Rc::new(RefCell::new(Some(label))));
+            let item = Rc::new(RefCell::new(Some(label)));
+            downcast_ctx(context).add_drawing(DrawingKind::Label, item.clone());
+            self.labels.update(item);
             Ok(RefData::clone(&self.labels).into_pf())
         }
     }
@@ -491,6 +539,17 @@ fn set_textalign_func<'a>(
     })
 }
 
+fn set_text_font_family_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    set_val_func(_context, param, |l, v| {
+        l.text_font_family = FontFamilyEnum::from_pf(v)? as i32;
+        Ok(())
+    })
+}
+
 fn set_text_func<'a>(
     _context: &mut dyn Ctx<'a>,
     param: Vec<Option<PineRef<'a>>>,
@@ -582,6 +641,10 @@ impl<'a> PineClass<'a> for PlotProps {
             "set_text" => Ok(PineRef::new(Callable::new(Some(set_text_func), None))),
             "set_textalign" => Ok(PineRef::new(Callable::new(Some(set_textalign_func), None))),
             "set_textcolor" => Ok(PineRef::new(Callable::new(Some(set_textcolor_func), None))),
+            "set_text_font_family" => Ok(PineRef::new(Callable::new(
+                Some(set_text_font_family_func),
+                None,
+            ))),
             "set_xloc" => Ok(PineRef::new(Callable::new(Some(set_xloc_func), None))),
             "set_yloc" => Ok(PineRef::new(Callable::new(Some(set_yloc_func), None))),
 
@@ -634,7 +697,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     );
     obj_type.insert(
         "new",
-        // x, y, text, xloc, yloc, color, style, textcolor, size, textalign
+        // x, y, text, xloc, yloc, color, style, textcolor, size, textalign, text_font_family
         SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
             vec![
                 ("x", SyntaxType::int_series()),
@@ -647,6 +710,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 ("textcolor", SyntaxType::color_series()),
                 ("size", SyntaxType::string_series()),
                 ("textalign", SyntaxType::string_series()),
+                ("text_font_family", SyntaxType::string_series()),
             ],
             SyntaxType::ObjectClass("label"),
         ))]))),
@@ -764,6 +828,16 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             SyntaxType::Void,
         ))]))),
     );
+    obj_type.insert(
+        "set_text_font_family",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("id", SyntaxType::ObjectClass("label")),
+                ("text_font_family", SyntaxType::string_series()),
+            ],
+            SyntaxType::Void,
+        ))]))),
+    );
     obj_type.insert(
         "set_xloc",
         SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
@@ -1107,4 +1181,55 @@ mod tests {
             .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn label_text_font_test() {
+        use crate::ast::stat_expr_types::VarIndex;
+        use crate::libs::{font, text};
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), text::declare_var(), font::declare_var()],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        );
+        let src = r#"
+        x = label.new(1, 2, textalign=text.align_right, text_font_family=font.family_monospace)
+        y = label.new(1, 2)
+        label.set_text_font_family(y, font.family_monospace)
+        label.set_textalign(y, text.align_center)
+        "#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.runl(&vec![], 1, None).unwrap();
+
+        let mut label = PerLabel::new();
+        label.x = Some(1i64);
+        label.y = Some(2f64);
+        label.textalign = TextAlignEnum::Right as i32;
+        label.text_font_family = FontFamilyEnum::Monospace as i32;
+        let result = runner.get_context().move_var(VarIndex::new(0, 0)).unwrap();
+        assert_eq!(
+            Series::implicity_from(result).unwrap(),
+            RefData::new(Series::from_vec(vec![Rc::new(RefCell::new(Some(
+                label.clone()
+            )))]))
+        );
+
+        label.textalign = TextAlignEnum::Center as i32;
+        let result = runner.get_context().move_var(VarIndex::new(1, 0)).unwrap();
+        assert_eq!(
+            Series::implicity_from(result).unwrap(),
+            RefData::new(Series::from_vec(vec![Rc::new(RefCell::new(Some(label)))]))
+        );
+
+        let src = "label.new(1, 2, text_font_family='serif')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.runl(&vec![], 1, None).err().map(|e| e.code),
+            Some(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("text_font_family")]
+            )))
+        );
+    }
 }
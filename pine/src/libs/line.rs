@@ -8,6 +8,7 @@ use crate::helper::{
     pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::drawing::DrawingKind;
 use crate::runtime::output::{OutputData, OutputInfo, PlotInfo, StrOptionsData};
 use crate::types::{
     downcast_pf, Bool, Callable, CallableFactory, CallableObject, Category, Color, ComplexType,
@@ -78,6 +79,14 @@ impl StyleEnum {
             ))),
         }
     }
+
+    // The style is solid if it is omitted.
+    pub fn from_pf<'a>(s: Option<PineRef<'a>>) -> Result<StyleEnum, RuntimeErr> {
+        match pine_ref_to_string(s) {
+            None => Ok(StyleEnum::Solid),
+            Some(s) => StyleEnum::from_str(&s[..]),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -97,6 +106,14 @@ impl XlocEnum {
             ))),
         }
     }
+
+    // The x coordinates are bar indexes if the xloc is omitted.
+    pub fn from_pf<'a>(s: Option<PineRef<'a>>) -> Result<XlocEnum, RuntimeErr> {
+        match pine_ref_to_string(s) {
+            None => Ok(XlocEnum::BarIndex),
+            Some(s) => XlocEnum::from_str(&s[..]),
+        }
+    }
 }
 
 fn is_line_na<'a>(val: Option<PineRef<'a>>) -> bool {
@@ -223,7 +240,7 @@ impl<'a> LineFromNaVal<'a> {
 impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
     fn step(
         &mut self,
-        _context: &mut dyn Ctx<'a>,
+        context: &mut dyn Ctx<'a>,
         mut p: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
@@ -260,7 +277,9 @@ impl<'a> SeriesCall<'a> for LineFromNaVal<'a> {
                 style: 0,
                 width: None,
             };
-            self.lines.update(Rc::new(RefCell::new(Some(line))));
+            let item = Rc::new(RefCell::new(Some(line)));
+            downcast_ctx(context).add_drawing(DrawingKind::Line, item.clone());
+            self.lines.update(item);
             Ok(RefData::clone(&self.lines).into_pf())
         }
     }
@@ -1053,4 +1072,53 @@ mod tests {
             .collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    fn max_lines_count_test() {
+        use crate::libs::study;
+        use crate::runtime::drawing::DrawingStats;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), study::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "study('hello', max_lines_count=2)\nx = line.new(1, close, 3, 4)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+        )];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_drawing_stats(),
+            DrawingStats {
+                lines: 2,
+                labels: 0,
+                boxes: 0,
+                polylines: 0
+            }
+        );
+
+        // The line of the last bar is replaced when the bar updates.
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(5f64)]),
+            )])
+            .unwrap();
+        assert_eq!(runner.get_drawing_stats().lines, 2);
+
+        let src = "x = line.new(1, close, 3, 4)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64); 60]))],
+                None,
+            )
+            .unwrap();
+        assert_eq!(runner.get_drawing_stats().lines, 50);
+    }
 }
@@ -0,0 +1,114 @@
+use super::VarResult;
+use crate::ast::syntax_type::SyntaxType;
+use crate::helper::err_msgs::*;
+use crate::helper::{pine_ref_to_string, str_replace};
+use crate::runtime::context::Ctx;
+use crate::types::{Object, PineClass, PineRef, RuntimeErr};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+pub const LOCATION_ABOVEBAR: &'static str = "abovebar";
+pub const LOCATION_BELOWBAR: &'static str = "belowbar";
+pub const LOCATION_TOP: &'static str = "top";
+pub const LOCATION_BOTTOM: &'static str = "bottom";
+pub const LOCATION_ABSOLUTE: &'static str = "absolute";
+
+const LOCATIONS: [&'static str; 5] = [
+    LOCATION_ABOVEBAR,
+    LOCATION_BELOWBAR,
+    LOCATION_TOP,
+    LOCATION_BOTTOM,
+    LOCATION_ABSOLUTE,
+];
+
+// Get the location argument of plotshape and plotchar, the value must be one of the location constants.
+pub fn pine_ref_to_location<'a>(val: Option<PineRef<'a>>) -> Result<Option<String>, RuntimeErr> {
+    match pine_ref_to_string(val) {
+        Some(v) if !LOCATIONS.contains(&v.as_str()) => Err(RuntimeErr::InvalidParameters(
+            str_replace(INVALID_VALS, vec![String::from("location")]),
+        )),
+        v => Ok(v),
+    }
+}
+
+struct LocationProps;
+
+impl<'a> PineClass<'a> for LocationProps {
+    fn custom_type(&self) -> &str {
+        "location"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "abovebar" => Ok(PineRef::new(String::from(LOCATION_ABOVEBAR))),
+            "belowbar" => Ok(PineRef::new(String::from(LOCATION_BELOWBAR))),
+            "top" => Ok(PineRef::new(String::from(LOCATION_TOP))),
+            "bottom" => Ok(PineRef::new(String::from(LOCATION_BOTTOM))),
+            "absolute" => Ok(PineRef::new(String::from(LOCATION_ABSOLUTE))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("location")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(LocationProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "location";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(LocationProps)));
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("abovebar", SyntaxType::string());
+    obj_type.insert("belowbar", SyntaxType::string());
+    obj_type.insert("top", SyntaxType::string());
+    obj_type.insert("bottom", SyntaxType::string());
+    obj_type.insert("absolute", SyntaxType::string());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::{downcast_pf, Tuple};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn location_fields_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"m = [
+            location.abovebar, location.belowbar, location.top, location.bottom, location.absolute
+        ]";
+
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let tuple_res =
+            downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(0, 0)).unwrap());
+        assert_eq!(
+            tuple_res.unwrap().into_inner().0,
+            vec![
+                PineRef::new(String::from("abovebar")),
+                PineRef::new(String::from("belowbar")),
+                PineRef::new(String::from("top")),
+                PineRef::new(String::from("bottom")),
+                PineRef::new(String::from("absolute")),
+            ]
+        );
+    }
+}
@@ -28,7 +28,7 @@ pub fn get_min_val<'a>(source: &Option<RefData<Series<Float>>>, length: i64) ->
 }
 
 pub fn declare_var<'a>() -> VarResult<'a> {
-    declare_s_var("lowest", "low", get_min_val)
+    declare_s_var("lowest", "low", false)
 }
 
 #[cfg(test)]
@@ -1,45 +1,18 @@
-use super::ema::rma_func;
 use super::highestbars::declare_s_var;
 use super::VarResult;
-use crate::ast::stat_expr_types::VarIndex;
-use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
-use crate::helper::{
-    float_abs, float_max, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_f64_series,
-    pine_ref_to_i64, require_param, series_index,
-};
-use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::InputSrc;
-use crate::types::{
-    downcast_pf_ref, int2float, Arithmetic, Callable, CallableCreator, CallableFactory, Float, Int,
-    PineRef, RefData, RuntimeErr, Series, SeriesCall,
-};
-use std::mem;
-use std::rc::Rc;
-
-fn get_min_val<'a>(source: &Option<RefData<Series<Float>>>, length: i64) -> Int {
-    let mut min_val = Some(std::f64::MAX);
-    let mut min_i = Some(0);
-    for i in 0..length {
-        let cur_val = series_index(source, i as usize);
-        if cur_val < min_val {
-            min_i = Some(-i);
-            min_val = cur_val;
-        }
-    }
-    min_i
-}
 
 pub fn declare_var<'a>() -> VarResult<'a> {
-    declare_s_var("lowestbars", "low", get_min_val)
+    declare_s_var("lowestbars", "low", false)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
     use crate::ast::syntax_type::SyntaxType;
     use crate::runtime::VarOperate;
     use crate::runtime::{AnySeries, NoneCallback};
-    use crate::types::Series;
+    use crate::types::{PineRef, Series};
     use crate::{LibInfo, PineParser, PineRunner};
 
     #[test]
@@ -73,7 +46,7 @@ mod tests {
         assert_eq!(
             runner.get_context().get_var(VarIndex::new(0, 0)),
             &Some(PineRef::new(Series::from_vec(vec![
-                Some(-1i64),
+                Some(0i64),
                 Some(-1i64),
                 Some(0i64)
             ])))
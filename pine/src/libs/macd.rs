@@ -1,4 +1,4 @@
-use super::ema::series_ema;
+use super::ema::{ema_func, MaState};
 use super::sma::{declare_ma_var, wma_func};
 use super::tr::tr_func;
 use super::VarResult;
@@ -32,24 +32,24 @@ fn val_generator<'a>(vals: (Float, Float, Float)) -> PineRef<'a> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct KcVal<'a> {
-    ema1s: Series<'a, Float>,
-    ema2s: Series<'a, Float>,
-    dems: Series<'a, Float>,
+pub struct KcVal {
+    ema1s: MaState,
+    ema2s: MaState,
+    dems: MaState,
 }
 
-impl<'a> KcVal<'a> {
-    pub fn new() -> KcVal<'a> {
+impl KcVal {
+    pub fn new() -> KcVal {
         KcVal {
-            ema1s: Series::new(),
-            ema2s: Series::new(),
-            dems: Series::new(),
+            ema1s: MaState::new(),
+            ema2s: MaState::new(),
+            dems: MaState::new(),
         }
     }
 
-    fn process_macd(
+    fn process_macd<'a>(
         &mut self,
-        _ctx: &mut dyn Ctx<'a>,
+        ctx: &mut dyn Ctx<'a>,
         mut param: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<(Float, Float, Float), RuntimeErr> {
@@ -60,22 +60,19 @@ impl<'a> KcVal<'a> {
         let slowlen = require_param("slowlen", pine_ref_to_i64(slowlen))?;
         let siglen = require_param("siglen", pine_ref_to_i64(siglen))?;
 
-        let ema1 = series_ema(close, fastlen, &mut self.ema1s)?;
-        let ema2 = series_ema(close, slowlen, &mut self.ema2s)?;
+        let warmup = downcast_ctx(ctx).get_ema_warmup();
+        let ema1 = self.ema1s.next(warmup, ema_func, close, fastlen)?;
+        let ema2 = self.ema2s.next(warmup, ema_func, close, slowlen)?;
         let dif = ema1.minus(ema2);
 
-        let dem = series_ema(dif, siglen, &mut self.dems)?;
+        let dem = self.dems.next(warmup, ema_func, dif, siglen)?;
         let osc = dif.minus(dem);
 
-        self.ema1s.commit();
-        self.ema2s.commit();
-        self.dems.commit();
-
         Ok((dif, dem, osc))
     }
 }
 
-impl<'a> SeriesCall<'a> for KcVal<'a> {
+impl<'a> SeriesCall<'a> for KcVal {
     fn step(
         &mut self,
         _ctx: &mut dyn Ctx<'a>,
@@ -85,6 +82,13 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(val_generator(self.process_macd(_ctx, param, _func_type)?))
     }
 
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.ema1s.back();
+        self.ema2s.back();
+        self.dems.back();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -0,0 +1,479 @@
+use super::abs::{float_abs, int_abs};
+use super::array::pine_ref_to_array;
+use super::cos::{
+    float_exp, float_log, float_log10, float_sign, float_sqrt, gen_int_math_type, gen_math_type,
+    int_math_callable, int_sign, math_callable,
+};
+use super::max::{gen_minmax_type, max_callable, min_callable};
+use super::pow::{gen_pow_type, pow_callable};
+use super::sma::{WindowKind, WindowVal};
+use super::syminfo::get_mintick;
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{
+    float_add, ge1_param_i64, move_element, pine_ref_to_f64, pine_ref_to_f64_series,
+    pine_ref_to_i64, require_param, round_to_mintick,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{
+    Callable, CallableFactory, Float, Object, ParamCollectCall, PineClass, PineRef, RuntimeErr,
+    Series, SeriesCall,
+};
+use std::collections::BTreeMap;
+use std::f64::consts;
+use std::rc::Rc;
+
+// The golden ratio and its reciprocal.
+const PHI: f64 = 1.618033988749895;
+const RPHI: f64 = 0.618033988749895;
+
+// The sum of the array elements. The `na` elements are skipped like the `sum` of the series.
+fn array_sum(items: Vec<Float>) -> Float {
+    items.into_iter().fold(Some(0f64), float_add)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SumCall {
+    window: WindowVal,
+}
+
+impl<'a> SeriesCall<'a> for SumCall {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        match func_type.get_type(0).unwrap() {
+            SyntaxType::List(SimpleSyntaxType::Float) => {
+                let id = move_element(&mut param, 0);
+                let items = pine_ref_to_array(id, pine_ref_to_f64, |v: Float| v);
+                Ok(PineRef::new_rc(Series::from(array_sum(items))))
+            }
+            _ => {
+                move_tuplet!((source, length) = param);
+                let source = require_param("source", pine_ref_to_f64_series(source))?;
+                let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+                let val = self.window.update(context, &source, length);
+                Ok(PineRef::new_rc(Series::from(val)))
+            }
+        }
+    }
+
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.window.invalidate();
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+fn round_to_mintick_func<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let mintick = get_mintick(context);
+    let x = pine_ref_to_f64(move_element(&mut param, 0));
+    let res = x.map(|v| round_to_mintick(v, mintick));
+    match func_type.get_type(0).unwrap() {
+        SyntaxType::Series(_) => Ok(PineRef::new_rc(Series::from(res))),
+        _ => Ok(PineRef::new(res)),
+    }
+}
+
+struct MathProps;
+
+impl<'a> PineClass<'a> for MathProps {
+    fn custom_type(&self) -> &str {
+        "math"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "abs" => Ok(PineRef::new(CallableFactory::new(|| {
+                int_math_callable(int_abs, float_abs)
+            }))),
+            "e" => Ok(PineRef::new(Some(consts::E))),
+            "exp" => Ok(PineRef::new(CallableFactory::new(|| {
+                math_callable(float_exp)
+            }))),
+            "log" => Ok(PineRef::new(CallableFactory::new(|| {
+                math_callable(float_log)
+            }))),
+            "log10" => Ok(PineRef::new(CallableFactory::new(|| {
+                math_callable(float_log10)
+            }))),
+            "phi" => Ok(PineRef::new(Some(PHI))),
+            "pi" => Ok(PineRef::new(Some(consts::PI))),
+            "pow" => Ok(PineRef::new(CallableFactory::new(pow_callable))),
+            "rphi" => Ok(PineRef::new(Some(RPHI))),
+            "sign" => Ok(PineRef::new(CallableFactory::new(|| {
+                int_math_callable(int_sign, float_sign)
+            }))),
+            "sqrt" => Ok(PineRef::new(CallableFactory::new(|| {
+                math_callable(float_sqrt)
+            }))),
+            "max" => Ok(PineRef::new(CallableFactory::new(max_callable))),
+            "min" => Ok(PineRef::new(CallableFactory::new(min_callable))),
+            "sum" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                        SumCall {
+                            window: WindowVal::new(WindowKind::Sum),
+                        },
+                    )))),
+                )
+            }))),
+            "round_to_mintick" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(round_to_mintick_func), None)
+            }))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("math")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(MathProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "math";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(MathProps)));
+
+    let mut obj_type = BTreeMap::new();
+    // The constants are simple floats since the const values are tracked as simple.
+    obj_type.insert("e", SyntaxType::float());
+    obj_type.insert("phi", SyntaxType::float());
+    obj_type.insert("pi", SyntaxType::float());
+    obj_type.insert("rphi", SyntaxType::float());
+    // math.abs(number) → T
+    // math.abs(number) → series[T]
+    obj_type.insert("abs", gen_int_math_type("number"));
+    // math.sign(number) → T
+    // math.sign(number) → series[T]
+    obj_type.insert("sign", gen_int_math_type("number"));
+    // math.exp(number) → float
+    // math.exp(number) → series[float]
+    obj_type.insert("exp", gen_math_type("number"));
+    obj_type.insert("log", gen_math_type("number"));
+    obj_type.insert("log10", gen_math_type("number"));
+    obj_type.insert("sqrt", gen_math_type("number"));
+    // math.pow(base, exponent) → float
+    // math.pow(base, exponent) → series[float]
+    obj_type.insert("pow", gen_pow_type());
+    // math.max(number0, number1, ...) → T
+    // math.max(number0, number1, ...) → series[T]
+    obj_type.insert("max", gen_minmax_type(["number0", "number1"]));
+    // math.min(number0, number1, ...) → T
+    // math.min(number0, number1, ...) → series[T]
+    obj_type.insert("min", gen_minmax_type(["number0", "number1"]));
+    // math.round_to_mintick(number) → float
+    // math.round_to_mintick(number) → series[float]
+    obj_type.insert(
+        "round_to_mintick",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            FunctionType::new((vec![("number", SyntaxType::float())], SyntaxType::float())),
+            FunctionType::new((
+                vec![("number", SyntaxType::float_series())],
+                SyntaxType::float_series(),
+            )),
+        ]))),
+    );
+    // math.sum(id) → series[float]
+    // math.sum(source, length) → series[float]
+    obj_type.insert(
+        "sum",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            FunctionType::new((
+                vec![("id", SyntaxType::List(SimpleSyntaxType::Float))],
+                SyntaxType::float_series(),
+            )),
+            FunctionType::new((
+                vec![
+                    ("source", SyntaxType::float_series()),
+                    ("length", SyntaxType::int()),
+                ],
+                SyntaxType::float_series(),
+            )),
+        ]))),
+    );
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::libs::array;
+    use crate::runtime::{AnySeries, NoneCallback, SymbolInfo};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn array_sum_test() {
+        assert_eq!(array_sum(vec![]), Some(0f64));
+        assert_eq!(array_sum(vec![Some(1f64), None, Some(2f64)]), Some(3f64));
+    }
+
+    #[test]
+    fn math_sum_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), array::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+m1 = math.sum([1, 2.5, 3])
+m2 = math.sum(close, 2)
+m3 = math.sum(array.from_series(close, 2))
+"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(4f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+
+        let ctx = runner.get_context();
+        assert_eq!(
+            ctx.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(6.5f64),
+                Some(6.5f64),
+                Some(6.5f64)
+            ])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                Some(3f64),
+                Some(6f64)
+            ])))
+        );
+        // The missing history is `na` and skipped by the sum.
+        assert_eq!(
+            ctx.move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1f64),
+                Some(3f64),
+                Some(6f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn math_max_min_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+m1 = math.max(1, 5, 3)
+m2 = math.max(1, 2.5)
+m3 = math.min(close, 3, 4, 1.5, 6, 7, 8, 9, 10, 11, 12)
+m4 = math.min(int(close), 3)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), None]),
+                )],
+                None,
+            )
+            .unwrap();
+
+        let ctx = runner.get_context();
+        assert_eq!(
+            ctx.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(5i64)))
+        );
+        // The int arguments are promoted to float.
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_box(Some(2.5f64)))
+        );
+        // The series arguments are compared bar by bar and the `na` values are skipped.
+        assert_eq!(
+            ctx.move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1f64),
+                Some(1.5f64),
+                Some(1.5f64)
+            ])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1i64),
+                Some(2i64),
+                Some(3i64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn math_int_overload_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+m1 = math.abs(-3)
+m2 = math.abs(int(close))
+m3 = math.sign(-3)
+m4 = math.sign(close)
+m5 = math.sqrt(16)
+m6 = math.pow(2, 3)
+m7 = math.log10(int(close) * -50)
+m8 = math.exp(0) + math.log(1)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(-2f64)]))],
+                None,
+            )
+            .unwrap();
+
+        let ctx = runner.get_context();
+        // The abs and sign of the int are int.
+        assert_eq!(
+            ctx.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_box(Some(3i64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(2i64)])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_box(Some(-1i64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(-1f64)])))
+        );
+        // The others convert the int to float.
+        assert_eq!(
+            ctx.move_var(VarIndex::new(4, 0)),
+            Some(PineRef::new_box(Some(4f64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(5, 0)),
+            Some(PineRef::new_box(Some(8f64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(6, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(2f64)])))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(7, 0)),
+            Some(PineRef::new_box(Some(1f64)))
+        );
+    }
+
+    #[test]
+    fn math_const_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+f(simple float x) => x * 2
+m1 = math.pi * 2
+m2 = math.e
+m3 = math.phi - math.rphi
+m4 = f(math.pi)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+
+        // The user function `f` is stored in the first variable.
+        let ctx = runner.get_context();
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new(Some(consts::PI * 2f64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new(Some(consts::E)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new(Some(PHI - RPHI)))
+        );
+    }
+
+    #[test]
+    fn round_to_mintick_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"
+m1 = math.round_to_mintick(1.2)
+m2 = math.round_to_mintick(close)
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(10.1f64), None, Some(10.4f64)]),
+                )],
+                Some(Rc::new(SymbolInfo {
+                    symbol_type: String::from("future"),
+                    timezone: String::from("UTC"),
+                    ticker: String::from("CME:ES"),
+                    session: String::from("regular"),
+                    trade_start: String::from(""),
+                    trade_end: String::from(""),
+                    root: None,
+                    currency: String::from("USD"),
+                    description: String::from(""),
+                    mintick: 0.25,
+                })),
+            )
+            .unwrap();
+
+        let ctx = runner.get_context();
+        assert_eq!(
+            ctx.move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new(Some(1.25f64)))
+        );
+        assert_eq!(
+            ctx.move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                Some(10f64),
+                None,
+                Some(10.5f64)
+            ])))
+        );
+    }
+}
@@ -1,8 +1,7 @@
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::{
-    move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_f64, pine_ref_to_i64,
-    pine_ref_to_string,
+    pine_ref_to_bool, pine_ref_to_color, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Callable, Float, Int, PineFrom, PineRef, RuntimeErr, Series, SeriesCall, NA};
@@ -29,11 +28,11 @@ impl<'a> SeriesCall<'a> for MinMaxCallVal {
     fn step(
         &mut self,
         _context: &mut dyn Ctx<'a>,
-        mut param: Vec<Option<PineRef<'a>>>,
+        param: Vec<Option<PineRef<'a>>>,
         func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
-        move_tuplet!((x1, x2, x3, x4, x5, x6, x7, x8, x9, x10) = param);
-        let input_vals = vec![x1, x2, x3, x4, x5, x6, x7, x8, x9, x10];
+        // The variadic arguments are all collected, so the count depends on the call.
+        let input_vals = param;
 
         let int_func =
             unsafe { transmute::<_, fn(Vec<Option<PineRef<'a>>>) -> Int>(self.int_func) };
@@ -66,83 +65,36 @@ impl<'a> SeriesCall<'a> for MinMaxCallVal {
     }
 }
 
-pub fn declare_minmax_var<'a>(
-    name: &'static str,
+// The variadic signatures whose arguments have the same type as the result, the int arguments
+// are promoted to float if any argument is float, e.g. `max(x1, x2, ...)`.
+pub fn gen_minmax_type<'a>(names: [&'a str; 2]) -> SyntaxType<'a> {
+    let gen_type = |arg_type: SyntaxType<'a>| {
+        FunctionType::new_with_templates(
+            vec![SimpleSyntaxType::Int, SimpleSyntaxType::Float],
+            (
+                vec![(names[0], arg_type.clone()), (names[1], arg_type.clone())],
+                arg_type,
+            ),
+        )
+        .into_variadic()
+    };
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
+        gen_type(SyntaxType::Simple(SimpleSyntaxType::Template)),
+        gen_type(SyntaxType::Series(SimpleSyntaxType::Template)),
+    ])))
+}
+
+fn minmax_callable<'a>(
     int_func: fn(Vec<Option<PineRef<'a>>>) -> Int,
     float_func: fn(Vec<Option<PineRef<'a>>>) -> Float,
-) -> VarResult<'a> {
-    let value = PineRef::new(Callable::new(
+) -> Callable<'a> {
+    Callable::new(
         None,
         Some(Box::new(MinMaxCallVal::new(
             int_func as *mut (),
             float_func as *mut (),
         ))),
-    ));
-
-    let func_type = FunctionTypes(vec![
-        FunctionType::new((
-            vec![
-                ("x1", SyntaxType::int()),
-                ("x2", SyntaxType::int()),
-                ("x3", SyntaxType::int()),
-                ("x4", SyntaxType::int()),
-                ("x5", SyntaxType::int()),
-                ("x6", SyntaxType::int()),
-                ("x7", SyntaxType::int()),
-                ("x8", SyntaxType::int()),
-                ("x9", SyntaxType::int()),
-                ("x10", SyntaxType::int()),
-            ],
-            SyntaxType::int(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x1", SyntaxType::float()),
-                ("x2", SyntaxType::float()),
-                ("x3", SyntaxType::float()),
-                ("x4", SyntaxType::float()),
-                ("x5", SyntaxType::float()),
-                ("x6", SyntaxType::float()),
-                ("x7", SyntaxType::float()),
-                ("x8", SyntaxType::float()),
-                ("x9", SyntaxType::float()),
-                ("x10", SyntaxType::float()),
-            ],
-            SyntaxType::float(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x1", SyntaxType::int_series()),
-                ("x2", SyntaxType::int_series()),
-                ("x3", SyntaxType::int_series()),
-                ("x4", SyntaxType::int_series()),
-                ("x5", SyntaxType::int_series()),
-                ("x6", SyntaxType::int_series()),
-                ("x7", SyntaxType::int_series()),
-                ("x8", SyntaxType::int_series()),
-                ("x9", SyntaxType::int_series()),
-                ("x10", SyntaxType::int_series()),
-            ],
-            SyntaxType::int_series(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x1", SyntaxType::float_series()),
-                ("x2", SyntaxType::float_series()),
-                ("x3", SyntaxType::float_series()),
-                ("x4", SyntaxType::float_series()),
-                ("x5", SyntaxType::float_series()),
-                ("x6", SyntaxType::float_series()),
-                ("x7", SyntaxType::float_series()),
-                ("x8", SyntaxType::float_series()),
-                ("x9", SyntaxType::float_series()),
-                ("x10", SyntaxType::float_series()),
-            ],
-            SyntaxType::float_series(),
-        )),
-    ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
-    VarResult::new(value, syntax_type, name)
+    )
 }
 
 fn int_max<'a>(vals: Vec<Option<PineRef<'a>>>) -> Int {
@@ -155,8 +107,13 @@ fn float_max<'a>(vals: Vec<Option<PineRef<'a>>>) -> Float {
         .max_by(|x1, x2| x1.partial_cmp(x2).unwrap_or(cmp::Ordering::Equal))
 }
 
+pub fn max_callable<'a>() -> Callable<'a> {
+    minmax_callable(int_max, float_max)
+}
+
 pub fn declare_max_var<'a>() -> VarResult<'a> {
-    declare_minmax_var("max", int_max, float_max)
+    let value = PineRef::new(max_callable());
+    VarResult::new(value, gen_minmax_type(["x1", "x2"]), "max")
 }
 
 fn int_min<'a>(vals: Vec<Option<PineRef<'a>>>) -> Int {
@@ -169,8 +126,13 @@ fn float_min<'a>(vals: Vec<Option<PineRef<'a>>>) -> Float {
         .min_by(|x1, x2| x1.partial_cmp(x2).unwrap_or(cmp::Ordering::Equal))
 }
 
+pub fn min_callable<'a>() -> Callable<'a> {
+    minmax_callable(int_min, float_min)
+}
+
 pub fn declare_min_var<'a>() -> VarResult<'a> {
-    declare_minmax_var("min", int_min, float_min)
+    let value = PineRef::new(min_callable());
+    VarResult::new(value, gen_minmax_type(["x1", "x2"]), "min")
 }
 
 #[cfg(test)]
@@ -0,0 +1,120 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::{
+    ge1_param_i64, pine_ref_to_f64_series, pine_ref_to_i64, require_param, series_index2, Median,
+    RollingWindow,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{
+    Callable, CallableFactory, Float, ParamCollectCall, PineRef, RuntimeErr, Series, SeriesCall,
+};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+struct MedianVal {
+    window: RollingWindow<Float, Median>,
+}
+
+impl MedianVal {
+    pub fn new() -> MedianVal {
+        MedianVal {
+            window: RollingWindow::new(Median::new()),
+        }
+    }
+}
+
+impl<'a> SeriesCall<'a> for MedianVal {
+    fn step(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let source = require_param("source", pine_ref_to_f64_series(param[0].take()))?;
+        let length = ge1_param_i64("length", pine_ref_to_i64(param[1].take()))?;
+
+        let bar = ctx.get_iterindex();
+        let res = self
+            .window
+            .update(bar, length as usize, |i| series_index2(&source, i))
+            .get();
+        Ok(PineRef::new_rc(Series::from(res)))
+    }
+
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.window.invalidate();
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub const VAR_NAME: &'static str = "median";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                MedianVal::new(),
+            )))),
+        )
+    }));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("source", SyntaxType::float_series()),
+            ("length", SyntaxType::int()),
+        ],
+        SyntaxType::float_series(),
+    ))]);
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn median_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = median(close, 3)\nn = median(close, 2)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+        let assert_vals = |runner: &mut PineRunner, i: i32, vals: Vec<Float>| {
+            assert_eq!(
+                runner.get_context().get_var(VarIndex::new(i, 0)),
+                &Some(PineRef::new(Series::from_vec(vals)))
+            );
+        };
+
+        runner
+            .run(&vec![("close", series(vec![5f64, 1f64, 4f64, 2f64]))], None)
+            .unwrap();
+        assert_vals(&mut runner, 0, vec![None, None, Some(4f64), Some(2f64)]);
+        assert_vals(
+            &mut runner,
+            1,
+            vec![None, Some(3f64), Some(2.5f64), Some(3f64)],
+        );
+
+        // The window of the last bar is rebuilt by the update.
+        runner.update(&vec![("close", series(vec![8f64]))]).unwrap();
+        assert_vals(&mut runner, 0, vec![None, None, Some(4f64), Some(4f64)]);
+        assert_vals(
+            &mut runner,
+            1,
+            vec![None, Some(3f64), Some(2.5f64), Some(6f64)],
+        );
+    }
+}
@@ -24,23 +24,40 @@ use std::rc::Rc;
 #[derive(Debug, Clone, PartialEq)]
 pub struct KcVal<'a> {
     volume_index: VarIndex,
+    close_index: VarIndex,
+    low_index: VarIndex,
+    high_index: VarIndex,
     upper_history: Series<'a, Float>,
     lower_history: Series<'a, Float>,
+    // The typical prices(hlc3) of the overload without the series.
+    tp_history: Series<'a, Float>,
 }
 
 impl<'a> KcVal<'a> {
     pub fn new() -> KcVal<'a> {
         KcVal {
             volume_index: VarIndex::new(0, 0),
+            close_index: VarIndex::new(0, 0),
+            low_index: VarIndex::new(0, 0),
+            high_index: VarIndex::new(0, 0),
             upper_history: Series::new(),
             lower_history: Series::new(),
+            tp_history: Series::new(),
         }
     }
 
-    fn handle_index(&mut self, ctx: &mut dyn Ctx<'a>) {
-        ensure_srcs(ctx, vec!["volume"], |indexs| {
-            self.volume_index = indexs[0];
-        });
+    fn handle_index(&mut self, ctx: &mut dyn Ctx<'a>, with_tp: bool) {
+        match with_tp {
+            true => ensure_srcs(ctx, vec!["volume", "close", "low", "high"], |indexs| {
+                self.volume_index = indexs[0];
+                self.close_index = indexs[1];
+                self.low_index = indexs[2];
+                self.high_index = indexs[3];
+            }),
+            false => ensure_srcs(ctx, vec!["volume"], |indexs| {
+                self.volume_index = indexs[0];
+            }),
+        }
     }
 
     fn process_rsi(
@@ -49,28 +66,42 @@ impl<'a> KcVal<'a> {
         mut param: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<Float, RuntimeErr> {
-        self.handle_index(_ctx);
-        move_tuplet!((series, length) = param);
-
-        let series = require_param("series", pine_ref_to_f64_series(series))?;
-        let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+        let with_tp = _func_type.signature.0.len() == 1;
+        self.handle_index(_ctx, with_tp);
+        let (s0, s1, length) = if with_tp {
+            // The typical price is the series if it is omitted.
+            let length = ge1_param_i64("length", pine_ref_to_i64(param[0].take()))?;
+            let close = pine_ref_to_f64(_ctx.get_var(self.close_index).clone());
+            let low = pine_ref_to_f64(_ctx.get_var(self.low_index).clone());
+            let high = pine_ref_to_f64(_ctx.get_var(self.high_index).clone());
+            self.tp_history
+                .update(high.add(low).add(close).div(Some(3f64)));
+            let (s0, s1) = (self.tp_history.at(0), self.tp_history.at(1));
+            self.tp_history.commit();
+            (s0, s1, length)
+        } else {
+            move_tuplet!((series, length) = param);
+            let series = require_param("series", pine_ref_to_f64_series(series))?;
+            let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+            (
+                series.index_value(0).unwrap(),
+                series.index_value(1).unwrap(),
+                length,
+            )
+        };
 
-        let s0 = series.index_value(0).unwrap();
-        let s1 = series.index_value(1).unwrap();
         let volume = pine_ref_to_f64(_ctx.get_var(self.volume_index).clone());
 
-        let upper = if s0.minus(s1) <= Some(0f64) {
-            Some(0f64)
-        } else {
-            s0.mul(volume)
+        // upper = sum(volume * (change(src) <= 0 ? 0 : src), length)
+        // lower = sum(volume * (change(src) >= 0 ? 0 : src), length)
+        // The comparisons with the na change are false, so the first bar counts in both sums.
+        let (upper, lower) = match s0.minus(s1) {
+            Some(c) if c > 0f64 => (s0.mul(volume), Some(0f64)),
+            Some(c) if c < 0f64 => (Some(0f64), s0.mul(volume)),
+            Some(_) => (Some(0f64), Some(0f64)),
+            None => (s0.mul(volume), s0.mul(volume)),
         };
         self.upper_history.update(upper);
-
-        let lower = if s0.minus(s1) >= Some(0f64) {
-            Some(0f64)
-        } else {
-            s0.mul(volume)
-        };
         self.lower_history.update(lower);
 
         // self.upper_history.push(upper);
@@ -103,6 +134,13 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(PineRef::new_rc(Series::from(res)))
     }
 
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.upper_history.roll_back();
+        self.lower_history.roll_back();
+        self.tp_history.roll_back();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
@@ -118,13 +156,19 @@ pub fn declare_var<'a>() -> VarResult<'a> {
         )
     }));
 
-    let func_type = FunctionTypes(vec![FunctionType::new((
-        vec![
-            ("series", SyntaxType::float_series()),
-            ("length", SyntaxType::int()),
-        ],
-        SyntaxType::float_series(),
-    ))]);
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((
+            vec![("length", SyntaxType::int())],
+            SyntaxType::float_series(),
+        )),
+        FunctionType::new((
+            vec![
+                ("series", SyntaxType::float_series()),
+                ("length", SyntaxType::int()),
+            ],
+            SyntaxType::float_series(),
+        )),
+    ]);
     let syntax_type = SyntaxType::Function(Rc::new(func_type));
     VarResult::new(value, syntax_type, "mfi")
 }
@@ -135,9 +179,13 @@ mod tests {
     use crate::ast::syntax_type::SyntaxType;
     use crate::runtime::VarOperate;
     use crate::runtime::{AnySeries, NoneCallback};
-    use crate::types::Series;
+    use crate::testing::approx::{assert_series_approx_eq, DEFAULT_TOLERANCE};
     use crate::{LibInfo, PineParser, PineRunner};
-    // use crate::libs::{floor, exp, };
+
+    fn result(runner: &mut PineRunner) -> Vec<Float> {
+        let val = runner.get_context().get_var(VarIndex::new(0, 0)).clone();
+        pine_ref_to_f64_series(val).unwrap().get_history().clone()
+    }
 
     #[test]
     fn mfi_test() {
@@ -168,15 +216,75 @@ mod tests {
             )
             .unwrap();
 
-        assert_eq!(
-            runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new(Series::from_vec(vec![
+        // The first bar counts in both the upper and lower sums.
+        assert_series_approx_eq(
+            &result(&mut runner),
+            &[None, Some(40f64), Some(80f64)],
+            DEFAULT_TOLERANCE,
+        );
+    }
+
+    #[test]
+    fn mfi_tp_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+                ("volume", SyntaxType::int_series()),
+            ],
+        );
+        let src = "m = mfi(2)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let gen_data = |close: Vec<f64>, high: Vec<f64>, low: Vec<f64>, volume: Vec<i64>| {
+            let series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+            vec![
+                ("close", series(close)),
+                ("high", series(high)),
+                ("low", series(low)),
+                (
+                    "volume",
+                    AnySeries::from_int_vec(volume.into_iter().map(Some).collect()),
+                ),
+            ]
+        };
+
+        // The typical prices are 10, 12 and 10.
+        runner
+            .run(
+                &gen_data(
+                    vec![10f64, 12f64, 8f64],
+                    vec![12f64, 14f64, 13f64],
+                    vec![8f64, 10f64, 9f64],
+                    vec![1, 2, 1],
+                ),
                 None,
-                Some(0f64),
-                Some(80f64),
-            ])))
+            )
+            .unwrap();
+        assert_series_approx_eq(
+            &result(&mut runner),
+            &[
+                None,
+                Some(100f64 - 100f64 / (1f64 + 34f64 / 10f64)),
+                Some(100f64 - 100f64 / (1f64 + 24f64 / 10f64)),
+            ],
+            DEFAULT_TOLERANCE,
+        );
+
+        // The sums of the last bar are rolled back by the update, the typical price is 11 now.
+        runner
+            .update(&gen_data(vec![11f64], vec![12f64], vec![10f64], vec![1]))
+            .unwrap();
+        assert_series_approx_eq(
+            &result(&mut runner),
+            &[
+                None,
+                Some(100f64 - 100f64 / (1f64 + 34f64 / 10f64)),
+                Some(100f64 - 100f64 / (1f64 + 24f64 / 11f64)),
+            ],
+            DEFAULT_TOLERANCE,
         );
     }
 }
-
-// 20 10 20, na 0 40, na 10 0  40 10  rs 2  res 100 - 100 / 5
@@ -1,14 +1,19 @@
 pub mod abs;
 pub mod accdist;
+pub mod alert;
 pub mod alma;
+pub mod array;
 pub mod atr;
 pub mod avg;
+pub mod barmerge;
 pub mod barstate;
 pub mod bb;
 pub mod bbw;
+pub mod bgcolor;
 pub mod cci;
 pub mod ceil;
 pub mod change;
+pub mod chart;
 pub mod cmo;
 pub mod cog;
 pub mod color;
@@ -21,6 +26,7 @@ pub mod ema;
 pub mod falling;
 pub mod fill;
 pub mod fixnan;
+pub mod font;
 pub mod format;
 pub mod highest;
 pub mod highestbars;
@@ -34,10 +40,13 @@ pub mod kc;
 pub mod kcw;
 pub mod label;
 pub mod line;
+pub mod location;
 pub mod lowest;
 pub mod lowestbars;
 pub mod macd;
+pub mod math;
 pub mod max;
+pub mod median;
 pub mod mfi;
 pub mod na;
 pub mod nz;
@@ -48,25 +57,32 @@ pub mod plotbar;
 pub mod plotcandle;
 pub mod plotchar;
 pub mod plotshape;
+pub mod polyline;
 pub mod pow;
 pub mod print;
+pub mod request;
 pub mod rising;
 pub mod rsi;
 pub mod security;
 pub mod size;
 pub mod sma;
 pub mod stoch;
+pub mod strategy;
 pub mod study;
 pub mod sum;
 pub mod swma;
 pub mod syminfo;
 pub mod text;
+pub mod ticker;
 pub mod time;
 pub mod timenow;
 pub mod timestamp;
+pub mod tostring;
 pub mod tr;
 pub mod tsi;
+pub mod vwap;
 pub mod vwma;
+pub mod wpr;
 pub mod xloc;
 pub mod year;
 pub mod yloc;
@@ -112,13 +128,15 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         plot::declare_var(),
         // print::declare_var(),
         input::declare_var(),
-        // plotarrow::declare_var(),
+        plotarrow::declare_var(),
         // plotbar::declare_var(),
         // plotcandle::declare_var(),
-        // plotchar::declare_var(),
-        // plotshape::declare_var(),
+        plotchar::declare_var(),
+        plotshape::declare_var(),
         color::declare_var(),
         study::declare_var(),
+        study::declare_indicator_var(),
+        strategy::declare_var(),
         // syminfo::declare_var(),
         // barstate::declare_var(),
         accdist::declare_var(),
@@ -126,6 +144,12 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         timenow::declare_var(),
         timestamp::declare_var(),
         // security::declare_var(),
+        request::declare_var(),
+        array::declare_var(),
+        math::declare_var(),
+        ticker::declare_var(),
+        ticker::declare_session_var(),
+        ticker::declare_adjustment_var(),
         year::declare_year_var(),
         year::declare_month_var(),
         year::declare_weekofyear_var(),
@@ -133,6 +157,8 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         year::declare_dayofmonth_var(),
         year::declare_hour_var(),
         year::declare_minute_var(),
+        year::declare_second_var(),
+        year::declare_sessbars_var(),
         abs::declare_var(),
         cos::declare_cos_var(),
         cos::declare_acos_var(),
@@ -147,6 +173,7 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         cos::declare_sign_var(),
         na::declare_var(),
         nz::declare_var(),
+        tostring::declare_var(),
         max::declare_max_var(),
         max::declare_min_var(),
         avg::declare_var(),
@@ -189,23 +216,34 @@ pub fn declare_vars<'a>() -> Vec<VarResult<'a>> {
         macd::declare_var(),
         rsi::declare_var(),
         mfi::declare_var(),
+        median::declare_var(),
         swma::declare_var(),
+        vwap::declare_var(),
         vwma::declare_var(),
+        wpr::declare_var(),
         hl2::declare_var(),
         hlc3::declare_var(),
         ohlc4::declare_var(),
         fill::declare_var(),
         format::declare_var(),
         hline::declare_var(),
+        bgcolor::declare_bgcolor_var(),
+        bgcolor::declare_barcolor_var(),
         tsi::declare_var(),
         stoch::declare_var(),
         // line::declare_var(),
         // label::declare_var(),
-        // xloc::declare_var(),
+        chart::declare_var(),
+        polyline::declare_var(),
+        xloc::declare_var(),
         // yloc::declare_var(),
-        // size::declare_var(),
-        // text::declare_var(),
+        size::declare_var(),
+        location::declare_var(),
+        text::declare_var(),
+        font::declare_var(),
         display::declare_var(),
+        barmerge::declare_var(),
+        alert::declare_var(),
     ];
     debug_assert!(
         check_names(&list).len() == 0,
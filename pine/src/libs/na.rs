@@ -140,7 +140,7 @@ mod tests {
     const NA_SCRIPT: &'static str = "
     v1 = close < 10 ? na : close    // CORRECT
 
-    v2 = close == na ? 1 : close    // CORRECT
+    v2 = close == na ? 1 : close    // INCORRECT, the comparison with na is false
     v3 = na(close) ? 1 : close    // CORRECT
     ";
 
@@ -168,16 +168,41 @@ mod tests {
         );
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(2f64), None])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
             Some(PineRef::new_rc(Series::from_vec(vec![
                 Some(2f64),
-                Some(1f64),
+                Some(1f64)
             ])))
         );
+    }
+
+    #[test]
+    fn na_ordered_comparison_test() {
+        use crate::runtime::op::NaComparison;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        );
+        let blk = PineParser::new(NA_SCRIPT, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.set_na_comparison(NaComparison::Ordered);
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(2f64), None]))],
+                None,
+            )
+            .unwrap();
+        // na is less than any number and equal to na.
         assert_eq!(
-            runner.get_context().move_var(VarIndex::new(2, 0)),
+            runner.get_context().move_var(VarIndex::new(1, 0)),
             Some(PineRef::new_rc(Series::from_vec(vec![
                 Some(2f64),
-                Some(1f64)
+                Some(1f64),
             ])))
         );
     }
@@ -66,32 +66,29 @@ pub const VAR_NAME: &'static str = "nz";
 pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(Callable::new(Some(na_func), None));
 
+    // nz(x: T, y: series T) -> T and nz(x: series T, y: series T) -> series T
+    let templates = vec![SimpleSyntaxType::Int, SimpleSyntaxType::Float];
     let func_type = FunctionTypes(vec![
-        FunctionType::new((
-            vec![("x", SyntaxType::int()), ("y", SyntaxType::int_series())],
-            SyntaxType::int(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x", SyntaxType::int_series()),
-                ("y", SyntaxType::int_series()),
-            ],
-            SyntaxType::int_series(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x", SyntaxType::float()),
-                ("y", SyntaxType::float_series()),
-            ],
-            SyntaxType::float(),
-        )),
-        FunctionType::new((
-            vec![
-                ("x", SyntaxType::float_series()),
-                ("y", SyntaxType::float_series()),
-            ],
-            SyntaxType::float_series(),
-        )),
+        FunctionType::new_with_templates(
+            templates.clone(),
+            (
+                vec![
+                    ("x", SyntaxType::Simple(SimpleSyntaxType::Template)),
+                    ("y", SyntaxType::Series(SimpleSyntaxType::Template)),
+                ],
+                SyntaxType::Simple(SimpleSyntaxType::Template),
+            ),
+        ),
+        FunctionType::new_with_templates(
+            templates,
+            (
+                vec![
+                    ("x", SyntaxType::Series(SimpleSyntaxType::Template)),
+                    ("y", SyntaxType::Series(SimpleSyntaxType::Template)),
+                ],
+                SyntaxType::Series(SimpleSyntaxType::Template),
+            ),
+        ),
     ]);
     let syntax_type = SyntaxType::Function(Rc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
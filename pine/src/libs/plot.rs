@@ -1,3 +1,4 @@
+use super::display::pine_ref_to_display;
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::err_msgs::*;
@@ -15,20 +16,6 @@ use crate::types::{
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
-fn resize_offset<'a, T>(data: &mut Vec<Option<T>>, offset: i64) {
-    match offset {
-        0 => {}
-        i if i > 0 => {
-            let mut fillv: Vec<Option<T>> = Vec::with_capacity(offset as usize);
-            fillv.resize_with(offset as usize, || None);
-            data.splice(..0, fillv);
-        }
-        _ => {
-            data.splice(0..offset.abs() as usize, vec![]);
-        }
-    };
-}
-
 fn plot_val<'a>(
     item_val: PineRef<'a>,
     // offset: i64,
@@ -49,23 +36,31 @@ pub fn plot_color<'a>(
     // plot_series(item_data.into_pf(), context)
     let mut items: RefData<Series<Color<'a>>> = Series::implicity_from(item_val).unwrap();
     let colors: Vec<Color<'a>> = items.move_history();
-    let mut options: Vec<&'a str> = vec![];
-    let mut values: Vec<Option<i32>> = vec![];
-
-    for color in colors.iter() {
-        match options.iter().position(|&x| x == color.0) {
-            None => {
-                options.push(color.0);
-                values.push(Some((options.len() - 1) as i32));
-            }
-            Some(i) => {
-                values.push(Some(i as i32));
-            }
+    // resize_offset(&mut values, offset);
+    Ok(StrOptionsData::from_values(
+        colors.into_iter().map(|color| Some(color.0)),
+    ))
+}
+
+// Stream the value of the plot-like output for the current bar to the host, the value is plotted on
+// the bar shifted by the offset, e.g. the future bar for the positive offset.
+pub fn stream_plot_point<'a>(
+    context: &mut dyn Ctx<'a>,
+    output_id: i32,
+    p: &[Option<PineRef<'a>>],
+    offset_index: usize,
+) {
+    if let Some(callback) = downcast_ctx(context).get_output_callback() {
+        let offset = pine_ref_to_i64(p.get(offset_index).cloned().flatten()).unwrap_or(0);
+        let bar_index = context.get_iterindex() as i64 + offset;
+        if output_id >= 0 && bar_index >= 0 {
+            callback.on_plot_point(
+                output_id as usize,
+                bar_index as i32,
+                pine_ref_to_f64(p[0].clone()),
+            );
         }
     }
-    let options = options.iter().map(|&x| String::from(x)).collect();
-    // resize_offset(&mut values, offset);
-    Ok(StrOptionsData { options, values })
 }
 
 fn pine_plot<'a>(
@@ -99,6 +94,37 @@ fn pine_plot<'a>(
     }
 }
 
+const PLOT_STYLES: [&str; 9] = [
+    "area",
+    "areabr",
+    "circles",
+    "columns",
+    "cross",
+    "histogram",
+    "line",
+    "linebr",
+    "stepline",
+];
+
+fn plot_style<'a>(style: Option<PineRef<'a>>) -> Result<Option<String>, RuntimeErr> {
+    match pine_ref_to_string(style) {
+        Some(s) if !PLOT_STYLES.contains(&s.as_str()) => Err(RuntimeErr::InvalidParameters(
+            str_replace(INVALID_VALS, vec![String::from("style")]),
+        )),
+        s => Ok(s),
+    }
+}
+
+fn plot_linewidth<'a>(linewidth: Option<PineRef<'a>>) -> Result<Option<i64>, RuntimeErr> {
+    match pine_ref_to_i64(linewidth) {
+        Some(w) if w < 1 => Err(RuntimeErr::InvalidParameters(str_replace(
+            GE_1,
+            vec![String::from("linewidth")],
+        ))),
+        w => Ok(w),
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PlotVal {
     output_id: i32,
@@ -114,7 +140,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
     fn step(
         &mut self,
         context: &mut dyn Ctx<'a>,
-        mut p: Vec<Option<PineRef<'a>>>,
+        p: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
         if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
@@ -122,7 +148,7 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 (
                     _series, title, color, linewidth, style, trackprice, opacity, histbase, offset,
                     join, editable, show_last, display
-                ) = p
+                ) = p.clone()
             );
             let plot_info = PlotInfo {
                 title: pine_ref_to_string(title),
@@ -130,8 +156,8 @@ impl<'a> SeriesCall<'a> for PlotVal {
                     Some(SyntaxType::Simple(_)) => pine_ref_to_color(color),
                     _ => Some(String::from("")),
                 },
-                linewidth: pine_ref_to_i64(linewidth),
-                style: pine_ref_to_string(style),
+                linewidth: plot_linewidth(linewidth)?,
+                style: plot_style(style)?,
                 opacity: pine_ref_to_i64(opacity),
                 trackprice: pine_ref_to_bool(trackprice),
                 histbase: pine_ref_to_f64(histbase),
@@ -139,11 +165,12 @@ impl<'a> SeriesCall<'a> for PlotVal {
                 join: pine_ref_to_bool(join),
                 editable: pine_ref_to_bool(editable),
                 show_last: pine_ref_to_i64(show_last),
-                display: pine_ref_to_i64(display),
+                display: pine_ref_to_display(display)?,
             };
             self.output_id =
                 downcast_ctx(context).push_output_info_retindex(OutputInfo::Plot(plot_info));
         }
+        stream_plot_point(context, self.output_id, &p, 8);
 
         Ok(PineRef::Box(Box::new(Some(self.output_id as i64))))
     }
@@ -246,7 +273,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     ]);
     let mut obj_type = BTreeMap::new();
     obj_type.insert("style_area", SyntaxType::string());
-    // obj_type.insert("style_areabr", SyntaxType::string());
+    obj_type.insert("style_areabr", SyntaxType::string());
     obj_type.insert("style_circles", SyntaxType::string());
     obj_type.insert("style_columns", SyntaxType::string());
     obj_type.insert("style_cross", SyntaxType::string());
@@ -495,8 +522,7 @@ mod tests {
             vec![("close", SyntaxType::float_series())],
         );
         let src = r"m = [
-            plot.style_area, plot.style_circles, plot.style_columns, 
-            // plot.style_areabr
+            plot.style_area, plot.style_areabr, plot.style_circles, plot.style_columns,
             plot.style_cross, plot.style_histogram, plot.style_line, plot.style_linebr,
             plot.style_stepline
         ]";
@@ -517,7 +543,7 @@ mod tests {
             tuple_vec,
             vec![
                 PineRef::new_rc(String::from("area")),
-                // PineRef::new_rc(String::from("areabr")),
+                PineRef::new_rc(String::from("areabr")),
                 PineRef::new_rc(String::from("circles")),
                 PineRef::new_rc(String::from("columns")),
                 PineRef::new_rc(String::from("cross")),
@@ -529,37 +555,100 @@ mod tests {
         );
     }
 
-    // #[test]
-    // fn plot_offset_test() {
-    //     let lib_info = LibInfo::new(
-    //         vec![declare_var()],
-    //         vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
-    //     );
-    //     let src = "plot(close, offset = 2)\nplot(close, offset = -1)";
-    //     let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
-    //     let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
-
-    //     runner
-    //         .run(
-    //             &vec![(
-    //                 "close",
-    //                 AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
-    //             )],
-    //             None,
-    //         )
-    //         .unwrap();
-
-    //     assert_eq!(
-    //         runner.move_output_data(),
-    //         vec![
-    //             Some(OutputData::new(vec![vec![
-    //                 None,
-    //                 None,
-    //                 Some(1f64),
-    //                 Some(2f64)
-    //             ]])),
-    //             Some(OutputData::new(vec![vec![Some(2f64)]]))
-    //         ]
-    //     );
-    // }
+    #[test]
+    fn plot_style_test() {
+        use crate::runtime::OutputInfo;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r"plot(close, style=plot.style_histogram, histbase=1.5, offset=-2, show_last=5)
+            plot(close, style=plot.style_stepline, join=true, trackprice=false, display=0)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let gen_info = |style: &str| PlotInfo {
+            title: None,
+            color: None,
+            linewidth: None,
+            style: Some(String::from(style)),
+            trackprice: None,
+            opacity: None,
+            histbase: None,
+            offset: None,
+            join: None,
+            editable: None,
+            show_last: None,
+            display: None,
+        };
+        assert_eq!(
+            runner.get_io_info().get_outputs(),
+            &vec![
+                OutputInfo::Plot(PlotInfo {
+                    histbase: Some(1.5f64),
+                    offset: Some(-2),
+                    show_last: Some(5),
+                    ..gen_info("histogram")
+                }),
+                OutputInfo::Plot(PlotInfo {
+                    join: Some(true),
+                    trackprice: Some(false),
+                    display: Some(0),
+                    ..gen_info("stepline")
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn plot_invalid_param_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        let blk = PineParser::new("plot(close, style='dots')", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("style")]
+            )))
+        );
+
+        let blk = PineParser::new("plot(close, linewidth=0)", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::InvalidParameters(str_replace(
+                GE_1,
+                vec![String::from("linewidth")]
+            )))
+        );
+
+        let blk = PineParser::new("plot(close, display=3)", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("display")]
+            )))
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use super::display::pine_ref_to_display;
+use super::plot::stream_plot_point;
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::{
@@ -8,7 +10,8 @@ use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::{OutputData, OutputInfo, PlotArrowInfo};
 use crate::types::{
     Bool, Callable, CallableFactory, CallableObject, DataType, Float, Int, ParamCollectCall,
-    PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
+    PineClass, PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall,
+    NA,
 };
 use std::rc::Rc;
 
@@ -17,45 +20,85 @@ fn pine_plot<'a>(
     mut param: Vec<Option<PineRef<'a>>>,
     _func_type: FunctionType<'a>,
 ) -> Result<(), RuntimeErr> {
-    move_tuplet!(
-        (
-            series, title, colorup, colordown, opacity, offset, minheight, maxheight, editable,
-            show_last, display
-        ) = param
-    );
-    if !downcast_ctx(context).check_is_output_info_ready() {
-        let plot_info = PlotArrowInfo {
-            title: pine_ref_to_string(title),
-            colorup: pine_ref_to_color(colorup),
-            colordown: pine_ref_to_color(colordown),
-            opacity: pine_ref_to_i64(opacity),
-            offset: pine_ref_to_i64(offset),
-            minheight: pine_ref_to_i64(minheight),
-            maxheight: pine_ref_to_i64(maxheight),
-            editable: pine_ref_to_bool(editable),
-            show_last: pine_ref_to_i64(show_last),
-            display: pine_ref_to_i64(display),
-        };
-        downcast_ctx(context).push_output_info(OutputInfo::PlotArrow(plot_info));
-    }
-    match series {
+    match move_element(&mut param, 0) {
         Some(item_val) => {
             let mut items: RefData<Series<Float>> = Series::implicity_from(item_val).unwrap();
-            let data = items.move_history();
-            let ctx_ins = downcast_ctx(context);
-            let data_range = ctx_ins.get_data_range();
-            ctx_ins.push_output_data(Some(OutputData::new(vec![data])));
+            downcast_ctx(context)
+                .push_output_data(Some(OutputData::new(vec![items.move_history()])));
             Ok(())
         }
         _ => Err(RuntimeErr::NotSupportOperator),
     }
 }
 
+#[derive(Debug, Clone)]
+struct PlotArrowVal {
+    output_id: i32,
+}
+
+impl PlotArrowVal {
+    fn new() -> PlotArrowVal {
+        PlotArrowVal { output_id: -1 }
+    }
+}
+
+impl<'a> SeriesCall<'a> for PlotArrowVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        p: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
+            move_tuplet!(
+                (
+                    _series, title, colorup, colordown, opacity, offset, minheight, maxheight,
+                    editable, show_last, display
+                ) = p.clone()
+            );
+            let plot_info = PlotArrowInfo {
+                title: pine_ref_to_string(title),
+                colorup: pine_ref_to_color(colorup),
+                colordown: pine_ref_to_color(colordown),
+                opacity: pine_ref_to_i64(opacity),
+                offset: pine_ref_to_i64(offset),
+                minheight: pine_ref_to_i64(minheight),
+                maxheight: pine_ref_to_i64(maxheight),
+                editable: pine_ref_to_bool(editable),
+                show_last: pine_ref_to_i64(show_last),
+                display: pine_ref_to_display(display)?,
+            };
+            self.output_id =
+                downcast_ctx(context).push_output_info_retindex(OutputInfo::PlotArrow(plot_info));
+        }
+        stream_plot_point(context, self.output_id, &p, 5);
+        Ok(PineRef::new_box(NA))
+    }
+
+    fn run_with_cd(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        params: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<(), RuntimeErr> {
+        pine_plot(context, params, func_type)
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
 pub const VAR_NAME: &'static str = "plotarrow";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new(|| {
-        Callable::new(None, Some(Box::new(ParamCollectCall::new(pine_plot))))
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                PlotArrowVal::new(),
+            )))),
+        )
     }));
 
     // plot(series, title, color, linewidth, style, trackprice, opacity, histbase, offset, join, editable, show_last) → plot
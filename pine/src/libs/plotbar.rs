@@ -1,3 +1,4 @@
+use super::display::pine_ref_to_display;
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::err_msgs::*;
@@ -26,7 +27,7 @@ fn pine_plot<'a>(
             color: pine_ref_to_color(color),
             editable: pine_ref_to_bool(editable),
             show_last: pine_ref_to_i64(show_last),
-            display: pine_ref_to_i64(display),
+            display: pine_ref_to_display(display)?,
         };
         downcast_ctx(context).push_output_info(OutputInfo::PlotBar(plot_info));
     }
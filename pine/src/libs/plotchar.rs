@@ -1,3 +1,7 @@
+use super::display::pine_ref_to_display;
+use super::location::pine_ref_to_location;
+use super::plot::stream_plot_point;
+use super::size::pine_ref_to_size;
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::err_msgs::*;
@@ -6,48 +10,47 @@ use crate::helper::{
     move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_i64, pine_ref_to_string,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::output::{OutputData, OutputInfo, PlotCharInfo};
+use crate::runtime::output::{OutputData, OutputInfo, PlotCharInfo, StrOptionsData};
 use crate::types::{
     Bool, Callable, CallableFactory, DataType, Float, Int, ParamCollectCall, PineClass, PineFrom,
-    PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
+    PineRef, PineType, RefData, RuntimeErr, SecondType, Series, SeriesCall, NA,
 };
 use std::rc::Rc;
 
+// The char argument must be exactly one character, e.g. `'❄'`.
+fn pine_ref_to_char<'a>(val: Option<PineRef<'a>>) -> Result<Option<String>, RuntimeErr> {
+    match pine_ref_to_string(val) {
+        Some(v) if v.chars().count() != 1 => Err(RuntimeErr::InvalidParameters(str_replace(
+            INVALID_VALS,
+            vec![String::from("char")],
+        ))),
+        v => Ok(v),
+    }
+}
+
+// The text of every bar, the empty text is na.
+pub fn plot_text<'a>(item_val: PineRef<'a>) -> StrOptionsData {
+    let mut items: RefData<Series<String>> = Series::implicity_from(item_val).unwrap();
+    let texts: Vec<String> = items.move_history();
+    StrOptionsData::from_values(texts.into_iter().map(|v| Some(v).filter(|v| !v.is_empty())))
+}
+
 fn pine_plot<'a>(
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
-    _func_type: FunctionType<'a>,
+    func_type: FunctionType<'a>,
 ) -> Result<(), RuntimeErr> {
-    move_tuplet!(
-        (
-            series, title, char, location, color, opacity, offset, text, textcolor, editable, size,
-            show_last, display
-        ) = param
-    );
-    if !downcast_ctx(context).check_is_output_info_ready() {
-        let plot_info = PlotCharInfo {
-            title: pine_ref_to_string(title),
-            char: pine_ref_to_string(char),
-            location: pine_ref_to_string(location),
-            color: pine_ref_to_color(color),
-            opacity: pine_ref_to_i64(opacity),
-            offset: pine_ref_to_i64(offset),
-            text: pine_ref_to_string(text),
-            textcolor: pine_ref_to_color(textcolor),
-
-            editable: pine_ref_to_bool(editable),
-            size: pine_ref_to_string(size),
-
-            show_last: pine_ref_to_i64(show_last),
-            display: pine_ref_to_i64(display),
-        };
-        downcast_ctx(context).push_output_info(OutputInfo::PlotChar(plot_info));
-    }
+    let series = move_element(&mut param, 0);
+    let text = move_element(&mut param, 7);
     match series {
         Some(item_val) => {
             let mut items: RefData<Series<Float>> = Series::implicity_from(item_val).unwrap();
-            downcast_ctx(context)
-                .push_output_data(Some(OutputData::new(vec![items.move_history()])));
+            let mut data = OutputData::new(vec![items.move_history()]);
+            // The series text is output with the data of every bar instead of the plot info.
+            if let (Some(SyntaxType::Series(_)), Some(text)) = (func_type.get_type(7), text) {
+                data = data.with_texts(vec![plot_text(text)]);
+            }
+            downcast_ctx(context).push_output_data(Some(data));
             Ok(())
         }
         _ => Err(RuntimeErr::MissingParameters(str_replace(
@@ -57,31 +60,109 @@ fn pine_plot<'a>(
     }
 }
 
+#[derive(Debug, Clone)]
+struct PlotCharVal {
+    output_id: i32,
+}
+
+impl PlotCharVal {
+    fn new() -> PlotCharVal {
+        PlotCharVal { output_id: -1 }
+    }
+}
+
+impl<'a> SeriesCall<'a> for PlotCharVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        p: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.output_id < 0 && !downcast_ctx(context).check_is_output_info_ready() {
+            move_tuplet!(
+                (
+                    _series, title, char, location, color, opacity, offset, text, textcolor,
+                    editable, size, show_last, display
+                ) = p.clone()
+            );
+            let text = match func_type.get_type(7) {
+                Some(SyntaxType::Series(_)) => None,
+                _ => text,
+            };
+            let plot_info = PlotCharInfo {
+                title: pine_ref_to_string(title),
+                char: pine_ref_to_char(char)?,
+                location: pine_ref_to_location(location)?,
+                color: pine_ref_to_color(color),
+                opacity: pine_ref_to_i64(opacity),
+                offset: pine_ref_to_i64(offset),
+                text: pine_ref_to_string(text),
+                textcolor: pine_ref_to_color(textcolor),
+
+                editable: pine_ref_to_bool(editable),
+                size: pine_ref_to_size(size)?,
+
+                show_last: pine_ref_to_i64(show_last),
+                display: pine_ref_to_display(display)?,
+            };
+            self.output_id =
+                downcast_ctx(context).push_output_info_retindex(OutputInfo::PlotChar(plot_info));
+        }
+        stream_plot_point(context, self.output_id, &p, 6);
+        Ok(PineRef::new_box(NA))
+    }
+
+    fn run_with_cd(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        params: Vec<Option<PineRef<'a>>>,
+        func_type: FunctionType<'a>,
+    ) -> Result<(), RuntimeErr> {
+        pine_plot(context, params, func_type)
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
 pub const VAR_NAME: &'static str = "plotchar";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new(|| {
-        Callable::new(None, Some(Box::new(ParamCollectCall::new(pine_plot))))
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                PlotCharVal::new(),
+            )))),
+        )
     }));
 
-    let func_type = FunctionTypes(vec![FunctionType::new((
-        vec![
-            ("series", SyntaxType::Series(SimpleSyntaxType::Float)),
-            ("title", SyntaxType::string()),
-            ("char", SyntaxType::string()),
-            ("location", SyntaxType::string()),
-            ("color", SyntaxType::color()),
-            ("opacity", SyntaxType::int()),
-            ("offset", SyntaxType::int()),
-            ("text", SyntaxType::string()),
-            ("textcolor", SyntaxType::color()),
-            ("editable", SyntaxType::bool()),
-            ("size", SyntaxType::string()),
-            ("show_last", SyntaxType::int()),
-            ("display", SyntaxType::int()),
-        ],
-        SyntaxType::Void,
-    ))]);
+    // The text is series if it's updated on every bar, e.g. `str.tostring(close)`.
+    let gen_type = |text_type: SyntaxType<'a>| {
+        FunctionType::new((
+            vec![
+                ("series", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("title", SyntaxType::string()),
+                ("char", SyntaxType::string()),
+                ("location", SyntaxType::string()),
+                ("color", SyntaxType::color()),
+                ("opacity", SyntaxType::int()),
+                ("offset", SyntaxType::int()),
+                ("text", text_type),
+                ("textcolor", SyntaxType::color()),
+                ("editable", SyntaxType::bool()),
+                ("size", SyntaxType::string()),
+                ("show_last", SyntaxType::int()),
+                ("display", SyntaxType::int()),
+            ],
+            SyntaxType::Void,
+        ))
+    };
+    let func_type = FunctionTypes(vec![
+        gen_type(SyntaxType::string()),
+        gen_type(SyntaxType::string_series()),
+    ]);
     let syntax_type = SyntaxType::Function(Rc::new(func_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
@@ -96,13 +177,15 @@ mod tests {
     fn plot_info_test() {
         use crate::runtime::OutputInfo;
 
+        use crate::libs::{location, size};
+
         let lib_info = LibInfo::new(
-            vec![declare_var()],
+            vec![declare_var(), location::declare_var(), size::declare_var()],
             vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
         );
-        let src = r"plotchar(close, title='Title', char='h', location='a', color=#00ffaa, 
+        let src = r"plotchar(close, title='Title', char='h', location=location.top, color=#00ffaa, 
             opacity=70, offset=15, text='hello', textcolor=#111111, 
-            editable=true, size='t', show_last=100, display=1)";
+            editable=true, size=size.tiny, show_last=100, display=1)";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
 
@@ -120,17 +203,84 @@ mod tests {
             &vec![OutputInfo::PlotChar(PlotCharInfo {
                 title: Some(String::from("Title")),
                 char: Some(String::from("h")),
-                location: Some(String::from("a")),
+                location: Some(String::from("top")),
                 color: Some(String::from("#00ffaa")),
                 opacity: Some(70),
                 offset: Some(15),
                 text: Some(String::from("hello")),
                 textcolor: Some(String::from("#111111")),
                 editable: Some(true),
-                size: Some(String::from("t")),
+                size: Some(String::from("tiny")),
                 show_last: Some(100),
                 display: Some(1)
             })]
         )
     }
+
+    #[test]
+    fn series_text_test() {
+        use crate::runtime::{downcast_ctx, OutputInfo};
+
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        );
+        let src = "t = close > 1 ? 'up' : close < 1 ? 'down' : na\nplotchar(close, text=t)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(2f64), Some(0f64), Some(1f64), Some(3f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+
+        match &runner.get_io_info().get_outputs()[0] {
+            OutputInfo::PlotChar(info) => assert_eq!(info.text, None),
+            _ => unreachable!(),
+        }
+        let data = downcast_ctx(runner.get_context()).move_output_data();
+        assert_eq!(
+            data[0].as_ref().unwrap().texts,
+            vec![StrOptionsData {
+                options: vec![String::from("up"), String::from("down")],
+                values: vec![Some(0), Some(1), None, Some(0)]
+            }]
+        );
+    }
+
+    #[test]
+    fn plotchar_invalid_test() {
+        use crate::libs::size;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), size::declare_var()],
+            vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+        );
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+        for (src, name) in [
+            ("plotchar(close, char='ab')", "char"),
+            ("plotchar(close, size='big')", "size"),
+            ("plotchar(close, location='left')", "location"),
+        ] {
+            let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+            let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+            assert_eq!(
+                runner.run(&data, None).err().map(|e| e.code),
+                Some(RuntimeErr::InvalidParameters(str_replace(
+                    INVALID_VALS,
+                    vec![String::from(name)]
+                )))
+            );
+        }
+
+        let blk = PineParser::new("plotchar(close, char='❄', size=size.small)", &lib_info)
+            .parse_blk()
+            .unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert!(runner.run(&data, None).is_ok());
+    }
 }
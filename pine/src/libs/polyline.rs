@@ -0,0 +1,396 @@
+use super::array::pine_ref_to_array;
+use super::chart::{pine_ref_to_point, ChartPoint, POINT_ARRAY_CLASS};
+use super::line::{StyleEnum, XlocEnum};
+use super::xloc::{bar_index_to_time, time_to_bar_index};
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{move_element, pine_ref_to_bool, pine_ref_to_color, pine_ref_to_i64};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::drawing::DrawingKind;
+use crate::types::{
+    downcast_pf, Callable, CallableFactory, CallableObject, DataType, Float, Int, PineClass,
+    PineFrom, PineRef, PineStaticType, PineType, RefData, RuntimeErr, SecondType, Series,
+    SeriesCall, SimpleType, NA,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+pub type PerPolylineItem = Rc<RefCell<Option<PerPolyline>>>;
+
+fn is_polyline_na<'a>(val: Option<PineRef<'a>>) -> bool {
+    if val.is_none() {
+        return true;
+    }
+    match PerPolylineItem::implicity_from(val.unwrap()) {
+        Ok(res) => {
+            let item: &PerPolylineItem = res.deref();
+            item.borrow().is_none()
+        }
+        Err(_) => true,
+    }
+}
+
+fn pine_ref_to_polyline<'a>(val: Option<PineRef<'a>>) -> PerPolylineItem {
+    if val.is_none() {
+        return Rc::new(RefCell::new(None));
+    }
+    match PerPolylineItem::implicity_from(val.unwrap()) {
+        Ok(res) => res.into_inner(),
+        Err(_) => Rc::new(RefCell::new(None)),
+    }
+}
+
+// The polyline definition that connects the points by the straight or curved segments. It is
+// serialized for the host to draw it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerPolyline {
+    pub points: Vec<ChartPoint>,
+    pub curved: bool,
+    pub closed: bool,
+    pub xloc: i32,
+    pub line_color: Option<String>,
+    pub fill_color: Option<String>,
+    pub line_style: i32,
+    pub line_width: Option<i32>,
+}
+
+impl PerPolyline {
+    pub fn new(points: Vec<ChartPoint>) -> PerPolyline {
+        PerPolyline {
+            points,
+            ..PerPolyline::default()
+        }
+    }
+
+    // Get the points as (bar index, price), the point without the index is located by its time.
+    pub fn get_bar_index_points(&self, times: &[Int]) -> Vec<(Int, Float)> {
+        self.points
+            .iter()
+            .map(|p| match (self.xloc, p.index) {
+                (v, Some(index)) if v == XlocEnum::BarIndex as i32 => (Some(index), p.price),
+                _ => (p.time.and_then(|t| time_to_bar_index(times, t)), p.price),
+            })
+            .collect()
+    }
+
+    // Get the points as (bar time, price), the point without the time is located by its index.
+    pub fn get_bar_time_points(&self, times: &[Int]) -> Vec<(Int, Float)> {
+        self.points
+            .iter()
+            .map(|p| match (self.xloc, p.time) {
+                (v, Some(time)) if v == XlocEnum::BarTime as i32 => (Some(time), p.price),
+                _ => (p.index.and_then(|i| bar_index_to_time(times, i)), p.price),
+            })
+            .collect()
+    }
+}
+
+impl PineStaticType for PerPolylineItem {
+    fn static_type() -> (DataType, SecondType) {
+        (DataType::Polyline, SecondType::Simple)
+    }
+}
+
+impl<'a> PineFrom<'a, PerPolylineItem> for PerPolylineItem {
+    fn implicity_from(t: PineRef<'a>) -> Result<RefData<PerPolylineItem>, RuntimeErr> {
+        match t.get_type() {
+            (DataType::Polyline, SecondType::Simple) => {
+                Ok(downcast_pf::<PerPolylineItem>(t).unwrap())
+            }
+            (DataType::Polyline, SecondType::Series) => {
+                let f: RefData<Series<PerPolylineItem>> =
+                    downcast_pf::<Series<PerPolylineItem>>(t).unwrap();
+                Ok(RefData::new(f.get_current()))
+            }
+            (DataType::NA, _) => Ok(RefData::new(Rc::new(RefCell::new(None)))),
+            _ => Err(RuntimeErr::UnknownRuntimeErr),
+        }
+    }
+}
+
+impl<'a> PineType<'a> for PerPolylineItem {
+    fn get_type(&self) -> (DataType, SecondType) {
+        (DataType::Polyline, SecondType::Simple)
+    }
+    fn copy(&self) -> PineRef<'a> {
+        PineRef::new(self.clone())
+    }
+}
+
+impl SimpleType for PerPolylineItem {}
+
+// The polyline invocation that creates the new polyline object.
+#[derive(Debug)]
+struct PolylineFromNaVal<'a> {
+    polylines: RefData<Series<'a, PerPolylineItem>>,
+}
+
+impl<'a> Clone for PolylineFromNaVal<'a> {
+    fn clone(&self) -> Self {
+        PolylineFromNaVal {
+            polylines: RefData::clone(&self.polylines),
+        }
+    }
+}
+
+impl<'a> PolylineFromNaVal<'a> {
+    fn new() -> PolylineFromNaVal<'a> {
+        PolylineFromNaVal {
+            polylines: RefData::new(Series::from(Rc::new(RefCell::new(None)))),
+        }
+    }
+}
+
+impl<'a> SeriesCall<'a> for PolylineFromNaVal<'a> {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut p: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if p.len() == 1 {
+            let v = p[0].take();
+            match is_polyline_na(v.clone()) {
+                true => Ok(RefData::clone(&self.polylines).into_pf()),
+                false => Ok(Series::<'a, PerPolylineItem>::implicity_from(v.unwrap())
+                    .unwrap()
+                    .into_pf()),
+            }
+        } else {
+            move_tuplet!(
+                (points, curved, closed, xloc, line_color, fill_color, line_style, line_width) = p
+            );
+            // The na points are skipped.
+            let points: Vec<Option<ChartPoint>> =
+                pine_ref_to_array(points, pine_ref_to_point, Some);
+            let polyline = PerPolyline {
+                points: points.into_iter().flatten().collect(),
+                curved: pine_ref_to_bool(curved).unwrap_or(false),
+                closed: pine_ref_to_bool(closed).unwrap_or(false),
+                xloc: XlocEnum::from_pf(xloc)? as i32,
+                line_color: pine_ref_to_color(line_color),
+                fill_color: pine_ref_to_color(fill_color),
+                line_style: StyleEnum::from_pf(line_style)? as i32,
+                line_width: pine_ref_to_i64(line_width).map(|v| v as i32),
+            };
+            let item = Rc::new(RefCell::new(Some(polyline)));
+            downcast_ctx(context).add_drawing(DrawingKind::Polyline, item.clone());
+            self.polylines.update(item);
+            Ok(RefData::clone(&self.polylines).into_pf())
+        }
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+fn delete_func<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let id = param[0].take();
+    let polyline = pine_ref_to_polyline(id);
+    polyline.replace(None);
+    Ok(PineRef::new(NA))
+}
+
+struct PolylineProps;
+
+impl<'a> PineClass<'a> for PolylineProps {
+    fn custom_type(&self) -> &str {
+        "polyline"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "new" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(None, Some(Box::new(PolylineFromNaVal::new())))
+            }))),
+            "delete" => Ok(PineRef::new(Callable::new(Some(delete_func), None))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("polyline")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(PolylineProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "polyline";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableObject::new(Box::new(PolylineProps), || {
+        Callable::new(None, Some(Box::new(PolylineFromNaVal::new())))
+    }));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![("x", SyntaxType::Simple(SimpleSyntaxType::Na))],
+        SyntaxType::ObjectClass("polyline"),
+    ))]);
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert(
+        "delete",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![("id", SyntaxType::ObjectClass("polyline"))],
+            SyntaxType::Void,
+        ))]))),
+    );
+    obj_type.insert(
+        "new",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("points", SyntaxType::ObjectClass(POINT_ARRAY_CLASS)),
+                ("curved", SyntaxType::bool()),
+                ("closed", SyntaxType::bool()),
+                ("xloc", SyntaxType::string_series()),
+                ("line_color", SyntaxType::color_series()),
+                ("fill_color", SyntaxType::color_series()),
+                ("line_style", SyntaxType::string_series()),
+                ("line_width", SyntaxType::int_series()),
+            ],
+            SyntaxType::ObjectClass("polyline"),
+        ))]))),
+    );
+
+    let syntax_type = SyntaxType::ObjectFunction(Rc::new(obj_type), Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::libs::{chart, study};
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    fn point(time: Int, index: Int, price: Float) -> ChartPoint {
+        ChartPoint { time, index, price }
+    }
+
+    #[test]
+    fn polyline_new_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), chart::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"
+        pts = [chart.point.from_index(0, close), chart.point.from_time(100, close + 1)]
+        x = polyline.new(pts, curved=true, line_color=#ff0000, line_style="dashed", line_width=2)
+        y = polyline(na)
+        y := polyline.new([chart.point.from_index(1, close)], closed=true)
+        polyline.delete(y[1])
+        "#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(runner.get_drawing_stats().polylines, 3);
+
+        let mut polyline = PerPolyline::new(vec![
+            point(None, Some(0), Some(2f64)),
+            point(Some(100), None, Some(3f64)),
+        ]);
+        polyline.curved = true;
+        polyline.line_color = Some(String::from("#ff0000"));
+        polyline.line_style = StyleEnum::Dashed as i32;
+        polyline.line_width = Some(2);
+        let result = runner.get_context().move_var(VarIndex::new(1, 0)).unwrap();
+        let mut first = polyline.clone();
+        first.points = vec![
+            point(None, Some(0), Some(1f64)),
+            point(Some(100), None, Some(2f64)),
+        ];
+        assert_eq!(
+            Series::implicity_from(result).unwrap(),
+            RefData::new(Series::from_vec(vec![
+                Rc::new(RefCell::new(Some(first))),
+                Rc::new(RefCell::new(Some(polyline)))
+            ]))
+        );
+
+        // The polyline of the first bar is deleted by the second bar.
+        let result = runner.get_context().move_var(VarIndex::new(2, 0)).unwrap();
+        let mut polyline = PerPolyline::new(vec![point(None, Some(1), Some(2f64))]);
+        polyline.closed = true;
+        assert_eq!(
+            Series::implicity_from(result).unwrap(),
+            RefData::new(Series::from_vec(vec![
+                Rc::new(RefCell::new(None)),
+                Rc::new(RefCell::new(Some(polyline)))
+            ]))
+        );
+    }
+
+    #[test]
+    fn polyline_points_test() {
+        let times = vec![Some(100), Some(200), Some(300)];
+        let mut polyline = PerPolyline::new(vec![
+            point(Some(200), Some(0), Some(1f64)),
+            point(None, Some(4), Some(2f64)),
+            point(Some(300), None, Some(3f64)),
+        ]);
+        assert_eq!(
+            polyline.get_bar_index_points(&times),
+            vec![
+                (Some(0), Some(1f64)),
+                (Some(4), Some(2f64)),
+                (Some(2), Some(3f64))
+            ]
+        );
+        polyline.xloc = XlocEnum::BarTime as i32;
+        assert_eq!(
+            polyline.get_bar_time_points(&times),
+            vec![
+                (Some(200), Some(1f64)),
+                (Some(500), Some(2f64)),
+                (Some(300), Some(3f64))
+            ]
+        );
+
+        let json = serde_json::to_string(&polyline).unwrap();
+        assert!(json.starts_with(r#"{"points":[{"time":200,"index":0,"price":1.0},"#));
+        assert_eq!(
+            serde_json::from_str::<PerPolyline>(&json).unwrap(),
+            polyline
+        );
+    }
+
+    #[test]
+    fn max_polylines_count_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), chart::declare_var(), study::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "study('hello', max_polylines_count=2)\n\
+                   x = polyline.new([chart.point.from_index(0, close)])";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64); 4]))],
+                None,
+            )
+            .unwrap();
+        let stats = runner.get_drawing_stats();
+        assert_eq!((stats.lines, stats.polylines), (0, 2));
+    }
+}
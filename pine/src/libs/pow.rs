@@ -35,10 +35,13 @@ impl<'a> SeriesCall<'a> for MathCallVal {
     }
 }
 
-pub fn declare_var<'a>() -> VarResult<'a> {
-    let value = PineRef::new(Callable::new(None, Some(Box::new(MathCallVal))));
+pub fn pow_callable<'a>() -> Callable<'a> {
+    Callable::new(None, Some(Box::new(MathCallVal)))
+}
 
-    let func_type = FunctionTypes(vec![
+// The int arguments are converted to float, so the result is always float.
+pub fn gen_pow_type<'a>() -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![
         FunctionType::new((
             vec![
                 ("base", SyntaxType::float()),
@@ -53,9 +56,11 @@ pub fn declare_var<'a>() -> VarResult<'a> {
             ],
             SyntaxType::float_series(),
         )),
-    ]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
-    VarResult::new(value, syntax_type, "pow")
+    ])))
+}
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    VarResult::new(PineRef::new(pow_callable()), gen_pow_type(), "pow")
 }
 
 #[cfg(test)]
@@ -0,0 +1,562 @@
+use super::security::{gen_lower_tf_type, SecurityInfo};
+use super::VarResult;
+use crate::ast::stat_expr_types::VarIndex;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{move_element, pine_ref_to_bool, pine_ref_to_i64, pine_ref_to_string};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::data_src::{RequestData, RequestDesc, RequestKind};
+use crate::types::{
+    Callable, CallableFactory, Float, Object, PineClass, PineRef, RuntimeErr, Series, SeriesCall,
+};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// Build the request descriptor from the arguments (symbol, field, timeframe, gaps).
+fn gen_desc<'a>(
+    kind: RequestKind,
+    symbol: Option<PineRef<'a>>,
+    field: Option<PineRef<'a>>,
+    timeframe: Option<PineRef<'a>>,
+    gaps: Option<PineRef<'a>>,
+) -> Result<RequestDesc, RuntimeErr> {
+    match pine_ref_to_string(symbol) {
+        Some(symbol) => Ok(RequestDesc {
+            kind,
+            symbol,
+            timeframe: pine_ref_to_string(timeframe),
+            field: pine_ref_to_string(field),
+            gaps: pine_ref_to_bool(gaps).unwrap_or(false),
+        }),
+        None => Err(RuntimeErr::InvalidParameters(str_replace(
+            REQUIRED_PARAMETERS,
+            vec![String::from("symbol")],
+        ))),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RequestVal {
+    kind: RequestKind,
+    desc: Option<RequestDesc>,
+    data: Option<Rc<RequestData>>,
+    time_index: Option<VarIndex>,
+    // The index of the next data point that has not been returned.
+    next_index: usize,
+    last_val: Float,
+    // The cursor and the last value before each bar to restore them when the bar is rolled back.
+    state_history: Vec<(usize, Float)>,
+}
+
+impl RequestVal {
+    fn new(kind: RequestKind) -> RequestVal {
+        RequestVal {
+            kind,
+            desc: None,
+            data: None,
+            time_index: None,
+            next_index: 0,
+            last_val: None,
+            state_history: vec![],
+        }
+    }
+
+    fn init<'a>(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+    ) -> Result<(), RuntimeErr> {
+        let desc = match self.kind {
+            RequestKind::Financial => {
+                move_tuplet!((symbol, financial_id, period, gaps) = param);
+                gen_desc(self.kind.clone(), symbol, financial_id, period, gaps)?
+            }
+            RequestKind::Dividends | RequestKind::Splits => {
+                move_tuplet!((ticker, field, gaps) = param);
+                gen_desc(self.kind.clone(), ticker, field, None, gaps)?
+            }
+            RequestKind::Economic => {
+                move_tuplet!((country_code, field, gaps) = param);
+                gen_desc(self.kind.clone(), country_code, field, None, gaps)?
+            }
+            RequestKind::External => {
+                move_tuplet!((id, timeframe, gaps) = param);
+                gen_desc(self.kind.clone(), id, None, timeframe, gaps)?
+            }
+        };
+        // The same request of the other calls is fetched from the host only once in the run.
+        self.data = match downcast_ctx(context).get_request_data(&desc) {
+            Some(data) => data,
+            None => {
+                // The series registered by the host are preferred to the data request.
+                let registered = match downcast_ctx(context).get_external_series() {
+                    Some(series) => series.get(&desc),
+                    None => None,
+                };
+                let data = match (registered, context.get_callback()) {
+                    (Some(data), _) => Some(data),
+                    (None, Some(callback)) => match callback.get_data_request() {
+                        Some(request) => request.request(&desc).map(Rc::new),
+                        None => None,
+                    },
+                    (None, None) => None,
+                };
+                downcast_ctx(context).insert_request_data(desc.clone(), data.clone());
+                data
+            }
+        };
+        self.desc = Some(desc);
+        self.time_index = downcast_ctx(context).get_top_varname_index("_time");
+        Ok(())
+    }
+
+    // Get the value of the latest data point whose time is not greater than the current time.
+    // The cursor is advanced from the last bar, so the data points are scanned only once.
+    fn get_val(&mut self, cur_time: i64) -> Float {
+        let data = self.data.as_ref().unwrap();
+        let mut end_index = self.next_index.min(data.time.len());
+        while end_index < data.time.len() && data.time[end_index] <= cur_time {
+            end_index += 1;
+        }
+        if end_index > self.next_index {
+            self.next_index = end_index;
+            self.last_val = data.values.get(end_index - 1).cloned().flatten();
+            self.last_val
+        } else if self.desc.as_ref().unwrap().gaps {
+            None
+        } else {
+            self.last_val
+        }
+    }
+}
+
+impl<'a> SeriesCall<'a> for RequestVal {
+    fn step(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.desc.is_none() {
+            self.init(context, param)?;
+        }
+        self.state_history.push((self.next_index, self.last_val));
+        let cur_time = match &self.time_index {
+            Some(index) => pine_ref_to_i64(context.get_var(*index).clone()),
+            None => None,
+        };
+        let val = match (cur_time, &self.data) {
+            (Some(cur_time), Some(_)) => self.get_val(cur_time),
+            _ => None,
+        };
+        Ok(PineRef::new_rc(Series::from(val)))
+    }
+
+    // The data is requested again for the updated bars, the cursor is kept for the data points
+    // that have been returned.
+    fn run(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.desc = None;
+        self.data = None;
+        Ok(())
+    }
+
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        if let Some((next_index, last_val)) = self.state_history.pop() {
+            self.next_index = next_index;
+            self.last_val = last_val;
+        }
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(RequestVal::new(self.kind.clone()))
+    }
+}
+
+struct RequestProps;
+
+impl<'a> PineClass<'a> for RequestProps {
+    fn custom_type(&self) -> &str {
+        "request"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "financial" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(RequestVal::new(RequestKind::Financial))),
+                )
+            }))),
+            "dividends" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(
+                    None,
+                    Some(Box::new(RequestVal::new(RequestKind::Dividends))),
+                )
+            }))),
+            "splits" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(None, Some(Box::new(RequestVal::new(RequestKind::Splits))))
+            }))),
+            "economic" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(None, Some(Box::new(RequestVal::new(RequestKind::Economic))))
+            }))),
+            "external" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(None, Some(Box::new(RequestVal::new(RequestKind::External))))
+            }))),
+            "security_lower_tf" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(None, Some(Box::new(SecurityInfo::new_lower_tf())))
+            }))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("request")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(RequestProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "request";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(RequestProps)));
+
+    let mut obj_type = BTreeMap::new();
+    // request.financial(symbol, financial_id, period, gaps) → series[float]
+    obj_type.insert(
+        "financial",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("symbol", SyntaxType::string()),
+                ("financial_id", SyntaxType::string()),
+                ("period", SyntaxType::string()),
+                ("gaps", SyntaxType::bool()),
+            ],
+            SyntaxType::float_series(),
+        ))]))),
+    );
+    // request.dividends(ticker, field, gaps) → series[float]
+    obj_type.insert(
+        "dividends",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("ticker", SyntaxType::string()),
+                ("field", SyntaxType::string()),
+                ("gaps", SyntaxType::bool()),
+            ],
+            SyntaxType::float_series(),
+        ))]))),
+    );
+    // request.splits(ticker, field, gaps) → series[float]
+    obj_type.insert(
+        "splits",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("ticker", SyntaxType::string()),
+                ("field", SyntaxType::string()),
+                ("gaps", SyntaxType::bool()),
+            ],
+            SyntaxType::float_series(),
+        ))]))),
+    );
+    // request.economic(country_code, field, gaps) → series[float]
+    obj_type.insert(
+        "economic",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("country_code", SyntaxType::string()),
+                ("field", SyntaxType::string()),
+                ("gaps", SyntaxType::bool()),
+            ],
+            SyntaxType::float_series(),
+        ))]))),
+    );
+    // request.external(id, timeframe, gaps) → series[float]
+    obj_type.insert(
+        "external",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("id", SyntaxType::string()),
+                ("timeframe", SyntaxType::string()),
+                ("gaps", SyntaxType::bool()),
+            ],
+            SyntaxType::float_series(),
+        ))]))),
+    );
+    // request.security_lower_tf(symbol, resolution, expression) → float[]
+    obj_type.insert("security_lower_tf", gen_lower_tf_type());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::plot;
+    use crate::runtime::data_src::{Callback, DataRequest};
+    use crate::runtime::external::{economic_id, ExternalSeries};
+    use crate::runtime::{AnySeries, OutputData};
+    use crate::{LibInfo, PineParser, PineRunner};
+    use std::cell::RefCell;
+
+    struct MyRequest {
+        descs: RefCell<Vec<RequestDesc>>,
+    }
+
+    impl DataRequest for MyRequest {
+        fn request(&self, desc: &RequestDesc) -> Option<RequestData> {
+            self.descs.borrow_mut().push(desc.clone());
+            Some(RequestData::new(vec![10, 30], vec![Some(1f64), Some(2f64)]))
+        }
+    }
+
+    impl Callback for MyRequest {
+        fn get_data_request(&self) -> Option<&dyn DataRequest> {
+            Some(self)
+        }
+    }
+
+    #[test]
+    fn request_test() {
+        let callback = MyRequest {
+            descs: RefCell::new(vec![]),
+        };
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("_time", SyntaxType::int_series())],
+        );
+        let src = "m1 = request.financial('NASDAQ:AAPL', 'EBITDA', 'FQ')\n\
+                   m2 = request.dividends('NASDAQ:AAPL', 'net', gaps=true)\n\
+                   m3 = request.splits('NASDAQ:AAPL', 'numerator')\n\
+                   plot(m1)\nplot(m2)\nplot(m3)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+
+        runner
+            .run(
+                &vec![(
+                    "_time",
+                    AnySeries::from_int_vec(vec![Some(0), Some(10), Some(20), Some(30)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            callback.descs.borrow().clone(),
+            vec![
+                RequestDesc {
+                    kind: RequestKind::Financial,
+                    symbol: String::from("NASDAQ:AAPL"),
+                    timeframe: Some(String::from("FQ")),
+                    field: Some(String::from("EBITDA")),
+                    gaps: false,
+                },
+                RequestDesc {
+                    kind: RequestKind::Dividends,
+                    symbol: String::from("NASDAQ:AAPL"),
+                    timeframe: None,
+                    field: Some(String::from("net")),
+                    gaps: true,
+                },
+                RequestDesc {
+                    kind: RequestKind::Splits,
+                    symbol: String::from("NASDAQ:AAPL"),
+                    timeframe: None,
+                    field: Some(String::from("numerator")),
+                    gaps: false,
+                },
+            ]
+        );
+
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(1f64),
+                    Some(1f64),
+                    Some(2f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(1f64),
+                    None,
+                    Some(2f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(1f64),
+                    Some(1f64),
+                    Some(2f64)
+                ]])),
+            ]
+        );
+    }
+
+    #[test]
+    fn request_cache_test() {
+        let callback = MyRequest {
+            descs: RefCell::new(vec![]),
+        };
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("_time", SyntaxType::int_series())],
+        );
+        let src = "m1 = request.financial('NASDAQ:AAPL', 'EBITDA', 'FQ')\n\
+                   m2 = request.financial('NASDAQ:AAPL', 'EBITDA', 'FQ')\n\
+                   m3 = request.financial('NASDAQ:AAPL', 'EBITDA', 'FY')\n\
+                   plot(m1 + m2)\nplot(m3)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+
+        runner
+            .run(
+                &vec![(
+                    "_time",
+                    AnySeries::from_int_vec(vec![Some(0), Some(10), Some(20)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(callback.descs.borrow().len(), 2);
+        assert_eq!(
+            runner.move_output_data()[0],
+            Some(OutputData::new(vec![vec![None, Some(2f64), Some(2f64)]]))
+        );
+
+        // The new data may have the new data points, so the host is requested again.
+        runner
+            .update(&vec![(
+                "_time",
+                AnySeries::from_int_vec(vec![Some(20), Some(30)]),
+            )])
+            .unwrap();
+        assert_eq!(callback.descs.borrow().len(), 4);
+        assert_eq!(
+            runner.move_output_data()[0],
+            Some(OutputData::new(vec![vec![Some(2f64), Some(4f64)]]))
+        );
+    }
+
+    #[test]
+    fn request_rollback_test() {
+        let callback = MyRequest {
+            descs: RefCell::new(vec![]),
+        };
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("_time", SyntaxType::int_series())],
+        );
+        let src = "m = request.dividends('NASDAQ:AAPL', 'net', gaps=true)\nplot(m)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+
+        runner
+            .run(
+                &vec![(
+                    "_time",
+                    AnySeries::from_int_vec(vec![Some(0), Some(10), Some(20)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![None, Some(1f64), None]]))]
+        );
+
+        // The rerun bar gets the gap again because the cursor is restored by the rollback.
+        runner
+            .update(&vec![(
+                "_time",
+                AnySeries::from_int_vec(vec![Some(20), Some(30)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![None, Some(2f64)]]))]
+        );
+
+        runner
+            .update(&vec![(
+                "_time",
+                AnySeries::from_int_vec(vec![Some(30), Some(40)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![Some(2f64), None]]))]
+        );
+    }
+
+    #[test]
+    fn external_series_test() {
+        let callback = MyRequest {
+            descs: RefCell::new(vec![]),
+        };
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("_time", SyntaxType::int_series())],
+        );
+        let src = "m1 = request.economic('US', 'GDP')\n\
+                   m2 = request.external('BTCUSDT.FUNDING', '480', gaps=true)\n\
+                   m3 = request.economic('EU', 'GDP')\n\
+                   plot(m1)\nplot(m2)\nplot(m3)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+
+        let mut series = ExternalSeries::new();
+        series.register(
+            &economic_id("US", "GDP"),
+            None,
+            RequestData::new(vec![0, 20], vec![Some(5f64), Some(6f64)]),
+        );
+        series.register(
+            "BTCUSDT.FUNDING",
+            Some("480"),
+            RequestData::new(vec![10], vec![Some(0.01f64)]),
+        );
+        runner.set_external_series(Some(Rc::new(series)));
+        runner
+            .run(
+                &vec![(
+                    "_time",
+                    AnySeries::from_int_vec(vec![Some(0), Some(10), Some(20), Some(30)]),
+                )],
+                None,
+            )
+            .unwrap();
+        // Only the series not registered is requested from the host.
+        assert_eq!(
+            callback.descs.borrow().clone(),
+            vec![RequestDesc {
+                kind: RequestKind::Economic,
+                symbol: String::from("EU"),
+                timeframe: None,
+                field: Some(String::from("GDP")),
+                gaps: false,
+            }]
+        );
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                Some(OutputData::new(vec![vec![
+                    Some(5f64),
+                    Some(5f64),
+                    Some(6f64),
+                    Some(6f64)
+                ]])),
+                Some(OutputData::new(vec![vec![None, Some(0.01f64), None, None]])),
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(1f64),
+                    Some(1f64),
+                    Some(2f64)
+                ]])),
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::ema::ema_func;
-use super::ema::series_rma;
+use super::ema::{rma_func, EmaWarmup, MaState};
 use super::sma::{declare_ma_var, wma_func};
 use super::tr::tr_func;
 use super::VarResult;
@@ -25,14 +25,15 @@ pub fn calc_rsi(
     s0: Float,
     length: i64,
     s1: Float,
-    upwards: &mut Series<Float>,
-    downwards: &mut Series<Float>,
+    warmup: EmaWarmup,
+    upwards: &mut MaState,
+    downwards: &mut MaState,
 ) -> Result<(Float, Float, Float), RuntimeErr> {
     let upward = float_max2(s0.minus(s1), Some(0f64));
     let downward = float_max2(s1.minus(s0), Some(0f64));
 
-    let rma1 = series_rma(upward, length, upwards)?;
-    let rma2 = series_rma(downward, length, downwards)?;
+    let rma1 = upwards.next(warmup, rma_func, upward, length)?;
+    let rma2 = downwards.next(warmup, rma_func, downward, length)?;
     let rs = rma1.div(rma2);
 
     let res = Some(100f64).minus(Some(100f64).div(rs.add(Some(1f64))));
@@ -47,20 +48,20 @@ pub fn calc_rsi_series(s0: Float, s1: Float) -> Result<Float, RuntimeErr> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct KcVal<'a> {
-    upwards: Series<'a, Float>,
-    downwards: Series<'a, Float>,
+pub struct KcVal {
+    upwards: MaState,
+    downwards: MaState,
 }
 
-impl<'a> KcVal<'a> {
-    pub fn new() -> KcVal<'a> {
+impl KcVal {
+    pub fn new() -> KcVal {
         KcVal {
-            upwards: Series::new(),
-            downwards: Series::new(),
+            upwards: MaState::new(),
+            downwards: MaState::new(),
         }
     }
 
-    fn process_rsi(
+    fn process_rsi<'a>(
         &mut self,
         _ctx: &mut dyn Ctx<'a>,
         mut param: Vec<Option<PineRef<'a>>>,
@@ -74,10 +75,15 @@ impl<'a> KcVal<'a> {
                 let length = ge1_param_i64("y", pine_ref_to_i64(y))?;
                 let s0 = series.index_value(0).unwrap();
                 let s1 = series.index_value(1).unwrap();
-                let (res, upward, downward) =
-                    calc_rsi(s0, length, s1, &mut self.upwards, &mut self.downwards)?;
-                self.upwards.commit();
-                self.downwards.commit();
+                let warmup = downcast_ctx(_ctx).get_ema_warmup();
+                let (res, _, _) = calc_rsi(
+                    s0,
+                    length,
+                    s1,
+                    warmup,
+                    &mut self.upwards,
+                    &mut self.downwards,
+                )?;
                 Ok(res)
             }
             _ => {
@@ -92,7 +98,7 @@ impl<'a> KcVal<'a> {
     }
 }
 
-impl<'a> SeriesCall<'a> for KcVal<'a> {
+impl<'a> SeriesCall<'a> for KcVal {
     fn step(
         &mut self,
         _ctx: &mut dyn Ctx<'a>,
@@ -103,6 +109,12 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
         Ok(PineRef::new_rc(Series::from(res)))
     }
 
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.upwards.back();
+        self.downwards.back();
+        Ok(())
+    }
+
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(self.clone())
     }
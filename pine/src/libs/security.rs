@@ -1,6 +1,7 @@
+use super::ticker::TickerId;
 use super::VarResult;
 use crate::ast::input::StrRange;
-use crate::ast::stat_expr_types::VarIndex;
+use crate::ast::stat_expr_types::{FunctionDef, VarIndex};
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
@@ -11,6 +12,7 @@ use crate::helper::{
 use crate::runtime::context::{
     downcast_ctx, downcast_ctx_const, Context, ContextType, Ctx, VarOperate,
 };
+use crate::runtime::data_src::CHART_TIME_NAME;
 use crate::runtime::function::Function;
 use crate::runtime::output::InputSrc;
 use crate::runtime::{AnySeries, AnySeriesType};
@@ -18,18 +20,156 @@ use crate::types::{
     downcast_pf, Callable, CallableFactory, Color, DataType, Float, Int, PineFrom, PineRef,
     RefData, RuntimeErr, Series, SeriesCall, NA,
 };
+use chrono_tz::Tz;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::mem;
 use std::mem::ManuallyDrop;
 use std::rc::Rc;
 
-struct SecurityInfo<'a> {
+// The sub-runtime that runs the expression on the bars of the ticker. The security calls of the
+// same ticker and expression share it by the main context, so the expression runs once on every
+// bar of the ticker however many times it is requested.
+pub struct SecurityEval<'a> {
+    ctx: Box<dyn Ctx<'a>>,
+    fun_def: RefData<Function<'a>>,
+    data_names: Vec<String>,
+    // The results of the ticker bars of the current data, the index is the index of the bar.
+    results: Vec<PineRef<'a>>,
+}
+
+// The key of the shared sub-runtimes, the ticker with the resolution and the hash of the expression.
+pub type SecurityKey = (String, u64);
+
+// Hash the expression function regardless of where it is written, so the same expressions in the
+// different calls get the same hash. The source ranges are skipped in the debug format of the
+// parameters and the body.
+fn hash_expr<'a>(def: &FunctionDef<'a>) -> u64 {
+    let params: Vec<_> = def.params.iter().map(|p| p.value).collect();
+    let text = format!("{:?}{:?}", params, def.body);
+    let mut hasher = DefaultHasher::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("StrRange {") {
+        rest[..start].hash(&mut hasher);
+        let mut depth = 0;
+        let mut end = rest.len();
+        for (i, c) in rest[start..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => continue,
+            }
+            if depth == 0 {
+                end = start + i + 1;
+                break;
+            }
+        }
+        rest = &rest[end..];
+    }
+    rest.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<'a> SecurityEval<'a> {
+    fn new(
+        context: &mut dyn Ctx<'a>,
+        fun_def: RefData<Function<'a>>,
+        ticker: &str,
+    ) -> SecurityEval<'a> {
+        let mut subctx = Box::new(Context::new(Some(context), ContextType::FuncDefBlock));
+        subctx.init(
+            fun_def.get_var_count(),
+            fun_def.get_subctx_count(),
+            fun_def.get_libfun_count(),
+        );
+        let params = &fun_def.get_def().params;
+        let names: Vec<_> = params.iter().map(|s| s.value).collect();
+
+        // bar_index generated by the function, not got from the external.
+        let data_names = names
+            .iter()
+            .map(|&name| match name {
+                "bar_index" => String::from("bar_index"),
+                name => format!("{}-{}", ticker, name),
+            })
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            subctx.set_varname_index(name, i as i32);
+        }
+        let s: Box<dyn Ctx<'a>> = subctx;
+        let ctx = unsafe { mem::transmute::<Box<dyn Ctx<'a>>, Box<dyn Ctx<'a>>>(s) };
+        SecurityEval {
+            ctx,
+            fun_def,
+            data_names,
+            results: vec![],
+        }
+    }
+
+    fn get_input_at(&self, data_name: &str, i: isize) -> PineRef<'a> {
+        match downcast_ctx_const(&*self.ctx).get_input_data(data_name) {
+            None => PineRef::new_rc(Series::from(Int::from(None))),
+            Some(origin_data) => match origin_data.get_type() {
+                AnySeriesType::Int => PineRef::new_rc(Series::from(origin_data.index::<Int>(i))),
+                AnySeriesType::Float => {
+                    PineRef::new_rc(Series::from(origin_data.index::<Float>(i)))
+                }
+            },
+        }
+    }
+
+    fn run_one_index(&mut self, i: isize) -> Result<PineRef<'a>, RuntimeErr> {
+        let input_params: Vec<PineRef<'a>> = self
+            .data_names
+            .iter()
+            .map(|data_name| match data_name.as_str() {
+                "bar_index" => PineRef::new_rc(Series::from(Some(i as i64))),
+                data_name => self.get_input_at(data_name, i),
+            })
+            .collect();
+
+        // TODO: bar_index need be handled specially.
+        let result = self
+            .fun_def
+            .call(&mut *self.ctx, input_params, vec![], StrRange::new_empty());
+        downcast_ctx(&mut *self.ctx).commit();
+        match result {
+            Ok(val) => Ok(val),
+            Err(e) => Err(e.code),
+        }
+    }
+
+    // Get the result of the ticker bar, the bars before it that have not run are run first.
+    fn get_result(&mut self, i: isize) -> Result<PineRef<'a>, RuntimeErr> {
+        while self.results.len() as isize <= i {
+            let result = self.run_one_index(self.results.len() as isize)?;
+            self.results.push(result);
+        }
+        Ok(self.results[i as usize].clone())
+    }
+}
+
+pub(crate) struct SecurityInfo<'a> {
+    // Collect all of the intrabar values of the lower timeframe into an array.
+    lower_tf: bool,
     ticker: Option<String>,
-    ctx: Option<Box<dyn Ctx<'a>>>,
+    eval: Option<Rc<RefCell<SecurityEval<'a>>>>,
     fun_def: Option<RefData<Function<'a>>>,
     time_index: Option<VarIndex>,
     start_time_data_index: isize,
-    data_names: Vec<String>,
     last_result: Option<PineRef<'a>>,
+    // The time and the collected intrabar values of the last chart bar.
+    intrabar_values: (Option<i64>, Vec<Float>),
+    // The states before the chart bars to restore them when the bars are rolled back.
+    state_history: Vec<SecurityState<'a>>,
+}
+
+// The progress of the ticker bars and the results of the last chart bar.
+struct SecurityState<'a> {
+    start_time_data_index: isize,
+    last_result: Option<PineRef<'a>>,
+    intrabar_values: (Option<i64>, Vec<Float>),
 }
 
 fn gen_ticker<'a>(
@@ -58,13 +198,22 @@ fn get_func<'a>(func: Option<PineRef<'a>>) -> Result<RefData<Function<'a>>, Runt
 impl<'a> SecurityInfo<'a> {
     pub fn new() -> SecurityInfo<'a> {
         SecurityInfo {
+            lower_tf: false,
             ticker: None,
-            ctx: None,
+            eval: None,
             fun_def: None,
             time_index: None,
             start_time_data_index: 0,
-            data_names: vec![],
             last_result: None,
+            intrabar_values: (None, vec![]),
+            state_history: vec![],
+        }
+    }
+
+    pub fn new_lower_tf() -> SecurityInfo<'a> {
+        SecurityInfo {
+            lower_tf: true,
+            ..SecurityInfo::new()
         }
     }
 
@@ -75,6 +224,11 @@ impl<'a> SecurityInfo<'a> {
         resolution: Option<PineRef<'a>>,
         expression: Option<PineRef<'a>>,
     ) -> Result<(), RuntimeErr> {
+        // The transformed bars are calculated from all the origin OHLCV series.
+        let is_transformed = match pine_ref_to_string(symbol.clone()) {
+            Some(s) => TickerId::parse(&s).transform.is_some(),
+            None => false,
+        };
         self.ticker = Some(gen_ticker(symbol, resolution)?);
         let func_ins = get_func(expression)?;
         let var_i = downcast_ctx(_context)
@@ -98,81 +252,37 @@ impl<'a> SecurityInfo<'a> {
         if !names.contains(&String::from("time")) {
             names.push(String::from("time"));
         }
+        if is_transformed {
+            for src in ["open", "high", "low", "close", "volume"].iter() {
+                if !names.iter().any(|n| n == src) {
+                    names.push(String::from(*src));
+                }
+            }
+        }
         // Add the new ticker information to input sources.
         downcast_ctx(_context).add_input_src(InputSrc::new(self.ticker.clone(), names));
         Ok(())
     }
 
-    pub fn init_subctx(&mut self, _context: &mut dyn Ctx<'a>) {
-        let mut subctx = Box::new(Context::new(Some(_context), ContextType::FuncDefBlock));
-        let func_ins = self.fun_def.as_ref().unwrap();
-        subctx.init(
-            func_ins.get_var_count(),
-            func_ins.get_subctx_count(),
-            func_ins.get_libfun_count(),
-        );
-        let params = &func_ins.get_def().params;
-        let names: Vec<_> = params.iter().map(|s| s.value).collect();
-
-        let ticker = self.ticker.as_ref().unwrap();
-        // bar_index generated by the function, not got from the external.
-        self.data_names = names
-            .iter()
-            .map(|&name| match name {
-                "bar_index" => String::from("bar_index"),
-                name => format!("{}-{}", ticker, name),
-            })
-            .collect();
-        for (i, name) in names.iter().enumerate() {
-            subctx.set_varname_index(name, i as i32);
-        }
-        let s: Box<dyn Ctx<'a>> = subctx;
-        let ctx = unsafe { mem::transmute::<Box<dyn Ctx<'a>>, Box<dyn Ctx<'a>>>(s) };
-        self.ctx = Some(ctx);
-    }
-
-    fn get_subctx(&mut self) -> &mut dyn Ctx<'a> {
-        &mut **self.ctx.as_mut().unwrap()
-    }
-
-    fn get_input_at(&self, data_name: &str, i: isize) -> PineRef<'a> {
-        let subctx = &**self.ctx.as_ref().unwrap();
-
-        match downcast_ctx_const(subctx).get_input_data(data_name) {
-            None => PineRef::new_rc(Series::from(Int::from(None))),
-            Some(origin_data) => match origin_data.get_type() {
-                AnySeriesType::Int => {
-                    PineRef::new_rc(Series::from(origin_data.index::<Int>(i as isize)))
-                }
-                AnySeriesType::Float => {
-                    PineRef::new_rc(Series::from(origin_data.index::<Float>(i as isize)))
-                }
-            },
-        }
+    // Get the shared sub-runtime of the ticker and the expression, it is created by the first call.
+    fn init_eval(&mut self, _context: &mut dyn Ctx<'a>) {
+        let ticker = self.ticker.clone().unwrap();
+        let fun_def = self.fun_def.take().unwrap();
+        let key = (ticker, hash_expr(fun_def.get_def()));
+        let eval = match downcast_ctx(_context).get_security_eval(&key) {
+            Some(eval) => eval,
+            None => {
+                let eval = SecurityEval::new(_context, fun_def, &key.0);
+                let eval = Rc::new(RefCell::new(eval));
+                downcast_ctx(_context).insert_security_eval(key, eval.clone());
+                eval
+            }
+        };
+        self.eval = Some(eval);
     }
 
-    fn run_one_index(&mut self, i: isize) -> Result<PineRef<'a>, RuntimeErr> {
-        let input_params: Vec<PineRef<'a>> = self
-            .data_names
-            .iter()
-            .map(|data_name| match data_name.as_str() {
-                "bar_index" => PineRef::new_rc(Series::from(Some(i as i64))),
-                data_name => self.get_input_at(data_name, i),
-            })
-            .collect();
-
-        // TODO: bar_index need be handled specially.
-        let result = self.fun_def.as_mut().unwrap().call(
-            &mut **self.ctx.as_mut().unwrap(),
-            input_params,
-            vec![],
-            StrRange::new_empty(),
-        );
-        downcast_ctx(self.get_subctx()).commit();
-        match result {
-            Ok(val) => Ok(val),
-            Err(e) => Err(e.code),
-        }
+    fn get_result(&self, i: isize) -> Result<PineRef<'a>, RuntimeErr> {
+        self.eval.as_ref().unwrap().borrow_mut().get_result(i)
     }
 }
 
@@ -189,6 +299,16 @@ fn find_nearest_index(data: &ManuallyDrop<Vec<Int>>, val: &Int, is_ge: bool) ->
     }
 }
 
+// Get the time of the chart bar after the bar at the current time.
+fn get_next_chart_time<'a>(context: &mut dyn Ctx<'a>, cur_time: i64) -> Option<i64> {
+    let series = downcast_ctx(context).get_input_data(CHART_TIME_NAME)?;
+    let times = series.as_vec::<Int>();
+    match times.binary_search(&Some(cur_time)) {
+        Ok(index) => times.get(index + 1).cloned().flatten(),
+        Err(index) => times.get(index).cloned().flatten(),
+    }
+}
+
 fn gen_def_val<'a>(func_type: &FunctionType<'a>) -> Option<PineRef<'a>> {
     match &func_type.signature.1 {
         &SyntaxType::Series(SimpleSyntaxType::Int) => {
@@ -199,7 +319,7 @@ fn gen_def_val<'a>(func_type: &FunctionType<'a>) -> Option<PineRef<'a>> {
         }
         &SyntaxType::Series(SimpleSyntaxType::Bool) => Some(PineRef::new_rc(Series::from(false))),
         &SyntaxType::Series(SimpleSyntaxType::Color) => {
-            Some(PineRef::new_rc(Series::from(Color(""))))
+            Some(PineRef::new_rc(Series::from(Color::new(""))))
         }
         &SyntaxType::Series(SimpleSyntaxType::String) => {
             Some(PineRef::new_rc(Series::from(String::from(""))))
@@ -208,6 +328,81 @@ fn gen_def_val<'a>(func_type: &FunctionType<'a>) -> Option<PineRef<'a>> {
     }
 }
 
+impl<'a> SecurityInfo<'a> {
+    // Aggregate the bars of the ticker from the base resolution bars when the host does not
+    // provide the bars of the requested resolution.
+    fn aggregate_bars(&mut self, context: &mut dyn Ctx<'a>) -> bool {
+        let ticker = self.ticker.clone().unwrap();
+        let symbol = match ticker.rfind('-') {
+            Some(i) => &ticker[..i],
+            None => return false,
+        };
+        if TickerId::parse(symbol).transform.is_some() {
+            return false;
+        }
+        // The bars are aggregated in the timezone of the symbol like the `time` function.
+        let (is_chart_symbol, tz) = match downcast_ctx(context.get_main_ctx()).get_syminfo() {
+            Some(info) => (
+                symbol == info.ticker || symbol.ends_with(&format!(":{}", info.ticker)),
+                info.timezone.parse().unwrap_or(Tz::America__New_York),
+            ),
+            None => (true, Tz::America__New_York),
+        };
+        downcast_ctx(context).aggregate_input_data(&ticker, is_chart_symbol, &tz)
+    }
+
+    // Run the expression on all of the intrabars whose time is in the range of the current
+    // chart bar, that is from the current bar time to the next chart bar time.
+    fn step_lower_tf(
+        &mut self,
+        _context: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((symbol, resolution, expression) = param);
+
+        if !downcast_ctx(_context).check_is_input_info_ready() {
+            self.init_input_info(_context, symbol, resolution, expression)?;
+        }
+        if self.eval.is_none() {
+            self.init_eval(_context);
+        }
+
+        let time_index = self.time_index.unwrap();
+        let cur_time = match pine_ref_to_i64(_context.get_var(time_index).clone()) {
+            None => return Ok(PineRef::new_rc(Vec::<Float>::new())),
+            Some(cur_time) => cur_time,
+        };
+        let next_time = get_next_chart_time(_context, cur_time);
+        let time_name = format!("{}-_time", self.ticker.as_ref().unwrap());
+        let (start_index, end_index) = match downcast_ctx(_context).get_input_data(&time_name) {
+            None => return Ok(PineRef::new_rc(Vec::<Float>::new())),
+            Some(series) => {
+                let time_data = series.as_vec::<Int>();
+                // The intrabars before the current bar are run but not collected.
+                let start_index = find_nearest_index(&time_data, &Some(cur_time), true);
+                let end_index = match next_time {
+                    Some(t) => find_nearest_index(&time_data, &Some(t), true),
+                    None => time_data.len() as isize,
+                };
+                (start_index, end_index)
+            }
+        };
+
+        // The chart bar may be run again with the new intrabars in the realtime.
+        if self.intrabar_values.0 != Some(cur_time) {
+            self.intrabar_values = (Some(cur_time), vec![]);
+        }
+        for i in self.start_time_data_index..end_index {
+            let result = self.get_result(i)?;
+            if i >= start_index {
+                self.intrabar_values.1.push(pine_ref_to_f64(Some(result)));
+            }
+        }
+        self.start_time_data_index = self.start_time_data_index.max(end_index);
+        Ok(PineRef::new_rc(self.intrabar_values.1.clone()))
+    }
+}
+
 impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
     fn step(
         &mut self,
@@ -215,14 +410,22 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
         mut param: Vec<Option<PineRef<'a>>>,
         _func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
+        self.state_history.push(SecurityState {
+            start_time_data_index: self.start_time_data_index,
+            last_result: self.last_result.clone(),
+            intrabar_values: self.intrabar_values.clone(),
+        });
+        if self.lower_tf {
+            return self.step_lower_tf(_context, param);
+        }
         move_tuplet!((symbol, resolution, expression, gaps, lookahead) = param);
 
         if !downcast_ctx(_context).check_is_input_info_ready() {
             self.init_input_info(_context, symbol, resolution, expression)?;
         }
-        // Crate the sub context to run the function.
-        if self.ctx.is_none() {
-            self.init_subctx(_context);
+        // Get the sub context to run the function.
+        if self.eval.is_none() {
+            self.init_eval(_context);
         }
         let gaps = pine_ref_to_bool(gaps).unwrap_or(false);
         let lookahead = pine_ref_to_bool(lookahead).unwrap_or(false);
@@ -234,26 +437,32 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
             None => Ok(PineRef::new_box(NA)),
             Some(cur_time) => {
                 let time_name = format!("{}-_time", self.ticker.as_ref().unwrap());
-                match downcast_ctx(self.get_subctx()).get_input_data(&time_name) {
+                if downcast_ctx(_context).get_input_data(&time_name).is_none() {
+                    self.aggregate_bars(_context);
+                }
+                match downcast_ctx(_context).get_input_data(&time_name) {
                     Some(series) => {
                         let time_data = series.as_vec::<Int>();
                         // If the lookahead is false, we will find the point that the time is equal or less thant current time.
                         // else if the lookahead is true, we will find the point that the time is equal or greater than current time.
-                        let end_index =
+                        let mut end_index =
                             find_nearest_index(&time_data, &Some(cur_time), lookahead) + 1;
+                        // In the realtime, the bar that contains the current time may not exist yet,
+                        // so the lookahead only gets the latest bar like the request without lookahead.
+                        if lookahead {
+                            end_index = end_index.min(time_data.len() as isize);
+                        }
 
                         // Will run the data in the range start_time_data_index..end_index
                         if end_index > self.start_time_data_index {
-                            let mut results: Vec<PineRef<'a>> = vec![];
-                            for i in self.start_time_data_index..end_index {
-                                results.push(self.run_one_index(i)?);
-                            }
+                            let first = self.get_result(self.start_time_data_index)?;
+                            let last = self.get_result(end_index - 1)?;
                             self.start_time_data_index = end_index;
-                            self.last_result = Some(results.last().unwrap().clone());
+                            self.last_result = Some(last.clone());
                             if lookahead {
-                                return Ok(results.last().unwrap().clone());
+                                Ok(last)
                             } else {
-                                return Ok(results[0].clone());
+                                Ok(first)
                             }
                         } else {
                             // If this time has no data, then return na value for gaps=true.
@@ -273,26 +482,59 @@ impl<'a> SeriesCall<'a> for SecurityInfo<'a> {
     }
 
     fn run(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        // The ticker bars of the next data are indexed from 0 again.
+        if let Some(eval) = &self.eval {
+            eval.borrow_mut().results.clear();
+        }
         self.start_time_data_index = 0;
         self.last_result = None;
+        self.intrabar_values = (None, vec![]);
+        self.state_history.clear();
+        Ok(())
+    }
+
+    // The bar is run again after the rollback, e.g. by the intrabar calculation of the strategy,
+    // so the ticker bars of the bar are run and collected again.
+    fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        if let Some(state) = self.state_history.pop() {
+            self.start_time_data_index = state.start_time_data_index;
+            self.last_result = state.last_result;
+            self.intrabar_values = state.intrabar_values;
+        }
         Ok(())
     }
 
     fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
         Box::new(SecurityInfo {
+            lower_tf: self.lower_tf,
             ticker: self.ticker.clone(),
-            ctx: None,
+            eval: None,
             fun_def: None,
             time_index: None,
             start_time_data_index: 0,
-            data_names: vec![],
             last_result: None,
+            intrabar_values: (None, vec![]),
+            state_history: vec![],
         })
     }
 }
 
 pub const VAR_NAME: &'static str = "security";
 
+pub fn gen_lower_tf_type<'a>() -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("symbol", SyntaxType::string()),
+            ("resolution", SyntaxType::string()),
+            (
+                "expression",
+                SyntaxType::DynamicExpr(Box::new(SyntaxType::float_series())),
+            ),
+        ],
+        SyntaxType::List(SimpleSyntaxType::Float),
+    ))])))
+}
+
 pub fn declare_var<'a>() -> VarResult<'a> {
     let value = PineRef::new(CallableFactory::new(|| {
         Callable::new(None, Some(Box::new(SecurityInfo::new())))
@@ -305,7 +547,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
                 "expression",
                 SyntaxType::DynamicExpr(Box::new(SyntaxType::float_series())),
             ),
-            ("gaps", SyntaxType::color()),
+            ("gaps", SyntaxType::bool()),
             ("lookahead", SyntaxType::bool()),
         ],
         SyntaxType::float_series(),
@@ -317,7 +559,7 @@ pub fn declare_var<'a>() -> VarResult<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::stat_expr_types::VarIndex;
+    use crate::ast::stat_expr_types::{Statement, VarIndex};
     use crate::runtime::context::VarOperate;
     use crate::runtime::{AnySeries, NoneCallback};
     use crate::{LibInfo, PineParser, PineRunner};
@@ -439,6 +681,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn security_shared_eval_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "a = close + 1\n\
+                   m1 = security('MSFT', '1D', close + a)\n\
+                   m2 = security('MSFT', '1D',  close  +  a, lookahead=true)\n\
+                   m3 = security('MSFT', '1D', close - a)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let hashes: Vec<_> = blk
+            .stmts
+            .iter()
+            .filter_map(|s| match s {
+                Statement::FuncDef(def) => Some(hash_expr(def)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(2f64)]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64), Some(30i64)]),
+                    ),
+                    (
+                        "MSFT-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(15i64), Some(30i64)]),
+                    ),
+                    (
+                        "MSFT-1D-close",
+                        AnySeries::from_float_vec(vec![Some(15f64), Some(20f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        let main_ctx = downcast_ctx(runner.get_context());
+        // The first two calls share the sub-runtime with the cache.
+        let eval = main_ctx.get_security_eval(&(String::from("MSFT-1D"), hashes[0]));
+        assert_eq!(eval.map(|e| Rc::strong_count(&e)), Some(4));
+        let eval = main_ctx.get_security_eval(&(String::from("MSFT-1D"), hashes[2]));
+        assert_eq!(eval.map(|e| Rc::strong_count(&e)), Some(3));
+
+        let vals: Vec<_> = [2, 4, 6]
+            .iter()
+            .map(|&i| main_ctx.move_var(VarIndex::new(i, 0)))
+            .collect();
+        assert_eq!(
+            vals,
+            vec![
+                Some(PineRef::new_rc(Series::from_vec(vec![
+                    None,
+                    Some(31f64),
+                    Some(41f64)
+                ]))),
+                Some(PineRef::new_rc(Series::from_vec(vec![
+                    Some(31f64),
+                    Some(41f64),
+                    Some(41f64)
+                ]))),
+                Some(PineRef::new_rc(Series::from_vec(vec![
+                    None,
+                    Some(-1f64),
+                    Some(-1f64)
+                ]))),
+            ]
+        );
+    }
+
     #[test]
     fn security_with_time_and_bar_index_test() {
         use crate::libs::time;
@@ -583,4 +908,464 @@ mod tests {
             ])))
         );
     }
+
+    #[test]
+    fn security_aggregate_test() {
+        use crate::libs::plot;
+        use crate::runtime::OutputData;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "plot(security('MSFT', '2', close * 1))\nplot(security('AAPL', '2', close * 1))";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let minutes =
+            |v: Vec<i64>| AnySeries::from_int_vec(v.into_iter().map(|m| Some(m * 60000)).collect());
+
+        // The 2 minutes bars of MSFT are aggregated from the chart bars and the bars of AAPL
+        // are aggregated from the 1 minute bars provided by the host.
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![
+                            Some(1f64),
+                            Some(2f64),
+                            Some(3f64),
+                            Some(4f64),
+                            Some(5f64),
+                        ]),
+                    ),
+                    ("_time", minutes(vec![0, 1, 2, 3, 4])),
+                    ("AAPL-1-_time", minutes(vec![1, 2, 3])),
+                    (
+                        "AAPL-1-close",
+                        AnySeries::from_float_vec(vec![Some(10f64), Some(20f64), Some(30f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(2f64),
+                    Some(2f64),
+                    Some(4f64),
+                    Some(5f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(10f64),
+                    Some(10f64),
+                    Some(30f64),
+                    Some(30f64)
+                ]])),
+            ]
+        );
+    }
+
+    #[test]
+    fn security_lower_tf_test() {
+        use crate::libs::request;
+
+        let lib_info = LibInfo::new(
+            vec![request::declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = request.security_lower_tf('MSFT', '1', close * 2)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let get_values = |ctx: &mut dyn Ctx| {
+            let val = ctx.move_var(VarIndex::new(1, 0)).unwrap();
+            downcast_pf::<Vec<Float>>(val).unwrap().into_inner()
+        };
+        let gen_data = |times: Vec<i64>, intrabar_times: Vec<i64>| {
+            let closes = (1..=intrabar_times.len()).map(|v| Some(v as f64)).collect();
+            vec![
+                (
+                    "close",
+                    AnySeries::from_float_vec(times.iter().map(|_| Some(1f64)).collect()),
+                ),
+                (
+                    "_time",
+                    AnySeries::from_int_vec(times.into_iter().map(Some).collect()),
+                ),
+                (
+                    "MSFT-1-_time",
+                    AnySeries::from_int_vec(intrabar_times.into_iter().map(Some).collect()),
+                ),
+                ("MSFT-1-close", AnySeries::from_float_vec(closes)),
+            ]
+        };
+
+        // The intrabars of the last chart bar are [20, 25), the intrabar at 5 is before the first bar.
+        runner
+            .run(&gen_data(vec![10, 20], vec![5, 10, 13, 20, 25, 30]), None)
+            .unwrap();
+        assert_eq!(
+            get_values(runner.get_context()),
+            vec![Some(8f64), Some(10f64), Some(12f64)]
+        );
+
+        runner
+            .run(
+                &gen_data(vec![10, 20, 30], vec![5, 10, 13, 20, 25, 30]),
+                None,
+            )
+            .unwrap();
+        assert_eq!(get_values(runner.get_context()), vec![Some(12f64)]);
+        assert_eq!(
+            runner.get_io_info().get_input_srcs()[1],
+            InputSrc::new(
+                Some(String::from("MSFT-1")),
+                vec![String::from("close"), String::from("time")]
+            )
+        );
+    }
+
+    #[test]
+    fn security_heikinashi_test() {
+        use crate::libs::ticker;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), ticker::declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = security(ticker.heikinashi('MSFT'), '1D', close * 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        let ticker = "MSFT;transform=heikinashi-1D";
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                    ),
+                    (
+                        "MSFT;transform=heikinashi-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                    ),
+                    (
+                        "MSFT;transform=heikinashi-1D-open",
+                        AnySeries::from_float_vec(vec![Some(10f64), Some(12f64)]),
+                    ),
+                    (
+                        "MSFT;transform=heikinashi-1D-high",
+                        AnySeries::from_float_vec(vec![Some(14f64), Some(15f64)]),
+                    ),
+                    (
+                        "MSFT;transform=heikinashi-1D-low",
+                        AnySeries::from_float_vec(vec![Some(8f64), Some(11f64)]),
+                    ),
+                    (
+                        "MSFT;transform=heikinashi-1D-close",
+                        AnySeries::from_float_vec(vec![Some(12f64), Some(14f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        // All the OHLCV sources of the transformed ticker are requested.
+        assert_eq!(
+            runner.get_io_info().get_input_srcs()[1],
+            InputSrc::new(
+                Some(String::from(ticker)),
+                vec![
+                    String::from("close"),
+                    String::from("time"),
+                    String::from("open"),
+                    String::from("high"),
+                    String::from("low"),
+                    String::from("volume"),
+                ]
+            )
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(11f64),
+                Some(13f64)
+            ])))
+        );
+    }
+    #[test]
+    fn security_symbol_input_test() {
+        use crate::libs::input;
+        use crate::runtime::InputVal;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), input::declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "sym = input('NASDAQ:AAPL', type=input.symbol)\n\
+        m = security(sym, '1D', close * 1)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                    ),
+                    (
+                        "NASDAQ:AAPL-1D-close",
+                        AnySeries::from_float_vec(vec![Some(100f64)]),
+                    ),
+                    (
+                        "NASDAQ:AAPL-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(15i64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        // The ticker of the symbol input is requested from the host.
+        assert_eq!(
+            runner.get_io_info().get_input_srcs()[1].ticker,
+            Some(String::from("NASDAQ:AAPL-1D"))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![None, Some(100f64)])))
+        );
+
+        // The host picks another symbol.
+        runner.change_inputs(vec![Some(InputVal::Symbol(String::from("NYSE:IBM")))]);
+        runner
+            .run(
+                &vec![
+                    (
+                        "close",
+                        AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                    ),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64)]),
+                    ),
+                    (
+                        "NYSE:IBM-1D-close",
+                        AnySeries::from_float_vec(vec![Some(200f64)]),
+                    ),
+                    (
+                        "NYSE:IBM-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(15i64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_io_info().get_input_srcs()[1].ticker,
+            Some(String::from("NYSE:IBM-1D"))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![None, Some(200f64)])))
+        );
+    }
+
+    #[test]
+    fn security_barmerge_test() {
+        use crate::libs::barmerge;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), barmerge::declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m1 = security('MSFT', '1D', close * 1, lookahead=barmerge.lookahead_off)
+m2 = security('MSFT', '1D', close * 1, lookahead=barmerge.lookahead_on)
+m3 = security('MSFT', '1D', close[1], lookahead=barmerge.lookahead_on)
+m4 = security('MSFT', '1D', close * 1, gaps=barmerge.gaps_on)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // The time of the daily bar is the time it is completed.
+        runner
+            .run(
+                &vec![
+                    ("close", AnySeries::from_float_vec(vec![Some(1f64); 4])),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![
+                            Some(10i64),
+                            Some(20i64),
+                            Some(30i64),
+                            Some(40i64),
+                        ]),
+                    ),
+                    (
+                        "MSFT-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(20i64), Some(40i64)]),
+                    ),
+                    (
+                        "MSFT-1D-close",
+                        AnySeries::from_float_vec(vec![Some(100f64), Some(200f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+
+        // Without lookahead, the daily value is only available after the daily bar is completed.
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                Some(100f64),
+                Some(100f64),
+                Some(200f64)
+            ])))
+        );
+        // With lookahead, the historical bars get the future value of the daily bar.
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(3, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(100f64),
+                Some(100f64),
+                Some(200f64),
+                Some(200f64)
+            ])))
+        );
+        // The previous daily value with lookahead never uses the future data, so it doesn't repaint.
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(5, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                None,
+                Some(100f64),
+                Some(100f64)
+            ])))
+        );
+        // The gaps are filled with na.
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(7, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                None,
+                Some(100f64),
+                None,
+                Some(200f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn security_realtime_lookahead_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::Series(SimpleSyntaxType::Float)),
+                ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
+            ],
+        );
+        let src = "m = security('MSFT', '1D', close * 1, lookahead=true)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // The daily bar of the last chart bars is not provided yet, the latest daily bar is used.
+        runner
+            .run(
+                &vec![
+                    ("close", AnySeries::from_float_vec(vec![Some(1f64); 3])),
+                    (
+                        "_time",
+                        AnySeries::from_int_vec(vec![Some(10i64), Some(20i64), Some(30i64)]),
+                    ),
+                    ("MSFT-1D-_time", AnySeries::from_int_vec(vec![Some(15i64)])),
+                    (
+                        "MSFT-1D-close",
+                        AnySeries::from_float_vec(vec![Some(15f64)]),
+                    ),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(15f64),
+                Some(15f64),
+                Some(15f64)
+            ])))
+        );
+    }
+
+    #[test]
+    fn security_intrabar_rollback_test() {
+        use crate::libs::{plot, strategy};
+        use crate::runtime::OutputData;
+
+        let lib_info = LibInfo::new(
+            vec![declare_var(), strategy::declare_var(), plot::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+                ("_time", SyntaxType::int_series()),
+            ],
+        );
+        // The script is calculated on the tick that fills the entry before the bar is calculated,
+        // the rolled back bar gets the ticker bar again instead of the gap.
+        let src = "strategy('s', calc_on_order_fills=true)\n\
+                   strategy.entry('L', strategy.long, when=strategy.closedtrades == 0)\n\
+                   m = security('MSFT', '1D', close * 1, gaps=true)\n\
+                   plot(m)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let gen_series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+        runner
+            .run(
+                &vec![
+                    ("open", gen_series(vec![10f64, 10f64])),
+                    ("high", gen_series(vec![10f64, 12f64])),
+                    ("low", gen_series(vec![10f64, 9.5])),
+                    ("close", gen_series(vec![10f64, 11f64])),
+                    ("_time", AnySeries::from_int_vec(vec![Some(10), Some(20)])),
+                    (
+                        "MSFT-1D-_time",
+                        AnySeries::from_int_vec(vec![Some(10), Some(20)]),
+                    ),
+                    ("MSFT-1D-close", gen_series(vec![1f64, 2f64])),
+                ],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![Some(1f64), Some(2f64)]]))]
+        );
+    }
 }
@@ -1,7 +1,7 @@
 use super::VarResult;
 use crate::ast::syntax_type::SyntaxType;
 use crate::helper::err_msgs::*;
-use crate::helper::str_replace;
+use crate::helper::{pine_ref_to_string, str_replace};
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::output::{OutputData, OutputInfo, PlotInfo, StrOptionsData};
 use crate::types::{
@@ -12,6 +12,33 @@ use crate::types::{
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+pub const SIZE_AUTO: &'static str = "auto";
+pub const SIZE_HUGE: &'static str = "huge";
+pub const SIZE_LARGE: &'static str = "large";
+pub const SIZE_NORMAL: &'static str = "normal";
+pub const SIZE_SMALL: &'static str = "small";
+pub const SIZE_TINY: &'static str = "tiny";
+
+const SIZES: [&'static str; 6] = [
+    SIZE_AUTO,
+    SIZE_HUGE,
+    SIZE_LARGE,
+    SIZE_NORMAL,
+    SIZE_SMALL,
+    SIZE_TINY,
+];
+
+// Get the size argument of plotshape and plotchar, the value must be one of the size constants.
+pub fn pine_ref_to_size<'a>(val: Option<PineRef<'a>>) -> Result<Option<String>, RuntimeErr> {
+    match pine_ref_to_string(val) {
+        Some(v) if !SIZES.contains(&v.as_str()) => Err(RuntimeErr::InvalidParameters(str_replace(
+            INVALID_VALS,
+            vec![String::from("size")],
+        ))),
+        v => Ok(v),
+    }
+}
+
 struct PlotProps;
 
 impl<'a> PineClass<'a> for PlotProps {
@@ -21,15 +48,15 @@ impl<'a> PineClass<'a> for PlotProps {
 
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
-            "auto" => Ok(PineRef::new(String::from("auto"))),
-            "huge" => Ok(PineRef::new(String::from("huge"))),
-            "large" => Ok(PineRef::new(String::from("large"))),
-            "normal" => Ok(PineRef::new(String::from("normal"))),
-            "small" => Ok(PineRef::new(String::from("small"))),
-            "tiny" => Ok(PineRef::new(String::from("tiny"))),
+            "auto" => Ok(PineRef::new(String::from(SIZE_AUTO))),
+            "huge" => Ok(PineRef::new(String::from(SIZE_HUGE))),
+            "large" => Ok(PineRef::new(String::from(SIZE_LARGE))),
+            "normal" => Ok(PineRef::new(String::from(SIZE_NORMAL))),
+            "small" => Ok(PineRef::new(String::from(SIZE_SMALL))),
+            "tiny" => Ok(PineRef::new(String::from(SIZE_TINY))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
-                vec![String::from(name), String::from("plot")],
+                vec![String::from(name), String::from("size")],
             ))),
         }
     }
@@ -61,7 +88,7 @@ mod tests {
     use crate::runtime::{AnySeries, NoneCallback};
     use crate::{LibInfo, PineParser, PineRunner};
     #[test]
-    fn display_fields_test() {
+    fn size_fields_test() {
         use crate::ast::stat_expr_types::VarIndex;
         use crate::types::{downcast_pf, Tuple};
 
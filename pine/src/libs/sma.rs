@@ -5,7 +5,7 @@ use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{
     ge1_param_i64, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_f64_series,
-    pine_ref_to_i64, require_param,
+    pine_ref_to_i64, require_param, series_index2, Moments, RollingWindow,
 };
 use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::runtime::InputSrc;
@@ -106,10 +106,6 @@ fn dev_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, Ru
     generic_dev_func(&*source, length, deviation)
 }
 
-fn variance_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
-    generic_dev_func(&*source, length, variance)
-}
-
 pub fn stdev_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
     generic_dev_func(&*source, length, stdev)
 }
@@ -189,8 +185,116 @@ pub fn declare_ma_var<'a>(name: &'static str, handle: HandleFunc) -> VarResult<'
     VarResult::new(value, syntax_type, name)
 }
 
+// The aggregates of the source values calculated incrementally by the rolling window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowKind {
+    Sum,
+    Mean,
+    Variance,
+    Stdev,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowVal {
+    kind: WindowKind,
+    window: RollingWindow<Float, Moments>,
+}
+
+impl WindowVal {
+    pub fn new(kind: WindowKind) -> WindowVal {
+        WindowVal {
+            kind,
+            window: RollingWindow::new(Moments::new()),
+        }
+    }
+
+    pub fn update<'a>(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        source: &Series<Float>,
+        length: i64,
+    ) -> Float {
+        let bar = ctx.get_iterindex();
+        let agg = self
+            .window
+            .update(bar, length as usize, |i| series_index2(source, i));
+        match self.kind {
+            WindowKind::Sum => agg.sum(),
+            WindowKind::Mean => agg.mean(),
+            WindowKind::Variance => agg.variance(),
+            WindowKind::Stdev => agg.stdev(),
+        }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.window.invalidate();
+    }
+}
+
+impl<'a> SeriesCall<'a> for WindowVal {
+    fn step(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        move_tuplet!((source, length) = param);
+
+        let source = require_param("source", pine_ref_to_f64_series(source))?;
+        let length = ge1_param_i64("length", pine_ref_to_i64(length))?;
+        Ok(PineRef::new(Series::from(
+            self.update(ctx, &source, length),
+        )))
+    }
+
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.invalidate();
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WindowCreator {
+    kind: WindowKind,
+}
+
+impl<'a> CallableCreator<'a> for WindowCreator {
+    fn create(&self) -> Callable<'a> {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                WindowVal::new(self.kind),
+            )))),
+        )
+    }
+
+    fn copy(&self) -> Box<dyn CallableCreator<'a>> {
+        Box::new(self.clone())
+    }
+}
+
+pub fn declare_window_var<'a>(name: &'static str, kind: WindowKind) -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new_with_creator(Box::new(WindowCreator {
+        kind,
+    })));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![
+            ("source", SyntaxType::float_series()),
+            ("length", SyntaxType::int()),
+        ],
+        SyntaxType::float_series(),
+    ))]);
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, name)
+}
+
 pub fn declare_sma_var<'a>() -> VarResult<'a> {
-    declare_ma_var("sma", sma_func)
+    declare_window_var("sma", WindowKind::Mean)
 }
 
 pub fn declare_wma_var<'a>() -> VarResult<'a> {
@@ -202,11 +306,11 @@ pub fn declare_dev_var<'a>() -> VarResult<'a> {
 }
 
 pub fn declare_variance_var<'a>() -> VarResult<'a> {
-    declare_ma_var("variance", variance_func)
+    declare_window_var("variance", WindowKind::Variance)
 }
 
 pub fn declare_stdev_var<'a>() -> VarResult<'a> {
-    declare_ma_var("stdev", stdev_func)
+    declare_window_var("stdev", WindowKind::Stdev)
 }
 
 #[cfg(test)]
@@ -215,7 +319,7 @@ mod tests {
     use crate::ast::syntax_type::SyntaxType;
     use crate::runtime::VarOperate;
     use crate::runtime::{AnySeries, NoneCallback};
-    use crate::types::Series;
+    use crate::types::{downcast_pf, Series};
     use crate::{LibInfo, PineParser, PineRunner};
     // use crate::libs::{floor, exp, };
 
@@ -268,5 +372,63 @@ mod tests {
             Some(PineRef::new(Series::from_vec(vec![None, Some(3f64)])))
         );
     }
+
+    #[test]
+    fn window_update_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_sma_var(), declare_variance_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = sma(close, 3)\nm2 = variance(close, 3)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        // Replace the last bar twice and append the new bar.
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(9f64)]),
+            )])
+            .unwrap();
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(7f64), Some(5f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new(Series::from_vec(vec![
+                None,
+                None,
+                Some(2f64),
+                Some(4f64),
+                Some(5f64)
+            ])))
+        );
+        let variance = runner.get_context().move_var(VarIndex::new(1, 0));
+        let variance = downcast_pf::<Series<Float>>(variance.unwrap()).unwrap();
+        let expected = [
+            None,
+            None,
+            Some(2f64 / 3f64),
+            Some(14f64 / 3f64),
+            Some(8f64 / 3f64),
+        ];
+        assert_eq!(variance.get_history().len(), expected.len());
+        for (res, v) in variance.get_history().iter().zip(expected.iter()) {
+            assert_eq!(res.is_some(), v.is_some());
+            assert!((res.unwrap_or(0f64) - v.unwrap_or(0f64)).abs() < 1e-12);
+        }
+    }
 }
 // 4 * 12 + 2 * 6  4 + 2  5 * 12 / 6 =
@@ -0,0 +1,1153 @@
+use super::study::{declare_script, gen_decl_params};
+use super::syminfo::get_mintick;
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{
+    move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
+};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::runtime::output::InputSrc;
+use crate::runtime::strategy::{
+    Broker, BrokerConfig, CommissionType, Direction, DrawdownType, OcaType, Order, OrderKind,
+    QtyType,
+};
+use crate::types::{
+    Callable, CallableFactory, Float, Int, Object, PineClass, PineRef, RuntimeErr, Series,
+    SimpleCallableObject, NA,
+};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+const DECL_PARAM_COUNT: usize = 10;
+
+fn invalid(name: &str) -> RuntimeErr {
+    RuntimeErr::InvalidParameters(str_replace(INVALID_VALS, vec![String::from(name)]))
+}
+
+fn missing(name: &str) -> RuntimeErr {
+    RuntimeErr::MissingParameters(str_replace(REQUIRED_PARAMETERS, vec![String::from(name)]))
+}
+
+fn get_broker<'a, 'c>(
+    context: &'c mut (dyn Ctx<'a> + 'c),
+    name: &str,
+) -> Result<&'c mut Broker<'a>, RuntimeErr> {
+    match downcast_ctx(context).get_broker() {
+        Some(broker) => Ok(broker),
+        None => Err(RuntimeErr::NotImplement(str_replace(
+            STRATEGY_NOT_DECLARED,
+            vec![format!("strategy.{}", name)],
+        ))),
+    }
+}
+
+fn gen_config<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+) -> Result<BrokerConfig, RuntimeErr> {
+    move_tuplet!(
+        (
+            initial_capital,
+            commission_type,
+            commission_value,
+            slippage,
+            default_qty_type,
+            default_qty_value,
+            pyramiding,
+            calc_on_order_fills,
+            calc_on_every_tick
+        ) = param
+    );
+    let default = BrokerConfig::default();
+    let commission_type = match pine_ref_to_string(commission_type) {
+        Some(s) => CommissionType::from_name(&s).ok_or_else(|| invalid("commission_type"))?,
+        None => default.commission_type,
+    };
+    let default_qty_type = match pine_ref_to_string(default_qty_type) {
+        Some(s) => QtyType::from_name(&s).ok_or_else(|| invalid("default_qty_type"))?,
+        None => default.default_qty_type,
+    };
+    let mintick = get_mintick(context);
+    Ok(BrokerConfig {
+        initial_capital: pine_ref_to_f64(initial_capital).unwrap_or(default.initial_capital),
+        commission_type,
+        commission_value: pine_ref_to_f64(commission_value).unwrap_or(default.commission_value),
+        slippage: pine_ref_to_i64(slippage).unwrap_or(default.slippage),
+        default_qty_type,
+        default_qty_value: pine_ref_to_f64(default_qty_value).unwrap_or(default.default_qty_value),
+        pyramiding: pine_ref_to_i64(pyramiding).unwrap_or(default.pyramiding),
+        mintick,
+        calc_on_order_fills: pine_ref_to_bool(calc_on_order_fills)
+            .unwrap_or(default.calc_on_order_fills),
+        calc_on_every_tick: pine_ref_to_bool(calc_on_every_tick)
+            .unwrap_or(default.calc_on_every_tick),
+    })
+}
+
+fn strategy<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let broker_param = param.split_off(DECL_PARAM_COUNT);
+    declare_script(VAR_NAME, context, param)?;
+    if downcast_ctx(context).get_broker().is_none() {
+        let config = gen_config(context, broker_param)?;
+        downcast_ctx(context).set_broker(Broker::new(config));
+    }
+    // The orders are filled with the prices of the bar and the profit is updated with the close price.
+    if !downcast_ctx(context).check_is_input_info_ready() {
+        let srcs: Vec<String> = vec!["open", "high", "low", "close"]
+            .into_iter()
+            .filter(|s| context.get_top_varname_index(s).is_some())
+            .map(String::from)
+            .collect();
+        downcast_ctx(context).add_input_src(InputSrc::new(None, srcs));
+    }
+    Ok(PineRef::new(NA))
+}
+
+fn place_order<'a>(
+    context: &mut dyn Ctx<'a>,
+    name: &str,
+    when: Option<PineRef<'a>>,
+    order: Order,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    if pine_ref_to_bool(when) == Some(false) {
+        return Ok(PineRef::new(NA));
+    }
+    get_broker(context, name)?.place_order(order);
+    Ok(PineRef::new(NA))
+}
+
+fn get_id<'a>(id: Option<PineRef<'a>>) -> Result<String, RuntimeErr> {
+    pine_ref_to_string(id).ok_or_else(|| missing("id"))
+}
+
+fn entry<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((id, long, qty, limit, stop, oca_name, oca_type, when) = param);
+    let id = get_id(id)?;
+    let direction = match pine_ref_to_bool(long) {
+        Some(true) => Direction::Long,
+        Some(false) => Direction::Short,
+        None => return Err(missing("long")),
+    };
+    let mut order = Order::new(id, OrderKind::Entry(direction), pine_ref_to_f64(qty));
+    order.limit = pine_ref_to_f64(limit);
+    order.stop = pine_ref_to_f64(stop);
+    if let Some(oca_name) = pine_ref_to_string(oca_name) {
+        let oca_type = match pine_ref_to_string(oca_type) {
+            Some(s) => OcaType::from_name(&s).ok_or_else(|| invalid("oca_type"))?,
+            None => OcaType::None,
+        };
+        order.oca = Some((oca_name, oca_type));
+    }
+    place_order(context, "entry", when, order)
+}
+
+fn exit<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!(
+        (
+            id,
+            from_entry,
+            profit,
+            limit,
+            loss,
+            stop,
+            trail_points,
+            trail_offset,
+            oca_name,
+            when
+        ) = param
+    );
+    let from_entry = pine_ref_to_string(from_entry);
+    let mut order = Order::new(get_id(id)?, OrderKind::Exit(from_entry), None);
+    order.profit = pine_ref_to_f64(profit);
+    order.limit = pine_ref_to_f64(limit);
+    order.loss = pine_ref_to_f64(loss);
+    order.stop = pine_ref_to_f64(stop);
+    order.trail_points = pine_ref_to_f64(trail_points);
+    order.trail_offset = pine_ref_to_f64(trail_offset);
+    // The exit orders of one OCA group reduce each other.
+    order.oca = pine_ref_to_string(oca_name).map(|name| (name, OcaType::Reduce));
+    place_order(context, "exit", when, order)
+}
+
+fn close<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((id, when) = param);
+    let order = Order::new(get_id(id)?, OrderKind::Close, None);
+    place_order(context, "close", when, order)
+}
+
+fn close_all<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let when = move_element(&mut param, 0);
+    let order = Order::new(String::new(), OrderKind::CloseAll, None);
+    place_order(context, "close_all", when, order)
+}
+
+fn max_drawdown<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((value, dd_type) = param);
+    let value = pine_ref_to_f64(value).ok_or_else(|| missing("value"))?;
+    let dd_type = match pine_ref_to_string(dd_type) {
+        Some(s) => DrawdownType::from_name(&s).ok_or_else(|| invalid("type"))?,
+        None => return Err(missing("type")),
+    };
+    get_broker(context, "risk.max_drawdown")?
+        .get_risk_rules()
+        .max_drawdown = Some((value, dd_type));
+    Ok(PineRef::new(NA))
+}
+
+fn max_position_size<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let contracts = move_element(&mut param, 0);
+    let contracts = pine_ref_to_f64(contracts).ok_or_else(|| missing("contracts"))?;
+    get_broker(context, "risk.max_position_size")?
+        .get_risk_rules()
+        .max_position_size = Some(contracts);
+    Ok(PineRef::new(NA))
+}
+
+fn allow_entry_in<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let value = move_element(&mut param, 0);
+    let direction = match pine_ref_to_string(value) {
+        Some(ref s) if s == "all" => None,
+        Some(s) => Some(Direction::from_name(&s).ok_or_else(|| invalid("value"))?),
+        None => return Err(missing("value")),
+    };
+    get_broker(context, "risk.allow_entry_in")?
+        .get_risk_rules()
+        .allow_entry_in = direction;
+    Ok(PineRef::new(NA))
+}
+
+// Get the field of the open or closed trade of the index, na is returned if the trade doesn't exist.
+fn trade_field<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    closed: bool,
+    name: &str,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let trade_num = pine_ref_to_i64(move_element(&mut param, 0));
+    let prefix = if closed { "closedtrades" } else { "opentrades" };
+    let broker = get_broker(context, &format!("{}.{}", prefix, name))?;
+    let trade = match trade_num {
+        Some(i) if closed => broker.get_closed_trade(i),
+        Some(i) => broker.get_open_trade(i),
+        None => None,
+    };
+    let float_val: Option<Float> = match name {
+        "entry_price" => Some(trade.map(|t| t.entry_price)),
+        "exit_price" => Some(trade.and_then(|t| t.exit_price)),
+        "profit" => Some(trade.map(|t| broker.trade_profit(t))),
+        // The size is negative for the short trade.
+        "size" => Some(trade.map(|t| t.qty * t.direction.sign())),
+        _ => None,
+    };
+    if let Some(val) = float_val {
+        return Ok(PineRef::new_rc(Series::from(val)));
+    }
+    let int_val: Int = match name {
+        "entry_bar_index" => trade.map(|t| t.entry_bar as i64),
+        "exit_bar_index" => trade.and_then(|t| t.exit_bar).map(|i| i as i64),
+        _ => {
+            return Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), format!("strategy.{}", prefix)],
+            )))
+        }
+    };
+    Ok(PineRef::new_rc(Series::from(int_val)))
+}
+
+// The `strategy.opentrades.*` and `strategy.closedtrades.*` functions that read the trades of
+// the broker by the trade index.
+struct TradesProps {
+    closed: bool,
+}
+
+impl<'a> PineClass<'a> for TradesProps {
+    fn custom_type(&self) -> &str {
+        if self.closed {
+            "strategy.closedtrades"
+        } else {
+            "strategy.opentrades"
+        }
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        let factory = match (self.closed, name) {
+            (false, "entry_price") => CallableFactory::new(|| {
+                Callable::new(
+                    Some(|c, p, _| trade_field(c, p, false, "entry_price")),
+                    None,
+                )
+            }),
+            (false, "entry_bar_index") => CallableFactory::new(|| {
+                Callable::new(
+                    Some(|c, p, _| trade_field(c, p, false, "entry_bar_index")),
+                    None,
+                )
+            }),
+            (false, "profit") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, false, "profit")), None)
+            }),
+            (false, "size") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, false, "size")), None)
+            }),
+            (true, "entry_price") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, true, "entry_price")), None)
+            }),
+            (true, "entry_bar_index") => CallableFactory::new(|| {
+                Callable::new(
+                    Some(|c, p, _| trade_field(c, p, true, "entry_bar_index")),
+                    None,
+                )
+            }),
+            (true, "exit_price") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, true, "exit_price")), None)
+            }),
+            (true, "exit_bar_index") => CallableFactory::new(|| {
+                Callable::new(
+                    Some(|c, p, _| trade_field(c, p, true, "exit_bar_index")),
+                    None,
+                )
+            }),
+            (true, "profit") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, true, "profit")), None)
+            }),
+            (true, "size") => CallableFactory::new(|| {
+                Callable::new(Some(|c, p, _| trade_field(c, p, true, "size")), None)
+            }),
+            _ => {
+                return Err(RuntimeErr::NotImplement(str_replace(
+                    NO_FIELD_IN_OBJECT,
+                    vec![String::from(name), String::from(self.custom_type())],
+                )))
+            }
+        };
+        Ok(PineRef::new(factory))
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(TradesProps {
+            closed: self.closed,
+        })
+    }
+}
+
+struct RiskProps;
+
+impl<'a> PineClass<'a> for RiskProps {
+    fn custom_type(&self) -> &str {
+        "strategy.risk"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "max_drawdown" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(max_drawdown), None)
+            }))),
+            "max_position_size" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(max_position_size), None)
+            }))),
+            "allow_entry_in" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(allow_entry_in), None)
+            }))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("strategy.risk")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(RiskProps)
+    }
+}
+
+struct DirectionProps;
+
+impl<'a> PineClass<'a> for DirectionProps {
+    fn custom_type(&self) -> &str {
+        "strategy.direction"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "all" | "long" | "short" => Ok(PineRef::new_rc(String::from(name))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("strategy.direction")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(DirectionProps)
+    }
+}
+
+struct OcaProps;
+
+impl<'a> PineClass<'a> for OcaProps {
+    fn custom_type(&self) -> &str {
+        "strategy.oca"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "cancel" | "reduce" | "none" => Ok(PineRef::new_rc(String::from(name))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("strategy.oca")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(OcaProps)
+    }
+}
+
+struct CommissionProps;
+
+impl<'a> PineClass<'a> for CommissionProps {
+    fn custom_type(&self) -> &str {
+        "strategy.commission"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "percent" | "cash_per_contract" | "cash_per_order" => {
+                Ok(PineRef::new_rc(String::from(name)))
+            }
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("strategy.commission")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(CommissionProps)
+    }
+}
+
+struct StrategyProps;
+
+impl<'a> PineClass<'a> for StrategyProps {
+    fn custom_type(&self) -> &str {
+        "strategy"
+    }
+
+    fn get(&self, ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "long" => Ok(PineRef::new_box(true)),
+            "short" => Ok(PineRef::new_box(false)),
+            "fixed" | "cash" | "percent_of_equity" => Ok(PineRef::new_rc(String::from(name))),
+            "commission" => Ok(PineRef::new(Object::new(Box::new(CommissionProps)))),
+            "risk" => Ok(PineRef::new(Object::new(Box::new(RiskProps)))),
+            "direction" => Ok(PineRef::new(Object::new(Box::new(DirectionProps)))),
+            "oca" => Ok(PineRef::new(Object::new(Box::new(OcaProps)))),
+            "entry" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(entry), None)
+            }))),
+            "exit" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(exit), None)
+            }))),
+            "close" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(close), None)
+            }))),
+            "close_all" => Ok(PineRef::new(CallableFactory::new(|| {
+                Callable::new(Some(close_all), None)
+            }))),
+            "equity" | "openprofit" | "netprofit" => {
+                Ok(get_broker(ctx, name)?.get_metric_series(name).into_pf())
+            }
+            _ => {
+                let broker = get_broker(ctx, name)?;
+                let float_val = match name {
+                    "position_size" => Some(broker.position_size()),
+                    "grossprofit" => Some(broker.gross_profit()),
+                    "grossloss" => Some(broker.gross_loss()),
+                    "initial_capital" => Some(broker.get_config().initial_capital),
+                    _ => None,
+                };
+                if let Some(val) = float_val {
+                    return Ok(PineRef::new_rc(Series::from(Some(val))));
+                }
+                let int_val: Int = match name {
+                    "closedtrades" => Some(broker.closed_trades()),
+                    "opentrades" => Some(broker.open_trades()),
+                    "wintrades" => Some(broker.win_trades()),
+                    "losstrades" => Some(broker.loss_trades()),
+                    _ => {
+                        return Err(RuntimeErr::NotImplement(str_replace(
+                            NO_FIELD_IN_OBJECT,
+                            vec![String::from(name), String::from("strategy")],
+                        )))
+                    }
+                };
+                Ok(PineRef::new_rc(Series::from(int_val)))
+            }
+        }
+    }
+
+    // `strategy.opentrades` and `strategy.closedtrades` are the trade counts, and also the
+    // namespaces of the trade functions.
+    fn get_obj(&self, ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "opentrades" => Ok(PineRef::new(Object::new(Box::new(TradesProps {
+                closed: false,
+            })))),
+            "closedtrades" => Ok(PineRef::new(Object::new(Box::new(TradesProps {
+                closed: true,
+            })))),
+            _ => self.get(ctx, name),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(StrategyProps)
+    }
+}
+
+pub const VAR_NAME: &'static str = "strategy";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(SimpleCallableObject::new(Box::new(StrategyProps), || {
+        Callable::new(Some(strategy), None)
+    }));
+
+    let mut commission_type = BTreeMap::new();
+    commission_type.insert("percent", SyntaxType::string());
+    commission_type.insert("cash_per_contract", SyntaxType::string());
+    commission_type.insert("cash_per_order", SyntaxType::string());
+
+    let mut direction_type = BTreeMap::new();
+    direction_type.insert("all", SyntaxType::string());
+    direction_type.insert("long", SyntaxType::string());
+    direction_type.insert("short", SyntaxType::string());
+
+    let mut risk_type = BTreeMap::new();
+    // strategy.risk.max_drawdown(value, type) → void
+    risk_type.insert(
+        "max_drawdown",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("value", SyntaxType::float()),
+                ("type", SyntaxType::string()),
+            ],
+            SyntaxType::Void,
+        ))]))),
+    );
+    // strategy.risk.max_position_size(contracts) → void
+    risk_type.insert(
+        "max_position_size",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![("contracts", SyntaxType::float())],
+            SyntaxType::Void,
+        ))]))),
+    );
+    // strategy.risk.allow_entry_in(value) → void
+    risk_type.insert(
+        "allow_entry_in",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![("value", SyntaxType::string())],
+            SyntaxType::Void,
+        ))]))),
+    );
+
+    let mut oca_type = BTreeMap::new();
+    oca_type.insert("cancel", SyntaxType::string());
+    oca_type.insert("reduce", SyntaxType::string());
+    oca_type.insert("none", SyntaxType::string());
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("long", SyntaxType::bool());
+    obj_type.insert("short", SyntaxType::bool());
+    obj_type.insert("fixed", SyntaxType::string());
+    obj_type.insert("cash", SyntaxType::string());
+    obj_type.insert("percent_of_equity", SyntaxType::string());
+    obj_type.insert("commission", SyntaxType::Object(Rc::new(commission_type)));
+    obj_type.insert("direction", SyntaxType::Object(Rc::new(direction_type)));
+    obj_type.insert("oca", SyntaxType::Object(Rc::new(oca_type)));
+    obj_type.insert("risk", SyntaxType::Object(Rc::new(risk_type)));
+    for name in [
+        "position_size",
+        "netprofit",
+        "grossprofit",
+        "grossloss",
+        "openprofit",
+        "equity",
+        "initial_capital",
+    ]
+    .iter()
+    {
+        obj_type.insert(name, SyntaxType::float_series());
+    }
+    for name in ["wintrades", "losstrades"].iter() {
+        obj_type.insert(name, SyntaxType::int_series());
+    }
+    let trade_func = |ret_type| {
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![("trade_num", SyntaxType::int_series())],
+            ret_type,
+        ))])))
+    };
+    // strategy.opentrades.entry_price(trade_num) → series[float]
+    let mut opentrades_type = BTreeMap::new();
+    opentrades_type.insert("entry_price", trade_func(SyntaxType::float_series()));
+    opentrades_type.insert("entry_bar_index", trade_func(SyntaxType::int_series()));
+    opentrades_type.insert("profit", trade_func(SyntaxType::float_series()));
+    opentrades_type.insert("size", trade_func(SyntaxType::float_series()));
+    let mut closedtrades_type = opentrades_type.clone();
+    closedtrades_type.insert("exit_price", trade_func(SyntaxType::float_series()));
+    closedtrades_type.insert("exit_bar_index", trade_func(SyntaxType::int_series()));
+    // The trade count can't be called as a function.
+    for (name, trades_type) in [
+        ("opentrades", opentrades_type),
+        ("closedtrades", closedtrades_type),
+    ] {
+        obj_type.insert(
+            name,
+            SyntaxType::ValObjectFunction(
+                Box::new(SyntaxType::int_series()),
+                Rc::new(trades_type),
+                Rc::new(FunctionTypes(vec![])),
+            ),
+        );
+    }
+    // strategy.entry(id, long, qty, limit, stop, oca_name, oca_type, when) → void
+    obj_type.insert(
+        "entry",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("id", SyntaxType::string()),
+                ("long", SyntaxType::bool_series()),
+                ("qty", SyntaxType::float_series()),
+                ("limit", SyntaxType::float_series()),
+                ("stop", SyntaxType::float_series()),
+                ("oca_name", SyntaxType::string()),
+                ("oca_type", SyntaxType::string()),
+                ("when", SyntaxType::bool_series()),
+            ],
+            SyntaxType::Void,
+        ))]))),
+    );
+    // strategy.exit(id, from_entry, profit, limit, loss, stop, trail_points, trail_offset, oca_name, when) → void
+    obj_type.insert(
+        "exit",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("id", SyntaxType::string()),
+                ("from_entry", SyntaxType::string()),
+                ("profit", SyntaxType::float_series()),
+                ("limit", SyntaxType::float_series()),
+                ("loss", SyntaxType::float_series()),
+                ("stop", SyntaxType::float_series()),
+                ("trail_points", SyntaxType::float_series()),
+                ("trail_offset", SyntaxType::float_series()),
+                ("oca_name", SyntaxType::string()),
+                ("when", SyntaxType::bool_series()),
+            ],
+            SyntaxType::Void,
+        ))]))),
+    );
+    // strategy.close(id, when) → void
+    obj_type.insert(
+        "close",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![
+                ("id", SyntaxType::string()),
+                ("when", SyntaxType::bool_series()),
+            ],
+            SyntaxType::Void,
+        ))]))),
+    );
+    // strategy.close_all(when) → void
+    obj_type.insert(
+        "close_all",
+        SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+            vec![("when", SyntaxType::bool_series())],
+            SyntaxType::Void,
+        ))]))),
+    );
+
+    let mut params = gen_decl_params();
+    params.append(&mut vec![
+        ("initial_capital", SyntaxType::float()),
+        ("commission_type", SyntaxType::string()),
+        ("commission_value", SyntaxType::float()),
+        ("slippage", SyntaxType::int()),
+        ("default_qty_type", SyntaxType::string()),
+        ("default_qty_value", SyntaxType::float()),
+        ("pyramiding", SyntaxType::int()),
+        ("calc_on_order_fills", SyntaxType::bool()),
+        ("calc_on_every_tick", SyntaxType::bool()),
+    ]);
+    debug_assert!(params.len() == DECL_PARAM_COUNT + 9);
+    let syntax_type = SyntaxType::ObjectFunction(
+        Rc::new(obj_type),
+        Rc::new(FunctionTypes(vec![FunctionType::new((
+            params,
+            SyntaxType::Void,
+        ))])),
+    );
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::plot;
+    use crate::runtime::{AnySeries, NoneCallback, OutputData};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn strategy_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+            ],
+        );
+        let src = "strategy('s', commission_type=strategy.commission.cash_per_order, \
+                   commission_value=0.5, default_qty_type=strategy.fixed, default_qty_value=2)\n\
+                   strategy.entry('L', strategy.long, when=close > open)\n\
+                   strategy.close('L', when=close < open)\n\
+                   plot(strategy.position_size)\nplot(strategy.netprofit)\n\
+                   plot(strategy.grossprofit)\nplot(strategy.grossloss)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(4f64), Some(3f64)]),
+            ),
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(2f64), Some(3f64), Some(3f64), Some(1f64)]),
+            ),
+        ];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                Some(OutputData::new(vec![vec![
+                    Some(0f64),
+                    Some(2f64),
+                    Some(2f64),
+                    Some(0f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    Some(0f64),
+                    Some(0f64),
+                    Some(0f64),
+                    Some(1f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    Some(0f64),
+                    Some(0f64),
+                    Some(0f64),
+                    Some(1f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    Some(0f64),
+                    Some(0f64),
+                    Some(0f64),
+                    Some(0f64)
+                ]])),
+            ]
+        );
+        let report = runner.get_strategy_result().unwrap();
+        assert_eq!(report.closed_trades.len(), 1);
+        // Bought 2 contracts at 2, sold at 3 and paid 1 for the commission.
+        assert_eq!(report.net_profit, 1f64);
+        assert_eq!(report.commission_paid, 1f64);
+        assert_eq!(report.closed_trades[0].gross_profit, 2f64);
+    }
+
+    #[test]
+    fn equity_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+            ],
+        );
+        let src = "strategy('s', commission_type=strategy.commission.cash_per_order, \
+                   commission_value=0.5, default_qty_value=2)\n\
+                   strategy.entry('L', strategy.long, when=close > open)\n\
+                   strategy.close('L', when=close < open)\n\
+                   plot(strategy.equity - strategy.equity[1])\n\
+                   plot(strategy.openprofit[1])\nplot(strategy.netprofit)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(4f64), Some(3f64)]),
+            ),
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(2f64), Some(3f64), Some(3f64), Some(1f64)]),
+            ),
+        ];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(1.5f64),
+                    Some(0f64),
+                    Some(-0.5f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    None,
+                    Some(0f64),
+                    Some(2f64),
+                    Some(2f64)
+                ]])),
+                Some(OutputData::new(vec![vec![
+                    Some(0f64),
+                    Some(0f64),
+                    Some(0f64),
+                    Some(1f64)
+                ]])),
+            ]
+        );
+        let report = runner.get_strategy_result().unwrap();
+        assert_eq!(
+            report
+                .bar_metrics
+                .iter()
+                .map(|m| m.equity)
+                .collect::<Vec<_>>(),
+            vec![1000000f64, 1000001.5f64, 1000001.5f64, 1000001f64]
+        );
+        assert_eq!(report.bar_metrics[2].open_profit, 2f64);
+    }
+
+    #[test]
+    fn risk_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "strategy('s', default_qty_value=10)\n\
+                   strategy.risk.allow_entry_in(strategy.direction.long)\n\
+                   strategy.risk.max_position_size(4)\n\
+                   strategy.risk.max_drawdown(20, strategy.percent_of_equity)\n\
+                   strategy.entry('S', strategy.short)\n\
+                   strategy.entry('L', strategy.long)\n\
+                   plot(strategy.position_size)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(10f64), Some(10f64), Some(6f64), Some(6f64)]),
+        )];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![
+                Some(0f64),
+                Some(4f64),
+                Some(4f64),
+                Some(4f64)
+            ]]))]
+        );
+
+        let src = "strategy('s', initial_capital=100, default_qty_value=10)\n\
+                   strategy.risk.max_drawdown(20, strategy.percent_of_equity)\n\
+                   strategy.entry('L', strategy.long)\n\
+                   plot(strategy.position_size)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![
+                Some(0f64),
+                Some(10f64),
+                Some(0f64),
+                Some(0f64)
+            ]]))]
+        );
+        let report = runner.get_strategy_result().unwrap();
+        assert!(report.halted);
+        let rules: Vec<&str> = report.risk_events.iter().map(|e| e.rule.as_str()).collect();
+        assert_eq!(rules, vec!["max_drawdown"]);
+    }
+
+    #[test]
+    fn order_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+            ],
+        );
+        let src = "strategy('s', pyramiding=2)\n\
+                   strategy.entry('L', strategy.long, stop=12, oca_name='bo', oca_type=strategy.oca.cancel)\n\
+                   strategy.entry('S', strategy.short, stop=8, oca_name='bo', oca_type=strategy.oca.cancel)\n\
+                   strategy.exit('TP', 'L', limit=14)\n\
+                   plot(strategy.position_size)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let gen_series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+        let data = vec![
+            ("open", gen_series(vec![10f64, 10f64, 12f64, 13f64])),
+            ("high", gen_series(vec![10f64, 13f64, 13f64, 15f64])),
+            ("low", gen_series(vec![10f64, 9f64, 11f64, 12f64])),
+            ("close", gen_series(vec![10f64, 12f64, 12f64, 14f64])),
+        ];
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.move_output_data(),
+            vec![Some(OutputData::new(vec![vec![
+                Some(0f64),
+                Some(1f64),
+                Some(2f64),
+                Some(1f64)
+            ]]))]
+        );
+        // The two entries exit at the limit price, then the stop entry is filled again at the
+        // exit price because the pyramiding is not full.
+        let report = runner.get_strategy_result().unwrap();
+        let exits: Vec<Option<f64>> = report.closed_trades.iter().map(|t| t.exit_price).collect();
+        assert_eq!(exits, vec![Some(14f64), Some(14f64)]);
+        assert_eq!(report.open_trades[0].entry_price, 14f64);
+    }
+
+    #[test]
+    fn trades_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+            ],
+        );
+        let src = "strategy('s')\n\
+                   strategy.entry('L', strategy.long, when=close > open)\n\
+                   strategy.close('L', when=close < open)\n\
+                   plot(strategy.opentrades)\n\
+                   plot(strategy.opentrades.entry_price(0))\n\
+                   plot(strategy.opentrades.size(0))\n\
+                   plot(strategy.opentrades.profit(strategy.opentrades - 1))\n\
+                   plot(strategy.closedtrades.profit(strategy.closedtrades - 1))\n\
+                   plot(strategy.closedtrades.exit_bar_index(0))";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let data = vec![
+            (
+                "open",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(4f64), Some(3f64)]),
+            ),
+            (
+                "close",
+                AnySeries::from_float_vec(vec![Some(2f64), Some(3f64), Some(3f64), Some(1f64)]),
+            ),
+        ];
+        runner.run(&data, None).unwrap();
+        let gen_output = |v: Vec<Float>| Some(OutputData::new(vec![v]));
+        assert_eq!(
+            runner.move_output_data(),
+            vec![
+                gen_output(vec![Some(0f64), Some(1f64), Some(1f64), Some(0f64)]),
+                gen_output(vec![None, Some(2f64), Some(2f64), None]),
+                gen_output(vec![None, Some(1f64), Some(1f64), None]),
+                gen_output(vec![None, Some(1f64), Some(1f64), None]),
+                gen_output(vec![None, None, None, Some(1f64)]),
+                gen_output(vec![None, None, None, Some(3f64)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn calc_on_order_fills_test() {
+        let gen_series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+        let data = vec![
+            ("open", gen_series(vec![10f64, 10f64])),
+            ("high", gen_series(vec![10f64, 12f64])),
+            ("low", gen_series(vec![10f64, 9.5])),
+            ("close", gen_series(vec![10f64, 11f64])),
+        ];
+        let run = |calc_on_order_fills: &str| {
+            let src = format!(
+                "strategy('s', calc_on_order_fills={})\n\
+                 strategy.entry('L', strategy.long, when=strategy.closedtrades == 0)\n\
+                 if strategy.position_size > 0\n    \
+                 strategy.exit('X', 'L', limit=strategy.opentrades.entry_price(0) + 1)\n\
+                 plot(strategy.position_size)",
+                calc_on_order_fills
+            );
+            let lib_info = LibInfo::new(
+                vec![declare_var(), plot::declare_var()],
+                vec![
+                    ("open", SyntaxType::float_series()),
+                    ("high", SyntaxType::float_series()),
+                    ("low", SyntaxType::float_series()),
+                    ("close", SyntaxType::float_series()),
+                ],
+            );
+            let blk = PineParser::new(&src, &lib_info).parse_blk().unwrap();
+            let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+            runner.run(&data, None).unwrap();
+            let output = runner.move_output_data();
+            let report = runner.get_strategy_result().unwrap();
+            let exits: Vec<Option<f64>> =
+                report.closed_trades.iter().map(|t| t.exit_price).collect();
+            (output, exits)
+        };
+
+        // The exit is placed after the entry is filled at the open, and filled when the price
+        // moves from the low to the high.
+        assert_eq!(
+            run("true"),
+            (
+                vec![Some(OutputData::new(vec![vec![Some(0f64), Some(0f64)]]))],
+                vec![Some(11f64)]
+            )
+        );
+        assert_eq!(
+            run("false"),
+            (
+                vec![Some(OutputData::new(vec![vec![Some(0f64), Some(1f64)]]))],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn calc_on_order_fills_stream_test() {
+        use crate::libs::alert;
+        use crate::runtime::{Callback, OutputCallback};
+        use std::cell::RefCell;
+
+        #[derive(Default)]
+        struct StreamCallback {
+            events: RefCell<Vec<String>>,
+        }
+        impl OutputCallback for StreamCallback {
+            fn on_plot_point(&self, output_index: usize, bar_index: i32, value: Option<f64>) {
+                self.events
+                    .borrow_mut()
+                    .push(format!("plot {} {} {:?}", output_index, bar_index, value));
+            }
+            fn on_alert(&self, bar_index: i32, message: String) {
+                self.events
+                    .borrow_mut()
+                    .push(format!("alert {} {}", bar_index, message));
+            }
+        }
+        impl Callback for StreamCallback {
+            fn get_output_callback(&self) -> Option<&dyn OutputCallback> {
+                Some(self)
+            }
+        }
+
+        let callback = StreamCallback::default();
+        let gen_series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+        let data = vec![
+            ("open", gen_series(vec![10f64, 10f64])),
+            ("high", gen_series(vec![10f64, 12f64])),
+            ("low", gen_series(vec![10f64, 9.5])),
+            ("close", gen_series(vec![10f64, 11f64])),
+        ];
+        let src = "strategy('s', calc_on_order_fills=true)\n\
+                   strategy.entry('L', strategy.long, when=strategy.closedtrades == 0)\n\
+                   if strategy.position_size > 0\n    \
+                   strategy.exit('X', 'L', limit=strategy.opentrades.entry_price(0) + 1)\n    \
+                   alert('open')\n\
+                   plot(strategy.position_size)";
+        let lib_info = LibInfo::new(
+            vec![declare_var(), plot::declare_var(), alert::declare_var()],
+            vec![
+                ("open", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+                ("close", SyntaxType::float_series()),
+            ],
+        );
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &callback);
+        runner.run(&data, None).unwrap();
+
+        // The position is only open in the calculation on the order fill, which is rolled back, so
+        // neither its plot point nor its alert is streamed.
+        assert_eq!(
+            callback.events.replace(vec![]),
+            vec!["plot 0 0 Some(0.0)", "plot 0 1 Some(0.0)"]
+        );
+    }
+
+    #[test]
+    fn strategy_err_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        let src = "strategy('s', commission_type='abc')";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::InvalidParameters(str_replace(
+                INVALID_VALS,
+                vec![String::from("commission_type")]
+            )))
+        );
+
+        let src = "strategy.entry('L', strategy.long)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::NotImplement(str_replace(
+                STRATEGY_NOT_DECLARED,
+                vec![String::from("strategy.entry")]
+            )))
+        );
+    }
+}
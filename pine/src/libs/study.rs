@@ -4,26 +4,57 @@ use crate::helper::err_msgs::*;
 use crate::helper::str_replace;
 use crate::helper::{move_element, pine_ref_to_bool, pine_ref_to_i64, pine_ref_to_string};
 use crate::runtime::context::{downcast_ctx, Ctx};
-use crate::runtime::{ScriptPurpose, StudyScript};
+use crate::runtime::ScriptMeta;
 use crate::types::{Callable, CallableFactory, PineRef, RuntimeErr, NA};
 use std::rc::Rc;
 
-fn study<'a>(
+fn script_count<'a>(name: &str, val: Option<PineRef<'a>>) -> Result<Option<i64>, RuntimeErr> {
+    match pine_ref_to_i64(val) {
+        Some(v) if v < 1 => Err(RuntimeErr::InvalidParameters(str_replace(
+            GE_1,
+            vec![String::from(name)],
+        ))),
+        v => Ok(v),
+    }
+}
+
+pub fn declare_script<'a>(
+    declaration: &str,
     context: &mut dyn Ctx<'a>,
     mut param: Vec<Option<PineRef<'a>>>,
-    _func_type: FunctionType<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
-    move_tuplet!((title, shorttitle, overlay, format, precision) = param);
+    move_tuplet!(
+        (
+            title,
+            shorttitle,
+            overlay,
+            format,
+            precision,
+            max_bars_back,
+            max_lines_count,
+            max_labels_count,
+            max_boxes_count,
+            max_polylines_count
+        ) = param
+    );
     if !downcast_ctx(context).check_is_input_info_ready() {
         if let Some(title) = pine_ref_to_string(title) {
-            let study = StudyScript {
+            let meta = ScriptMeta {
+                declaration: String::from(declaration),
                 title,
                 shorttitle: pine_ref_to_string(shorttitle),
                 overlay: pine_ref_to_bool(overlay),
                 format: pine_ref_to_string(format),
                 precision: pine_ref_to_i64(precision),
+                max_bars_back: script_count("max_bars_back", max_bars_back)?,
+                max_lines_count: script_count("max_lines_count", max_lines_count)?,
+                max_labels_count: script_count("max_labels_count", max_labels_count)?,
+                max_boxes_count: script_count("max_boxes_count", max_boxes_count)?,
+                max_polylines_count: script_count("max_polylines_count", max_polylines_count)?,
+                description: None,
+                strategy_alert_message: None,
             };
-            downcast_ctx(context).set_script_type(ScriptPurpose::Study(study));
+            downcast_ctx(context).set_script_meta(meta);
         } else {
             return Err(RuntimeErr::MissingParameters(str_replace(
                 REQUIRED_PARAMETERS,
@@ -34,30 +65,68 @@ fn study<'a>(
     Ok(PineRef::new(NA))
 }
 
-pub const VAR_NAME: &'static str = "study";
+fn study<'a>(
+    context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    declare_script(VAR_NAME, context, param)
+}
 
-pub fn declare_var<'a>() -> VarResult<'a> {
-    let value = PineRef::new(CallableFactory::new(|| Callable::new(Some(study), None)));
+fn indicator<'a>(
+    context: &mut dyn Ctx<'a>,
+    param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    declare_script(INDICATOR_NAME, context, param)
+}
 
+// The parameters shared by all of the script declarations.
+pub fn gen_decl_params<'a>() -> Vec<(&'static str, SyntaxType<'a>)> {
+    vec![
+        ("title", SyntaxType::string()),
+        ("shorttitle", SyntaxType::string()),
+        ("overlay", SyntaxType::bool()),
+        ("format", SyntaxType::string()),
+        ("precision", SyntaxType::int()),
+        ("max_bars_back", SyntaxType::int()),
+        ("max_lines_count", SyntaxType::int()),
+        ("max_labels_count", SyntaxType::int()),
+        ("max_boxes_count", SyntaxType::int()),
+        ("max_polylines_count", SyntaxType::int()),
+    ]
+}
+
+fn gen_func_type<'a>() -> SyntaxType<'a> {
     let func_type = FunctionTypes(vec![FunctionType::new((
-        vec![
-            ("title", SyntaxType::string()),
-            ("shorttitle", SyntaxType::string()),
-            ("overlay", SyntaxType::bool()),
-            ("format", SyntaxType::string()),
-            ("precision", SyntaxType::int()),
-        ],
+        gen_decl_params(),
         SyntaxType::Void,
     ))]);
-    let syntax_type = SyntaxType::Function(Rc::new(func_type));
-    VarResult::new(value, syntax_type, VAR_NAME)
+    SyntaxType::Function(Rc::new(func_type))
+}
+
+pub const VAR_NAME: &'static str = "study";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| Callable::new(Some(study), None)));
+    VarResult::new(value, gen_func_type(), VAR_NAME)
+}
+
+pub const INDICATOR_NAME: &'static str = "indicator";
+
+pub fn declare_indicator_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(Some(indicator), None)
+    }));
+    VarResult::new(value, gen_func_type(), INDICATOR_NAME)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ast::syntax_type::SimpleSyntaxType;
-    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::libs::strategy;
+    use crate::runtime::{AnySeries, NoneCallback, ScriptPurpose, StudyScript};
     use crate::{LibInfo, PineParser, PineRunner};
 
     #[test]
@@ -91,4 +160,107 @@ mod tests {
             }))
         );
     }
+
+    #[test]
+    fn script_meta_test() {
+        let lib_info = LibInfo::new(
+            vec![
+                declare_var(),
+                declare_indicator_var(),
+                strategy::declare_var(),
+            ],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+
+        let src = r"indicator('hello', overlay=true, max_bars_back=100, max_lines_count=50)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_io_info().get_script_meta(),
+            &Some(ScriptMeta {
+                declaration: String::from("indicator"),
+                title: String::from("hello"),
+                shorttitle: None,
+                overlay: Some(true),
+                format: None,
+                precision: None,
+                max_bars_back: Some(100),
+                max_lines_count: Some(50),
+                max_labels_count: None,
+                max_boxes_count: None,
+                max_polylines_count: None,
+                description: None,
+                strategy_alert_message: None,
+            })
+        );
+
+        let src = r"strategy('st', max_boxes_count=10)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.run(&data, None).unwrap();
+        assert_eq!(
+            runner.get_io_info().get_script_type(),
+            &Some(ScriptPurpose::Strategy(StudyScript {
+                title: String::from("st"),
+                shorttitle: None,
+                overlay: None,
+                format: None,
+                precision: None,
+            }))
+        );
+        assert_eq!(
+            runner
+                .get_io_info()
+                .get_script_meta()
+                .as_ref()
+                .unwrap()
+                .max_boxes_count,
+            Some(10)
+        );
+
+        let src = r"study('st', max_labels_count=0)";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        assert_eq!(
+            runner.run(&data, None).map_err(|e| e.code),
+            Err(RuntimeErr::InvalidParameters(str_replace(
+                GE_1,
+                vec![String::from("max_labels_count")]
+            )))
+        );
+    }
+
+    #[test]
+    fn script_decl_syntax_test() {
+        use crate::ast::error::PineErrorKind;
+
+        let lib_info = LibInfo::new(
+            vec![
+                declare_var(),
+                declare_indicator_var(),
+                strategy::declare_var(),
+            ],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let get_errors = |src| match PineParser::new(src, &lib_info).parse_blk() {
+            Ok(_) => vec![],
+            Err(errs) => errs.into_iter().map(|e| e.code).collect(),
+        };
+
+        assert_eq!(get_errors("study('a')\nm = close"), vec![]);
+        assert_eq!(
+            get_errors("study('a')\nstrategy('b')"),
+            vec![PineErrorKind::ScriptDeclDuplicate]
+        );
+        assert_eq!(
+            get_errors("if close > 1\n    indicator('a')"),
+            vec![PineErrorKind::ScriptDeclNotTopLevel]
+        );
+        assert_eq!(
+            get_errors("m = study('a')"),
+            vec![PineErrorKind::ScriptDeclNotTopLevel]
+        );
+    }
 }
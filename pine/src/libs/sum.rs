@@ -1,4 +1,4 @@
-use super::sma::declare_ma_var;
+use super::sma::{declare_window_var, WindowKind};
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
 use crate::helper::{
@@ -7,7 +7,7 @@ use crate::helper::{
 };
 use crate::types::{
     downcast_pf_ref, int2float, Arithmetic, Callable, Evaluate, EvaluateVal, Float, Int, PineRef,
-    RefData, RuntimeErr, Series, SeriesCall, NA,
+    RuntimeErr, Series, SeriesCall, NA,
 };
 
 pub fn series_sum<'a>(source: &Series<Float>, length: i64) -> Result<Float, RuntimeErr> {
@@ -19,17 +19,8 @@ pub fn series_sum<'a>(source: &Series<Float>, length: i64) -> Result<Float, Runt
     Ok(sum_val)
 }
 
-fn sum_func<'a>(source: RefData<Series<Float>>, length: i64) -> Result<Float, RuntimeErr> {
-    let mut sum_val = Some(0f64);
-    for i in 0..length {
-        let val = source.index_value(i as usize).unwrap();
-        sum_val = sum_val.add(val);
-    }
-    Ok(sum_val)
-}
-
 pub fn declare_var<'a>() -> VarResult<'a> {
-    declare_ma_var("sum", sum_func)
+    declare_window_var("sum", WindowKind::Sum)
 }
 
 #[cfg(test)]
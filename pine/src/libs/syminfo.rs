@@ -93,6 +93,17 @@ impl<'a> PineClass<'a> for SymInfoProps {
     }
 }
 
+// The default min tick is the same as the strategy broker when the symbol info is not provided.
+pub const DEFAULT_MINTICK: f64 = 0.01;
+
+// Get the min tick of the main symbol, the functions that round or format the prices use it.
+pub fn get_mintick<'a>(ctx: &mut dyn Ctx<'a>) -> f64 {
+    match downcast_ctx(ctx.get_main_ctx()).get_syminfo() {
+        Some(syminfo) => syminfo.mintick,
+        None => DEFAULT_MINTICK,
+    }
+}
+
 pub const VAR_NAME: &'static str = "syminfo";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
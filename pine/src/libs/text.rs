@@ -12,6 +12,15 @@ use crate::types::{
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+pub const TEXT_ALIGN_LEFT: &'static str = "left";
+pub const TEXT_ALIGN_CENTER: &'static str = "center";
+pub const TEXT_ALIGN_RIGHT: &'static str = "right";
+pub const TEXT_ALIGN_TOP: &'static str = "top";
+pub const TEXT_ALIGN_BOTTOM: &'static str = "bottom";
+
+pub const TEXT_WRAP_AUTO: &'static str = "auto";
+pub const TEXT_WRAP_NONE: &'static str = "none";
+
 struct PlotProps;
 
 impl<'a> PineClass<'a> for PlotProps {
@@ -21,12 +30,16 @@ impl<'a> PineClass<'a> for PlotProps {
 
     fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
         match name {
-            "align_center" => Ok(PineRef::new(String::from("center"))),
-            "align_left" => Ok(PineRef::new(String::from("left"))),
-            "align_right" => Ok(PineRef::new(String::from("right"))),
+            "align_center" => Ok(PineRef::new(String::from(TEXT_ALIGN_CENTER))),
+            "align_left" => Ok(PineRef::new(String::from(TEXT_ALIGN_LEFT))),
+            "align_right" => Ok(PineRef::new(String::from(TEXT_ALIGN_RIGHT))),
+            "align_top" => Ok(PineRef::new(String::from(TEXT_ALIGN_TOP))),
+            "align_bottom" => Ok(PineRef::new(String::from(TEXT_ALIGN_BOTTOM))),
+            "wrap_auto" => Ok(PineRef::new(String::from(TEXT_WRAP_AUTO))),
+            "wrap_none" => Ok(PineRef::new(String::from(TEXT_WRAP_NONE))),
             _ => Err(RuntimeErr::NotImplement(str_replace(
                 NO_FIELD_IN_OBJECT,
-                vec![String::from(name), String::from("plot")],
+                vec![String::from(name), String::from("text")],
             ))),
         }
     }
@@ -45,6 +58,10 @@ pub fn declare_var<'a>() -> VarResult<'a> {
     obj_type.insert("align_center", SyntaxType::string());
     obj_type.insert("align_left", SyntaxType::string());
     obj_type.insert("align_right", SyntaxType::string());
+    obj_type.insert("align_top", SyntaxType::string());
+    obj_type.insert("align_bottom", SyntaxType::string());
+    obj_type.insert("wrap_auto", SyntaxType::string());
+    obj_type.insert("wrap_none", SyntaxType::string());
     let syntax_type = SyntaxType::Object(Rc::new(obj_type));
     VarResult::new(value, syntax_type, VAR_NAME)
 }
@@ -56,7 +73,7 @@ mod tests {
     use crate::{LibInfo, PineParser, PineRunner};
 
     #[test]
-    fn display_fields_test() {
+    fn text_fields_test() {
         use crate::ast::stat_expr_types::VarIndex;
         use crate::types::{downcast_pf, Tuple};
 
@@ -65,7 +82,8 @@ mod tests {
             vec![("close", SyntaxType::float_series())],
         );
         let src = r"m = [
-            text.align_center, text.align_left, text.align_right
+            text.align_center, text.align_left, text.align_right, text.align_top,
+            text.align_bottom, text.wrap_auto, text.wrap_none
         ]";
 
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
@@ -86,6 +104,10 @@ mod tests {
                 PineRef::new(String::from("center")),
                 PineRef::new(String::from("left")),
                 PineRef::new(String::from("right")),
+                PineRef::new(String::from("top")),
+                PineRef::new(String::from("bottom")),
+                PineRef::new(String::from("auto")),
+                PineRef::new(String::from("none")),
             ]
         );
     }
@@ -0,0 +1,491 @@
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::helper::{move_element, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string};
+use crate::runtime::context::Ctx;
+use crate::types::{Callable, Object, PineClass, PineRef, RuntimeErr};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+// The session types of the ticker. The regular session only contains the bars in the trade time
+// of the symbol, the extended session also contains the pre-market and post-market bars.
+pub const SESSION_REGULAR: &str = "regular";
+pub const SESSION_EXTENDED: &str = "extended";
+
+// The adjustment types of the ticker. The bars are raw with `none`, the splits adjustment only
+// adjusts the splits and the dividends adjustment adjusts both the splits and the dividends.
+pub const ADJUSTMENT_NONE: &str = "none";
+pub const ADJUSTMENT_SPLITS: &str = "splits";
+pub const ADJUSTMENT_DIVIDENDS: &str = "dividends";
+
+// The transformation that converts the origin bars to the non-standard chart bars.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BarTransform {
+    HeikinAshi,
+    // Renko(style, param), style is "ATR" or "Traditional"
+    Renko(String, f64),
+    // Kagi(reversal)
+    Kagi(f64),
+    // LineBreak(number_of_lines)
+    LineBreak(i64),
+}
+
+// The ticker identifier with the modifiers. It's formatted as the string like
+// `NASDAQ:AAPL;session=extended;adjustment=dividends;transform=renko(ATR,10)`,
+// so it can be passed to `security` and the data request layer can parse it back.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TickerId {
+    pub symbol: String,
+    pub session: Option<String>,
+    pub adjustment: Option<String>,
+    pub transform: Option<BarTransform>,
+}
+
+impl fmt::Display for BarTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BarTransform::HeikinAshi => write!(f, "heikinashi"),
+            BarTransform::Renko(style, param) => write!(f, "renko({},{})", style, param),
+            BarTransform::Kagi(reversal) => write!(f, "kagi({})", reversal),
+            BarTransform::LineBreak(lines) => write!(f, "linebreak({})", lines),
+        }
+    }
+}
+
+impl BarTransform {
+    pub fn parse(s: &str) -> Option<BarTransform> {
+        if s == "heikinashi" {
+            return Some(BarTransform::HeikinAshi);
+        }
+        let start = s.find('(')?;
+        let args: Vec<&str> = s[start + 1..].strip_suffix(')')?.split(',').collect();
+        match (&s[..start], args.as_slice()) {
+            ("renko", [style, param]) => Some(BarTransform::Renko(
+                String::from(*style),
+                param.parse().ok()?,
+            )),
+            ("kagi", [reversal]) => Some(BarTransform::Kagi(reversal.parse().ok()?)),
+            ("linebreak", [lines]) => Some(BarTransform::LineBreak(lines.parse().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TickerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol)?;
+        if let Some(session) = &self.session {
+            write!(f, ";session={}", session)?;
+        }
+        if let Some(adjustment) = &self.adjustment {
+            write!(f, ";adjustment={}", adjustment)?;
+        }
+        if let Some(transform) = &self.transform {
+            write!(f, ";transform={}", transform)?;
+        }
+        Ok(())
+    }
+}
+
+impl TickerId {
+    pub fn new(symbol: String) -> TickerId {
+        TickerId {
+            symbol,
+            ..TickerId::default()
+        }
+    }
+
+    // Parse the ticker string, the unknown modifiers are ignored.
+    pub fn parse(s: &str) -> TickerId {
+        let mut parts = s.split(';');
+        let mut ticker = TickerId::new(String::from(parts.next().unwrap_or("")));
+        for part in parts {
+            if let Some(i) = part.find('=') {
+                match (&part[..i], &part[i + 1..]) {
+                    ("session", v) => ticker.session = Some(String::from(v)),
+                    ("adjustment", v) => ticker.adjustment = Some(String::from(v)),
+                    ("transform", v) => ticker.transform = BarTransform::parse(v),
+                    _ => {}
+                }
+            }
+        }
+        ticker
+    }
+}
+
+fn require_str<'a>(val: Option<PineRef<'a>>, name: &str) -> Result<String, RuntimeErr> {
+    match pine_ref_to_string(val) {
+        Some(s) => Ok(s),
+        None => Err(RuntimeErr::InvalidParameters(str_replace(
+            REQUIRED_PARAMETERS,
+            vec![String::from(name)],
+        ))),
+    }
+}
+
+fn ticker_new<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((prefix, ticker, session, adjustment) = param);
+    let symbol = format!(
+        "{}:{}",
+        require_str(prefix, "prefix")?,
+        require_str(ticker, "ticker")?
+    );
+    let ticker = TickerId {
+        session: pine_ref_to_string(session),
+        adjustment: pine_ref_to_string(adjustment),
+        ..TickerId::new(symbol)
+    };
+    Ok(PineRef::new_rc(ticker.to_string()))
+}
+
+fn ticker_modify<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((tickerid, session, adjustment) = param);
+    let mut ticker = TickerId::parse(&require_str(tickerid, "tickerid")?);
+    if let Some(session) = pine_ref_to_string(session) {
+        ticker.session = Some(session);
+    }
+    if let Some(adjustment) = pine_ref_to_string(adjustment) {
+        ticker.adjustment = Some(adjustment);
+    }
+    Ok(PineRef::new_rc(ticker.to_string()))
+}
+
+fn with_transform<'a>(
+    symbol: Option<PineRef<'a>>,
+    transform: BarTransform,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let mut ticker = TickerId::parse(&require_str(symbol, "symbol")?);
+    ticker.transform = Some(transform);
+    Ok(PineRef::new_rc(ticker.to_string()))
+}
+
+fn gen_positive_err(name: &str) -> RuntimeErr {
+    RuntimeErr::InvalidParameters(str_replace(GE_1, vec![String::from(name)]))
+}
+
+fn ticker_heikinashi<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    with_transform(move_element(&mut param, 0), BarTransform::HeikinAshi)
+}
+
+fn ticker_renko<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, style, param) = param);
+    let style = pine_ref_to_string(style).unwrap_or(String::from("ATR"));
+    let param = pine_ref_to_f64(param).unwrap_or(14f64);
+    if style != "ATR" && style != "Traditional" {
+        return Err(RuntimeErr::InvalidParameters(str_replace(
+            INVALID_VALS,
+            vec![String::from("style")],
+        )));
+    }
+    if param <= 0f64 {
+        return Err(gen_positive_err("param"));
+    }
+    with_transform(symbol, BarTransform::Renko(style, param))
+}
+
+fn ticker_kagi<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, reversal) = param);
+    let reversal = pine_ref_to_f64(reversal).unwrap_or(1f64);
+    if reversal <= 0f64 {
+        return Err(gen_positive_err("reversal"));
+    }
+    with_transform(symbol, BarTransform::Kagi(reversal))
+}
+
+fn ticker_linebreak<'a>(
+    _context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    _func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    move_tuplet!((symbol, number_of_lines) = param);
+    let lines = pine_ref_to_i64(number_of_lines).unwrap_or(3);
+    if lines < 1 {
+        return Err(gen_positive_err("number_of_lines"));
+    }
+    with_transform(symbol, BarTransform::LineBreak(lines))
+}
+
+struct TickerProps;
+
+impl<'a> PineClass<'a> for TickerProps {
+    fn custom_type(&self) -> &str {
+        "ticker"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "new" => Ok(PineRef::new(Callable::new(Some(ticker_new), None))),
+            "modify" => Ok(PineRef::new(Callable::new(Some(ticker_modify), None))),
+            "heikinashi" => Ok(PineRef::new(Callable::new(Some(ticker_heikinashi), None))),
+            "renko" => Ok(PineRef::new(Callable::new(Some(ticker_renko), None))),
+            "kagi" => Ok(PineRef::new(Callable::new(Some(ticker_kagi), None))),
+            "linebreak" => Ok(PineRef::new(Callable::new(Some(ticker_linebreak), None))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("ticker")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(TickerProps)
+    }
+}
+
+fn gen_func_type<'a>(params: Vec<(&'a str, SyntaxType<'a>)>) -> SyntaxType<'a> {
+    SyntaxType::Function(Rc::new(FunctionTypes(vec![FunctionType::new((
+        params,
+        SyntaxType::string(),
+    ))])))
+}
+
+pub const VAR_NAME: &'static str = "ticker";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(TickerProps)));
+
+    let mut obj_type = BTreeMap::new();
+    // ticker.new(prefix, ticker, session, adjustment) → string
+    obj_type.insert(
+        "new",
+        gen_func_type(vec![
+            ("prefix", SyntaxType::string()),
+            ("ticker", SyntaxType::string()),
+            ("session", SyntaxType::string()),
+            ("adjustment", SyntaxType::string()),
+        ]),
+    );
+    // ticker.modify(tickerid, session, adjustment) → string
+    obj_type.insert(
+        "modify",
+        gen_func_type(vec![
+            ("tickerid", SyntaxType::string()),
+            ("session", SyntaxType::string()),
+            ("adjustment", SyntaxType::string()),
+        ]),
+    );
+    obj_type.insert(
+        "heikinashi",
+        gen_func_type(vec![("symbol", SyntaxType::string())]),
+    );
+    obj_type.insert(
+        "renko",
+        gen_func_type(vec![
+            ("symbol", SyntaxType::string()),
+            ("style", SyntaxType::string()),
+            ("param", SyntaxType::float()),
+        ]),
+    );
+    obj_type.insert(
+        "kagi",
+        gen_func_type(vec![
+            ("symbol", SyntaxType::string()),
+            ("reversal", SyntaxType::float()),
+        ]),
+    );
+    obj_type.insert(
+        "linebreak",
+        gen_func_type(vec![
+            ("symbol", SyntaxType::string()),
+            ("number_of_lines", SyntaxType::int()),
+        ]),
+    );
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+struct SessionProps;
+
+impl<'a> PineClass<'a> for SessionProps {
+    fn custom_type(&self) -> &str {
+        "session"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "regular" => Ok(PineRef::new_rc(String::from(SESSION_REGULAR))),
+            "extended" => Ok(PineRef::new_rc(String::from(SESSION_EXTENDED))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("session")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(SessionProps)
+    }
+}
+
+pub const SESSION_NAME: &'static str = "session";
+
+pub fn declare_session_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(SessionProps)));
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("regular", SyntaxType::string());
+    obj_type.insert("extended", SyntaxType::string());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, SESSION_NAME)
+}
+
+struct AdjustmentProps;
+
+impl<'a> PineClass<'a> for AdjustmentProps {
+    fn custom_type(&self) -> &str {
+        "adjustment"
+    }
+
+    fn get(&self, _ctx: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        match name {
+            "none" => Ok(PineRef::new_rc(String::from(ADJUSTMENT_NONE))),
+            "dividends" => Ok(PineRef::new_rc(String::from(ADJUSTMENT_DIVIDENDS))),
+            "splits" => Ok(PineRef::new_rc(String::from(ADJUSTMENT_SPLITS))),
+            _ => Err(RuntimeErr::NotImplement(str_replace(
+                NO_FIELD_IN_OBJECT,
+                vec![String::from(name), String::from("adjustment")],
+            ))),
+        }
+    }
+
+    fn copy(&self) -> Box<dyn PineClass<'a> + 'a> {
+        Box::new(AdjustmentProps)
+    }
+}
+
+pub const ADJUSTMENT_NAME: &'static str = "adjustment";
+
+pub fn declare_adjustment_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Object::new(Box::new(AdjustmentProps)));
+
+    let mut obj_type = BTreeMap::new();
+    obj_type.insert("none", SyntaxType::string());
+    obj_type.insert("dividends", SyntaxType::string());
+    obj_type.insert("splits", SyntaxType::string());
+    let syntax_type = SyntaxType::Object(Rc::new(obj_type));
+    VarResult::new(value, syntax_type, ADJUSTMENT_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::types::{downcast_pf, Tuple};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn ticker_id_test() {
+        let ticker = TickerId {
+            symbol: String::from("NASDAQ:AAPL"),
+            session: Some(String::from("extended")),
+            adjustment: None,
+            transform: Some(BarTransform::Renko(String::from("ATR"), 10f64)),
+        };
+        assert_eq!(
+            ticker.to_string(),
+            "NASDAQ:AAPL;session=extended;transform=renko(ATR,10)"
+        );
+        assert_eq!(TickerId::parse(&ticker.to_string()), ticker);
+        assert_eq!(
+            TickerId::parse("BATS:MSFT"),
+            TickerId::new(String::from("BATS:MSFT"))
+        );
+        assert_eq!(
+            BarTransform::parse("linebreak(3)"),
+            Some(BarTransform::LineBreak(3))
+        );
+        assert_eq!(BarTransform::parse("kagi(x)"), None);
+    }
+
+    #[test]
+    fn ticker_test() {
+        let lib_info = LibInfo::new(
+            vec![
+                declare_var(),
+                declare_session_var(),
+                declare_adjustment_var(),
+            ],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r#"t = ticker.new("NASDAQ", "AAPL", session.extended)
+m = [
+    t,
+    ticker.modify(t, adjustment=adjustment.dividends),
+    ticker.heikinashi(t),
+    ticker.renko("NASDAQ:AAPL", "Traditional", 2.5),
+    ticker.kagi("NASDAQ:AAPL", 2),
+    ticker.linebreak("NASDAQ:AAPL", 3)
+]"#;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            )
+            .unwrap();
+        let tuple_res =
+            downcast_pf::<Tuple>(runner.get_context().move_var(VarIndex::new(1, 0)).unwrap());
+        let tuple_vec = tuple_res.unwrap().into_inner().0;
+        assert_eq!(
+            tuple_vec,
+            vec![
+                PineRef::new_rc(String::from("NASDAQ:AAPL;session=extended")),
+                PineRef::new_rc(String::from(
+                    "NASDAQ:AAPL;session=extended;adjustment=dividends"
+                )),
+                PineRef::new_rc(String::from(
+                    "NASDAQ:AAPL;session=extended;transform=heikinashi"
+                )),
+                PineRef::new_rc(String::from("NASDAQ:AAPL;transform=renko(Traditional,2.5)")),
+                PineRef::new_rc(String::from("NASDAQ:AAPL;transform=kagi(2)")),
+                PineRef::new_rc(String::from("NASDAQ:AAPL;transform=linebreak(3)")),
+            ]
+        );
+    }
+
+    #[test]
+    fn ticker_invalid_param_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        for src in [
+            "m = ticker.renko('NASDAQ:AAPL', 'Box', 2)",
+            "m = ticker.kagi('NASDAQ:AAPL', 0)",
+            "m = ticker.linebreak('NASDAQ:AAPL', 0)",
+        ] {
+            let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+            let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+            let res = runner.run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))],
+                None,
+            );
+            assert!(res.is_err());
+        }
+    }
+}
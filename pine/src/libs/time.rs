@@ -1,7 +1,10 @@
 use super::VarResult;
 use crate::ast::stat_expr_types::VarIndex;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
-use crate::helper::{move_element, pine_ref_to_i64, pine_ref_to_string, Resolution, Session};
+use crate::helper::{
+    move_element, pine_ref_to_i64, pine_ref_to_string, Resolution, Session, TradeTimeSpan,
+};
+use crate::libs::ticker::{SESSION_EXTENDED, SESSION_REGULAR};
 use crate::runtime::{downcast_ctx, Ctx};
 use crate::types::{
     Callable, CallableEvaluate, Evaluate, EvaluateVal, Float, Int, PineRef, RuntimeErr, Series,
@@ -46,6 +49,8 @@ impl<'a> EvaluateVal<'a> for TimeVal {
 struct TimeCallVal {
     time_index: RefCell<Option<VarIndex>>,
     tz: RefCell<Option<Tz>>,
+    // The trade time of the symbol for the `session.regular` session.
+    regular: RefCell<Option<TradeTimeSpan>>,
 }
 
 impl TimeCallVal {
@@ -53,6 +58,7 @@ impl TimeCallVal {
         TimeCallVal {
             time_index: RefCell::new(None),
             tz: RefCell::new(None),
+            regular: RefCell::new(None),
         }
     }
 }
@@ -70,6 +76,10 @@ impl<'a> SeriesCall<'a> for TimeCallVal {
             match downcast_ctx(ctx).get_syminfo() {
                 Some(syminfo) => {
                     self.tz.replace(Some(syminfo.timezone.parse().unwrap()));
+                    self.regular.replace(TradeTimeSpan::try_parse_str(
+                        &syminfo.trade_start,
+                        &syminfo.trade_end,
+                    ));
                 }
                 _ => {
                     self.tz.replace(Some(Tz::America__New_York));
@@ -81,6 +91,7 @@ impl<'a> SeriesCall<'a> for TimeCallVal {
             _p,
             pine_ref_to_i64(ctx.get_var(index).clone()),
             self.tz.borrow().as_ref().unwrap(),
+            self.regular.borrow().as_ref(),
         )
     }
 
@@ -89,10 +100,26 @@ impl<'a> SeriesCall<'a> for TimeCallVal {
     }
 }
 
+// Check if the bar is in the session. The bars of the chart are all in the session if the session
+// is omitted or `session.extended`, `session.regular` is the trade time of the symbol.
+fn is_in_session(
+    session: &str,
+    timeval: i64,
+    tz: &Tz,
+    regular: Option<&TradeTimeSpan>,
+) -> Result<bool, RuntimeErr> {
+    match session {
+        "" | SESSION_EXTENDED => Ok(true),
+        SESSION_REGULAR => Ok(regular.is_none_or(|span| span.is_in(timeval, tz))),
+        _ => Ok(Session::parse(session)?.is_in(timeval, tz)),
+    }
+}
+
 fn process_time<'a>(
     mut param: Vec<Option<PineRef<'a>>>,
     timeval: Option<i64>,
     tz: &Tz,
+    regular: Option<&TradeTimeSpan>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     match timeval {
         Some(timeval) => {
@@ -100,7 +127,7 @@ fn process_time<'a>(
                 move_tuplet!((res, session) = param);
                 let res = pine_ref_to_string(res).unwrap_or(String::from(""));
                 let session = pine_ref_to_string(session).unwrap_or(String::from(""));
-                if !Session::parse(&session)?.is_in(timeval, tz) {
+                if !is_in_session(&session, timeval, tz, regular)? {
                     Ok(PineRef::new_rc(Series::from(Int::from(None))))
                 } else {
                     Ok(PineRef::new_rc(Series::from(Some(
@@ -185,7 +212,8 @@ mod tests {
                 ("_time", SyntaxType::Series(SimpleSyntaxType::Int)),
             ],
         );
-        let src = "m = time('5', '0900-1700')";
+        let src =
+            "m = time('5', '0900-1700')\nm2 = time('5', 'regular')\nm3 = time('5', 'extended')";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
         let ts = Tz::Asia__Shanghai
@@ -204,8 +232,8 @@ mod tests {
                     timezone: String::from("Asia/Shanghai"),
                     ticker: String::from(""),
                     session: String::from(""),
-                    trade_start: String::from(""),
-                    trade_end: String::from(""),
+                    trade_start: String::from("9:30"),
+                    trade_end: String::from("15:00"),
                     root: None,
                     currency: String::from(""),
                     description: String::from(""),
@@ -223,5 +251,14 @@ mod tests {
             runner.get_context().move_var(VarIndex::new(0, 0)),
             Some(PineRef::new_rc(Series::from_vec(vec![Some(res_ts)])))
         );
+        // The bar is out of the regular trade time of the symbol.
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![None as Int])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![Some(res_ts)])))
+        );
     }
 }
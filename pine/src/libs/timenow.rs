@@ -1,18 +1,15 @@
 use super::VarResult;
 use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
 use crate::helper::{move_element, pine_ref_to_bool, pine_ref_to_i64, pine_ref_to_string};
-use crate::runtime::context::Ctx;
+use crate::runtime::context::{downcast_ctx, Ctx};
 use crate::types::{Evaluate, EvaluateVal, PineRef, RuntimeErr};
-use chrono::Utc;
 
 #[derive(Debug, Clone, PartialEq)]
-struct TimenowVal {
-    now_time: i64,
-}
+struct TimenowVal;
 
 impl<'a> TimenowVal {
     fn new() -> TimenowVal {
-        TimenowVal { now_time: 0 }
+        TimenowVal
     }
 }
 
@@ -21,11 +18,10 @@ impl<'a> EvaluateVal<'a> for TimenowVal {
         "timenow"
     }
 
-    fn call(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, RuntimeErr> {
-        if self.now_time == 0 {
-            self.now_time = Utc::now().timestamp_millis();
-        }
-        Ok(PineRef::new_box(Some(self.now_time)))
+    // The time is read from the clock injected by the host, so the realtime updates get the new time.
+    fn call(&mut self, ctx: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+        let now = downcast_ctx(ctx).get_now_millis();
+        Ok(PineRef::new_box(Some(now)))
     }
 
     fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
@@ -45,9 +41,10 @@ pub fn declare_var<'a>() -> VarResult<'a> {
 mod tests {
     use super::*;
     use crate::ast::stat_expr_types::VarIndex;
-    use crate::runtime::{AnySeries, NoneCallback, VarOperate};
+    use crate::runtime::{AnySeries, FixedClock, NoneCallback, VarOperate};
     use crate::types::{Int, PineFrom};
     use crate::{LibInfo, PineParser, PineRunner};
+    use std::rc::Rc;
 
     #[test]
     fn timenow_test() {
@@ -71,4 +68,44 @@ mod tests {
             .into_inner();
         assert!(val.unwrap() > 0);
     }
+
+    #[test]
+    fn timenow_clock_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "m = timenow";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let clock = Rc::new(FixedClock::new(1000));
+        runner.set_clock(Some(clock.clone()));
+
+        runner
+            .run(
+                &vec![(
+                    "close",
+                    AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+                )],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(0, 0)),
+            &Some(PineRef::new_box(Some(1000)))
+        );
+
+        // The realtime update reads the new time of the clock.
+        clock.set(2000);
+        runner
+            .update(&vec![(
+                "close",
+                AnySeries::from_float_vec(vec![Some(3f64)]),
+            )])
+            .unwrap();
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(0, 0)),
+            &Some(PineRef::new_box(Some(2000)))
+        );
+    }
 }
@@ -0,0 +1,235 @@
+use super::syminfo::get_mintick;
+use super::VarResult;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::helper::{
+    move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_i64, pine_ref_to_string,
+    round_to_mintick,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{Callable, PineRef, RuntimeErr, Series};
+use std::rc::Rc;
+
+// The number of decimals when the format is not specified.
+const DEFAULT_DECIMALS: usize = 10;
+
+const NA_STR: &'static str = "NaN";
+
+// Format the float with the decimals, the trailing zeros after the `min_decimals` are removed.
+fn format_decimals(val: f64, decimals: usize, min_decimals: usize) -> String {
+    let mut s = format!("{:.*}", decimals, val);
+    if decimals > min_decimals {
+        let trim_len = s.trim_end_matches('0').len();
+        let min_len = s.len() - (decimals - min_decimals);
+        s.truncate(trim_len.max(min_len));
+        if s.ends_with('.') {
+            s.pop();
+        }
+    }
+    if s.starts_with('-') && s[1..].chars().all(|c| c == '0' || c == '.') {
+        s.remove(0);
+    }
+    s
+}
+
+// Format the volume like 1.234K, 5.6M.
+fn format_volume(val: f64) -> String {
+    let units = [(1e9, "B"), (1e6, "M"), (1e3, "K")];
+    match units.iter().find(|(base, _)| val.abs() >= *base) {
+        Some((base, unit)) => format!("{}{}", format_decimals(val / base, 3, 0), unit),
+        None => format_decimals(val, 3, 0),
+    }
+}
+
+// The count of decimals of the min tick, e.g. 2 for 0.01 and 0.25, 0 for 1.
+fn mintick_decimals(mintick: f64) -> usize {
+    (0..DEFAULT_DECIMALS)
+        .find(|&d| {
+            let scaled = mintick * 10f64.powi(d as i32);
+            (scaled - scaled.round()).abs() < 1e-9
+        })
+        .unwrap_or(DEFAULT_DECIMALS)
+}
+
+// Format the number by the format string. The format can be the pattern like `#.##` or `#.00`
+// where the count of `#` and `0` after the point is the decimals and `0` means the decimal is
+// always displayed, or the `format.volume`, or the `format.price` that displays the decimals of
+// the min tick.
+fn format_float(val: Option<f64>, format: Option<String>, mintick: f64) -> String {
+    let val = match val {
+        Some(v) if v.is_finite() => v,
+        _ => return String::from(NA_STR),
+    };
+    match format.as_deref() {
+        Some("volume") => format_volume(val),
+        Some("price") => {
+            let decimals = mintick_decimals(mintick);
+            format_decimals(round_to_mintick(val, mintick), decimals, decimals)
+        }
+        Some(pattern) if pattern.contains('#') || pattern.contains('0') => {
+            let decimals = match pattern.find('.') {
+                Some(i) => &pattern[i + 1..],
+                None => "",
+            };
+            let max = decimals.chars().filter(|&c| c == '#' || c == '0').count();
+            let min = decimals.chars().filter(|&c| c == '0').count();
+            format_decimals(val, max, min)
+        }
+        _ => format_decimals(val, DEFAULT_DECIMALS, 0),
+    }
+}
+
+fn to_string<'a>(
+    val: Option<PineRef<'a>>,
+    format: Option<String>,
+    mintick: f64,
+    t: &SimpleSyntaxType,
+) -> String {
+    match t {
+        SimpleSyntaxType::Int => match pine_ref_to_i64(val) {
+            Some(v) if format.is_none() => v.to_string(),
+            v => format_float(v.map(|v| v as f64), format, mintick),
+        },
+        SimpleSyntaxType::Float => format_float(pine_ref_to_f64(val), format, mintick),
+        SimpleSyntaxType::Bool => pine_ref_to_bool(val).unwrap_or(false).to_string(),
+        _ => pine_ref_to_string(val).unwrap_or_default(),
+    }
+}
+
+fn tostring_func<'a>(
+    context: &mut dyn Ctx<'a>,
+    mut param: Vec<Option<PineRef<'a>>>,
+    func_type: FunctionType<'a>,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let x = move_element(&mut param, 0);
+    let format = pine_ref_to_string(move_element(&mut param, 1));
+    let mintick = get_mintick(context);
+
+    match &((func_type.signature.0)[0]).1 {
+        SyntaxType::Simple(t) => Ok(PineRef::new_rc(to_string(x, format, mintick, t))),
+        SyntaxType::Series(t) => Ok(PineRef::new_rc(Series::from(to_string(
+            x, format, mintick, t,
+        )))),
+        _ => unreachable!(),
+    }
+}
+
+pub const VAR_NAME: &'static str = "tostring";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(Callable::new(Some(tostring_func), None));
+
+    // The int overloads come first, so the int value is not formatted as float.
+    let types = vec![
+        SimpleSyntaxType::Int,
+        SimpleSyntaxType::Float,
+        SimpleSyntaxType::Bool,
+        SimpleSyntaxType::String,
+    ];
+    let func_type = FunctionTypes(
+        types
+            .into_iter()
+            .flat_map(|t| {
+                vec![
+                    FunctionType::new((
+                        vec![
+                            ("x", SyntaxType::Simple(t.clone())),
+                            ("format", SyntaxType::string()),
+                        ],
+                        SyntaxType::string(),
+                    )),
+                    FunctionType::new((
+                        vec![
+                            ("x", SyntaxType::Series(t)),
+                            ("format", SyntaxType::string()),
+                        ],
+                        SyntaxType::string_series(),
+                    )),
+                ]
+            })
+            .collect(),
+    );
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stat_expr_types::VarIndex;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn format_float_test() {
+        let fmt = |v, f: &str| format_float(Some(v), Some(String::from(f)), 0.01);
+        assert_eq!(format_float(Some(0.1 + 0.2), None, 0.01), "0.3");
+        assert_eq!(format_float(Some(2f64), None, 0.01), "2");
+        assert_eq!(format_float(Some(-1.5), None, 0.01), "-1.5");
+        assert_eq!(format_float(None, None, 0.01), "NaN");
+        assert_eq!(fmt(1.23456, "#.##"), "1.23");
+        assert_eq!(fmt(3.1, "#.00"), "3.10");
+        assert_eq!(fmt(3.1, "#.0#"), "3.1");
+        assert_eq!(fmt(3.0, "#.0#"), "3.0");
+        assert_eq!(fmt(2.6, "#"), "3");
+        assert_eq!(fmt(-0.001, "#.##"), "0");
+        assert_eq!(fmt(1234.0, "volume"), "1.234K");
+        assert_eq!(fmt(2500000.0, "volume"), "2.5M");
+        assert_eq!(fmt(12.0, "volume"), "12");
+        assert_eq!(fmt(1.5, "price"), "1.50");
+        assert_eq!(fmt(1.2345, "price"), "1.23");
+        assert_eq!(
+            format_float(Some(3.3), Some(String::from("price")), 0.25),
+            "3.25"
+        );
+        assert_eq!(
+            format_float(Some(12.6), Some(String::from("price")), 1f64),
+            "13"
+        );
+    }
+
+    #[test]
+    fn mintick_decimals_test() {
+        assert_eq!(mintick_decimals(1f64), 0);
+        assert_eq!(mintick_decimals(0.01), 2);
+        assert_eq!(mintick_decimals(0.25), 2);
+        assert_eq!(mintick_decimals(0.00001), 5);
+    }
+
+    #[test]
+    fn tostring_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = r##"
+m1 = "a" + tostring(12)
+m2 = "close: " + tostring(close, "#.##")
+m3 = tostring(close > 1) + tostring(close > 2 ? 1 : 2)
+"##;
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        runner
+            .run(
+                &vec![("close", AnySeries::from_float_vec(vec![Some(1.2345f64)]))],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(String::from("a12")))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(1, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![String::from(
+                "close: 1.23"
+            )])))
+        );
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(2, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![String::from(
+                "true2"
+            )])))
+        );
+    }
+}
@@ -1,5 +1,5 @@
-use super::ema::ema_func;
 use super::ema::rma_func;
+use super::ema::{ema_func, MaState};
 use super::sma::{declare_ma_var, wma_func};
 use super::tr::tr_func;
 use super::VarResult;
@@ -24,20 +24,20 @@ use std::rc::Rc;
 #[derive(Debug, Clone, PartialEq)]
 pub struct KcVal<'a> {
     closes: Series<'a, Float>,
-    smooth1: Series<'a, Float>,
-    smooth2: Series<'a, Float>,
-    asmooth1: Series<'a, Float>,
-    asmooth2: Series<'a, Float>,
+    smooth1: MaState,
+    smooth2: MaState,
+    asmooth1: MaState,
+    asmooth2: MaState,
 }
 
 impl<'a> KcVal<'a> {
     pub fn new() -> KcVal<'a> {
         KcVal {
             closes: Series::new(),
-            smooth1: Series::new(),
-            smooth2: Series::new(),
-            asmooth1: Series::new(),
-            asmooth2: Series::new(),
+            smooth1: MaState::new(),
+            smooth2: MaState::new(),
+            asmooth1: MaState::new(),
+            asmooth2: MaState::new(),
         }
     }
 
@@ -57,10 +57,11 @@ impl<'a> KcVal<'a> {
         // PC = Current Price minus Prior Price
         // First Smoothing = 25-period EMA of PC
         // Second Smoothing = 13-period EMA of 25-period EMA of PC
+        let warmup = downcast_ctx(_ctx).get_ema_warmup();
         let close = pine_ref_to_f64(source);
         let pc = close.minus(self.closes.index_value(1)?);
-        let s1val = ema_func(pc, long, self.smooth1.index_value(1)?)?;
-        let s2val = ema_func(s1val, short, self.smooth2.index_value(1)?)?;
+        let s1val = self.smooth1.next(warmup, ema_func, pc, long)?;
+        let s2val = self.smooth2.next(warmup, ema_func, s1val, short)?;
 
         // Double Smoothed Absolute PC
         // ---------------------------
@@ -68,16 +69,12 @@ impl<'a> KcVal<'a> {
         // First Smoothing = 25-period EMA of |PC|
         // Second Smoothing = 13-period EMA of 25-period EMA of |PC|
         let apc = float_abs(close.minus(self.closes.index_value(1)?));
-        let as1val = ema_func(apc, long, self.asmooth1.index_value(1)?)?;
-        let as2val = ema_func(as1val, short, self.asmooth2.index_value(1)?)?;
+        let as1val = self.asmooth1.next(warmup, ema_func, apc, long)?;
+        let as2val = self.asmooth2.next(warmup, ema_func, as1val, short)?;
 
         // TSI = (Double Smoothed PC / Double Smoothed Absolute PC)
         let tsi = s2val.div(as2val);
         self.closes.update_commit(close);
-        self.smooth1.update_commit(s1val);
-        self.smooth2.update_commit(s2val);
-        self.asmooth1.update_commit(as1val);
-        self.asmooth2.update_commit(as2val);
         Ok(tsi)
     }
 }
@@ -95,10 +92,10 @@ impl<'a> SeriesCall<'a> for KcVal<'a> {
 
     fn back(&mut self, _context: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
         self.closes.roll_back();
-        self.smooth1.roll_back();
-        self.smooth2.roll_back();
-        self.asmooth1.roll_back();
-        self.asmooth2.roll_back();
+        self.smooth1.back();
+        self.smooth2.back();
+        self.asmooth1.back();
+        self.asmooth2.back();
         Ok(())
     }
 
@@ -140,7 +137,7 @@ mod tests {
             vec![declare_var()],
             vec![("close", SyntaxType::float_series())],
         );
-        let src = "m = tsi(close, 2, 2)\n";
+        let src = "m = tsi(close, 1, 2)\n";
         let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
         let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
 
@@ -148,7 +145,7 @@ mod tests {
             .run(
                 &vec![(
                     "close",
-                    AnySeries::from_float_vec(vec![Some(20f64), Some(10f64)]),
+                    AnySeries::from_float_vec(vec![Some(20f64), Some(10f64), Some(40f64)]),
                 )],
                 None,
             )
@@ -156,7 +153,7 @@ mod tests {
 
         assert_eq!(
             runner.get_context().move_var(VarIndex::new(0, 0)),
-            Some(PineRef::new(Series::from_vec(vec![None, Some(-1.0)])))
+            Some(PineRef::new(Series::from_vec(vec![None, None, Some(0.5)])))
         );
     }
 }
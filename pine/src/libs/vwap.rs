@@ -0,0 +1,302 @@
+use super::year::MyTz;
+use super::VarResult;
+use crate::ast::stat_expr_types::VarIndex;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::{
+    ensure_srcs, move_element, pine_ref_to_bool, pine_ref_to_f64, pine_ref_to_i64,
+};
+use crate::runtime::bar_time::{parse_tz, session_start_minutes, trading_day};
+use crate::runtime::context::{downcast_ctx, Ctx};
+use crate::types::{
+    Arithmetic, Callable, CallableEvaluate, Evaluate, EvaluateVal, Float, ParamCollectCall,
+    PineRef, RuntimeErr, Series, SeriesCall,
+};
+use std::rc::Rc;
+
+// The accumulated sums of the current period of the bar.
+#[derive(Debug, Clone, PartialEq)]
+struct VwapSum {
+    // The trading day of the bar, the period without the anchor starts on the new day.
+    day: Option<i64>,
+    price_volume: f64,
+    volume: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct VwapVal {
+    close_index: VarIndex,
+    low_index: VarIndex,
+    high_index: VarIndex,
+    volume_index: VarIndex,
+    time_index: VarIndex,
+    // The exchange timezone and the session start minutes to get the trading day of the bar.
+    session: Option<(MyTz, i32)>,
+    sum_history: Vec<VwapSum>,
+}
+
+impl VwapVal {
+    pub fn new() -> VwapVal {
+        VwapVal {
+            close_index: VarIndex::new(0, 0),
+            low_index: VarIndex::new(0, 0),
+            high_index: VarIndex::new(0, 0),
+            volume_index: VarIndex::new(0, 0),
+            time_index: VarIndex::new(0, 0),
+            session: None,
+            sum_history: vec![],
+        }
+    }
+
+    fn handle_index<'a>(&mut self, ctx: &mut dyn Ctx<'a>) {
+        ensure_srcs(
+            ctx,
+            vec!["close", "low", "high", "volume", "_time"],
+            |indexs| {
+                self.close_index = indexs[0];
+                self.low_index = indexs[1];
+                self.high_index = indexs[2];
+                self.volume_index = indexs[3];
+                self.time_index = indexs[4];
+            },
+        );
+    }
+
+    // Accumulate the source weighted by the volume from the start of the period. The period
+    // starts when the anchor is true, or on the new trading day if there is no anchor.
+    fn calc_vwap<'a>(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        source: Float,
+        anchor: Option<bool>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        let volume = pine_ref_to_i64(ctx.get_var(self.volume_index).clone());
+        if self.session.is_none() {
+            let syminfo = downcast_ctx(ctx.get_main_ctx()).get_syminfo().clone();
+            self.session = Some((
+                parse_tz(syminfo.as_deref()),
+                session_start_minutes(syminfo.as_deref()),
+            ));
+        }
+        let (tz, start_m) = self.session.as_ref().unwrap();
+        let day = pine_ref_to_i64(ctx.get_var(self.time_index).clone())
+            .map(|time| trading_day(time, tz, *start_m));
+
+        let mut sum = match (self.sum_history.last(), anchor) {
+            (Some(prev), Some(false)) => prev.clone(),
+            (Some(prev), None) if prev.day == day => prev.clone(),
+            _ => VwapSum {
+                day,
+                price_volume: 0f64,
+                volume: 0f64,
+            },
+        };
+        sum.day = day;
+        if let (Some(source), Some(volume)) = (source, volume) {
+            sum.price_volume += source * volume as f64;
+            sum.volume += volume as f64;
+        }
+        let res = match sum.volume {
+            v if v > 0f64 => Some(sum.price_volume / v),
+            _ => None,
+        };
+        self.sum_history.push(sum);
+        Ok(PineRef::new_rc(Series::from(res)))
+    }
+}
+
+impl<'a> EvaluateVal<'a> for VwapVal {
+    fn custom_name(&self) -> &str {
+        "vwap"
+    }
+
+    fn call(&mut self, ctx: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+        self.handle_index(ctx);
+        let close = pine_ref_to_f64(ctx.get_var(self.close_index).clone());
+        let low = pine_ref_to_f64(ctx.get_var(self.low_index).clone());
+        let high = pine_ref_to_f64(ctx.get_var(self.high_index).clone());
+        let hlc3 = high.add(low).add(close).div(Some(3f64));
+        self.calc_vwap(ctx, hlc3, None)
+    }
+
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.sum_history.pop();
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<'a> SeriesCall<'a> for VwapVal {
+    fn step(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        self.handle_index(ctx);
+        let source = pine_ref_to_f64(move_element(&mut param, 0));
+        // The na anchor does not start the new period.
+        let anchor = match param.len() {
+            2 => Some(pine_ref_to_bool(move_element(&mut param, 1)).unwrap_or(false)),
+            _ => None,
+        };
+        self.calc_vwap(ctx, source, anchor)
+    }
+
+    fn back(&mut self, _ctx: &mut dyn Ctx<'a>) -> Result<(), RuntimeErr> {
+        self.sum_history.pop();
+        Ok(())
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub const VAR_NAME: &'static str = "vwap";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableEvaluate::new(
+        || Evaluate::new(Box::new(VwapVal::new())),
+        || {
+            Callable::new(
+                None,
+                Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                    VwapVal::new(),
+                )))),
+            )
+        },
+    ));
+
+    let func_type = FunctionTypes(vec![
+        FunctionType::new((
+            vec![("source", SyntaxType::float_series())],
+            SyntaxType::float_series(),
+        )),
+        FunctionType::new((
+            vec![
+                ("source", SyntaxType::float_series()),
+                ("anchor", SyntaxType::bool_series()),
+            ],
+            SyntaxType::float_series(),
+        )),
+    ]);
+    let syntax_type =
+        SyntaxType::ValFunction(Box::new(SyntaxType::float_series()), Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{AnySeries, NoneCallback, SymbolInfo};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    fn assert_vals<'a>(runner: &mut PineRunner<'a>, i: i32, vals: Vec<Float>) {
+        assert_eq!(
+            runner.get_context().get_var(VarIndex::new(i, 0)),
+            &Some(PineRef::new(Series::from_vec(vals)))
+        );
+    }
+
+    #[test]
+    fn vwap_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+                ("volume", SyntaxType::int_series()),
+                ("_time", SyntaxType::int_series()),
+            ],
+        );
+        let src = "m = vwap\nn = vwap(close)\na = vwap(close, close == 3)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let gen_data = |close: Vec<f64>, volume: Vec<i64>, time: Vec<i64>| {
+            vec![
+                (
+                    "close",
+                    AnySeries::from_float_vec(close.iter().map(|v| Some(*v)).collect()),
+                ),
+                (
+                    "high",
+                    AnySeries::from_float_vec(close.iter().map(|v| Some(v + 1f64)).collect()),
+                ),
+                (
+                    "low",
+                    AnySeries::from_float_vec(close.iter().map(|v| Some(v - 1f64)).collect()),
+                ),
+                (
+                    "volume",
+                    AnySeries::from_int_vec(volume.into_iter().map(Some).collect()),
+                ),
+                (
+                    "_time",
+                    AnySeries::from_int_vec(time.into_iter().map(Some).collect()),
+                ),
+            ]
+        };
+        let syminfo = Rc::new(SymbolInfo {
+            symbol_type: String::from("crypto"),
+            timezone: String::from("UTC"),
+            ticker: String::from("BINANCE:BTCUSDT"),
+            session: String::from("regular"),
+            trade_start: String::from(""),
+            trade_end: String::from(""),
+            root: None,
+            currency: String::from("USD"),
+            description: String::from(""),
+            mintick: 0.01,
+        });
+
+        // The period without the anchor starts on the new day.
+        const HOUR: i64 = 3_600_000;
+        runner
+            .run(
+                &gen_data(
+                    vec![1f64, 3f64, 5f64, 7f64],
+                    vec![1, 1, 2, 2],
+                    vec![0, HOUR, 24 * HOUR, 25 * HOUR],
+                ),
+                Some(syminfo),
+            )
+            .unwrap();
+        let session_vals = vec![Some(1f64), Some(2f64), Some(5f64), Some(6f64)];
+        assert_vals(&mut runner, 0, session_vals.clone());
+        assert_vals(&mut runner, 1, session_vals);
+        assert_vals(
+            &mut runner,
+            2,
+            vec![
+                Some(1f64),
+                Some(3f64),
+                Some(13f64 / 3f64),
+                Some(27f64 / 5f64),
+            ],
+        );
+
+        // The sums of the last bar are rolled back by the update.
+        runner
+            .update(&gen_data(vec![9f64], vec![2], vec![25 * HOUR]))
+            .unwrap();
+        assert_vals(
+            &mut runner,
+            1,
+            vec![Some(1f64), Some(2f64), Some(5f64), Some(7f64)],
+        );
+        assert_vals(
+            &mut runner,
+            2,
+            vec![
+                Some(1f64),
+                Some(3f64),
+                Some(13f64 / 3f64),
+                Some(31f64 / 5f64),
+            ],
+        );
+    }
+}
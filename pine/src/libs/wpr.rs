@@ -0,0 +1,142 @@
+use super::VarResult;
+use crate::ast::stat_expr_types::VarIndex;
+use crate::ast::syntax_type::{FunctionType, FunctionTypes, SyntaxType};
+use crate::helper::{
+    ensure_srcs, ge1_param_i64, pine_ref_to_f64, pine_ref_to_f64_series, pine_ref_to_i64,
+};
+use crate::runtime::context::Ctx;
+use crate::types::{
+    Arithmetic, Callable, CallableFactory, Float, ParamCollectCall, PineRef, RuntimeErr, Series,
+    SeriesCall,
+};
+use std::rc::Rc;
+
+// The highest or lowest value of the last `length` bars, it is na if any value is na.
+fn window_extreme<'a>(series: Option<PineRef<'a>>, length: i64, is_max: bool) -> Float {
+    let series = pine_ref_to_f64_series(series)?;
+    let mut res: Option<f64> = None;
+    for i in 0..length as usize {
+        let val = series.index_value(i).unwrap()?;
+        res = match res {
+            Some(v) if (is_max && v >= val) || (!is_max && v <= val) => Some(v),
+            _ => Some(val),
+        };
+    }
+    res
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WprVal {
+    close_index: VarIndex,
+    low_index: VarIndex,
+    high_index: VarIndex,
+}
+
+impl WprVal {
+    pub fn new() -> WprVal {
+        WprVal {
+            close_index: VarIndex::new(0, 0),
+            low_index: VarIndex::new(0, 0),
+            high_index: VarIndex::new(0, 0),
+        }
+    }
+
+    fn handle_index<'a>(&mut self, ctx: &mut dyn Ctx<'a>) {
+        ensure_srcs(ctx, vec!["close", "low", "high"], |indexs| {
+            self.close_index = indexs[0];
+            self.low_index = indexs[1];
+            self.high_index = indexs[2];
+        });
+    }
+}
+
+// wpr = 100 * (close - highest(high, length)) / (highest(high, length) - lowest(low, length))
+impl<'a> SeriesCall<'a> for WprVal {
+    fn step(
+        &mut self,
+        ctx: &mut dyn Ctx<'a>,
+        mut param: Vec<Option<PineRef<'a>>>,
+        _func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        self.handle_index(ctx);
+        let length = ge1_param_i64("length", pine_ref_to_i64(param[0].take()))?;
+
+        let close = pine_ref_to_f64(ctx.get_var(self.close_index).clone());
+        let highest = window_extreme(ctx.get_var(self.high_index).clone(), length, true);
+        let lowest = window_extreme(ctx.get_var(self.low_index).clone(), length, false);
+        let res = Some(100f64)
+            .mul(close.minus(highest))
+            .div(highest.minus(lowest));
+        Ok(PineRef::new_rc(Series::from(res)))
+    }
+
+    fn copy(&self) -> Box<dyn SeriesCall<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+pub const VAR_NAME: &'static str = "wpr";
+
+pub fn declare_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(CallableFactory::new(|| {
+        Callable::new(
+            None,
+            Some(Box::new(ParamCollectCall::new_with_caller(Box::new(
+                WprVal::new(),
+            )))),
+        )
+    }));
+
+    let func_type = FunctionTypes(vec![FunctionType::new((
+        vec![("length", SyntaxType::int())],
+        SyntaxType::float_series(),
+    ))]);
+    let syntax_type = SyntaxType::Function(Rc::new(func_type));
+    VarResult::new(value, syntax_type, VAR_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{AnySeries, NoneCallback};
+    use crate::testing::approx::{assert_series_approx_eq, DEFAULT_TOLERANCE};
+    use crate::{LibInfo, PineParser, PineRunner};
+
+    #[test]
+    fn wpr_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_var()],
+            vec![
+                ("close", SyntaxType::float_series()),
+                ("high", SyntaxType::float_series()),
+                ("low", SyntaxType::float_series()),
+            ],
+        );
+        let src = "m = wpr(2)\n";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        let series = |v: Vec<f64>| AnySeries::from_float_vec(v.into_iter().map(Some).collect());
+
+        runner
+            .run(
+                &vec![
+                    ("close", series(vec![10f64, 12f64, 9f64, 14f64])),
+                    ("high", series(vec![11f64, 13f64, 12f64, 14f64])),
+                    ("low", series(vec![8f64, 10f64, 6f64, 11f64])),
+                ],
+                None,
+            )
+            .unwrap();
+        let val = runner.get_context().get_var(VarIndex::new(0, 0)).clone();
+        assert_series_approx_eq(
+            pine_ref_to_f64_series(val).unwrap().get_history(),
+            &[
+                None,
+                Some(100f64 * (12f64 - 13f64) / (13f64 - 8f64)),
+                Some(100f64 * (9f64 - 13f64) / (13f64 - 6f64)),
+                Some(0f64),
+            ],
+            DEFAULT_TOLERANCE,
+        );
+    }
+}
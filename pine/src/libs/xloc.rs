@@ -42,6 +42,54 @@ impl<'a> PineClass<'a> for PlotProps {
     }
 }
 
+// The interval between the last two bars that is used to extrapolate the future bars.
+fn last_interval(times: &[Int]) -> Option<i64> {
+    match times {
+        [.., Some(t1), Some(t2)] if t2 > t1 => Some(t2 - t1),
+        _ => None,
+    }
+}
+
+// Convert the bar time to the bar index. The time between two bars maps to the earlier bar,
+// and the time after the last bar is extrapolated by the interval of the last two bars.
+pub fn time_to_bar_index(times: &[Int], time: i64) -> Int {
+    let last_time = match times.last() {
+        Some(Some(t)) => *t,
+        _ => return None,
+    };
+    if time > last_time {
+        let interval = last_interval(times)?;
+        return Some(times.len() as i64 - 1 + (time - last_time) / interval);
+    }
+    let count = times
+        .iter()
+        .take_while(|t| match t {
+            Some(t) => *t <= time,
+            None => true,
+        })
+        .count();
+    match count {
+        0 => None,
+        n => Some(n as i64 - 1),
+    }
+}
+
+// Convert the bar index to the bar time, the index after the last bar is extrapolated by the
+// interval of the last two bars.
+pub fn bar_index_to_time(times: &[Int], index: i64) -> Int {
+    if index < 0 {
+        return None;
+    }
+    match times.get(index as usize) {
+        Some(t) => *t,
+        None => {
+            let last_time = (*times.last()?)?;
+            let interval = last_interval(times)?;
+            Some(last_time + (index - times.len() as i64 + 1) * interval)
+        }
+    }
+}
+
 pub const VAR_NAME: &'static str = "xloc";
 
 pub fn declare_var<'a>() -> VarResult<'a> {
@@ -60,6 +108,24 @@ mod tests {
     use crate::runtime::{AnySeries, NoneCallback};
     use crate::{LibInfo, PineParser, PineRunner};
 
+    #[test]
+    fn time_to_bar_index_test() {
+        let times = vec![Some(100), Some(200), Some(300)];
+        assert_eq!(time_to_bar_index(&times, 50), None);
+        assert_eq!(time_to_bar_index(&times, 100), Some(0));
+        assert_eq!(time_to_bar_index(&times, 250), Some(1));
+        assert_eq!(time_to_bar_index(&times, 300), Some(2));
+        assert_eq!(time_to_bar_index(&times, 500), Some(4));
+        assert_eq!(time_to_bar_index(&[], 100), None);
+        assert_eq!(time_to_bar_index(&[Some(100)], 200), None);
+
+        assert_eq!(bar_index_to_time(&times, -1), None);
+        assert_eq!(bar_index_to_time(&times, 0), Some(100));
+        assert_eq!(bar_index_to_time(&times, 2), Some(300));
+        assert_eq!(bar_index_to_time(&times, 4), Some(500));
+        assert_eq!(bar_index_to_time(&[], 0), None);
+    }
+
     #[test]
     fn xloc_test() {
         use crate::ast::stat_expr_types::VarIndex;
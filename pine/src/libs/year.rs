@@ -6,10 +6,11 @@ use crate::helper::str_replace;
 use crate::helper::{
     ensure_srcs, move_element, pine_ref_to_i64, pine_ref_to_string, Resolution, Session,
 };
+use crate::runtime::bar_time::{parse_tz, session_start_minutes};
 use crate::runtime::{downcast_ctx, Ctx};
 use crate::types::{
-    CallObjEval, Callable, CallableEvaluate, Evaluate, EvaluateVal, Float, Int, PineClass,
-    PineFrom, PineRef, RefData, RuntimeErr, Series, SeriesCall,
+    CallObjEval, Callable, CallableEvaluate, Evaluate, EvaluateFactory, EvaluateVal, Float, Int,
+    PineClass, PineFrom, PineRef, RefData, RuntimeErr, Series, SeriesCall,
 };
 use chrono::{DateTime, Datelike, Local, TimeZone, Timelike};
 use chrono_tz::Tz;
@@ -89,16 +90,27 @@ impl MyTz {
             MyTz::Local => Local.timestamp_millis(millis).second() as i64,
         }
     }
+
+    // Get the trading day of the time for the session that starts at `start_m` minutes of the
+    // day. The session starts at the previous day has the negative `start_m`, so the bars after
+    // the session start belong to the next day. It's None for the time out of the range of dates.
+    fn session_day(&self, millis: i64, start_m: i32) -> Option<i32> {
+        let millis = millis.checked_sub(start_m as i64 * 60_000)?;
+        match self {
+            MyTz::Tz(tz) => tz
+                .timestamp_millis_opt(millis)
+                .single()
+                .map(|t| t.num_days_from_ce()),
+            MyTz::Local => Local
+                .timestamp_millis_opt(millis)
+                .single()
+                .map(|t| t.num_days_from_ce()),
+        }
+    }
 }
 
 pub fn parse_tz_from_ctx<'a>(ctx: &mut dyn Ctx<'a>) -> MyTz {
-    match downcast_ctx(ctx).get_syminfo() {
-        Some(syminfo) => match syminfo.timezone.parse() {
-            Ok(tz) => MyTz::Tz(tz),
-            Err(_) => MyTz::Local,
-        },
-        None => MyTz::Local,
-    }
+    parse_tz(downcast_ctx(ctx).get_syminfo().as_deref())
 }
 
 // fn get_year_from_ts(tz: &Tz, cur_time: Int) -> Int {
@@ -292,6 +304,77 @@ fn get_second<'a>(time: Option<PineRef<'a>>, tz: &MyTz) -> PineRef<'a> {
     PineRef::new(Series::from(val))
 }
 
+// Get the session start minutes of the day from the symbol info, the calendar day is used when the
+// trade time is not available.
+fn parse_session_start<'a>(ctx: &mut dyn Ctx<'a>) -> i32 {
+    session_start_minutes(downcast_ctx(ctx).get_syminfo().as_deref())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SessBarsVal {
+    time_index: Option<VarIndex>,
+    tz: Option<MyTz>,
+    start_m: i32,
+    // The time, trading day and bar count of the last bar.
+    last_bar: Option<(i64, i32, i64)>,
+}
+
+impl SessBarsVal {
+    pub fn new() -> SessBarsVal {
+        SessBarsVal {
+            time_index: None,
+            tz: None,
+            start_m: 0,
+            last_bar: None,
+        }
+    }
+}
+
+impl<'a> EvaluateVal<'a> for SessBarsVal {
+    fn custom_name(&self) -> &str {
+        "sessbars"
+    }
+
+    fn call(&mut self, ctx: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, RuntimeErr> {
+        if self.time_index.is_none() {
+            ensure_srcs(ctx, vec!["_time"], |indexs| {
+                self.time_index = Some(indexs[0]);
+            });
+            self.tz = Some(parse_tz_from_ctx(ctx));
+            self.start_m = parse_session_start(ctx);
+        }
+        let tz = self.tz.as_ref().unwrap();
+        let (time, day) = match pine_ref_to_i64(ctx.get_var(self.time_index.unwrap()).clone()) {
+            Some(time) => match tz.session_day(time, self.start_m) {
+                Some(day) => (time, day),
+                None => return Ok(PineRef::new(Series::from(None as Int))),
+            },
+            None => return Ok(PineRef::new(Series::from(None as Int))),
+        };
+        let count = match self.last_bar {
+            // The last bar is updated by the realtime data.
+            Some((last_time, _, count)) if last_time == time => count,
+            Some((last_time, last_day, count)) if last_day == day && last_time < time => count + 1,
+            _ => 1,
+        };
+        self.last_bar = Some((time, day, count));
+        Ok(PineRef::new(Series::from(Some(count))))
+    }
+
+    fn copy(&self) -> Box<dyn EvaluateVal<'a>> {
+        Box::new(self.clone())
+    }
+}
+
+// The count of bars from the start of the current trading session to the current bar.
+pub fn declare_sessbars_var<'a>() -> VarResult<'a> {
+    let value = PineRef::new(EvaluateFactory::new(|| {
+        Evaluate::new(Box::new(SessBarsVal::new()))
+    }));
+    let syntax_type = SyntaxType::Val(Box::new(SyntaxType::int_series()));
+    VarResult::new(value, syntax_type, "sessbars")
+}
+
 pub fn declare_year_var<'a>() -> VarResult<'a> {
     declare_time_var(
         "year",
@@ -664,4 +747,51 @@ mod tests {
             Some(PineRef::new_rc(Series::from_vec(vec![Some(4i64)])))
         );
     }
+
+    #[test]
+    fn sessbars_test() {
+        let lib_info = LibInfo::new(
+            vec![declare_sessbars_var()],
+            vec![("_time", SyntaxType::Series(SimpleSyntaxType::Int))],
+        );
+        let src = "m = sessbars";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+
+        // The session starts at 17:00 of the previous day in the exchange timezone.
+        let mut syminfo = get_syminfo(String::from("America/Chicago"));
+        syminfo.trade_start = String::from("17:00");
+        syminfo.trade_end = String::from("16:00");
+        let at = |d, h| {
+            Tz::America__Chicago
+                .ymd(2020, 1, d)
+                .and_hms(h, 0, 0)
+                .timestamp_millis()
+        };
+        let times = vec![
+            Some(at(20, 15)),
+            Some(at(20, 17)),
+            Some(at(20, 18)),
+            None,
+            Some(at(21, 9)),
+            Some(at(21, 9)),
+        ];
+        runner
+            .run(
+                &vec![("_time", AnySeries::from_int_vec(times))],
+                Some(Rc::new(syminfo)),
+            )
+            .unwrap();
+        assert_eq!(
+            runner.get_context().move_var(VarIndex::new(0, 0)),
+            Some(PineRef::new_rc(Series::from_vec(vec![
+                Some(1i64),
+                Some(1i64),
+                Some(2i64),
+                None,
+                Some(3i64),
+                Some(3i64)
+            ])))
+        );
+    }
 }
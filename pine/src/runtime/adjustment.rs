@@ -0,0 +1,184 @@
+// Back-adjust the raw bars of the adjusted tickers by the splits and dividends supplied by the
+// host, so the prices before the events are comparable with the latest prices.
+use super::data_src::AdjustmentEvent;
+use super::AnySeries;
+use crate::types::{Float, Int};
+use std::collections::HashMap;
+
+const PRICE_SRCS: [&str; 4] = ["open", "high", "low", "close"];
+
+// The (price, volume) factors of the bars. The factor of a bar is the product of the factors of
+// the events after it. The split of 4 for 1 divides the prices by 4 and multiplies the volumes
+// by 4. The dividend multiplies the prices by `1 - amount / close` where the close is the raw
+// close of the last bar before the ex-date.
+pub fn adjust_factors(
+    time: &[Int],
+    close: &[Float],
+    events: &[AdjustmentEvent],
+    dividends: bool,
+) -> Vec<(f64, f64)> {
+    let mut events: Vec<&AdjustmentEvent> = events
+        .iter()
+        .filter(|e| dividends || matches!(e, AdjustmentEvent::Split { .. }))
+        .collect();
+    events.sort_by_key(|e| match e {
+        AdjustmentEvent::Split { time, .. } | AdjustmentEvent::Dividend { time, .. } => *time,
+    });
+
+    let mut factors = vec![(1f64, 1f64); time.len()];
+    let (mut price, mut volume) = (1f64, 1f64);
+    for i in (0..time.len()).rev() {
+        if let Some(t) = time[i] {
+            while let Some(event) = events.last() {
+                match **event {
+                    AdjustmentEvent::Split {
+                        time,
+                        numerator,
+                        denominator,
+                    } if time > t => {
+                        if numerator > 0f64 && denominator > 0f64 {
+                            price *= denominator / numerator;
+                            volume *= numerator / denominator;
+                        }
+                    }
+                    AdjustmentEvent::Dividend { time, amount } if time > t => {
+                        if let Some(close) = close.get(i).cloned().flatten() {
+                            if close > amount {
+                                price *= 1f64 - amount / close;
+                            }
+                        }
+                    }
+                    _ => break,
+                }
+                events.pop();
+            }
+        }
+        factors[i] = (price, volume);
+    }
+    factors
+}
+
+// Adjust the price and volume series of the ticker in place. The bars are the bars of the time,
+// the bars out of the shorter series are na.
+pub fn adjust_bars(
+    srcs: &mut HashMap<&str, AnySeries>,
+    events: &[AdjustmentEvent],
+    dividends: bool,
+) {
+    let (time, close) = match srcs.get("_time") {
+        Some(time) => {
+            let len = time.len();
+            let close = srcs.get("close");
+            (
+                (0..len).map(|i| time.get::<Int>(i)).collect::<Vec<_>>(),
+                (0..len)
+                    .map(|i| close.and_then(|c| c.get::<Float>(i)))
+                    .collect::<Vec<_>>(),
+            )
+        }
+        None => return,
+    };
+    let factors = adjust_factors(&time, &close, events, dividends);
+    for src in PRICE_SRCS.iter() {
+        if let Some(series) = srcs.get_mut(src) {
+            let vals = factors
+                .iter()
+                .enumerate()
+                .map(|(i, (price, _))| series.get::<Float>(i).map(|v| v * price))
+                .collect();
+            *series = AnySeries::from_float_vec(vals);
+        }
+    }
+    if let Some(series) = srcs.get_mut("volume") {
+        let vals = factors
+            .iter()
+            .enumerate()
+            .map(|(i, (_, volume))| {
+                series
+                    .get::<Int>(i)
+                    .map(|v| (v as f64 * volume).round() as i64)
+            })
+            .collect();
+        *series = AnySeries::from_int_vec(vals);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(series: &AnySeries) -> Vec<Float> {
+        (0..series.len() as isize)
+            .map(|i| series.index(i))
+            .collect()
+    }
+
+    #[test]
+    fn adjust_test() {
+        let events = vec![
+            AdjustmentEvent::Dividend {
+                time: 2,
+                amount: 10f64,
+            },
+            AdjustmentEvent::Split {
+                time: 3,
+                numerator: 2f64,
+                denominator: 1f64,
+            },
+        ];
+        let mut srcs = HashMap::new();
+        srcs.insert(
+            "_time",
+            AnySeries::from_int_vec(vec![Some(0), Some(1), Some(2), Some(3)]),
+        );
+        srcs.insert(
+            "close",
+            AnySeries::from_float_vec(vec![Some(20f64), Some(20f64), Some(10f64), Some(5f64)]),
+        );
+        srcs.insert(
+            "volume",
+            AnySeries::from_int_vec(vec![Some(10), Some(10), Some(10), Some(20)]),
+        );
+        let mut splits = srcs.clone();
+
+        // The dividend is half of the close before the ex-date.
+        adjust_bars(&mut srcs, &events, true);
+        assert_eq!(floats(&srcs["close"]), vec![Some(5f64); 4]);
+        let volume: Vec<Int> = (0..4).map(|i| srcs["volume"].index(i)).collect();
+        assert_eq!(volume, vec![Some(20); 4]);
+
+        adjust_bars(&mut splits, &events, false);
+        assert_eq!(
+            floats(&splits["close"]),
+            vec![Some(10f64), Some(10f64), Some(5f64), Some(5f64)]
+        );
+    }
+
+    #[test]
+    fn adjust_short_series_test() {
+        let events = vec![AdjustmentEvent::Split {
+            time: 3,
+            numerator: 2f64,
+            denominator: 1f64,
+        }];
+        let mut srcs = HashMap::new();
+        srcs.insert(
+            "_time",
+            AnySeries::from_int_vec(vec![Some(0), Some(1), Some(2), Some(3)]),
+        );
+        srcs.insert(
+            "close",
+            AnySeries::from_float_vec(vec![Some(20f64), Some(20f64)]),
+        );
+        srcs.insert("volume", AnySeries::from_int_vec(vec![Some(10)]));
+
+        // The bars out of the shorter series are na.
+        adjust_bars(&mut srcs, &events, false);
+        assert_eq!(
+            floats(&srcs["close"]),
+            vec![Some(10f64), Some(10f64), None, None]
+        );
+        let volume: Vec<Int> = (0..4).map(|i| srcs["volume"].index(i)).collect();
+        assert_eq!(volume, vec![Some(20), None, None, None]);
+    }
+}
@@ -17,6 +17,10 @@ pub struct AnySeries {
     series_type: AnySeriesType,
 }
 
+// The series owns its buffer like the vector it is created from, so it can be moved to the
+// jobs of `runtime::parallel::eval_jobs`.
+unsafe impl Send for AnySeries {}
+
 impl AnySeries {
     pub fn from_int_vec(mut v: Vec<Int>) -> AnySeries {
         v.shrink_to_fit();
@@ -65,6 +69,15 @@ impl AnySeries {
         }
     }
 
+    // Get the value at the index like `index`, the index out of the series gets the default value(na).
+    pub fn get<T: Default>(&self, i: usize) -> T {
+        if i < self.len {
+            self.index(i as isize)
+        } else {
+            T::default()
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
@@ -0,0 +1,205 @@
+// Build the higher timeframe bars from the base resolution bars, so `security` can request the
+// higher timeframes of the symbols whose bars are only provided in one resolution by the host.
+use super::bar_transform::{OhlcvBars, BAR_SRCS};
+use super::AnySeries;
+use crate::helper::Resolution;
+use crate::types::{Float, Int};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+fn merge_max(a: Float, b: Float) -> Float {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn merge_min(a: Float, b: Float) -> Float {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn merge_sum(a: Int, b: Int) -> Int {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+// Merge the consecutive base bars in the same period into one bar.
+//
+// The time of the merged bar is the time of its last base bar instead of the period start,
+// so the `security` call without lookahead only gets the bars that have been completed at the
+// chart bar time. The last merged bar may be partial and contains the base bars so far.
+pub fn aggregate_bars(bars: &OhlcvBars, resolution: &Resolution, tz: &Tz) -> OhlcvBars {
+    let mut res = OhlcvBars::default();
+    let mut last_period: Option<i64> = None;
+    for i in 0..bars.len() {
+        let time = match bars.time[i] {
+            Some(t) => t,
+            None => continue,
+        };
+        let period = resolution.get_restime(time, tz);
+        if last_period == Some(period) {
+            let last = res.len() - 1;
+            res.time[last] = Some(time);
+            res.open[last] = res.open[last].or(bars.open[i]);
+            res.high[last] = merge_max(res.high[last], bars.high[i]);
+            res.low[last] = merge_min(res.low[last], bars.low[i]);
+            res.close[last] = bars.close[i].or(res.close[last]);
+            res.volume[last] = merge_sum(res.volume[last], bars.volume[i]);
+        } else {
+            last_period = Some(period);
+            res.time.push(Some(time));
+            res.open.push(bars.open[i]);
+            res.high.push(bars.high[i]);
+            res.low.push(bars.low[i]);
+            res.close.push(bars.close[i]);
+            res.volume.push(bars.volume[i]);
+        }
+    }
+    res
+}
+
+fn get_bars(input_data: &HashMap<String, AnySeries>, prefix: &str) -> Option<OhlcvBars> {
+    let time = input_data.get(&format!("{}_time", prefix))?;
+    let len = time.len();
+    let get_float_vec = |src: &str| match input_data.get(&format!("{}{}", prefix, src)) {
+        Some(s) => (0..len as isize).map(|i| s.index::<Float>(i)).collect(),
+        None => vec![None; len],
+    };
+    let get_int_vec = |src: &str| match input_data.get(&format!("{}{}", prefix, src)) {
+        Some(s) => (0..len as isize).map(|i| s.index::<Int>(i)).collect(),
+        None => vec![None; len],
+    };
+    Some(OhlcvBars {
+        time: get_int_vec("_time"),
+        open: get_float_vec("open"),
+        high: get_float_vec("high"),
+        low: get_float_vec("low"),
+        close: get_float_vec("close"),
+        volume: get_int_vec("volume"),
+    })
+}
+
+// Aggregate the bars of the ticker `<symbol>-<resolution>` requested by `security`. The base
+// bars are the series `<symbol>-<res>-<src>` of another resolution provided by the host, or the
+// chart series if the symbol is the chart symbol.
+pub fn aggregate_input_data(
+    input_data: &HashMap<String, AnySeries>,
+    ticker: &str,
+    is_chart_symbol: bool,
+    tz: &Tz,
+) -> Vec<(String, AnySeries)> {
+    let (symbol, resolution) = match ticker.rfind('-') {
+        Some(i) => (&ticker[..i], &ticker[i + 1..]),
+        None => return vec![],
+    };
+    let resolution = match Resolution::parse(resolution) {
+        Ok(r) => r,
+        Err(_) => return vec![],
+    };
+
+    // Choose the base ticker in the name order, so the result does not depend on the hash order.
+    let time_suffix = "-_time";
+    let mut base_tickers: Vec<&str> = input_data
+        .keys()
+        .filter_map(|name| match name.strip_suffix(time_suffix) {
+            Some(t) if t != ticker && matches!(t.rfind('-'), Some(i) if &t[..i] == symbol) => {
+                Some(t)
+            }
+            _ => None,
+        })
+        .collect();
+    base_tickers.sort_unstable();
+    let bars = match base_tickers.first() {
+        Some(base) => get_bars(input_data, &format!("{}-", base)),
+        None if is_chart_symbol => get_bars(input_data, ""),
+        None => None,
+    };
+    let bars = match bars {
+        Some(bars) => aggregate_bars(&bars, &resolution, tz),
+        None => return vec![],
+    };
+
+    let OhlcvBars {
+        time,
+        open,
+        high,
+        low,
+        close,
+        volume,
+    } = bars;
+    BAR_SRCS
+        .iter()
+        .zip(vec![
+            AnySeries::from_int_vec(time),
+            AnySeries::from_float_vec(open),
+            AnySeries::from_float_vec(high),
+            AnySeries::from_float_vec(low),
+            AnySeries::from_float_vec(close),
+            AnySeries::from_int_vec(volume),
+        ])
+        .map(|(src, series)| (format!("{}-{}", ticker, src), series))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_bars_test() {
+        let minute = |m: i64| Some(m * 60000);
+        let bars = OhlcvBars {
+            time: vec![minute(0), minute(1), minute(2), minute(3), minute(4)],
+            open: vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64), Some(5f64)],
+            high: vec![Some(3f64), Some(6f64), Some(4f64), Some(5f64), Some(6f64)],
+            low: vec![Some(1f64), Some(0f64), Some(2f64), Some(3f64), Some(4f64)],
+            close: vec![Some(2f64), Some(3f64), Some(4f64), Some(5f64), None],
+            volume: vec![Some(1), Some(2), Some(3), None, Some(5)],
+        };
+        let res = aggregate_bars(&bars, &Resolution::parse("2").unwrap(), &Tz::UTC);
+        assert_eq!(res.time, vec![minute(1), minute(3), minute(4)]);
+        assert_eq!(res.open, vec![Some(1f64), Some(3f64), Some(5f64)]);
+        assert_eq!(res.high, vec![Some(6f64), Some(5f64), Some(6f64)]);
+        assert_eq!(res.low, vec![Some(0f64), Some(2f64), Some(4f64)]);
+        assert_eq!(res.close, vec![Some(3f64), Some(5f64), None]);
+        assert_eq!(res.volume, vec![Some(3), Some(3), Some(5)]);
+    }
+
+    #[test]
+    fn aggregate_input_data_test() {
+        let mut input_data = HashMap::new();
+        input_data.insert(
+            String::from("_time"),
+            AnySeries::from_int_vec(vec![Some(0), Some(60000)]),
+        );
+        input_data.insert(
+            String::from("close"),
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64)]),
+        );
+        input_data.insert(
+            String::from("MSFT-1-_time"),
+            AnySeries::from_int_vec(vec![Some(0), Some(60000), Some(2 * 60000)]),
+        );
+        input_data.insert(
+            String::from("MSFT-1-close"),
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        );
+
+        let res = aggregate_input_data(&input_data, "MSFT-2", false, &Tz::UTC);
+        assert_eq!(res.len(), BAR_SRCS.len());
+        assert_eq!(res[0].0, "MSFT-2-_time");
+        assert_eq!(res[4].0, "MSFT-2-close");
+        assert_eq!(*res[4].1.as_vec::<Float>(), vec![Some(2f64), Some(3f64)]);
+
+        // The chart series are only used for the chart symbol.
+        assert!(aggregate_input_data(&input_data, "AAPL-2", false, &Tz::UTC).is_empty());
+        let res = aggregate_input_data(&input_data, "AAPL-2", true, &Tz::UTC);
+        assert_eq!(res[4].0, "AAPL-2-close");
+        assert_eq!(*res[4].1.as_vec::<Float>(), vec![Some(2f64)]);
+    }
+}
@@ -0,0 +1,164 @@
+// Derive the close times and the trading days of the chart bars from their open times for
+// `time_close` and `time_tradingday`, when the host does not provide them.
+use super::output::SymbolInfo;
+use super::AnySeries;
+use crate::helper::TradeTimeSpan;
+use crate::libs::year::MyTz;
+use crate::types::Int;
+use chrono::{Local, TimeZone};
+
+// The duration of the chart bars. It is the smallest gap between the consecutive bars, because
+// the session breaks, weekends and holidays only make the gaps longer.
+pub fn infer_bar_interval(times: &AnySeries) -> Option<i64> {
+    let mut interval: Option<i64> = None;
+    let mut prev: Int = None;
+    for i in 0..times.len() {
+        let time: Int = times.index(i as isize);
+        if let (Some(p), Some(t)) = (prev, time) {
+            if t > p && interval.is_none_or(|v| t - p < v) {
+                interval = Some(t - p);
+            }
+        }
+        if time.is_some() {
+            prev = time;
+        }
+    }
+    interval
+}
+
+// The bar closes when the next bar opens unless there is a break between them, then it closes one
+// interval after its open. The gap up to 1.5 intervals is not a break, e.g. the monthly bars have
+// different durations.
+pub fn bar_close_time(time: Int, next_time: Int, interval: Option<i64>) -> Int {
+    let time = time?;
+    match (next_time, interval) {
+        (Some(next), Some(interval)) if next > time && next - time <= interval + interval / 2 => {
+            Some(next)
+        }
+        (Some(next), None) if next > time => Some(next),
+        (_, Some(interval)) => Some(time + interval),
+        _ => None,
+    }
+}
+
+// The exchange timezone of the symbol, it is the local timezone if the symbol is unknown.
+pub fn parse_tz(syminfo: Option<&SymbolInfo>) -> MyTz {
+    match syminfo.map(|s| s.timezone.parse()) {
+        Some(Ok(tz)) => MyTz::Tz(tz),
+        _ => MyTz::Local,
+    }
+}
+
+// The minutes of the day when the trading session starts, it is negative if the session starts on
+// the previous day, e.g. 17:00-16:00. It is 0 if the trade time of the symbol is unknown.
+pub fn session_start_minutes(syminfo: Option<&SymbolInfo>) -> i32 {
+    syminfo
+        .and_then(|s| TradeTimeSpan::try_parse_str(&s.trade_start, &s.trade_end))
+        .map_or(0, |span| span.start.to_m())
+}
+
+// The trading day of the bar as the 00:00 UTC time of its date like Pine. The bars after the
+// start of the overnight session belong to the next day, the other bars belong to their
+// calendar day in the exchange timezone.
+pub fn trading_day(time: i64, tz: &MyTz, start_m: i32) -> i64 {
+    let millis = time - start_m.min(0) as i64 * 60_000;
+    let date = match tz {
+        MyTz::Tz(tz) => tz.timestamp_millis_opt(millis).unwrap().date_naive(),
+        MyTz::Local => Local.timestamp_millis_opt(millis).unwrap().date_naive(),
+    };
+    date.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Tz;
+
+    const MINUTE: i64 = 60_000;
+
+    #[test]
+    fn close_time_test() {
+        let times = AnySeries::from_int_vec(vec![
+            Some(0),
+            Some(5 * MINUTE),
+            None,
+            Some(15 * MINUTE),
+            Some(100 * MINUTE),
+        ]);
+        let interval = infer_bar_interval(&times);
+        assert_eq!(interval, Some(5 * MINUTE));
+        assert_eq!(
+            infer_bar_interval(&AnySeries::from_int_vec(vec![Some(0)])),
+            None
+        );
+
+        // The next bar, the break and the last bar.
+        assert_eq!(
+            bar_close_time(Some(0), Some(5 * MINUTE), interval),
+            Some(5 * MINUTE)
+        );
+        assert_eq!(
+            bar_close_time(Some(15 * MINUTE), Some(100 * MINUTE), interval),
+            Some(20 * MINUTE)
+        );
+        assert_eq!(
+            bar_close_time(Some(100 * MINUTE), None, interval),
+            Some(105 * MINUTE)
+        );
+        assert_eq!(bar_close_time(Some(0), None, None), None);
+        assert_eq!(bar_close_time(None, Some(5 * MINUTE), interval), None);
+
+        // The monthly bars of 31 days after the shortest month of 28 days.
+        let day = 24 * 60 * MINUTE;
+        assert_eq!(
+            bar_close_time(Some(0), Some(31 * day), Some(28 * day)),
+            Some(31 * day)
+        );
+    }
+
+    #[test]
+    fn trading_day_test() {
+        let at = |d, h| {
+            Tz::America__Chicago
+                .with_ymd_and_hms(2020, 1, d, h, 0, 0)
+                .unwrap()
+                .timestamp_millis()
+        };
+        let utc_day = |d| {
+            Tz::UTC
+                .with_ymd_and_hms(2020, 1, d, 0, 0, 0)
+                .unwrap()
+                .timestamp_millis()
+        };
+
+        let mut syminfo = SymbolInfo {
+            symbol_type: String::from(""),
+            timezone: String::from("America/Chicago"),
+            ticker: String::from(""),
+            session: String::from(""),
+            trade_start: String::from("17:00"),
+            trade_end: String::from("16:00"),
+            root: None,
+            currency: String::from(""),
+            description: String::from(""),
+            mintick: 0.01,
+        };
+        let tz = parse_tz(Some(&syminfo));
+        assert_eq!(tz, MyTz::Tz(Tz::America__Chicago));
+        let start_m = session_start_minutes(Some(&syminfo));
+        assert_eq!(start_m, -7 * 60);
+        assert_eq!(trading_day(at(20, 15), &tz, start_m), utc_day(20));
+        assert_eq!(trading_day(at(20, 17), &tz, start_m), utc_day(21));
+        assert_eq!(trading_day(at(20, 23), &tz, start_m), utc_day(21));
+
+        // The regular session and the premarket bars belong to the calendar day.
+        syminfo.trade_start = String::from("9:30");
+        let start_m = session_start_minutes(Some(&syminfo));
+        assert_eq!(trading_day(at(20, 4), &tz, start_m), utc_day(20));
+        assert_eq!(trading_day(at(20, 23), &tz, start_m), utc_day(20));
+        assert_eq!(session_start_minutes(None), 0);
+    }
+}
@@ -0,0 +1,649 @@
+// Convert the host-provided OHLCV bars into the non-standard chart bars(heikin-ashi, renko, kagi
+// and line-break) for the transformed tickers created by `ticker.heikinashi`, `ticker.renko`, etc.
+// The bars of the tickers with the regular session are also filtered and the adjusted tickers
+// are back-adjusted here.
+use super::adjustment::adjust_bars;
+use super::data_src::{AdjustmentEvent, DataRequest};
+use super::output::SymbolInfo;
+use super::parallel::eval_jobs;
+use super::{AnySeries, AnySeriesType};
+use crate::helper::TradeTimeSpan;
+use crate::libs::ticker::{
+    BarTransform, TickerId, ADJUSTMENT_DIVIDENDS, ADJUSTMENT_SPLITS, SESSION_REGULAR,
+};
+use crate::types::{Float, Int};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+pub const BAR_SRCS: [&str; 6] = ["_time", "open", "high", "low", "close", "volume"];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OhlcvBars {
+    pub time: Vec<Int>,
+    pub open: Vec<Float>,
+    pub high: Vec<Float>,
+    pub low: Vec<Float>,
+    pub close: Vec<Float>,
+    pub volume: Vec<Int>,
+}
+
+impl OhlcvBars {
+    pub fn len(&self) -> usize {
+        self.time.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.time.is_empty()
+    }
+
+    fn push(&mut self, time: Int, open: f64, close: f64, volume: Int) {
+        self.time.push(time);
+        self.open.push(Some(open));
+        self.high.push(Some(open.max(close)));
+        self.low.push(Some(open.min(close)));
+        self.close.push(Some(close));
+        self.volume.push(volume);
+    }
+}
+
+fn heikinashi(bars: &OhlcvBars) -> OhlcvBars {
+    let mut res = OhlcvBars::default();
+    let mut prev: Option<(f64, f64)> = None;
+    for i in 0..bars.len() {
+        match (bars.open[i], bars.high[i], bars.low[i], bars.close[i]) {
+            (Some(o), Some(h), Some(l), Some(c)) => {
+                let ha_close = (o + h + l + c) / 4f64;
+                let ha_open = match prev {
+                    Some((po, pc)) => (po + pc) / 2f64,
+                    None => (o + c) / 2f64,
+                };
+                prev = Some((ha_open, ha_close));
+                res.time.push(bars.time[i]);
+                res.open.push(Some(ha_open));
+                res.high.push(Some(h.max(ha_open).max(ha_close)));
+                res.low.push(Some(l.min(ha_open).min(ha_close)));
+                res.close.push(Some(ha_close));
+                res.volume.push(bars.volume[i]);
+            }
+            _ => {
+                res.time.push(bars.time[i]);
+                res.open.push(None);
+                res.high.push(None);
+                res.low.push(None);
+                res.close.push(None);
+                res.volume.push(bars.volume[i]);
+            }
+        }
+    }
+    res
+}
+
+// The average true range of the last `length` bars, it's used as the renko box size.
+fn last_atr(bars: &OhlcvBars, length: usize) -> Option<f64> {
+    let mut atr: Option<f64> = None;
+    let mut prev_close: Option<f64> = None;
+    for i in 0..bars.len() {
+        if let (Some(h), Some(l)) = (bars.high[i], bars.low[i]) {
+            let tr = match prev_close {
+                Some(pc) => (h - l).max((h - pc).abs()).max((l - pc).abs()),
+                None => h - l,
+            };
+            atr = Some(match atr {
+                Some(v) => (v * (length as f64 - 1f64) + tr) / length as f64,
+                None => tr,
+            });
+        }
+        if bars.close[i].is_some() {
+            prev_close = bars.close[i];
+        }
+    }
+    atr
+}
+
+fn renko(bars: &OhlcvBars, style: &str, param: f64) -> OhlcvBars {
+    let mut res = OhlcvBars::default();
+    let box_size = match style {
+        "ATR" => last_atr(bars, param.max(1f64) as usize),
+        _ => Some(param),
+    };
+    let box_size = match box_size {
+        Some(v) if v > 0f64 => v,
+        _ => return res,
+    };
+
+    // The top and bottom of the last brick.
+    let mut range: Option<(f64, f64)> = None;
+    for i in 0..bars.len() {
+        let c = match bars.close[i] {
+            Some(c) => c,
+            None => continue,
+        };
+        let (mut top, mut bottom) = match range {
+            Some(r) => r,
+            None => {
+                range = Some((c, c));
+                continue;
+            }
+        };
+        let mut volume = bars.volume[i];
+        while c >= top + box_size {
+            res.push(bars.time[i], top, top + box_size, volume.take());
+            bottom = top;
+            top += box_size;
+        }
+        while c <= bottom - box_size {
+            res.push(bars.time[i], bottom, bottom - box_size, volume.take());
+            top = bottom;
+            bottom -= box_size;
+        }
+        range = Some((top, bottom));
+    }
+    res
+}
+
+fn kagi(bars: &OhlcvBars, reversal: f64) -> OhlcvBars {
+    let mut res = OhlcvBars::default();
+    // The start price, the extreme price and the direction of the current line.
+    let mut line: Option<(f64, f64, i32)> = None;
+    let mut last_time = None;
+    let mut volume = 0i64;
+    for i in 0..bars.len() {
+        let c = match bars.close[i] {
+            Some(c) => c,
+            None => continue,
+        };
+        last_time = bars.time[i];
+        volume += bars.volume[i].unwrap_or(0);
+        let (start, extreme, dir) = match line {
+            Some(l) => l,
+            None => {
+                line = Some((c, c, 0));
+                continue;
+            }
+        };
+        line = Some(match dir {
+            0 if (c - start).abs() >= reversal => (start, c, if c > start { 1 } else { -1 }),
+            0 => (start, extreme, 0),
+            1 if c > extreme => (start, c, 1),
+            -1 if c < extreme => (start, c, -1),
+            _ if (c - extreme).abs() >= reversal => {
+                res.push(bars.time[i], start, extreme, Some(volume));
+                volume = 0;
+                (extreme, c, -dir)
+            }
+            _ => (start, extreme, dir),
+        });
+    }
+    if let Some((start, extreme, dir)) = line {
+        if dir != 0 {
+            res.push(last_time, start, extreme, Some(volume));
+        }
+    }
+    res
+}
+
+fn linebreak(bars: &OhlcvBars, number_of_lines: i64) -> OhlcvBars {
+    let mut res = OhlcvBars::default();
+    let mut base: Option<f64> = None;
+    // The (open, close) of the lines.
+    let mut lines: Vec<(f64, f64)> = vec![];
+    for i in 0..bars.len() {
+        let c = match bars.close[i] {
+            Some(c) => c,
+            None => continue,
+        };
+        let new_line = match (lines.last(), base) {
+            (None, None) => {
+                base = Some(c);
+                None
+            }
+            (None, Some(b)) if c != b => Some((b, c)),
+            (None, Some(_)) => None,
+            (Some(&(o, lc)), _) => {
+                let recent = &lines[lines.len().saturating_sub(number_of_lines as usize)..];
+                let up = lc > o;
+                let highest = recent.iter().map(|l| l.0.max(l.1)).fold(f64::MIN, f64::max);
+                let lowest = recent.iter().map(|l| l.0.min(l.1)).fold(f64::MAX, f64::min);
+                if (up && c > lc) || (!up && c < lc) {
+                    Some((lc, c))
+                } else if (up && c < lowest) || (!up && c > highest) {
+                    Some((o, c))
+                } else {
+                    None
+                }
+            }
+        };
+        if let Some((o, lc)) = new_line {
+            lines.push((o, lc));
+            res.push(bars.time[i], o, lc, bars.volume[i]);
+        }
+    }
+    res
+}
+
+pub fn transform_bars(bars: &OhlcvBars, transform: &BarTransform) -> OhlcvBars {
+    match transform {
+        BarTransform::HeikinAshi => heikinashi(bars),
+        BarTransform::Renko(style, param) => renko(bars, style, *param),
+        BarTransform::Kagi(reversal) => kagi(bars, *reversal),
+        BarTransform::LineBreak(lines) => linebreak(bars, *lines),
+    }
+}
+
+fn get_float_vec(data: &HashMap<&str, AnySeries>, name: &str, len: usize) -> Vec<Float> {
+    match data.get(name) {
+        Some(s) => (0..len).map(|i| s.get::<Float>(i)).collect(),
+        None => vec![None; len],
+    }
+}
+
+fn get_int_vec(data: &HashMap<&str, AnySeries>, name: &str, len: usize) -> Vec<Int> {
+    match data.get(name) {
+        Some(s) => (0..len).map(|i| s.get::<Int>(i)).collect(),
+        None => vec![None; len],
+    }
+}
+
+// The regular trade time of the chart symbol in its timezone, None if it is unknown.
+pub fn regular_session(syminfo: Option<&SymbolInfo>) -> Option<(TradeTimeSpan, Tz)> {
+    let info = syminfo?;
+    let span = TradeTimeSpan::try_parse_str(&info.trade_start, &info.trade_end)?;
+    Some((span, info.timezone.parse().ok()?))
+}
+
+// Keep the bars of the series by the flags of the time, the bars out of the series are na.
+fn filter_series(series: &AnySeries, keep: &[bool]) -> AnySeries {
+    let indexes = keep.iter().enumerate().filter(|(_, k)| **k).map(|(i, _)| i);
+    match series.get_type() {
+        AnySeriesType::Int => {
+            AnySeries::from_int_vec(indexes.map(|i| series.get::<Int>(i)).collect())
+        }
+        AnySeriesType::Float => {
+            AnySeries::from_float_vec(indexes.map(|i| series.get::<Float>(i)).collect())
+        }
+    }
+}
+
+// Transform the input data of the transformed tickers. The host provides the origin bars of
+// the ticker under the names `<ticker>-<src>`, e.g. `NASDAQ:AAPL;transform=heikinashi-1D-close`,
+// and these series are replaced by the transformed series. The bars of the tickers with
+// `session=regular` outside the regular session of the chart symbol are removed before the
+// transformation, so the host can provide the extended bars for both sessions. The raw bars of
+// the tickers with `adjustment=splits` or `adjustment=dividends` are back-adjusted by the events
+// supplied by the host.
+pub fn transform_input_data(
+    data: &[(&str, AnySeries)],
+    session: Option<&(TradeTimeSpan, Tz)>,
+    request: Option<&dyn DataRequest>,
+) -> Vec<(String, AnySeries)> {
+    // Group the series by the ticker.
+    let mut tickers: HashMap<&str, HashMap<&str, &AnySeries>> = HashMap::new();
+    for (name, series) in data.iter() {
+        if let Some(src) = BAR_SRCS
+            .iter()
+            .find(|src| name.ends_with(&format!("-{}", src)))
+        {
+            let ticker = &name[..name.len() - src.len() - 1];
+            tickers.entry(ticker).or_default().insert(src, series);
+        }
+    }
+
+    // The adjustment events are requested from the host first, then the series of the tickers
+    // are converted by `eval_jobs`, concurrently with the `parallel` feature.
+    let mut jobs = vec![];
+    for (ticker, srcs) in tickers.into_iter() {
+        // The ticker is formatted as `<symbol>-<resolution>` by security.
+        let symbol = match ticker.rfind('-') {
+            Some(i) => &ticker[..i],
+            None => ticker,
+        };
+        let ticker_id = TickerId::parse(symbol);
+        let regular = match (ticker_id.session.as_deref(), session) {
+            (Some(SESSION_REGULAR), Some(session)) => Some(session),
+            _ => None,
+        };
+        let events = match (ticker_id.adjustment.as_deref(), request) {
+            (Some(ADJUSTMENT_SPLITS), Some(request))
+            | (Some(ADJUSTMENT_DIVIDENDS), Some(request)) => request.adjustments(&ticker_id.symbol),
+            _ => vec![],
+        };
+        if !srcs.contains_key("_time")
+            || (regular.is_none() && events.is_empty() && ticker_id.transform.is_none())
+        {
+            continue;
+        }
+
+        let srcs: HashMap<&str, AnySeries> = srcs
+            .into_iter()
+            .map(|(src, series)| (src, series.clone()))
+            .collect();
+        jobs.push(move || transform_ticker(ticker, srcs, regular, &events, ticker_id));
+    }
+    eval_jobs(jobs).into_iter().flatten().collect()
+}
+
+fn transform_ticker(
+    ticker: &str,
+    mut srcs: HashMap<&str, AnySeries>,
+    regular: Option<&(TradeTimeSpan, Tz)>,
+    events: &[AdjustmentEvent],
+    ticker_id: TickerId,
+) -> Vec<(String, AnySeries)> {
+    let mut res = vec![];
+    if let Some((span, tz)) = regular {
+        let time_series = match srcs.get("_time") {
+            Some(time) => time,
+            None => return res,
+        };
+        let keep: Vec<bool> = (0..time_series.len())
+            .map(|i| time_series.get::<Int>(i).is_some_and(|t| span.is_in(t, tz)))
+            .collect();
+        for series in srcs.values_mut() {
+            *series = filter_series(series, &keep);
+        }
+    }
+    if !events.is_empty() {
+        let dividends = ticker_id.adjustment.as_deref() == Some(ADJUSTMENT_DIVIDENDS);
+        adjust_bars(&mut srcs, events, dividends);
+    }
+    let transform = match ticker_id.transform {
+        Some(t) => t,
+        None => {
+            for (src, series) in srcs.into_iter() {
+                res.push((format!("{}-{}", ticker, src), series));
+            }
+            return res;
+        }
+    };
+    let len = match srcs.get("_time") {
+        Some(time) => time.len(),
+        None => return res,
+    };
+    let bars = OhlcvBars {
+        time: get_int_vec(&srcs, "_time", len),
+        open: get_float_vec(&srcs, "open", len),
+        high: get_float_vec(&srcs, "high", len),
+        low: get_float_vec(&srcs, "low", len),
+        close: get_float_vec(&srcs, "close", len),
+        volume: get_int_vec(&srcs, "volume", len),
+    };
+    let bars = transform_bars(&bars, &transform);
+    res.push((
+        format!("{}-_time", ticker),
+        AnySeries::from_int_vec(bars.time),
+    ));
+    res.push((
+        format!("{}-open", ticker),
+        AnySeries::from_float_vec(bars.open),
+    ));
+    res.push((
+        format!("{}-high", ticker),
+        AnySeries::from_float_vec(bars.high),
+    ));
+    res.push((
+        format!("{}-low", ticker),
+        AnySeries::from_float_vec(bars.low),
+    ));
+    res.push((
+        format!("{}-close", ticker),
+        AnySeries::from_float_vec(bars.close),
+    ));
+    res.push((
+        format!("{}-volume", ticker),
+        AnySeries::from_int_vec(bars.volume),
+    ));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::data_src::{AdjustmentEvent, RequestData, RequestDesc};
+
+    fn gen_bars(closes: Vec<f64>) -> OhlcvBars {
+        let len = closes.len();
+        OhlcvBars {
+            time: (0..len as i64).map(Some).collect(),
+            open: closes.iter().map(|&c| Some(c)).collect(),
+            high: closes.iter().map(|&c| Some(c + 1f64)).collect(),
+            low: closes.iter().map(|&c| Some(c - 1f64)).collect(),
+            close: closes.iter().map(|&c| Some(c)).collect(),
+            volume: vec![Some(1); len],
+        }
+    }
+
+    #[test]
+    fn heikinashi_test() {
+        let bars = OhlcvBars {
+            time: vec![Some(1), Some(2)],
+            open: vec![Some(10f64), Some(12f64)],
+            high: vec![Some(14f64), Some(15f64)],
+            low: vec![Some(8f64), Some(11f64)],
+            close: vec![Some(12f64), Some(14f64)],
+            volume: vec![Some(1), Some(2)],
+        };
+        let res = transform_bars(&bars, &BarTransform::HeikinAshi);
+        assert_eq!(res.close, vec![Some(11f64), Some(13f64)]);
+        assert_eq!(res.open, vec![Some(11f64), Some(11f64)]);
+        assert_eq!(res.high, vec![Some(14f64), Some(15f64)]);
+        assert_eq!(res.low, vec![Some(8f64), Some(11f64)]);
+        assert_eq!(res.time, bars.time);
+    }
+
+    #[test]
+    fn renko_test() {
+        let bars = gen_bars(vec![10f64, 12.5f64, 13f64, 11f64, 9f64]);
+        let res = transform_bars(
+            &bars,
+            &BarTransform::Renko(String::from("Traditional"), 1f64),
+        );
+        assert_eq!(
+            res.time,
+            vec![Some(1), Some(1), Some(2), Some(3), Some(4), Some(4)]
+        );
+        assert_eq!(
+            res.open,
+            vec![
+                Some(10f64),
+                Some(11f64),
+                Some(12f64),
+                Some(12f64),
+                Some(11f64),
+                Some(10f64)
+            ]
+        );
+        assert_eq!(
+            res.close,
+            vec![
+                Some(11f64),
+                Some(12f64),
+                Some(13f64),
+                Some(11f64),
+                Some(10f64),
+                Some(9f64)
+            ]
+        );
+        assert_eq!(
+            res.volume,
+            vec![Some(1), None, Some(1), Some(1), Some(1), None]
+        );
+    }
+
+    #[test]
+    fn kagi_test() {
+        let bars = gen_bars(vec![10f64, 11f64, 13f64, 12.5f64, 11f64, 12f64]);
+        let res = transform_bars(&bars, &BarTransform::Kagi(2f64));
+        assert_eq!(res.open, vec![Some(10f64), Some(13f64)]);
+        assert_eq!(res.close, vec![Some(13f64), Some(11f64)]);
+        assert_eq!(res.time, vec![Some(4), Some(5)]);
+    }
+
+    #[test]
+    fn linebreak_test() {
+        let bars = gen_bars(vec![10f64, 11f64, 12f64, 11.5f64, 13f64, 9f64]);
+        let res = transform_bars(&bars, &BarTransform::LineBreak(3));
+        assert_eq!(
+            res.open,
+            vec![Some(10f64), Some(11f64), Some(12f64), Some(12f64)]
+        );
+        assert_eq!(
+            res.close,
+            vec![Some(11f64), Some(12f64), Some(13f64), Some(9f64)]
+        );
+        assert_eq!(res.time, vec![Some(1), Some(2), Some(4), Some(5)]);
+    }
+
+    #[test]
+    fn transform_input_data_test() {
+        let data = vec![
+            ("close", AnySeries::from_float_vec(vec![Some(1f64)])),
+            (
+                "MSFT;transform=heikinashi-1D-_time",
+                AnySeries::from_int_vec(vec![Some(1)]),
+            ),
+            (
+                "MSFT;transform=heikinashi-1D-open",
+                AnySeries::from_float_vec(vec![Some(10f64)]),
+            ),
+            (
+                "MSFT;transform=heikinashi-1D-close",
+                AnySeries::from_float_vec(vec![Some(12f64)]),
+            ),
+            (
+                "MSFT-1D-close",
+                AnySeries::from_float_vec(vec![Some(12f64)]),
+            ),
+        ];
+        let res = transform_input_data(&data, None, None);
+        assert_eq!(res.len(), 6);
+        let close = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;transform=heikinashi-1D-close")
+            .unwrap();
+        // The high and low are na, so the close is na.
+        assert_eq!(close.1.index::<Float>(0), None);
+        let time = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;transform=heikinashi-1D-_time")
+            .unwrap();
+        assert_eq!(time.1.index::<Int>(0), Some(1));
+    }
+
+    #[test]
+    fn transform_short_series_test() {
+        // The series shorter than the time are padded with na, the ticker without the time
+        // is not transformed.
+        let names: Vec<String> = ["open", "high", "low", "close"]
+            .iter()
+            .map(|src| format!("MSFT;transform=heikinashi-1D-{}", src))
+            .collect();
+        let mut data = vec![(
+            "MSFT;transform=heikinashi-1D-_time",
+            AnySeries::from_int_vec(vec![Some(1), Some(2)]),
+        )];
+        for name in names.iter() {
+            data.push((name, AnySeries::from_float_vec(vec![Some(10f64)])));
+        }
+        data.push((
+            "AAPL;transform=heikinashi-1D-close",
+            AnySeries::from_float_vec(vec![Some(10f64)]),
+        ));
+        let res = transform_input_data(&data, None, None);
+        assert_eq!(res.len(), 6);
+        let close = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;transform=heikinashi-1D-close")
+            .unwrap();
+        assert_eq!(close.1.len(), 2);
+        assert_eq!(close.1.index::<Float>(0), Some(10f64));
+        assert_eq!(close.1.index::<Float>(1), None);
+    }
+
+    #[test]
+    fn regular_session_test() {
+        let hour = 3_600_000i64;
+        let data = vec![
+            (
+                "MSFT;session=regular-_time",
+                AnySeries::from_int_vec(vec![Some(8 * hour), Some(10 * hour), Some(17 * hour)]),
+            ),
+            (
+                "MSFT;session=regular-close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+            ),
+            (
+                "MSFT;session=regular-open",
+                AnySeries::from_float_vec(vec![Some(1f64)]),
+            ),
+            (
+                "MSFT;session=extended-close",
+                AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+            ),
+        ];
+        let session = (TradeTimeSpan::parse_str("9:30", "16:00"), Tz::UTC);
+        let res = transform_input_data(&data, Some(&session), None);
+        assert_eq!(res.len(), 3);
+        let close = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;session=regular-close")
+            .unwrap();
+        assert_eq!(close.1.len(), 1);
+        assert_eq!(close.1.index::<Float>(0), Some(2f64));
+        // The bar out of the shorter series is na.
+        let open = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;session=regular-open")
+            .unwrap();
+        assert_eq!(open.1.len(), 1);
+        assert_eq!(open.1.index::<Float>(0), None);
+
+        // The session of the chart symbol is unknown.
+        assert!(transform_input_data(&data, None, None).is_empty());
+    }
+
+    struct MyRequest;
+
+    impl DataRequest for MyRequest {
+        fn request(&self, _desc: &RequestDesc) -> Option<RequestData> {
+            None
+        }
+
+        fn adjustments(&self, symbol: &str) -> Vec<AdjustmentEvent> {
+            assert_eq!(symbol, "MSFT");
+            vec![AdjustmentEvent::Split {
+                time: 2,
+                numerator: 2f64,
+                denominator: 1f64,
+            }]
+        }
+    }
+
+    #[test]
+    fn adjustment_test() {
+        let data = vec![
+            (
+                "MSFT;adjustment=splits-1D-_time",
+                AnySeries::from_int_vec(vec![Some(1), Some(2)]),
+            ),
+            (
+                "MSFT;adjustment=splits-1D-close",
+                AnySeries::from_float_vec(vec![Some(10f64), Some(5f64)]),
+            ),
+            (
+                "MSFT;adjustment=none-1D-close",
+                AnySeries::from_float_vec(vec![Some(10f64), Some(5f64)]),
+            ),
+        ];
+        let res = transform_input_data(&data, None, Some(&MyRequest));
+        assert_eq!(res.len(), 2);
+        let close = res
+            .iter()
+            .find(|(n, _)| n == "MSFT;adjustment=splits-1D-close")
+            .unwrap();
+        assert_eq!(close.1.index::<Float>(0), Some(5f64));
+        assert_eq!(close.1.index::<Float>(1), Some(5f64));
+
+        // The raw bars are kept without the events.
+        assert!(transform_input_data(&data, None, None).is_empty());
+    }
+}
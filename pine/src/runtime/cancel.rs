@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// The token to abort the running script. It can be cloned and cancelled from other threads,
+// the runner checks it before every bar and every loop iteration.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // Reset the token so it can be used by the next run.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn cancel_token_test() {
+        let token = CancelToken::new();
+        assert!(!token.is_cancelled());
+
+        let other = token.clone();
+        thread::spawn(move || other.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+
+        token.reset();
+        assert!(!token.is_cancelled());
+    }
+}
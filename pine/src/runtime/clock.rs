@@ -0,0 +1,59 @@
+use chrono::Utc;
+use std::cell::Cell;
+use std::fmt::Debug;
+
+// The wall clock read by `timenow`. The host can inject its own clock to make the runs
+// deterministic, e.g. in the tests or the replays of the recorded sessions.
+pub trait Clock: Debug {
+    // The current UNIX time in milliseconds.
+    fn now_millis(&self) -> i64;
+}
+
+// The clock of the system, it is used if the host doesn't set the clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        Utc::now().timestamp_millis()
+    }
+}
+
+// The clock that returns the time set by the host until it is changed.
+#[derive(Debug, Default)]
+pub struct FixedClock {
+    now: Cell<i64>,
+}
+
+impl FixedClock {
+    pub fn new(now: i64) -> FixedClock {
+        FixedClock {
+            now: Cell::new(now),
+        }
+    }
+
+    pub fn set(&self, now: i64) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for FixedClock {
+    fn now_millis(&self) -> i64 {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_test() {
+        let clock = FixedClock::new(1000);
+        assert_eq!(clock.now_millis(), 1000);
+        clock.set(2000);
+        assert_eq!(clock.now_millis(), 2000);
+
+        assert!(SystemClock.now_millis() > 0);
+    }
+}
@@ -1,15 +1,29 @@
-use super::data_src::Callback;
+use super::bar_aggregate::aggregate_input_data;
+use super::cancel::CancelToken;
+use super::clock::{Clock, SystemClock};
+use super::data_src::{Callback, OutputCallback, RequestData, RequestDesc};
+use super::drawing::{Drawing, DrawingKind, DrawingRegistry, DrawingStats};
+use super::exp::HistoryIndexCheck;
+use super::external::ExternalSeries;
+use super::memory::{CtxSites, LiveObject, MemoryReport, ObjectKind};
+use super::op::NaComparison;
 use super::output::InputVal;
 use super::output::{
-    IOInfo, InputInfo, InputSrc, OutputData, OutputInfo, ScriptPurpose, SymbolInfo,
+    ChartInfo, IOInfo, InputInfo, InputSrc, OutputData, OutputInfo, ScriptMeta, ScriptPurpose,
+    SymbolInfo,
 };
+use super::profile::{Profile, Profiler};
+use super::strategy::Broker;
 use crate::ast::input::{Position, StrRange};
 use crate::ast::stat_expr_types::VarIndex;
+use crate::libs::ema::EmaWarmup;
+use crate::libs::security::{SecurityEval, SecurityKey};
 use crate::runtime::AnySeries;
 use crate::types::{
     Bool, Callable, Color, DataType, Float, Int, PineFrom, PineRef, PineStaticType, PineType,
     RefData, Runnable, RuntimeErr, SecondType, Series, NA,
 };
+use chrono_tz::Tz;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -125,6 +139,12 @@ pub struct Context<'a, 'b, 'c> {
     // Custom shapes that require commit. The Context will commit the shape object after every iteration
     reqcom_shapes: Vec<PineRef<'a>>,
 
+    // The drawings created by the script, the oldest ones are deleted if the count exceeds the limit.
+    drawings: DrawingRegistry,
+
+    // The builtin calls and statements timing collected with the `profile` feature.
+    profiler: Profiler,
+
     // The iterator index, start from 0
     iterindex: i32,
 
@@ -135,6 +155,8 @@ pub struct Context<'a, 'b, 'c> {
 
     // Input data for some external ticker.
     input_data: HashMap<String, AnySeries>,
+    // The names of the input data aggregated from the base resolution bars.
+    aggregated_names: Vec<String>,
     // The output data that will be exported.
     output_data: Vec<Option<OutputData>>,
 
@@ -143,6 +165,9 @@ pub struct Context<'a, 'b, 'c> {
     is_input_info_ready: bool,
     // Check if input_info is ready
     is_output_info_ready: bool,
+    // The streaming outputs are muted while the bar is calculated on the tick of an order fill,
+    // since the calculation is rolled back.
+    is_output_muted: bool,
 
     // The symbol information
     syminfo: Option<Rc<SymbolInfo>>,
@@ -150,6 +175,35 @@ pub struct Context<'a, 'b, 'c> {
     // The range of data
     data_range: (Option<i32>, Option<i32>),
 
+    // The broker emulator that is created by the strategy declaration.
+    broker: Option<Broker<'a>>,
+
+    // The token to abort the execution, it is checked by the loops.
+    cancel_token: Option<CancelToken>,
+
+    // How the comparison operators treat na.
+    na_comparison: NaComparison,
+
+    // How ema and rma start before enough bars are calculated.
+    ema_warmup: EmaWarmup,
+
+    // How the negative history references like `close[-1]` are handled.
+    history_index_check: HistoryIndexCheck,
+
+    // The wall clock read by `timenow`, the system clock is used if it is None.
+    clock: Option<Rc<dyn Clock>>,
+
+    // The chart environment of the host read by the `chart.*` variables.
+    chart_info: Option<Rc<ChartInfo>>,
+
+    // The named series registered by the host for `request.economic` and `request.external`.
+    external_series: Option<Rc<ExternalSeries>>,
+
+    // The sub-runtimes shared by the security calls of the same ticker and expression.
+    security_evals: HashMap<SecurityKey, Rc<RefCell<SecurityEval<'a>>>>,
+    // The data of the `request.*` calls, the host is requested once for the same request.
+    request_data: HashMap<RequestDesc, Option<Rc<RequestData>>>,
+
     // The output values
     callback: Option<&'a dyn Callback>,
     first_commit: bool,
@@ -207,6 +261,10 @@ pub fn commit_series_for_operator<'a>(operator: &mut dyn VarOperate<'a>) {
                     use crate::libs::label::PerLabelItem;
                     commit_series::<PerLabelItem>(val)
                 }
+                (DataType::Polyline, SecondType::Series) => {
+                    use crate::libs::polyline::PerPolylineItem;
+                    commit_series::<PerPolylineItem>(val)
+                }
                 _ => val,
             };
             operator.update_var(index, ret_val);
@@ -239,6 +297,10 @@ pub fn rollback_series_for_operator<'a>(operator: &mut dyn VarOperate<'a>) {
                     use crate::libs::label::PerLabelItem;
                     roll_back_series::<PerLabelItem>(val)
                 }
+                (DataType::Polyline, SecondType::Series) => {
+                    use crate::libs::polyline::PerPolylineItem;
+                    roll_back_series::<PerPolylineItem>(val)
+                }
                 _ => val,
             };
             operator.update_var(index, ret_val);
@@ -246,6 +308,54 @@ pub fn rollback_series_for_operator<'a>(operator: &mut dyn VarOperate<'a>) {
     }
 }
 
+// The length and the bytes of the history buffer of the series.
+fn series_usage<'a, D>(val: PineRef<'a>) -> (usize, usize)
+where
+    D: Default + PartialEq + PineStaticType + PineType<'a> + PineFrom<'a, D> + Clone + Debug + 'a,
+{
+    let series: RefData<Series<D>> = Series::implicity_from(val).unwrap();
+    let history = series.get_history();
+    (history.len(), history.capacity() * mem::size_of::<D>())
+}
+
+// The length and the bytes of the elements of the array.
+fn array_usage<'a, D>(val: PineRef<'a>) -> (usize, usize)
+where
+    D: PartialEq + PineStaticType + PineFrom<'a, D> + Clone + Debug + 'a,
+{
+    let items: RefData<Vec<D>> = Vec::implicity_from(val).unwrap();
+    (items.len(), items.capacity() * mem::size_of::<D>())
+}
+
+// The kind, length and bytes of the series or the array held by the variable.
+fn var_usage<'a>(val: PineRef<'a>) -> Option<(ObjectKind, usize, usize)> {
+    let usage = match val.get_type() {
+        (DataType::Float, SecondType::Series) => (ObjectKind::Series, series_usage::<Float>(val)),
+        (DataType::Int, SecondType::Series) => (ObjectKind::Series, series_usage::<Int>(val)),
+        (DataType::Color, SecondType::Series) => (ObjectKind::Series, series_usage::<Color>(val)),
+        (DataType::Bool, SecondType::Series) => (ObjectKind::Series, series_usage::<Bool>(val)),
+        (DataType::String, SecondType::Series) => (ObjectKind::Series, series_usage::<String>(val)),
+        (DataType::Line, SecondType::Series) => {
+            use crate::libs::line::PerLineItem;
+            (ObjectKind::Series, series_usage::<PerLineItem>(val))
+        }
+        (DataType::Label, SecondType::Series) => {
+            use crate::libs::label::PerLabelItem;
+            (ObjectKind::Series, series_usage::<PerLabelItem>(val))
+        }
+        (DataType::Polyline, SecondType::Series) => {
+            use crate::libs::polyline::PerPolylineItem;
+            (ObjectKind::Series, series_usage::<PerPolylineItem>(val))
+        }
+        (DataType::Float, SecondType::Array) => (ObjectKind::Array, array_usage::<Float>(val)),
+        (DataType::Int, SecondType::Array) => (ObjectKind::Array, array_usage::<Int>(val)),
+        (DataType::Bool, SecondType::Array) => (ObjectKind::Array, array_usage::<Bool>(val)),
+        (DataType::String, SecondType::Array) => (ObjectKind::Array, array_usage::<String>(val)),
+        _ => return None,
+    };
+    Some((usage.0, (usage.1).0, (usage.1).1))
+}
+
 fn roll_back_series<'a, D>(val: PineRef<'a>) -> PineRef<'a>
 where
     D: Default + PartialEq + PineStaticType + PineType<'a> + PineFrom<'a, D> + Clone + Debug + 'a,
@@ -267,18 +377,32 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
             runnables: vec![],
             shapes: vec![],
             reqcom_shapes: vec![],
+            drawings: DrawingRegistry::new(),
+            profiler: Profiler::new(),
             iterindex: 0,
             // declare_vars: HashSet::new(),
             callback: None,
             inputs: vec![],
             input_index: -1,
             input_data: HashMap::new(),
+            aggregated_names: vec![],
             output_data: vec![],
             io_info: IOInfo::new(),
             is_input_info_ready: false,
             is_output_info_ready: false,
+            is_output_muted: false,
             syminfo: None,
             data_range: (Some(0), Some(0)),
+            broker: None,
+            cancel_token: None,
+            na_comparison: NaComparison::default(),
+            ema_warmup: EmaWarmup::default(),
+            history_index_check: HistoryIndexCheck::default(),
+            clock: None,
+            chart_info: None,
+            external_series: None,
+            security_evals: HashMap::new(),
+            request_data: HashMap::new(),
             first_commit: false,
             is_run: false,
         }
@@ -295,18 +419,32 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
             runnables: vec![],
             shapes: vec![],
             reqcom_shapes: vec![],
+            drawings: DrawingRegistry::new(),
+            profiler: Profiler::new(),
             iterindex: 0,
             // declare_vars: HashSet::new(),
             callback: Some(callback),
             inputs: vec![],
             input_index: -1,
             input_data: HashMap::new(),
+            aggregated_names: vec![],
             output_data: vec![],
             io_info: IOInfo::new(),
             is_input_info_ready: false,
             is_output_info_ready: false,
+            is_output_muted: false,
             syminfo: None,
             data_range: (Some(0), Some(0)),
+            broker: None,
+            cancel_token: None,
+            na_comparison: NaComparison::default(),
+            ema_warmup: EmaWarmup::default(),
+            history_index_check: HistoryIndexCheck::default(),
+            clock: None,
+            chart_info: None,
+            external_series: None,
+            security_evals: HashMap::new(),
+            request_data: HashMap::new(),
             first_commit: false,
             is_run: false,
         }
@@ -412,6 +550,20 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
+    pub fn set_callback(&mut self, callback: &'a dyn Callback) {
+        self.callback = Some(callback);
+    }
+
+    pub fn set_script_meta(&mut self, script_meta: ScriptMeta) {
+        if self.context_type == ContextType::Main {
+            self.io_info.set_script_meta(script_meta);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).set_script_meta(script_meta)
+        } else {
+            unreachable!()
+        }
+    }
+
     pub fn push_input_info(&mut self, input: InputInfo) {
         if self.context_type == ContextType::Main {
             self.io_info.push_input(input);
@@ -502,9 +654,38 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
+    // Aggregate the bars of the ticker from the base resolution bars in the input data.
+    pub fn aggregate_input_data(&mut self, ticker: &str, is_chart_symbol: bool, tz: &Tz) -> bool {
+        if self.context_type == ContextType::Library {
+            let series = aggregate_input_data(&self.input_data, ticker, is_chart_symbol, tz);
+            let is_aggregated = !series.is_empty();
+            for (name, data) in series.into_iter() {
+                self.aggregated_names.push(name.clone());
+                self.input_data.insert(name, data);
+            }
+            is_aggregated
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).aggregate_input_data(ticker, is_chart_symbol, tz)
+        } else {
+            unreachable!()
+        }
+    }
+
+    // Remove the aggregated input data, so it will be aggregated again from the new base bars.
+    pub fn clear_aggregated_data(&mut self) {
+        if self.context_type == ContextType::Library {
+            for name in mem::take(&mut self.aggregated_names) {
+                self.input_data.remove(&name);
+            }
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).clear_aggregated_data()
+        } else {
+            unreachable!()
+        }
+    }
+
     pub fn get_input_data(&self, name: &str) -> Option<&AnySeries> {
         if self.context_type == ContextType::Library {
-            println!("Get input {:?} {:?}", name, self.input_data.get(name));
             self.input_data.get(name)
         } else if let Some(p) = &self.parent {
             downcast_ctx_const(*p).get_input_data(name)
@@ -523,6 +704,19 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
     }
 
+    pub fn get_output_data(&self, index: usize) -> Option<&OutputData> {
+        if self.context_type == ContextType::Main {
+            match self.output_data.get(index) {
+                Some(Some(data)) => Some(data),
+                _ => None,
+            }
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_output_data(index)
+        } else {
+            unreachable!()
+        }
+    }
+
     pub fn move_output_data(&mut self) -> Vec<Option<OutputData>> {
         debug_assert!(self.is_main());
         debug_assert_eq!(self.output_data.len(), self.io_info.get_outputs().len());
@@ -554,6 +748,317 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         self.data_range = range;
     }
 
+    pub fn set_broker(&mut self, broker: Broker<'a>) {
+        if self.context_type == ContextType::Main {
+            self.broker = Some(broker);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).set_broker(broker)
+        } else {
+            unreachable!()
+        }
+    }
+
+    pub fn get_security_eval(
+        &mut self,
+        key: &SecurityKey,
+    ) -> Option<Rc<RefCell<SecurityEval<'a>>>> {
+        if self.context_type == ContextType::Main {
+            self.security_evals.get(key).cloned()
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).get_security_eval(key)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert_security_eval(&mut self, key: SecurityKey, eval: Rc<RefCell<SecurityEval<'a>>>) {
+        if self.context_type == ContextType::Main {
+            self.security_evals.insert(key, eval);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).insert_security_eval(key, eval)
+        }
+    }
+
+    // Get the data of the request that has been fetched, the data is None if the host has no data.
+    pub fn get_request_data(&mut self, desc: &RequestDesc) -> Option<Option<Rc<RequestData>>> {
+        if self.context_type == ContextType::Main {
+            self.request_data.get(desc).cloned()
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).get_request_data(desc)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert_request_data(&mut self, desc: RequestDesc, data: Option<Rc<RequestData>>) {
+        if self.context_type == ContextType::Main {
+            self.request_data.insert(desc, data);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).insert_request_data(desc, data)
+        }
+    }
+
+    // Remove the fetched request data, so the requests of the next data are sent to the host again.
+    pub fn clear_request_data(&mut self) {
+        debug_assert!(self.is_main());
+        self.request_data.clear();
+    }
+
+    pub fn take_broker(&mut self) -> Option<Broker<'a>> {
+        debug_assert!(self.is_main());
+        self.broker.take()
+    }
+
+    pub fn get_broker(&mut self) -> Option<&mut Broker<'a>> {
+        if self.context_type == ContextType::Main {
+            self.broker.as_mut()
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).get_broker()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        debug_assert!(self.is_main());
+        self.cancel_token = token;
+    }
+
+    // Return the Cancelled error if the execution is cancelled by the host.
+    pub fn check_cancelled(&self) -> Result<(), RuntimeErr> {
+        if self.context_type == ContextType::Main {
+            match &self.cancel_token {
+                Some(token) if token.is_cancelled() => {
+                    Err(RuntimeErr::Cancelled(self.iterindex - 1))
+                }
+                _ => Ok(()),
+            }
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).check_cancelled()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_na_comparison(&mut self, na_cmp: NaComparison) {
+        debug_assert!(self.is_main());
+        self.na_comparison = na_cmp;
+    }
+
+    pub fn get_na_comparison(&self) -> NaComparison {
+        if self.context_type == ContextType::Main {
+            self.na_comparison
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_na_comparison()
+        } else {
+            NaComparison::default()
+        }
+    }
+
+    pub fn set_ema_warmup(&mut self, warmup: EmaWarmup) {
+        debug_assert!(self.is_main());
+        self.ema_warmup = warmup;
+    }
+
+    pub fn get_ema_warmup(&self) -> EmaWarmup {
+        if self.context_type == ContextType::Main {
+            self.ema_warmup
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_ema_warmup()
+        } else {
+            EmaWarmup::default()
+        }
+    }
+
+    pub fn set_history_index_check(&mut self, check: HistoryIndexCheck) {
+        debug_assert!(self.is_main());
+        self.history_index_check = check;
+    }
+
+    pub fn get_history_index_check(&self) -> HistoryIndexCheck {
+        if self.context_type == ContextType::Main {
+            self.history_index_check
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_history_index_check()
+        } else {
+            HistoryIndexCheck::default()
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Option<Rc<dyn Clock>>) {
+        debug_assert!(self.is_main());
+        self.clock = clock;
+    }
+
+    pub fn set_chart_info(&mut self, chart_info: Option<Rc<ChartInfo>>) {
+        debug_assert!(self.is_main());
+        self.chart_info = chart_info;
+    }
+
+    pub fn get_chart_info(&self) -> Option<Rc<ChartInfo>> {
+        if self.context_type == ContextType::Main {
+            self.chart_info.clone()
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_chart_info()
+        } else {
+            None
+        }
+    }
+
+    pub fn set_external_series(&mut self, series: Option<Rc<ExternalSeries>>) {
+        debug_assert!(self.is_main());
+        self.external_series = series;
+    }
+
+    pub fn get_external_series(&self) -> Option<Rc<ExternalSeries>> {
+        if self.context_type == ContextType::Main {
+            self.external_series.clone()
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_external_series()
+        } else {
+            None
+        }
+    }
+
+    // The current time in milliseconds of the clock injected by the host.
+    pub fn get_now_millis(&self) -> i64 {
+        if self.context_type == ContextType::Main {
+            match &self.clock {
+                Some(clock) => clock.now_millis(),
+                None => SystemClock.now_millis(),
+            }
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).get_now_millis()
+        } else {
+            SystemClock.now_millis()
+        }
+    }
+
+    pub fn add_drawing(&mut self, kind: DrawingKind, item: Rc<dyn Drawing>) {
+        if self.context_type == ContextType::Main {
+            self.drawings.add(kind, item);
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).add_drawing(kind, item)
+        } else {
+            unreachable!()
+        }
+    }
+
+    // The count of the alive lines, labels and boxes.
+    pub fn get_drawing_stats(&self) -> DrawingStats {
+        debug_assert!(self.is_main());
+        self.drawings.get_stats()
+    }
+
+    // The receiver of the streaming outputs provided by the host, it's None while the outputs are muted.
+    pub fn get_output_callback(&self) -> Option<&'a dyn OutputCallback> {
+        if self.check_is_output_muted() {
+            return None;
+        }
+        match self.get_callback() {
+            Some(callback) => callback.get_output_callback(),
+            None => None,
+        }
+    }
+
+    pub fn check_is_output_muted(&self) -> bool {
+        if self.context_type == ContextType::Main {
+            self.is_output_muted
+        } else if let Some(p) = &self.parent {
+            downcast_ctx_const(*p).check_is_output_muted()
+        } else {
+            unreachable!()
+        }
+    }
+
+    pub fn set_output_muted(&mut self, muted: bool) {
+        debug_assert!(self.is_main());
+        self.is_output_muted = muted;
+    }
+
+    pub fn get_profiler(&mut self) -> Option<&mut Profiler> {
+        if self.context_type == ContextType::Main {
+            Some(&mut self.profiler)
+        } else if let Some(p) = &mut self.parent {
+            downcast_ctx(*p).get_profiler()
+        } else {
+            None
+        }
+    }
+
+    // The bytes of the history buffers of the series variables in this context and the sub contexts.
+    pub fn get_series_memory(&self) -> usize {
+        let mut visited: HashSet<*const (dyn PineType<'a> + 'a)> = HashSet::new();
+        let mut bytes = 0;
+        for val in self.vars.iter().flatten() {
+            if !visited.insert(val.as_ptr()) {
+                continue;
+            }
+            bytes += match var_usage(val.clone()) {
+                Some((ObjectKind::Series, _, size)) => size,
+                _ => 0,
+            };
+        }
+        for ctx in self.sub_contexts.iter().flatten() {
+            bytes += downcast_ctx_const(&**ctx).get_series_memory();
+        }
+        bytes
+    }
+
+    // Collect the series and arrays held by the variables of this context and the sub contexts,
+    // `sites` maps the variables to the declarations and `scope` is the path of this context.
+    pub fn collect_objects(
+        &self,
+        sites: Option<&CtxSites<'a>>,
+        scope: &mut Vec<i32>,
+        objects: &mut Vec<LiveObject>,
+    ) {
+        let mut visited: HashSet<*const (dyn PineType<'a> + 'a)> = HashSet::new();
+        for (varid, val) in self.vars.iter().enumerate() {
+            let val = match val {
+                Some(val) if visited.insert(val.as_ptr()) => val,
+                _ => continue,
+            };
+            if let Some((kind, len, bytes)) = var_usage(val.clone()) {
+                let site = sites.and_then(|s| s.get_var(varid as i32));
+                objects.push(LiveObject {
+                    kind,
+                    name: site
+                        .map(|(name, _)| String::from(*name))
+                        .unwrap_or_default(),
+                    id: Some((scope.clone(), varid as i32)),
+                    len,
+                    bytes,
+                    range: site.map(|(_, range)| *range),
+                });
+            }
+        }
+        for (ctxid, ctx) in self.sub_contexts.iter().enumerate() {
+            if let Some(ctx) = ctx {
+                scope.push(ctxid as i32);
+                downcast_ctx_const(&**ctx).collect_objects(
+                    sites.and_then(|s| s.get_sub(ctxid as i32)),
+                    scope,
+                    objects,
+                );
+                scope.pop();
+            }
+        }
+    }
+
+    // The live objects of the script, the declarations are looked up in the block of the script.
+    pub fn gen_memory_report(&self, sites: &CtxSites<'a>) -> MemoryReport {
+        debug_assert!(self.is_main());
+        let mut objects = vec![];
+        self.collect_objects(Some(sites), &mut vec![], &mut objects);
+        MemoryReport::new(objects, self.get_drawing_stats())
+    }
+
+    pub fn gen_profile(&self) -> Profile {
+        debug_assert!(self.is_main());
+        self.profiler.gen_profile(self.get_series_memory())
+    }
+
     pub fn create_sub_context(
         &'c mut self,
         index: i32,
@@ -591,6 +1096,48 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         self.get_sub_context(index).unwrap()
     }
 
+    // Move out the sub contexts, so the main context of the next run can reuse them.
+    pub fn take_sub_contexts(&mut self) -> Vec<Option<Box<dyn 'c + Ctx<'a>>>> {
+        mem::take(&mut self.sub_contexts)
+    }
+
+    // Reuse the sub contexts created by the previous run of the same script. The pool is indexed
+    // by the ctxid like `sub_contexts`, so every function call and block gets back its own
+    // context with the same variable counts instead of allocating the context tree again.
+    pub fn recycle_sub_contexts(&mut self, pool: Vec<Option<Box<dyn 'c + Ctx<'a>>>>) {
+        if pool.len() != self.sub_contexts.len() {
+            return;
+        }
+        let ptr: *mut Context<'a, 'b, 'c> = self;
+        for (index, subctx) in pool.into_iter().enumerate() {
+            if let Some(mut subctx) = subctx {
+                let context = downcast_ctx(subctx.as_mut());
+                context.reset();
+                // The nested contexts still point to their own parents that are not moved.
+                unsafe {
+                    context.parent = Some(ptr.as_mut().unwrap());
+                }
+                self.sub_contexts[index] = Some(subctx);
+            }
+        }
+    }
+
+    // Clear the values of the previous run, the allocated vectors and sub contexts are kept.
+    fn reset(&mut self) {
+        self.vars.iter_mut().for_each(|v| *v = None);
+        self.fun_instances.iter_mut().for_each(|v| *v = None);
+        self.varname_indexs.clear();
+        self.runnables.clear();
+        self.shapes.clear();
+        self.reqcom_shapes.clear();
+        self.iterindex = 0;
+        self.first_commit = false;
+        self.is_run = false;
+        for subctx in self.sub_contexts.iter_mut().flatten() {
+            downcast_ctx(subctx.as_mut()).reset();
+        }
+    }
+
     pub fn map_var<F>(&mut self, index: VarIndex, f: F)
     where
         F: Fn(Option<PineRef<'a>>) -> Option<PineRef<'a>>,
@@ -619,7 +1166,11 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
             self.first_commit = true;
         }
 
-        // Commit all of the shapes(Line, Label)
+        if let Some(broker) = self.broker.as_mut() {
+            broker.commit();
+        }
+
+        // Commit all of the shapes(Line, Label, Polyline)
         for shape in self.reqcom_shapes.iter_mut() {
             match shape.get_type() {
                 (DataType::Line, SecondType::Series) => {
@@ -634,9 +1185,32 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
                         .unwrap()
                         .commit()
                 }
+                (DataType::Polyline, SecondType::Series) => {
+                    use crate::libs::polyline::PerPolylineItem;
+                    Series::<'a, PerPolylineItem>::implicity_from(shape.clone())
+                        .unwrap()
+                        .commit()
+                }
                 _ => unreachable!(),
             }
         }
+
+        if self.is_main() {
+            let limits = match self.io_info.get_script_meta() {
+                Some(meta) => [
+                    meta.max_lines_count,
+                    meta.max_labels_count,
+                    meta.max_boxes_count,
+                    meta.max_polylines_count,
+                ],
+                None => [None, None, None, None],
+            };
+            if self.drawings.commit(limits) {
+                if let Some(callback) = self.get_output_callback() {
+                    callback.on_drawing_update(self.iterindex, &self.drawings.get_stats());
+                }
+            }
+        }
     }
 
     pub fn roll_back(&mut self) -> Result<(), PineRuntimeError> {
@@ -650,7 +1224,11 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
         }
         mem::replace(&mut self.runnables, callables);
 
-        // Roll back all of the shapes(Line, Label)
+        if let Some(broker) = self.broker.as_mut() {
+            broker.roll_back();
+        }
+
+        // Roll back all of the shapes(Line, Label, Polyline)
         for shape in self.reqcom_shapes.iter_mut() {
             match shape.get_type() {
                 (DataType::Line, SecondType::Series) => {
@@ -665,9 +1243,19 @@ impl<'a, 'b, 'c> Context<'a, 'b, 'c> {
                         .unwrap()
                         .roll_back()
                 }
+                (DataType::Polyline, SecondType::Series) => {
+                    use crate::libs::polyline::PerPolylineItem;
+                    Series::<'a, PerPolylineItem>::implicity_from(shape.clone())
+                        .unwrap()
+                        .roll_back()
+                }
                 _ => unreachable!(),
             }
         }
+
+        if self.is_main() {
+            self.drawings.roll_back();
+        }
         Ok(())
     }
 
@@ -862,7 +1450,7 @@ impl<'a, 'b, 'c> Ctx<'a> for Context<'a, 'b, 'c> {
     fn declare_shape(&mut self, shape: PineRef<'a>, req_commit: bool) {
         debug_assert!(self.is_main());
         match shape.get_type().0 {
-            DataType::Line | DataType::Label => {
+            DataType::Line | DataType::Label | DataType::Polyline => {
                 if self
                     .shapes
                     .iter()
@@ -913,7 +1501,11 @@ impl<'a, 'b, 'c> Ctx<'a> for Context<'a, 'b, 'c> {
     }
 
     fn get_callback(&self) -> Option<&'a dyn Callback> {
-        self.callback
+        match (self.callback, &self.parent) {
+            (Some(callback), _) => Some(callback),
+            (None, Some(p)) => p.get_callback(),
+            (None, None) => None,
+        }
     }
 
     fn set_iterindex(&mut self, index: i32) {
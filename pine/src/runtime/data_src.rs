@@ -2,9 +2,26 @@ use super::context::{
     downcast_ctx, Context, ContextType, Ctx, PineRuntimeError, Runner, VarOperate,
 };
 // use super::ctxid_parser::CtxIdParser;
-use super::output::{InputSrc, InputVal, SymbolInfo};
+use super::bar_time::{
+    bar_close_time, infer_bar_interval, parse_tz, session_start_minutes, trading_day,
+};
+use super::bar_transform::{regular_session, transform_input_data, BAR_SRCS};
+use super::cancel::CancelToken;
+use super::clock::Clock;
+use super::drawing::DrawingStats;
+use super::exp::HistoryIndexCheck;
+use super::external::ExternalSeries;
+use super::host_event::{apply_event, HostEvent};
+use super::memory::{CtxSites, LeakChecker, LeakWarning, MemoryReport};
+use super::op::NaComparison;
+use super::output::{ChartInfo, InputSrc, InputVal, SymbolInfo};
+use super::parallel::level_order;
+use super::strategy::BarPrice;
 use super::{AnySeries, AnySeriesType};
 use crate::ast::stat_expr_types::{Block, VarIndex};
+use crate::helper::{pine_ref_to_f64, pine_ref_to_i64};
+use crate::libs::ema::EmaWarmup;
+use crate::syntax::stmt_deps::gen_stmt_levels;
 use crate::types::{
     DataType, Float, Int, PineFrom, PineRef, PineType, RefData, RuntimeErr, Series,
 };
@@ -15,8 +32,111 @@ pub trait Callback {
     fn print(&self, _str: String) {}
 
     fn plot(&self, _floats: Vec<f64>) {}
+
+    // The data layer that backs the `request.*` builtins, None means no external data.
+    fn get_data_request(&self) -> Option<&dyn DataRequest> {
+        None
+    }
+
+    // The receiver of the streaming outputs, None means the outputs are only collected after running.
+    fn get_output_callback(&self) -> Option<&dyn OutputCallback> {
+        None
+    }
+}
+
+// The outputs streamed during the execution, so the hosts can render them progressively.
+// The `bar_index` is the index of the running bar. The bar that is updated by `update` runs again,
+// so the hosts should replace the outputs of the same bar.
+pub trait OutputCallback {
+    // The value of the `plot`, `plotshape`, `plotchar` or `plotarrow` output at the index of the
+    // output infos for the bar. The bar is shifted by the `offset` of the output, so it can be after
+    // the running bar. The candles, bars and fills are only collected after running.
+    fn on_plot_point(&self, _output_index: usize, _bar_index: i32, _value: Option<f64>) {}
+
+    // The color of the `bgcolor` or `barcolor` output for the bar shifted by the `offset`, None is na.
+    fn on_plot_color(&self, _output_index: usize, _bar_index: i32, _color: Option<String>) {}
+
+    // Some drawings(line, label, box) are created or deleted by the bar.
+    fn on_drawing_update(&self, _bar_index: i32, _stats: &DrawingStats) {}
+
+    // The `alert` function is triggered by the bar.
+    fn on_alert(&self, _bar_index: i32, _message: String) {}
+
+    // The bar is completed, all the outputs of the bar have been sent.
+    fn on_bar_complete(&self, _bar_index: i32) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RequestKind {
+    Financial,
+    Dividends,
+    Splits,
+    // The economic indicator of the country, the symbol is the country code.
+    Economic,
+    // The series registered by the host, the symbol is the series id.
+    External,
+}
+
+// The typed descriptor of one `request.*` call.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestDesc {
+    pub kind: RequestKind,
+    pub symbol: String,
+    // The timeframe(period) of the requested data, e.g. FQ, FY
+    pub timeframe: Option<String>,
+    // The requested field, e.g. the financial id or the dividends field
+    pub field: Option<String>,
+    pub gaps: bool,
+}
+
+// The requested data points, the times must be sorted in the ascending order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestData {
+    pub time: Vec<i64>,
+    pub values: Vec<Float>,
+}
+
+impl RequestData {
+    pub fn new(time: Vec<i64>, values: Vec<Float>) -> RequestData {
+        RequestData { time, values }
+    }
+}
+
+// The corporate action that changes the prices of the symbol, the time is the ex-date.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdjustmentEvent {
+    // The split of `numerator` new shares for `denominator` old shares, e.g. 4 for 1.
+    Split {
+        time: i64,
+        numerator: f64,
+        denominator: f64,
+    },
+    // The dividend paid per share.
+    Dividend {
+        time: i64,
+        amount: f64,
+    },
+}
+
+// Hosts implement this trait to back the `request.*` builtins from their own data layer.
+pub trait DataRequest {
+    fn request(&self, desc: &RequestDesc) -> Option<RequestData>;
+
+    // The splits and dividends of the symbol that back-adjust the raw bars of the tickers with
+    // `adjustment=splits` or `adjustment=dividends`, empty means the bars are not adjusted.
+    fn adjustments(&self, _symbol: &str) -> Vec<AdjustmentEvent> {
+        vec![]
+    }
 }
 
+// The input data name of the chart bar times.
+pub const CHART_TIME_NAME: &'static str = "_time";
+
+// The input data names of the bar close times and the trading days, they are derived from the bar
+// times unless the host provides them.
+pub const TIME_CLOSE_NAME: &'static str = "time_close";
+pub const TIME_TRADINGDAY_NAME: &'static str = "time_tradingday";
+
 pub struct NoneCallback();
 impl Callback for NoneCallback {}
 
@@ -30,6 +150,21 @@ pub struct DataSrc<'a> {
     inputs: Vec<Option<InputVal>>,
     input_srcs: Option<InputSrc>,
     has_run: bool,
+    // The names of the transformed series of the last data.
+    transformed_names: Vec<String>,
+    cancel_token: Option<CancelToken>,
+    na_comparison: NaComparison,
+    ema_warmup: EmaWarmup,
+    history_index_check: HistoryIndexCheck,
+    clock: Option<Rc<dyn Clock>>,
+    chart_info: Option<Rc<ChartInfo>>,
+    external_series: Option<Rc<ExternalSeries>>,
+    // The order of the top-level statements by the dependency levels, see `set_level_order`.
+    stmt_order: Option<Vec<usize>>,
+    // The duration of the chart bars inferred from the times of the data, see `bar_time`.
+    bar_interval: Option<i64>,
+    // The declarations of the variables and the checker of the growing objects, see `set_leak_check`.
+    leak_check: Option<(CtxSites<'a>, LeakChecker)>,
 }
 
 pub fn parse_datalen<'a>(
@@ -95,6 +230,7 @@ impl<'a> DataSrc<'a> {
             ContextType::Main,
         ));
         main_ctx.init(blk.var_count, blk.subctx_count, blk.libfun_count);
+        main_ctx.set_callback(callback);
 
         DataSrc {
             blk,
@@ -106,14 +242,31 @@ impl<'a> DataSrc<'a> {
             inputs: vec![],
             input_srcs: None,
             has_run: false,
+            transformed_names: vec![],
+            cancel_token: None,
+            na_comparison: NaComparison::default(),
+            ema_warmup: EmaWarmup::default(),
+            history_index_check: HistoryIndexCheck::default(),
+            clock: None,
+            chart_info: None,
+            external_series: None,
+            stmt_order: None,
+            bar_interval: None,
+            leak_check: None,
         }
     }
 
     pub fn reset_vars(&mut self) {
+        self.bar_interval = None;
         if !self.has_run {
             return;
         }
-        println!("Will reset vars");
+        // Take the sub contexts of the last run before the main context is replaced.
+        let pool: Vec<Option<Box<dyn Ctx<'a> + 'a>>> = unsafe {
+            mem::transmute::<Vec<Option<Box<dyn Ctx<'a> + '_>>>, _>(
+                downcast_ctx(self.context.as_mut()).take_sub_contexts(),
+            )
+        };
         let parent = unsafe { mem::transmute::<_, &mut (dyn Ctx<'a>)>(self.lib_context.as_mut()) };
         let mut main_ctx = Context::new(Some(parent), ContextType::Main);
         // Set the inputs and input sources.
@@ -128,7 +281,99 @@ impl<'a> DataSrc<'a> {
             self.blk.subctx_count,
             self.blk.libfun_count,
         );
+        main_ctx.set_callback(self.callback);
+        main_ctx.set_cancel_token(self.cancel_token.clone());
+        main_ctx.set_na_comparison(self.na_comparison);
+        main_ctx.set_ema_warmup(self.ema_warmup);
+        main_ctx.set_history_index_check(self.history_index_check);
+        main_ctx.set_clock(self.clock.clone());
+        main_ctx.set_chart_info(self.chart_info.clone());
+        main_ctx.set_external_series(self.external_series.clone());
         self.context = Box::new(main_ctx);
+        // The sub contexts are reparented after the main context is boxed at its final address.
+        downcast_ctx(self.context.as_mut()).recycle_sub_contexts(pool);
+        if let Some((_, checker)) = &mut self.leak_check {
+            checker.reset();
+        }
+    }
+
+    // Set the token to abort the execution, the run returns the Cancelled error once it is cancelled.
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel_token = token;
+        downcast_ctx(self.context.as_mut()).set_cancel_token(self.cancel_token.clone());
+    }
+
+    // Set how the comparison operators treat na, the default is the Pine comparison.
+    pub fn set_na_comparison(&mut self, na_cmp: NaComparison) {
+        self.na_comparison = na_cmp;
+        downcast_ctx(self.context.as_mut()).set_na_comparison(na_cmp);
+    }
+
+    // Evaluate the top-level statements level by level by their dependencies instead of the source
+    // order. The statements with side effects like the plots and alerts keep their order. The
+    // statements are still evaluated one by one on the runner thread, since the runtime values
+    // can't be shared by the threads, so it's disabled by default.
+    pub fn set_level_order(&mut self, enabled: bool) {
+        self.stmt_order = match enabled {
+            true => Some(level_order(&gen_stmt_levels(self.blk))),
+            false => None,
+        };
+    }
+
+    fn run_blk(&mut self) -> Result<PineRef<'a>, PineRuntimeError> {
+        match &self.stmt_order {
+            Some(order) => self.blk.run_in_order(order, self.context.as_mut()),
+            None => self.blk.run(self.context.as_mut()),
+        }
+    }
+
+    // Set how ema and rma start before enough bars are calculated, the default is the sma seeding.
+    pub fn set_ema_warmup(&mut self, warmup: EmaWarmup) {
+        self.ema_warmup = warmup;
+        downcast_ctx(self.context.as_mut()).set_ema_warmup(warmup);
+    }
+
+    // Set how the negative history references are handled, the default returns na.
+    pub fn set_history_index_check(&mut self, check: HistoryIndexCheck) {
+        self.history_index_check = check;
+        downcast_ctx(self.context.as_mut()).set_history_index_check(check);
+    }
+
+    // Set the wall clock read by `timenow`, the system clock is used if it is None.
+    pub fn set_clock(&mut self, clock: Option<Rc<dyn Clock>>) {
+        self.clock = clock;
+        downcast_ctx(self.context.as_mut()).set_clock(self.clock.clone());
+    }
+
+    // Set the chart environment read by the `chart.*` variables, they are na if it is None.
+    pub fn set_chart_info(&mut self, chart_info: Option<Rc<ChartInfo>>) {
+        self.chart_info = chart_info;
+        downcast_ctx(self.context.as_mut()).set_chart_info(self.chart_info.clone());
+    }
+
+    // Set the series registered by the host, they are looked up before the data request.
+    pub fn set_external_series(&mut self, series: Option<Rc<ExternalSeries>>) {
+        self.external_series = series;
+        downcast_ctx(self.context.as_mut()).set_external_series(self.external_series.clone());
+    }
+
+    // Check the arrays and drawings after every bar, the object that grows on `min_bars` bars
+    // without shrinking is reported by `get_leak_warnings`. None disables the check.
+    pub fn set_leak_check(&mut self, min_bars: Option<usize>) {
+        self.leak_check = min_bars.map(|n| (CtxSites::new(self.blk), LeakChecker::new(n)));
+    }
+
+    pub fn get_leak_warnings(&self) -> Vec<LeakWarning> {
+        match &self.leak_check {
+            Some((_, checker)) => checker.get_warnings().clone(),
+            None => vec![],
+        }
+    }
+
+    // The live series, arrays and drawings of the script.
+    pub fn gen_memory_report(&mut self) -> MemoryReport {
+        let sites = CtxSites::new(self.blk);
+        downcast_ctx(self.context.as_mut()).gen_memory_report(&sites)
     }
 
     pub fn change_inputs(&mut self, inputs: Vec<Option<InputVal>>) {
@@ -136,24 +381,198 @@ impl<'a> DataSrc<'a> {
         downcast_ctx(self.context.as_mut()).change_inputs(self.inputs.clone());
     }
 
+    // Apply the event of the host to the inputs, the inputs are checked with the inputs collected
+    // by the last run. Return true if any input is changed.
+    pub fn apply_event(&mut self, event: &HostEvent) -> Result<bool, PineRuntimeError> {
+        let main_ctx = downcast_ctx(self.context.as_mut());
+        let count = main_ctx.get_io_info().get_inputs().len();
+        let mut inputs = self.inputs.clone();
+        match apply_event(&mut inputs, event, count) {
+            Ok(true) => {
+                self.change_inputs(inputs);
+                Ok(true)
+            }
+            Ok(false) => Ok(false),
+            Err(err) => Err(PineRuntimeError::new_no_range(err)),
+        }
+    }
+
     pub fn set_input_srcs(&mut self, srcs: Vec<String>) {
         self.input_srcs = Some(InputSrc::new(None, srcs));
         downcast_ctx(self.context.as_mut())
             .add_input_src(self.input_srcs.as_ref().unwrap().clone());
     }
 
+    // Get the current value of the input source.
+    fn get_input(&self, name: &str) -> Option<PineRef<'a>> {
+        match self.input_names.iter().position(|(s, _)| *s == name) {
+            Some(pos) => {
+                let var_index = VarIndex::new(self.input_index + pos as i32, 0);
+                self.lib_context.get_var(var_index).clone()
+            }
+            None => None,
+        }
+    }
+
+    // The indexes of the input variables for the data.
+    fn gen_name_indexs(&self, data: &Vec<(&'static str, AnySeries)>) -> Vec<Option<usize>> {
+        data.iter()
+            .map(|(k, _)| self.input_names.iter().position(|(s, _)| *s == *k))
+            .collect()
+    }
+
+    // Insert the whole series that are read by the index into the library context.
+    fn prepare_data(
+        &mut self,
+        data: &Vec<(&'static str, AnySeries)>,
+        name_indexs: &[Option<usize>],
+    ) {
+        // The `request.*` data is fetched again for the new data.
+        downcast_ctx(self.context.as_mut()).clear_request_data();
+
+        // The series of the transformed tickers(heikin-ashi, renko, etc), the tickers of the
+        // regular session and the adjusted tickers are converted once.
+        let syminfo = downcast_ctx(self.context.as_mut()).get_syminfo().clone();
+        let session = regular_session(syminfo.as_deref());
+        let request = self.callback.get_data_request();
+        let transformed = transform_input_data(data, session.as_ref(), request);
+        self.transformed_names = transformed.iter().map(|(n, _)| n.clone()).collect();
+        for (name, series) in transformed.into_iter() {
+            downcast_ctx(self.lib_context.as_mut()).insert_input_data(name, series);
+        }
+
+        // The chart bars are used to align the intrabars of the lower timeframes and to aggregate
+        // the higher timeframes. The data that is not the input variable is inserted in the loop below.
+        let lib_ctx = downcast_ctx(self.lib_context.as_mut());
+        lib_ctx.clear_aggregated_data();
+        for (index, (name, series)) in data.iter().enumerate() {
+            if name_indexs[index].is_some() && BAR_SRCS.contains(name) {
+                lib_ctx.insert_input_data(String::from(*name), series.clone());
+            }
+        }
+
+        // The updates of the realtime bars may not contain enough bars to infer the interval.
+        if let Some((_, times)) = data.iter().find(|(n, _)| *n == CHART_TIME_NAME) {
+            self.bar_interval = match (self.bar_interval, infer_bar_interval(times)) {
+                (Some(v1), Some(v2)) => Some(v1.min(v2)),
+                (v1, v2) => v1.or(v2),
+            };
+        }
+    }
+
+    fn update_int_input(&mut self, name_index: usize, val: Int) {
+        let var_index = VarIndex::new(self.input_index + name_index as i32, 0);
+        let series = self.lib_context.move_var(var_index).unwrap();
+        let mut int_s: RefData<Series<Int>> = Series::implicity_from(series).unwrap();
+        int_s.update(val);
+        self.lib_context.update_var(var_index, int_s.into_pf());
+    }
+
+    // Fill the orders of the strategy with the ticks of the bar. The script is calculated again after
+    // the orders are filled on the tick if `calc_on_order_fills` is on, so the new orders can be
+    // filled by the following ticks of the same bar.
+    fn fill_orders(&mut self, bar_index: i32, price: BarPrice) -> Result<(), PineRuntimeError> {
+        let broker = downcast_ctx(self.context.as_mut()).get_broker().unwrap();
+        if !broker.get_config().calc_on_order_fills {
+            broker.on_bar(bar_index, price);
+            return Ok(());
+        }
+        let ticks = broker.start_bar(bar_index, price);
+        for (i, tick) in ticks.iter().enumerate() {
+            let broker = downcast_ctx(self.context.as_mut()).get_broker().unwrap();
+            // The orders filled on the close are handled by the calculation of the bar.
+            if broker.on_tick(bar_index, *tick) > 0 && i + 1 < ticks.len() {
+                self.run_intrabar(bar_index, *tick)?;
+            }
+        }
+        downcast_ctx(self.context.as_mut())
+            .get_broker()
+            .unwrap()
+            .end_bar(bar_index, price);
+        Ok(())
+    }
+
+    // Calculate the script with the close price of the tick, then roll back everything except the
+    // orders of the broker. The streaming outputs are muted since the calculation is discarded, the
+    // outputs of the bar are streamed by the calculation of the bar.
+    fn run_intrabar(&mut self, bar_index: i32, tick: f64) -> Result<(), PineRuntimeError> {
+        let close_index = match self.input_names.iter().position(|(s, _)| *s == "close") {
+            Some(pos) => VarIndex::new(self.input_index + pos as i32, 0),
+            None => return Ok(()),
+        };
+        let set_close = |ctx: &mut dyn Ctx<'a>, val: Float| -> Float {
+            let mut close_s: RefData<Series<Float>> =
+                Series::implicity_from(ctx.move_var(close_index).unwrap()).unwrap();
+            let prev = close_s.get_current();
+            close_s.update(val);
+            ctx.update_var(close_index, close_s.into_pf());
+            prev
+        };
+        let close = set_close(self.lib_context.as_mut(), Some(tick));
+
+        self.context.set_iterindex(bar_index);
+        downcast_ctx(self.context.as_mut()).set_output_muted(true);
+        let res = self.run_blk();
+        let main_ctx = downcast_ctx(self.context.as_mut());
+        let broker = main_ctx.take_broker();
+        main_ctx.commit();
+        let rb_res = main_ctx.roll_back();
+        if let Some(broker) = broker {
+            main_ctx.set_broker(broker);
+        }
+        main_ctx.set_output_muted(false);
+        main_ctx.clear_is_run();
+        main_ctx.reset_input_index();
+
+        set_close(self.lib_context.as_mut(), close);
+        res.and(rb_res)
+    }
+
     fn run_data(
         &mut self,
         data: &Vec<(&'static str, AnySeries)>,
         start: i64,
         len: usize,
+    ) -> Result<(), PineRuntimeError> {
+        let name_indexs = self.gen_name_indexs(data);
+        self.prepare_data(data, &name_indexs);
+        self.run_bars(data, &name_indexs, start, start, len)
+    }
+
+    // Run the bars from `start` to `start + len`, the first element of the data is the bar `data_start`.
+    fn run_bars(
+        &mut self,
+        data: &Vec<(&'static str, AnySeries)>,
+        name_indexs: &[Option<usize>],
+        data_start: i64,
+        start: i64,
+        len: usize,
     ) -> Result<(), PineRuntimeError> {
         let bar_index = self.input_names.iter().position(|(s, _)| *s == "bar_index");
-        let name_indexs: Vec<Option<usize>> = data
+        // The close time and the trading day are derived from the bar time if the data does not
+        // contain them.
+        let derived_index = |name: &str| match data.iter().any(|(n, _)| *n == name) {
+            true => None,
+            false => self.input_names.iter().position(|(s, _)| *s == name),
+        };
+        let time_close = derived_index(TIME_CLOSE_NAME);
+        let time_tradingday = derived_index(TIME_TRADINGDAY_NAME);
+        let times = data
             .iter()
-            .map(|(k, _)| self.input_names.iter().position(|(s, _)| *s == *k))
-            .collect();
+            .find(|(n, _)| *n == CHART_TIME_NAME)
+            .map(|v| &v.1);
+        let syminfo = downcast_ctx(self.context.as_mut()).get_syminfo().clone();
+        let tz = parse_tz(syminfo.as_deref());
+        let start_m = session_start_minutes(syminfo.as_deref());
         for iter_i in start..(start + len as i64) {
+            if let Some(token) = &self.cancel_token {
+                if token.is_cancelled() {
+                    return Err(PineRuntimeError::new_no_range(RuntimeErr::Cancelled(
+                        iter_i as i32 - 1,
+                    )));
+                }
+            }
+
             // Extract data into context
             for (index, (_k, v)) in data.iter().enumerate() {
                 if let Some(name_index) = name_indexs[index] {
@@ -163,18 +582,18 @@ impl<'a> DataSrc<'a> {
                         DataType::Float => {
                             let mut float_s: RefData<Series<Float>> =
                                 Series::implicity_from(series).unwrap();
-                            float_s.update(v.index((iter_i - start) as isize));
+                            float_s.update(v.index((iter_i - data_start) as isize));
                             self.lib_context.update_var(var_index, float_s.into_pf());
                         }
                         DataType::Int => {
                             let mut int_s: RefData<Series<Int>> =
                                 Series::implicity_from(series).unwrap();
-                            int_s.update(v.index((iter_i - start) as isize));
+                            int_s.update(v.index((iter_i - data_start) as isize));
                             self.lib_context.update_var(var_index, int_s.into_pf());
                         }
                         _ => unreachable!(),
                     }
-                } else {
+                } else if !self.transformed_names.iter().any(|n| n == _k) {
                     // TODO: Remove this data copy.
                     downcast_ctx(self.lib_context.as_mut())
                         .insert_input_data(String::from(*_k), v.clone());
@@ -182,15 +601,38 @@ impl<'a> DataSrc<'a> {
             }
 
             if let Some(bar_index) = bar_index {
-                let var_index = VarIndex::new(self.input_index + bar_index as i32, 0);
-                let series = self.lib_context.move_var(var_index).unwrap();
-                let mut index_s: RefData<Series<Int>> = Series::implicity_from(series).unwrap();
-                index_s.update(Some(iter_i as i64));
-                self.lib_context.update_var(var_index, index_s.into_pf());
+                self.update_int_input(bar_index, Some(iter_i as i64));
+            }
+
+            let data_i = (iter_i - data_start) as usize;
+            let time = times.and_then(|v| v.index::<Int>(data_i as isize));
+            if let Some(time_close) = time_close {
+                let next_time = match times {
+                    Some(v) if data_i + 1 < v.len() => v.index::<Int>(data_i as isize + 1),
+                    _ => None,
+                };
+                let val = bar_close_time(time, next_time, self.bar_interval);
+                self.update_int_input(time_close, val);
+            }
+            if let Some(time_tradingday) = time_tradingday {
+                let val = time.map(|t| trading_day(t, &tz, start_m));
+                self.update_int_input(time_tradingday, val);
+            }
+
+            // Fill the orders placed by the strategy on the previous bar.
+            if downcast_ctx(self.context.as_mut()).get_broker().is_some() {
+                let price = BarPrice {
+                    time: pine_ref_to_i64(self.get_input("_time")),
+                    open: pine_ref_to_f64(self.get_input("open")),
+                    high: pine_ref_to_f64(self.get_input("high")),
+                    low: pine_ref_to_f64(self.get_input("low")),
+                    close: pine_ref_to_f64(self.get_input("close")),
+                };
+                self.fill_orders(iter_i as i32, price)?;
             }
 
             self.context.set_iterindex(iter_i as i32);
-            self.blk.run(self.context.as_mut())?;
+            self.run_blk()?;
 
             let lib_ctx = downcast_ctx(self.lib_context.as_mut());
             // main context is not children of Library context, so commit it alone.
@@ -202,6 +644,15 @@ impl<'a> DataSrc<'a> {
             // self.context.clear_declare();
             main_ctx.reset_input_index();
             main_ctx.let_input_info_ready();
+
+            if let Some((sites, checker)) = &mut self.leak_check {
+                let report = main_ctx.gen_memory_report(sites);
+                checker.observe(iter_i as i32, &report.objects);
+            }
+
+            if let Some(callback) = self.callback.get_output_callback() {
+                callback.on_bar_complete(iter_i as i32);
+            }
         }
         let main_ctx = downcast_ctx(self.context.as_mut());
         match downcast_ctx(main_ctx).run_callbacks() {
@@ -239,6 +690,45 @@ impl<'a> DataSrc<'a> {
         self.run_data(data, 0, len)
     }
 
+    // Run the bars before `from` and prepare the data to replay the bars after it one by one.
+    // Return the count of all the bars.
+    pub fn replay_from(
+        &mut self,
+        data: &Vec<(&'static str, AnySeries)>,
+        from: usize,
+        syminfo: Option<Rc<SymbolInfo>>,
+    ) -> Result<usize, PineRuntimeError> {
+        let len = parse_datalen(data, &self.input_names)?;
+        let from = from.min(len);
+        self.reset_vars();
+        let main_ctx = downcast_ctx(self.context.as_mut());
+        main_ctx.update_data_range((Some(0), Some(from as i32)));
+        if let Some(syminfo) = syminfo {
+            main_ctx.set_syminfo(syminfo);
+        }
+        self.has_run = true;
+
+        let name_indexs = self.gen_name_indexs(data);
+        self.prepare_data(data, &name_indexs);
+        // The output information is collected by the first bar, so nothing runs for no bars.
+        if from > 0 {
+            self.run_bars(data, &name_indexs, 0, 0, from)?;
+        }
+        Ok(len)
+    }
+
+    // Run the bar at the index after the replayed bars, the data is the data of `replay_from`.
+    pub fn replay_step(
+        &mut self,
+        data: &Vec<(&'static str, AnySeries)>,
+        index: usize,
+    ) -> Result<(), PineRuntimeError> {
+        let main_ctx = downcast_ctx(self.context.as_mut());
+        main_ctx.update_data_range((Some(index as i32), Some(index as i32 + 1)));
+        let name_indexs = self.gen_name_indexs(data);
+        self.run_bars(data, &name_indexs, 0, index as i64, 1)
+    }
+
     pub fn update(
         &mut self,
         data: &Vec<(&'static str, AnySeries)>,
@@ -355,4 +845,39 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn recycle_sub_contexts_test() {
+        let mut blk = crate::parse_ast("f(x) =>\n    y = x * 2\n    y[1]\nm = f(close)\n").unwrap();
+        let typemap = vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))];
+        assert!(SyntaxParser::new_with_libvars(&typemap)
+            .parse_blk(&mut blk)
+            .is_ok());
+
+        let mut datasrc = DataSrc::new(
+            &blk,
+            vec![],
+            vec![("close", AnySeriesType::Float)],
+            &MyCallback,
+        );
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64)]),
+        )];
+        let result = |datasrc: &mut DataSrc| {
+            let ctx = downcast_ctx(datasrc.context.as_mut());
+            let subctx = ctx.get_sub_context(0).unwrap().as_ref() as *const _ as *const u8 as usize;
+            let val = ctx.move_var(VarIndex::new(1, 0)).unwrap();
+            let ser: RefData<Series<Float>> = Series::implicity_from(val).unwrap();
+            (subctx, ser.get_history().clone())
+        };
+
+        assert_eq!(datasrc.run(&data, None), Ok(()));
+        let (subctx, history) = result(&mut datasrc);
+        assert_eq!(history, vec![None, Some(2f64), Some(4f64)]);
+
+        // The rerun reuses the function context and starts it with the empty history.
+        assert_eq!(datasrc.run(&data, None), Ok(()));
+        assert_eq!(result(&mut datasrc), (subctx, history));
+    }
 }
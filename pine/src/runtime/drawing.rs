@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+// The default count limit of every kind of drawings if the script declaration does not specify it.
+pub const DEFAULT_MAX_DRAWINGS: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawingKind {
+    Line = 0,
+    Label = 1,
+    Box = 2,
+    Polyline = 3,
+}
+
+// The drawing object(line, label, box, polyline) that can be deleted by the garbage collection.
+pub trait Drawing {
+    fn is_deleted(&self) -> bool;
+
+    fn delete(&self);
+}
+
+impl<T> Drawing for RefCell<Option<T>> {
+    fn is_deleted(&self) -> bool {
+        self.borrow().is_none()
+    }
+
+    fn delete(&self) {
+        self.replace(None);
+    }
+}
+
+// The count of the alive drawings of every kind.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DrawingStats {
+    pub lines: usize,
+    pub labels: usize,
+    pub boxes: usize,
+    pub polylines: usize,
+}
+
+#[derive(Default)]
+struct DrawingQueue {
+    // The committed drawings ordered by the creation time.
+    items: VecDeque<Rc<dyn Drawing>>,
+    // The drawings created by the current bar.
+    pending: Vec<Rc<dyn Drawing>>,
+    // The count of drawings added by the last commit, they are removed when the bar rolls back.
+    last_commit: usize,
+}
+
+impl DrawingQueue {
+    // Return true if any drawing is created or deleted since the last commit.
+    fn commit(&mut self, limit: usize) -> bool {
+        let count = self.items.len();
+        self.items.retain(|d| !d.is_deleted());
+        let mut changed = self.items.len() != count || !self.pending.is_empty();
+        self.pending.retain(|d| !d.is_deleted());
+        self.last_commit = self.pending.len();
+        self.items.extend(self.pending.drain(..));
+
+        // Delete the oldest drawings if the count exceeds the limit.
+        while self.items.len() > limit {
+            if let Some(d) = self.items.pop_front() {
+                d.delete();
+                changed = true;
+            }
+        }
+        self.last_commit = self.last_commit.min(self.items.len());
+        changed
+    }
+
+    fn roll_back(&mut self) {
+        self.pending.clear();
+        let len = self.items.len() - self.last_commit;
+        self.items.truncate(len);
+        self.last_commit = 0;
+    }
+
+    fn count(&self) -> usize {
+        self.items
+            .iter()
+            .chain(self.pending.iter())
+            .filter(|d| !d.is_deleted())
+            .count()
+    }
+}
+
+// Keep track of the drawings created by the script and delete the oldest ones when
+// the count of any kind exceeds `max_lines_count`, `max_labels_count`, `max_boxes_count` or
+// `max_polylines_count`.
+#[derive(Default)]
+pub struct DrawingRegistry {
+    queues: [DrawingQueue; 4],
+}
+
+impl DrawingRegistry {
+    pub fn new() -> DrawingRegistry {
+        DrawingRegistry::default()
+    }
+
+    pub fn add(&mut self, kind: DrawingKind, item: Rc<dyn Drawing>) {
+        self.queues[kind as usize].pending.push(item);
+    }
+
+    // Commit the drawings of the current bar, `limits` are the max counts of lines, labels, boxes
+    // and polylines. Return true if any drawing is created or deleted by the bar.
+    pub fn commit(&mut self, limits: [Option<i64>; 4]) -> bool {
+        let mut changed = false;
+        for (queue, limit) in self.queues.iter_mut().zip(limits.iter()) {
+            changed |= queue.commit(limit.unwrap_or(DEFAULT_MAX_DRAWINGS).max(1) as usize);
+        }
+        changed
+    }
+
+    pub fn roll_back(&mut self) {
+        for queue in self.queues.iter_mut() {
+            queue.roll_back();
+        }
+    }
+
+    pub fn get_stats(&self) -> DrawingStats {
+        DrawingStats {
+            lines: self.queues[DrawingKind::Line as usize].count(),
+            labels: self.queues[DrawingKind::Label as usize].count(),
+            boxes: self.queues[DrawingKind::Box as usize].count(),
+            polylines: self.queues[DrawingKind::Polyline as usize].count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_item(v: i32) -> Rc<RefCell<Option<i32>>> {
+        Rc::new(RefCell::new(Some(v)))
+    }
+
+    #[test]
+    fn drawing_gc_test() {
+        let mut registry = DrawingRegistry::new();
+        let items: Vec<_> = (0..3).map(new_item).collect();
+        for item in items.iter() {
+            registry.add(DrawingKind::Line, item.clone());
+        }
+        registry.add(DrawingKind::Label, new_item(10));
+        assert_eq!(
+            registry.get_stats(),
+            DrawingStats {
+                lines: 3,
+                labels: 1,
+                boxes: 0,
+                polylines: 0
+            }
+        );
+
+        assert!(registry.commit([Some(2), None, None, None]));
+        assert_eq!(items[0].borrow().clone(), None);
+        assert_eq!(items[1].borrow().clone(), Some(1));
+        assert_eq!(items[2].borrow().clone(), Some(2));
+        assert_eq!(registry.get_stats().lines, 2);
+
+        // The deleted drawings are not counted.
+        items[1].delete();
+        assert_eq!(registry.get_stats().lines, 1);
+
+        // Nothing is changed except the deleted drawing.
+        assert!(registry.commit([Some(2), None, None, None]));
+        assert!(!registry.commit([Some(2), None, None, None]));
+
+        let item = new_item(3);
+        registry.add(DrawingKind::Line, item.clone());
+        assert!(registry.commit([Some(2), None, None, None]));
+        assert_eq!(items[2].borrow().clone(), Some(2));
+        assert_eq!(registry.get_stats().lines, 2);
+
+        // The drawings of the last bar are removed by rolling back.
+        registry.roll_back();
+        assert_eq!(registry.get_stats().lines, 1);
+        assert_eq!(item.borrow().clone(), Some(3));
+    }
+}
@@ -3,6 +3,7 @@ use crate::ast::input::StrRange;
 use crate::ast::state::PineInputError;
 use crate::helper::str_replace;
 use crate::runtime::context::PineRuntimeError;
+use crate::syntax::lint::{LintCode, LintWarning};
 use crate::types::error::RuntimeErr;
 use std::collections::HashMap;
 use std::string::ToString;
@@ -47,6 +48,7 @@ static ERROR_MAP: &[(&'static str, &'static str)] = &[
     ("ExpReturnNa", "The code block in expression that return na is invalid."),
     ("TypeMismatch", "The types returned from different branches are not compatible."),
     ("ForRangeIndexNotInt", "The index in for-range expression must be integer."),
+    ("ForInNotIterable", "The expression in for-in statement must be an array."),
     ("UnaryTypeNotNum", "The destination type for unary expression must be numeric."),
     ("BinaryTypeNotNum", "The destination types for binary expression must be numeric or string."),
     ("BoolExpTypeNotBool", "The destination types used in bool expression must be convertible to bool."),
@@ -54,6 +56,15 @@ static ERROR_MAP: &[(&'static str, &'static str)] = &[
     ("BreakNotInForStmt", "The break statement can only be used in a for-range statement."),
     ("ContinueNotInForStmt", "The continue statement can only be used in a for-range statement."),
     ("NonRecongnizeStmt", "This statement is invalid."),
+    ("ScriptDeclNotTopLevel", "The script declaration(study, indicator, strategy) must be a statement in the global scope."),
+    ("ScriptDeclDuplicate", "The script declaration(study, indicator, strategy) can only be called once."),
+    ("ArgQualifierNotMatch", "The parameter {} requires a {} value, but the argument is {}."),
+    ("ShadowBuiltinVar", "This variable shadows the built-in variable or function with the same name."),
+    ("TypeArgNotMatch", "The type argument is not supported by this function."),
+    ("BinaryOpTypeNotMatch", "The operator {} can't be applied to the operands of type {} and {}."),
+    ("TupleVarAssign", "The tuple can't be reassigned with `:=`. Declare the tuple with `[a, b] = ...` and reassign the variables one by one."),
+    ("InvalidVarAssignTarget", "Only a variable name can be reassigned with `:=`, the fields and history references are not assignable."),
+    ("InputDefvalNotInOptions", "The default value of the input must be one of the options."),
 
     ("NotValidParam", "The parameters are invalid."),
     ("NotSupportOperator", "The operation is not available now."),
@@ -68,7 +79,19 @@ static ERROR_MAP: &[(&'static str, &'static str)] = &[
     ("UnknownRuntimeErr", "Unknown runtime error."),
     ("Continue", "Continue statement."),
     ("Break", "Break statement."),
-    ("ForRangeIndexIsNA", "The index used in for-range statement can't be na.")
+    ("ForRangeIndexIsNA", "The index used in for-range statement can't be na."),
+    ("Cancelled", "The execution is cancelled after the bar {}."),
+    ("NegativeHistoryIndex", "The history reference index {} is negative, the index must be 0 or greater."),
+
+    ("UnusedVariable", "The variable {} is declared but never used."),
+    ("UnreachableCode", "The code after break or continue is never executed."),
+    ("ConstantCondition", "The condition is constant, so the same branch is always selected."),
+    ("SecurityLookahead", "The security call with lookahead gets the future data on the historical bars, request the previous value like close[1] to avoid repainting."),
+    ("FutureOffset", "The negative offset references the future bars, so the values are repainted."),
+    ("CalcOnEveryTick", "The strategy is calculated on every tick in the realtime but only once per bar on the history, so the results are repainted."),
+    ("RealtimeVariable", "The variable {} has different values on the historical and realtime bars."),
+    ("NaComparison", "The comparison with na is always false, use na() to test the value."),
+    ("V4FunctionName", "The function {} is renamed to {} in v5."),
 ];
 
 pub struct ErrorFormater {
@@ -135,6 +158,7 @@ impl ErrorFormater {
             PineErrorKind::ForRangeIndexNotInt => {
                 String::from(self.error_map["ForRangeIndexNotInt"])
             }
+            PineErrorKind::ForInNotIterable => String::from(self.error_map["ForInNotIterable"]),
             PineErrorKind::UnaryTypeNotNum => String::from(self.error_map["UnaryTypeNotNum"]),
             PineErrorKind::BinaryTypeNotNum => String::from(self.error_map["BinaryTypeNotNum"]),
             PineErrorKind::BoolExpTypeNotBool => String::from(self.error_map["BoolExpTypeNotBool"]),
@@ -144,6 +168,33 @@ impl ErrorFormater {
                 String::from(self.error_map["ContinueNotInForStmt"])
             }
             PineErrorKind::NonRecongnizeStmt => String::from(self.error_map["NonRecongnizeStmt"]),
+            PineErrorKind::ScriptDeclNotTopLevel => {
+                String::from(self.error_map["ScriptDeclNotTopLevel"])
+            }
+            PineErrorKind::ScriptDeclDuplicate => {
+                String::from(self.error_map["ScriptDeclDuplicate"])
+            }
+            PineErrorKind::ArgQualifierNotMatch {
+                param,
+                qualifier,
+                found,
+            } => str_replace(
+                self.error_map["ArgQualifierNotMatch"],
+                vec![param, qualifier.to_string(), found.to_string()],
+            ),
+            PineErrorKind::ShadowBuiltinVar => String::from(self.error_map["ShadowBuiltinVar"]),
+            PineErrorKind::TypeArgNotMatch => String::from(self.error_map["TypeArgNotMatch"]),
+            PineErrorKind::BinaryOpTypeNotMatch { op, left, right } => str_replace(
+                self.error_map["BinaryOpTypeNotMatch"],
+                vec![op, left, right],
+            ),
+            PineErrorKind::TupleVarAssign => String::from(self.error_map["TupleVarAssign"]),
+            PineErrorKind::InvalidVarAssignTarget => {
+                String::from(self.error_map["InvalidVarAssignTarget"])
+            }
+            PineErrorKind::InputDefvalNotInOptions => {
+                String::from(self.error_map["InputDefvalNotInOptions"])
+            }
         }
     }
 
@@ -171,6 +222,33 @@ impl ErrorFormater {
             RuntimeErr::Continue => String::from(self.error_map["Continue"]),
             RuntimeErr::Break => String::from(self.error_map["Break"]),
             RuntimeErr::ForRangeIndexIsNA => String::from(self.error_map["ForRangeIndexIsNA"]),
+            RuntimeErr::Cancelled(index) => {
+                str_replace(self.error_map["Cancelled"], vec![index.to_string()])
+            }
+            RuntimeErr::NegativeHistoryIndex(index) => str_replace(
+                self.error_map["NegativeHistoryIndex"],
+                vec![index.to_string()],
+            ),
+        }
+    }
+
+    pub fn format_lint(&self, code: LintCode) -> String {
+        match code {
+            LintCode::UnusedVariable(name) => {
+                str_replace(self.error_map["UnusedVariable"], vec![name])
+            }
+            LintCode::UnreachableCode => String::from(self.error_map["UnreachableCode"]),
+            LintCode::ConstantCondition => String::from(self.error_map["ConstantCondition"]),
+            LintCode::SecurityLookahead => String::from(self.error_map["SecurityLookahead"]),
+            LintCode::FutureOffset => String::from(self.error_map["FutureOffset"]),
+            LintCode::CalcOnEveryTick => String::from(self.error_map["CalcOnEveryTick"]),
+            LintCode::RealtimeVariable(name) => {
+                str_replace(self.error_map["RealtimeVariable"], vec![name])
+            }
+            LintCode::NaComparison => String::from(self.error_map["NaComparison"]),
+            LintCode::V4FunctionName(v4, v5) => {
+                str_replace(self.error_map["V4FunctionName"], vec![v4, v5])
+            }
         }
     }
 }
@@ -179,19 +257,30 @@ impl ErrorFormater {
 pub struct PineFormatError {
     pub message: String,
     pub range: StrRange,
+    pub related_ranges: Vec<StrRange>,
 }
 
 impl PineFormatError {
     pub fn from_input_error(formatter: &ErrorFormater, input_err: PineInputError) -> Self {
         PineFormatError {
             range: input_err.range,
+            related_ranges: input_err.related_ranges,
             message: formatter.format_error(input_err.code),
         }
     }
 
+    pub fn from_lint_warning(formatter: &ErrorFormater, warning: LintWarning) -> Self {
+        PineFormatError {
+            range: warning.range,
+            related_ranges: vec![],
+            message: formatter.format_lint(warning.code),
+        }
+    }
+
     pub fn from_runtime_error(formatter: &ErrorFormater, runtime_err: PineRuntimeError) -> Self {
         PineFormatError {
             range: runtime_err.range,
+            related_ranges: vec![],
             message: formatter.format_runtime_error(runtime_err.code),
         }
     }
@@ -1,9 +1,11 @@
 use super::context::{
-    Ctx, PineRuntimeError, RVRunner, Runner, RunnerForAssign, RunnerForFunc, RunnerForObj,
+    downcast_ctx, Ctx, PineRuntimeError, RVRunner, Runner, RunnerForAssign, RunnerForFunc,
+    RunnerForObj,
 };
 use super::instance_caller::*;
 use super::op::{binary_op_run, unary_op_run};
 use super::runtime_convert::convert;
+use crate::ast::input::StrRange;
 use crate::ast::num::Numeral;
 pub use crate::ast::stat_expr_types::{
     Condition, DataType, Exp, FunctionCall, PrefixExp, RVVarName, RefCall, Statement, TypeCast,
@@ -25,7 +27,7 @@ impl<'a> Runner<'a> for Exp<'a> {
             Exp::Num(Numeral::Float(f)) => Ok(PineRef::new_box(Some(f.value))),
             Exp::Num(Numeral::Int(n)) => Ok(PineRef::new_box(Some(n.value))),
             Exp::Str(ref s) => Ok(PineRef::new_rc(String::from(s.value.clone()))),
-            Exp::Color(s) => Ok(PineRef::new_box(Color(s.value))),
+            Exp::Color(s) => Ok(PineRef::new_box(Color::new(s.value))),
             Exp::VarName(s) => Ok(PineRef::new_box(PineVar(s.name.value))),
             Exp::Tuple(ref tuple) => {
                 let mut col: Vec<PineRef<'a>> = vec![];
@@ -41,6 +43,7 @@ impl<'a> Runner<'a> for Exp<'a> {
             Exp::Condition(ref cond) => cond.run(_context),
             Exp::Ite(ref ite) => ite.run(_context),
             Exp::ForRange(ref for_range) => for_range.run(_context),
+            Exp::ForIn(ref for_in) => for_in.run(_context),
             Exp::Assignment(ref assign) => assign.run(_context),
             Exp::VarAssignment(ref assign) => assign.run(_context),
             Exp::UnaryExp(ref node) => unary_op_run(&node, _context),
@@ -103,10 +106,15 @@ impl<'a> RunnerForAssign<'a> for Exp<'a> {
             | Exp::BinaryExp(_)
             | Exp::RefCall(_) => self.rv_run(context),
             Exp::VarName(_) => match self.rv_run(context) {
-                // The line and label type should not to copy the origin object
+                // The line, label and polyline type should not to copy the origin object
                 // other object should copy the origin object for assignment
                 Ok(v) => match v.get_type() {
-                    (FirstType::Line, _) | (FirstType::Label, _) => Ok(v),
+                    (FirstType::Line, _) | (FirstType::Label, _) | (FirstType::Polyline, _) => {
+                        Ok(v)
+                    }
+                    // The series is shared with the variable, it will be copied only if it is
+                    // stored to the new variable, see process_assign_val.
+                    (_, SecondType::Series) => Ok(v),
                     _ => Ok(v.copy_inner()),
                 },
                 Err(e) => Err(e),
@@ -181,6 +189,7 @@ impl<'a> RunnerForObj<'a> for Exp<'a> {
                     }
                 }
             },
+            Exp::PrefixExp(prefix) => prefix.run_prop(context, true),
             _ => self.rv_run(context),
         }
     }
@@ -239,32 +248,50 @@ impl<'a> Runner<'a> for TypeCast<'a> {
     }
 }
 
-impl<'a> Runner<'a> for PrefixExp<'a> {
-    fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
-        let var = self.left_exp.run_for_obj(context)?;
-        // let var = context.move_var(self.var_index).unwrap();
-        match var.get_type() {
-            (FirstType::Object, SecondType::Simple) => {
-                let object = downcast_pf::<Object>(var).unwrap();
-                let subobj = object.get(context, self.right_name.value).unwrap();
-                Ok(subobj)
-            }
-            (FirstType::CallableObject, SecondType::Simple) => {
-                let object = downcast_pf::<CallableObject>(var).unwrap();
-                let subobj = object.get(context, self.right_name.value).unwrap();
-                Ok(subobj)
+// Get the property of the object, `for_obj` is true when the property is the object of the next
+// prefix.
+fn get_prop<'a>(
+    context: &mut dyn Ctx<'a>,
+    var: PineRef<'a>,
+    name: &'a str,
+    for_obj: bool,
+) -> Option<PineRef<'a>> {
+    macro_rules! get_prop {
+        ($obj: expr) => {
+            if for_obj {
+                $obj.get_obj(context, name).unwrap()
+            } else {
+                $obj.get(context, name).unwrap()
             }
-            (FirstType::SimpleCallableObject, SecondType::Simple) => {
-                let object = downcast_pf::<SimpleCallableObject>(var).unwrap();
-                let subobj = object.get(context, self.right_name.value).unwrap();
-                Ok(subobj)
-            }
-            (FirstType::CallableObjectEvaluate, SecondType::Simple) => {
-                let object = downcast_pf::<CallObjEval>(var).unwrap();
-                let subobj = object.get(context, self.right_name.value).unwrap();
-                Ok(subobj)
-            }
-            _ => Err(PineRuntimeError::new(
+        };
+    }
+    match var.get_type() {
+        (FirstType::Object, SecondType::Simple) => {
+            Some(get_prop!(downcast_pf::<Object>(var).unwrap()))
+        }
+        (FirstType::CallableObject, SecondType::Simple) => {
+            Some(get_prop!(downcast_pf::<CallableObject>(var).unwrap()))
+        }
+        (FirstType::SimpleCallableObject, SecondType::Simple) => {
+            Some(get_prop!(downcast_pf::<SimpleCallableObject>(var).unwrap()))
+        }
+        (FirstType::CallableObjectEvaluate, SecondType::Simple) => {
+            Some(get_prop!(downcast_pf::<CallObjEval>(var).unwrap()))
+        }
+        _ => None,
+    }
+}
+
+impl<'a> PrefixExp<'a> {
+    fn run_prop(
+        &'a self,
+        context: &mut dyn Ctx<'a>,
+        for_obj: bool,
+    ) -> Result<PineRef<'a>, PineRuntimeError> {
+        let var = self.left_exp.run_for_obj(context)?;
+        match get_prop(context, var, self.right_name.value, for_obj) {
+            Some(subobj) => Ok(subobj),
+            None => Err(PineRuntimeError::new(
                 RuntimeErr::UnknownRuntimeErr,
                 self.range,
             )),
@@ -272,6 +299,14 @@ impl<'a> Runner<'a> for PrefixExp<'a> {
     }
 }
 
+impl<'a> Runner<'a> for PrefixExp<'a> {
+    fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
+        self.run_prop(context, false)
+    }
+}
+
+// Only the taken branch is evaluated like Pine, so the function calls of the other branch
+// don't update their history and can't raise errors.
 impl<'a> Runner<'a> for Condition<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         let cond = self.cond.rv_run(context)?;
@@ -303,9 +338,20 @@ impl<'a> RunnerForAssign<'a> for Condition<'a> {
     }
 }
 
+// How the negative history references like `close[-1]` are handled. The references beyond the
+// available history always get na like Pine, e.g. `close[1]` on the first bar.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HistoryIndexCheck {
+    // The negative reference gets na.
+    #[default]
+    Na,
+    // The negative reference raises the `RuntimeErr::NegativeHistoryIndex` error.
+    Error,
+}
+
 fn get_slice<'a, D>(
-    // context: &mut dyn Ctx<'a>,
-    // name: &'a str,
+    context: &mut dyn Ctx<'a>,
+    range: StrRange,
     obj: PineRef<'a>,
     arg: PineRef<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr>
@@ -313,14 +359,26 @@ where
     D: Default + PineType<'a> + PineStaticType + PartialEq + PineFrom<'a, D> + Debug + Clone + 'a,
 {
     let s: RefData<Series<D>> = Series::implicity_from(obj)?;
-    let arg_type = arg.get_type();
     let i = Int::implicity_from(arg)?;
     match *i {
         None => Err(RuntimeErr::UnknownRuntimeErr),
+        Some(i) if i < 0 => {
+            let ctx = downcast_ctx(context);
+            if let Some(profiler) = ctx.get_profiler() {
+                profiler.record_history_index(range, true);
+            }
+            match ctx.get_history_index_check() {
+                HistoryIndexCheck::Na => Ok(PineRef::new_rc(Series::from(D::default()))),
+                HistoryIndexCheck::Error => Err(RuntimeErr::NegativeHistoryIndex(i)),
+            }
+        }
         Some(i) => {
-            let res = PineRef::new_rc(s.index(i as usize)?);
-            // context.update_var(name, s.into_pf());
-            Ok(res)
+            if i as usize > s.get_history().len() {
+                if let Some(profiler) = downcast_ctx(context).get_profiler() {
+                    profiler.record_history_index(range, false);
+                }
+            }
+            Ok(PineRef::new_rc(s.index(i as usize)?))
         }
     }
 }
@@ -340,18 +398,22 @@ impl<'a> Runner<'a> for RefCall<'a> {
 
         // let var = var_opt.unwrap();
         let result = match var.get_type() {
-            (FirstType::Int, _) => get_slice::<Int>(var, arg),
-            (FirstType::Float, _) => get_slice::<Float>(var, arg),
-            (FirstType::Bool, _) => get_slice::<Bool>(var, arg),
-            (FirstType::Color, _) => get_slice::<Color>(var, arg),
-            (FirstType::String, _) => get_slice::<String>(var, arg),
+            (FirstType::Int, _) => get_slice::<Int>(context, self.range, var, arg),
+            (FirstType::Float, _) => get_slice::<Float>(context, self.range, var, arg),
+            (FirstType::Bool, _) => get_slice::<Bool>(context, self.range, var, arg),
+            (FirstType::Color, _) => get_slice::<Color>(context, self.range, var, arg),
+            (FirstType::String, _) => get_slice::<String>(context, self.range, var, arg),
             (FirstType::Line, _) => {
                 use crate::libs::line::PerLineItem;
-                get_slice::<PerLineItem>(var, arg)
+                get_slice::<PerLineItem>(context, self.range, var, arg)
             }
             (FirstType::Label, _) => {
                 use crate::libs::label::PerLabelItem;
-                get_slice::<PerLabelItem>(var, arg)
+                get_slice::<PerLabelItem>(context, self.range, var, arg)
+            }
+            (FirstType::Polyline, _) => {
+                use crate::libs::polyline::PerPolylineItem;
+                get_slice::<PerPolylineItem>(context, self.range, var, arg)
             }
             _ => Err(RuntimeErr::NotSupportOperator),
         };
@@ -540,7 +602,7 @@ mod tests {
             )),
             String::from("hello"),
         );
-        simple_exp(Exp::Color(ColorNode::from_str("#12")), Color("#12"));
+        simple_exp(Exp::Color(ColorNode::from_str("#12")), Color::new("#12"));
         simple_exp(
             Exp::VarName(RVVarName::new_no_range("name")),
             PineVar("name"),
@@ -585,7 +647,7 @@ mod tests {
             )),
             String::from("hello"),
         );
-        simple_rv_exp(Exp::Color(ColorNode::from_str("#12")), Color("#12"));
+        simple_rv_exp(Exp::Color(ColorNode::from_str("#12")), Color::new("#12"));
 
         let mut name = RVVarName::new_no_range("name");
         name.var_index = VarIndex::new(0, 0);
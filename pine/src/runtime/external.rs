@@ -0,0 +1,114 @@
+use super::data_src::{DataRequest, RequestData, RequestDesc, RequestKind};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// The id of the economic series of `request.economic`, e.g. `ECONOMICS:USGDP`.
+pub fn economic_id(country_code: &str, field: &str) -> String {
+    format!("ECONOMICS:{}{}", country_code, field)
+}
+
+// The id of the series that the request asks the registry for, the other requests are only
+// sent to the data request of the host.
+pub fn series_id(desc: &RequestDesc) -> Option<String> {
+    match desc.kind {
+        RequestKind::Economic => Some(economic_id(
+            &desc.symbol,
+            desc.field.as_deref().unwrap_or(""),
+        )),
+        RequestKind::External => Some(desc.symbol.clone()),
+        _ => None,
+    }
+}
+
+// The named series registered by the host, like the economic indicators or the funding rates.
+// The scripts request them by `request.economic` or `request.external`, and the values are
+// aligned to the chart bars like the other requested data.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalSeries {
+    series: HashMap<(String, Option<String>), Rc<RequestData>>,
+}
+
+impl ExternalSeries {
+    pub fn new() -> ExternalSeries {
+        ExternalSeries::default()
+    }
+
+    // Register the series of the id, the series without the timeframe serves the requests of
+    // all the timeframes that have no series registered.
+    pub fn register(&mut self, id: &str, timeframe: Option<&str>, data: RequestData) {
+        self.series.insert(
+            (String::from(id), timeframe.map(String::from)),
+            Rc::new(data),
+        );
+    }
+
+    pub fn get(&self, desc: &RequestDesc) -> Option<Rc<RequestData>> {
+        let id = series_id(desc)?;
+        match self.series.get(&(id.clone(), desc.timeframe.clone())) {
+            Some(data) => Some(Rc::clone(data)),
+            None => self.series.get(&(id, None)).cloned(),
+        }
+    }
+}
+
+// The registry can be used as the data request of the host directly.
+impl DataRequest for ExternalSeries {
+    fn request(&self, desc: &RequestDesc) -> Option<RequestData> {
+        self.get(desc).map(|data| (*data).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_desc(
+        kind: RequestKind,
+        symbol: &str,
+        field: Option<&str>,
+        timeframe: Option<&str>,
+    ) -> RequestDesc {
+        RequestDesc {
+            kind,
+            symbol: String::from(symbol),
+            timeframe: timeframe.map(String::from),
+            field: field.map(String::from),
+            gaps: false,
+        }
+    }
+
+    #[test]
+    fn external_series_test() {
+        let mut registry = ExternalSeries::new();
+        let gdp = RequestData::new(vec![10], vec![Some(1f64)]);
+        let funding = RequestData::new(vec![10], vec![Some(0.01f64)]);
+        let funding_8h = RequestData::new(vec![10], vec![Some(0.08f64)]);
+        registry.register("ECONOMICS:USGDP", None, gdp.clone());
+        registry.register("BINANCE:BTCUSDT.FUNDING", None, funding.clone());
+        registry.register("BINANCE:BTCUSDT.FUNDING", Some("480"), funding_8h.clone());
+
+        let desc = gen_desc(RequestKind::Economic, "US", Some("GDP"), None);
+        assert_eq!(registry.request(&desc), Some(gdp));
+        let desc = gen_desc(RequestKind::Economic, "EU", Some("GDP"), None);
+        assert_eq!(registry.request(&desc), None);
+
+        let desc = gen_desc(
+            RequestKind::External,
+            "BINANCE:BTCUSDT.FUNDING",
+            None,
+            Some("480"),
+        );
+        assert_eq!(registry.request(&desc), Some(funding_8h));
+        let desc = gen_desc(
+            RequestKind::External,
+            "BINANCE:BTCUSDT.FUNDING",
+            None,
+            Some("D"),
+        );
+        assert_eq!(registry.request(&desc), Some(funding));
+
+        // The financial data is not in the registry.
+        let desc = gen_desc(RequestKind::Financial, "ECONOMICS", Some("USGDP"), None);
+        assert_eq!(registry.request(&desc), None);
+    }
+}
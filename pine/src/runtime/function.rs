@@ -1,7 +1,8 @@
 use super::context::{downcast_ctx, Ctx, PineRuntimeError, Runner};
-use super::statement::process_assign_val;
+use super::statement::{implicity_cast_val, process_assign_val};
 use crate::ast::input::StrRange;
 use crate::ast::stat_expr_types::FunctionDef;
+use crate::ast::syntax_type::TypeQualifier;
 use crate::types::{
     Category, ComplexType, DataType, PineFrom, PineRef, PineStaticType, PineType, RuntimeErr,
     SecondType,
@@ -45,7 +46,7 @@ impl<'a> Function<'a> {
         &self,
         context: &mut dyn Ctx<'a>,
         pos_args: Vec<PineRef<'a>>,
-        dict_args: Vec<(&'a str, PineRef<'a>)>,
+        dict_args: Vec<(usize, PineRef<'a>)>,
         range: StrRange,
     ) -> Result<PineRef<'a>, PineRuntimeError> {
         if pos_args.len() > self.def.params.len() {
@@ -68,6 +69,17 @@ impl<'a> Function<'a> {
         // let mut new_context = Context::new(Some(context), ContextType::FuncDefBlock);
         for (i, v) in pos_args.into_iter().enumerate() {
             // context.create_var(k, v);
+            // Cast the argument to the annotated parameter type.
+            let v = match &self.def.param_types[i] {
+                Some(param_type) => {
+                    let to_series = param_type.qualifier == Some(TypeQualifier::Series);
+                    match implicity_cast_val(v, &param_type.data_type, to_series) {
+                        Ok(v) => v,
+                        Err(err) => return Err(PineRuntimeError::new(err, range)),
+                    }
+                }
+                None => v,
+            };
             if let Err(err) = process_assign_val(v, downcast_ctx(context), i as i32, None) {
                 return Err(PineRuntimeError::new(err, range));
             }
@@ -0,0 +1,105 @@
+// The events pushed by the host while the user interacts with the chart, e.g. the input changed in
+// the settings or the point picked on the chart for the interactive `input.time` and
+// `input.price`. The runner applies the event to the input values and recalculates the bars.
+use super::output::InputVal;
+use crate::helper::err_msgs::*;
+use crate::helper::str_replace;
+use crate::types::RuntimeErr;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum HostEvent {
+    // The value of the input at the index of the input settings is changed.
+    InputChanged {
+        index: usize,
+        value: InputVal,
+    },
+    // The point picked on the chart for the anchored drawings, the time is set to the time input
+    // and the price is set to the price input if their indexes are given.
+    PointSelected {
+        time_input: Option<usize>,
+        price_input: Option<usize>,
+        time: i64,
+        price: f64,
+    },
+}
+
+impl HostEvent {
+    // The indexes and the new values of the inputs changed by the event.
+    pub fn input_changes(&self) -> Vec<(usize, InputVal)> {
+        match self {
+            HostEvent::InputChanged { index, value } => vec![(*index, value.clone())],
+            HostEvent::PointSelected {
+                time_input,
+                price_input,
+                time,
+                price,
+            } => vec![
+                time_input.map(|i| (i, InputVal::Int(*time))),
+                price_input.map(|i| (i, InputVal::Float(*price))),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        }
+    }
+}
+
+// Apply the event to the input values of the script with `count` inputs, the None values are the
+// default values. Return true if any input value is changed.
+pub fn apply_event(
+    inputs: &mut Vec<Option<InputVal>>,
+    event: &HostEvent,
+    count: usize,
+) -> Result<bool, RuntimeErr> {
+    let changes = event.input_changes();
+    if let Some((index, _)) = changes.iter().find(|(index, _)| *index >= count) {
+        return Err(RuntimeErr::InvalidParameters(str_replace(
+            INPUT_INDEX,
+            vec![index.to_string(), count.to_string()],
+        )));
+    }
+    inputs.resize(count, None);
+    let mut changed = false;
+    for (index, value) in changes {
+        if inputs[index].as_ref() != Some(&value) {
+            inputs[index] = Some(value);
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_event_test() {
+        let mut inputs = vec![];
+        let event = HostEvent::PointSelected {
+            time_input: Some(0),
+            price_input: Some(2),
+            time: 100,
+            price: 1.5f64,
+        };
+        assert_eq!(apply_event(&mut inputs, &event, 3), Ok(true));
+        assert_eq!(
+            inputs,
+            vec![
+                Some(InputVal::Int(100)),
+                None,
+                Some(InputVal::Float(1.5f64))
+            ]
+        );
+        assert_eq!(apply_event(&mut inputs, &event, 3), Ok(false));
+
+        let event = HostEvent::InputChanged {
+            index: 1,
+            value: InputVal::Bool(true),
+        };
+        assert_eq!(apply_event(&mut inputs, &event, 3), Ok(true));
+        assert_eq!(inputs[1], Some(InputVal::Bool(true)));
+        assert!(apply_event(&mut inputs, &event, 1).is_err());
+        assert_eq!(inputs.len(), 3);
+    }
+}
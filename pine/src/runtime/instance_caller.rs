@@ -52,7 +52,7 @@ pub fn call_func_factory<'a>(
     func_id: i32,
     s: PineRef<'a>,
     pos_args: Vec<PineRef<'a>>,
-    dict_args: Vec<(&'a str, PineRef<'a>)>,
+    dict_args: Vec<(usize, PineRef<'a>)>,
     func_type: FunctionType<'a>,
 ) -> Result<PineRef<'a>, RuntimeErr> {
     let mut opt_instance = context.move_fun_instance(func_id);
@@ -85,7 +85,7 @@ pub fn call_func_factory<'a>(
     }
     let mut callable = downcast_pf::<Callable>(opt_instance.unwrap()).unwrap();
 
-    let result = callable.call(context, pos_args, dict_args, func_type);
+    let result = callable.call_with_indexs(context, pos_args, dict_args, func_type);
 
     context.create_fun_instance(func_id, RefData::clone(&callable).into_pf());
     context.create_runnable(callable.into_rc());
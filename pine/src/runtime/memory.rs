@@ -0,0 +1,382 @@
+// The introspection of the live runtime objects, and the leak check that warns when an object
+// keeps growing across the bars, e.g. the array that is extended on every bar and never shrinks.
+use super::drawing::DrawingStats;
+use crate::ast::input::StrRange;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ObjectKind {
+    // The history buffer of the series variable.
+    Series,
+    Array,
+    Line,
+    Label,
+    Box,
+}
+
+// The ids of the sub contexts from the main context to the context of the variable, and the id of
+// the variable in that context.
+pub type ObjectId = (Vec<i32>, i32);
+
+// One live object held by the runtime.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveObject {
+    pub kind: ObjectKind,
+    // The variable name, or the kind name like `line` for the drawings.
+    pub name: String,
+    // None for the drawings that are counted by the kind.
+    pub id: Option<ObjectId>,
+    // The count of the history values, the array elements or the alive drawings.
+    pub len: usize,
+    // The bytes of the buffer, the drawings are not estimated.
+    pub bytes: usize,
+    // The statement that declares the variable.
+    pub range: Option<StrRange>,
+}
+
+// All of the live objects ordered by the bytes descending.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct MemoryReport {
+    pub objects: Vec<LiveObject>,
+    pub drawings: DrawingStats,
+    pub total_bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn new(mut objects: Vec<LiveObject>, drawings: DrawingStats) -> MemoryReport {
+        let counts = [
+            (ObjectKind::Line, "line", drawings.lines),
+            (ObjectKind::Label, "label", drawings.labels),
+            (ObjectKind::Box, "box", drawings.boxes),
+        ];
+        for (kind, name, len) in counts.iter() {
+            objects.push(LiveObject {
+                kind: *kind,
+                name: String::from(*name),
+                id: None,
+                len: *len,
+                bytes: 0,
+                range: None,
+            });
+        }
+        objects.sort_by_key(|o| Reverse(o.bytes));
+        let total_bytes = objects.iter().map(|o| o.bytes).sum();
+        MemoryReport {
+            objects,
+            drawings,
+            total_bytes,
+        }
+    }
+}
+
+// The declarations of the variables in one context and its sub contexts, they are collected from
+// the syntax tree so the runtime objects can be mapped to the statements that create them.
+#[derive(Debug, Default, PartialEq)]
+pub struct CtxSites<'a> {
+    vars: HashMap<i32, (&'a str, StrRange)>,
+    subs: HashMap<i32, CtxSites<'a>>,
+}
+
+impl<'a> CtxSites<'a> {
+    pub fn new(blk: &'a Block<'a>) -> CtxSites<'a> {
+        // The user functions are defined in the main block, the calls run the bodies in sub contexts.
+        let funcs = blk
+            .stmts
+            .iter()
+            .filter_map(|s| match s {
+                Statement::FuncDef(def) => Some((def.name.value, &**def)),
+                _ => None,
+            })
+            .collect();
+        let mut visitor = SitesVisitor {
+            funcs: &funcs,
+            sites: CtxSites::default(),
+        };
+        visitor.visit_blk(blk);
+        visitor.sites
+    }
+
+    pub fn get_var(&self, varid: i32) -> Option<&(&'a str, StrRange)> {
+        self.vars.get(&varid)
+    }
+
+    pub fn get_sub(&self, ctxid: i32) -> Option<&CtxSites<'a>> {
+        self.subs.get(&ctxid)
+    }
+}
+
+struct SitesVisitor<'a, 'b> {
+    funcs: &'b HashMap<&'a str, &'a FunctionDef<'a>>,
+    // The sites of the context being visited.
+    sites: CtxSites<'a>,
+}
+
+impl<'a, 'b> SitesVisitor<'a, 'b> {
+    fn visit_sub(&mut self, ctxid: i32, blk: &'a Block<'a>) {
+        let parent = mem::take(&mut self.sites);
+        self.visit_blk(blk);
+        let sub = mem::replace(&mut self.sites, parent);
+        self.sites.subs.insert(ctxid, sub);
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'a> for SitesVisitor<'a, 'b> {
+    fn visit_assign(&mut self, assign: &'a Assignment<'a>) {
+        walk_assign(self, assign);
+        if let Some(varids) = &assign.varids {
+            for (name, varid) in assign.names.iter().zip(varids.iter()) {
+                self.sites.vars.insert(*varid, (name.value, assign.range));
+            }
+        }
+    }
+
+    fn visit_func_call(&mut self, call: &'a FunctionCall<'a>) {
+        walk_func_call(self, call);
+        if let Exp::VarName(name) = &call.method {
+            if let Some(def) = self.funcs.get(name.name.value) {
+                let def = match &def.spec_defs {
+                    Some(defs) if (call.spec_index as usize) < defs.len() => {
+                        &defs[call.spec_index as usize]
+                    }
+                    _ => def,
+                };
+                self.visit_sub(call.ctxid, &def.body);
+            }
+        }
+    }
+
+    fn visit_ite(&mut self, ite: &'a IfThenElse<'a>) {
+        self.visit_exp(&ite.cond);
+        self.visit_sub(ite.then_ctxid, &ite.then_blk);
+        if let Some(blk) = &ite.else_blk {
+            self.visit_sub(ite.else_ctxid, blk);
+        }
+    }
+
+    fn visit_for_range(&mut self, fr: &'a ForRange<'a>) {
+        self.visit_exp(&fr.start);
+        self.visit_exp(&fr.end);
+        if let Some(step) = &fr.step {
+            self.visit_exp(step);
+        }
+        self.visit_sub(fr.ctxid, &fr.do_blk);
+    }
+
+    fn visit_for_in(&mut self, fi: &'a ForIn<'a>) {
+        self.visit_exp(&fi.iter);
+        self.visit_sub(fi.ctxid, &fi.do_blk);
+    }
+
+    // The function bodies are visited by the calls, since every call runs in its own context.
+    fn visit_func_def(&mut self, _def: &'a FunctionDef<'a>) {}
+}
+
+// The object that has grown on `bars` bars since the bar `from_bar` without shrinking.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeakWarning {
+    pub kind: ObjectKind,
+    pub name: String,
+    pub range: Option<StrRange>,
+    pub from_bar: i32,
+    pub bar_index: i32,
+    pub from_len: usize,
+    pub len: usize,
+    pub bars: usize,
+}
+
+type LeakKey = (ObjectKind, Option<ObjectId>);
+
+struct Growth {
+    from_bar: i32,
+    from_len: usize,
+    len: usize,
+    bars: usize,
+}
+
+// Watch the lengths of the arrays and the drawings after every bar. The object is reported once
+// when it grows on `min_bars` bars and never shrinks in between. The series are not checked since
+// their histories grow with the bars by design.
+pub struct LeakChecker {
+    min_bars: usize,
+    growths: HashMap<LeakKey, Growth>,
+    warned: HashSet<LeakKey>,
+    warnings: Vec<LeakWarning>,
+}
+
+impl LeakChecker {
+    pub fn new(min_bars: usize) -> LeakChecker {
+        LeakChecker {
+            min_bars: min_bars.max(1),
+            growths: HashMap::new(),
+            warned: HashSet::new(),
+            warnings: vec![],
+        }
+    }
+
+    pub fn observe(&mut self, bar_index: i32, objects: &[LiveObject]) {
+        for obj in objects.iter().filter(|o| o.kind != ObjectKind::Series) {
+            let key = (obj.kind, obj.id.clone());
+            let growth = self.growths.entry(key.clone()).or_insert(Growth {
+                from_bar: bar_index,
+                from_len: obj.len,
+                len: obj.len,
+                bars: 0,
+            });
+            if obj.len < growth.len {
+                *growth = Growth {
+                    from_bar: bar_index,
+                    from_len: obj.len,
+                    len: obj.len,
+                    bars: 0,
+                };
+            } else if obj.len > growth.len {
+                growth.len = obj.len;
+                growth.bars += 1;
+            }
+            if growth.bars >= self.min_bars && self.warned.insert(key) {
+                self.warnings.push(LeakWarning {
+                    kind: obj.kind,
+                    name: obj.name.clone(),
+                    range: obj.range,
+                    from_bar: growth.from_bar,
+                    bar_index,
+                    from_len: growth.from_len,
+                    len: growth.len,
+                    bars: growth.bars,
+                });
+            }
+        }
+    }
+
+    // Forget the growths and warnings of the last run.
+    pub fn reset(&mut self) {
+        self.growths.clear();
+        self.warned.clear();
+        self.warnings.clear();
+    }
+
+    pub fn get_warnings(&self) -> &Vec<LeakWarning> {
+        &self.warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array_obj(len: usize) -> LiveObject {
+        LiveObject {
+            kind: ObjectKind::Array,
+            name: String::from("a"),
+            id: Some((vec![], 0)),
+            len,
+            bytes: len * 8,
+            range: None,
+        }
+    }
+
+    #[test]
+    fn leak_checker_test() {
+        let mut checker = LeakChecker::new(3);
+        for (i, len) in [1, 2, 2, 3, 1, 2, 3, 4, 5].iter().enumerate() {
+            checker.observe(i as i32, &[array_obj(*len)]);
+        }
+        // The growth from the bar 0 is reset by the shrink on the bar 4.
+        assert_eq!(
+            checker.get_warnings(),
+            &vec![LeakWarning {
+                kind: ObjectKind::Array,
+                name: String::from("a"),
+                range: None,
+                from_bar: 4,
+                bar_index: 7,
+                from_len: 1,
+                len: 4,
+                bars: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn memory_report_test() {
+        let report = MemoryReport::new(
+            vec![array_obj(1), array_obj(4)],
+            DrawingStats {
+                lines: 2,
+                labels: 0,
+                boxes: 0,
+                polylines: 0,
+            },
+        );
+        assert_eq!(report.total_bytes, 40);
+        assert_eq!(report.objects.len(), 5);
+        assert_eq!(report.objects[0].len, 4);
+        assert_eq!(report.objects[2].name, "line");
+        assert_eq!(report.objects[2].len, 2);
+    }
+
+    #[test]
+    fn runner_memory_test() {
+        use crate::ast::input::Position;
+        use crate::ast::syntax_type::SyntaxType;
+        use crate::libs::{array, line};
+        use crate::runtime::{AnySeries, NoneCallback};
+        use crate::{LibInfo, PineParser, PineRunner};
+
+        let lib_info = LibInfo::new(
+            vec![array::declare_var(), line::declare_var()],
+            vec![("close", SyntaxType::float_series())],
+        );
+        let src = "f(x) =>
+    y = x * 2
+    y
+a = array.new<float>(3, close)
+b = f(close)
+l = line.new(1, close, 3, 4)
+if close > 1
+    c = close + 1
+";
+        let blk = PineParser::new(src, &lib_info).parse_blk().unwrap();
+        let mut runner = PineRunner::new(&lib_info, &blk, &NoneCallback());
+        runner.set_leak_check(Some(3));
+        let data = vec![(
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(2f64), Some(3f64), Some(4f64)]),
+        )];
+        runner.run(&data, None).unwrap();
+
+        let report = runner.memory_report();
+        assert_eq!(report.drawings.lines, 4);
+        let find = |name: &str| report.objects.iter().find(|o| o.name == name).unwrap();
+        assert_eq!(find("a").kind, ObjectKind::Array);
+        assert_eq!(find("a").len, 3);
+        assert_eq!(find("a").range.unwrap().start, Position::new(3, 0));
+        assert_eq!(find("b").kind, ObjectKind::Series);
+        // The variables of the function body and the if block are in the sub contexts.
+        assert_eq!(find("y").range.unwrap().start, Position::new(1, 4));
+        assert_eq!(find("y").id.as_ref().unwrap().0.len(), 1);
+        assert_eq!(find("c").range.unwrap().start, Position::new(7, 4));
+        assert_eq!(find("line").len, 4);
+        assert_eq!(
+            report.total_bytes,
+            report.objects.iter().map(|o| o.bytes).sum::<usize>()
+        );
+
+        // The lines grow on the bars 1, 2 and 3.
+        let warnings = runner.get_leak_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, ObjectKind::Line);
+        assert_eq!((warnings[0].from_len, warnings[0].len), (1, 4));
+        assert_eq!(warnings[0].bar_index, 3);
+
+        // The warnings of the last run are cleared by a new run.
+        runner.run(&data, None).unwrap();
+        assert_eq!(runner.get_leak_warnings().len(), 1);
+        runner.set_leak_check(None);
+        assert!(runner.get_leak_warnings().is_empty());
+    }
+}
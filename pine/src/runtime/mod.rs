@@ -1,20 +1,39 @@
+pub mod adjustment;
 pub mod any_series;
+pub mod bar_aggregate;
+pub mod bar_time;
+pub mod bar_transform;
+pub mod cancel;
+pub mod clock;
 pub mod context;
 pub mod data_src;
+pub mod drawing;
 pub mod error_format;
 pub mod exp;
+pub mod external;
 pub mod function;
+pub mod host_event;
 pub mod instance_caller;
+pub mod memory;
 pub mod op;
 pub mod output;
+pub mod parallel;
+pub mod profile;
 pub mod runtime_convert;
 pub mod statement;
+pub mod strategy;
 
 pub use any_series::*;
+pub use cancel::*;
+pub use clock::*;
 pub use context::*;
 pub use data_src::*;
+pub use drawing::*;
 pub use error_format::*;
+pub use host_event::*;
+pub use memory::*;
 pub use output::*;
+pub use profile::*;
 // use crate::ast::stat_expr_types::Block;
 // use crate::types::PineRef;
 // use context::{Context, ContextType, PineRuntimeError, Runner, VarOperate};
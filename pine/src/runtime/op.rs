@@ -1,13 +1,24 @@
-use super::context::{Ctx, PineRuntimeError, RVRunner};
+use super::context::{downcast_ctx_const, Ctx, PineRuntimeError, RVRunner};
 use crate::ast::op::{BinaryOp, UnaryOp};
 use crate::ast::stat_expr_types::{BinaryExp, UnaryExp};
 use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
 use crate::types::{
-    downcast_pf, Arithmetic, Bool, Color, DataType as FirstType, Float, Int, Negative, PineFrom,
-    PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
+    downcast_pf, Arithmetic, Bool, Color, Comparator, DataType as FirstType, Float, Int, Negative,
+    PineFrom, PineRef, PineType, RefData, RuntimeErr, SecondType, Series, NA,
 };
 use std::fmt::Debug;
 
+// How the numbers are compared with na by the operators `<`, `<=`, `>`, `>=`, `==` and `!=`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NaComparison {
+    // Any comparison with na is false like Pine, so `na(x)` must be used to test na.
+    #[default]
+    Pine,
+    // The behavior of the old versions of the runtime: na is equal to na and less than any number,
+    // e.g. `x == na` tests na and `na < 1` is true.
+    Ordered,
+}
+
 pub fn unary_op_run<'a>(
     unary_exp: &'a UnaryExp<'a>,
     context: &mut (dyn Ctx<'a>),
@@ -50,22 +61,34 @@ pub fn unary_op_run<'a>(
     }
 }
 
-fn bi_operate<'a, D>(op: &BinaryOp, d1: RefData<D>, d2: RefData<D>) -> PineRef<'a>
+fn bi_operate<'a, D>(
+    op: &BinaryOp,
+    d1: RefData<D>,
+    d2: RefData<D>,
+    na_cmp: NaComparison,
+) -> PineRef<'a>
 where
-    D: Arithmetic + PartialOrd + PartialEq + Debug + Clone + PineType<'a> + 'a,
+    D: Arithmetic + Comparator + PartialOrd + PartialEq + Debug + Clone + PineType<'a> + 'a,
 {
+    if na_cmp == NaComparison::Ordered {
+        match op {
+            BinaryOp::Lt => return PineRef::new(*d1 < *d2),
+            BinaryOp::Leq => return PineRef::new(*d1 <= *d2),
+            BinaryOp::Gt => return PineRef::new(*d1 > *d2),
+            BinaryOp::Geq => return PineRef::new(*d1 >= *d2),
+            _ => {}
+        }
+    }
     match op {
         BinaryOp::Plus => PineRef::new(d1.into_inner().add(d2.into_inner())),
         BinaryOp::Minus => PineRef::new(d1.into_inner().minus(d2.into_inner())),
         BinaryOp::Mul => PineRef::new(d1.into_inner().mul(d2.into_inner())),
         BinaryOp::Div => PineRef::new(d1.into_inner().div(d2.into_inner())),
         BinaryOp::Mod => PineRef::new(d1.into_inner().rem(d2.into_inner())),
-        BinaryOp::Eq => PineRef::new(*d1 == *d2),
-        BinaryOp::Neq => PineRef::new(*d1 != *d2),
-        BinaryOp::Lt => PineRef::new(*d1 < *d2),
-        BinaryOp::Leq => PineRef::new(*d1 <= *d2),
-        BinaryOp::Gt => PineRef::new(*d1 > *d2),
-        BinaryOp::Geq => PineRef::new(*d1 >= *d2),
+        BinaryOp::Lt => PineRef::new(Comparator::lt(d1.into_inner(), d2.into_inner())),
+        BinaryOp::Leq => PineRef::new(Comparator::le(d1.into_inner(), d2.into_inner())),
+        BinaryOp::Gt => PineRef::new(Comparator::gt(d1.into_inner(), d2.into_inner())),
+        BinaryOp::Geq => PineRef::new(Comparator::ge(d1.into_inner(), d2.into_inner())),
         _ => unreachable!(),
     }
 }
@@ -81,12 +104,36 @@ where
     }
 }
 
+// With the Pine comparison, the equality of the numbers is false if any operand is na,
+// so both `x == na` and `x != na` are false.
+fn na_eq_operate<T: PartialEq>(
+    op: &BinaryOp,
+    d1: &Option<T>,
+    d2: &Option<T>,
+    na_cmp: NaComparison,
+) -> bool {
+    match (d1, d2, na_cmp) {
+        (Some(v1), Some(v2), _) => match op {
+            BinaryOp::Eq => v1 == v2,
+            BinaryOp::Neq => v1 != v2,
+            _ => unreachable!(),
+        },
+        (_, _, NaComparison::Ordered) => match op {
+            BinaryOp::Eq => d1.is_none() && d2.is_none(),
+            BinaryOp::Neq => d1.is_some() || d2.is_some(),
+            _ => unreachable!(),
+        },
+        _ => false,
+    }
+}
+
 fn eq_run<'a, 'b>(
     binary_exp: &'a BinaryExp<'a>,
     context: &mut (dyn 'b + Ctx<'a>),
 ) -> Result<PineRef<'a>, PineRuntimeError> {
     let val1 = binary_exp.exp1.rv_run(context)?;
     let val2 = binary_exp.exp2.rv_run(context)?;
+    let na_cmp = downcast_ctx_const(context).get_na_comparison();
     match binary_exp.ref_type {
         SyntaxType::Series(SimpleSyntaxType::Bool) => {
             let s1: RefData<Series<Bool>> = Series::implicity_from(val1).unwrap();
@@ -97,21 +144,18 @@ fn eq_run<'a, 'b>(
         SyntaxType::Series(SimpleSyntaxType::Int) => {
             let s1: RefData<Series<Int>> = Series::implicity_from(val1).unwrap();
             let s2: RefData<Series<Int>> = Series::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &s1.get_current(), &s2.get_current());
+            let res = na_eq_operate(&binary_exp.op, &s1.get_current(), &s2.get_current(), na_cmp);
             Ok(PineRef::new_rc(Series::from(res)))
         }
         SyntaxType::Series(SimpleSyntaxType::Float) => {
             let s1: RefData<Series<Float>> = Series::implicity_from(val1).unwrap();
             let s2: RefData<Series<Float>> = Series::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &s1.get_current(), &s2.get_current());
-            Ok(PineRef::new_rc(Series::from(res)))
-        }
-        SyntaxType::Series(SimpleSyntaxType::Na) => {
-            let s1: RefData<Series<NA>> = Series::implicity_from(val1).unwrap();
-            let s2: RefData<Series<NA>> = Series::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &s1.get_current(), &s2.get_current());
+            let res = na_eq_operate(&binary_exp.op, &s1.get_current(), &s2.get_current(), na_cmp);
             Ok(PineRef::new_rc(Series::from(res)))
         }
+        SyntaxType::Series(SimpleSyntaxType::Na) => Ok(PineRef::new_rc(Series::from(
+            na_eq_operate::<NA>(&binary_exp.op, &None, &None, na_cmp),
+        ))),
         SyntaxType::Series(SimpleSyntaxType::Color) => {
             let s1: RefData<Series<Color>> = Series::implicity_from(val1).unwrap();
             let s2: RefData<Series<Color>> = Series::implicity_from(val2).unwrap();
@@ -134,21 +178,21 @@ fn eq_run<'a, 'b>(
         SyntaxType::Simple(SimpleSyntaxType::Int) => {
             let s1: RefData<Int> = Int::implicity_from(val1).unwrap();
             let s2: RefData<Int> = Int::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &*s1, &*s2);
+            let res = na_eq_operate(&binary_exp.op, &*s1, &*s2, na_cmp);
             Ok(PineRef::new_box(res))
         }
         SyntaxType::Simple(SimpleSyntaxType::Float) => {
             let s1: RefData<Float> = Float::implicity_from(val1).unwrap();
             let s2: RefData<Float> = Float::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &*s1, &*s2);
-            Ok(PineRef::new_box(res))
-        }
-        SyntaxType::Simple(SimpleSyntaxType::Na) => {
-            let s1: RefData<NA> = NA::implicity_from(val1).unwrap();
-            let s2: RefData<NA> = NA::implicity_from(val2).unwrap();
-            let res = bi_eq_operate(&binary_exp.op, &*s1, &*s2);
+            let res = na_eq_operate(&binary_exp.op, &*s1, &*s2, na_cmp);
             Ok(PineRef::new_box(res))
         }
+        SyntaxType::Simple(SimpleSyntaxType::Na) => Ok(PineRef::new_box(na_eq_operate::<NA>(
+            &binary_exp.op,
+            &None,
+            &None,
+            na_cmp,
+        ))),
         SyntaxType::Simple(SimpleSyntaxType::Color) => {
             let s1: RefData<Color> = Color::implicity_from(val1).unwrap();
             let s2: RefData<Color> = Color::implicity_from(val2).unwrap();
@@ -203,6 +247,7 @@ pub fn binary_op_run<'a, 'b>(
         _ => {
             let val1 = binary_exp.exp1.rv_run(context)?;
             let val2 = binary_exp.exp2.rv_run(context)?;
+            let na_cmp = downcast_ctx_const(context).get_na_comparison();
             match (&binary_exp.op, &binary_exp.ref_type) {
                 // series(string) + series(string)
                 (BinaryOp::Plus, &SyntaxType::Series(SimpleSyntaxType::String)) => {
@@ -223,25 +268,25 @@ pub fn binary_op_run<'a, 'b>(
                 (op, &SyntaxType::Series(SimpleSyntaxType::Float)) => {
                     let f1: RefData<Series<Float>> = Series::implicity_from(val1).unwrap();
                     let f2: RefData<Series<Float>> = Series::implicity_from(val2).unwrap();
-                    Ok(bi_operate(op, f1, f2))
+                    Ok(bi_operate(op, f1, f2, na_cmp))
                 }
                 // float +/-/.. any
                 (op, &SyntaxType::Simple(SimpleSyntaxType::Float)) => {
                     let f1 = Float::implicity_from(val1).unwrap();
                     let f2 = Float::implicity_from(val2).unwrap();
-                    Ok(bi_operate(op, f1, f2))
+                    Ok(bi_operate(op, f1, f2, na_cmp))
                 }
                 // series(int) +/-/.. any
                 (op, &SyntaxType::Series(SimpleSyntaxType::Int)) => {
                     let d1: RefData<Series<Int>> = Series::implicity_from(val1).unwrap();
                     let d2: RefData<Series<Int>> = Series::implicity_from(val2).unwrap();
-                    Ok(bi_operate(op, d1, d2))
+                    Ok(bi_operate(op, d1, d2, na_cmp))
                 }
                 // int +/-/.. any
                 (op, &SyntaxType::Simple(SimpleSyntaxType::Int)) => {
                     let d1 = Int::implicity_from(val1).unwrap();
                     let d2 = Int::implicity_from(val2).unwrap();
-                    Ok(bi_operate(op, d1, d2))
+                    Ok(bi_operate(op, d1, d2, na_cmp))
                 }
                 _ => Err(PineRuntimeError::new(
                     RuntimeErr::NotSupportOperator,
@@ -257,7 +302,7 @@ mod tests {
     use super::*;
     use crate::ast::input::StrRange;
     use crate::ast::num::Numeral;
-    use crate::ast::stat_expr_types::{BoolNode, Exp, RVVarName, VarIndex};
+    use crate::ast::stat_expr_types::{BoolNode, Exp, NaNode, RVVarName, VarIndex};
     use crate::ast::string::StringNode;
     use crate::runtime::context::{Context, ContextType, VarOperate};
     use crate::syntax::SyntaxParser;
@@ -650,4 +695,142 @@ mod tests {
             Ok(RefData::new_box(true))
         );
     }
+
+    fn na_compare<'a>(
+        op: BinaryOp,
+        ref_type: SyntaxType<'a>,
+        v1: Float,
+        v2: Float,
+        na_cmp: NaComparison,
+    ) -> bool {
+        let mut exp = BinaryExp::new(
+            op,
+            var_exp("arg1", 0),
+            var_exp("arg2", 1),
+            StrRange::new_empty(),
+        );
+        exp.ref_type = ref_type.clone();
+        let mut context = Context::new(None, ContextType::Main);
+        context.set_na_comparison(na_cmp);
+        let to_int = |v: Float| v.map(|v| v as i64);
+        context.init_vars(match ref_type {
+            SyntaxType::Simple(SimpleSyntaxType::Int) => vec![
+                Some(PineRef::new_box(to_int(v1))),
+                Some(PineRef::new_box(to_int(v2))),
+            ],
+            SyntaxType::Simple(SimpleSyntaxType::Float) => {
+                vec![Some(PineRef::new_box(v1)), Some(PineRef::new_box(v2))]
+            }
+            SyntaxType::Series(SimpleSyntaxType::Int) => vec![
+                Some(PineRef::new_rc(Series::from(to_int(v1)))),
+                Some(PineRef::new_rc(Series::from(to_int(v2)))),
+            ],
+            _ => vec![
+                Some(PineRef::new_rc(Series::from(v1))),
+                Some(PineRef::new_rc(Series::from(v2))),
+            ],
+        });
+        let res = binary_op_run(&exp, &mut context).unwrap();
+        *Bool::implicity_from(res).unwrap()
+    }
+
+    #[test]
+    fn na_comparison_test() {
+        let ops = [
+            BinaryOp::Lt,
+            BinaryOp::Leq,
+            BinaryOp::Gt,
+            BinaryOp::Geq,
+            BinaryOp::Eq,
+            BinaryOp::Neq,
+        ];
+        let types = [
+            SyntaxType::int(),
+            SyntaxType::float(),
+            SyntaxType::int_series(),
+            SyntaxType::float_series(),
+        ];
+        // The results of the operators ordered as `ops` for the operands.
+        let cases: [(Float, Float, [bool; 6], [bool; 6]); 5] = [
+            (
+                Some(1f64),
+                Some(2f64),
+                [true, true, false, false, false, true],
+                [true, true, false, false, false, true],
+            ),
+            (
+                Some(2f64),
+                Some(2f64),
+                [false, true, false, true, true, false],
+                [false, true, false, true, true, false],
+            ),
+            (
+                None,
+                Some(1f64),
+                [false; 6],
+                [true, true, false, false, false, true],
+            ),
+            (
+                Some(1f64),
+                None,
+                [false; 6],
+                [false, false, true, true, false, true],
+            ),
+            (
+                None,
+                None,
+                [false; 6],
+                [false, true, false, true, true, false],
+            ),
+        ];
+        for ref_type in types.iter() {
+            for (v1, v2, pine_res, ordered_res) in cases.iter() {
+                for (i, op) in ops.iter().enumerate() {
+                    assert_eq!(
+                        na_compare(op.clone(), ref_type.clone(), *v1, *v2, NaComparison::Pine),
+                        pine_res[i],
+                        "{:?} {:?} {:?} {:?}",
+                        ref_type,
+                        v1,
+                        op,
+                        v2
+                    );
+                    assert_eq!(
+                        na_compare(
+                            op.clone(),
+                            ref_type.clone(),
+                            *v1,
+                            *v2,
+                            NaComparison::Ordered
+                        ),
+                        ordered_res[i],
+                        "{:?} {:?} {:?} {:?}",
+                        ref_type,
+                        v1,
+                        op,
+                        v2
+                    );
+                }
+            }
+        }
+
+        // The na literals are never equal with the Pine comparison.
+        let mut exp = BinaryExp::new(
+            BinaryOp::Eq,
+            Exp::Na(NaNode::new(StrRange::new_empty())),
+            Exp::Na(NaNode::new(StrRange::new_empty())),
+            StrRange::new_empty(),
+        );
+        exp.ref_type = SyntaxType::Simple(SimpleSyntaxType::Na);
+        let mut context = Context::new(None, ContextType::Main);
+        assert_eq!(
+            downcast_pf::<Bool>(binary_op_run(&exp, &mut context).unwrap()),
+            Ok(RefData::new_box(false))
+        );
+        context.set_na_comparison(NaComparison::Ordered);
+        assert_eq!(
+            downcast_pf::<Bool>(binary_op_run(&exp, &mut context).unwrap()),
+            Ok(RefData::new_box(true))
+        );
+    }
 }
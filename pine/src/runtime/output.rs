@@ -1,3 +1,6 @@
+use crate::types::Color;
+use std::collections::HashMap;
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct StudyScript {
     pub title: String,
@@ -11,6 +14,52 @@ pub struct StudyScript {
 #[serde(tag = "type")]
 pub enum ScriptPurpose {
     Study(StudyScript),
+    Strategy(StudyScript),
+}
+
+// The metadata collected from the script declaration call(study, indicator or strategy).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ScriptMeta {
+    pub declaration: String,
+    pub title: String,
+    pub shorttitle: Option<String>,
+    pub overlay: Option<bool>,
+    pub format: Option<String>,
+    pub precision: Option<i64>,
+    pub max_bars_back: Option<i64>,
+    pub max_lines_count: Option<i64>,
+    pub max_labels_count: Option<i64>,
+    pub max_boxes_count: Option<i64>,
+    pub max_polylines_count: Option<i64>,
+    // The `//@description` and `//@strategy_alert_message` annotations of the script.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub strategy_alert_message: Option<String>,
+}
+
+impl ScriptMeta {
+    pub fn to_purpose(&self) -> ScriptPurpose {
+        let study = StudyScript {
+            title: self.title.clone(),
+            shorttitle: self.shorttitle.clone(),
+            overlay: self.overlay,
+            format: self.format.clone(),
+            precision: self.precision,
+        };
+        match self.declaration.as_str() {
+            "strategy" => ScriptPurpose::Strategy(study),
+            _ => ScriptPurpose::Study(study),
+        }
+    }
+}
+
+// The layout metadata of the input used by the host to arrange the settings dialog.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct InputMeta {
+    pub inline: Option<String>, // The inputs with the same inline id are placed on one line.
+    pub group: Option<String>,  // The header of the section that contains the input.
+    pub tooltip: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -19,6 +68,8 @@ pub struct BoolInputInfo {
     pub title: Option<String>,
     pub input_type: String,
     pub confirm: Option<bool>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -31,6 +82,8 @@ pub struct IntInputInfo {
     pub confirm: Option<bool>,
     pub step: Option<i64>,
     pub options: Option<Vec<i64>>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -43,6 +96,8 @@ pub struct FloatInputInfo {
     pub confirm: Option<bool>,
     pub step: Option<f64>,
     pub options: Option<Vec<f64>>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -52,6 +107,8 @@ pub struct StringInputInfo {
     pub input_type: String,
     pub confirm: Option<bool>,
     pub options: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -59,6 +116,40 @@ pub struct SourceInputInfo {
     pub defval: Option<String>,
     pub title: Option<String>,
     pub input_type: String,
+    #[serde(default)]
+    pub confirm: Option<bool>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
+}
+
+// The symbol input that the host shows as a symbol picker, the value is passed to `security`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SymbolInputInfo {
+    // The default symbol, it is the chart symbol(`syminfo.tickerid`) if the script gives na.
+    pub defval: Option<String>,
+    pub title: Option<String>,
+    pub input_type: String,
+    pub confirm: Option<bool>,
+    // The exchange prefix of the default symbol like `NASDAQ` in `NASDAQ:AAPL`, so the picker
+    // can search the symbols of the exchange.
+    pub exchange: Option<String>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
+}
+
+// The color input that the host shows as a color picker with the opacity slider.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ColorInputInfo {
+    // The default color as `#RRGGBB`, it is None if the script gives na so the host can pick the
+    // default color of its theme.
+    pub defval: Option<String>,
+    // The default opacity of the color in [0, 255], 255 is opaque.
+    pub alpha: Option<u8>,
+    pub title: Option<String>,
+    pub input_type: String,
+    pub confirm: Option<bool>,
+    #[serde(flatten)]
+    pub meta: InputMeta,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -69,6 +160,8 @@ pub enum InputInfo {
     Float(FloatInputInfo),
     String(StringInputInfo),
     Source(SourceInputInfo),
+    Symbol(SymbolInputInfo),
+    Color(ColorInputInfo),
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -175,6 +268,25 @@ pub struct HLineInfo {
     pub editable: Option<bool>,
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BgColorInfo {
+    pub title: Option<String>,
+    pub color: Option<String>,
+    pub transp: Option<i64>,
+    pub offset: Option<i64>,
+    pub editable: Option<bool>,
+    pub show_last: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BarColorInfo {
+    pub title: Option<String>,
+    pub color: Option<String>,
+    pub offset: Option<i64>,
+    pub editable: Option<bool>,
+    pub show_last: Option<i64>,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum OutputInfo {
@@ -186,6 +298,34 @@ pub enum OutputInfo {
     PlotShape(PlotShapeInfo),
     Fill(FillInfo),
     HLine(HLineInfo),
+    BgColor(BgColorInfo),
+    BarColor(BarColorInfo),
+}
+
+// The values of the `display` argument. The output data of the hidden outputs are still generated,
+// so the hosts can use them for the alerts and the exports.
+pub const DISPLAY_NONE: i64 = 0;
+pub const DISPLAY_ALL: i64 = 1;
+pub const DISPLAY_PANE: i64 = 2;
+
+impl OutputInfo {
+    // Get the display value of the output, None if the output has no display argument.
+    pub fn get_display(&self) -> Option<i64> {
+        match self {
+            OutputInfo::Plot(info) => info.display,
+            OutputInfo::PlotArrow(info) => info.display,
+            OutputInfo::PlotBar(info) => info.display,
+            OutputInfo::PlotCandle(info) => info.display,
+            OutputInfo::PlotChar(info) => info.display,
+            OutputInfo::PlotShape(info) => info.display,
+            _ => None,
+        }
+    }
+
+    // Whether the output should be drawn on the chart.
+    pub fn is_visible(&self) -> bool {
+        self.get_display() != Some(DISPLAY_NONE)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -203,6 +343,8 @@ impl InputSrc {
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct IOInfo {
     script_type: Option<ScriptPurpose>,
+    #[serde(default)]
+    script_meta: Option<ScriptMeta>,
     inputs: Vec<InputInfo>,
     input_srcs: Vec<InputSrc>,
     outputs: Vec<OutputInfo>,
@@ -220,6 +362,7 @@ impl IOInfo {
     pub fn new() -> IOInfo {
         IOInfo {
             script_type: None,
+            script_meta: None,
             inputs: vec![],
             input_srcs: IOInfo::gen_srcs(),
             outputs: vec![],
@@ -234,6 +377,7 @@ impl IOInfo {
     ) -> IOInfo {
         IOInfo {
             script_type: None,
+            script_meta: None,
             inputs,
             outputs,
             input_srcs,
@@ -258,6 +402,11 @@ impl IOInfo {
         self.script_type = Some(script_type);
     }
 
+    pub fn set_script_meta(&mut self, script_meta: ScriptMeta) {
+        self.script_type = Some(script_meta.to_purpose());
+        self.script_meta = Some(script_meta);
+    }
+
     pub fn add_input_src(&mut self, mut input_src: InputSrc) {
         // If the input source contain `_time`, we should transfer to `time`
         let pos = input_src.srcs.iter().position(|s| s.as_str() == "_time");
@@ -302,6 +451,10 @@ impl IOInfo {
     pub fn get_script_type(&self) -> &Option<ScriptPurpose> {
         &self.script_type
     }
+
+    pub fn get_script_meta(&self) -> &Option<ScriptMeta> {
+        &self.script_meta
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -317,6 +470,32 @@ impl StrOptionsData {
             values: vec![],
         }
     }
+
+    // Intern the strings of every bar, only the first occurrence of a string is moved into the options.
+    pub fn from_values<S, I>(vals: I) -> StrOptionsData
+    where
+        S: AsRef<str> + Into<String>,
+        I: IntoIterator<Item = Option<S>>,
+    {
+        let mut indexs: HashMap<String, i32> = HashMap::new();
+        let mut options: Vec<String> = vec![];
+        let values = vals
+            .into_iter()
+            .map(|val| {
+                val.map(|v| match indexs.get(v.as_ref()) {
+                    Some(i) => *i,
+                    None => {
+                        let v: String = v.into();
+                        let i = options.len() as i32;
+                        indexs.insert(v.clone(), i);
+                        options.push(v);
+                        i
+                    }
+                })
+            })
+            .collect();
+        StrOptionsData { options, values }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -325,6 +504,9 @@ pub struct OutputData {
     // pub to: Option<i32>,
     pub series: Vec<Vec<Option<f64>>>,
     pub colors: Vec<StrOptionsData>,
+    // The texts of every bar given by the series string arguments, e.g. the `text` of plotchar.
+    #[serde(default)]
+    pub texts: Vec<StrOptionsData>,
 }
 
 impl OutputData {
@@ -332,6 +514,7 @@ impl OutputData {
         OutputData {
             series,
             colors: vec![],
+            texts: vec![],
         }
     }
 
@@ -339,8 +522,14 @@ impl OutputData {
         OutputData {
             series,
             colors: colors,
+            texts: vec![],
         }
     }
+
+    pub fn with_texts(mut self, texts: Vec<StrOptionsData>) -> OutputData {
+        self.texts = texts;
+        self
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -348,6 +537,15 @@ pub struct OutputDataCollect {
     pub from: i32,
     pub to: i32,
     pub data_list: Vec<Option<OutputData>>,
+    // The final background color of every bar blended from all the bgcolor outputs,
+    // None if the script doesn't call bgcolor.
+    #[serde(default)]
+    pub bgcolors: Option<StrOptionsData>,
+    // The values of the plots with the positive offsets that are shifted after the last bar, they
+    // are the values of the bars `to..to + offset`. It's indexed like `data_list` and the plots
+    // without the positive offset get None, it's empty if no plot has the positive offset.
+    #[serde(default)]
+    pub projections: Vec<Option<Vec<Option<f64>>>>,
 }
 
 impl OutputDataCollect {
@@ -356,6 +554,8 @@ impl OutputDataCollect {
             from,
             to,
             data_list,
+            bgcolors: None,
+            projections: vec![],
         }
     }
 
@@ -364,8 +564,109 @@ impl OutputDataCollect {
             from,
             to,
             data_list: vec![Some(OutputData::new(vec![data]))],
+            bgcolors: None,
+            projections: vec![],
+        }
+    }
+}
+
+// Composite the color `src` over the color `dst`, the channels are rgba.
+fn blend_rgba(dst: [u8; 4], src: [u8; 4]) -> [u8; 4] {
+    let src_a = src[3] as f64 / 255f64;
+    let dst_a = dst[3] as f64 / 255f64 * (1f64 - src_a);
+    let out_a = src_a + dst_a;
+    if out_a == 0f64 {
+        return [0, 0, 0, 0];
+    }
+    let channel =
+        |i: usize| ((src[i] as f64 * src_a + dst[i] as f64 * dst_a) / out_a).round() as u8;
+    [
+        channel(0),
+        channel(1),
+        channel(2),
+        (out_a * 255f64).round() as u8,
+    ]
+}
+
+// Blend the colors of the bgcolor outputs into the background color of every bar. The outputs
+// are painted in the order of the calls, so the later bgcolor is drawn over the earlier ones.
+// The alpha of the colors and the `transp` argument are applied and the `offset` shifts the colors
+// to the right. The bars without any background color get None.
+pub fn blend_bgcolors(
+    outputs: &[OutputInfo],
+    data_list: &[Option<OutputData>],
+    len: usize,
+) -> Option<StrOptionsData> {
+    let mut bg_found = false;
+    let mut layers: Vec<Option<[u8; 4]>> = vec![None; len];
+    for (i, output) in outputs.iter().enumerate() {
+        let info = match output {
+            OutputInfo::BgColor(info) => info,
+            _ => continue,
+        };
+        bg_found = true;
+        let opacity = 1f64 - info.transp.unwrap_or(0).clamp(0, 100) as f64 / 100f64;
+        let offset = info.offset.unwrap_or(0);
+        let series_colors = match data_list.get(i) {
+            Some(Some(data)) => data.colors.first(),
+            _ => None,
+        };
+        for (bar, layer) in layers.iter_mut().enumerate() {
+            let src_bar = bar as i64 - offset;
+            if src_bar < 0 || src_bar >= len as i64 {
+                continue;
+            }
+            // The series colors are saved in the output data and the simple color in the info.
+            let color = match series_colors {
+                Some(colors) => colors
+                    .values
+                    .get(src_bar as usize)
+                    .and_then(|v| v.and_then(|v| colors.options.get(v as usize))),
+                None => info.color.as_ref(),
+            };
+            let rgba = match color.and_then(|c| Color::new(c).to_rgba()) {
+                Some(rgba) => rgba,
+                None => continue,
+            };
+            let rgba = [
+                rgba[0],
+                rgba[1],
+                rgba[2],
+                (rgba[3] as f64 * opacity).round() as u8,
+            ];
+            if rgba[3] == 0 {
+                continue;
+            }
+            *layer = Some(match layer {
+                Some(dst) => blend_rgba(*dst, rgba),
+                None => rgba,
+            });
         }
     }
+    if !bg_found {
+        return None;
+    }
+
+    let mut bgcolors = StrOptionsData::new();
+    for layer in layers {
+        let color = match layer {
+            Some(rgba) => Color::from_rgba(rgba).0.into_owned(),
+            None => {
+                bgcolors.values.push(None);
+                continue;
+            }
+        };
+        match bgcolors.options.iter().position(|x| *x == color) {
+            Some(i) => bgcolors.values.push(Some(i as i32)),
+            None => {
+                bgcolors.options.push(color);
+                bgcolors
+                    .values
+                    .push(Some((bgcolors.options.len() - 1) as i32));
+            }
+        }
+    }
+    Some(bgcolors)
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -376,6 +677,9 @@ pub enum InputVal {
     Bool(bool),
     String(String),
     Source(String),
+    Symbol(String),
+    // The `#RRGGBB` or `#RRGGBBAA` color chosen by the user.
+    Color(String),
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -392,10 +696,125 @@ pub struct SymbolInfo {
     pub mintick: f64, // Min tick value for current symbol
 }
 
+// The chart environment of the host read by the `chart.*` variables, the values are constant
+// during the run. The None value is na.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ChartInfo {
+    pub bg_color: Option<String>, // The `#RRGGBB` or `#RRGGBBAA` background color of the chart.
+    pub fg_color: Option<String>, // The color that contrasts with the background color.
+    pub left_visible_bar_time: Option<i64>,
+    pub right_visible_bar_time: Option<i64>,
+}
+
+// The plot values of the previous runs by the bar index for the plots with the offsets. The values
+// shifted by the offset come from the bars of the previous runs after `update`, and the values
+// shifted after the last bar are buffered as the projections of the collected outputs.
+#[derive(Debug, Clone, Default)]
+pub struct PlotOffsets {
+    history: HashMap<usize, PlotHistory>,
+}
+
+// The values and the color names of the plot by the bar index.
+type PlotHistory = (Vec<Option<f64>>, Vec<Option<String>>);
+
+impl PlotOffsets {
+    pub fn new() -> PlotOffsets {
+        PlotOffsets::default()
+    }
+
+    // Shift the series and the colors of the plots with the offsets in the outputs of the bars
+    // `collect.from..collect.to`. The negative offset shifts the values to the left, so the last
+    // bars are na until their source bars run.
+    pub fn apply(&mut self, outputs: &[OutputInfo], collect: &mut OutputDataCollect) {
+        let (from, to) = (collect.from.max(0) as usize, collect.to.max(0) as usize);
+        let mut projections = vec![None; collect.data_list.len()];
+        for (i, output) in outputs.iter().enumerate() {
+            let offset = match output {
+                OutputInfo::Plot(PlotInfo {
+                    offset: Some(offset),
+                    ..
+                }) if *offset != 0 => *offset,
+                _ => continue,
+            };
+            let data = match collect.data_list.get_mut(i) {
+                Some(Some(data)) if !data.series.is_empty() => data,
+                _ => continue,
+            };
+
+            // The bars after `from` run again, so their old values are replaced.
+            let (values, colors) = self.history.entry(i).or_default();
+            values.resize(from, None);
+            values.extend(data.series[0].iter().cloned());
+            colors.resize(from, None);
+            match data.colors.first() {
+                Some(options) => colors.extend(
+                    options
+                        .values
+                        .iter()
+                        .map(|v| v.and_then(|v| options.options.get(v as usize).cloned())),
+                ),
+                None => colors.resize(values.len(), None),
+            }
+
+            let src_bar = |bar: usize| {
+                let src = bar as i64 - offset;
+                if src >= 0 && (src as usize) < values.len() {
+                    Some(src as usize)
+                } else {
+                    None
+                }
+            };
+            data.series[0] = (from..to)
+                .map(|bar| src_bar(bar).and_then(|src| values[src]))
+                .collect();
+            if let Some(options) = data.colors.first_mut() {
+                let mut shifted = StrOptionsData::new();
+                for bar in from..to {
+                    let color = src_bar(bar).and_then(|src| colors[src].clone());
+                    let value =
+                        color.map(
+                            |color| match shifted.options.iter().position(|x| *x == color) {
+                                Some(i) => i as i32,
+                                None => {
+                                    shifted.options.push(color);
+                                    (shifted.options.len() - 1) as i32
+                                }
+                            },
+                        );
+                    shifted.values.push(value);
+                }
+                *options = shifted;
+            }
+            if offset > 0 {
+                projections[i] = Some(
+                    (to..to + offset as usize)
+                        .map(|bar| src_bar(bar).and_then(|src| values[src]))
+                        .collect(),
+                );
+            }
+        }
+        if projections.iter().any(|p| p.is_some()) {
+            collect.projections = projections;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn str_options_test() {
+        let data = StrOptionsData::from_values(vec![Some("b"), None, Some("a"), Some("b")]);
+        assert_eq!(
+            data,
+            StrOptionsData {
+                options: vec![String::from("b"), String::from("a")],
+                values: vec![Some(0), None, Some(1), Some(0)]
+            }
+        );
+    }
+
     #[test]
     fn input_src_test() {
         let mut io_info = IOInfo::new();
@@ -445,4 +864,149 @@ mod tests {
             ]
         );
     }
+
+    fn bgcolor_info(color: Option<&str>, transp: Option<i64>, offset: Option<i64>) -> OutputInfo {
+        OutputInfo::BgColor(BgColorInfo {
+            title: None,
+            color: color.map(String::from),
+            transp,
+            offset,
+            editable: None,
+            show_last: None,
+        })
+    }
+
+    #[test]
+    fn blend_bgcolors_test() {
+        assert_eq!(blend_rgba([0, 0, 0, 0], [255, 0, 0, 128]), [255, 0, 0, 128]);
+        assert_eq!(
+            blend_rgba([255, 0, 0, 255], [0, 0, 255, 255]),
+            [0, 0, 255, 255]
+        );
+        assert_eq!(
+            blend_rgba([255, 0, 0, 255], [0, 0, 255, 128]),
+            [127, 0, 128, 255]
+        );
+
+        // No bgcolor outputs.
+        assert_eq!(blend_bgcolors(&[], &[], 2), None);
+
+        let outputs = vec![
+            bgcolor_info(Some("#FF0000"), None, None),
+            bgcolor_info(Some(""), None, None),
+            bgcolor_info(Some("#0000FF"), Some(50), Some(1)),
+        ];
+        let series_colors = OutputData::new_with_sc(
+            vec![],
+            vec![StrOptionsData {
+                options: vec![String::from("#00FF00"), String::from("")],
+                values: vec![Some(1), Some(0), Some(1)],
+            }],
+        );
+        let data_list = vec![None, Some(series_colors), None];
+        assert_eq!(
+            blend_bgcolors(&outputs, &data_list, 3),
+            Some(StrOptionsData {
+                options: vec![
+                    String::from("#FF0000"),
+                    String::from("#007F80"),
+                    String::from("#7F0080")
+                ],
+                values: vec![Some(0), Some(1), Some(2)],
+            })
+        );
+
+        // The bars without colors get None.
+        let outputs = vec![bgcolor_info(Some("#FF0000"), Some(100), None)];
+        assert_eq!(
+            blend_bgcolors(&outputs, &[None], 2),
+            Some(StrOptionsData {
+                options: vec![],
+                values: vec![None, None],
+            })
+        );
+    }
+
+    fn plot_info(offset: Option<i64>) -> OutputInfo {
+        OutputInfo::Plot(PlotInfo {
+            title: None,
+            color: None,
+            linewidth: None,
+            style: None,
+            opacity: None,
+            trackprice: None,
+            histbase: None,
+            offset,
+            join: None,
+            editable: None,
+            show_last: None,
+            display: None,
+        })
+    }
+
+    #[test]
+    fn plot_offsets_test() {
+        let outputs = vec![plot_info(Some(2)), plot_info(Some(-1)), plot_info(None)];
+        let gen_data = |vals: Vec<Option<f64>>| {
+            let colors = StrOptionsData {
+                options: vec![String::from("#FF0000")],
+                values: vals.iter().map(|_| Some(0)).collect(),
+            };
+            Some(OutputData::new_with_sc(vec![vals], vec![colors]))
+        };
+        let mut offsets = PlotOffsets::new();
+        let vals = vec![Some(1f64), Some(2f64), Some(3f64)];
+        let mut collect = OutputDataCollect::new(
+            0,
+            3,
+            vec![
+                gen_data(vals.clone()),
+                gen_data(vals.clone()),
+                gen_data(vals),
+            ],
+        );
+        offsets.apply(&outputs, &mut collect);
+        assert_eq!(
+            collect.data_list[0].as_ref().unwrap().series[0],
+            vec![None, None, Some(1f64)]
+        );
+        assert_eq!(
+            collect.data_list[0].as_ref().unwrap().colors[0].values,
+            vec![None, None, Some(0)]
+        );
+        assert_eq!(
+            collect.data_list[1].as_ref().unwrap().series[0],
+            vec![Some(2f64), Some(3f64), None]
+        );
+        assert_eq!(
+            collect.data_list[2].as_ref().unwrap().series[0],
+            vec![Some(1f64), Some(2f64), Some(3f64)]
+        );
+        assert_eq!(
+            collect.projections,
+            vec![Some(vec![Some(2f64), Some(3f64)]), None, None]
+        );
+
+        // The update runs the last bar again, the shifted values come from the previous run.
+        let vals = vec![Some(4f64), Some(5f64)];
+        let mut collect = OutputDataCollect::new(
+            2,
+            4,
+            vec![
+                gen_data(vals.clone()),
+                gen_data(vals.clone()),
+                gen_data(vals),
+            ],
+        );
+        offsets.apply(&outputs, &mut collect);
+        assert_eq!(
+            collect.data_list[0].as_ref().unwrap().series[0],
+            vec![Some(1f64), Some(2f64)]
+        );
+        assert_eq!(
+            collect.data_list[1].as_ref().unwrap().series[0],
+            vec![Some(5f64), None]
+        );
+        assert_eq!(collect.projections[0], Some(vec![Some(4f64), Some(5f64)]));
+    }
 }
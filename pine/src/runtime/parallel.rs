@@ -0,0 +1,58 @@
+// Evaluate the independent jobs and return the results in the same order as the jobs.
+// The runtime values(PineRef) are not thread-safe, so only the jobs that work on the owned
+// data(e.g. the data fetched by security requests, the big window statistics) can be dispatched
+// here. The jobs are evaluated by rayon if the `parallel` feature is enabled, otherwise they are
+// evaluated one by one.
+#[cfg(feature = "parallel")]
+pub fn eval_jobs<T, F>(jobs: Vec<F>) -> Vec<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    use rayon::prelude::*;
+
+    jobs.into_par_iter().map(|job| job()).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn eval_jobs<T, F>(jobs: Vec<F>) -> Vec<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    jobs.into_iter().map(|job| job()).collect()
+}
+
+// The order to evaluate the statements of the levels generated by
+// `syntax::stmt_deps::gen_stmt_levels`. The levels are evaluated in the ascending order and the
+// statements of the same level keep the source order.
+pub fn level_order(levels: &[usize]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    order.sort_by_key(|&i| levels[i]);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eval_jobs_test() {
+        let data: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        let jobs: Vec<_> = (1..5)
+            .map(|n| {
+                let data = &data;
+                move || data.iter().step_by(n).sum::<f64>()
+            })
+            .collect();
+        assert_eq!(
+            eval_jobs(jobs),
+            vec![499500f64, 249500f64, 166833f64, 124500f64]
+        );
+    }
+
+    #[test]
+    fn level_order_test() {
+        assert_eq!(level_order(&[1, 0, 2, 0, 1]), vec![1, 3, 0, 4, 2]);
+    }
+}
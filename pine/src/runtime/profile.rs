@@ -0,0 +1,214 @@
+use crate::ast::input::StrRange;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// The call count and cumulative time of one builtin function.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CallProfile {
+    pub name: String,
+    pub count: u64,
+    pub time: Duration,
+}
+
+// The execution count and cumulative time of one statement, the time includes the nested statements.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct StatementProfile {
+    pub range: StrRange,
+    pub count: u64,
+    pub time: Duration,
+}
+
+// The count of the history references like `close[n]` at one range that get na because the index
+// is negative or beyond the available history.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct HistoryIndexProfile {
+    pub range: StrRange,
+    pub negative: u64,
+    pub out_of_range: u64,
+}
+
+// The runtime statistics of the script. The calls and statements are only collected
+// with the `profile` feature, they are ordered by the cumulative time descending.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct Profile {
+    pub calls: Vec<CallProfile>,
+    pub statements: Vec<StatementProfile>,
+    // The bytes of the history buffers of all the series variables.
+    pub series_memory: usize,
+    // The invalid history indexes are always collected, they are ordered by the range.
+    pub history_indexes: Vec<HistoryIndexProfile>,
+}
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    calls: HashMap<String, (u64, Duration)>,
+    // The statements are keyed by the address of the statement node.
+    statements: HashMap<usize, (StrRange, u64, Duration)>,
+    // The negative and out-of-range counts of the history references keyed by the range.
+    history_indexes: Vec<HistoryIndexProfile>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    pub fn record_call(&mut self, name: &str, time: Duration) {
+        match self.calls.get_mut(name) {
+            Some((count, total)) => {
+                *count += 1;
+                *total += time;
+            }
+            None => {
+                self.calls.insert(String::from(name), (1, time));
+            }
+        }
+    }
+
+    pub fn record_statement(&mut self, id: usize, range: StrRange, time: Duration) {
+        let item = self
+            .statements
+            .entry(id)
+            .or_insert((range, 0, Duration::default()));
+        item.1 += 1;
+        item.2 += time;
+    }
+
+    pub fn record_history_index(&mut self, range: StrRange, negative: bool) {
+        let pos = self.history_indexes.iter().position(|h| h.range == range);
+        let item = match pos {
+            Some(i) => &mut self.history_indexes[i],
+            None => {
+                self.history_indexes.push(HistoryIndexProfile {
+                    range,
+                    negative: 0,
+                    out_of_range: 0,
+                });
+                self.history_indexes.last_mut().unwrap()
+            }
+        };
+        if negative {
+            item.negative += 1;
+        } else {
+            item.out_of_range += 1;
+        }
+    }
+
+    pub fn gen_profile(&self, series_memory: usize) -> Profile {
+        let mut calls: Vec<_> = self
+            .calls
+            .iter()
+            .map(|(name, (count, time))| CallProfile {
+                name: name.clone(),
+                count: *count,
+                time: *time,
+            })
+            .collect();
+        calls.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.name.cmp(&b.name)));
+
+        let mut statements: Vec<_> = self
+            .statements
+            .values()
+            .map(|(range, count, time)| StatementProfile {
+                range: *range,
+                count: *count,
+                time: *time,
+            })
+            .collect();
+        statements.sort_by(|a, b| {
+            b.time.cmp(&a.time).then_with(|| {
+                a.range
+                    .start
+                    .partial_cmp(&b.range.start)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+
+        let mut history_indexes = self.history_indexes.clone();
+        history_indexes.sort_by(|a, b| {
+            a.range
+                .start
+                .partial_cmp(&b.range.start)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Profile {
+            calls,
+            statements,
+            series_memory,
+            history_indexes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::input::Position;
+
+    #[test]
+    fn profiler_test() {
+        let mut profiler = Profiler::new();
+        profiler.record_call("sma", Duration::from_millis(2));
+        profiler.record_call("ema", Duration::from_millis(5));
+        profiler.record_call("sma", Duration::from_millis(4));
+
+        let range = StrRange::new(Position::new(0, 0), Position::new(0, 10));
+        profiler.record_statement(1, range, Duration::from_millis(3));
+        profiler.record_statement(1, range, Duration::from_millis(3));
+
+        let profile = profiler.gen_profile(16);
+        assert_eq!(
+            profile.calls,
+            vec![
+                CallProfile {
+                    name: String::from("sma"),
+                    count: 2,
+                    time: Duration::from_millis(6)
+                },
+                CallProfile {
+                    name: String::from("ema"),
+                    count: 1,
+                    time: Duration::from_millis(5)
+                }
+            ]
+        );
+        assert_eq!(
+            profile.statements,
+            vec![StatementProfile {
+                range,
+                count: 2,
+                time: Duration::from_millis(6)
+            }]
+        );
+        assert_eq!(profile.series_memory, 16);
+        assert!(profile.history_indexes.is_empty());
+    }
+
+    #[test]
+    fn history_index_test() {
+        let mut profiler = Profiler::new();
+        let range1 = StrRange::new(Position::new(1, 0), Position::new(1, 8));
+        let range2 = StrRange::new(Position::new(0, 4), Position::new(0, 12));
+        profiler.record_history_index(range1, true);
+        profiler.record_history_index(range2, false);
+        profiler.record_history_index(range1, true);
+        profiler.record_history_index(range1, false);
+
+        assert_eq!(
+            profiler.gen_profile(0).history_indexes,
+            vec![
+                HistoryIndexProfile {
+                    range: range2,
+                    negative: 0,
+                    out_of_range: 1
+                },
+                HistoryIndexProfile {
+                    range: range1,
+                    negative: 2,
+                    out_of_range: 1
+                }
+            ]
+        );
+    }
+}
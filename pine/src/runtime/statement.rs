@@ -8,7 +8,7 @@ use super::runtime_convert::convert;
 use crate::ast::input::StrRange;
 use crate::ast::name::VarName;
 use crate::ast::stat_expr_types::{
-    Assignment, Block, DataType, ForRange, FunctionCall, FunctionDef, IfThenElse, Statement,
+    Assignment, Block, DataType, ForIn, ForRange, FunctionCall, FunctionDef, IfThenElse, Statement,
     VarAssignment, VarIndex,
 };
 use crate::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
@@ -18,6 +18,8 @@ use crate::types::{
     RuntimeErr, SecondType, Series, Tuple, NA,
 };
 use std::fmt::Debug;
+#[cfg(feature = "profile")]
+use {crate::ast::stat_expr_types::Exp, std::time::Instant};
 
 impl<'a> StmtRunner<'a> for Statement<'a> {
     fn st_run(&'a self, context: &mut dyn Ctx<'a>) -> Result<(), PineRuntimeError> {
@@ -29,6 +31,7 @@ impl<'a> StmtRunner<'a> for Statement<'a> {
             Statement::VarAssignment(ref var_assign) => var_assign.st_run(context),
             Statement::Ite(ref ite) => StmtRunner::st_run(ite.as_ref(), context),
             Statement::ForRange(ref fr) => StmtRunner::st_run(fr.as_ref(), context),
+            Statement::ForIn(ref fi) => StmtRunner::st_run(fi.as_ref(), context),
             Statement::FuncCall(ref fun_call) => StmtRunner::st_run(fun_call.as_ref(), context),
             Statement::FuncDef(ref fun_def) => fun_def.st_run(context),
             Statement::Exp(ref exp) => exp.st_run(context),
@@ -117,6 +120,12 @@ pub fn process_assign_val<'a>(
     let index = VarIndex::new(varid, 0);
     match context.move_var(index) {
         None => {
+            // The series may be shared with other variable, copy it on creating the new variable.
+            let true_val = if is_series_val(&true_val) {
+                true_val.into_unique()
+            } else {
+                true_val
+            };
             // If the syntax type is specified, then we convert the val to this type
             if let Some(syntax_type) = syntax_type {
                 // let true_val = true_val.copy_inner();
@@ -159,6 +168,10 @@ pub fn process_assign_val<'a>(
                 use crate::libs::label::PerLabelItem;
                 update_series::<PerLabelItem>(context, index, current_val, true_val)
             }
+            ((FirstType::Polyline, SecondType::Series), _) => {
+                use crate::libs::polyline::PerPolylineItem;
+                update_series::<PerPolylineItem>(context, index, current_val, true_val)
+            }
             ((_, SecondType::Series), _) | (_, (_, SecondType::Series)) => {
                 // Err(RuntimeErr::TypeMismatch(format!(
                 //     "Series type can only be Int, Float, Bool, Color, String, but get {:?}",
@@ -211,12 +224,10 @@ pub fn process_assign_val<'a>(
                     Ok(true_val)
                 }
             }
-            _ => {
-                // return Err(RuntimeErr::TypeMismatch(format!(
-                //     "Variable type can only be Int, Float, Bool, Color, String, but get {:?}",
-                //     true_val.get_type()
-                // )))
-                Err(RuntimeErr::UnknownRuntimeErr)
+            ((_, SecondType::Array), (_, SecondType::Array)) => {
+                // The array variable refers to the new array.
+                context.create_var(varid, true_val.clone());
+                Ok(true_val)
             }
         },
     }
@@ -254,10 +265,61 @@ where
     }
 }
 
-// Check if the item is Line/Label object or not.
+// Cast the value to the data type implicitly, the value is casted to series if it's series or
+// `to_series` is true. The custom types are returned as is.
+pub fn implicity_cast_val<'a>(
+    val: PineRef<'a>,
+    data_type: &DataType<'a>,
+    to_series: bool,
+) -> Result<PineRef<'a>, RuntimeErr> {
+    let is_series = to_series || val.get_type().1 == SecondType::Series;
+    match data_type {
+        DataType::Int if is_series => Ok(Series::<Int>::implicity_from(val)?.into_pf()),
+        DataType::Int => Ok(Int::implicity_from(val)?.into_pf()),
+        DataType::Bool if is_series => Ok(Series::<Bool>::implicity_from(val)?.into_pf()),
+        DataType::Bool => Ok(Bool::implicity_from(val)?.into_pf()),
+        DataType::Float if is_series => Ok(Series::<Float>::implicity_from(val)?.into_pf()),
+        DataType::Float => Ok(Float::implicity_from(val)?.into_pf()),
+        DataType::Color if is_series => Ok(Series::<Color>::implicity_from(val)?.into_pf()),
+        DataType::Color => Ok(Color::implicity_from(val)?.into_pf()),
+        DataType::String if is_series => Ok(Series::<String>::implicity_from(val)?.into_pf()),
+        DataType::String => Ok(String::implicity_from(val)?.into_pf()),
+        DataType::Custom(_) => Ok(val),
+    }
+}
+
+// The current value of the series is reset after committing, so the variable declared with var
+// should carry the last committed value over to the new bar.
+fn keep_var_series<'a, D>(context: &mut dyn Ctx<'a>, index: VarIndex) -> Result<(), RuntimeErr>
+where
+    D: Default + PineType<'a> + PineStaticType + 'a + PineFrom<'a, D> + Clone + PartialEq + Debug,
+{
+    let mut s: RefData<Series<D>> = Series::implicity_from(context.move_var(index).unwrap())?;
+    if s.get_current() == D::default() {
+        if let Some(last) = s.get_history().last().cloned() {
+            s.update(last);
+        }
+    }
+    context.update_var(index, s.into_pf());
+    Ok(())
+}
+
+// Check if the item is the series value that should not be shared by variables.
+// The Line/Label/Polyline series refer to the same drawing objects, so they are always shared.
+fn is_series_val<'a>(item: &PineRef<'a>) -> bool {
+    match item.get_type() {
+        (FirstType::Line, _) | (FirstType::Label, _) | (FirstType::Polyline, _) => false,
+        (_, SecondType::Series) => true,
+        _ => false,
+    }
+}
+
+// Check if the item is Line/Label/Polyline object or not.
 fn check_shape<'a>(item: &PineRef<'a>) -> bool {
     match item.get_type() {
-        (FirstType::Label, SecondType::Series) | (FirstType::Line, SecondType::Series) => true,
+        (FirstType::Label, SecondType::Series)
+        | (FirstType::Line, SecondType::Series)
+        | (FirstType::Polyline, SecondType::Series) => true,
         _ => false,
     }
 }
@@ -280,38 +342,26 @@ impl<'a> RunnerForName<'a> for Assignment<'a> {
         }
         // For variable declare with var type, it only need initialize once.
         if self.var && context.contains_var_scope(varid) {
-            return Ok(context
-                .get_var(VarIndex::new(varid, 0))
-                .as_ref()
-                .unwrap()
-                .clone());
+            let index = VarIndex::new(varid, 0);
+            match context.get_var(index).as_ref().unwrap().get_type() {
+                (FirstType::Int, SecondType::Series) => keep_var_series::<Int>(context, index)?,
+                (FirstType::Float, SecondType::Series) => keep_var_series::<Float>(context, index)?,
+                (FirstType::Bool, SecondType::Series) => keep_var_series::<Bool>(context, index)?,
+                (FirstType::Color, SecondType::Series) => keep_var_series::<Color>(context, index)?,
+                (FirstType::String, SecondType::Series) => {
+                    keep_var_series::<String>(context, index)?
+                }
+                _ => {}
+            }
+            return Ok(context.get_var(index).as_ref().unwrap().clone());
         }
         // let val = self.val.rv_run(context)?;
         let true_val: PineRef<'a> = match self.var_type {
             None => val,
-            Some(DataType::Int) => match val.get_type().1 {
-                SecondType::Series => Series::<Int>::implicity_from(val)?.into_pf(),
-                _ => Int::implicity_from(val)?.into_pf(),
-            },
-            Some(DataType::Bool) => match val.get_type().1 {
-                SecondType::Series => Series::<Bool>::implicity_from(val)?.into_pf(),
-                _ => Bool::implicity_from(val)?.into_pf(),
-            },
-            Some(DataType::Float) => match val.get_type().1 {
-                SecondType::Series => Series::<Float>::implicity_from(val)?.into_pf(),
-                _ => Float::implicity_from(val)?.into_pf(),
-            },
-            Some(DataType::Color) => match val.get_type().1 {
-                SecondType::Series => Series::<Color>::implicity_from(val)?.into_pf(),
-                _ => Color::implicity_from(val)?.into_pf(),
-            },
-            Some(DataType::String) => match val.get_type().1 {
-                SecondType::Series => Series::<String>::implicity_from(val)?.into_pf(),
-                _ => String::implicity_from(val)?.into_pf(),
-            },
             Some(DataType::Custom(_)) => {
                 type_cast_custom(context, self.cast_index, self.cast_func_index, val)?
             } // Some(DataType::Custom(_)) => val,
+            Some(ref data_type) => implicity_cast_val(val, data_type, false)?,
         };
         if let (FirstType::NA, _) = true_val.get_type() {
             return Err(RuntimeErr::InvalidNADeclarer);
@@ -407,6 +457,16 @@ impl<'a> Runner<'a> for VarAssignment<'a> {
                 use crate::libs::label::PerLabelItem;
                 update_series_range::<PerLabelItem>(ctx_instance, index, exist_val, val, self.range)
             }
+            (FirstType::Polyline, _) => {
+                use crate::libs::polyline::PerPolylineItem;
+                update_series_range::<PerPolylineItem>(
+                    ctx_instance,
+                    index,
+                    exist_val,
+                    val,
+                    self.range,
+                )
+            }
             _ => Err(PineRuntimeError::new(
                 RuntimeErr::NotSupportOperator,
                 self.range,
@@ -482,6 +542,46 @@ fn extract_int(val: Int, range: StrRange) -> Result<i64, PineRuntimeError> {
     }
 }
 
+// Abort the loop if the execution is cancelled, so the long loops can be interrupted in time.
+fn check_cancelled<'a>(context: &mut dyn Ctx<'a>, range: StrRange) -> Result<(), PineRuntimeError> {
+    match downcast_ctx(context).check_cancelled() {
+        Ok(_) => Ok(()),
+        Err(code) => Err(PineRuntimeError::new(code, range)),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum LoopFlow {
+    Next,
+    Break,
+}
+
+// Run one iteration of the loop body. The value of the loop expression is the value returned
+// by the last completed iteration. The iteration interrupted by `break` or `continue` doesn't
+// return a value and the return expression is not evaluated, so the loop that never completes
+// an iteration returns na.
+fn run_loop_body<'a>(
+    blk: &'a Block<'a>,
+    subctx: &mut dyn Ctx<'a>,
+    ret_val: &mut PineRef<'a>,
+) -> Result<LoopFlow, PineRuntimeError> {
+    match blk.run(subctx) {
+        Ok(val) => {
+            *ret_val = val;
+            Ok(LoopFlow::Next)
+        }
+        Err(PineRuntimeError {
+            code: RuntimeErr::Break,
+            range: _,
+        }) => Ok(LoopFlow::Break),
+        Err(PineRuntimeError {
+            code: RuntimeErr::Continue,
+            range: _,
+        }) => Ok(LoopFlow::Next),
+        Err(e) => Err(e),
+    }
+}
+
 impl<'a> Runner<'a> for ForRange<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         let start = extract_int(
@@ -514,34 +614,13 @@ impl<'a> Runner<'a> for ForRange<'a> {
         );
         // iterator index need contain end edge.
         while (step > 0 && iter <= end) || (step < 0 && iter >= end) {
+            check_cancelled(subctx, self.range)?;
             subctx.create_var(self.varid, PineRef::new_box(Some(iter)));
 
-            match self.do_blk.run(subctx) {
-                Ok(val) => {
-                    ret_val = val;
-                    iter += step;
-                }
-                Err(PineRuntimeError {
-                    code: RuntimeErr::Break,
-                    range: _,
-                }) => {
-                    if let Some(ref exp) = self.do_blk.ret_stmt {
-                        ret_val = exp.rv_run(subctx)?
-                    }
-                    break;
-                }
-                Err(PineRuntimeError {
-                    code: RuntimeErr::Continue,
-                    range: _,
-                }) => {
-                    if let Some(ref exp) = self.do_blk.ret_stmt {
-                        ret_val = exp.rv_run(subctx)?
-                    }
-                    iter += step;
-                    continue;
-                }
-                e => return e,
+            if run_loop_body(&self.do_blk, subctx, &mut ret_val)? == LoopFlow::Break {
+                break;
             }
+            iter += step;
             // subctx.clear_declare();
         }
         subctx.set_is_run(true);
@@ -559,17 +638,96 @@ impl<'a> StmtRunner<'a> for ForRange<'a> {
     }
 }
 
+fn array_items<'a, D>(val: PineRef<'a>) -> Vec<PineRef<'a>>
+where
+    D: PineStaticType + PineType<'a> + Clone + PartialEq + Debug + 'a,
+{
+    downcast_pf::<Vec<D>>(val)
+        .unwrap()
+        .into_inner()
+        .into_iter()
+        .map(PineRef::new)
+        .collect()
+}
+
+// Get the elements of the tuple literal or the array value iterated by the for-in statement.
+fn extract_items<'a>(val: PineRef<'a>) -> Result<Vec<PineRef<'a>>, RuntimeErr> {
+    match val.get_type() {
+        (FirstType::Tuple, _) => Ok(downcast_pf::<Tuple>(val).unwrap().into_inner().0),
+        (FirstType::Bool, SecondType::Array) => Ok(array_items::<Bool>(val)),
+        (FirstType::Int, SecondType::Array) => Ok(array_items::<Int>(val)),
+        (FirstType::Float, SecondType::Array) => Ok(array_items::<Float>(val)),
+        (FirstType::Color, SecondType::Array) => Ok(array_items::<Color>(val)),
+        (FirstType::String, SecondType::Array) => Ok(array_items::<String>(val)),
+        _ => Err(RuntimeErr::NotSupportOperator),
+    }
+}
+
+impl<'a> Runner<'a> for ForIn<'a> {
+    fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
+        let items = match extract_items(self.iter.rv_run(context)?) {
+            Ok(items) => items,
+            Err(e) => return Err(PineRuntimeError::new(e, self.iter.range())),
+        };
+        let mut ret_val: PineRef<'a> = PineRef::new_box(NA);
+        let subctx = create_sub_ctx(
+            context,
+            self.ctxid,
+            ContextType::ForRangeBlock,
+            self.get_var_count(),
+            self.get_libfun_count(),
+            self.get_subctx_count(),
+        );
+        for (i, item) in items.into_iter().enumerate() {
+            check_cancelled(subctx, self.range)?;
+            if self.index_var.is_some() {
+                subctx.create_var(self.index_varid, PineRef::new_box(Some(i as i64)));
+            }
+            subctx.create_var(self.varid, convert(item, &self.item_type));
+
+            if run_loop_body(&self.do_blk, subctx, &mut ret_val)? == LoopFlow::Break {
+                break;
+            }
+        }
+        subctx.set_is_run(true);
+        Ok(convert(ret_val, &self.result_type))
+    }
+}
+
+impl<'a> StmtRunner<'a> for ForIn<'a> {
+    fn st_run(&'a self, context: &mut dyn Ctx<'a>) -> Result<(), PineRuntimeError> {
+        match Runner::run(self, context) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// The parameter position of the i-th dict argument. The position is resolved by the syntax pass,
+// the name is only searched for the function call that has not passed through it.
+fn dict_arg_index<'a>(fun_call: &'a FunctionCall<'a>, i: usize) -> Result<usize, PineRuntimeError> {
+    if let Some(indexs) = &fun_call.dict_arg_indexs {
+        return Ok(indexs[i]);
+    }
+    let (name, exp) = &fun_call.dict_args[i];
+    fun_call
+        .func_type
+        .as_ref()
+        .and_then(|t| t.signature.0.iter().position(|s| s.0 == name.value))
+        .ok_or_else(|| PineRuntimeError::new(RuntimeErr::NotValidParam, exp.range()))
+}
+
 fn extract_args_assign<'a>(
     context: &mut dyn Ctx<'a>,
     exp: &'a FunctionCall<'a>,
-) -> Result<(Vec<PineRef<'a>>, Vec<(&'a str, PineRef<'a>)>), PineRuntimeError> {
-    let mut ret_pos = vec![];
+) -> Result<(Vec<PineRef<'a>>, Vec<(usize, PineRef<'a>)>), PineRuntimeError> {
+    let mut ret_pos = Vec::with_capacity(exp.pos_args.len());
     for exp in exp.pos_args.iter() {
         ret_pos.push(exp.run_for_assign(context)?);
     }
-    let mut ret_dict = vec![];
-    for (n, exp) in exp.dict_args.iter() {
-        ret_dict.push((n.value, exp.run_for_assign(context)?));
+    let mut ret_dict = Vec::with_capacity(exp.dict_args.len());
+    for (i, (_, arg)) in exp.dict_args.iter().enumerate() {
+        ret_dict.push((dict_arg_index(exp, i)?, arg.run_for_assign(context)?));
     }
     Ok((ret_pos, ret_dict))
 }
@@ -577,14 +735,14 @@ fn extract_args_assign<'a>(
 fn extract_args<'a>(
     context: &mut dyn Ctx<'a>,
     exp: &'a FunctionCall<'a>,
-) -> Result<(Vec<PineRef<'a>>, Vec<(&'a str, PineRef<'a>)>), PineRuntimeError> {
-    let mut ret_pos = vec![];
+) -> Result<(Vec<PineRef<'a>>, Vec<(usize, PineRef<'a>)>), PineRuntimeError> {
+    let mut ret_pos = Vec::with_capacity(exp.pos_args.len());
     for exp in exp.pos_args.iter() {
         ret_pos.push(exp.rv_run(context)?);
     }
-    let mut ret_dict = vec![];
-    for (n, exp) in exp.dict_args.iter() {
-        ret_dict.push((n.value, exp.rv_run(context)?));
+    let mut ret_dict = Vec::with_capacity(exp.dict_args.len());
+    for (i, (_, arg)) in exp.dict_args.iter().enumerate() {
+        ret_dict.push((dict_arg_index(exp, i)?, arg.rv_run(context)?));
     }
     Ok((ret_pos, ret_dict))
 }
@@ -636,7 +794,9 @@ fn assign_run<'a>(
 ) -> Result<PineRef<'a>, PineRuntimeError> {
     // let result = fun_call.method.run_for_func(context)?;
 
-    println!("Function method type {:?}", method.get_type());
+    #[cfg(feature = "profile")]
+    let start = Instant::now();
+
     let result = match method.get_type() {
         (FirstType::Callable, SecondType::Simple) => {
             let mut callable = downcast_pf::<Callable>(method).unwrap();
@@ -644,7 +804,7 @@ fn assign_run<'a>(
             let func_type = fun_call.func_type.as_ref().unwrap().clone();
             let (pos_args, dict_args) = extract_args_assign(context, fun_call)?;
 
-            let result = callable.call(context, pos_args, dict_args, func_type);
+            let result = callable.call_with_indexs(context, pos_args, dict_args, func_type);
             // ctx_ref.set_is_run(true);
             context.create_runnable(callable.into_rc());
             result
@@ -697,12 +857,33 @@ fn assign_run<'a>(
         }
         _ => Err(RuntimeErr::NotSupportOperator),
     };
+
+    // The user-defined functions have returned, so only the builtin calls are recorded.
+    #[cfg(feature = "profile")]
+    if let Some(profiler) = downcast_ctx(context).get_profiler() {
+        profiler.record_call(&method_name(&fun_call.method), start.elapsed());
+    }
+
     match result {
         Ok(val) => Ok(val),
         Err(code) => Err(PineRuntimeError::new(code, fun_call.range)),
     }
 }
 
+// The name of the called method, e.g. `sma` or `line.new`.
+#[cfg(feature = "profile")]
+fn method_name<'a>(exp: &Exp<'a>) -> String {
+    match exp {
+        Exp::VarName(name) => String::from(name.name.value),
+        Exp::PrefixExp(prefix) => format!(
+            "{}.{}",
+            method_name(&prefix.left_exp),
+            prefix.right_name.value
+        ),
+        _ => String::from("?"),
+    }
+}
+
 impl<'a> Runner<'a> for FunctionCall<'a> {
     fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         let result = self.method.run_for_func(context)?;
@@ -750,11 +931,37 @@ impl<'a> StmtRunner<'a> for FunctionDef<'a> {
     }
 }
 
-impl<'a> Runner<'a> for Block<'a> {
-    fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
-        for st in self.stmts.iter() {
-            st.st_run(context)?;
+fn run_blk_stmt<'a>(
+    st: &'a Statement<'a>,
+    context: &mut dyn Ctx<'a>,
+) -> Result<(), PineRuntimeError> {
+    #[cfg(feature = "profile")]
+    let start = Instant::now();
+
+    st.st_run(context)?;
+
+    #[cfg(feature = "profile")]
+    if let Some(profiler) = downcast_ctx(context).get_profiler() {
+        profiler.record_statement(st as *const Statement as usize, st.range(), start.elapsed());
+    }
+    Ok(())
+}
+
+impl<'a> Block<'a> {
+    // Run the statements by the order of the indexes, e.g. the order of the dependency levels
+    // generated by `runtime::parallel::level_order`.
+    pub fn run_in_order(
+        &'a self,
+        order: &[usize],
+        context: &mut dyn Ctx<'a>,
+    ) -> Result<PineRef<'a>, PineRuntimeError> {
+        for &i in order.iter() {
+            run_blk_stmt(&self.stmts[i], context)?;
         }
+        self.run_ret(context)
+    }
+
+    fn run_ret(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
         if let Some(ref exp) = self.ret_stmt {
             exp.rv_run(context)
         } else {
@@ -763,6 +970,15 @@ impl<'a> Runner<'a> for Block<'a> {
     }
 }
 
+impl<'a> Runner<'a> for Block<'a> {
+    fn run(&'a self, context: &mut dyn Ctx<'a>) -> Result<PineRef<'a>, PineRuntimeError> {
+        for st in self.stmts.iter() {
+            run_blk_stmt(st, context)?;
+        }
+        self.run_ret(context)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1251,9 +1467,10 @@ mod tests {
         let result = Runner::run(&for_range, &mut context);
         assert!(result.is_ok());
 
+        // No iteration is completed before the break statement.
         assert_eq!(
             Int::implicity_from(result.unwrap()),
-            Ok(RefData::new_box(Some(1)))
+            Ok(RefData::new_box(None))
         );
 
         // assert!(context.move_var("a").is_none());
@@ -1288,9 +1505,10 @@ mod tests {
         let result = Runner::run(&for_range, &mut context);
         assert!(result.is_ok());
 
+        // All of the iterations are interrupted by the continue statement.
         assert_eq!(
             Int::implicity_from(result.unwrap()),
-            Ok(RefData::new_box(Some(10)))
+            Ok(RefData::new_box(None))
         );
 
         // assert!(context.move_var("a").is_none());
@@ -0,0 +1,1536 @@
+// The broker emulator behind the `strategy.*` builtins. The orders placed by the script on one bar
+// are filled at the open price of the next bar, the commission, slippage and position sizing models
+// are taken from the arguments of the `strategy` declaration.
+use crate::helper::round_to_mintick;
+use crate::types::{Float, Int, RefData, Series};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum CommissionType {
+    // The percent of the cash volume of the order.
+    Percent,
+    // The money for each contract.
+    CashPerContract,
+    // The money for each order.
+    CashPerOrder,
+}
+
+impl CommissionType {
+    pub fn from_name(s: &str) -> Option<CommissionType> {
+        match s {
+            "percent" => Some(CommissionType::Percent),
+            "cash_per_contract" => Some(CommissionType::CashPerContract),
+            "cash_per_order" => Some(CommissionType::CashPerOrder),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum QtyType {
+    // The number of contracts.
+    Fixed,
+    // The amount of money.
+    Cash,
+    // The percent of the current equity.
+    PercentOfEquity,
+}
+
+impl QtyType {
+    pub fn from_name(s: &str) -> Option<QtyType> {
+        match s {
+            "fixed" => Some(QtyType::Fixed),
+            "cash" => Some(QtyType::Cash),
+            "percent_of_equity" => Some(QtyType::PercentOfEquity),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BrokerConfig {
+    pub initial_capital: f64,
+    pub commission_type: CommissionType,
+    pub commission_value: f64,
+    // The slippage in ticks that is added to the fill price of every market order.
+    pub slippage: i64,
+    pub default_qty_type: QtyType,
+    pub default_qty_value: f64,
+    // The max count of the entries in the same direction, 0 also allows one entry.
+    pub pyramiding: i64,
+    pub mintick: f64,
+    // Calculate the script again after the orders are filled within the bar.
+    pub calc_on_order_fills: bool,
+    // Calculate the script on every tick of the realtime bar, the orders placed by the previous
+    // ticks are not rolled back.
+    pub calc_on_every_tick: bool,
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        BrokerConfig {
+            initial_capital: 1000000f64,
+            commission_type: CommissionType::Percent,
+            commission_value: 0f64,
+            slippage: 0,
+            default_qty_type: QtyType::Fixed,
+            default_qty_value: 1f64,
+            pyramiding: 0,
+            mintick: 0.01,
+            calc_on_order_fills: false,
+            calc_on_every_tick: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Direction {
+    Long,
+    Short,
+}
+
+impl Direction {
+    pub fn from_name(s: &str) -> Option<Direction> {
+        match s {
+            "long" => Some(Direction::Long),
+            "short" => Some(Direction::Short),
+            _ => None,
+        }
+    }
+
+    pub fn sign(&self) -> f64 {
+        match self {
+            Direction::Long => 1f64,
+            Direction::Short => -1f64,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::Long => "long",
+            Direction::Short => "short",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum DrawdownType {
+    // The percent of the peak equity.
+    PercentOfEquity,
+    Cash,
+}
+
+impl DrawdownType {
+    pub fn from_name(s: &str) -> Option<DrawdownType> {
+        match s {
+            "percent_of_equity" => Some(DrawdownType::PercentOfEquity),
+            "cash" => Some(DrawdownType::Cash),
+            _ => None,
+        }
+    }
+}
+
+// The risk rules set by the `strategy.risk.*` builtins.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RiskRules {
+    pub max_drawdown: Option<(f64, DrawdownType)>,
+    pub max_position_size: Option<f64>,
+    // The only direction that can be entered, None means both directions.
+    pub allow_entry_in: Option<Direction>,
+}
+
+// The event that is generated when an order or the equity breaches one risk rule.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RiskEvent {
+    pub bar_index: i32,
+    pub rule: String,
+    pub message: String,
+}
+
+// How the other orders of the same OCA(One-Cancels-All) group are handled when one order is filled.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum OcaType {
+    // Cancel the other orders.
+    Cancel,
+    // Reduce the quantity of the other orders by the filled quantity.
+    Reduce,
+    None,
+}
+
+impl OcaType {
+    pub fn from_name(s: &str) -> Option<OcaType> {
+        match s {
+            "cancel" => Some(OcaType::Cancel),
+            "reduce" => Some(OcaType::Reduce),
+            "none" => Some(OcaType::None),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum OrderKind {
+    Entry(Direction),
+    // Exit the open trades of the entry id, or all of the open trades if the entry id is None.
+    Exit(Option<String>),
+    // Close the open trades of the entry id.
+    Close,
+    CloseAll,
+}
+
+// The order that waits to be filled. The market order is filled on the next bar, the limit and stop
+// orders are kept until the price reaches them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Order {
+    pub id: String,
+    pub kind: OrderKind,
+    pub qty: Option<f64>,
+    pub limit: Option<f64>,
+    pub stop: Option<f64>,
+    // The profit and loss targets of the exit order in ticks from the entry price.
+    pub profit: Option<f64>,
+    pub loss: Option<f64>,
+    // The trailing stop of the exit order, the activation and offset are in ticks from the entry
+    // price and the best price.
+    pub trail_points: Option<f64>,
+    pub trail_offset: Option<f64>,
+    pub oca: Option<(String, OcaType)>,
+}
+
+impl Order {
+    pub fn new(id: String, kind: OrderKind, qty: Option<f64>) -> Order {
+        Order {
+            id,
+            kind,
+            qty,
+            limit: None,
+            stop: None,
+            profit: None,
+            loss: None,
+            trail_points: None,
+            trail_offset: None,
+            oca: None,
+        }
+    }
+
+    fn is_market(&self) -> bool {
+        self.limit.is_none()
+            && self.stop.is_none()
+            && self.profit.is_none()
+            && self.loss.is_none()
+            && self.trail_points.is_none()
+    }
+
+    // The new order replaces the pending entry or exit order with the same id.
+    fn is_replaced_by(&self, other: &Order) -> bool {
+        match (&self.kind, &other.kind) {
+            (OrderKind::Entry(_), OrderKind::Entry(_))
+            | (OrderKind::Exit(_), OrderKind::Exit(_)) => self.id == other.id,
+            _ => false,
+        }
+    }
+}
+
+// The time and prices of the bar that fills the orders.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct BarPrice {
+    pub time: Int,
+    pub open: Float,
+    pub high: Float,
+    pub low: Float,
+    pub close: Float,
+}
+
+// The trailing stop of the exit order for one trade. It's activated when the best price of the
+// trade reaches the activation price, then the stop follows the best price by the offset.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct TrailStop {
+    activation: f64,
+    offset: f64,
+    best: f64,
+}
+
+enum FillResult {
+    Filled(f64),
+    // The order is removed without being filled.
+    Dropped,
+    Pending,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub entry_id: String,
+    pub direction: Direction,
+    pub qty: f64,
+    pub entry_bar: i32,
+    pub entry_time: Int,
+    pub entry_price: f64,
+    pub exit_bar: Option<i32>,
+    pub exit_time: Int,
+    pub exit_price: Option<f64>,
+    // The commission of both the entry and the exit.
+    pub commission: f64,
+    // The profit without the commission.
+    pub gross_profit: f64,
+    // The profit after the commission.
+    pub profit: f64,
+    // The best price since the entry, the highest for the long trade and the lowest for the short.
+    #[serde(skip)]
+    pub best_price: f64,
+}
+
+// The performance summary of the strategy that is exported to the host.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct StrategyResult {
+    pub initial_capital: f64,
+    pub net_profit: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    pub commission_paid: f64,
+    pub open_profit: f64,
+    pub equity: f64,
+    pub position_size: f64,
+    pub win_trades: i64,
+    pub loss_trades: i64,
+    pub closed_trades: Vec<Trade>,
+    pub open_trades: Vec<Trade>,
+    pub risk_events: Vec<RiskEvent>,
+    // Whether the trading is stopped by the max drawdown rule.
+    pub halted: bool,
+    // The metrics of every committed bar.
+    pub bar_metrics: Vec<BarMetrics>,
+}
+
+// The strategy metrics at the close of one bar.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BarMetrics {
+    pub equity: f64,
+    pub open_profit: f64,
+    pub net_profit: f64,
+}
+
+// The summary metrics of the closed trades.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct StrategySummary {
+    pub net_profit: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    pub commission_paid: f64,
+    pub total_trades: i64,
+    pub win_rate: Option<f64>,
+    // The gross profit divided by the gross loss.
+    pub profit_factor: Option<f64>,
+    // The mean divided by the standard deviation of the returns of the closed trades.
+    pub sharpe_ratio: Option<f64>,
+}
+
+// One row of the exported trade list.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub entry_id: String,
+    pub direction: Direction,
+    pub entry_time: Int,
+    pub exit_time: Int,
+    pub qty: f64,
+    pub entry_price: f64,
+    pub exit_price: Option<f64>,
+    pub profit: f64,
+    // The equity after the trade is closed.
+    pub cumulative_equity: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct StrategyExport {
+    summary: StrategySummary,
+    trades: Vec<TradeRecord>,
+}
+
+impl StrategyResult {
+    pub fn gen_trade_records(&self) -> Vec<TradeRecord> {
+        let mut equity = self.initial_capital;
+        self.closed_trades
+            .iter()
+            .map(|t| {
+                equity += t.profit;
+                TradeRecord {
+                    entry_id: t.entry_id.clone(),
+                    direction: t.direction,
+                    entry_time: t.entry_time,
+                    exit_time: t.exit_time,
+                    qty: t.qty,
+                    entry_price: t.entry_price,
+                    exit_price: t.exit_price,
+                    profit: t.profit,
+                    cumulative_equity: equity,
+                }
+            })
+            .collect()
+    }
+
+    pub fn gen_summary(&self) -> StrategySummary {
+        let total = self.closed_trades.len();
+        let win_rate = if total > 0 {
+            Some(self.win_trades as f64 / total as f64)
+        } else {
+            None
+        };
+        let profit_factor = if self.gross_loss > 0f64 {
+            Some(self.gross_profit / self.gross_loss)
+        } else {
+            None
+        };
+
+        // The return of each trade is the profit divided by the equity before the trade.
+        let mut equity = self.initial_capital;
+        let returns: Vec<f64> = self
+            .closed_trades
+            .iter()
+            .map(|t| {
+                let ret = t.profit / equity;
+                equity += t.profit;
+                ret
+            })
+            .collect();
+        let sharpe_ratio = if returns.len() > 1 {
+            let n = returns.len() as f64;
+            let mean = sum(returns.iter().cloned()) / n;
+            let var = sum(returns.iter().map(|r| (r - mean).powi(2))) / (n - 1f64);
+            if var > 0f64 {
+                Some(mean / var.sqrt())
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        StrategySummary {
+            net_profit: self.net_profit,
+            gross_profit: self.gross_profit,
+            gross_loss: self.gross_loss,
+            commission_paid: self.commission_paid,
+            total_trades: total as i64,
+            win_rate,
+            profit_factor,
+            sharpe_ratio,
+        }
+    }
+
+    // Export the summary and the trade list as a JSON object.
+    pub fn to_json(&self) -> String {
+        let export = StrategyExport {
+            summary: self.gen_summary(),
+            trades: self.gen_trade_records(),
+        };
+        serde_json::to_string(&export).unwrap()
+    }
+
+    // Export the trade list as CSV, followed by an empty line and the summary metrics.
+    pub fn to_csv(&self) -> String {
+        let opt_f64 = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let opt_i64 = |v: Option<i64>| v.map(|v| v.to_string()).unwrap_or_default();
+
+        let mut lines = vec![String::from(
+            "entry_id,direction,entry_time,exit_time,qty,entry_price,exit_price,profit,cumulative_equity",
+        )];
+        for r in self.gen_trade_records() {
+            lines.push(format!(
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&r.entry_id),
+                r.direction.name(),
+                opt_i64(r.entry_time),
+                opt_i64(r.exit_time),
+                r.qty,
+                r.entry_price,
+                opt_f64(r.exit_price),
+                r.profit,
+                r.cumulative_equity
+            ));
+        }
+
+        let summary = self.gen_summary();
+        lines.push(String::new());
+        lines.push(String::from("metric,value"));
+        for (name, val) in [
+            ("net_profit", Some(summary.net_profit)),
+            ("gross_profit", Some(summary.gross_profit)),
+            ("gross_loss", Some(summary.gross_loss)),
+            ("commission_paid", Some(summary.commission_paid)),
+            ("total_trades", Some(summary.total_trades as f64)),
+            ("win_rate", summary.win_rate),
+            ("profit_factor", summary.profit_factor),
+            ("sharpe_ratio", summary.sharpe_ratio),
+        ] {
+            lines.push(format!("{},{}", name, opt_f64(val)));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+// Quote the CSV field that contains the separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        String::from(s)
+    }
+}
+
+// The part of the broker state that can be rolled back.
+#[derive(Debug, PartialEq, Clone, Default)]
+struct BrokerState {
+    pending: Vec<Order>,
+    open_trades: Vec<Trade>,
+    // The count of the closed trades, the closed trades are only appended.
+    closed_count: usize,
+    // The count of the risk events, the risk events are only appended.
+    event_count: usize,
+    last_price: Float,
+    // The price of the previous tick of the bar, None before the first tick.
+    tick_from: Option<f64>,
+    bar_time: Int,
+    peak_equity: Option<f64>,
+    halted: bool,
+}
+
+// The state of the realtime bar that is rolled back for the next tick. The orders and trades of the
+// previous ticks are kept if the strategy is calculated on every tick.
+#[derive(Debug, PartialEq, Clone)]
+struct TickState {
+    bar_index: i32,
+    state: BrokerState,
+    closed_trades: Vec<Trade>,
+    risk_events: Vec<RiskEvent>,
+}
+
+// The series of the metrics read by `strategy.equity`, `strategy.openprofit` and
+// `strategy.netprofit`, shared with the script so reading them doesn't copy the history.
+#[derive(Debug, PartialEq)]
+struct MetricSeries<'a> {
+    equity: RefData<Series<'a, Float>>,
+    open_profit: RefData<Series<'a, Float>>,
+    net_profit: RefData<Series<'a, Float>>,
+}
+
+impl<'a> MetricSeries<'a> {
+    fn new() -> MetricSeries<'a> {
+        MetricSeries {
+            equity: RefData::new_rc(Series::new()),
+            open_profit: RefData::new_rc(Series::new()),
+            net_profit: RefData::new_rc(Series::new()),
+        }
+    }
+
+    fn commit(&mut self, metrics: BarMetrics) {
+        self.equity.update_commit(Some(metrics.equity));
+        self.open_profit.update_commit(Some(metrics.open_profit));
+        self.net_profit.update_commit(Some(metrics.net_profit));
+    }
+
+    fn roll_back(&mut self) {
+        self.equity.roll_back();
+        self.open_profit.roll_back();
+        self.net_profit.roll_back();
+    }
+
+    fn gen_bar_metrics(&self) -> Vec<BarMetrics> {
+        let equity = self.equity.get_history().iter();
+        let open_profit = self.open_profit.get_history().iter();
+        let net_profit = self.net_profit.get_history().iter();
+        equity
+            .zip(open_profit)
+            .zip(net_profit)
+            .map(|((e, o), n)| BarMetrics {
+                equity: e.unwrap_or_default(),
+                open_profit: o.unwrap_or_default(),
+                net_profit: n.unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Broker<'a> {
+    config: BrokerConfig,
+    state: BrokerState,
+    // The index of the bar of the state.
+    cur_bar: i32,
+    tick_state: Option<TickState>,
+    closed_trades: Vec<Trade>,
+    risk: RiskRules,
+    risk_events: Vec<RiskEvent>,
+    // The committed states of the previous bars.
+    history: Vec<BrokerState>,
+    // The metrics of the committed bars.
+    metrics: MetricSeries<'a>,
+}
+
+impl<'a> Broker<'a> {
+    pub fn new(config: BrokerConfig) -> Broker<'a> {
+        Broker {
+            config,
+            state: BrokerState::default(),
+            cur_bar: -1,
+            tick_state: None,
+            closed_trades: vec![],
+            risk: RiskRules::default(),
+            risk_events: vec![],
+            history: vec![],
+            metrics: MetricSeries::new(),
+        }
+    }
+
+    pub fn get_config(&self) -> &BrokerConfig {
+        &self.config
+    }
+
+    pub fn get_risk_rules(&mut self) -> &mut RiskRules {
+        &mut self.risk
+    }
+
+    // The limit and stop prices are rounded to the min tick like the exchange does.
+    pub fn place_order(&mut self, mut order: Order) {
+        let mintick = self.config.mintick;
+        order.limit = order.limit.map(|p| round_to_mintick(p, mintick));
+        order.stop = order.stop.map(|p| round_to_mintick(p, mintick));
+        self.state.pending.retain(|o| !o.is_replaced_by(&order));
+        self.state.pending.push(order);
+    }
+
+    fn push_event(&mut self, bar_index: i32, rule: &str, message: String) {
+        self.risk_events.push(RiskEvent {
+            bar_index,
+            rule: String::from(rule),
+            message,
+        });
+        self.state.event_count = self.risk_events.len();
+    }
+
+    // Fill the pending orders with the prices of the bar and mark the open trades to the close price.
+    pub fn on_bar(&mut self, bar_index: i32, bar: BarPrice) {
+        for price in self.start_bar(bar_index, bar) {
+            self.on_tick(bar_index, price);
+        }
+        self.end_bar(bar_index, bar);
+    }
+
+    // Start the bar and get the prices that the bar reaches in order. The price moves from the open
+    // to the nearer one of the high and low first, then to the other one and the close. The bar
+    // that is rolled back by the realtime tick continues from the last tick with the close.
+    pub fn start_bar(&mut self, bar_index: i32, bar: BarPrice) -> Vec<f64> {
+        if let Some(tick) = self.tick_state.take() {
+            if tick.bar_index == bar_index {
+                self.state = tick.state;
+                self.closed_trades.extend(tick.closed_trades);
+                self.risk_events.extend(tick.risk_events);
+                self.state.bar_time = bar.time;
+                return bar.close.into_iter().collect();
+            }
+        }
+        self.state.bar_time = bar.time;
+        self.state.tick_from = None;
+        let open = match bar.open.or(bar.close) {
+            Some(open) => open,
+            None => return vec![],
+        };
+        let close = bar.close.unwrap_or(open);
+        let high = bar.high.unwrap_or_else(|| open.max(close));
+        let low = bar.low.unwrap_or_else(|| open.min(close));
+        if high - open <= open - low {
+            vec![open, high, low, close]
+        } else {
+            vec![open, low, high, close]
+        }
+    }
+
+    // Fill the pending orders that are reached when the price moves from the previous tick of the
+    // bar to this price. Return the count of the filled orders.
+    pub fn on_tick(&mut self, bar_index: i32, price: f64) -> usize {
+        let mut count = 0;
+        let mut orders = std::mem::take(&mut self.state.pending);
+        let mut i = 0;
+        while i < orders.len() {
+            match self.fill(bar_index, price, &orders[i]) {
+                FillResult::Filled(qty) => {
+                    let order = orders.remove(i);
+                    if let Some((name, oca_type)) = order.oca {
+                        apply_oca(&mut orders, &name, oca_type, qty);
+                    }
+                    count += 1;
+                    // The filled order may let the previous orders be filled.
+                    i = 0;
+                }
+                FillResult::Dropped => {
+                    orders.remove(i);
+                }
+                FillResult::Pending => i += 1,
+            }
+        }
+        self.state.pending = orders;
+
+        for t in self.state.open_trades.iter_mut() {
+            t.best_price = match t.direction {
+                Direction::Long => t.best_price.max(price),
+                Direction::Short => t.best_price.min(price),
+            };
+        }
+        self.state.tick_from = Some(price);
+        self.state.last_price = Some(price);
+        self.cur_bar = bar_index;
+        count
+    }
+
+    pub fn end_bar(&mut self, bar_index: i32, bar: BarPrice) {
+        if bar.close.is_some() {
+            self.state.last_price = bar.close;
+        }
+        self.cur_bar = bar_index;
+        self.check_drawdown(bar_index);
+    }
+
+    // Close all of the positions and stop trading when the drawdown reaches the limit.
+    fn check_drawdown(&mut self, bar_index: i32) {
+        let equity = self.equity();
+        let peak = match self.state.peak_equity {
+            Some(peak) if peak > equity => peak,
+            _ => equity,
+        };
+        self.state.peak_equity = Some(peak);
+        let (value, dd_type) = match self.risk.max_drawdown {
+            Some(rule) if !self.state.halted => rule,
+            _ => return,
+        };
+        let limit = match dd_type {
+            DrawdownType::Cash => value,
+            DrawdownType::PercentOfEquity => peak * value / 100f64,
+        };
+        let drawdown = peak - equity;
+        if drawdown >= limit {
+            self.state.pending.clear();
+            if let Some(price) = self.state.last_price {
+                self.close_trades(bar_index, price, true, |_| true);
+            }
+            self.state.halted = true;
+            self.push_event(
+                bar_index,
+                "max_drawdown",
+                format!(
+                    "The drawdown {} reaches the limit {}, all of the positions are closed.",
+                    drawdown, limit
+                ),
+            );
+        }
+    }
+
+    pub fn commit(&mut self) {
+        self.history.push(self.state.clone());
+        let metrics = self.gen_bar_metrics();
+        self.metrics.commit(metrics);
+    }
+
+    pub fn roll_back(&mut self) {
+        self.history.pop();
+        self.metrics.roll_back();
+        let state = self.history.last().cloned().unwrap_or_default();
+        let closed_trades = self.closed_trades.split_off(state.closed_count);
+        let risk_events = self.risk_events.split_off(state.event_count);
+        // Only the last bar is the realtime bar that receives the ticks.
+        self.tick_state = match self.tick_state {
+            None if self.config.calc_on_every_tick => Some(TickState {
+                bar_index: self.cur_bar,
+                state: std::mem::replace(&mut self.state, state),
+                closed_trades,
+                risk_events,
+            }),
+            _ => {
+                self.state = state;
+                None
+            }
+        };
+        self.cur_bar -= 1;
+    }
+
+    fn fill_price(&self, price: f64, buy: bool) -> f64 {
+        let slippage = self.config.slippage as f64 * self.config.mintick;
+        if buy {
+            price + slippage
+        } else {
+            price - slippage
+        }
+    }
+
+    fn commission(&self, price: f64, qty: f64) -> f64 {
+        match self.config.commission_type {
+            CommissionType::Percent => price * qty * self.config.commission_value / 100f64,
+            CommissionType::CashPerContract => qty * self.config.commission_value,
+            CommissionType::CashPerOrder => self.config.commission_value,
+        }
+    }
+
+    fn order_qty(&self, qty: Option<f64>, price: f64) -> f64 {
+        if let Some(qty) = qty {
+            return qty;
+        }
+        let value = self.config.default_qty_value;
+        match self.config.default_qty_type {
+            QtyType::Fixed => value,
+            QtyType::Cash => value / price,
+            QtyType::PercentOfEquity => self.equity() * value / 100f64 / price,
+        }
+    }
+
+    // Get the price that fills the order when the price moves from the previous tick or fill to
+    // this tick, and whether the slippage is applied to the price.
+    fn trigger_price(
+        from: Option<f64>,
+        to: f64,
+        buy: bool,
+        limit: Option<f64>,
+        stop: Option<f64>,
+        trail: Option<TrailStop>,
+    ) -> Option<(f64, bool)> {
+        if limit.is_none() && stop.is_none() && trail.is_none() {
+            return Some((to, true));
+        }
+        // The trailing stop follows the best price before this tick, the buy order exits the short trade.
+        let trail_stop = match trail {
+            Some(t) if buy && t.best <= t.activation => Some(t.best + t.offset),
+            Some(t) if !buy && t.best >= t.activation => Some(t.best - t.offset),
+            _ => None,
+        };
+        // The tighter one of the stop and the trailing stop is used.
+        let stop = match (stop, trail_stop) {
+            (Some(s), Some(t)) if buy => Some(s.min(t)),
+            (Some(s), Some(t)) => Some(s.max(t)),
+            (s, t) => s.or(t),
+        };
+        // The order is filled at the start of the move if the price is already over it, like the
+        // gap at the open.
+        let from = from.unwrap_or(to);
+        let reach = |level: f64, above: bool| match above {
+            true if from >= level => Some(from),
+            true if to >= level => Some(level),
+            false if from <= level => Some(from),
+            false if to <= level => Some(level),
+            _ => None,
+        };
+        let stop_price = stop.and_then(|s| reach(s, buy));
+        let limit_price = limit.and_then(|l| reach(l, !buy));
+        // The stop is checked first because it is the worse one at the gap.
+        match (stop_price, limit_price) {
+            (Some(price), _) => Some((price, true)),
+            (None, Some(price)) => Some((price, false)),
+            (None, None) => None,
+        }
+    }
+
+    fn fill(&mut self, bar_index: i32, price: f64, order: &Order) -> FillResult {
+        let from = self.state.tick_from;
+        let unfilled = if order.is_market() {
+            FillResult::Dropped
+        } else {
+            FillResult::Pending
+        };
+        match &order.kind {
+            OrderKind::Entry(direction) => {
+                let direction = *direction;
+                if self.state.halted {
+                    return FillResult::Dropped;
+                }
+                let same_count = self
+                    .state
+                    .open_trades
+                    .iter()
+                    .filter(|t| t.direction == direction)
+                    .count() as i64;
+                if same_count >= self.config.pyramiding.max(1) {
+                    return unfilled;
+                }
+                let buy = direction == Direction::Long;
+                let (price, slippage) =
+                    match Self::trigger_price(from, price, buy, order.limit, order.stop, None) {
+                        Some(res) => res,
+                        None => return FillResult::Pending,
+                    };
+                // The next orders of the tick are checked from the price of this fill.
+                self.state.tick_from = Some(price);
+                // Reverse the position by closing all of the opposite trades.
+                self.close_trades(bar_index, price, slippage, |t| t.direction != direction);
+                match self.risk.allow_entry_in {
+                    Some(allowed) if allowed != direction => {
+                        let message = format!(
+                            "The {} entry {} is not allowed.",
+                            direction.name(),
+                            order.id
+                        );
+                        self.push_event(bar_index, "allow_entry_in", message);
+                        return FillResult::Dropped;
+                    }
+                    _ => {}
+                }
+                let price = if slippage {
+                    self.fill_price(price, buy)
+                } else {
+                    price
+                };
+                let mut qty = self.order_qty(order.qty, price);
+                if qty.is_nan() || qty <= 0f64 {
+                    return FillResult::Dropped;
+                }
+                if let Some(max) = self.risk.max_position_size {
+                    let room = max - self.position_size().abs();
+                    if room <= 0f64 {
+                        let message = format!(
+                            "The entry {} is rejected because the position size reaches {}.",
+                            order.id, max
+                        );
+                        self.push_event(bar_index, "max_position_size", message);
+                        return FillResult::Dropped;
+                    } else if qty > room {
+                        let message = format!(
+                            "The quantity of the entry {} is reduced from {} to {}.",
+                            order.id, qty, room
+                        );
+                        self.push_event(bar_index, "max_position_size", message);
+                        qty = room;
+                    }
+                }
+                let commission = self.commission(price, qty);
+                self.state.open_trades.push(Trade {
+                    entry_id: order.id.clone(),
+                    direction,
+                    qty,
+                    entry_bar: bar_index,
+                    entry_time: self.state.bar_time,
+                    entry_price: price,
+                    exit_bar: None,
+                    exit_time: None,
+                    exit_price: None,
+                    commission,
+                    gross_profit: 0f64,
+                    profit: -commission,
+                    best_price: price,
+                });
+                FillResult::Filled(qty)
+            }
+            OrderKind::Exit(from_entry) => {
+                let mintick = self.config.mintick;
+                let mut exits = vec![];
+                for (i, t) in self.state.open_trades.iter().enumerate() {
+                    if matches!(from_entry, Some(id) if id != &t.entry_id) {
+                        continue;
+                    }
+                    let sign = t.direction.sign();
+                    let limit = order
+                        .limit
+                        .or_else(|| order.profit.map(|p| t.entry_price + sign * p * mintick));
+                    let stop = order
+                        .stop
+                        .or_else(|| order.loss.map(|l| t.entry_price - sign * l * mintick));
+                    let trail = order.trail_points.map(|points| TrailStop {
+                        activation: t.entry_price + sign * points * mintick,
+                        offset: order.trail_offset.unwrap_or(0f64) * mintick,
+                        best: t.best_price,
+                    });
+                    let buy = t.direction == Direction::Short;
+                    if let Some(res) = Self::trigger_price(from, price, buy, limit, stop, trail) {
+                        exits.push((i, res));
+                    }
+                }
+                if exits.is_empty() {
+                    // The exit order waits for the entry to be filled.
+                    return FillResult::Pending;
+                }
+                let mut qty = 0f64;
+                for (i, (price, slippage)) in exits.into_iter().rev() {
+                    let trade = self.state.open_trades.remove(i);
+                    qty += trade.qty;
+                    self.state.tick_from = Some(price);
+                    self.close_trade(bar_index, price, slippage, trade);
+                }
+                self.state.closed_count = self.closed_trades.len();
+                FillResult::Filled(qty)
+            }
+            OrderKind::Close | OrderKind::CloseAll => {
+                let qty = self.position_size().abs();
+                match &order.kind {
+                    OrderKind::Close => {
+                        self.close_trades(bar_index, price, true, |t| t.entry_id == order.id)
+                    }
+                    _ => self.close_trades(bar_index, price, true, |_| true),
+                }
+                let closed = qty - self.position_size().abs();
+                if closed > 0f64 {
+                    FillResult::Filled(closed)
+                } else {
+                    FillResult::Dropped
+                }
+            }
+        }
+    }
+
+    fn close_trade(&mut self, bar_index: i32, price: f64, slippage: bool, mut trade: Trade) {
+        let exit_price = if slippage {
+            self.fill_price(price, trade.direction == Direction::Short)
+        } else {
+            price
+        };
+        trade.exit_bar = Some(bar_index);
+        trade.exit_time = self.state.bar_time;
+        trade.exit_price = Some(exit_price);
+        trade.commission += self.commission(exit_price, trade.qty);
+        trade.gross_profit = (exit_price - trade.entry_price) * trade.qty * trade.direction.sign();
+        trade.profit = trade.gross_profit - trade.commission;
+        self.closed_trades.push(trade);
+    }
+
+    fn close_trades<F>(&mut self, bar_index: i32, price: f64, slippage: bool, f: F)
+    where
+        F: Fn(&Trade) -> bool,
+    {
+        let trades = std::mem::take(&mut self.state.open_trades);
+        let (closed, open): (Vec<Trade>, Vec<Trade>) = trades.into_iter().partition(|t| f(t));
+        self.state.open_trades = open;
+        for trade in closed {
+            self.close_trade(bar_index, price, slippage, trade);
+        }
+        self.state.closed_count = self.closed_trades.len();
+    }
+
+    // The signed size of the position, positive for long and negative for short.
+    pub fn position_size(&self) -> f64 {
+        sum(self
+            .state
+            .open_trades
+            .iter()
+            .map(|t| t.qty * t.direction.sign()))
+    }
+
+    pub fn net_profit(&self) -> f64 {
+        sum(self.closed_trades.iter().map(|t| t.profit))
+    }
+
+    pub fn gross_profit(&self) -> f64 {
+        sum(self
+            .closed_trades
+            .iter()
+            .filter(|t| t.profit > 0f64)
+            .map(|t| t.profit))
+    }
+
+    pub fn gross_loss(&self) -> f64 {
+        sum(self
+            .closed_trades
+            .iter()
+            .filter(|t| t.profit < 0f64)
+            .map(|t| -t.profit))
+    }
+
+    pub fn commission_paid(&self) -> f64 {
+        let closed = sum(self.closed_trades.iter().map(|t| t.commission));
+        closed + self.open_commission()
+    }
+
+    fn open_commission(&self) -> f64 {
+        sum(self.state.open_trades.iter().map(|t| t.commission))
+    }
+
+    // The unrealized profit of the open trades, the entry commission is not included.
+    pub fn open_profit(&self) -> f64 {
+        sum(self.state.open_trades.iter().map(|t| self.trade_profit(t)))
+    }
+
+    // The unrealized profit of the open trade, or the profit of the closed trade.
+    pub fn trade_profit(&self, trade: &Trade) -> f64 {
+        match (trade.exit_bar, self.state.last_price) {
+            (Some(_), _) => trade.profit,
+            (None, Some(price)) => (price - trade.entry_price) * trade.qty * trade.direction.sign(),
+            (None, None) => 0f64,
+        }
+    }
+
+    pub fn closed_trades(&self) -> i64 {
+        self.closed_trades.len() as i64
+    }
+
+    pub fn open_trades(&self) -> i64 {
+        self.state.open_trades.len() as i64
+    }
+
+    // The closed trade of the index, the first closed trade is 0.
+    pub fn get_closed_trade(&self, index: i64) -> Option<&Trade> {
+        if index < 0 {
+            return None;
+        }
+        self.closed_trades.get(index as usize)
+    }
+
+    // The open trade of the index, the earliest open trade is 0.
+    pub fn get_open_trade(&self, index: i64) -> Option<&Trade> {
+        if index < 0 {
+            return None;
+        }
+        self.state.open_trades.get(index as usize)
+    }
+
+    pub fn win_trades(&self) -> i64 {
+        self.closed_trades
+            .iter()
+            .filter(|t| t.profit > 0f64)
+            .count() as i64
+    }
+
+    pub fn loss_trades(&self) -> i64 {
+        self.closed_trades
+            .iter()
+            .filter(|t| t.profit < 0f64)
+            .count() as i64
+    }
+
+    pub fn equity(&self) -> f64 {
+        self.config.initial_capital + self.net_profit() + self.open_profit()
+            - self.open_commission()
+    }
+
+    pub fn gen_bar_metrics(&self) -> BarMetrics {
+        BarMetrics {
+            equity: self.equity(),
+            open_profit: self.open_profit(),
+            net_profit: self.net_profit(),
+        }
+    }
+
+    // The series of the metric `equity`, `openprofit` or `netprofit`, the current value is the
+    // metric of the bar being calculated.
+    pub fn get_metric_series(&mut self, name: &str) -> RefData<Series<'a, Float>> {
+        let metrics = self.gen_bar_metrics();
+        let (series, val) = match name {
+            "equity" => (&mut self.metrics.equity, metrics.equity),
+            "openprofit" => (&mut self.metrics.open_profit, metrics.open_profit),
+            _ => (&mut self.metrics.net_profit, metrics.net_profit),
+        };
+        series.update(Some(val));
+        RefData::clone(series)
+    }
+
+    pub fn gen_result(&self) -> StrategyResult {
+        StrategyResult {
+            initial_capital: self.config.initial_capital,
+            net_profit: self.net_profit(),
+            gross_profit: self.gross_profit(),
+            gross_loss: self.gross_loss(),
+            commission_paid: self.commission_paid(),
+            open_profit: self.open_profit(),
+            equity: self.equity(),
+            position_size: self.position_size(),
+            win_trades: self.win_trades(),
+            loss_trades: self.loss_trades(),
+            closed_trades: self.closed_trades.clone(),
+            open_trades: self.state.open_trades.clone(),
+            risk_events: self.risk_events.clone(),
+            halted: self.state.halted,
+            bar_metrics: self.metrics.gen_bar_metrics(),
+        }
+    }
+}
+
+// Sum the values from the positive zero, so the sum of nothing is not -0.0.
+fn sum<I: Iterator<Item = f64>>(iter: I) -> f64 {
+    iter.fold(0f64, |acc, v| acc + v)
+}
+
+// Cancel or reduce the other orders of the OCA group after one order is filled.
+fn apply_oca(orders: &mut Vec<Order>, name: &str, oca_type: OcaType, filled_qty: f64) {
+    let in_group = |o: &Order| matches!(&o.oca, Some((n, _)) if n == name);
+    match oca_type {
+        OcaType::Cancel => orders.retain(|o| !in_group(o)),
+        OcaType::Reduce => {
+            for o in orders.iter_mut().filter(|o| in_group(o)) {
+                o.qty = o.qty.map(|qty| qty - filled_qty);
+            }
+            orders.retain(|o| !in_group(o) || matches!(o.qty, Some(qty) if qty > 0f64));
+        }
+        OcaType::None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, direction: Direction) -> Order {
+        Order::new(String::from(id), OrderKind::Entry(direction), None)
+    }
+
+    fn bar(open: f64, close: f64) -> BarPrice {
+        BarPrice {
+            time: None,
+            open: Some(open),
+            high: None,
+            low: None,
+            close: Some(close),
+        }
+    }
+
+    #[test]
+    fn fill_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            commission_type: CommissionType::CashPerOrder,
+            commission_value: 1f64,
+            slippage: 2,
+            default_qty_value: 10f64,
+            ..BrokerConfig::default()
+        });
+        broker.place_order(entry("L", Direction::Long));
+        // The order is filled on the next bar.
+        broker.on_bar(1, bar(100f64, 102f64));
+        assert_eq!(broker.position_size(), 10f64);
+        assert_eq!(broker.state.open_trades[0].entry_price, 100.02);
+        assert!((broker.open_profit() - 19.8).abs() < 1e-6);
+
+        broker.place_order(entry("S", Direction::Short));
+        broker.on_bar(2, bar(110f64, 108f64));
+        assert_eq!(broker.position_size(), -10f64);
+        let trade = &broker.closed_trades[0];
+        assert_eq!(trade.exit_price, Some(109.98));
+        assert!((trade.gross_profit - 99.6).abs() < 1e-6);
+        assert!((trade.profit - 97.6).abs() < 1e-6);
+        assert!((broker.commission_paid() - 3f64).abs() < 1e-6);
+
+        broker.place_order(Order::new(String::from("S"), OrderKind::Close, None));
+        broker.on_bar(3, bar(100f64, 100f64));
+        assert_eq!(broker.position_size(), 0f64);
+        let report = broker.gen_result();
+        assert!((report.gross_profit - 195.2).abs() < 1e-6);
+        assert!((report.gross_loss - 0f64).abs() < 1e-6);
+        assert_eq!(report.win_trades, 2);
+        assert!((report.equity - 1000000f64 - report.net_profit).abs() < 1e-6);
+    }
+
+    #[test]
+    fn qty_type_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            initial_capital: 1000f64,
+            commission_type: CommissionType::Percent,
+            commission_value: 0.1,
+            default_qty_type: QtyType::PercentOfEquity,
+            default_qty_value: 50f64,
+            ..BrokerConfig::default()
+        });
+        broker.place_order(entry("L", Direction::Long));
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 50f64);
+        assert!((broker.commission_paid() - 0.5).abs() < 1e-6);
+
+        broker.config.default_qty_type = QtyType::Cash;
+        broker.place_order(Order::new(String::from("L"), OrderKind::Close, None));
+        broker.place_order(entry("S", Direction::Short));
+        broker.on_bar(2, bar(20f64, 20f64));
+        assert_eq!(broker.position_size(), -2.5f64);
+    }
+
+    #[test]
+    fn export_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            initial_capital: 1000f64,
+            ..BrokerConfig::default()
+        });
+        let mut price = bar(10f64, 10f64);
+        for (i, (open, direction)) in vec![
+            (10f64, Direction::Long),
+            (12f64, Direction::Short),
+            (13f64, Direction::Long),
+            (11f64, Direction::Short),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            broker.place_order(entry("A,\"B\"", direction));
+            price.time = Some(i as i64 * 10);
+            price.open = Some(open);
+            broker.on_bar(i as i32, price);
+        }
+        let result = broker.gen_result();
+        let summary = result.gen_summary();
+        // The profits of the closed trades are 2, -1 and -2.
+        assert_eq!(summary.total_trades, 3);
+        assert_eq!(summary.win_rate, Some(1f64 / 3f64));
+        assert_eq!(summary.profit_factor, Some(2f64 / 3f64));
+        assert!(summary.sharpe_ratio.unwrap() < 0f64);
+
+        let csv = result.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "\"A,\"\"B\"\"\",long,0,10,1,10,12,2,1002");
+        assert_eq!(lines[3], "\"A,\"\"B\"\"\",long,20,30,1,13,11,-2,999");
+        assert_eq!(lines[5], "metric,value");
+        assert_eq!(lines[10], "total_trades,3");
+
+        let json: serde_json::Value = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!(json["trades"].as_array().unwrap().len(), 3);
+        assert_eq!(json["trades"][1]["cumulative_equity"], 1001f64);
+        assert_eq!(json["summary"]["total_trades"], 3);
+    }
+
+    #[test]
+    fn risk_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            default_qty_value: 10f64,
+            ..BrokerConfig::default()
+        });
+        broker.get_risk_rules().allow_entry_in = Some(Direction::Long);
+        broker.get_risk_rules().max_position_size = Some(5f64);
+        broker.place_order(entry("S", Direction::Short));
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 0f64);
+        broker.place_order(entry("L", Direction::Long));
+        broker.on_bar(2, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 5f64);
+        let rules: Vec<String> = broker.risk_events.iter().map(|e| e.rule.clone()).collect();
+        assert_eq!(rules, vec!["allow_entry_in", "max_position_size"]);
+
+        broker.get_risk_rules().max_drawdown = Some((10f64, DrawdownType::Cash));
+        broker.on_bar(3, bar(12f64, 12f64));
+        assert_eq!(broker.position_size(), 5f64);
+        // The equity drops from the peak by 15.
+        broker.on_bar(4, bar(9f64, 9f64));
+        assert_eq!(broker.position_size(), 0f64);
+        assert!(broker.gen_result().halted);
+        assert_eq!(broker.risk_events[2].bar_index, 4);
+
+        broker.place_order(entry("L", Direction::Long));
+        broker.on_bar(5, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 0f64);
+    }
+
+    #[test]
+    fn pyramiding_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            pyramiding: 2,
+            ..BrokerConfig::default()
+        });
+        broker.place_order(entry("L1", Direction::Long));
+        broker.place_order(entry("L2", Direction::Long));
+        broker.place_order(entry("L3", Direction::Long));
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 2f64);
+        // The market entry that exceeds the pyramiding is dropped.
+        assert!(broker.state.pending.is_empty());
+
+        broker.place_order(entry("S", Direction::Short));
+        broker.on_bar(2, bar(11f64, 11f64));
+        assert_eq!(broker.position_size(), -1f64);
+        assert_eq!(broker.closed_trades.len(), 2);
+    }
+
+    #[test]
+    fn bracket_test() {
+        let mut broker = Broker::new(BrokerConfig::default());
+        broker.place_order(entry("L", Direction::Long));
+        let mut exit = Order::new(
+            String::from("TP/SL"),
+            OrderKind::Exit(Some(String::from("L"))),
+            None,
+        );
+        exit.profit = Some(100f64);
+        exit.loss = Some(50f64);
+        broker.place_order(exit);
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 1f64);
+        assert_eq!(broker.state.pending.len(), 1);
+
+        let price = BarPrice {
+            time: None,
+            open: Some(10.2),
+            high: Some(11.5),
+            low: Some(10.1),
+            close: Some(11.2),
+        };
+        broker.on_bar(2, price);
+        assert_eq!(broker.position_size(), 0f64);
+        assert_eq!(broker.closed_trades[0].exit_price, Some(11f64));
+        assert!(broker.state.pending.is_empty());
+    }
+
+    fn ohlc(open: f64, high: f64, low: f64, close: f64) -> BarPrice {
+        BarPrice {
+            time: None,
+            open: Some(open),
+            high: Some(high),
+            low: Some(low),
+            close: Some(close),
+        }
+    }
+
+    #[test]
+    fn trail_test() {
+        let mut broker = Broker::new(BrokerConfig::default());
+        broker.place_order(entry("L", Direction::Long));
+        let mut exit = Order::new(String::from("TS"), OrderKind::Exit(None), None);
+        exit.trail_points = Some(100f64);
+        exit.trail_offset = Some(50f64);
+        broker.place_order(exit);
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.position_size(), 1f64);
+
+        // The price moves to the low first, then the high activates the trailing stop at 11.3.
+        broker.on_bar(2, ohlc(10f64, 11.8, 9.9, 11.5));
+        assert_eq!(broker.position_size(), 1f64);
+        assert_eq!(broker.state.open_trades[0].best_price, 11.8);
+        broker.on_bar(3, ohlc(11.6, 11.7, 11f64, 11.2));
+        assert_eq!(broker.position_size(), 0f64);
+        assert_eq!(broker.closed_trades[0].exit_price, Some(11.3));
+    }
+
+    #[test]
+    fn mintick_order_test() {
+        let mut broker = Broker::new(BrokerConfig {
+            mintick: 0.25,
+            ..BrokerConfig::default()
+        });
+        let mut order = entry("L", Direction::Long);
+        order.limit = Some(10.1);
+        order.stop = Some(10.4);
+        broker.place_order(order);
+        assert_eq!(broker.state.pending[0].limit, Some(10f64));
+        assert_eq!(broker.state.pending[0].stop, Some(10.5));
+    }
+
+    #[test]
+    fn bracket_priority_test() {
+        let run = |direction: Direction, price: BarPrice| {
+            let mut broker = Broker::new(BrokerConfig::default());
+            broker.place_order(entry("E", direction));
+            let mut exit = Order::new(String::from("X"), OrderKind::Exit(None), None);
+            exit.profit = Some(200f64);
+            exit.loss = Some(100f64);
+            broker.place_order(exit);
+            broker.on_bar(1, bar(10f64, 10f64));
+            broker.on_bar(2, price);
+            broker.closed_trades[0].exit_price
+        };
+        // The price moves to the nearer high first and reaches the limit.
+        assert_eq!(
+            run(Direction::Long, ohlc(10.5, 12.5, 8.5, 10f64)),
+            Some(12f64)
+        );
+        // The price moves to the nearer low first and reaches the stop.
+        assert_eq!(
+            run(Direction::Long, ohlc(10.2, 12.5, 8.5, 10f64)),
+            Some(9f64)
+        );
+        assert_eq!(
+            run(Direction::Short, ohlc(9.8, 11.5, 7.5, 10f64)),
+            Some(11f64)
+        );
+        // The gap over the stop or limit is filled at the open.
+        assert_eq!(run(Direction::Long, ohlc(8.5, 9.5, 8f64, 9f64)), Some(8.5));
+        assert_eq!(
+            run(Direction::Long, ohlc(12.5, 13f64, 12f64, 12f64)),
+            Some(12.5)
+        );
+        assert_eq!(
+            run(Direction::Short, ohlc(11.5, 12f64, 11f64, 11f64)),
+            Some(11.5)
+        );
+    }
+
+    #[test]
+    fn every_tick_test() {
+        let run = |calc_on_every_tick: bool| {
+            let mut broker = Broker::new(BrokerConfig {
+                calc_on_every_tick,
+                ..BrokerConfig::default()
+            });
+            broker.place_order(entry("L", Direction::Long));
+            broker.on_bar(0, bar(10f64, 10f64));
+            broker.commit();
+            // The first tick of the realtime bar fills the entry and places the exit.
+            broker.on_bar(1, bar(10f64, 10f64));
+            let mut exit = Order::new(String::from("X"), OrderKind::Exit(None), None);
+            exit.limit = Some(11f64);
+            broker.place_order(exit);
+            broker.commit();
+            // The next tick rolls back the bar and runs it again.
+            broker.roll_back();
+            broker.on_bar(1, ohlc(10f64, 11.5, 10f64, 11.5));
+            broker.position_size()
+        };
+        assert_eq!(run(true), 0f64);
+        assert_eq!(run(false), 1f64);
+    }
+
+    #[test]
+    fn oca_test() {
+        let oca = Some((String::from("bo"), OcaType::Cancel));
+        let mut long = entry("L", Direction::Long);
+        long.stop = Some(12f64);
+        long.oca = oca.clone();
+        let mut short = entry("S", Direction::Short);
+        short.stop = Some(8f64);
+        short.oca = oca;
+        let mut broker = Broker::new(BrokerConfig::default());
+        broker.place_order(long);
+        broker.place_order(short);
+        broker.on_bar(1, bar(10f64, 10f64));
+        assert_eq!(broker.state.pending.len(), 2);
+
+        let price = BarPrice {
+            time: None,
+            open: Some(10f64),
+            high: Some(13f64),
+            low: Some(9f64),
+            close: Some(12f64),
+        };
+        broker.on_bar(2, price);
+        assert_eq!(broker.position_size(), 1f64);
+        assert_eq!(broker.state.open_trades[0].entry_price, 12f64);
+        assert!(broker.state.pending.is_empty());
+
+        let gen_order = |id: &str, qty: f64, group: &str| {
+            let mut order = entry(id, Direction::Long);
+            order.qty = Some(qty);
+            order.oca = Some((String::from(group), OcaType::Reduce));
+            order
+        };
+        let mut orders = vec![
+            gen_order("A", 3f64, "r"),
+            gen_order("B", 5f64, "r"),
+            gen_order("C", 5f64, "other"),
+        ];
+        apply_oca(&mut orders, "r", OcaType::Reduce, 4f64);
+        let qtys: Vec<(String, Option<f64>)> = orders.into_iter().map(|o| (o.id, o.qty)).collect();
+        assert_eq!(
+            qtys,
+            vec![
+                (String::from("B"), Some(1f64)),
+                (String::from("C"), Some(5f64))
+            ]
+        );
+    }
+
+    #[test]
+    fn roll_back_test() {
+        let mut broker = Broker::new(BrokerConfig::default());
+        broker.place_order(entry("L", Direction::Long));
+        broker.commit();
+        broker.on_bar(1, bar(10f64, 10f64));
+        broker.place_order(Order::new(String::from("L"), OrderKind::CloseAll, None));
+        broker.commit();
+        broker.on_bar(2, bar(12f64, 12f64));
+        broker.commit();
+        assert_eq!(broker.closed_trades.len(), 1);
+        assert_eq!(
+            broker
+                .gen_result()
+                .bar_metrics
+                .iter()
+                .map(|m| m.equity)
+                .collect::<Vec<_>>(),
+            vec![1000000f64, 1000000f64, 1000002f64]
+        );
+
+        broker.roll_back();
+        assert_eq!(broker.gen_result().bar_metrics.len(), 2);
+        assert_eq!(broker.closed_trades.len(), 0);
+        assert_eq!(broker.position_size(), 1f64);
+        assert_eq!(broker.state.pending.len(), 1);
+    }
+}
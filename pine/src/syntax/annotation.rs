@@ -0,0 +1,289 @@
+use crate::ast::input::{Position, StrRange};
+use crate::ast::name::VarName;
+use crate::ast::stat_expr_types::*;
+
+// The `//@name value` comment, the version is written as `//@version=5`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Annotation {
+    pub name: String,
+    pub value: String,
+    pub range: StrRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParamDoc {
+    pub name: String,
+    pub description: String,
+    // The range of the parameter name in the function declaration.
+    pub range: Option<StrRange>,
+}
+
+// The documentation of the function or variable from the annotations right above it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeclDoc {
+    pub name: String,
+    // The range of the declared name.
+    pub range: StrRange,
+    pub description: Option<String>,
+    pub params: Vec<ParamDoc>,
+    pub returns: Option<String>,
+}
+
+impl DeclDoc {
+    // Render the documentation to the markdown for the hover.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("**{}**", self.name)];
+        if let Some(desc) = &self.description {
+            lines.push(desc.clone());
+        }
+        for param in self.params.iter() {
+            lines.push(format!("- `{}`: {}", param.name, param.description));
+        }
+        if let Some(returns) = &self.returns {
+            lines.push(format!("Returns: {}", returns));
+        }
+        lines.join("\n\n")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ScriptAnnotations {
+    pub version: Option<i64>,
+    pub description: Option<String>,
+    pub strategy_alert_message: Option<String>,
+    pub docs: Vec<DeclDoc>,
+    // All the annotations in the source, including the unknown ones.
+    pub annotations: Vec<Annotation>,
+}
+
+impl ScriptAnnotations {
+    // Get the documentation of the declaration or the parameter with the name range.
+    pub fn doc_of(&self, range: StrRange) -> Option<String> {
+        for doc in self.docs.iter() {
+            if doc.range == range {
+                return Some(doc.to_markdown());
+            }
+            if let Some(param) = doc.params.iter().find(|p| p.range == Some(range)) {
+                return Some(format!("**{}**\n\n{}", param.name, param.description));
+            }
+        }
+        None
+    }
+}
+
+fn parse_annotation(line: u32, text: &str) -> Option<Annotation> {
+    let start = text.find("//@")?;
+    if !text[..start].trim().is_empty() {
+        return None;
+    }
+    let body = &text[start + 3..];
+    let end = body
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(body.len());
+    if end == 0 {
+        return None;
+    }
+    let rest = body[end..].trim_end_matches('\r');
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+    let start_ch = text[..start].chars().count() as u32;
+    Some(Annotation {
+        name: String::from(&body[..end]),
+        value: String::from(value),
+        range: StrRange::new(
+            Position::new(line, start_ch),
+            Position::new(
+                line,
+                start_ch + rest.chars().count() as u32 + end as u32 + 3,
+            ),
+        ),
+    })
+}
+
+// The plain comment line that continues the description of the previous annotation.
+fn continuation(text: &str) -> Option<&str> {
+    let text = text.trim();
+    match text.strip_prefix("//") {
+        Some(rest) if !rest.starts_with('@') => Some(rest.trim()),
+        _ => None,
+    }
+}
+
+fn is_multiline(name: &str) -> bool {
+    name == "description" || DOC_NAMES.contains(&name)
+}
+
+fn append(value: &mut String, line: &str) {
+    if !line.is_empty() {
+        value.push('\n');
+        value.push_str(line);
+    }
+}
+
+// The name range and the parameters of the top-level declaration starting at the line.
+fn decl_at<'a, 'b>(
+    blk: &'b Block<'a>,
+    line: u32,
+) -> Option<(&'b str, StrRange, &'b [VarName<'a>])> {
+    blk.stmts
+        .iter()
+        .find(|s| s.range().start.get_line() == line)
+        .and_then(|stmt| match stmt {
+            Statement::FuncDef(def) => Some((def.name.value, def.name.range, &def.params[..])),
+            Statement::Assignment(assign) if assign.names.len() == 1 => {
+                Some((assign.names[0].value, assign.names[0].range, &[][..]))
+            }
+            _ => None,
+        })
+}
+
+const DOC_NAMES: [&str; 4] = ["function", "variable", "param", "returns"];
+
+fn gen_doc(group: &[Annotation], blk: &Block, next_line: u32) -> Option<DeclDoc> {
+    if !group.iter().any(|a| DOC_NAMES.contains(&a.name.as_str())) {
+        return None;
+    }
+    let (name, range, params) = decl_at(blk, next_line)?;
+    let mut doc = DeclDoc {
+        name: String::from(name),
+        range,
+        description: None,
+        params: vec![],
+        returns: None,
+    };
+    for annotation in group {
+        match annotation.name.as_str() {
+            "function" | "variable" => doc.description = Some(annotation.value.clone()),
+            "returns" => doc.returns = Some(annotation.value.clone()),
+            "param" => {
+                let mut iter = annotation.value.splitn(2, char::is_whitespace);
+                let param_name = iter.next().unwrap_or("");
+                doc.params.push(ParamDoc {
+                    name: String::from(param_name),
+                    description: String::from(iter.next().unwrap_or("").trim()),
+                    range: params
+                        .iter()
+                        .find(|p| p.value == param_name)
+                        .map(|p| p.range),
+                });
+            }
+            _ => {}
+        }
+    }
+    Some(doc)
+}
+
+// Collect the `//@` annotations of the script. The version, description and strategy alert
+// message describe the whole script, while the groups of the function, param, returns and
+// variable annotations document the declaration on the next line. The plain comment lines
+// after the annotation continue its value.
+pub fn parse_annotations(src: &str, blk: Option<&Block>) -> ScriptAnnotations {
+    let mut result = ScriptAnnotations::default();
+    let mut group: Vec<Annotation> = vec![];
+    for (i, text) in src.split('\n').enumerate() {
+        if let Some(annotation) = parse_annotation(i as u32, text) {
+            group.push(annotation);
+            continue;
+        }
+        match (group.last_mut(), continuation(text)) {
+            (Some(last), Some(line)) if is_multiline(&last.name) => {
+                append(&mut last.value, line);
+                continue;
+            }
+            _ => {}
+        }
+        if let (false, Some(blk)) = (group.is_empty(), blk) {
+            if let Some(doc) = gen_doc(&group, blk, i as u32) {
+                result.docs.push(doc);
+            }
+        }
+        result.annotations.append(&mut group);
+    }
+    result.annotations.append(&mut group);
+
+    for annotation in result.annotations.iter() {
+        match annotation.name.as_str() {
+            "version" => result.version = annotation.value.parse().ok(),
+            "description" => result.description = Some(annotation.value.clone()),
+            "strategy_alert_message" => {
+                result.strategy_alert_message = Some(annotation.value.clone())
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    const SRC: &str = "//@version=4
+// The comment.
+//@description The test library.
+//@strategy_alert_message {{strategy.order.action}} at {{close}}
+strategy(\"Test\")
+//@function Add the values.
+// The values can be series.
+//@param a The first value.
+//@param b The second value.
+//@returns The sum.
+add(a, b) => a + b
+//@variable The length.
+len = 14
+//@variable Not attached.
+
+m = add(close, len)";
+
+    #[test]
+    fn annotation_test() {
+        let blk = parse_ast(SRC).unwrap();
+        let result = parse_annotations(SRC, Some(&blk));
+        assert_eq!(result.version, Some(4));
+        assert_eq!(result.description, Some(String::from("The test library.")));
+        assert_eq!(
+            result.strategy_alert_message,
+            Some(String::from("{{strategy.order.action}} at {{close}}"))
+        );
+        assert_eq!(result.annotations.len(), 9);
+        assert_eq!(
+            result.annotations[0].range,
+            StrRange::new(Position::new(0, 0), Position::new(0, 12))
+        );
+
+        assert_eq!(
+            result.docs,
+            vec![
+                DeclDoc {
+                    name: String::from("add"),
+                    range: StrRange::from_start("add", Position::new(10, 0)),
+                    description: Some(String::from("Add the values.\nThe values can be series.")),
+                    params: vec![
+                        ParamDoc {
+                            name: String::from("a"),
+                            description: String::from("The first value."),
+                            range: Some(StrRange::from_start("a", Position::new(10, 4))),
+                        },
+                        ParamDoc {
+                            name: String::from("b"),
+                            description: String::from("The second value."),
+                            range: Some(StrRange::from_start("b", Position::new(10, 7))),
+                        },
+                    ],
+                    returns: Some(String::from("The sum.")),
+                },
+                DeclDoc {
+                    name: String::from("len"),
+                    range: StrRange::from_start("len", Position::new(12, 0)),
+                    description: Some(String::from("The length.")),
+                    params: vec![],
+                    returns: None,
+                },
+            ]
+        );
+        assert_eq!(
+            result.doc_of(StrRange::from_start("b", Position::new(10, 7))),
+            Some(String::from("**b**\n\nThe second value."))
+        );
+    }
+}
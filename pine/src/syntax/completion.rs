@@ -0,0 +1,418 @@
+use crate::ast::input::Position;
+use crate::ast::stat_expr_types::*;
+use crate::ast::syntax_type::{FunctionTypes, SimpleSyntaxType, SyntaxType};
+
+// The named argument offered in the call, the snippet fills the argument name and puts the
+// cursor on the value placeholder.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParamCompletion {
+    pub name: String,
+    pub snippet: String,
+    // The types of the parameter in the signatures, e.g. `series float | float[]`.
+    pub detail: String,
+}
+
+impl ParamCompletion {
+    fn new(name: &str, detail: String) -> ParamCompletion {
+        ParamCompletion {
+            name: String::from(name),
+            snippet: format!("{}=${{1:{}}}", name, name),
+            detail,
+        }
+    }
+}
+
+// The call whose argument list contains the cursor.
+#[derive(Debug, Clone, PartialEq)]
+struct CallSite<'a> {
+    method: &'a str,
+    pos_count: usize,
+    named: Vec<&'a str>,
+}
+
+enum Bracket<'a> {
+    Call(CallSite<'a>, usize),
+    Paren,
+    Index,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Get the dotted name like `ta.sma` at the end of the source.
+fn tail_method(src: &str) -> Option<&str> {
+    let src = src.trim_end();
+    let start = src
+        .rfind(|c: char| !is_name_char(c) && c != '.')
+        .map_or(0, |i| i + 1);
+    let method = &src[start..];
+    match method.chars().next() {
+        Some(c) if c.is_alphabetic() || c == '_' => Some(method),
+        _ => None,
+    }
+}
+
+// Get the name of the named argument like `length = 10`.
+fn arg_name(arg: &str) -> Option<&str> {
+    let arg = arg.trim_start();
+    let end = arg.find(|c: char| !is_name_char(c)).unwrap_or(arg.len());
+    let rest = arg[end..].trim_start();
+    if end > 0 && rest.starts_with('=') && !rest.starts_with("==") {
+        Some(&arg[..end])
+    } else {
+        None
+    }
+}
+
+fn pos_to_offset(src: &str, pos: Position) -> Option<usize> {
+    let mut offset = 0;
+    for (i, line) in src.split('\n').enumerate() {
+        if i as u32 == pos.get_line() {
+            return match line.char_indices().nth(pos.get_character() as usize) {
+                Some((index, _)) => Some(offset + index),
+                None if line.chars().count() == pos.get_character() as usize => {
+                    Some(offset + line.len())
+                }
+                None => None,
+            };
+        }
+        offset += line.len() + 1;
+    }
+    None
+}
+
+// Scan the source before the cursor to find the innermost call that the cursor is in. The
+// source is usually incomplete when the user is typing, so it's scanned by the text instead
+// of the AST. None is returned if the cursor is in the value of an argument.
+fn call_site(src: &str) -> Option<CallSite<'_>> {
+    let mut brackets: Vec<Bracket> = vec![];
+    let mut chars = src.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                while let Some((_, s)) = chars.next() {
+                    match s {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\n' => break,
+                        s if s == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                for (_, s) in chars.by_ref() {
+                    if s == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => match tail_method(&src[..i]) {
+                Some(method) => brackets.push(Bracket::Call(
+                    CallSite {
+                        method,
+                        pos_count: 0,
+                        named: vec![],
+                    },
+                    i + 1,
+                )),
+                None => brackets.push(Bracket::Paren),
+            },
+            '[' => brackets.push(Bracket::Index),
+            ')' | ']' => {
+                brackets.pop();
+            }
+            ',' => {
+                if let Some(Bracket::Call(site, arg_start)) = brackets.last_mut() {
+                    match arg_name(&src[*arg_start..i]) {
+                        Some(name) => site.named.push(name),
+                        None => site.pos_count += 1,
+                    }
+                    *arg_start = i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    match brackets.pop() {
+        Some(Bracket::Call(site, arg_start)) => {
+            let arg = src[arg_start..].trim_start();
+            if arg.chars().all(is_name_char) {
+                Some(site)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// The function or variable declared by the script that the call resolves to.
+enum Declared<'a> {
+    Function(&'a FunctionDef<'a>),
+    Variable,
+}
+
+fn find_decl<'a>(blk: &'a Block<'a>, name: &str, pos: Position) -> Option<Declared<'a>> {
+    let mut found = None;
+    for stmt in blk.stmts.iter().take_while(|s| s.range().start < pos) {
+        let range = stmt.range();
+        let inner = match stmt {
+            Statement::Assignment(assign) => {
+                if assign.names.iter().any(|n| n.value == name) {
+                    found = Some(Declared::Variable);
+                }
+                None
+            }
+            Statement::FuncDef(def) => {
+                if def.name.value == name {
+                    found = Some(Declared::Function(def));
+                }
+                if range.contain(pos) {
+                    if def.params.iter().any(|n| n.value == name) {
+                        found = Some(Declared::Variable);
+                    }
+                    Some(&def.body)
+                } else {
+                    None
+                }
+            }
+            Statement::Ite(ite) if range.contain(pos) => match &ite.else_blk {
+                Some(blk) if blk.range.start <= pos => Some(blk),
+                _ => Some(&ite.then_blk),
+            },
+            Statement::ForRange(for_range) if range.contain(pos) => {
+                if for_range.var.value == name {
+                    found = Some(Declared::Variable);
+                }
+                Some(&for_range.do_blk)
+            }
+            Statement::ForIn(for_in) if range.contain(pos) => {
+                let index_var = for_in.index_var.as_ref().map(|n| n.value);
+                if for_in.var.value == name || index_var == Some(name) {
+                    found = Some(Declared::Variable);
+                }
+                Some(&for_in.do_blk)
+            }
+            _ => None,
+        };
+        if let Some(inner) = inner {
+            if let Some(decl) = find_decl(inner, name, pos) {
+                found = Some(decl);
+            }
+        }
+    }
+    found
+}
+
+fn func_types<'a, 'b>(syntax_type: &'b SyntaxType<'a>) -> Option<&'b FunctionTypes<'a>> {
+    match syntax_type {
+        SyntaxType::Function(types)
+        | SyntaxType::ObjectFunction(_, types)
+        | SyntaxType::ValFunction(_, types)
+        | SyntaxType::ValObjectFunction(_, _, types) => Some(types),
+        _ => None,
+    }
+}
+
+fn find_builtin<'a, 'b>(
+    var_types: &'b [(&'a str, SyntaxType<'a>)],
+    method: &str,
+) -> Option<&'b FunctionTypes<'a>> {
+    let mut names = method.split('.');
+    let first = names.next()?;
+    let mut cur = &var_types.iter().find(|v| v.0 == first)?.1;
+    for name in names {
+        cur = match cur {
+            SyntaxType::Object(props)
+            | SyntaxType::ObjectFunction(props, _)
+            | SyntaxType::ValObjectFunction(_, props, _) => props.get(name)?,
+            _ => return None,
+        };
+    }
+    func_types(cur)
+}
+
+// Get the named arguments to complete at the position in the call. The parameters of the
+// builtin function come from its signatures, while the function declared by the script or the
+// variable shadowing the builtin one resolves to the declaration visible at the position. The
+// parameters given by the named arguments or filled by the positional arguments are skipped.
+pub fn param_completions<'a>(
+    src: &str,
+    blk: Option<&Block<'a>>,
+    pos: Position,
+    var_types: &[(&'a str, SyntaxType<'a>)],
+) -> Vec<ParamCompletion> {
+    let site = match pos_to_offset(src, pos).and_then(|offset| call_site(&src[..offset])) {
+        Some(site) => site,
+        None => return vec![],
+    };
+    let is_unused =
+        |name: &str, index: usize| index >= site.pos_count && !site.named.contains(&name);
+
+    let root_name = site.method.split('.').next().unwrap();
+    match blk.and_then(|blk| find_decl(blk, root_name, pos)) {
+        Some(Declared::Function(def)) if root_name == site.method => {
+            return def
+                .params
+                .iter()
+                .zip(def.param_types.iter())
+                .enumerate()
+                .filter(|(i, (name, _))| is_unused(name.value, *i))
+                .map(|(_, (name, param_type))| {
+                    let detail = match param_type {
+                        Some(t) => {
+                            let data_type = SimpleSyntaxType::from(t.data_type.clone());
+                            match t.qualifier {
+                                Some(qualifier) => {
+                                    format!("{} {}", qualifier, data_type.to_string())
+                                }
+                                None => data_type.to_string(),
+                            }
+                        }
+                        None => String::new(),
+                    };
+                    ParamCompletion::new(name.value, detail)
+                })
+                .collect();
+        }
+        Some(_) => return vec![],
+        None => {}
+    }
+
+    let types = match find_builtin(var_types, site.method) {
+        Some(types) => types.instances(),
+        None => return vec![],
+    };
+    let mut completions: Vec<(ParamCompletion, Vec<String>)> = vec![];
+    for func in types.0.iter() {
+        for (i, (name, syntax_type)) in func.signature.0.iter().enumerate() {
+            if !is_unused(name, i) {
+                continue;
+            }
+            let type_str = syntax_type.to_string();
+            match completions.iter_mut().find(|c| c.0.name == *name) {
+                Some((_, types)) => {
+                    if !types.contains(&type_str) {
+                        types.push(type_str);
+                    }
+                }
+                None => {
+                    completions.push((ParamCompletion::new(name, String::new()), vec![type_str]))
+                }
+            }
+        }
+    }
+    completions
+        .into_iter()
+        .map(|(mut completion, types)| {
+            completion.detail = types.join(" | ");
+            completion
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::syntax_type::FunctionType;
+    use crate::parse_ast;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    fn var_types() -> Vec<(&'static str, SyntaxType<'static>)> {
+        let series = SyntaxType::Series(SimpleSyntaxType::Float);
+        let int = SyntaxType::Simple(SimpleSyntaxType::Int);
+        let sma = SyntaxType::Function(Rc::new(FunctionTypes(vec![
+            FunctionType::new((
+                vec![("source", series.clone()), ("length", int.clone())],
+                series.clone(),
+            )),
+            FunctionType::new((
+                vec![("source", series.clone()), ("length", series.clone())],
+                series.clone(),
+            )),
+        ])));
+        let mut props = BTreeMap::new();
+        props.insert("sma", sma.clone());
+        vec![
+            ("ta", SyntaxType::Object(Rc::new(props))),
+            ("sma", sma),
+            ("close", series),
+        ]
+    }
+
+    fn names(completions: Vec<ParamCompletion>) -> Vec<String> {
+        completions.into_iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn call_site_test() {
+        assert_eq!(
+            call_site("m = ta.sma(close, length = 2, "),
+            Some(CallSite {
+                method: "ta.sma",
+                pos_count: 1,
+                named: vec!["length"],
+            })
+        );
+        assert_eq!(
+            call_site("m = sma(f(1, 2), (1, 2), [1, 2], \"(\", "),
+            Some(CallSite {
+                method: "sma",
+                pos_count: 4,
+                named: vec![],
+            })
+        );
+        assert_eq!(
+            call_site("m = sma(close, len == 1, le").unwrap().pos_count,
+            2
+        );
+        assert_eq!(call_site("m = sma(close, 1 + "), None);
+        assert_eq!(call_site("m = sma(close)"), None);
+        assert_eq!(call_site("m = (close + "), None);
+    }
+
+    #[test]
+    fn builtin_completion_test() {
+        let var_types = var_types();
+        let src = "m = ta.sma(close, ";
+        let completions = param_completions(src, None, Position::new(0, 18), &var_types);
+        assert_eq!(
+            completions,
+            vec![ParamCompletion {
+                name: String::from("length"),
+                snippet: String::from("length=${1:length}"),
+                detail: String::from("int | series float"),
+            }]
+        );
+
+        let src = "m = sma(length=1, \n)";
+        let completions = param_completions(src, None, Position::new(0, 18), &var_types);
+        assert_eq!(names(completions), vec!["source"]);
+
+        // The cursor is out of the call.
+        let completions = param_completions(src, None, Position::new(1, 1), &var_types);
+        assert_eq!(completions, vec![]);
+    }
+
+    #[test]
+    fn scope_completion_test() {
+        let var_types = var_types();
+        let src =
+            "sma(a, b) => a + b\nm = sma(1, 2)\nf(ta) =>\n    ta.sma(close, 1)\nta.sma(close, 1)";
+        let blk = parse_ast(src).unwrap();
+
+        let completions = param_completions(src, Some(&blk), Position::new(1, 8), &var_types);
+        assert_eq!(names(completions), vec!["a", "b"]);
+
+        // The parameter `ta` shadows the builtin object in the function.
+        let completions = param_completions(src, Some(&blk), Position::new(3, 11), &var_types);
+        assert_eq!(completions, vec![]);
+
+        let completions = param_completions(src, Some(&blk), Position::new(4, 7), &var_types);
+        assert_eq!(names(completions), vec!["source", "length"]);
+    }
+}
@@ -102,6 +102,11 @@ pub fn implicity_convert<'a>(origin_type: &SyntaxType<'a>, dest_type: &SyntaxTyp
             SyntaxType::List(SimpleSyntaxType::Color) => types
                 .iter()
                 .all(|s| implicity_convert(&s, &SyntaxType::Simple(SimpleSyntaxType::Color))),
+            // Tuple(Object) can convert to the object array like `chart.point[]`
+            SyntaxType::ObjectClass(cls) if cls.ends_with("[]") => {
+                let item_type = SyntaxType::ObjectClass(&cls[..cls.len() - 2]);
+                types.iter().all(|s| implicity_convert(s, &item_type))
+            }
             _ => false,
         },
         _ => false,
@@ -338,5 +343,23 @@ mod tests {
             &SyntaxType::Simple(SimpleSyntaxType::Float),
             &SyntaxType::Series(SimpleSyntaxType::Float),
         ));
+
+        // The tuple of objects => object array
+        let points = SyntaxType::Tuple(Rc::new(vec![
+            SyntaxType::ObjectClass("chart.point"),
+            SyntaxType::ObjectClass("chart.point"),
+        ]));
+        assert!(implicity_convert(
+            &points,
+            &SyntaxType::ObjectClass("chart.point[]")
+        ));
+        assert!(!implicity_convert(
+            &points,
+            &SyntaxType::ObjectClass("line[]")
+        ));
+        assert!(!implicity_convert(
+            &points,
+            &SyntaxType::ObjectClass("chart.point")
+        ));
     }
 }
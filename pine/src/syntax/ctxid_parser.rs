@@ -1,6 +1,6 @@
 use crate::ast::stat_expr_types::{
-    Block, Condition, Exp, ForRange, FunctionCall, FunctionDef, IfThenElse, RefCall, Statement,
-    TupleNode, VarIndex,
+    Block, Condition, Exp, ForIn, ForRange, FunctionCall, FunctionDef, IfThenElse, RefCall,
+    Statement, TupleNode, VarIndex,
 };
 
 pub struct CtxIdParser {
@@ -67,6 +67,14 @@ impl CtxIdParser {
         self.parse_blk(&mut for_range.do_blk);
     }
 
+    fn parse_forin<'a>(&mut self, for_in: &mut ForIn<'a>) {
+        for_in.ctxid = self.ctxid;
+        self.ctxid += 1;
+
+        self.parse_exp(&mut for_in.iter);
+        self.parse_blk(&mut for_in.do_blk);
+    }
+
     fn parse_exp<'a>(&mut self, exp: &mut Exp<'a>) {
         match exp {
             Exp::Tuple(tuple) => {
@@ -80,6 +88,7 @@ impl CtxIdParser {
             Exp::Condition(condition) => self.parse_condition(condition),
             Exp::Ite(ite) => self.parse_ifthenelse(ite),
             Exp::ForRange(fr) => self.parse_forrange(fr),
+            Exp::ForIn(fi) => self.parse_forin(fi),
             Exp::UnaryExp(node) => self.parse_exp(&mut node.exp),
             Exp::BinaryExp(node) => {
                 self.parse_exp(&mut node.exp1);
@@ -94,6 +103,7 @@ impl CtxIdParser {
             Statement::FuncCall(func_call) => self.parse_func_call(func_call),
             Statement::Ite(ite) => self.parse_ifthenelse(ite),
             Statement::ForRange(fr) => self.parse_forrange(fr),
+            Statement::ForIn(fi) => self.parse_forin(fi),
             Statement::Assignment(assign) => {
                 self.parse_exp(&mut assign.val);
             }
@@ -0,0 +1,256 @@
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+use std::collections::HashSet;
+
+// The data requested by one `security` call.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct SecurityDep {
+    // The ticker is None if the symbol is not a string literal, e.g. `syminfo.tickerid`.
+    pub ticker: Option<String>,
+    pub resolution: Option<String>,
+    // The sources read by the expression argument.
+    pub srcs: Vec<String>,
+}
+
+// The input function and the literal title of one input.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct InputDep {
+    pub func: String,
+    pub title: Option<String>,
+}
+
+// The data and builtin libraries required by the script, so the host can prefetch the data before running.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+pub struct ScriptDeps {
+    // The sources of the main chart such as close and volume.
+    pub srcs: Vec<String>,
+    pub securities: Vec<SecurityDep>,
+    pub inputs: Vec<InputDep>,
+    // The builtin library variables read by the script such as ta and math.
+    pub libraries: Vec<String>,
+}
+
+// Get the full name of the called method like `request.security`.
+fn method_name<'a>(exp: &Exp<'a>) -> Option<String> {
+    match exp {
+        Exp::VarName(name) => Some(String::from(name.name.value)),
+        Exp::PrefixExp(prefix) => {
+            method_name(&prefix.left_exp).map(|s| format!("{}.{}", s, prefix.right_name.value))
+        }
+        _ => None,
+    }
+}
+
+// Get the argument by the name or the position.
+fn get_arg<'a, 'b>(call: &'b FunctionCall<'a>, pos: usize, name: &str) -> Option<&'b Exp<'a>> {
+    match call.dict_args.iter().find(|(n, _)| n.value == name) {
+        Some((_, exp)) => Some(exp),
+        None => call.pos_args.get(pos),
+    }
+}
+
+fn get_str_arg<'a>(call: &FunctionCall<'a>, pos: usize, name: &str) -> Option<String> {
+    match get_arg(call, pos, name) {
+        Some(Exp::Str(s)) => Some(s.value.clone()),
+        _ => None,
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, name: &str) {
+    if !list.iter().any(|s| s == name) {
+        list.push(String::from(name));
+    }
+}
+
+struct DepCollector<'a, 'b> {
+    client_srcs: &'b [&'a str],
+    declares: HashSet<&'a str>,
+    reads: Vec<&'a str>,
+    securities: Vec<SecurityDep>,
+    inputs: Vec<InputDep>,
+}
+
+impl<'a, 'b, 'c> Visitor<'a, 'c> for DepCollector<'a, 'b> {
+    fn visit_func_call(&mut self, call: &'c FunctionCall<'a>) {
+        match method_name(&call.method).as_deref() {
+            Some("security") | Some("request.security") | Some("request.security_lower_tf") => {
+                let mut srcs = vec![];
+                if let Some(exp) = get_arg(call, 2, "expression") {
+                    let mut collector = DepCollector {
+                        client_srcs: self.client_srcs,
+                        declares: HashSet::new(),
+                        reads: vec![],
+                        securities: vec![],
+                        inputs: vec![],
+                    };
+                    collector.visit_exp(exp);
+                    for name in collector.reads {
+                        if self.client_srcs.contains(&name) {
+                            push_unique(&mut srcs, name);
+                        } else {
+                            self.reads.push(name);
+                        }
+                    }
+                    self.declares.extend(collector.declares);
+                    self.securities.append(&mut collector.securities);
+                    self.inputs.append(&mut collector.inputs);
+                }
+                self.securities.push(SecurityDep {
+                    ticker: get_str_arg(call, 0, "symbol"),
+                    resolution: get_str_arg(call, 1, "resolution")
+                        .or_else(|| get_str_arg(call, 1, "timeframe")),
+                    srcs,
+                });
+                // The sources of the expression belong to the requested ticker instead of the main chart.
+                self.visit_exp(&call.method);
+                call.pos_args
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != 2)
+                    .for_each(|(_, e)| self.visit_exp(e));
+                call.dict_args
+                    .iter()
+                    .filter(|(n, _)| n.value != "expression")
+                    .for_each(|(_, e)| self.visit_exp(e));
+                return;
+            }
+            Some(name) if name == "input" || name.starts_with("input.") => {
+                self.inputs.push(InputDep {
+                    func: String::from(name),
+                    title: get_str_arg(call, 1, "title"),
+                });
+            }
+            _ => {}
+        }
+        walk_func_call(self, call);
+    }
+
+    fn visit_for_range(&mut self, fr: &'c ForRange<'a>) {
+        self.declares.insert(fr.var.value);
+        walk_for_range(self, fr);
+    }
+
+    fn visit_for_in(&mut self, fi: &'c ForIn<'a>) {
+        self.declares.insert(fi.var.value);
+        if let Some(index) = &fi.index_var {
+            self.declares.insert(index.value);
+        }
+        walk_for_in(self, fi);
+    }
+
+    fn visit_assign(&mut self, assign: &'c Assignment<'a>) {
+        self.declares.extend(assign.names.iter().map(|n| n.value));
+        walk_assign(self, assign);
+    }
+
+    fn visit_func_def(&mut self, def: &'c FunctionDef<'a>) {
+        self.declares.insert(def.name.value);
+        self.declares.extend(def.params.iter().map(|n| n.value));
+        walk_func_def(self, def);
+    }
+
+    fn visit_exp(&mut self, exp: &'c Exp<'a>) {
+        if let Exp::VarName(name) = exp {
+            self.reads.push(name.name.value);
+        }
+        walk_exp(self, exp);
+    }
+}
+
+// Collect the dependencies of the script. The `client_srcs` are the sources provided by the host
+// and the `lib_names` are the builtin library variables. The names declared by the script
+// shadow the builtin names, so they are not reported.
+pub fn collect_deps<'a>(
+    blk: &Block<'a>,
+    client_srcs: &[&'a str],
+    lib_names: &[&'a str],
+) -> ScriptDeps {
+    let mut collector = DepCollector {
+        client_srcs,
+        declares: HashSet::new(),
+        reads: vec![],
+        securities: vec![],
+        inputs: vec![],
+    };
+    collector.visit_blk(blk);
+
+    let mut deps = ScriptDeps {
+        securities: collector.securities,
+        inputs: collector.inputs,
+        ..ScriptDeps::default()
+    };
+    for name in collector.reads {
+        if collector.declares.contains(name) {
+            continue;
+        }
+        if client_srcs.contains(&name) {
+            push_unique(&mut deps.srcs, name);
+        } else if lib_names.contains(&name) {
+            push_unique(&mut deps.libraries, name);
+        }
+    }
+    deps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    #[test]
+    fn collect_deps_test() {
+        let src = r#"len = input(10, "Length")
+src = input.source(close, title="Source")
+m = security("NASDAQ:AAPL", "D", sma(high, len))
+n = security(syminfo.tickerid, "W", volume)
+plot(math.max(m, n) + src)"#;
+        let blk = parse_ast(src).unwrap();
+        let deps = collect_deps(
+            &blk,
+            &["close", "open", "high", "low", "volume", "time"],
+            &["input", "security", "sma", "plot", "math", "syminfo", "ta"],
+        );
+        assert_eq!(
+            deps,
+            ScriptDeps {
+                srcs: vec![String::from("close")],
+                securities: vec![
+                    SecurityDep {
+                        ticker: Some(String::from("NASDAQ:AAPL")),
+                        resolution: Some(String::from("D")),
+                        srcs: vec![String::from("high")],
+                    },
+                    SecurityDep {
+                        ticker: None,
+                        resolution: Some(String::from("W")),
+                        srcs: vec![String::from("volume")],
+                    },
+                ],
+                inputs: vec![
+                    InputDep {
+                        func: String::from("input"),
+                        title: Some(String::from("Length")),
+                    },
+                    InputDep {
+                        func: String::from("input.source"),
+                        title: Some(String::from("Source")),
+                    },
+                ],
+                libraries: vec![
+                    String::from("input"),
+                    String::from("sma"),
+                    String::from("security"),
+                    String::from("syminfo"),
+                    String::from("plot"),
+                    String::from("math"),
+                ],
+            }
+        );
+
+        // The variables declared by the script shadow the builtin names.
+        let blk = parse_ast("close = 1\nf(high) => high * 2\nplot(f(close))").unwrap();
+        let deps = collect_deps(&blk, &["close", "high"], &["plot"]);
+        assert_eq!(deps.srcs, Vec::<String>::new());
+        assert_eq!(deps.libraries, vec![String::from("plot")]);
+    }
+}
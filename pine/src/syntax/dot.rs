@@ -0,0 +1,335 @@
+use crate::ast::num::Numeral;
+use crate::ast::op::UnaryOp;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+use std::fmt::Write;
+
+// Render the AST to the graphviz DOT graph to see how the script is parsed, e.g. by
+// `dot -Tsvg ast.dot -o ast.svg`. If `with_scopes` is true, the blocks are drawn as the nested
+// clusters of their contexts and the variables are labeled with the indexes assigned by the
+// syntax parser, so the AST should be the one checked by the syntax parser.
+pub fn to_dot<'a>(blk: &Block<'a>, with_scopes: bool) -> String {
+    let mut writer = DotWriter {
+        with_scopes,
+        out: String::from("digraph ast {\n    node [shape=box, fontname=\"monospace\"];\n"),
+        next_id: 0,
+        last: 0,
+        scope: String::from("main"),
+    };
+    writer.visit_blk(blk);
+    writer.out.push_str("}\n");
+    writer.out
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn fmt_index(index: &VarIndex) -> String {
+    format!("({}, {})", index.varid, index.rel_ctx)
+}
+
+struct DotWriter {
+    with_scopes: bool,
+    out: String,
+    next_id: usize,
+    // The id of the node written by the last visited AST node.
+    last: usize,
+    // The name of the cluster of the next visited block.
+    scope: String,
+}
+
+impl DotWriter {
+    fn node(&mut self, label: &str) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        writeln!(self.out, "    n{} [label=\"{}\"];", id, escape(label)).unwrap();
+        id
+    }
+
+    // The edge is written after the child is declared, so the child stays in its own cluster.
+    fn edge(&mut self, from: usize, to: usize, label: &str) {
+        if label.is_empty() {
+            writeln!(self.out, "    n{} -> n{};", from, to).unwrap();
+        } else {
+            writeln!(
+                self.out,
+                "    n{} -> n{} [label=\"{}\"];",
+                from,
+                to,
+                escape(label)
+            )
+            .unwrap();
+        }
+    }
+
+    fn child_exp<'a>(&mut self, from: usize, exp: &Exp<'a>, label: &str) {
+        self.visit_exp(exp);
+        self.edge(from, self.last, label);
+    }
+
+    fn child_blk<'a>(&mut self, from: usize, blk: &Block<'a>, scope: &str, label: &str) {
+        self.scope = String::from(scope);
+        self.visit_blk(blk);
+        self.edge(from, self.last, label);
+    }
+}
+
+// Every AST node is written as one graph node, so all of the nodes are overridden to label the
+// edges to their children.
+impl<'a, 'b> Visitor<'a, 'b> for DotWriter {
+    fn visit_blk(&mut self, blk: &'b Block<'a>) {
+        let scope = std::mem::take(&mut self.scope);
+        let id = if self.with_scopes {
+            writeln!(
+                self.out,
+                "    subgraph cluster_{} {{\n    label=\"{}\";",
+                self.next_id,
+                escape(&scope)
+            )
+            .unwrap();
+            self.node(&format!(
+                "Block\nvars: {}, libfuns: {}, subctxs: {}",
+                blk.var_count, blk.libfun_count, blk.subctx_count
+            ))
+        } else {
+            self.node("Block")
+        };
+        for stmt in blk.stmts.iter() {
+            self.visit_stmt(stmt);
+            self.edge(id, self.last, "");
+        }
+        if let Some(exp) = &blk.ret_stmt {
+            self.child_exp(id, exp, "return");
+        }
+        if self.with_scopes {
+            self.out.push_str("    }\n");
+        }
+        self.last = id;
+    }
+
+    fn visit_ite(&mut self, ite: &'b IfThenElse<'a>) {
+        let id = self.node("If");
+        self.child_exp(id, &ite.cond, "cond");
+        self.child_blk(
+            id,
+            &ite.then_blk,
+            &format!("ctx {}", ite.then_ctxid),
+            "then",
+        );
+        if let Some(blk) = &ite.else_blk {
+            self.child_blk(id, blk, &format!("ctx {}", ite.else_ctxid), "else");
+        }
+        self.last = id;
+    }
+
+    fn visit_for_range(&mut self, fr: &'b ForRange<'a>) {
+        let id = match self.with_scopes {
+            true => self.node(&format!("For {}\nvarid: {}", fr.var.value, fr.varid)),
+            false => self.node(&format!("For {}", fr.var.value)),
+        };
+        self.child_exp(id, &fr.start, "start");
+        self.child_exp(id, &fr.end, "end");
+        if let Some(step) = &fr.step {
+            self.child_exp(id, step, "step");
+        }
+        self.child_blk(id, &fr.do_blk, &format!("ctx {}", fr.ctxid), "do");
+        self.last = id;
+    }
+
+    fn visit_for_in(&mut self, fi: &'b ForIn<'a>) {
+        let names = match &fi.index_var {
+            Some(index) => format!("[{}, {}]", index.value, fi.var.value),
+            None => String::from(fi.var.value),
+        };
+        let id = self.node(&format!("For {} in", names));
+        self.child_exp(id, &fi.iter, "iter");
+        self.child_blk(id, &fi.do_blk, &format!("ctx {}", fi.ctxid), "do");
+        self.last = id;
+    }
+
+    fn visit_assign(&mut self, assign: &'b Assignment<'a>) {
+        let mut label = String::new();
+        if assign.var {
+            label.push_str("var ");
+        }
+        if let Some(data_type) = &assign.var_type {
+            write!(label, "{:?} ", data_type).unwrap();
+        }
+        let names: Vec<_> = assign.names.iter().map(|n| n.value).collect();
+        write!(label, "{} =", names.join(", ")).unwrap();
+        if let (true, Some(varids)) = (self.with_scopes, &assign.varids) {
+            write!(label, "\nvarids: {:?}", varids).unwrap();
+        }
+        let id = self.node(&label);
+        self.child_exp(id, &assign.val, "");
+        self.last = id;
+    }
+
+    fn visit_var_assign(&mut self, assign: &'b VarAssignment<'a>) {
+        let id = match self.with_scopes {
+            true => self.node(&format!(
+                "{} :=\n{}",
+                assign.name.value,
+                fmt_index(&assign.var_index)
+            )),
+            false => self.node(&format!("{} :=", assign.name.value)),
+        };
+        self.child_exp(id, &assign.val, "");
+        self.last = id;
+    }
+
+    fn visit_func_call(&mut self, call: &'b FunctionCall<'a>) {
+        let id = match self.with_scopes {
+            true => self.node(&format!("Call\nctxid: {}", call.ctxid)),
+            false => self.node("Call"),
+        };
+        self.child_exp(id, &call.method, "method");
+        for (i, exp) in call.pos_args.iter().enumerate() {
+            self.child_exp(id, exp, &i.to_string());
+        }
+        for (name, exp) in call.dict_args.iter() {
+            self.child_exp(id, exp, name.value);
+        }
+        self.last = id;
+    }
+
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        let params: Vec<_> = def.params.iter().map(|n| n.value).collect();
+        let id = self.node(&format!(
+            "Function {}({})",
+            def.name.value,
+            params.join(", ")
+        ));
+        let scope = format!("function {}", def.name.value);
+        self.child_blk(id, &def.body, &scope, "body");
+        self.last = id;
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        let id = match exp {
+            Exp::FuncCall(_)
+            | Exp::Ite(_)
+            | Exp::ForRange(_)
+            | Exp::ForIn(_)
+            | Exp::Assignment(_)
+            | Exp::VarAssignment(_) => {
+                walk_exp(self, exp);
+                return;
+            }
+            Exp::Na(_) => self.node("na"),
+            Exp::Bool(node) => self.node(&node.value.to_string()),
+            Exp::Num(Numeral::Int(node)) => self.node(&node.value.to_string()),
+            Exp::Num(Numeral::Float(node)) => self.node(&node.to_literal()),
+            Exp::Str(node) => self.node(&format!("{:?}", node.value)),
+            Exp::Color(node) => self.node(node.value),
+            Exp::VarName(name) => match self.with_scopes {
+                true => self.node(&format!(
+                    "{}\n{}",
+                    name.name.value,
+                    fmt_index(&name.var_index)
+                )),
+                false => self.node(name.name.value),
+            },
+            Exp::Tuple(tuple) => {
+                let id = self.node("[...]");
+                for (i, exp) in tuple.exps.iter().enumerate() {
+                    self.child_exp(id, exp, &i.to_string());
+                }
+                id
+            }
+            Exp::TypeCast(cast) => {
+                let id = self.node(&format!("Cast {:?}", cast.data_type));
+                self.child_exp(id, &cast.exp, "");
+                id
+            }
+            Exp::RefCall(call) => {
+                let id = self.node("History []");
+                self.child_exp(id, &call.name, "name");
+                self.child_exp(id, &call.arg, "index");
+                id
+            }
+            Exp::PrefixExp(prefix) => {
+                let id = self.node(&format!(".{}", prefix.right_name.value));
+                self.child_exp(id, &prefix.left_exp, "");
+                id
+            }
+            Exp::Condition(cond) => {
+                let id = self.node("?:");
+                self.child_exp(id, &cond.cond, "cond");
+                self.child_exp(id, &cond.exp1, "then");
+                self.child_exp(id, &cond.exp2, "else");
+                id
+            }
+            Exp::UnaryExp(node) => {
+                let op = match node.op {
+                    UnaryOp::Plus => "+",
+                    UnaryOp::Minus => "-",
+                    UnaryOp::BoolNot => "not",
+                };
+                let id = self.node(op);
+                self.child_exp(id, &node.exp, "");
+                id
+            }
+            Exp::BinaryExp(node) => {
+                let id = self.node(&node.op.to_string());
+                self.child_exp(id, &node.exp1, "");
+                self.child_exp(id, &node.exp2, "");
+                id
+            }
+        };
+        self.last = id;
+    }
+
+    fn visit_stmt(&mut self, stmt: &'b Statement<'a>) {
+        self.last = match stmt {
+            Statement::Break(_) => self.node("break"),
+            Statement::Continue(_) => self.node("continue"),
+            Statement::None(_) => self.node("None"),
+            _ => {
+                walk_stmt(self, stmt);
+                return;
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    #[test]
+    fn to_dot_test() {
+        let blk =
+            parse_ast("m = close > 1.50 ? \"a\\\"b\" : na\nif m\n    n = sma(close, length=2)\n")
+                .unwrap();
+        let dot = to_dot(&blk, false);
+        assert!(dot.starts_with("digraph ast {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("n0 [label=\"Block\"];"));
+        assert!(dot.contains("[label=\"m =\"];"));
+        assert!(dot.contains("[label=\"?:\"];"));
+        assert!(dot.contains("[label=\"1.50\"];"));
+        assert!(dot.contains("[label=\"\\\"a\\\\\\\"b\\\"\"];"));
+        assert!(dot.contains("[label=\"length\"];"));
+        assert!(dot.contains("[label=\"then\"];"));
+        assert!(!dot.contains("subgraph"));
+
+        // The child node is declared before its edge and the clusters are balanced.
+        let dot = to_dot(&blk, true);
+        assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+        assert!(dot.contains("label=\"main\";"));
+        assert!(dot.contains("Call\\nctxid: 0"));
+        for line in dot.lines().filter(|l| l.contains("->")) {
+            let to = line.split("-> ").nth(1).unwrap();
+            let to = to.split([';', ' ']).next().unwrap();
+            let decl = dot.find(&format!("    {} [label", to)).unwrap();
+            assert!(decl < dot.find(line).unwrap());
+        }
+    }
+}
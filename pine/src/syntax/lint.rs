@@ -0,0 +1,286 @@
+use crate::ast::input::StrRange;
+use crate::ast::name::VarName;
+use crate::ast::op::BinaryOp;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LintCode {
+    // The variable is declared but never read.
+    UnusedVariable(String),
+    // The statements after `break` or `continue` are never executed.
+    UnreachableCode,
+    // The condition only contains the literals, so the same branch is always selected.
+    ConstantCondition,
+    // The repainting constructs reported by the repaint audit.
+    SecurityLookahead,
+    FutureOffset,
+    CalcOnEveryTick,
+    RealtimeVariable(String),
+    // The comparison like `x == na` is always false, `na(x)` must be used.
+    NaComparison,
+    // The v4 function name that has the v5 name like `study` and `indicator`.
+    V4FunctionName(String, String),
+}
+
+impl LintCode {
+    // The unused variables and the v4 names are reported as hints, the others as warnings.
+    pub fn is_hint(&self) -> bool {
+        matches!(
+            self,
+            LintCode::UnusedVariable(_) | LintCode::V4FunctionName(_, _)
+        )
+    }
+}
+
+// The v4 function names and the v5 names supported by the runtime.
+pub const V5_FUNCTION_NAMES: [(&str, &str); 12] = [
+    ("abs", "math.abs"),
+    ("exp", "math.exp"),
+    ("financial", "request.financial"),
+    ("log", "math.log"),
+    ("log10", "math.log10"),
+    ("max", "math.max"),
+    ("min", "math.min"),
+    ("pow", "math.pow"),
+    ("sign", "math.sign"),
+    ("sqrt", "math.sqrt"),
+    ("study", "indicator"),
+    ("sum", "math.sum"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub code: LintCode,
+    pub range: StrRange,
+}
+
+impl LintWarning {
+    pub fn new(code: LintCode, range: StrRange) -> LintWarning {
+        LintWarning { code, range }
+    }
+}
+
+// Check if the expression only consists of the literals.
+fn is_const_exp<'a>(exp: &Exp<'a>) -> bool {
+    match exp {
+        Exp::Na(_) | Exp::Bool(_) | Exp::Num(_) | Exp::Str(_) | Exp::Color(_) => true,
+        Exp::UnaryExp(node) => is_const_exp(&node.exp),
+        Exp::BinaryExp(node) => is_const_exp(&node.exp1) && is_const_exp(&node.exp2),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Linter<'a> {
+    // The variables declared by the assignment statements.
+    declares: Vec<VarName<'a>>,
+    // The names read by all the expressions, the scopes are not distinguished.
+    reads: HashSet<&'a str>,
+    // The user functions, they shadow the v4 function names.
+    funcs: HashSet<&'a str>,
+    warnings: Vec<LintWarning>,
+}
+
+impl<'a> Linter<'a> {
+    fn check_cond(&mut self, cond: &Exp<'a>) {
+        if is_const_exp(cond) {
+            self.warnings
+                .push(LintWarning::new(LintCode::ConstantCondition, cond.range()));
+        }
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for Linter<'a> {
+    fn visit_blk(&mut self, blk: &'b Block<'a>) {
+        walk_blk(self, blk);
+
+        // Report the statements after `break` or `continue` as one warning.
+        let stop = blk
+            .stmts
+            .iter()
+            .position(|s| matches!(s, Statement::Break(_) | Statement::Continue(_)));
+        if let Some(stop) = stop {
+            let ranges: Vec<_> = blk.stmts[stop + 1..]
+                .iter()
+                .filter(|s| !matches!(s, Statement::None(_)))
+                .map(|s| s.range())
+                .chain(blk.ret_stmt.iter().map(|e| e.range()))
+                .collect();
+            if let (Some(first), Some(last)) = (ranges.first(), ranges.last()) {
+                self.warnings.push(LintWarning::new(
+                    LintCode::UnreachableCode,
+                    StrRange::new(first.start, last.end),
+                ));
+            }
+        }
+    }
+
+    fn visit_func_call(&mut self, call: &'b FunctionCall<'a>) {
+        if let Exp::VarName(name) = &call.method {
+            let v5_name = V5_FUNCTION_NAMES
+                .iter()
+                .find(|(v4, _)| *v4 == name.name.value);
+            if let Some((v4, v5)) = v5_name {
+                if !self.funcs.contains(v4) {
+                    self.warnings.push(LintWarning::new(
+                        LintCode::V4FunctionName(String::from(*v4), String::from(*v5)),
+                        name.name.range,
+                    ));
+                }
+            }
+        }
+        walk_func_call(self, call);
+    }
+
+    fn visit_ite(&mut self, ite: &'b IfThenElse<'a>) {
+        self.check_cond(&ite.cond);
+        walk_ite(self, ite);
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        match exp {
+            Exp::VarName(name) => {
+                self.reads.insert(name.name.value);
+            }
+            Exp::Condition(c) => self.check_cond(&c.cond),
+            Exp::BinaryExp(node) => {
+                let is_na = |e: &Exp<'a>| matches!(e, Exp::Na(_));
+                if (node.op == BinaryOp::Eq || node.op == BinaryOp::Neq)
+                    && (is_na(&node.exp1) || is_na(&node.exp2))
+                {
+                    self.warnings
+                        .push(LintWarning::new(LintCode::NaComparison, node.range));
+                }
+            }
+            _ => {}
+        }
+        walk_exp(self, exp);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'b Statement<'a>) {
+        // The assignment expression is the value of the block, so only the assignment
+        // statements declare the variables that may be unused.
+        if let Statement::Assignment(assign) = stmt {
+            self.declares
+                .extend(assign.names.iter().filter(|n| n.value != "_").cloned());
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        self.funcs.insert(def.name.value);
+        walk_func_def(self, def);
+    }
+}
+
+// Find the unused variables, the unreachable statements and the constant conditions of the script.
+// The warnings are ordered by the position.
+pub fn lint<'a>(blk: &Block<'a>) -> Vec<LintWarning> {
+    let mut linter = Linter::default();
+    linter.visit_blk(blk);
+
+    let Linter {
+        declares,
+        reads,
+        mut warnings,
+        ..
+    } = linter;
+    warnings.extend(
+        declares
+            .into_iter()
+            .filter(|n| !reads.contains(n.value))
+            .map(|n| LintWarning::new(LintCode::UnusedVariable(String::from(n.value)), n.range)),
+    );
+    warnings.sort_by(|a, b| {
+        a.range
+            .start
+            .partial_cmp(&b.range.start)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::input::Position;
+    use crate::parse_ast;
+
+    #[test]
+    fn unused_var_test() {
+        let blk = parse_ast("a = close\nb = a + 1\n[c, _] = [1, 2]\nplot(c)").unwrap();
+        assert_eq!(
+            lint(&blk),
+            vec![LintWarning::new(
+                LintCode::UnusedVariable(String::from("b")),
+                StrRange::from_start("b", Position::new(1, 0))
+            )]
+        );
+
+        // The variable read by the function and the value of the function are used.
+        let blk = parse_ast("m = 1\nf(x) =>\n    n = x + m\n    k = n * 2\nplot(f(1))").unwrap();
+        assert_eq!(lint(&blk), vec![]);
+    }
+
+    #[test]
+    fn unreachable_test() {
+        let blk =
+            parse_ast("s = 0\nfor i = 1 to 10\n    break\n    s := s + i\n    s := s * 2\nplot(s)")
+                .unwrap();
+        assert_eq!(
+            lint(&blk),
+            vec![LintWarning::new(
+                LintCode::UnreachableCode,
+                StrRange::new(Position::new(3, 4), Position::new(4, 14))
+            )]
+        );
+    }
+
+    #[test]
+    fn const_cond_test() {
+        let blk =
+            parse_ast("m = 1 > 2 ? close : open\nif true\n    plot(m)\nif m > 1\n    plot(m)")
+                .unwrap();
+        assert_eq!(
+            lint(&blk),
+            vec![
+                LintWarning::new(
+                    LintCode::ConstantCondition,
+                    StrRange::new(Position::new(0, 4), Position::new(0, 9))
+                ),
+                LintWarning::new(
+                    LintCode::ConstantCondition,
+                    StrRange::from_start("true", Position::new(1, 3))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn v5_migration_test() {
+        let blk = parse_ast("study('s')\nm = max(close, open)\nplot(m == na ? 1 : 2)").unwrap();
+        assert_eq!(
+            lint(&blk),
+            vec![
+                LintWarning::new(
+                    LintCode::V4FunctionName(String::from("study"), String::from("indicator")),
+                    StrRange::from_start("study", Position::new(0, 0))
+                ),
+                LintWarning::new(
+                    LintCode::V4FunctionName(String::from("max"), String::from("math.max")),
+                    StrRange::from_start("max", Position::new(1, 4))
+                ),
+                LintWarning::new(
+                    LintCode::NaComparison,
+                    StrRange::from_start("m == na", Position::new(2, 5))
+                ),
+            ]
+        );
+
+        // The user function shadows the v4 function name.
+        let blk = parse_ast("max(a, b) => a > b ? a : b\nplot(max(close, open))").unwrap();
+        assert_eq!(lint(&blk), vec![]);
+    }
+}
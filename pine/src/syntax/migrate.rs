@@ -0,0 +1,288 @@
+use super::lint::V5_FUNCTION_NAMES;
+use super::quick_fix::{apply_edits, range_text, TextEdit};
+use super::references::ReferenceIndex;
+use crate::ast::input::{Position, StrRange};
+use crate::ast::name::VarName;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum PineVersion {
+    V3,
+    V4,
+    V5,
+}
+
+impl PineVersion {
+    pub fn number(&self) -> u32 {
+        match self {
+            PineVersion::V3 => 3,
+            PineVersion::V4 => 4,
+            PineVersion::V5 => 5,
+        }
+    }
+}
+
+// The color constants of v3 that are moved into the color namespace in v4.
+const V3_COLOR_NAMES: [&str; 17] = [
+    "aqua", "black", "blue", "fuchsia", "gray", "green", "lime", "maroon", "navy", "olive",
+    "orange", "purple", "red", "silver", "teal", "white", "yellow",
+];
+
+// The v4 functions with the `transp` argument, the position of `transp` and the names,
+// positions and default values of the color arguments that the transparency applies to.
+type TranspFunc = (
+    &'static str,
+    usize,
+    &'static [(&'static str, usize, Option<&'static str>)],
+);
+
+const TRANSP_FUNCS: [TranspFunc; 6] = [
+    ("bgcolor", 1, &[("color", 0, None)]),
+    ("fill", 3, &[("color", 2, None)]),
+    ("plot", 6, &[("color", 2, Some("color.blue"))]),
+    (
+        "plotarrow",
+        4,
+        &[
+            ("colorup", 2, Some("color.green")),
+            ("colordown", 3, Some("color.red")),
+        ],
+    ),
+    ("plotchar", 5, &[("color", 4, Some("color.blue"))]),
+    ("plotshape", 5, &[("color", 4, Some("color.blue"))]),
+];
+
+fn get_arg<'a, 'b>(call: &'b FunctionCall<'a>, pos: usize, name: &str) -> Option<&'b Exp<'a>> {
+    match call.dict_args.iter().find(|(n, _)| n.value == name) {
+        Some((_, exp)) => Some(exp),
+        None => call.pos_args.get(pos),
+    }
+}
+
+struct Migrator<'a, 'b, 'c> {
+    src: &'c str,
+    from: PineVersion,
+    to: PineVersion,
+    index: &'b ReferenceIndex<'a>,
+    edits: Vec<TextEdit>,
+}
+
+impl<'a, 'b, 'c> Migrator<'a, 'b, 'c> {
+    // The name is not declared by the script, so it's the builtin variable or function.
+    fn is_builtin(&self, name: &VarName<'a>) -> bool {
+        self.index.symbol_at(name.range.start).is_none()
+    }
+
+    // Replace `transp=` with the colors wrapped by `color.new`. The omitted color argument is
+    // added with its default color, and the transparency is dropped if it has no default.
+    fn migrate_transp(
+        &mut self,
+        call: &FunctionCall<'a>,
+        transp_pos: usize,
+        colors: &[(&str, usize, Option<&str>)],
+    ) {
+        // Only the last positional argument can be removed without moving the other arguments.
+        let transp = match call.dict_args.iter().find(|(n, _)| n.value == "transp") {
+            Some((name, exp)) => Some((StrRange::new(name.range.start, exp.range().end), exp)),
+            None if call.pos_args.len() == transp_pos + 1 => {
+                let exp = &call.pos_args[transp_pos];
+                Some((exp.range(), exp))
+            }
+            None => None,
+        };
+        let (transp_range, transp_exp) = match transp {
+            Some(t) => t,
+            None => return,
+        };
+        let transp_text = match range_text(self.src, transp_exp.range()) {
+            Some(text) => text,
+            None => return,
+        };
+        let mut missing_colors = vec![];
+        for (name, pos, default) in colors {
+            match (get_arg(call, *pos, name), default) {
+                (Some(exp), _) => {
+                    let range = exp.range();
+                    self.edits.push(TextEdit::new(
+                        StrRange::new(range.start, range.start),
+                        String::from("color.new("),
+                    ));
+                    self.edits.push(TextEdit::new(
+                        StrRange::new(range.end, range.end),
+                        format!(", {})", transp_text),
+                    ));
+                }
+                (None, Some(default)) => {
+                    missing_colors.push(format!("{}=color.new({}, {})", name, default, transp_text))
+                }
+                (None, None) => {}
+            }
+        }
+        // The positional `transp` follows all of the color arguments, so only the named one
+        // can be replaced by the omitted colors.
+        if !missing_colors.is_empty() {
+            self.edits
+                .push(TextEdit::new(transp_range, missing_colors.join(", ")));
+            return;
+        }
+
+        // Remove the argument with the separator before it.
+        let prev_end = call
+            .pos_args
+            .iter()
+            .map(|e| e.range().end)
+            .chain(call.dict_args.iter().map(|(_, e)| e.range().end))
+            .filter(|end| *end <= transp_range.start)
+            .fold(None, |max: Option<Position>, end| match max {
+                Some(m) if m >= end => Some(m),
+                _ => Some(end),
+            });
+        let next_start = call
+            .pos_args
+            .iter()
+            .map(|e| e.range().start)
+            .chain(call.dict_args.iter().map(|(n, _)| n.range.start))
+            .filter(|start| *start >= transp_range.end)
+            .fold(None, |min: Option<Position>, start| match min {
+                Some(m) if m <= start => Some(m),
+                _ => Some(start),
+            });
+        let range = match (prev_end, next_start) {
+            (Some(end), _) => StrRange::new(end, transp_range.end),
+            (None, Some(start)) => StrRange::new(transp_range.start, start),
+            (None, None) => transp_range,
+        };
+        self.edits.push(TextEdit::new(range, String::new()));
+    }
+}
+
+impl<'a, 'b, 'c, 'd> Visitor<'a, 'd> for Migrator<'a, 'b, 'c> {
+    fn visit_func_call(&mut self, call: &'d FunctionCall<'a>) {
+        if let Exp::VarName(name) = &call.method {
+            let name = &name.name;
+            if self.to == PineVersion::V5 && self.is_builtin(name) {
+                if let Some((_, v5)) = V5_FUNCTION_NAMES.iter().find(|(v4, _)| *v4 == name.value) {
+                    self.edits
+                        .push(TextEdit::new(name.range, String::from(*v5)));
+                }
+                if let Some((_, pos, colors)) =
+                    TRANSP_FUNCS.iter().find(|(n, _, _)| *n == name.value)
+                {
+                    self.migrate_transp(call, *pos, colors);
+                }
+            }
+        }
+        walk_func_call(self, call);
+    }
+
+    fn visit_exp(&mut self, exp: &'d Exp<'a>) {
+        if let Exp::VarName(name) = exp {
+            let name = &name.name;
+            if self.from == PineVersion::V3
+                && V3_COLOR_NAMES.contains(&name.value)
+                && self.is_builtin(name)
+            {
+                self.edits
+                    .push(TextEdit::new(name.range, format!("color.{}", name.value)));
+            }
+        }
+        walk_exp(self, exp);
+    }
+}
+
+// Replace the version of the `//@version=` comment, or insert the comment before the script.
+fn migrate_version(src: &str, to: PineVersion) -> TextEdit {
+    for (line, text) in src.split('\n').enumerate() {
+        let trimmed = text.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("//@version=") {
+            let start = (text.chars().count() - trimmed.chars().count()) as u32 + 11;
+            let len = rest.chars().take_while(|c| c.is_ascii_digit()).count() as u32;
+            return TextEdit::new(
+                StrRange::new(
+                    Position::new(line as u32, start),
+                    Position::new(line as u32, start + len),
+                ),
+                to.number().to_string(),
+            );
+        }
+    }
+    let start = Position::new(0, 0);
+    TextEdit::new(
+        StrRange::new(start, start),
+        format!("//@version={}\n", to.number()),
+    )
+}
+
+// Convert the script of the old version to the new version. The v3 color constants are moved
+// into the color namespace, and for v5 the functions are renamed to the namespaced names,
+// `study` is renamed to `indicator` and the `transp` arguments are converted to `color.new`.
+// The edits are applied to the original source, so the comments and the formatting are kept
+// and the result can be compared with the source line by line. The source that can't be parsed
+// is returned unchanged.
+pub fn migrate(src: &str, from: PineVersion, to: PineVersion) -> String {
+    if from >= to {
+        return String::from(src);
+    }
+    let blk = match crate::parse_ast(src) {
+        Ok(blk) | Err((Some(blk), _)) => blk,
+        Err((None, _)) => return String::from(src),
+    };
+    let index = ReferenceIndex::new(&blk);
+    let mut migrator = Migrator {
+        src,
+        from,
+        to,
+        index: &index,
+        edits: vec![migrate_version(src, to)],
+    };
+    migrator.visit_blk(&blk);
+    apply_edits(src, &migrator.edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_v4_test() {
+        let src = r#"//@version=4
+study("Test", overlay=true)
+// The highest of the closes.
+m = max(close, open)
+plot(m, color=color.red, transp=50)
+bgcolor(close > open ? color.green : color.red, 70)
+fill(p1, p2, color=color.blue, transp=80, title="Fill")
+plot(close, transp=20)
+plotarrow(close - open, colordown=color.orange, transp=40)
+fill(p1, p2, transp=90)"#;
+        assert_eq!(
+            migrate(src, PineVersion::V4, PineVersion::V5),
+            r#"//@version=5
+indicator("Test", overlay=true)
+// The highest of the closes.
+m = math.max(close, open)
+plot(m, color=color.new(color.red, 50))
+bgcolor(color.new(close > open ? color.green : color.red, 70))
+fill(p1, p2, color=color.new(color.blue, 80), title="Fill")
+plot(close, color=color.new(color.blue, 20))
+plotarrow(close - open, colordown=color.new(color.orange, 40), colorup=color.new(color.green, 40))
+fill(p1, p2)"#
+        );
+    }
+
+    #[test]
+    fn migrate_v3_test() {
+        // The user function and variable shadow the builtin names.
+        let src = "study(\"Test\")\nmax(a, b) => a > b ? a : b\nwhite = #FFFFFF\nplot(max(close, 1), color=red)\nplot(close, color=white)";
+        assert_eq!(
+            migrate(src, PineVersion::V3, PineVersion::V4),
+            "//@version=4\nstudy(\"Test\")\nmax(a, b) => a > b ? a : b\nwhite = #FFFFFF\nplot(max(close, 1), color=color.red)\nplot(close, color=white)"
+        );
+        assert_eq!(
+            migrate(src, PineVersion::V3, PineVersion::V5),
+            "//@version=5\nindicator(\"Test\")\nmax(a, b) => a > b ? a : b\nwhite = #FFFFFF\nplot(max(close, 1), color=color.red)\nplot(close, color=white)"
+        );
+        assert_eq!(migrate(src, PineVersion::V5, PineVersion::V4), src);
+    }
+}
@@ -4,22 +4,37 @@ use crate::ast::name::VarName;
 use crate::ast::num::Numeral;
 use crate::ast::op::{BinaryOp, UnaryOp};
 use crate::ast::stat_expr_types::{
-    Assignment, BinaryExp, Block, Condition, DataType, Exp, ForRange, FunctionCall, FunctionDef,
-    IfThenElse, PrefixExp, RVVarName, RefCall, Statement, TupleNode, TypeCast, UnaryExp,
-    VarAssignment, VarIndex,
+    Assignment, BinaryExp, Block, Condition, DataType, Exp, ForIn, ForRange, FunctionCall,
+    FunctionDef, IfThenElse, ParamType, PrefixExp, RVVarName, RefCall, Statement, TupleNode,
+    TypeCast, UnaryExp, VarAssignment, VarIndex,
 };
 use crate::ast::state::PineInputError;
-use crate::ast::syntax_type::{FunctionTypes, SimpleSyntaxType, SyntaxType};
+use crate::ast::syntax_type::{
+    FunctionType, FunctionTypes, SimpleSyntaxType, SyntaxType, TypeQualifier,
+};
 use std::collections::HashMap;
 use std::convert::From;
 use std::mem;
 use std::ptr::NonNull;
 use std::rc::Rc;
 
+pub mod annotation;
+pub mod completion;
 mod convert;
 pub mod ctxid_parser;
+pub mod dependency;
+pub mod dot;
 mod input_detector;
+pub mod lint;
+pub mod migrate;
 mod name_rel_parser;
+pub mod quick_fix;
+pub mod references;
+pub mod repaint;
+#[cfg(feature = "runtime")]
+pub mod specialize;
+pub mod stmt_deps;
+pub mod structure;
 mod type_cast;
 pub mod types_id_gen;
 
@@ -30,6 +45,18 @@ use convert::{common_type, implicity_convert, similar_type, simple_to_series};
 use type_cast::{explicity_type_cast, implicity_type_cast};
 use types_id_gen::TypesIdGen;
 
+// The element type of the array selected by the type argument.
+fn type_arg_to_simple(data_type: &DataType) -> Option<SimpleSyntaxType> {
+    match data_type {
+        DataType::Float => Some(SimpleSyntaxType::Float),
+        DataType::Int => Some(SimpleSyntaxType::Int),
+        DataType::Bool => Some(SimpleSyntaxType::Bool),
+        DataType::Color => Some(SimpleSyntaxType::Color),
+        DataType::String => Some(SimpleSyntaxType::String),
+        DataType::Custom(_) => None,
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ContextType {
     Library,
@@ -81,6 +108,8 @@ pub struct SyntaxContext<'a> {
     subctxs: Vec<Box<dyn SyntaxCtx<'a> + 'a>>,
 
     vars: HashMap<&'a str, SyntaxType<'a>>,
+    // The const and input qualifiers of the variables, the others are derived from their types.
+    qualifiers: HashMap<&'a str, TypeQualifier>,
     // User defined function's type for the specific name, the name is like method_name@id
     user_func_types: HashMap<String, (SyntaxType<'a>, i32)>,
 
@@ -236,6 +265,7 @@ impl<'a> SyntaxContext<'a> {
             subctxs: vec![],
             user_func_types: HashMap::new(),
             vars: HashMap::new(),
+            qualifiers: HashMap::new(),
             input_detector: None,
             input_varnames: vec![],
             var_indexs: HashMap::new(),
@@ -245,6 +275,26 @@ impl<'a> SyntaxContext<'a> {
         }
     }
 
+    // Set the qualifier of the variable declared in the context, None means the qualifier is
+    // derived from the type.
+    pub fn declare_qualifier(&mut self, name: &'a str, qualifier: Option<TypeQualifier>) {
+        match qualifier {
+            Some(qualifier) => self.qualifiers.insert(name, qualifier),
+            None => self.qualifiers.remove(name),
+        };
+    }
+
+    // Get the qualifier of the variable from the context that declares it.
+    pub fn get_qualifier(&self, name: &'a str) -> Option<TypeQualifier> {
+        if self.vars.contains_key(name) {
+            self.qualifiers.get(name).cloned()
+        } else if let Some(p) = self.parent {
+            downcast_ctx(p.as_ptr()).get_qualifier(name)
+        } else {
+            None
+        }
+    }
+
     pub fn set_input_detector(&mut self, detector: *const dyn InputSrcDetector<'a>) {
         debug_assert_eq!(self.context_type, ContextType::Main);
         self.input_detector = Some(detector);
@@ -307,31 +357,210 @@ pub struct SyntaxParser<'a> {
     // The types id generator that generate same id for the same types.
     types_id_gen: TypesIdGen<'a>,
     errors: Vec<PineInputError>,
+    // Whether the script declaration(study, indicator, strategy) has been called.
+    has_script_decl: bool,
+    // The severity for the variables that shadow the builtin variables.
+    shadow_severity: ShadowSeverity,
+    warnings: Vec<PineInputError>,
+}
+
+// The severity of the diagnostic reported when the script declares a variable or function with
+// the name of the builtin variable or function, e.g. `close = 1`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ShadowSeverity {
+    Allow,
+    Warning,
+    Error,
+}
+
+// Generate the type cast error, the types that are not simple or series can't be casted.
+fn type_cast_err<'a>(origin: &SyntaxType<'a>, cast: Option<SimpleSyntaxType>) -> PineErrorKind {
+    match (origin, cast) {
+        (SyntaxType::Simple(origin), Some(cast)) | (SyntaxType::Series(origin), Some(cast)) => {
+            PineErrorKind::InvalidTypeCast {
+                origin: origin.clone(),
+                cast,
+            }
+        }
+        _ => PineErrorKind::TypeMismatch,
+    }
+}
+
+// The error of the binary expression shows both operand types and points at both operands.
+fn binary_type_err<'a>(
+    binary: &BinaryExp<'a>,
+    exp1_type: &SyntaxType<'a>,
+    exp2_type: &SyntaxType<'a>,
+) -> PineInputError {
+    PineInputError::new_with_related(
+        PineErrorKind::BinaryOpTypeNotMatch {
+            op: binary.op.to_string(),
+            left: exp1_type.to_string(),
+            right: exp2_type.to_string(),
+        },
+        binary.range,
+        vec![binary.exp1.range(), binary.exp2.range()],
+    )
+}
+
+fn arg_match<'a>(arg: &SyntaxType<'a>, param: &SyntaxType<'a>) -> bool {
+    arg == param || implicity_convert(arg, param)
+}
+
+// Find the series argument passed to the parameter requiring the simple value. The error is
+// reported only if the call matches the signature when the series arguments are simple.
+fn find_qualifier_err<'a>(
+    func_call: &FunctionCall<'a>,
+    fun_type: &FunctionTypes<'a>,
+    pos_arg_type: &[ParseValue<'a>],
+    dict_arg_type: &[(VarName<'a>, ParseValue<'a>)],
+) -> Option<PineInputError> {
+    'outer: for func in fun_type.0.iter() {
+        let (params, _) = &func.signature;
+        if params.len() < pos_arg_type.len() {
+            continue;
+        }
+        let mut args = vec![];
+        for (i, (arg, param)) in pos_arg_type.iter().zip(params.iter()).enumerate() {
+            args.push((&arg.syntax_type, param, func_call.pos_args[i].range()));
+        }
+        for (i, (name, arg)) in dict_arg_type.iter().enumerate() {
+            match params.iter().find(|p| p.0 == name.value) {
+                Some(param) => {
+                    args.push((&arg.syntax_type, param, func_call.dict_args[i].1.range()))
+                }
+                None => continue 'outer,
+            }
+        }
+        if !args
+            .iter()
+            .all(|(arg, param, _)| arg_match(&arg.to_simple(), &param.1))
+        {
+            continue;
+        }
+        if let Some((_, param, range)) = args
+            .iter()
+            .find(|(arg, param, _)| !arg_match(arg, &param.1))
+        {
+            return Some(PineInputError::new(
+                PineErrorKind::ArgQualifierNotMatch {
+                    param: String::from(param.0),
+                    qualifier: param.1.get_qualifier().unwrap_or(TypeQualifier::Simple),
+                    found: TypeQualifier::Series,
+                },
+                *range,
+            ));
+        }
+    }
+    None
+}
+
+// The literal default value or option of the input, the numbers are compared as float.
+#[derive(PartialEq)]
+enum InputLiteral<'b> {
+    Num(f64),
+    Str(&'b str),
+}
+
+// The default value and the options of the input call. The options are the 5th argument of the
+// string input and the 8th argument of the numeric inputs.
+fn input_args<'a, 'b>(
+    func_call: &'b FunctionCall<'a>,
+) -> Option<(&'b Exp<'a>, Option<&'b Exp<'a>>)> {
+    let find_arg =
+        |name: &str, pos: usize| match func_call.dict_args.iter().find(|(n, _)| n.value == name) {
+            Some((_, exp)) => Some(exp),
+            None => func_call.pos_args.get(pos),
+        };
+    let defval = find_arg("defval", 0)?;
+    let options_pos = match defval {
+        Exp::Str(_) => 4,
+        _ => 7,
+    };
+    Some((defval, find_arg("options", options_pos)))
+}
+
+fn input_literal<'a, 'b>(exp: &'b Exp<'a>) -> Option<InputLiteral<'b>> {
+    match exp {
+        Exp::Num(Numeral::Int(node)) => Some(InputLiteral::Num(node.value as f64)),
+        Exp::Num(Numeral::Float(node)) => Some(InputLiteral::Num(node.value)),
+        Exp::Str(node) => Some(InputLiteral::Str(&node.value)),
+        _ => None,
+    }
 }
 
+// The library functions that declare the script.
+const SCRIPT_DECLARATIONS: [&str; 3] = ["study", "indicator", "strategy"];
+
+// The parameters of the library functions that require the stronger qualifiers than simple, e.g.
+// the titles are shown before the script runs, so they must be const.
+const QUALIFIED_PARAMS: [(&str, &str, TypeQualifier); 21] = [
+    ("study", "title", TypeQualifier::Const),
+    ("study", "shorttitle", TypeQualifier::Const),
+    ("indicator", "title", TypeQualifier::Const),
+    ("indicator", "shorttitle", TypeQualifier::Const),
+    ("strategy", "title", TypeQualifier::Const),
+    ("strategy", "shorttitle", TypeQualifier::Const),
+    ("input", "defval", TypeQualifier::Const),
+    ("input", "title", TypeQualifier::Const),
+    ("input", "minval", TypeQualifier::Const),
+    ("input", "maxval", TypeQualifier::Const),
+    ("input", "step", TypeQualifier::Const),
+    ("plot", "title", TypeQualifier::Const),
+    ("plotshape", "title", TypeQualifier::Const),
+    ("plotchar", "title", TypeQualifier::Const),
+    ("plotarrow", "title", TypeQualifier::Const),
+    ("plotcandle", "title", TypeQualifier::Const),
+    ("plotbar", "title", TypeQualifier::Const),
+    ("bgcolor", "title", TypeQualifier::Const),
+    ("fill", "title", TypeQualifier::Const),
+    ("hline", "title", TypeQualifier::Const),
+    ("hline", "price", TypeQualifier::Input),
+];
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParseValue<'a> {
     pub syntax_type: SyntaxType<'a>,
     pub varname: Option<&'a str>,
+    // The qualifier of the value, it's None if the type is not a value, e.g. the function.
+    pub qualifier: Option<TypeQualifier>,
 }
 
 impl<'a> ParseValue<'a> {
     pub fn new(syntax_type: SyntaxType<'a>, varname: &'a str) -> ParseValue<'a> {
         ParseValue {
+            qualifier: syntax_type.get_qualifier(),
             syntax_type,
             varname: Some(varname),
         }
     }
     pub fn new_with_type(syntax_type: SyntaxType<'a>) -> ParseValue<'a> {
         ParseValue {
+            qualifier: syntax_type.get_qualifier(),
             syntax_type,
             varname: None,
         }
     }
+
+    // Set the qualifier of the simple value if it's known, the series value is always series.
+    pub fn with_qualifier(mut self, qualifier: Option<TypeQualifier>) -> ParseValue<'a> {
+        if qualifier.is_some()
+            && self.qualifier.is_some()
+            && self.qualifier != Some(TypeQualifier::Series)
+        {
+            self.qualifier = qualifier;
+        }
+        self
+    }
 }
 
 type ParseResult<'a> = Result<ParseValue<'a>, PineInputError>;
 
+// The value of the literal.
+fn const_value<'a>(t: SimpleSyntaxType) -> ParseValue<'a> {
+    ParseValue::new_with_type(SyntaxType::Simple(t)).with_qualifier(Some(TypeQualifier::Const))
+}
+
 impl<'a> SyntaxParser<'a> {
     pub fn new() -> SyntaxParser<'a> {
         let mut _lib_ctx = Box::new(SyntaxContext::new(None, ContextType::Library));
@@ -350,6 +579,9 @@ impl<'a> SyntaxParser<'a> {
             user_funcs: HashMap::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
+            has_script_decl: false,
+            shadow_severity: ShadowSeverity::Warning,
+            warnings: vec![],
         }
     }
 
@@ -375,6 +607,9 @@ impl<'a> SyntaxParser<'a> {
             user_funcs: HashMap::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
+            has_script_decl: false,
+            shadow_severity: ShadowSeverity::Warning,
+            warnings: vec![],
         }
     }
 
@@ -401,6 +636,9 @@ impl<'a> SyntaxParser<'a> {
             user_funcs: HashMap::new(),
             types_id_gen: TypesIdGen::new(),
             errors: vec![],
+            has_script_decl: false,
+            shadow_severity: ShadowSeverity::Warning,
+            warnings: vec![],
         }
     }
 
@@ -427,6 +665,32 @@ impl<'a> SyntaxParser<'a> {
         mem::replace(&mut self.errors, vec![])
     }
 
+    pub fn set_shadow_severity(&mut self, severity: ShadowSeverity) {
+        self.shadow_severity = severity;
+    }
+
+    pub fn get_warnings(&self) -> &Vec<PineInputError> {
+        &self.warnings
+    }
+
+    pub fn move_warnings(&mut self) -> Vec<PineInputError> {
+        mem::replace(&mut self.warnings, vec![])
+    }
+
+    // Check if the declared name shadows the builtin variable or function.
+    fn check_shadow(&mut self, name: &VarName<'a>) {
+        if !self._lib_ctx.vars.contains_key(name.value) {
+            return;
+        }
+        // The function body is parsed for every specific definition, so report the name once.
+        let err = PineInputError::new(PineErrorKind::ShadowBuiltinVar, name.range);
+        match self.shadow_severity {
+            ShadowSeverity::Warning if !self.warnings.contains(&err) => self.warnings.push(err),
+            ShadowSeverity::Error if !self.errors.contains(&err) => self.catch(err),
+            _ => {}
+        }
+    }
+
     pub fn move_context(&mut self) -> Box<SyntaxContext<'a>> {
         mem::replace(
             &mut self._root_ctx,
@@ -452,8 +716,9 @@ impl<'a> SyntaxParser<'a> {
         def.varids = Some(varids);
 
         self.context = &mut *sub_ctx;
-        self.parse_blk_only(&mut def.body)?;
+        let res = self.parse_blk_only(&mut def.body);
         self.context = sub_ctx.parent.unwrap().as_ptr();
+        res?;
 
         // Insert function definition
         let def_ctxid = self._root_ctx.gen_var_index_only();
@@ -481,6 +746,43 @@ impl<'a> SyntaxParser<'a> {
             dict_arg_type.push((name.clone(), self.parse_exp(exp)?));
         }
 
+        // The generic signatures are matched by their concrete instances in the declaration order.
+        let fun_type = match fun_type.has_template() {
+            true => Rc::new(fun_type.instances()),
+            false => fun_type.clone(),
+        };
+
+        // The variadic signatures are expanded to accept all the positional arguments.
+        let fun_type = match fun_type.has_variadic() {
+            true => Rc::new(fun_type.expand_args(pos_arg_type.len())),
+            false => fun_type,
+        };
+
+        // The type argument like `array.new<float>()` selects the signatures that return the array
+        // of the element type.
+        let fun_type = match &func_call.type_arg {
+            None => fun_type,
+            Some(data_type) => {
+                let types: Vec<_> = match type_arg_to_simple(data_type) {
+                    Some(t) => fun_type
+                        .0
+                        .iter()
+                        .filter(|func| func.signature.1 == SyntaxType::List(t.clone()))
+                        .cloned()
+                        .collect(),
+                    None => vec![],
+                };
+                if types.is_empty() {
+                    return Err(PineInputError::new(
+                        PineErrorKind::TypeArgNotMatch,
+                        func_call.range,
+                    ));
+                }
+                Rc::new(FunctionTypes(types))
+            }
+        };
+        let fun_type = &fun_type;
+
         let res_fun = fun_type.0.iter().find(|func| {
             let (args, _) = &func.signature;
             if args.len() >= pos_arg_type.len() {
@@ -503,11 +805,15 @@ impl<'a> SyntaxParser<'a> {
 
         func_call.ctxid = downcast_ctx(self.context).gen_lib_func_index();
         match res_fun {
-            None => Err(PineInputError::new(
-                PineErrorKind::FuncCallSignatureNotMatch,
-                func_call.range,
-            )),
+            None => match find_qualifier_err(func_call, fun_type, &pos_arg_type, &dict_arg_type) {
+                Some(err) => Err(err),
+                None => Err(PineInputError::new(
+                    PineErrorKind::FuncCallSignatureNotMatch,
+                    func_call.range,
+                )),
+            },
             Some(d) => {
+                self.check_arg_qualifiers(func_call, d, &pos_arg_type, &dict_arg_type);
                 for i in 0..pos_arg_type.len() {
                     if let Some(&SyntaxType::DynamicExpr(_)) = d.get_type(i) {
                         let new_exp = self.build_dynamic_exp(&func_call.pos_args[i])?;
@@ -522,6 +828,21 @@ impl<'a> SyntaxParser<'a> {
                         func_call.dict_args[i] = (name, new_exp);
                     }
                 }
+                // Resolve the dict argument names to the parameter positions once, so the runtime
+                // need not search the names for every call.
+                func_call.dict_arg_indexs = Some(
+                    func_call
+                        .dict_args
+                        .iter()
+                        .map(|(name, _)| {
+                            d.signature
+                                .0
+                                .iter()
+                                .position(|s| s.0 == name.value)
+                                .unwrap()
+                        })
+                        .collect(),
+                );
                 func_call.func_type = Some(d.clone());
                 Ok(ParseValue::new_with_type((d.signature).1.clone()))
             }
@@ -549,6 +870,18 @@ impl<'a> SyntaxParser<'a> {
         unsafe {
             func_def = self.user_funcs[method_name].as_mut().unwrap();
         };
+        // The parameters annotated by const or input keep the qualifiers in the body.
+        for (&n, param_type) in names.iter().zip(func_def.param_types.iter()) {
+            if let Some(ParamType {
+                qualifier: Some(qualifier),
+                ..
+            }) = param_type
+            {
+                if *qualifier < TypeQualifier::Simple {
+                    sub_ctx.declare_qualifier(n, Some(*qualifier));
+                }
+            }
+        }
         // Create specific function definition from the origin function type.
         // The specific function definition contain the type information.
         let mut spec_def = func_def.gen_spec_def();
@@ -561,13 +894,17 @@ impl<'a> SyntaxParser<'a> {
         self.name_rel_parser
             .enter_ctx(self.context, func_call.ctxid);
 
-        let parse_res = self.parse_blk(&mut spec_def.body)?;
-        // Push the specific function definition to spec_defs.
-        func_def.spec_defs.as_mut().unwrap().push(spec_def);
-        let spec_index = func_def.spec_defs.as_ref().unwrap().len() as i32 - 1;
+        let parse_res = self.parse_blk(&mut spec_def.body);
 
+        // Restore the parent context before returning the error, otherwise the context
+        // would point to the dropped sub context.
         self.name_rel_parser.exit_ctx();
         self.context = sub_ctx.parent.unwrap().as_ptr();
+        let parse_res = parse_res?;
+
+        // Push the specific function definition to spec_defs.
+        func_def.spec_defs.as_mut().unwrap().push(spec_def);
+        let spec_index = func_def.spec_defs.as_ref().unwrap().len() as i32 - 1;
 
         let parent_context = downcast_ctx(self.context);
         parent_context.subctxs.push(sub_ctx);
@@ -580,13 +917,58 @@ impl<'a> SyntaxParser<'a> {
         Ok(ParseValue::new_with_type(parse_res.syntax_type))
     }
 
+    fn parse_param_type(
+        &mut self,
+        arg: &ParseValue<'a>,
+        name: &'a str,
+        param_type: &ParamType<'a>,
+        range: StrRange,
+    ) -> SyntaxType<'a> {
+        let arg_type = &arg.syntax_type;
+        let (is_cast_err, result) = implicity_type_cast(arg_type, &param_type.data_type);
+        if is_cast_err {
+            self.catch(PineInputError::new(
+                type_cast_err(
+                    arg_type,
+                    Some(SimpleSyntaxType::from(param_type.data_type.clone())),
+                ),
+                range,
+            ));
+        }
+        match (param_type.qualifier, result) {
+            (Some(TypeQualifier::Series), SyntaxType::Simple(t)) => SyntaxType::Series(t),
+            (Some(TypeQualifier::Series), result) | (None, result) => result,
+            (Some(qualifier), result) => {
+                if let Some(found) = arg.qualifier.filter(|found| *found > qualifier) {
+                    self.catch(PineInputError::new(
+                        PineErrorKind::ArgQualifierNotMatch {
+                            param: String::from(name),
+                            qualifier,
+                            found,
+                        },
+                        range,
+                    ));
+                }
+                match result {
+                    SyntaxType::Series(t) => SyntaxType::Simple(t),
+                    result => result,
+                }
+            }
+        }
+    }
+
     fn parse_user_func_call(
         &mut self,
         func_call: &mut FunctionCall<'a>,
         names: &Vec<&'a str>,
         method_name: &'a str,
     ) -> ParseResult<'a> {
-        if func_call.dict_args.len() > 0 {
+        if func_call.type_arg.is_some() {
+            Err(PineInputError::new(
+                PineErrorKind::TypeArgNotMatch,
+                func_call.range,
+            ))
+        } else if func_call.dict_args.len() > 0 {
             Err(PineInputError::new(
                 PineErrorKind::ForbiddenDictArgsForUserFunc,
                 func_call.range,
@@ -597,9 +979,23 @@ impl<'a> SyntaxParser<'a> {
                 func_call.range,
             ))
         } else {
-            let mut pos_arg_type = vec![];
+            let mut args = vec![];
             for arg in func_call.pos_args.iter_mut() {
-                pos_arg_type.push(self.parse_exp(arg)?.syntax_type);
+                args.push(self.parse_exp(arg)?);
+            }
+            // Cast the argument types to the annotated parameter types, so the calls with
+            // the compatible arguments can share the same specific definition.
+            let func_def = unsafe { self.user_funcs[method_name].as_ref().unwrap() };
+            let mut pos_arg_type = vec![];
+            for (i, param_type) in func_def.param_types.iter().enumerate() {
+                pos_arg_type.push(match param_type {
+                    Some(param_type) => {
+                        let name = func_def.params[i].value;
+                        let range = func_call.pos_args[i].range();
+                        self.parse_param_type(&args[i], name, param_type, range)
+                    }
+                    None => args[i].syntax_type.clone(),
+                });
             }
             let typeid = self.types_id_gen.get(&pos_arg_type);
             // Generate name by the argument types.
@@ -608,7 +1004,9 @@ impl<'a> SyntaxParser<'a> {
             let context = downcast_ctx(self.context);
             func_call.ctxid = context.gen_child_ctx_index();
 
-            match context.get_user_func(&fun_name) {
+            // Only reuse the specific definition generated in the current context, the variable
+            // indexes of the definition are relative to the context that calls the function.
+            match context.user_func_types.get(&fun_name) {
                 Some((SyntaxType::UserFunction(func_type), index)) => {
                     func_call.spec_index = *index;
                     Ok(ParseValue::new_with_type(func_type.1.clone()))
@@ -622,6 +1020,13 @@ impl<'a> SyntaxParser<'a> {
     }
 
     fn parse_func_call(&mut self, func_call: &mut FunctionCall<'a>) -> ParseResult<'a> {
+        self.check_input_options(func_call);
+        let qualifier = self.input_qualifier(func_call);
+        let res = self.parse_func_call_type(func_call)?;
+        Ok(res.with_qualifier(qualifier))
+    }
+
+    fn parse_func_call_type(&mut self, func_call: &mut FunctionCall<'a>) -> ParseResult<'a> {
         let method_type = self.parse_exp(&mut func_call.method)?;
         match method_type.syntax_type {
             SyntaxType::Function(fun_type) => self.parse_std_func_call(func_call, &fun_type),
@@ -645,6 +1050,123 @@ impl<'a> SyntaxParser<'a> {
         }
     }
 
+    // The script declaration must be called only once and as a statement in the top level block.
+    fn parse_script_decl(&mut self, func_call: &FunctionCall<'a>, is_top_stmt: bool) {
+        match self.lib_func_name(func_call) {
+            Some(name) if SCRIPT_DECLARATIONS.contains(&name) => {}
+            _ => return,
+        }
+        if !is_top_stmt {
+            self.catch(PineInputError::new(
+                PineErrorKind::ScriptDeclNotTopLevel,
+                func_call.range,
+            ));
+        } else if self.has_script_decl {
+            self.catch(PineInputError::new(
+                PineErrorKind::ScriptDeclDuplicate,
+                func_call.range,
+            ));
+        } else {
+            self.has_script_decl = true;
+        }
+    }
+
+    // The name of the library function called by the call, None if the function is declared by
+    // the script.
+    fn lib_func_name(&self, func_call: &FunctionCall<'a>) -> Option<&'a str> {
+        match &func_call.method {
+            Exp::VarName(RVVarName { name, .. })
+                if self._lib_ctx.vars.contains_key(name.value)
+                    && !self._root_ctx.vars.contains_key(name.value) =>
+            {
+                Some(name.value)
+            }
+            _ => None,
+        }
+    }
+
+    // The input with the options is const, so it can be passed to the const parameters.
+    fn input_qualifier(&self, func_call: &FunctionCall<'a>) -> Option<TypeQualifier> {
+        match self.lib_func_name(func_call) {
+            Some("input") => match input_args(func_call) {
+                Some((_, Some(_))) => Some(TypeQualifier::Const),
+                _ => Some(TypeQualifier::Input),
+            },
+            _ => None,
+        }
+    }
+
+    // Check the arguments of the library function against the qualifiers its parameters require.
+    // The series parameters like the source of the input accept any argument.
+    fn check_arg_qualifiers(
+        &mut self,
+        func_call: &FunctionCall<'a>,
+        func_type: &FunctionType<'a>,
+        pos_arg_type: &[ParseValue<'a>],
+        dict_arg_type: &[(VarName<'a>, ParseValue<'a>)],
+    ) {
+        let func_name = match self.lib_func_name(func_call) {
+            Some(name) => name,
+            None => return,
+        };
+        let pos_args = pos_arg_type.iter().enumerate().filter_map(|(i, arg)| {
+            let param = func_type.signature.0.get(i)?.0;
+            Some((param, arg, func_call.pos_args[i].range()))
+        });
+        let dict_args = dict_arg_type
+            .iter()
+            .enumerate()
+            .map(|(i, (name, arg))| (name.value, arg, func_call.dict_args[i].1.range()));
+        let mut errors = vec![];
+        for (param, arg, range) in pos_args.chain(dict_args) {
+            let qualifier = match QUALIFIED_PARAMS
+                .iter()
+                .find(|(f, p, _)| *f == func_name && *p == param)
+            {
+                Some((_, _, qualifier)) => *qualifier,
+                None => continue,
+            };
+            let param_type = func_type.get_type_by_name(param);
+            if param_type.and_then(|t| t.get_qualifier()) != Some(TypeQualifier::Simple) {
+                continue;
+            }
+            if let Some(found) = arg.qualifier.filter(|found| *found > qualifier) {
+                errors.push(PineInputError::new(
+                    PineErrorKind::ArgQualifierNotMatch {
+                        param: String::from(param),
+                        qualifier,
+                        found,
+                    },
+                    range,
+                ));
+            }
+        }
+        errors.into_iter().for_each(|err| self.catch(err));
+    }
+
+    // The literal default value of the input must be one of the literal options.
+    fn check_input_options(&mut self, func_call: &FunctionCall<'a>) {
+        if self.lib_func_name(func_call) != Some("input") {
+            return;
+        }
+        let (defval, options) = match input_args(func_call) {
+            Some((defval, Some(Exp::Tuple(options)))) => match input_literal(defval) {
+                Some(defval) => (defval, options),
+                None => return,
+            },
+            _ => return,
+        };
+        let literals: Option<Vec<InputLiteral>> = options.exps.iter().map(input_literal).collect();
+        if let Some(literals) = literals {
+            if !literals.contains(&defval) {
+                self.catch(PineInputError::new(
+                    PineErrorKind::InputDefvalNotInOptions,
+                    func_call.range,
+                ));
+            }
+        }
+    }
+
     fn parse_tuple(&mut self, tuple: &mut TupleNode<'a>) -> ParseResult<'a> {
         let mut tuple_type: Vec<SyntaxType<'a>> = vec![];
         for arg in tuple.exps.iter_mut() {
@@ -791,10 +1313,11 @@ impl<'a> SyntaxParser<'a> {
         self.context = &mut *if_ctx;
         self.name_rel_parser.enter_ctx(self.context, ite.then_ctxid);
 
-        let then_res = self.parse_blk(&mut ite.then_blk)?;
+        let then_res = self.parse_blk(&mut ite.then_blk);
 
         self.name_rel_parser.exit_ctx();
         self.context = if_ctx.parent.unwrap().as_ptr();
+        let then_res = then_res?;
 
         downcast_ctx(self.context).subctxs.push(if_ctx);
         Ok(then_res)
@@ -814,10 +1337,11 @@ impl<'a> SyntaxParser<'a> {
         self.context = &mut *else_ctx;
         self.name_rel_parser.enter_ctx(self.context, *ctxid);
 
-        let else_res = self.parse_blk(else_blk)?;
+        let else_res = self.parse_blk(else_blk);
 
         self.name_rel_parser.exit_ctx();
         self.context = else_ctx.parent.unwrap().as_ptr();
+        let else_res = else_res?;
 
         downcast_ctx(self.context).subctxs.push(else_ctx);
         Ok(else_res)
@@ -920,10 +1444,11 @@ impl<'a> SyntaxParser<'a> {
         self.name_rel_parser
             .enter_ctx(self.context, for_range.ctxid);
 
-        let blk_res = self.parse_blk(&mut for_range.do_blk)?;
+        let blk_res = self.parse_blk(&mut for_range.do_blk);
 
         self.name_rel_parser.exit_ctx();
         self.context = for_ctx.parent.unwrap().as_ptr();
+        let blk_res = blk_res?;
 
         downcast_ctx(self.context).subctxs.push(for_ctx);
 
@@ -955,6 +1480,82 @@ impl<'a> SyntaxParser<'a> {
         Ok(ParseValue::new_with_type(res_type))
     }
 
+    // Get the element type of the array iterated by the for-in statement.
+    fn get_item_type(&mut self, iter: &Exp<'a>, iter_type: SyntaxType<'a>) -> SyntaxType<'a> {
+        let item_type = match iter_type {
+            SyntaxType::List(t) => Some(SyntaxType::Simple(t)),
+            SyntaxType::Tuple(types) if !types.is_empty() => types
+                .iter()
+                .skip(1)
+                .try_fold(types[0].clone(), |t, s| common_type(&t, s)),
+            _ => None,
+        };
+        match item_type {
+            Some(t @ SyntaxType::Simple(_)) | Some(t @ SyntaxType::Series(_)) => t,
+            _ => {
+                self.catch(PineInputError::new(
+                    PineErrorKind::ForInNotIterable,
+                    iter.range(),
+                ));
+                SyntaxType::Any
+            }
+        }
+    }
+
+    fn parse_forin(&mut self, for_in: &mut ForIn<'a>) -> ParseResult<'a> {
+        let iter_res = self.parse_exp(&mut for_in.iter)?;
+        for_in.item_type = self.get_item_type(&for_in.iter, iter_res.syntax_type);
+        for_in.ctxid = downcast_ctx(self.context).gen_child_ctx_index();
+
+        let mut for_ctx = Box::new(SyntaxContext::new(
+            unsafe { Some(NonNull::new_unchecked(self.context)) },
+            ContextType::ForRangeBlock,
+        ));
+        if let Some(index_var) = &for_in.index_var {
+            for_ctx.declare_var(index_var.value, SyntaxType::Simple(SimpleSyntaxType::Int));
+            for_in.index_varid = for_ctx.gen_var_index(index_var.value);
+        }
+        for_ctx.declare_var(for_in.var.value, for_in.item_type.clone());
+        for_in.varid = for_ctx.gen_var_index(for_in.var.value);
+        self.context = &mut *for_ctx;
+        self.name_rel_parser.enter_ctx(self.context, for_in.ctxid);
+
+        let blk_res = self.parse_blk(&mut for_in.do_blk);
+
+        self.name_rel_parser.exit_ctx();
+        self.context = for_ctx.parent.unwrap().as_ptr();
+        let blk_res = blk_res?;
+
+        downcast_ctx(self.context).subctxs.push(for_ctx);
+
+        Ok(blk_res)
+    }
+
+    fn parse_forin_stmt(&mut self, for_in: &mut ForIn<'a>) -> ParseResult<'a> {
+        self.parse_forin(for_in)?;
+        for_in.result_type = SyntaxType::Void;
+        Ok(ParseValue::new_with_type(SyntaxType::Void))
+    }
+
+    pub fn parse_forin_exp(&mut self, for_in: &mut ForIn<'a>) -> ParseResult<'a> {
+        let blk_res = self.parse_forin(for_in)?;
+        if blk_res.syntax_type.is_void() {
+            return Err(PineInputError::new(
+                PineErrorKind::ExpNoReturn,
+                for_in.do_blk.range,
+            ));
+        }
+        if blk_res.syntax_type.is_na() {
+            return Err(PineInputError::new(
+                PineErrorKind::ExpReturnNa,
+                for_in.do_blk.range,
+            ));
+        }
+        let res_type = simple_to_series(blk_res.syntax_type);
+        for_in.result_type = res_type.clone();
+        Ok(ParseValue::new_with_type(res_type))
+    }
+
     fn parse_varname(&mut self, varname: &mut RVVarName<'a>) -> ParseResult<'a> {
         let name = varname.name.value;
         let ctx_ins = downcast_ctx(self.context);
@@ -984,13 +1585,15 @@ impl<'a> SyntaxParser<'a> {
                     }
                     _ => {}
                 }
-                Ok(ParseValue::new(val.clone(), name))
+                let qualifier = downcast_ctx(self.context).get_qualifier(name);
+                Ok(ParseValue::new(val.clone(), name).with_qualifier(qualifier))
             }
         }
     }
 
     fn parse_type_cast(&mut self, type_cast: &mut TypeCast<'a>) -> ParseResult<'a> {
-        let origin_type = self.parse_exp(&mut type_cast.exp)?.syntax_type;
+        let origin_res = self.parse_exp(&mut type_cast.exp)?;
+        let (origin_type, qualifier) = (origin_res.syntax_type, origin_res.qualifier);
         let (is_cast_err, result) = explicity_type_cast(&origin_type, &type_cast.data_type);
         match result {
             SyntaxType::ObjectClass(obj_cls) => {
@@ -1001,19 +1604,20 @@ impl<'a> SyntaxParser<'a> {
         }
         if is_cast_err {
             self.catch(PineInputError::new(
-                PineErrorKind::InvalidTypeCast {
-                    origin: SimpleSyntaxType::from(origin_type),
-                    cast: SimpleSyntaxType::from(type_cast.data_type.clone()),
-                },
+                type_cast_err(
+                    &origin_type,
+                    Some(SimpleSyntaxType::from(type_cast.data_type.clone())),
+                ),
                 type_cast.range,
             ));
         }
-        Ok(ParseValue::new_with_type(result))
+        Ok(ParseValue::new_with_type(result).with_qualifier(qualifier))
     }
 
     fn parse_unary(&mut self, unary: &mut UnaryExp<'a>) -> ParseResult<'a> {
-        let exp_type = self.parse_exp(&mut unary.exp)?.syntax_type;
-        match unary.op {
+        let exp_res = self.parse_exp(&mut unary.exp)?;
+        let (exp_type, qualifier) = (exp_res.syntax_type, exp_res.qualifier);
+        let res = match unary.op {
             UnaryOp::Plus | UnaryOp::Minus => {
                 if !exp_type.is_num() {
                     Err(PineInputError::new(
@@ -1042,12 +1646,25 @@ impl<'a> SyntaxParser<'a> {
                     ))
                 }
             }
-        }
+        };
+        res.map(|v| v.with_qualifier(qualifier))
     }
 
     pub fn parse_binary(&mut self, binary: &mut BinaryExp<'a>) -> ParseResult<'a> {
-        let exp1_type = self.parse_exp(&mut binary.exp1)?.syntax_type;
-        let exp2_type = self.parse_exp(&mut binary.exp2)?.syntax_type;
+        let exp1_res = self.parse_exp(&mut binary.exp1)?;
+        let exp2_res = self.parse_exp(&mut binary.exp2)?;
+        // The result is as strong as the weaker operand.
+        let qualifier = exp1_res.qualifier.max(exp2_res.qualifier);
+        let res = self.parse_binary_types(binary, exp1_res.syntax_type, exp2_res.syntax_type);
+        res.map(|v| v.with_qualifier(qualifier))
+    }
+
+    fn parse_binary_types(
+        &mut self,
+        binary: &mut BinaryExp<'a>,
+        exp1_type: SyntaxType<'a>,
+        exp2_type: SyntaxType<'a>,
+    ) -> ParseResult<'a> {
         let gen_bool =
             |binary: &mut BinaryExp<'a>, exp1_type: SyntaxType<'a>, exp2_type: SyntaxType<'a>| {
                 let result = match (exp1_type.into_v_for_vf(), exp2_type.into_v_for_vf()) {
@@ -1075,48 +1692,33 @@ impl<'a> SyntaxParser<'a> {
                 }
                 // The operator must be num type int, float, series(int), series(float)
                 if !(exp1_type.is_num() && exp2_type.is_num()) {
-                    return Err(PineInputError::new(
-                        PineErrorKind::BinaryTypeNotNum,
-                        binary.range,
-                    ));
+                    return Err(binary_type_err(binary, &exp1_type, &exp2_type));
                 }
                 if let Some(result_type) = similar_type(&exp1_type, &exp2_type) {
                     binary.result_type = result_type.clone();
                     binary.ref_type = result_type.clone();
                     Ok(ParseValue::new_with_type(result_type))
                 } else {
-                    Err(PineInputError::new(
-                        PineErrorKind::TypeMismatch,
-                        binary.range,
-                    ))
+                    Err(binary_type_err(binary, &exp1_type, &exp2_type))
                 }
             }
             BinaryOp::Gt | BinaryOp::Geq | BinaryOp::Lt | BinaryOp::Leq => {
-                if !(exp1_type.is_num() && exp2_type.is_num()) {
-                    self.catch(PineInputError::new(
-                        PineErrorKind::BinaryTypeNotNum,
-                        binary.range,
-                    ));
+                let result_type = similar_type(&exp1_type, &exp2_type);
+                if result_type.is_none() {
+                    return Err(binary_type_err(binary, &exp1_type, &exp2_type));
                 }
-                if let Some(result_type) = similar_type(&exp1_type, &exp2_type) {
-                    binary.ref_type = result_type.clone();
-                    gen_bool(binary, exp1_type, exp2_type)
-                } else {
-                    Err(PineInputError::new(
-                        PineErrorKind::TypeMismatch,
-                        binary.range,
-                    ))
+                if !(exp1_type.is_num() && exp2_type.is_num()) {
+                    self.catch(binary_type_err(binary, &exp1_type, &exp2_type));
                 }
+                binary.ref_type = result_type.unwrap();
+                gen_bool(binary, exp1_type, exp2_type)
             }
             BinaryOp::Eq | BinaryOp::Neq => {
                 if let Some(_type) = similar_type(&exp2_type, &exp1_type) {
                     binary.ref_type = _type;
                     gen_bool(binary, exp1_type, exp2_type)
                 } else {
-                    self.catch(PineInputError::new(
-                        PineErrorKind::TypeMismatch,
-                        binary.range,
-                    ));
+                    self.catch(binary_type_err(binary, &exp1_type, &exp2_type));
                     gen_bool(binary, exp1_type, exp2_type)
                 }
             }
@@ -1127,10 +1729,7 @@ impl<'a> SyntaxParser<'a> {
                 {
                     gen_bool(binary, exp1_type, exp2_type)
                 } else {
-                    self.catch(PineInputError::new(
-                        PineErrorKind::BoolExpTypeNotBool,
-                        binary.range,
-                    ));
+                    self.catch(binary_type_err(binary, &exp1_type, &exp2_type));
                     gen_bool(binary, exp1_type, exp2_type)
                 }
             }
@@ -1142,8 +1741,12 @@ impl<'a> SyntaxParser<'a> {
         assign: &mut Assignment<'a>,
         name: &VarName<'a>,
         val: SyntaxType<'a>,
+        qualifier: Option<TypeQualifier>,
     ) -> Result<SyntaxType<'a>, PineInputError> {
+        self.check_shadow(name);
         let context = downcast_ctx(self.context);
+        // The variable declared by `var` can be updated on every bar.
+        let qualifier = qualifier.filter(|q| *q < TypeQualifier::Simple && !assign.var);
         // if context.get_var_scope(name.value).is_some() {
         //     self.catch(PineInputError::new(
         //         PineErrorKind::VarHasDeclare,
@@ -1154,15 +1757,13 @@ impl<'a> SyntaxParser<'a> {
             let (is_cast_err, result) = implicity_type_cast(&val, &data_type);
             if is_cast_err {
                 self.catch(PineInputError::new(
-                    PineErrorKind::InvalidTypeCast {
-                        origin: SimpleSyntaxType::from(val),
-                        cast: SimpleSyntaxType::from(data_type.clone()),
-                    },
+                    type_cast_err(&val, Some(SimpleSyntaxType::from(data_type.clone()))),
                     assign.range,
                 ));
             }
             if name.value != "_" {
                 context.declare_var(name.value, result.clone());
+                context.declare_qualifier(name.value, qualifier);
             }
 
             // If the assign need type cast, then we need record the cast index
@@ -1177,6 +1778,7 @@ impl<'a> SyntaxParser<'a> {
         } else {
             if name.value != "_" {
                 context.declare_var(name.value, val.clone());
+                context.declare_qualifier(name.value, qualifier);
             }
             Ok(val)
         }
@@ -1195,7 +1797,12 @@ impl<'a> SyntaxParser<'a> {
                     let mut ret_tuple = vec![];
                     let mut names = mem::replace(&mut assign.names, vec![]);
                     for (name, val_type) in names.iter().zip(tuple.iter()) {
-                        ret_tuple.push(self.parse_one_assign(assign, name, val_type.clone())?);
+                        ret_tuple.push(self.parse_one_assign(
+                            assign,
+                            name,
+                            val_type.clone(),
+                            None,
+                        )?);
                     }
                     mem::replace(&mut assign.names, names);
                     let context = downcast_ctx(self.context);
@@ -1229,7 +1836,8 @@ impl<'a> SyntaxParser<'a> {
             }
         } else {
             let names = mem::replace(&mut assign.names, vec![]);
-            let rtype = self.parse_one_assign(assign, &names[0], val_res.syntax_type)?;
+            let rtype =
+                self.parse_one_assign(assign, &names[0], val_res.syntax_type, val_res.qualifier)?;
             mem::replace(&mut assign.names, names);
             let context = downcast_ctx(self.context);
 
@@ -1264,11 +1872,12 @@ impl<'a> SyntaxParser<'a> {
                 if implicity_convert(&val_res.syntax_type, &last_type) {
                     Ok(ParseValue::new_with_type(last_type))
                 } else {
+                    let cast = match &last_type {
+                        SyntaxType::Simple(t) | SyntaxType::Series(t) => Some(t.clone()),
+                        _ => None,
+                    };
                     self.catch(PineInputError::new(
-                        PineErrorKind::InvalidTypeCast {
-                            origin: SimpleSyntaxType::from(val_res.syntax_type),
-                            cast: SimpleSyntaxType::from(last_type.clone()),
-                        },
+                        type_cast_err(&val_res.syntax_type, cast),
                         assign.range,
                     ));
                     Ok(ParseValue::new_with_type(last_type))
@@ -1278,6 +1887,7 @@ impl<'a> SyntaxParser<'a> {
     }
 
     fn parse_func_def(&mut self, func_def: &mut FunctionDef<'a>) -> ParseResult<'a> {
+        self.check_shadow(&func_def.name);
         let context = downcast_ctx(self.context);
         let name = func_def.name.value;
         self.user_funcs.insert(name, func_def);
@@ -1298,33 +1908,25 @@ impl<'a> SyntaxParser<'a> {
 
     fn parse_exp(&mut self, exp: &mut Exp<'a>) -> ParseResult<'a> {
         match exp {
-            Exp::Na(_) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Na,
-            ))),
-            Exp::Bool(_) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool,
-            ))),
-            Exp::Num(Numeral::Int(_)) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Int,
-            ))),
-            Exp::Num(Numeral::Float(_)) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Float,
-            ))),
-            Exp::Str(_) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::String,
-            ))),
-            Exp::Color(_) => Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Color,
-            ))),
+            Exp::Na(_) => Ok(const_value(SimpleSyntaxType::Na)),
+            Exp::Bool(_) => Ok(const_value(SimpleSyntaxType::Bool)),
+            Exp::Num(Numeral::Int(_)) => Ok(const_value(SimpleSyntaxType::Int)),
+            Exp::Num(Numeral::Float(_)) => Ok(const_value(SimpleSyntaxType::Float)),
+            Exp::Str(_) => Ok(const_value(SimpleSyntaxType::String)),
+            Exp::Color(_) => Ok(const_value(SimpleSyntaxType::Color)),
             Exp::VarName(name) => self.parse_varname(name),
             Exp::Tuple(tuple) => self.parse_tuple(tuple),
             Exp::TypeCast(type_cast) => self.parse_type_cast(type_cast),
-            Exp::FuncCall(func_call) => self.parse_func_call(func_call),
+            Exp::FuncCall(func_call) => {
+                self.parse_script_decl(func_call, false);
+                self.parse_func_call(func_call)
+            }
             Exp::RefCall(ref_call) => self.parse_ref_call(ref_call),
             Exp::PrefixExp(prefix) => self.parse_prefix(prefix),
             Exp::Condition(condition) => self.parse_condition(condition),
             Exp::Ite(ite) => self.parse_ifthenelse_exp(ite),
             Exp::ForRange(fr) => self.parse_forrange_exp(fr),
+            Exp::ForIn(fi) => self.parse_forin_exp(fi),
             Exp::Assignment(assign) => self.parse_assign(assign),
             Exp::VarAssignment(var_assign) => self.parse_var_assign(var_assign),
             Exp::UnaryExp(node) => self.parse_unary(node),
@@ -1333,7 +1935,14 @@ impl<'a> SyntaxParser<'a> {
     }
 
     fn parse_interrupt(&mut self, range: &StrRange, code: PineErrorKind) -> ParseResult<'a> {
-        let context = downcast_ctx(self.context);
+        // The interrupt statement can be nested in the if blocks of the loop.
+        let mut context = downcast_ctx(self.context);
+        while context.get_type() == ContextType::IfElseBlock {
+            match context.get_parent() {
+                Some(parent) => context = downcast_ctx(parent),
+                None => break,
+            }
+        }
         if context.get_type() != ContextType::ForRangeBlock {
             self.catch(PineInputError::new(code, *range));
         }
@@ -1352,17 +1961,25 @@ impl<'a> SyntaxParser<'a> {
         match stmt {
             Statement::Break(node) => self.parse_break(node),
             Statement::Continue(node) => self.parse_continue(node),
-            Statement::FuncCall(func_call) => self.parse_func_call(func_call),
+            Statement::FuncCall(func_call) => self.parse_stmt_func_call(func_call),
             Statement::Ite(ite) => self.parse_ifthenelse_stmt(ite),
             Statement::ForRange(fr) => self.parse_forrange_stmt(fr),
+            Statement::ForIn(fi) => self.parse_forin_stmt(fi),
             Statement::Assignment(assign) => self.parse_assign(assign),
             Statement::VarAssignment(assign) => self.parse_var_assign(assign),
             Statement::FuncDef(func_def) => self.parse_func_def(func_def),
             Statement::None(_) => Ok(ParseValue::new_with_type(SyntaxType::Void)),
+            Statement::Exp(Exp::FuncCall(func_call)) => self.parse_stmt_func_call(func_call),
             Statement::Exp(exp) => self.parse_exp(exp),
         }
     }
 
+    fn parse_stmt_func_call(&mut self, func_call: &mut FunctionCall<'a>) -> ParseResult<'a> {
+        let is_top_stmt = downcast_ctx(self.context).get_type() == ContextType::Main;
+        self.parse_script_decl(func_call, is_top_stmt);
+        self.parse_func_call(func_call)
+    }
+
     pub fn parse_blk_only(&mut self, blk: &mut Block<'a>) -> ParseResult<'a> {
         for stmt in blk.stmts.iter_mut() {
             self.parse_stmt(stmt)?;
@@ -1544,6 +2161,20 @@ mod tests {
             parser.parse_func_call(&mut func_call),
             Ok(ParseValue::new_with_type(INT_TYPE))
         );
+        assert_eq!(func_call.dict_arg_indexs, Some(vec![1]));
+
+        let mut func_call = FunctionCall::new(
+            fun_nm(),
+            vec![],
+            vec![(varname("arg2"), int_exp(1)), (varname("arg1"), int_exp(2))],
+            1,
+            StrRange::new_empty(),
+        );
+        assert_eq!(
+            parser.parse_func_call(&mut func_call),
+            Ok(ParseValue::new_with_type(INT_TYPE))
+        );
+        assert_eq!(func_call.dict_arg_indexs, Some(vec![1, 0]));
 
         let mut func_call = FunctionCall::new(
             fun_nm(),
@@ -1583,6 +2214,80 @@ mod tests {
                 StrRange::new_empty()
             ))
         );
+
+        // The series argument can't be passed to the simple parameter.
+        downcast_ctx(parser.context)
+            .declare_var_with_index("series_int", SyntaxType::Series(SimpleSyntaxType::Int));
+        let mut func_call = FunctionCall::new(
+            fun_nm(),
+            vec![int_exp(1)],
+            vec![(varname("arg2"), Exp::VarName(rvarname("series_int")))],
+            1,
+            StrRange::new_empty(),
+        );
+        assert_eq!(
+            parser.parse_func_call(&mut func_call),
+            Err(PineInputError::new(
+                PineErrorKind::ArgQualifierNotMatch {
+                    param: String::from("arg2"),
+                    qualifier: TypeQualifier::Simple,
+                    found: TypeQualifier::Series
+                },
+                StrRange::new_empty()
+            ))
+        );
+    }
+
+    #[test]
+    fn template_func_call_test() {
+        let mut parser = SyntaxParser::new();
+        downcast_ctx(parser.context).declare_var_with_index(
+            "func",
+            SyntaxType::Function(Rc::new(FunctionTypes(vec![
+                FunctionType::new_with_templates(
+                    vec![SimpleSyntaxType::Int, SimpleSyntaxType::Float],
+                    (
+                        vec![
+                            ("arg1", SyntaxType::Simple(SimpleSyntaxType::Template)),
+                            ("arg2", SyntaxType::Simple(SimpleSyntaxType::Template)),
+                        ],
+                        SyntaxType::Simple(SimpleSyntaxType::Template),
+                    ),
+                ),
+            ]))),
+        );
+
+        let mut func_call = FunctionCall::new(
+            fun_nm(),
+            vec![int_exp(1), int_exp(2)],
+            vec![],
+            1,
+            StrRange::new_empty(),
+        );
+        assert_eq!(
+            parser.parse_func_call(&mut func_call),
+            Ok(ParseValue::new_with_type(INT_TYPE))
+        );
+
+        // The runtime receives the instantiated signature.
+        let mut func_call = FunctionCall::new(
+            fun_nm(),
+            vec![int_exp(1)],
+            vec![(varname("arg2"), float_exp(1f64))],
+            1,
+            StrRange::new_empty(),
+        );
+        assert_eq!(
+            parser.parse_func_call(&mut func_call),
+            Ok(ParseValue::new_with_type(FLOAT_TYPE))
+        );
+        assert_eq!(
+            func_call.func_type,
+            Some(FunctionType::new((
+                vec![("arg1", FLOAT_TYPE), ("arg2", FLOAT_TYPE)],
+                FLOAT_TYPE
+            )))
+        );
     }
 
     #[test]
@@ -1768,37 +2473,27 @@ mod tests {
         let mut parser = SyntaxParser::new();
         assert_eq!(
             parser.parse_exp(&mut Exp::Na(NaNode::new(StrRange::new_empty()))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Na
-            )))
+            Ok(const_value(SimpleSyntaxType::Na))
         );
 
         assert_eq!(
             parser.parse_exp(&mut Exp::Bool(BoolNode::new_no_range(true))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
 
         assert_eq!(
             parser.parse_exp(&mut Exp::Num(Numeral::from_f64(1f64))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Float
-            )))
+            Ok(const_value(SimpleSyntaxType::Float))
         );
 
         assert_eq!(
             parser.parse_exp(&mut Exp::Num(Numeral::from_i64(1))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Int
-            )))
+            Ok(const_value(SimpleSyntaxType::Int))
         );
 
         assert_eq!(
             parser.parse_exp(&mut Exp::Color(ColorNode::from_str("#AAAAAA"))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Color
-            )))
+            Ok(const_value(SimpleSyntaxType::Color))
         );
 
         assert_eq!(
@@ -1806,9 +2501,7 @@ mod tests {
                 String::from("hello"),
                 StrRange::new_empty()
             ))),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::String
-            )))
+            Ok(const_value(SimpleSyntaxType::String))
         );
     }
 
@@ -1920,6 +2613,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn na_type_cast() {
+        let mut parser = SyntaxParser::new();
+        for (data_type, simple_type) in [
+            (DataType::Int, SimpleSyntaxType::Int),
+            (DataType::Float, SimpleSyntaxType::Float),
+            (DataType::Bool, SimpleSyntaxType::Bool),
+            (DataType::Color, SimpleSyntaxType::Color),
+            (DataType::String, SimpleSyntaxType::String),
+        ] {
+            assert_eq!(
+                parser.parse_exp(&mut Exp::TypeCast(Box::new(TypeCast::new_no_input(
+                    data_type,
+                    Exp::Na(NaNode::new(StrRange::new_empty()))
+                )))),
+                Ok(const_value(simple_type))
+            );
+        }
+        assert_eq!(parser.errors, vec![]);
+    }
+
     #[test]
     fn ref_call_cast() {
         let mut parser = SyntaxParser::new();
@@ -2096,9 +2810,7 @@ mod tests {
         let mut plus_exp = UnaryExp::new(UnaryOp::Plus, int_exp(1), StrRange::new_empty());
         assert_eq!(
             parser.parse_unary(&mut plus_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Int
-            )))
+            Ok(const_value(SimpleSyntaxType::Int))
         );
 
         context.declare_var_with_index("var", SyntaxType::Series(SimpleSyntaxType::Int));
@@ -2130,9 +2842,7 @@ mod tests {
         let mut bool_exp = UnaryExp::new(UnaryOp::BoolNot, int_exp(1), StrRange::new_empty());
         assert_eq!(
             parser.parse_unary(&mut bool_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
 
         // context.declare_var_with_index("var", SyntaxType::Series(SimpleSyntaxType::Int));
@@ -2164,9 +2874,7 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut str_add_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::String
-            )))
+            Ok(const_value(SimpleSyntaxType::String))
         );
         assert_eq!(
             str_add_exp.result_type,
@@ -2200,9 +2908,7 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut int_add_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Int
-            )))
+            Ok(const_value(SimpleSyntaxType::Int))
         );
         assert_eq!(
             int_add_exp.result_type,
@@ -2253,9 +2959,14 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut na_add_exp),
-            Err(PineInputError::new(
-                PineErrorKind::BinaryTypeNotNum,
-                StrRange::new_empty()
+            Err(PineInputError::new_with_related(
+                PineErrorKind::BinaryOpTypeNotMatch {
+                    op: String::from("+"),
+                    left: String::from("int"),
+                    right: String::from("na")
+                },
+                StrRange::new_empty(),
+                vec![StrRange::new_empty(), StrRange::new_empty()]
             ))
         );
         assert_eq!(na_add_exp.result_type, SyntaxType::Any);
@@ -2303,9 +3014,7 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut eq_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
         // assert!(!parser.errors.is_empty());
         assert_eq!(
@@ -2321,13 +3030,15 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut eq_dif_type_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
         assert_eq!(
-            *parser.errors.last().unwrap(),
-            PineInputError::new(PineErrorKind::TypeMismatch, StrRange::new_empty())
+            parser.errors.last().unwrap().code,
+            PineErrorKind::BinaryOpTypeNotMatch {
+                op: String::from("=="),
+                left: String::from("int"),
+                right: String::from("string")
+            }
         );
     }
 
@@ -2342,9 +3053,7 @@ mod tests {
             BinaryExp::new(BinaryOp::Geq, int_exp(1), int_exp(2), StrRange::new_empty());
         assert_eq!(
             parser.parse_binary(&mut geq_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
         assert_eq!(
             geq_exp.result_type,
@@ -2376,13 +3085,15 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut na_geq_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
         assert_eq!(
-            *parser.errors.last().unwrap(),
-            PineInputError::new(PineErrorKind::BinaryTypeNotNum, StrRange::new_empty())
+            parser.errors.last().unwrap().code,
+            PineErrorKind::BinaryOpTypeNotMatch {
+                op: String::from(">="),
+                left: String::from("int"),
+                right: String::from("na")
+            }
         );
 
         // BoolAnd
@@ -2407,13 +3118,15 @@ mod tests {
         );
         assert_eq!(
             parser.parse_binary(&mut bool_and_exp),
-            Ok(ParseValue::new_with_type(SyntaxType::Simple(
-                SimpleSyntaxType::Bool
-            )))
+            Ok(const_value(SimpleSyntaxType::Bool))
         );
         assert_eq!(
-            *parser.errors.last().unwrap(),
-            PineInputError::new(PineErrorKind::BoolExpTypeNotBool, StrRange::new_empty())
+            parser.errors.last().unwrap().code,
+            PineErrorKind::BinaryOpTypeNotMatch {
+                op: String::from("and"),
+                left: String::from("int"),
+                right: String::from("string")
+            }
         );
     }
 
@@ -2548,6 +3261,7 @@ mod tests {
             &PineInputError {
                 code: PineErrorKind::InvalidTypeCast { origin: _, cast: _ },
                 range: _,
+                ..
             }
         );
     }
@@ -2601,6 +3315,45 @@ mod tests {
         assert_eq!(blk.libfun_count, 0);
     }
 
+    #[test]
+    fn shadow_test() {
+        use crate::ast::stat_expr::block;
+        use crate::ast::state::AstState;
+
+        let vars = vec![("close", SyntaxType::float_series())];
+        let src = "close = 1\nf(x) =>\n    close = x\n    close\nf(1)\nf(1.0)";
+        let shadow_errs = vec![
+            PineInputError::new(
+                PineErrorKind::ShadowBuiltinVar,
+                StrRange::from_start("close", Position::new(0, 0)),
+            ),
+            PineInputError::new(
+                PineErrorKind::ShadowBuiltinVar,
+                StrRange::from_start("close", Position::new(2, 4)),
+            ),
+        ];
+
+        let mut parser = SyntaxParser::new_with_libvars(&vars);
+        let mut blk = block(Input::new_with_str(src), &AstState::new()).unwrap().1;
+        assert!(parser.parse_blk(&mut blk).is_ok());
+        assert!(parser.is_ok());
+        assert_eq!(parser.move_warnings(), shadow_errs);
+
+        let mut parser = SyntaxParser::new_with_libvars(&vars);
+        parser.set_shadow_severity(ShadowSeverity::Error);
+        let mut blk = block(Input::new_with_str(src), &AstState::new()).unwrap().1;
+        assert!(parser.parse_blk(&mut blk).is_ok());
+        assert_eq!(parser.move_errors(), shadow_errs);
+        assert_eq!(parser.move_warnings(), vec![]);
+
+        let mut parser = SyntaxParser::new_with_libvars(&vars);
+        parser.set_shadow_severity(ShadowSeverity::Allow);
+        let mut blk = block(Input::new_with_str(src), &AstState::new()).unwrap().1;
+        assert!(parser.parse_blk(&mut blk).is_ok());
+        assert!(parser.is_ok());
+        assert_eq!(parser.move_warnings(), vec![]);
+    }
+
     #[test]
     fn dynamic_expr_test() {
         use crate::ast::stat_expr::block;
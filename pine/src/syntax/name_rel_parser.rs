@@ -51,6 +51,12 @@ impl<'a> DepNameFinder<'a> for ForRange<'a> {
     }
 }
 
+impl<'a> DepNameFinder<'a> for ForIn<'a> {
+    fn find_names(&self) -> Vec<&'a str> {
+        self.iter.find_names()
+    }
+}
+
 impl<'a> DepNameFinder<'a> for FunctionCall<'a> {
     fn find_names(&self) -> Vec<&'a str> {
         [
@@ -90,6 +96,8 @@ impl<'a> DepNameFinder<'a> for Exp<'a> {
 
             Exp::ForRange(fr) => fr.find_names(),
 
+            Exp::ForIn(fi) => fi.find_names(),
+
             Exp::Assignment(assign) => assign.find_names(),
 
             Exp::VarAssignment(assign) => assign.find_names(),
@@ -117,6 +125,7 @@ impl<'a> DepNameFinder<'a> for Statement<'a> {
             Statement::VarAssignment(assign) => assign.find_names(),
             Statement::Ite(ite) => ite.cond.find_names(),
             Statement::ForRange(fr) => fr.find_names(),
+            Statement::ForIn(fi) => fi.find_names(),
             Statement::FuncCall(f) => f.find_names(),
             Statement::FuncDef(_) => vec![],
             Statement::Exp(e) => e.find_names(),
@@ -132,6 +141,7 @@ impl<'a> GenNameFinder<'a> for Statement<'a> {
             Statement::VarAssignment(assign) => assign.find_gen_names(),
             Statement::Ite(_) => vec![],
             Statement::ForRange(_) => vec![],
+            Statement::ForIn(_) => vec![],
             Statement::FuncCall(_) => vec![],
             Statement::FuncDef(d) => vec![d.name.value],
             Statement::Exp(e) => e.find_gen_names(),
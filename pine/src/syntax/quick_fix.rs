@@ -0,0 +1,289 @@
+use super::lint::{LintCode, LintWarning};
+use crate::ast::error::PineErrorKind;
+use crate::ast::input::{Position, StrRange};
+use crate::ast::op::BinaryOp;
+use crate::ast::stat_expr_types::*;
+use crate::ast::state::PineInputError;
+use crate::ast::visit::*;
+
+// Replace the text in the range with the new text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextEdit {
+    pub range: StrRange,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: StrRange, new_text: String) -> TextEdit {
+        TextEdit { range, new_text }
+    }
+}
+
+// The fix for the diagnostic in the range, the editor applies all the edits together.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuickFix {
+    pub title: String,
+    pub range: StrRange,
+    pub edits: Vec<TextEdit>,
+}
+
+// Get the text of the source in the range, the characters of the positions are counted in chars.
+pub fn range_text(src: &str, range: StrRange) -> Option<String> {
+    let lines: Vec<&str> = src.split('\n').collect();
+    let char_slice = |line: &str, start: usize, end: Option<usize>| -> String {
+        let chars = line.chars().skip(start);
+        match end {
+            Some(end) => chars.take(end.saturating_sub(start)).collect(),
+            None => chars.collect(),
+        }
+    };
+    let (start, end) = (range.start, range.end);
+    let (start_line, end_line) = (start.get_line() as usize, end.get_line() as usize);
+    if start_line > end_line || end_line >= lines.len() {
+        return None;
+    }
+    if start_line == end_line {
+        return Some(char_slice(
+            lines[start_line],
+            start.get_character() as usize,
+            Some(end.get_character() as usize),
+        ));
+    }
+    let mut text = char_slice(lines[start_line], start.get_character() as usize, None);
+    for line in &lines[start_line + 1..end_line] {
+        text.push('\n');
+        text.push_str(line);
+    }
+    text.push('\n');
+    text.push_str(&char_slice(
+        lines[end_line],
+        0,
+        Some(end.get_character() as usize),
+    ));
+    Some(text)
+}
+
+// Apply the edits to the source, the edit that overlaps the former edit is skipped.
+pub fn apply_edits(src: &str, edits: &[TextEdit]) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let offset = |pos: Position| -> usize {
+        match line_starts.get(pos.get_line() as usize) {
+            Some(start) => src[*start..]
+                .char_indices()
+                .map(|(i, _)| start + i)
+                .chain(std::iter::once(src.len()))
+                .nth(pos.get_character() as usize)
+                .unwrap_or(src.len()),
+            None => src.len(),
+        }
+    };
+    let mut edits: Vec<_> = edits
+        .iter()
+        .map(|e| (offset(e.range.start), offset(e.range.end), e))
+        .collect();
+    edits.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut res = String::with_capacity(src.len());
+    let mut last = 0;
+    for (start, end, edit) in edits {
+        if start < last {
+            continue;
+        }
+        res.push_str(&src[last..start]);
+        res.push_str(&edit.new_text);
+        last = end;
+    }
+    res.push_str(&src[last..]);
+    res
+}
+
+// The AST nodes that the fixes are generated from.
+#[derive(Default)]
+struct FixCollector<'a, 'b> {
+    // The `x == na` and `x != na` expressions.
+    na_compares: Vec<&'b BinaryExp<'a>>,
+    var_assigns: Vec<&'b VarAssignment<'a>>,
+    // The assignments with the declared type like `int m = 1.5`.
+    typed_assigns: Vec<&'b Assignment<'a>>,
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for FixCollector<'a, 'b> {
+    fn visit_assign(&mut self, assign: &'b Assignment<'a>) {
+        if assign.var_type.is_some() {
+            self.typed_assigns.push(assign);
+        }
+        walk_assign(self, assign);
+    }
+
+    fn visit_var_assign(&mut self, assign: &'b VarAssignment<'a>) {
+        self.var_assigns.push(assign);
+        walk_var_assign(self, assign);
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        if let Exp::BinaryExp(node) = exp {
+            let is_na = |e: &Exp<'a>| matches!(e, Exp::Na(_));
+            if (node.op == BinaryOp::Eq || node.op == BinaryOp::Neq)
+                && (is_na(&node.exp1) || is_na(&node.exp2))
+            {
+                self.na_compares.push(node);
+            }
+        }
+        walk_exp(self, exp);
+    }
+}
+
+// Replace `x == na` with `na(x)` and `x != na` with `not na(x)`.
+fn fix_na_compare<'a>(src: &str, node: &BinaryExp<'a>) -> Option<QuickFix> {
+    let operand = match (&node.exp1, &node.exp2) {
+        (Exp::Na(_), exp) | (exp, Exp::Na(_)) => exp,
+        _ => return None,
+    };
+    let operand_text = range_text(src, operand.range())?;
+    let new_text = match node.op {
+        BinaryOp::Eq => format!("na({})", operand_text),
+        _ => format!("not na({})", operand_text),
+    };
+    Some(QuickFix {
+        title: format!("Replace with {}", new_text),
+        range: node.range,
+        edits: vec![TextEdit::new(node.range, new_text)],
+    })
+}
+
+// Generate the quick fixes of the script for the lint warnings and the syntax errors:
+// the v4 function names are renamed to the v5 names, `== na` is replaced by `na()`,
+// the undeclared variable of `:=` is declared by `var` and the value assigned to the typed
+// variable is casted to the type. The fixes are ordered by the position.
+pub fn quick_fixes<'a>(
+    src: &str,
+    blk: &Block<'a>,
+    warnings: &[LintWarning],
+    errors: &[PineInputError],
+) -> Vec<QuickFix> {
+    let mut collector = FixCollector::default();
+    collector.visit_blk(blk);
+
+    let mut fixes: Vec<QuickFix> = vec![];
+    for warning in warnings {
+        if let LintCode::V4FunctionName(_, v5) = &warning.code {
+            fixes.push(QuickFix {
+                title: format!("Rename to {}", v5),
+                range: warning.range,
+                edits: vec![TextEdit::new(warning.range, v5.clone())],
+            });
+        }
+    }
+    fixes.extend(
+        collector
+            .na_compares
+            .iter()
+            .filter_map(|node| fix_na_compare(src, node)),
+    );
+
+    for err in errors {
+        match &err.code {
+            PineErrorKind::VarNotDeclare => {
+                let assign = collector.var_assigns.iter().find(|a| a.range == err.range);
+                if let Some(assign) = assign {
+                    // Replace `x :=` with `var x =`.
+                    let name = assign.name.value;
+                    fixes.push(QuickFix {
+                        title: format!("Declare {} with var", name),
+                        range: err.range,
+                        edits: vec![TextEdit::new(
+                            StrRange::new(assign.range.start, assign.val.range().start),
+                            format!("var {} = ", name),
+                        )],
+                    });
+                }
+            }
+            PineErrorKind::InvalidTypeCast { cast, .. } => {
+                let val = collector
+                    .typed_assigns
+                    .iter()
+                    .find(|a| a.range == err.range)
+                    .map(|a| &a.val)
+                    .or_else(|| {
+                        collector
+                            .var_assigns
+                            .iter()
+                            .find(|a| a.range == err.range)
+                            .map(|a| &a.val)
+                    });
+                if let Some(text) = val.and_then(|v| range_text(src, v.range())) {
+                    let cast = cast.to_string();
+                    let new_text = format!("{}({})", cast, text);
+                    fixes.push(QuickFix {
+                        title: format!("Cast to {}", cast),
+                        range: err.range,
+                        edits: vec![TextEdit::new(val.unwrap().range(), new_text)],
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    fixes.sort_by(|a, b| {
+        a.range
+            .start
+            .partial_cmp(&b.range.start)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    fixes
+}
+
+// Check if the ranges overlap, the editor requests the fixes for the selected range.
+pub fn range_overlap(r1: StrRange, r2: StrRange) -> bool {
+    let before = |p1: Position, p2: Position| p1 < p2;
+    !(before(r1.end, r2.start) || before(r2.end, r1.start))
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod tests {
+    use super::*;
+    use crate::PineScript;
+
+    #[test]
+    fn quick_fixes_test() {
+        let src = "study(\"x\")\nm = close == na\nn = na != close\nint j = 1.5\nk := 1";
+        let mut script = PineScript::new(None);
+        assert!(script.parse_src(String::from(src)).is_err());
+        // The title, the replaced text and the new text of the fixes.
+        let fixes: Vec<_> = script
+            .quick_fixes()
+            .into_iter()
+            .map(|f| {
+                let edit = &f.edits[0];
+                (
+                    f.title,
+                    range_text(src, edit.range).unwrap(),
+                    edit.new_text.clone(),
+                )
+            })
+            .collect();
+        let fix = |title: &str, old: &str, new: &str| {
+            (String::from(title), String::from(old), String::from(new))
+        };
+        assert_eq!(
+            fixes,
+            vec![
+                fix("Rename to indicator", "study", "indicator"),
+                fix("Replace with na(close)", "close == na", "na(close)"),
+                fix("Replace with not na(close)", "na != close", "not na(close)"),
+                fix("Cast to int", "1.5", "int(1.5)"),
+                fix("Declare k with var", "k := ", "var k = "),
+            ]
+        );
+    }
+
+    #[test]
+    fn range_overlap_test() {
+        let range = |s1, s2| StrRange::new(Position::new(0, s1), Position::new(0, s2));
+        assert!(range_overlap(range(0, 4), range(2, 6)));
+        assert!(range_overlap(range(2, 3), range(0, 6)));
+        assert!(!range_overlap(range(0, 2), range(3, 6)));
+    }
+}
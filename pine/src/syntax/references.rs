@@ -0,0 +1,396 @@
+use super::quick_fix::TextEdit;
+use crate::ast::error::PineErrorKind;
+use crate::ast::input::{Input, Position, StrRange};
+use crate::ast::name::{varname_only, VarName};
+use crate::ast::stat_expr_types::*;
+use crate::ast::state::PineInputError;
+use crate::ast::visit::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Parameter,
+}
+
+// The variable or function declared by the script and the ranges of the names that reference it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub decl: StrRange,
+    // The reads and the reassignments of the symbol after the declaration.
+    pub refs: Vec<StrRange>,
+}
+
+impl Symbol {
+    // The declaration and all the references ordered by the position.
+    pub fn ranges(&self) -> Vec<StrRange> {
+        let mut ranges = vec![self.decl];
+        ranges.extend(self.refs.iter().cloned());
+        ranges.sort_by(|a, b| {
+            a.start
+                .partial_cmp(&b.start)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ranges
+    }
+}
+
+// The block of the script, the function body and the if or for block have their own scopes.
+struct Scope<'a> {
+    parent: Option<usize>,
+    // The last declared symbol of the name, the later declaration shadows the former one.
+    names: HashMap<&'a str, usize>,
+}
+
+// The name occurrence in the source and the symbol and the scope it belongs to.
+struct Occurrence {
+    range: StrRange,
+    symbol: usize,
+    scope: usize,
+}
+
+// The index of the symbols declared by the script, each name in the source is resolved to
+// the declaration visible in its scope. The names of the builtin variables and functions
+// are not declared by the script, so they are not tracked.
+pub struct ReferenceIndex<'a> {
+    symbols: Vec<Symbol>,
+    // The scope that declares the symbol.
+    symbol_scopes: Vec<usize>,
+    scopes: Vec<Scope<'a>>,
+    occurrences: Vec<Occurrence>,
+    cur_scope: usize,
+}
+
+impl<'a> ReferenceIndex<'a> {
+    pub fn new(blk: &Block<'a>) -> ReferenceIndex<'a> {
+        let mut index = ReferenceIndex {
+            symbols: vec![],
+            symbol_scopes: vec![],
+            scopes: vec![Scope {
+                parent: None,
+                names: HashMap::new(),
+            }],
+            occurrences: vec![],
+            cur_scope: 0,
+        };
+        index.visit_blk(blk);
+        index
+    }
+
+    pub fn get_symbols(&self) -> &Vec<Symbol> {
+        &self.symbols
+    }
+
+    // Get the symbol whose declaration or reference contains the position.
+    pub fn symbol_at(&self, pos: Position) -> Option<&Symbol> {
+        self.find_occurrence(pos).map(|o| &self.symbols[o.symbol])
+    }
+
+    fn find_occurrence(&self, pos: Position) -> Option<&Occurrence> {
+        self.occurrences
+            .iter()
+            .find(|o| o.range.start <= pos && pos <= o.range.end)
+    }
+
+    // Check if the scope or its ancestors declare the name.
+    fn is_visible(&self, scope: usize, name: &str) -> Option<usize> {
+        let mut cur = Some(scope);
+        while let Some(id) = cur {
+            let found = self
+                .symbols
+                .iter()
+                .enumerate()
+                .find(|(i, s)| self.symbol_scopes[*i] == id && s.name == name);
+            if let Some((i, _)) = found {
+                return Some(i);
+            }
+            cur = self.scopes[id].parent;
+        }
+        None
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(Scope {
+            parent: Some(self.cur_scope),
+            names: HashMap::new(),
+        });
+        self.cur_scope = self.scopes.len() - 1;
+    }
+
+    fn exit_scope(&mut self) {
+        self.cur_scope = self.scopes[self.cur_scope].parent.unwrap();
+    }
+
+    fn declare(&mut self, name: &VarName<'a>, kind: SymbolKind) {
+        if name.value == "_" {
+            return;
+        }
+        let id = self.symbols.len();
+        self.symbols.push(Symbol {
+            name: String::from(name.value),
+            kind,
+            decl: name.range,
+            refs: vec![],
+        });
+        self.symbol_scopes.push(self.cur_scope);
+        self.scopes[self.cur_scope].names.insert(name.value, id);
+        self.occurrences.push(Occurrence {
+            range: name.range,
+            symbol: id,
+            scope: self.cur_scope,
+        });
+    }
+
+    fn reference(&mut self, name: &VarName<'a>) {
+        let mut cur = Some(self.cur_scope);
+        while let Some(scope) = cur {
+            if let Some(id) = self.scopes[scope].names.get(name.value) {
+                self.symbols[*id].refs.push(name.range);
+                self.occurrences.push(Occurrence {
+                    range: name.range,
+                    symbol: *id,
+                    scope: self.cur_scope,
+                });
+                return;
+            }
+            cur = self.scopes[scope].parent;
+        }
+    }
+
+    fn visit_scope_blk(&mut self, blk: &Block<'a>) {
+        self.enter_scope();
+        self.visit_blk(blk);
+        self.exit_scope();
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for ReferenceIndex<'a> {
+    fn visit_ite(&mut self, ite: &'b IfThenElse<'a>) {
+        self.visit_exp(&ite.cond);
+        self.visit_scope_blk(&ite.then_blk);
+        if let Some(blk) = &ite.else_blk {
+            self.visit_scope_blk(blk);
+        }
+    }
+
+    fn visit_for_range(&mut self, fr: &'b ForRange<'a>) {
+        self.visit_exp(&fr.start);
+        self.visit_exp(&fr.end);
+        if let Some(step) = &fr.step {
+            self.visit_exp(step);
+        }
+        self.enter_scope();
+        self.declare(&fr.var, SymbolKind::Variable);
+        self.visit_blk(&fr.do_blk);
+        self.exit_scope();
+    }
+
+    fn visit_for_in(&mut self, fi: &'b ForIn<'a>) {
+        self.visit_exp(&fi.iter);
+        self.enter_scope();
+        if let Some(index) = &fi.index_var {
+            self.declare(index, SymbolKind::Variable);
+        }
+        self.declare(&fi.var, SymbolKind::Variable);
+        self.visit_blk(&fi.do_blk);
+        self.exit_scope();
+    }
+
+    // The value is visited before the names are declared, so `a = a + 1` in the block
+    // reads the outer variable.
+    fn visit_assign(&mut self, assign: &'b Assignment<'a>) {
+        self.visit_exp(&assign.val);
+        assign
+            .names
+            .iter()
+            .for_each(|n| self.declare(n, SymbolKind::Variable));
+    }
+
+    fn visit_var_assign(&mut self, assign: &'b VarAssignment<'a>) {
+        self.visit_exp(&assign.val);
+        self.reference(&assign.name);
+    }
+
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        self.declare(&def.name, SymbolKind::Function);
+        // The parameters and the local variables share the scope of the function body.
+        self.enter_scope();
+        def.params
+            .iter()
+            .for_each(|n| self.declare(n, SymbolKind::Parameter));
+        self.visit_blk(&def.body);
+        self.exit_scope();
+    }
+
+    // The field name after the dot is not a variable, so only the variable names are resolved.
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        if let Exp::VarName(name) = exp {
+            self.reference(&name.name);
+        }
+        walk_exp(self, exp);
+    }
+}
+
+// Rename the variable or function at the position, the declaration and all the references are
+// replaced by the new name. The new name must be a valid identifier that is neither reserved nor
+// the name of the builtin variables, and it can't be resolved to another symbol at any of the
+// renamed places.
+pub fn rename(
+    index: &ReferenceIndex,
+    pos: Position,
+    new_name: &str,
+    builtin_names: &[&str],
+) -> Result<Vec<TextEdit>, PineInputError> {
+    let occurrence = match index.find_occurrence(pos) {
+        Some(o) => o,
+        None => {
+            return Err(PineInputError::new(
+                PineErrorKind::VarNotDeclare,
+                StrRange::new(pos, pos),
+            ))
+        }
+    };
+    let symbol = &index.symbols[occurrence.symbol];
+    let range = occurrence.range;
+
+    match varname_only(Input::new_with_str(new_name)) {
+        Ok((rest, _)) if rest.len() == 0 => {}
+        _ => return Err(PineInputError::new(PineErrorKind::InvalidIdentifier, range)),
+    }
+    // The `na` literal is parsed before the variable names, so it can't be a variable.
+    if new_name == "na" || VarName::new_no_input(new_name).is_reserved() {
+        return Err(PineInputError::new(PineErrorKind::ReservedVarName, range));
+    }
+    if builtin_names.contains(&new_name) {
+        return Err(PineInputError::new(PineErrorKind::ShadowBuiltinVar, range));
+    }
+    if new_name == symbol.name {
+        return Ok(vec![]);
+    }
+    let scopes = index
+        .occurrences
+        .iter()
+        .filter(|o| o.symbol == occurrence.symbol)
+        .map(|o| o.scope);
+    for scope in scopes {
+        if let Some(other) = index.is_visible(scope, new_name) {
+            return Err(PineInputError::new_with_related(
+                PineErrorKind::VarHasDeclare,
+                range,
+                vec![index.symbols[other].decl],
+            ));
+        }
+    }
+
+    Ok(symbol
+        .ranges()
+        .into_iter()
+        .map(|r| TextEdit::new(r, String::from(new_name)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    fn name_range(name: &str, line: u32, character: u32) -> StrRange {
+        StrRange::from_start(name, Position::new(line, character))
+    }
+
+    #[test]
+    fn reference_index_test() {
+        let src = "a = close\nf(x) =>\n    a = x * 2\n    a\nif a > 1\n    a := f(a)\nplot(a)";
+        let blk = parse_ast(src).unwrap();
+        let index = ReferenceIndex::new(&blk);
+
+        // The global variable is shadowed by the local variable of the function.
+        let global = index.symbol_at(Position::new(0, 0)).unwrap();
+        assert_eq!(global.kind, SymbolKind::Variable);
+        assert_eq!(
+            global.ranges(),
+            vec![
+                name_range("a", 0, 0),
+                name_range("a", 4, 3),
+                name_range("a", 5, 4),
+                name_range("a", 5, 11),
+                name_range("a", 6, 5),
+            ]
+        );
+        let local = index.symbol_at(Position::new(3, 5)).unwrap();
+        assert_eq!(
+            local.ranges(),
+            vec![name_range("a", 2, 4), name_range("a", 3, 4)]
+        );
+        let func = index.symbol_at(Position::new(5, 9)).unwrap();
+        assert_eq!(func.kind, SymbolKind::Function);
+        assert_eq!(
+            func.ranges(),
+            vec![name_range("f", 1, 0), name_range("f", 5, 9)]
+        );
+        let param = index.symbol_at(Position::new(2, 8)).unwrap();
+        assert_eq!(param.kind, SymbolKind::Parameter);
+
+        // The builtin names are not declared by the script.
+        assert_eq!(index.symbol_at(Position::new(0, 6)), None);
+    }
+
+    #[test]
+    fn rename_test() {
+        let src = "m = close\nf(x) =>\n    n = x + m\n    n\nplot(f(m))";
+        let blk = parse_ast(src).unwrap();
+        let index = ReferenceIndex::new(&blk);
+        let builtins = ["close", "plot"];
+
+        assert_eq!(
+            rename(&index, Position::new(4, 8), "val", &builtins),
+            Ok(vec![
+                TextEdit::new(name_range("m", 0, 0), String::from("val")),
+                TextEdit::new(name_range("m", 2, 12), String::from("val")),
+                TextEdit::new(name_range("m", 4, 7), String::from("val")),
+            ])
+        );
+        assert_eq!(
+            rename(&index, Position::new(1, 0), "g", &builtins),
+            Ok(vec![
+                TextEdit::new(name_range("f", 1, 0), String::from("g")),
+                TextEdit::new(name_range("f", 4, 5), String::from("g")),
+            ])
+        );
+
+        let err = |code, range| Err(PineInputError::new(code, range));
+        assert_eq!(
+            rename(&index, Position::new(0, 0), "1m", &builtins),
+            err(PineErrorKind::InvalidIdentifier, name_range("m", 0, 0))
+        );
+        assert_eq!(
+            rename(&index, Position::new(0, 0), "for", &builtins),
+            err(PineErrorKind::ReservedVarName, name_range("m", 0, 0))
+        );
+        assert_eq!(
+            rename(&index, Position::new(0, 0), "close", &builtins),
+            err(PineErrorKind::ShadowBuiltinVar, name_range("m", 0, 0))
+        );
+        // The global variable is read in the function that declares n.
+        assert_eq!(
+            rename(&index, Position::new(0, 0), "n", &builtins),
+            Err(PineInputError::new_with_related(
+                PineErrorKind::VarHasDeclare,
+                name_range("m", 0, 0),
+                vec![name_range("n", 2, 4)]
+            ))
+        );
+        // The local variable can be renamed to the name that is only declared in other scopes.
+        assert!(rename(&index, Position::new(2, 4), "k", &builtins).is_ok());
+        assert_eq!(
+            rename(&index, Position::new(0, 6), "k", &builtins),
+            err(
+                PineErrorKind::VarNotDeclare,
+                StrRange::new(Position::new(0, 6), Position::new(0, 6))
+            )
+        );
+    }
+}
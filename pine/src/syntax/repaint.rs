@@ -0,0 +1,217 @@
+use super::lint::{LintCode, LintWarning};
+use crate::ast::num::Numeral;
+use crate::ast::op::UnaryOp;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+
+// The variables whose values are different on the historical and realtime bars.
+const REALTIME_VARS: [&str; 4] = [
+    "barstate.isrealtime",
+    "barstate.isconfirmed",
+    "barstate.isnew",
+    "timenow",
+];
+
+// Get the full name of the variable or the called method like `barstate.isrealtime`.
+fn full_name<'a>(exp: &Exp<'a>) -> Option<String> {
+    match exp {
+        Exp::VarName(name) => Some(String::from(name.name.value)),
+        Exp::PrefixExp(prefix) => {
+            full_name(&prefix.left_exp).map(|s| format!("{}.{}", s, prefix.right_name.value))
+        }
+        _ => None,
+    }
+}
+
+fn get_arg<'a, 'b>(call: &'b FunctionCall<'a>, pos: usize, name: &str) -> Option<&'b Exp<'a>> {
+    match call.dict_args.iter().find(|(n, _)| n.value == name) {
+        Some((_, exp)) => Some(exp),
+        None => call.pos_args.get(pos),
+    }
+}
+
+fn is_true<'a>(exp: &Exp<'a>) -> bool {
+    match exp {
+        Exp::Bool(b) => b.value,
+        exp => full_name(exp).as_deref() == Some("barmerge.lookahead_on"),
+    }
+}
+
+fn is_negative<'a>(exp: &Exp<'a>) -> bool {
+    match exp {
+        Exp::Num(Numeral::Int(n)) => n.value < 0,
+        Exp::Num(Numeral::Float(n)) => n.value < 0f64,
+        Exp::UnaryExp(node) => match (&node.op, &node.exp) {
+            (UnaryOp::Minus, Exp::Num(Numeral::Int(n))) => n.value > 0,
+            (UnaryOp::Minus, Exp::Num(Numeral::Float(n))) => n.value > 0f64,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// The expression only references the previous bars like `close[1]`, so the lookahead doesn't
+// get the future data.
+fn is_history_exp<'a>(exp: &Exp<'a>) -> bool {
+    match exp {
+        Exp::RefCall(r) => match &r.arg {
+            Exp::Num(Numeral::Int(n)) => n.value >= 1,
+            _ => false,
+        },
+        Exp::Na(_) | Exp::Bool(_) | Exp::Num(_) | Exp::Str(_) | Exp::Color(_) => true,
+        Exp::UnaryExp(node) => is_history_exp(&node.exp),
+        Exp::BinaryExp(node) => is_history_exp(&node.exp1) && is_history_exp(&node.exp2),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct RepaintAuditor {
+    warnings: Vec<LintWarning>,
+}
+
+impl RepaintAuditor {
+    fn check_var<'a>(&mut self, exp: &Exp<'a>) {
+        if let Some(name) = full_name(exp) {
+            if REALTIME_VARS.contains(&name.as_str()) {
+                self.warnings.push(LintWarning::new(
+                    LintCode::RealtimeVariable(name),
+                    exp.range(),
+                ));
+            }
+        }
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for RepaintAuditor {
+    fn visit_func_call(&mut self, call: &'b FunctionCall<'a>) {
+        match full_name(&call.method).as_deref() {
+            Some("security") | Some("request.security") => {
+                if let Some(lookahead) = get_arg(call, 4, "lookahead") {
+                    let history = get_arg(call, 2, "expression").is_some_and(is_history_exp);
+                    if is_true(lookahead) && !history {
+                        self.warnings.push(LintWarning::new(
+                            LintCode::SecurityLookahead,
+                            lookahead.range(),
+                        ));
+                    }
+                }
+            }
+            Some("strategy") => {
+                if let Some((_, exp)) = call
+                    .dict_args
+                    .iter()
+                    .find(|(n, _)| n.value == "calc_on_every_tick")
+                {
+                    if is_true(exp) {
+                        self.warnings
+                            .push(LintWarning::new(LintCode::CalcOnEveryTick, exp.range()));
+                    }
+                }
+            }
+            _ => {}
+        }
+        // The negative offset of the plot functions shifts the values to the past bars.
+        if let Some((_, exp)) = call.dict_args.iter().find(|(n, _)| n.value == "offset") {
+            if is_negative(exp) {
+                self.warnings
+                    .push(LintWarning::new(LintCode::FutureOffset, exp.range()));
+            }
+        }
+
+        walk_func_call(self, call);
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        match exp {
+            // The fields of the namespaces like `barstate.isrealtime` are checked as a whole.
+            Exp::VarName(_) | Exp::PrefixExp(_) => self.check_var(exp),
+            Exp::RefCall(r) => {
+                if is_negative(&r.arg) {
+                    self.warnings
+                        .push(LintWarning::new(LintCode::FutureOffset, r.arg.range()));
+                }
+                walk_exp(self, exp);
+            }
+            _ => walk_exp(self, exp),
+        }
+    }
+}
+
+// Find the constructs that may calculate differently on the historical and realtime bars:
+// `security` with lookahead, the negative offsets, `calc_on_every_tick` and the realtime variables.
+// The warnings are ordered by the position.
+pub fn audit<'a>(blk: &Block<'a>) -> Vec<LintWarning> {
+    let mut auditor = RepaintAuditor::default();
+    auditor.visit_blk(blk);
+
+    let mut warnings = auditor.warnings;
+    warnings.sort_by(|a, b| {
+        a.range
+            .start
+            .partial_cmp(&b.range.start)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::input::{Position, StrRange};
+    use crate::parse_ast;
+
+    #[test]
+    fn security_lookahead_test() {
+        let blk = parse_ast(
+            "m1 = security('MSFT', 'D', close, lookahead=barmerge.lookahead_on)
+m2 = security('MSFT', 'D', close[1], lookahead=true)
+m3 = security('MSFT', 'D', close, lookahead=barmerge.lookahead_off)",
+        )
+        .unwrap();
+        assert_eq!(
+            audit(&blk),
+            vec![LintWarning::new(
+                LintCode::SecurityLookahead,
+                StrRange::from_start("barmerge.lookahead_on", Position::new(0, 44))
+            )]
+        );
+    }
+
+    #[test]
+    fn repaint_constructs_test() {
+        let blk = parse_ast(
+            "strategy('s', calc_on_every_tick=true)
+m = close[-1]
+plot(close, offset=-2)
+if barstate.isrealtime
+    plot(timenow)",
+        )
+        .unwrap();
+        assert_eq!(
+            audit(&blk),
+            vec![
+                LintWarning::new(
+                    LintCode::CalcOnEveryTick,
+                    StrRange::from_start("true", Position::new(0, 33))
+                ),
+                LintWarning::new(
+                    LintCode::FutureOffset,
+                    StrRange::from_start("-1", Position::new(1, 10))
+                ),
+                LintWarning::new(
+                    LintCode::FutureOffset,
+                    StrRange::from_start("-2", Position::new(2, 19))
+                ),
+                LintWarning::new(
+                    LintCode::RealtimeVariable(String::from("barstate.isrealtime")),
+                    StrRange::from_start("barstate.isrealtime", Position::new(3, 3))
+                ),
+                LintWarning::new(
+                    LintCode::RealtimeVariable(String::from("timenow")),
+                    StrRange::from_start("timenow", Position::new(4, 9))
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use crate::ast::input::StrRange;
+use crate::ast::num::{FloatNode, IntNode, Numeral};
+use crate::ast::op::{BinaryOp, UnaryOp};
+use crate::ast::stat_expr_types::*;
+use crate::ast::string::StringNode;
+use crate::ast::visit::*;
+use crate::runtime::output::InputVal;
+use std::collections::{HashMap, HashSet};
+
+fn is_input_call<'a>(call: &FunctionCall<'a>) -> bool {
+    match &call.method {
+        Exp::VarName(name) => name.name.value == "input",
+        Exp::PrefixExp(prefix) => match &prefix.left_exp {
+            Exp::VarName(name) => name.name.value == "input",
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_literal<'a>(exp: &Exp<'a>) -> bool {
+    matches!(exp, Exp::Bool(_) | Exp::Num(_) | Exp::Str(_))
+}
+
+fn called_name<'a>(call: &FunctionCall<'a>) -> Option<&'a str> {
+    match &call.method {
+        Exp::VarName(name) => Some(name.name.value),
+        _ => None,
+    }
+}
+
+// Find the input calls and the calls of the functions that call the inputs. The input calls in
+// the branches and loops are recorded separately since they may run in any order or not at all.
+struct InputScanner<'b, 'a> {
+    input_funcs: &'b HashSet<&'a str>,
+    found: bool,
+    calls_func: bool,
+    branched: bool,
+}
+
+impl<'b, 'a> InputScanner<'b, 'a> {
+    fn new(input_funcs: &'b HashSet<&'a str>) -> InputScanner<'b, 'a> {
+        InputScanner {
+            input_funcs,
+            found: false,
+            calls_func: false,
+            branched: false,
+        }
+    }
+
+    fn scan_call(&mut self, call: &FunctionCall<'a>) {
+        if called_name(call).is_some_and(|n| self.input_funcs.contains(n)) {
+            self.calls_func = true;
+        }
+        self.found |= self.calls_func || is_input_call(call);
+    }
+
+    fn scan_branch(&mut self, walk: impl FnOnce(&mut InputScanner<'b, 'a>)) {
+        let mut scanner = InputScanner::new(self.input_funcs);
+        walk(&mut scanner);
+        self.branched |= scanner.found;
+    }
+}
+
+impl<'b, 'a> AstMapper<'a> for InputScanner<'b, 'a> {
+    fn map_exp(&mut self, exp: &mut Exp<'a>) {
+        match exp {
+            Exp::FuncCall(call) => self.scan_call(call),
+            Exp::Ite(ite) => self.scan_branch(|s| walk_ite_mut(s, ite)),
+            Exp::ForRange(fr) => self.scan_branch(|s| walk_for_range_mut(s, fr)),
+            Exp::ForIn(fi) => self.scan_branch(|s| walk_for_in_mut(s, fi)),
+            _ => {}
+        }
+    }
+
+    fn map_stmt(&mut self, stmt: &mut Statement<'a>) {
+        match stmt {
+            Statement::FuncCall(call) => self.scan_call(call),
+            Statement::Ite(ite) => self.scan_branch(|s| walk_ite_mut(s, ite)),
+            Statement::ForRange(fr) => self.scan_branch(|s| walk_for_range_mut(s, fr)),
+            Statement::ForIn(fi) => self.scan_branch(|s| walk_for_in_mut(s, fi)),
+            _ => {}
+        }
+    }
+}
+
+// The names of the functions that call the inputs directly or by the other functions.
+fn gen_input_funcs<'a>(blk: &mut Block<'a>) -> HashSet<&'a str> {
+    let mut input_funcs = HashSet::new();
+    loop {
+        let mut found = vec![];
+        for stmt in blk.stmts.iter_mut() {
+            if let Statement::FuncDef(def) = stmt {
+                if input_funcs.contains(def.name.value) {
+                    continue;
+                }
+                let mut scanner = InputScanner::new(&input_funcs);
+                walk_blk_mut(&mut scanner, &mut def.body);
+                if scanner.found || scanner.branched {
+                    found.push(def.name.value);
+                }
+            }
+        }
+        if found.is_empty() {
+            return input_funcs;
+        }
+        input_funcs.extend(found);
+    }
+}
+
+// Replace the input calls by the literals of the fixed values. The values are matched to
+// the input calls in the evaluation order. The value is only used when its type is the same
+// as the literal default value, and the source inputs are never replaced.
+struct InputFolder<'b> {
+    inputs: &'b [Option<InputVal>],
+    index: usize,
+}
+
+impl<'a, 'b> AstMapper<'a> for InputFolder<'b> {
+    fn map_exp(&mut self, exp: &mut Exp<'a>) {
+        let lit = match exp {
+            Exp::FuncCall(call) if is_input_call(call) => {
+                let index = self.index;
+                self.index += 1;
+                let defval = match call.dict_args.iter().find(|(n, _)| n.value == "defval") {
+                    Some((_, e)) => Some(e),
+                    None => call.pos_args.first(),
+                };
+                let range = call.range;
+                match (self.inputs.get(index), defval) {
+                    (Some(Some(InputVal::Bool(v))), Some(Exp::Bool(_))) => {
+                        Exp::Bool(BoolNode::new(*v, range))
+                    }
+                    (Some(Some(InputVal::Int(v))), Some(Exp::Num(Numeral::Int(_)))) => {
+                        Exp::Num(Numeral::Int(IntNode::new(*v, range)))
+                    }
+                    (Some(Some(InputVal::Float(v))), Some(Exp::Num(Numeral::Float(_)))) => {
+                        Exp::Num(Numeral::Float(FloatNode::new(*v, range)))
+                    }
+                    (Some(Some(InputVal::String(v))), Some(Exp::Str(_))) => {
+                        Exp::Str(StringNode::new(v.clone(), range))
+                    }
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        *exp = lit;
+    }
+}
+
+// Count the declarations of all the variable names and record the reassigned names.
+#[derive(Default)]
+struct DeclCounter<'a> {
+    declares: HashMap<&'a str, usize>,
+    reassigns: HashSet<&'a str>,
+}
+
+impl<'a> DeclCounter<'a> {
+    fn declare(&mut self, name: &'a str) {
+        *self.declares.entry(name).or_insert(0) += 1;
+    }
+
+    fn declare_for_in(&mut self, fi: &ForIn<'a>) {
+        self.declare(fi.var.value);
+        if let Some(index) = &fi.index_var {
+            self.declare(index.value);
+        }
+    }
+}
+
+impl<'a> AstMapper<'a> for DeclCounter<'a> {
+    fn map_exp(&mut self, exp: &mut Exp<'a>) {
+        match exp {
+            Exp::Assignment(assign) => assign.names.iter().for_each(|n| self.declare(n.value)),
+            Exp::VarAssignment(assign) => {
+                self.reassigns.insert(assign.name.value);
+            }
+            Exp::ForRange(fr) => self.declare(fr.var.value),
+            Exp::ForIn(fi) => self.declare_for_in(fi),
+            _ => {}
+        }
+    }
+
+    fn map_stmt(&mut self, stmt: &mut Statement<'a>) {
+        match stmt {
+            Statement::Assignment(assign) => {
+                assign.names.iter().for_each(|n| self.declare(n.value))
+            }
+            Statement::VarAssignment(assign) => {
+                self.reassigns.insert(assign.name.value);
+            }
+            Statement::ForRange(fr) => self.declare(fr.var.value),
+            Statement::ForIn(fi) => self.declare_for_in(fi),
+            Statement::FuncDef(def) => {
+                self.declare(def.name.value);
+                def.params.iter().for_each(|n| self.declare(n.value));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Copy the literal to the given range, so the folded node maps back to the source of the node
+// it replaces instead of the source of the literal.
+fn literal_at<'a>(lit: &Exp<'a>, range: StrRange) -> Option<Exp<'a>> {
+    match lit {
+        Exp::Bool(b) => Some(Exp::Bool(BoolNode::new(b.value, range))),
+        Exp::Num(Numeral::Int(n)) => Some(Exp::Num(Numeral::Int(IntNode::new(n.value, range)))),
+        Exp::Num(Numeral::Float(n)) => Some(Exp::Num(Numeral::Float(FloatNode { range, ..*n }))),
+        Exp::Str(s) => Some(Exp::Str(StringNode::new(s.value.clone(), range))),
+        _ => None,
+    }
+}
+
+fn num_value(num: &Numeral) -> f64 {
+    match num {
+        Numeral::Int(n) => n.value as f64,
+        Numeral::Float(n) => n.value,
+    }
+}
+
+fn fold_unary<'a>(node: &UnaryExp<'a>) -> Option<Exp<'a>> {
+    let range = node.range;
+    match (&node.op, &node.exp) {
+        (UnaryOp::BoolNot, Exp::Bool(b)) => Some(Exp::Bool(BoolNode::new(!b.value, range))),
+        (UnaryOp::Minus, Exp::Num(Numeral::Int(n))) => {
+            Some(Exp::Num(Numeral::Int(IntNode::new(-n.value, range))))
+        }
+        (UnaryOp::Minus, Exp::Num(Numeral::Float(n))) => {
+            Some(Exp::Num(Numeral::Float(FloatNode::new(-n.value, range))))
+        }
+        (UnaryOp::Plus, Exp::Num(_)) => literal_at(&node.exp, range),
+        _ => None,
+    }
+}
+
+fn fold_binary<'a>(node: &BinaryExp<'a>) -> Option<Exp<'a>> {
+    let range = node.range;
+    let bool_exp = |v: bool| Some(Exp::Bool(BoolNode::new(v, range)));
+    match (&node.exp1, &node.exp2) {
+        (Exp::Bool(b1), Exp::Bool(b2)) => match node.op {
+            BinaryOp::BoolAnd => bool_exp(b1.value && b2.value),
+            BinaryOp::BoolOr => bool_exp(b1.value || b2.value),
+            BinaryOp::Eq => bool_exp(b1.value == b2.value),
+            BinaryOp::Neq => bool_exp(b1.value != b2.value),
+            _ => None,
+        },
+        (Exp::Str(s1), Exp::Str(s2)) => match node.op {
+            BinaryOp::Eq => bool_exp(s1.value == s2.value),
+            BinaryOp::Neq => bool_exp(s1.value != s2.value),
+            BinaryOp::Plus => Some(Exp::Str(StringNode::new(
+                format!("{}{}", s1.value, s2.value),
+                range,
+            ))),
+            _ => None,
+        },
+        (Exp::Num(Numeral::Int(n1)), Exp::Num(Numeral::Int(n2))) => {
+            let int_exp =
+                |v: Option<i64>| v.map(|v| Exp::Num(Numeral::Int(IntNode::new(v, range))));
+            match node.op {
+                BinaryOp::Plus => int_exp(n1.value.checked_add(n2.value)),
+                BinaryOp::Minus => int_exp(n1.value.checked_sub(n2.value)),
+                BinaryOp::Mul => int_exp(n1.value.checked_mul(n2.value)),
+                BinaryOp::Lt => bool_exp(n1.value < n2.value),
+                BinaryOp::Leq => bool_exp(n1.value <= n2.value),
+                BinaryOp::Gt => bool_exp(n1.value > n2.value),
+                BinaryOp::Geq => bool_exp(n1.value >= n2.value),
+                BinaryOp::Eq => bool_exp(n1.value == n2.value),
+                BinaryOp::Neq => bool_exp(n1.value != n2.value),
+                _ => None,
+            }
+        }
+        (Exp::Num(n1), Exp::Num(n2)) => {
+            let (v1, v2) = (num_value(n1), num_value(n2));
+            let float_exp = |v: f64| Some(Exp::Num(Numeral::Float(FloatNode::new(v, range))));
+            match node.op {
+                BinaryOp::Plus => float_exp(v1 + v2),
+                BinaryOp::Minus => float_exp(v1 - v2),
+                BinaryOp::Mul => float_exp(v1 * v2),
+                BinaryOp::Lt => bool_exp(v1 < v2),
+                BinaryOp::Leq => bool_exp(v1 <= v2),
+                BinaryOp::Gt => bool_exp(v1 > v2),
+                BinaryOp::Geq => bool_exp(v1 >= v2),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Replace the constant variables by their literals, fold the constant expressions and
+// remove the branches of the `if` statements with the constant conditions.
+struct ConstFolder<'a> {
+    consts: HashMap<&'a str, Exp<'a>>,
+}
+
+impl<'a> AstMapper<'a> for ConstFolder<'a> {
+    fn map_exp(&mut self, exp: &mut Exp<'a>) {
+        let folded = match exp {
+            Exp::VarName(name) => match self.consts.get(name.name.value) {
+                Some(lit) => literal_at(lit, name.name.range),
+                None => None,
+            },
+            Exp::UnaryExp(node) => fold_unary(node),
+            Exp::BinaryExp(node) => fold_binary(node),
+            _ => None,
+        };
+        if let Some(folded) = folded {
+            *exp = folded;
+        }
+    }
+
+    fn map_stmt(&mut self, stmt: &mut Statement<'a>) {
+        let pruned = match stmt {
+            Statement::Ite(ite) => match &ite.cond {
+                Exp::Bool(b) if b.value => {
+                    ite.else_blk = None;
+                    None
+                }
+                Exp::Bool(b) => match ite.else_blk.take() {
+                    // Move the else block to the then block, so the condition is always true.
+                    Some(blk) => {
+                        ite.then_blk = blk;
+                        ite.cond = Exp::Bool(BoolNode::new(true, b.range));
+                        None
+                    }
+                    None => Some(Statement::None(ite.range)),
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(pruned) = pruned {
+            *stmt = pruned;
+        }
+    }
+}
+
+// The inputs are numbered in the order that they are called on the first bar. The top-level
+// input calls are folded until the order of the following inputs is unknown, that's when the
+// statement calls the functions that call the inputs, or calls the inputs in the branches or
+// loops. The input calls in the function bodies are never folded.
+fn fold_inputs<'a>(blk: &mut Block<'a>, inputs: &[Option<InputVal>]) {
+    let input_funcs = gen_input_funcs(blk);
+    let mut folder = InputFolder { inputs, index: 0 };
+    for stmt in blk.stmts.iter_mut() {
+        if let Statement::FuncDef(_) = stmt {
+            continue;
+        }
+        let mut scanner = InputScanner::new(&input_funcs);
+        walk_stmt_mut(&mut scanner, stmt);
+        if scanner.branched || scanner.calls_func {
+            return;
+        }
+        walk_stmt_mut(&mut folder, stmt);
+    }
+    if let Some(exp) = &mut blk.ret_stmt {
+        let mut scanner = InputScanner::new(&input_funcs);
+        walk_exp_mut(&mut scanner, exp);
+        if !scanner.branched && !scanner.calls_func {
+            walk_exp_mut(&mut folder, exp);
+        }
+    }
+}
+
+// Specialize the script with the fixed input values. The input calls are replaced by the
+// literals, the top-level variables that are only assigned with literals once are constant
+// folded through the script, and the `if` branches guarded by the constant conditions are removed.
+// The replaced inputs are no longer reported as inputs of the script.
+// The folded nodes take the ranges of the nodes they replace and the moved blocks keep their
+// ranges, so the runtime errors and the statement profile still refer to the original source.
+pub fn specialize<'a>(blk: &mut Block<'a>, inputs: &[Option<InputVal>]) {
+    fold_inputs(blk, inputs);
+
+    let mut counter = DeclCounter::default();
+    walk_blk_mut(&mut counter, blk);
+
+    let mut folder = ConstFolder {
+        consts: HashMap::new(),
+    };
+    // Fold the constants in the statement order, so the variables assigned from the previous
+    // constants are also constant.
+    for stmt in blk.stmts.iter_mut() {
+        walk_stmt_mut(&mut folder, stmt);
+        if let Statement::Assignment(assign) = stmt {
+            if let [name] = assign.names.as_slice() {
+                if is_literal(&assign.val)
+                    && counter.declares.get(name.value) == Some(&1)
+                    && !counter.reassigns.contains(name.value)
+                {
+                    folder.consts.insert(name.value, assign.val.clone());
+                }
+            }
+        }
+    }
+    if let Some(exp) = &mut blk.ret_stmt {
+        walk_exp_mut(&mut folder, exp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    fn stmt_kinds<'a>(blk: &Block<'a>) -> Vec<&'static str> {
+        blk.stmts
+            .iter()
+            .map(|s| match s {
+                Statement::Assignment(_) => "assign",
+                Statement::Ite(_) => "if",
+                Statement::FuncCall(_) => "call",
+                Statement::None(_) => "none",
+                _ => "other",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn specialize_input_test() {
+        let mut blk = parse_ast(
+            "show = input(true, \"Show\")\nlen = input(10)\nn = len * 2\nif not show\n    plot(n)\nelse\n    plot(close)",
+        )
+        .unwrap();
+        specialize(
+            &mut blk,
+            &[Some(InputVal::Bool(true)), Some(InputVal::Int(5))],
+        );
+        assert_eq!(stmt_kinds(&blk), vec!["assign", "assign", "assign", "if"]);
+        match &blk.stmts[2] {
+            Statement::Assignment(assign) => match &assign.val {
+                Exp::Num(Numeral::Int(n)) => assert_eq!(n.value, 10),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+        match &blk.stmts[3] {
+            Statement::Ite(ite) => {
+                assert!(matches!(ite.cond, Exp::Bool(BoolNode { value: true, .. })));
+                assert!(ite.else_blk.is_none());
+                match ite.then_blk.stmts.first() {
+                    Some(Statement::Exp(Exp::FuncCall(call))) => {
+                        assert!(
+                            matches!(&call.pos_args[0], Exp::VarName(n) if n.name.value == "close")
+                        )
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn specialize_range_test() {
+        let src = "len = input(10)\nn = len * 2\nif len > 20\n    plot(n)\nelse\n    plot(close)";
+        let origin = parse_ast(src).unwrap();
+        let mut blk = parse_ast(src).unwrap();
+        specialize(&mut blk, &[Some(InputVal::Int(10))]);
+
+        let ranges = |blk: &Block| blk.stmts.iter().map(|s| s.range()).collect::<Vec<_>>();
+        assert_eq!(ranges(&blk), ranges(&origin));
+        let val_range = |blk: &Block| match &blk.stmts[1] {
+            Statement::Assignment(assign) => assign.val.range(),
+            _ => unreachable!(),
+        };
+        assert_eq!(val_range(&blk), val_range(&origin));
+
+        // The else block moved to the then block keeps the ranges of its statements.
+        match (&blk.stmts[2], &origin.stmts[2]) {
+            (Statement::Ite(ite), Statement::Ite(origin_ite)) => {
+                let else_blk = origin_ite.else_blk.as_ref().unwrap();
+                assert_eq!(ranges(&ite.then_blk), ranges(else_blk));
+                assert_eq!(ite.cond.range(), origin_ite.cond.range());
+            }
+            _ => unreachable!(),
+        }
+
+        // The literal folded from the unary expression maps back to the operator too.
+        let src = "len = input(10)\nm = +len";
+        let origin = parse_ast(src).unwrap();
+        let mut blk = parse_ast(src).unwrap();
+        specialize(&mut blk, &[Some(InputVal::Int(10))]);
+        match (&blk.stmts[1], &origin.stmts[1]) {
+            (Statement::Assignment(assign), Statement::Assignment(origin_assign)) => {
+                assert!(matches!(&assign.val, Exp::Num(Numeral::Int(n)) if n.value == 10));
+                assert_eq!(assign.val.range(), origin_assign.val.range());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn specialize_skip_test() {
+        // The reassigned variable and the mismatched input type are not specialized.
+        let mut blk = parse_ast(
+            "show = input(false)\nshow := close > 1\nlen = input(10)\nif show\n    plot(len)",
+        )
+        .unwrap();
+        specialize(
+            &mut blk,
+            &[Some(InputVal::Bool(false)), Some(InputVal::Float(1.5))],
+        );
+        assert_eq!(stmt_kinds(&blk), vec!["assign", "other", "assign", "if"]);
+        match &blk.stmts[2] {
+            Statement::Assignment(assign) => assert!(matches!(assign.val, Exp::FuncCall(_))),
+            _ => unreachable!(),
+        }
+
+        // The if statement without else block is removed by the false condition.
+        let mut blk = parse_ast("show = input(true)\nif show and 1 > 2\n    plot(close)").unwrap();
+        specialize(&mut blk, &[None]);
+        assert_eq!(stmt_kinds(&blk), vec!["assign", "if"]);
+        specialize(&mut blk, &[Some(InputVal::Bool(true))]);
+        assert_eq!(stmt_kinds(&blk), vec!["assign", "none"]);
+    }
+
+    #[test]
+    fn specialize_input_order_test() {
+        let is_folded = |stmt: &Statement| match stmt {
+            Statement::Assignment(assign) => matches!(assign.val, Exp::Num(_)),
+            _ => unreachable!(),
+        };
+        let inputs = [
+            Some(InputVal::Int(10)),
+            Some(InputVal::Int(20)),
+            Some(InputVal::Int(30)),
+        ];
+
+        // The inputs after the call of the function with the input are not folded.
+        let mut blk =
+            parse_ast("g() => input(1)\nf() => g()\na = input(2)\nb = f()\nc = input(3)").unwrap();
+        specialize(&mut blk, &inputs);
+        assert!(is_folded(&blk.stmts[2]));
+        assert!(!is_folded(&blk.stmts[4]));
+        match &blk.stmts[0] {
+            Statement::FuncDef(def) => {
+                assert!(matches!(def.body.ret_stmt, Some(Exp::FuncCall(_))))
+            }
+            _ => unreachable!(),
+        }
+
+        // The inputs after the input in the branch are not folded.
+        let mut blk =
+            parse_ast("a = input(1)\nif close > 1\n    b = input(2)\nc = input(3)").unwrap();
+        specialize(&mut blk, &inputs);
+        assert!(is_folded(&blk.stmts[0]));
+        assert!(!is_folded(&blk.stmts[2]));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+use std::collections::{HashMap, HashSet};
+
+// The functions that output data or change the state of the runner, like the plots, alerts,
+// inputs and the script declarations. The calls keep their order in the source.
+const EFFECT_FUNCS: [&str; 12] = [
+    "plot",
+    "bgcolor",
+    "barcolor",
+    "fill",
+    "hline",
+    "alert",
+    "alertcondition",
+    "print",
+    "input",
+    "study",
+    "indicator",
+    "strategy",
+];
+
+// The namespaces of the functions without side effects, the other namespaced functions and the
+// methods of the objects like `label.new`, `array.push` and `l.set_x` may change the objects.
+const PURE_NAMESPACES: [&str; 7] = [
+    "math",
+    "str",
+    "color",
+    "ta",
+    "ticker",
+    "timeframe",
+    "request",
+];
+
+fn is_effect_call(method: &Exp) -> bool {
+    match method {
+        Exp::VarName(name) => EFFECT_FUNCS.iter().any(|f| name.name.value.starts_with(f)),
+        Exp::PrefixExp(prefix) => match &prefix.left_exp {
+            Exp::VarName(name) => !PURE_NAMESPACES.contains(&name.name.value),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+// The variable names read and written by one statement, include the names in the nested blocks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StmtDeps<'a> {
+    pub reads: HashSet<&'a str>,
+    pub writes: HashSet<&'a str>,
+    // The statement calls the functions with side effects.
+    pub side_effect: bool,
+    // The statement defines a function. The functions generated by the syntax pass, e.g. for the
+    // security expressions, are not called by their names, so all the statements after it
+    // depend on it.
+    pub func_def: bool,
+}
+
+impl<'a> StmtDeps<'a> {
+    // Check if this statement must be evaluated after the previous statement. The statements
+    // with side effects are evaluated in the source order.
+    pub fn depend_on(&self, prev: &StmtDeps<'a>) -> bool {
+        prev.func_def
+            || (self.side_effect && prev.side_effect)
+            || self.reads.iter().any(|n| prev.writes.contains(n))
+            || self
+                .writes
+                .iter()
+                .any(|n| prev.writes.contains(n) || prev.reads.contains(n))
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for StmtDeps<'a> {
+    fn visit_func_call(&mut self, call: &'b FunctionCall<'a>) {
+        self.side_effect |= is_effect_call(&call.method);
+        walk_func_call(self, call);
+    }
+
+    fn visit_assign(&mut self, assign: &'b Assignment<'a>) {
+        walk_assign(self, assign);
+        assign.names.iter().for_each(|n| {
+            self.writes.insert(n.value);
+        });
+    }
+
+    fn visit_var_assign(&mut self, assign: &'b VarAssignment<'a>) {
+        walk_var_assign(self, assign);
+        self.writes.insert(assign.name.value);
+    }
+
+    // The function body is added to the statements that call the function.
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        self.func_def = true;
+        self.writes.insert(def.name.value);
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        if let Exp::VarName(name) = exp {
+            self.reads.insert(name.name.value);
+        }
+        walk_exp(self, exp);
+    }
+}
+
+// Add the dependencies of the called user functions to the statement.
+fn add_called<'a>(deps: &mut StmtDeps<'a>, func_deps: &HashMap<&'a str, StmtDeps<'a>>) {
+    let called: Vec<_> = deps.reads.iter().filter_map(|n| func_deps.get(n)).collect();
+    let reads: Vec<_> = called
+        .iter()
+        .flat_map(|d| d.reads.iter())
+        .cloned()
+        .collect();
+    let writes: Vec<_> = called
+        .iter()
+        .flat_map(|d| d.writes.iter())
+        .cloned()
+        .collect();
+    deps.side_effect |= called.iter().any(|d| d.side_effect);
+    deps.reads.extend(reads);
+    deps.writes.extend(writes);
+}
+
+pub fn gen_stmt_deps<'a>(blk: &Block<'a>) -> Vec<StmtDeps<'a>> {
+    // The names read and written by the user defined functions and their side effects, the
+    // function call statement also has them. The local names of the functions are kept, they
+    // only add the dependencies that are not necessary.
+    let mut func_deps: HashMap<&'a str, StmtDeps<'a>> = HashMap::new();
+    blk.stmts
+        .iter()
+        .map(|s| {
+            if let Statement::FuncDef(def) = s {
+                let mut body = StmtDeps::default();
+                body.visit_blk(&def.body);
+                add_called(&mut body, &func_deps);
+                func_deps.insert(def.name.value, body);
+            }
+            let mut deps = StmtDeps::default();
+            deps.visit_stmt(s);
+            add_called(&mut deps, &func_deps);
+            deps
+        })
+        .collect()
+}
+
+// Assign each top level statement to an evaluation level. The statements that have the same
+// level have no data dependencies between each other, so they can be evaluated concurrently
+// within a bar, and the levels must be evaluated in the ascending order.
+pub fn gen_stmt_levels<'a>(blk: &Block<'a>) -> Vec<usize> {
+    let deps = gen_stmt_deps(blk);
+    let mut levels: Vec<usize> = Vec::with_capacity(deps.len());
+    for (i, dep) in deps.iter().enumerate() {
+        let level = (0..i)
+            .filter(|&j| dep.depend_on(&deps[j]))
+            .map(|j| levels[j] + 1)
+            .max()
+            .unwrap_or(0);
+        levels.push(level);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    #[test]
+    fn stmt_levels_test() {
+        let blk = parse_ast(
+            "a = close + 1\nb = open * 2\nc = a + b\nd = sma(high, 10)\nplot(c)\nif d > 1\n    b := 2",
+        )
+        .unwrap();
+        assert_eq!(gen_stmt_levels(&blk), vec![0, 0, 1, 0, 2, 2]);
+    }
+
+    #[test]
+    fn stmt_levels_effect_test() {
+        let blk = parse_ast(
+            "a = input(1)
+b = input(2)
+plot(close)
+l = label.new(bar_index, high)
+m = math.abs(a)
+alert('b')",
+        )
+        .unwrap();
+        assert_eq!(gen_stmt_levels(&blk), vec![0, 1, 2, 3, 1, 4]);
+
+        // The calls of the user functions have the side effects and the writes of the functions.
+        let blk = parse_ast(
+            "n = 0
+f() =>
+    n := n + 1
+    plot(n)
+m = close
+f()
+plot(m)",
+        )
+        .unwrap();
+        assert_eq!(gen_stmt_levels(&blk), vec![0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn stmt_levels_func_test() {
+        let blk = parse_ast("m = close\nf(x) => x + m\na = f(1)\nb = f(2)").unwrap();
+        assert_eq!(gen_stmt_levels(&blk), vec![0, 0, 1, 1]);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::ast::input::{Position, StrRange};
+use crate::ast::name::VarName;
+use crate::ast::stat_expr_types::*;
+use crate::ast::visit::*;
+
+// The lines of the block that the editor can fold, the start line is kept visible.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FoldingRange {
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+#[derive(Default)]
+struct FoldingCollector {
+    ranges: Vec<FoldingRange>,
+}
+
+impl FoldingCollector {
+    fn add(&mut self, start_line: u32, blk: &Block) {
+        let end_line = blk.range.end.get_line();
+        if end_line > start_line {
+            self.ranges.push(FoldingRange {
+                start_line,
+                end_line,
+            });
+        }
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for FoldingCollector {
+    fn visit_ite(&mut self, ite: &'b IfThenElse<'a>) {
+        self.add(ite.range.start.get_line(), &ite.then_blk);
+        self.visit_exp(&ite.cond);
+        self.visit_blk(&ite.then_blk);
+        if let Some(blk) = &ite.else_blk {
+            // The `else` keyword is on the line before the block, or on the same line as
+            // the nested if of `else if`.
+            let then_end = ite.then_blk.range.end.get_line();
+            let else_line = (then_end + 1).max(blk.range.start.get_line().saturating_sub(1));
+            self.add(else_line, blk);
+            self.visit_blk(blk);
+        }
+    }
+
+    fn visit_for_range(&mut self, fr: &'b ForRange<'a>) {
+        self.add(fr.range.start.get_line(), &fr.do_blk);
+        walk_for_range(self, fr);
+    }
+
+    fn visit_for_in(&mut self, fi: &'b ForIn<'a>) {
+        self.add(fi.range.start.get_line(), &fi.do_blk);
+        walk_for_in(self, fi);
+    }
+
+    fn visit_func_def(&mut self, def: &'b FunctionDef<'a>) {
+        self.add(def.range.start.get_line(), &def.body);
+        walk_func_def(self, def);
+    }
+}
+
+// Get the folding ranges of the if, else, for and function bodies that span multiple lines.
+// The ranges are ordered by the start line.
+pub fn folding_ranges<'a>(blk: &Block<'a>) -> Vec<FoldingRange> {
+    let mut collector = FoldingCollector::default();
+    collector.visit_blk(blk);
+
+    let mut ranges = collector.ranges;
+    ranges.sort_by_key(|r| r.start_line);
+    ranges
+}
+
+fn contains(range: StrRange, pos: Position) -> bool {
+    range.start <= pos && pos <= range.end
+}
+
+// Collect the ranges of the AST nodes that contain the position from the outermost to the innermost.
+struct SelectionCollector {
+    pos: Position,
+    ranges: Vec<StrRange>,
+}
+
+impl SelectionCollector {
+    fn push(&mut self, range: StrRange) -> bool {
+        if !contains(range, self.pos) {
+            return false;
+        }
+        if self.ranges.last() != Some(&range) {
+            self.ranges.push(range);
+        }
+        true
+    }
+}
+
+impl<'a, 'b> Visitor<'a, 'b> for SelectionCollector {
+    fn visit_blk(&mut self, blk: &'b Block<'a>) {
+        if self.push(blk.range) {
+            walk_blk(self, blk);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &'b Statement<'a>) {
+        if self.push(stmt.range()) {
+            walk_stmt(self, stmt);
+        }
+    }
+
+    fn visit_exp(&mut self, exp: &'b Exp<'a>) {
+        if self.push(exp.range()) {
+            walk_exp(self, exp);
+        }
+    }
+
+    fn visit_var_name(&mut self, name: &'b VarName<'a>) {
+        self.push(name.range);
+    }
+}
+
+// Get the ranges to expand the selection at the position, they follow the ancestors of the
+// innermost AST node that contains the position, from the innermost to the whole script.
+pub fn selection_ranges<'a>(blk: &Block<'a>, pos: Position) -> Vec<StrRange> {
+    let mut collector = SelectionCollector {
+        pos,
+        ranges: vec![],
+    };
+    collector.visit_blk(blk);
+
+    let mut ranges = collector.ranges;
+    ranges.reverse();
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_ast;
+
+    #[test]
+    fn folding_ranges_test() {
+        let blk = parse_ast(
+            "f(x) =>\n    y = x * 2\n    y\nif close > 1\n    a = 1\n    a\nelse\n    b = 2\n    for i = 1 to 2\n        b := b + i\ng(x) => x + 1",
+        )
+        .unwrap();
+        let fold = |start_line, end_line| FoldingRange {
+            start_line,
+            end_line,
+        };
+        assert_eq!(
+            folding_ranges(&blk),
+            vec![fold(0, 2), fold(3, 5), fold(6, 9), fold(8, 9)]
+        );
+    }
+
+    #[test]
+    fn selection_ranges_test() {
+        let blk = parse_ast("m = 1\nif m > 1\n    plot(m + close)").unwrap();
+        let range = |l1, c1, l2, c2| StrRange::new(Position::new(l1, c1), Position::new(l2, c2));
+        assert_eq!(
+            selection_ranges(&blk, Position::new(2, 14)),
+            vec![
+                range(2, 13, 2, 18),
+                range(2, 9, 2, 18),
+                range(2, 4, 2, 19),
+                range(1, 0, 2, 19),
+                range(0, 0, 2, 19),
+            ]
+        );
+    }
+}
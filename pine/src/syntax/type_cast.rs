@@ -90,14 +90,16 @@ pub fn implicity_type_cast<'a>(
             }
         },
         DataType::Color => match cur_type {
-            SyntaxType::Series(SimpleSyntaxType::Color) => {
+            SyntaxType::Series(SimpleSyntaxType::Color)
+            | SyntaxType::Series(SimpleSyntaxType::Na) => {
                 SyntaxType::Series(SimpleSyntaxType::Color)
             }
             SyntaxType::Series(_) => {
                 is_cast_err = true;
                 SyntaxType::Series(SimpleSyntaxType::Color)
             }
-            SyntaxType::Simple(SimpleSyntaxType::Color) => {
+            SyntaxType::Simple(SimpleSyntaxType::Color)
+            | SyntaxType::Simple(SimpleSyntaxType::Na) => {
                 SyntaxType::Simple(SimpleSyntaxType::Color)
             }
             _ => {
@@ -106,14 +108,16 @@ pub fn implicity_type_cast<'a>(
             }
         },
         DataType::String => match cur_type {
-            SyntaxType::Series(SimpleSyntaxType::String) => {
+            SyntaxType::Series(SimpleSyntaxType::String)
+            | SyntaxType::Series(SimpleSyntaxType::Na) => {
                 SyntaxType::Series(SimpleSyntaxType::String)
             }
             SyntaxType::Series(_) => {
                 is_cast_err = true;
                 SyntaxType::Series(SimpleSyntaxType::String)
             }
-            SyntaxType::Simple(SimpleSyntaxType::String) => {
+            SyntaxType::Simple(SimpleSyntaxType::String)
+            | SyntaxType::Simple(SimpleSyntaxType::Na) => {
                 SyntaxType::Simple(SimpleSyntaxType::String)
             }
             _ => {
@@ -0,0 +1,109 @@
+// The float helpers for the indicator tests. The results of the indicators accumulate the
+// rounding errors differently on the platforms and the implementations, so the tests compare
+// the series within a tolerance and print the values in the canonical format.
+use crate::types::Float;
+
+// The default relative tolerance of the comparison.
+pub const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+// The number of the decimal digits kept by `format_float`.
+pub const FORMAT_DECIMALS: usize = 10;
+
+// Format the float value deterministically: na and NaN are `na`, the value is rounded to
+// `FORMAT_DECIMALS` decimal digits without the trailing zeros and the negative zero is `0`.
+pub fn format_float(val: Float) -> String {
+    match val {
+        None => String::from("na"),
+        Some(v) if v.is_nan() => String::from("na"),
+        Some(v) if v.is_infinite() => String::from(if v > 0f64 { "inf" } else { "-inf" }),
+        Some(v) => {
+            let s = format!("{:.*}", FORMAT_DECIMALS, v);
+            let s = s.trim_end_matches('0').trim_end_matches('.');
+            if s == "-0" {
+                String::from("0")
+            } else {
+                String::from(s)
+            }
+        }
+    }
+}
+
+// Check if the values are equal within the relative tolerance. The tolerance is absolute for the
+// values whose magnitude is less than 1. The na value equals NaN like Pine.
+pub fn float_approx_eq(actual: Float, expected: Float, tolerance: f64) -> bool {
+    match (actual, expected) {
+        (None, None) => true,
+        (Some(a), None) | (None, Some(a)) => a.is_nan(),
+        (Some(a), Some(e)) if a.is_nan() || e.is_nan() => a.is_nan() && e.is_nan(),
+        (Some(a), Some(e)) if a.is_infinite() || e.is_infinite() => a == e,
+        (Some(a), Some(e)) => (a - e).abs() <= tolerance * e.abs().max(1f64),
+    }
+}
+
+// Assert the series are equal within the tolerance, the message contains all the mismatched
+// bars in the canonical format.
+#[track_caller]
+pub fn assert_series_approx_eq(actual: &[Float], expected: &[Float], tolerance: f64) {
+    let mismatches: Vec<String> = actual
+        .iter()
+        .zip(expected.iter())
+        .enumerate()
+        .filter(|(_, (a, e))| !float_approx_eq(**a, **e, tolerance))
+        .map(|(i, (a, e))| {
+            format!(
+                "bar {}: actual {}, expected {}",
+                i,
+                format_float(*a),
+                format_float(*e)
+            )
+        })
+        .collect();
+    if actual.len() != expected.len() || !mismatches.is_empty() {
+        panic!(
+            "The series are not equal within the tolerance {}, actual length {}, expected length {}\n{}",
+            tolerance,
+            actual.len(),
+            expected.len(),
+            mismatches.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_float_test() {
+        assert_eq!(format_float(None), "na");
+        assert_eq!(format_float(Some(f64::NAN)), "na");
+        assert_eq!(format_float(Some(f64::NEG_INFINITY)), "-inf");
+        assert_eq!(format_float(Some(1.5f64)), "1.5");
+        assert_eq!(format_float(Some(2f64)), "2");
+        assert_eq!(format_float(Some(0.1f64 + 0.2f64)), "0.3");
+        assert_eq!(format_float(Some(-1e-12)), "0");
+        assert_eq!(format_float(Some(-12.25f64)), "-12.25");
+    }
+
+    #[test]
+    fn series_approx_eq_test() {
+        assert!(float_approx_eq(Some(f64::NAN), None, DEFAULT_TOLERANCE));
+        assert!(float_approx_eq(
+            Some(1e9 + 1f64),
+            Some(1e9),
+            DEFAULT_TOLERANCE
+        ));
+        assert!(!float_approx_eq(Some(1e-3), Some(2e-3), DEFAULT_TOLERANCE));
+        assert!(!float_approx_eq(Some(1f64), None, DEFAULT_TOLERANCE));
+
+        assert_series_approx_eq(
+            &[Some(0.1f64 + 0.2f64), None],
+            &[Some(0.3f64), Some(f64::NAN)],
+            DEFAULT_TOLERANCE,
+        );
+        let res = std::panic::catch_unwind(|| {
+            assert_series_approx_eq(&[Some(1f64)], &[Some(1f64), Some(2f64)], DEFAULT_TOLERANCE)
+        });
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// Grammar-aware random Pine script generator that is used for structured fuzzing.
+// The generator produces valid scripts and near-valid scripts(valid scripts with random
+// mutations) to exercise the error paths of the parser and the runtime.
+pub mod approx;
+
+use crate::runtime::data_src::NoneCallback;
+use crate::runtime::AnySeries;
+use crate::PineScript;
+
+// A small xorshift random generator, so the generated scripts are reproducible by the seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    // Generate a number in [0, n)
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    // Return true with the probability of `percent`%
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenConfig {
+    // The max count of statements in one block
+    pub max_stmts: usize,
+    // The max depth of the nested expressions and blocks
+    pub max_depth: usize,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            max_stmts: 8,
+            max_depth: 3,
+        }
+    }
+}
+
+const SRCS: [&str; 4] = ["close", "open", "high", "low"];
+const BINARY_OPS: [&str; 13] = [
+    "+", "-", "*", "/", "%", ">", "<", ">=", "<=", "==", "!=", "and", "or",
+];
+const UNARY_OPS: [&str; 3] = ["-", "+", "not "];
+const SERIES_FUNCS: [&str; 5] = ["sma", "ema", "rma", "highest", "lowest"];
+const MUTATE_TOKENS: [&str; 14] = [
+    "(", ")", "[", "]", ",", "=", ":=", "?", ":", "=>", "    ", "\n", "if", "na",
+];
+
+pub struct ScriptGen {
+    rng: Rng,
+    config: GenConfig,
+    // The declared variable names in the current scope
+    vars: Vec<Vec<String>>,
+    funcs: Vec<String>,
+    var_count: usize,
+}
+
+impl ScriptGen {
+    pub fn new(seed: u64, config: GenConfig) -> ScriptGen {
+        ScriptGen {
+            rng: Rng::new(seed),
+            config,
+            vars: vec![],
+            funcs: vec![],
+            var_count: 0,
+        }
+    }
+
+    fn gen_name(&mut self) -> String {
+        self.var_count += 1;
+        format!("v{}", self.var_count)
+    }
+
+    fn visible_vars(&self) -> Vec<String> {
+        self.vars.iter().flatten().cloned().collect()
+    }
+
+    fn gen_num(&mut self) -> String {
+        match self.rng.below(3) {
+            0 => format!("{}", self.rng.below(100)),
+            1 => format!("{}.{}", self.rng.below(100), self.rng.below(100)),
+            _ => String::from("na"),
+        }
+    }
+
+    fn gen_atom(&mut self) -> String {
+        let vars = self.visible_vars();
+        match self.rng.below(4) {
+            0 => self.gen_num(),
+            1 if !vars.is_empty() => self.rng.pick(&vars).clone(),
+            2 => format!("{}[{}]", self.rng.pick(&SRCS), self.rng.below(5)),
+            _ => String::from(*self.rng.pick(&SRCS)),
+        }
+    }
+
+    pub fn gen_exp(&mut self, depth: usize) -> String {
+        if depth >= self.config.max_depth {
+            return self.gen_atom();
+        }
+        match self.rng.below(7) {
+            0 => format!(
+                "{} {} {}",
+                self.gen_exp(depth + 1),
+                self.rng.pick(&BINARY_OPS),
+                self.gen_exp(depth + 1)
+            ),
+            1 => format!("{}{}", self.rng.pick(&UNARY_OPS), self.gen_atom()),
+            2 => format!(
+                "{} ? {} : {}",
+                self.gen_exp(depth + 1),
+                self.gen_exp(depth + 1),
+                self.gen_exp(depth + 1)
+            ),
+            3 => format!(
+                "{}({}, {})",
+                self.rng.pick(&SERIES_FUNCS),
+                self.rng.pick(&SRCS),
+                self.rng.below(20) + 1
+            ),
+            4 => format!("nz({})", self.gen_exp(depth + 1)),
+            5 if !self.funcs.is_empty() => {
+                let func = self.rng.pick(&self.funcs).clone();
+                format!("{}({})", func, self.gen_exp(depth + 1))
+            }
+            _ => format!("({})", self.gen_exp(depth + 1)),
+        }
+    }
+
+    fn gen_blk(&mut self, indent: usize, depth: usize) -> String {
+        self.vars.push(vec![]);
+        let count = self.rng.below(self.config.max_stmts) + 1;
+        let stmts: Vec<String> = (0..count)
+            .map(|_| format!("{}{}", "    ".repeat(indent), self.gen_stmt(indent, depth)))
+            .collect();
+        self.vars.pop();
+        stmts.join("\n")
+    }
+
+    pub fn gen_stmt(&mut self, indent: usize, depth: usize) -> String {
+        let nested = depth < self.config.max_depth;
+        match self.rng.below(7) {
+            0 if nested => {
+                let cond = self.gen_exp(depth + 1);
+                let then_blk = self.gen_blk(indent + 1, depth + 1);
+                if self.rng.chance(50) {
+                    let else_blk = self.gen_blk(indent + 1, depth + 1);
+                    format!(
+                        "if {}\n{}\n{}else\n{}",
+                        cond,
+                        then_blk,
+                        "    ".repeat(indent),
+                        else_blk
+                    )
+                } else {
+                    format!("if {}\n{}", cond, then_blk)
+                }
+            }
+            1 if nested => {
+                let name = self.gen_name();
+                let end = self.rng.below(10);
+                self.vars.push(vec![name.clone()]);
+                let blk = self.gen_blk(indent + 1, depth + 1);
+                self.vars.pop();
+                format!("for {} = 0 to {}\n{}", name, end, blk)
+            }
+            2 if indent == 0 => {
+                let name = self.gen_name();
+                self.vars.push(vec![String::from("x")]);
+                let body = self.gen_exp(depth + 1);
+                self.vars.pop();
+                self.funcs.push(name.clone());
+                format!("{}(x) => {}", name, body)
+            }
+            3 if !self.visible_vars().is_empty() => {
+                let vars = self.visible_vars();
+                let name = self.rng.pick(&vars).clone();
+                format!("{} := {}", name, self.gen_exp(depth))
+            }
+            4 if indent == 0 => format!("plot({})", self.gen_exp(depth)),
+            _ => {
+                let name = self.gen_name();
+                let exp = self.gen_exp(depth);
+                let prefix = if self.rng.chance(20) { "var " } else { "" };
+                self.vars.last_mut().unwrap().push(name.clone());
+                format!("{}{} = {}", prefix, name, exp)
+            }
+        }
+    }
+
+    // Generate a syntactically valid script, the script may still contain type errors.
+    pub fn gen_script(&mut self) -> String {
+        self.vars.clear();
+        self.funcs.clear();
+        self.var_count = 0;
+        self.gen_blk(0, 0)
+    }
+
+    // Generate a near-valid script by inserting, removing or replacing some tokens.
+    pub fn gen_mutated_script(&mut self) -> String {
+        let src = self.gen_script();
+        let mut chars: Vec<char> = src.chars().collect();
+        let count = self.rng.below(3) + 1;
+        for _ in 0..count {
+            let pos = self.rng.below(chars.len() + 1);
+            match self.rng.below(3) {
+                0 if pos < chars.len() => {
+                    chars.remove(pos);
+                }
+                1 if pos < chars.len() => {
+                    let token: Vec<char> = self.rng.pick(&MUTATE_TOKENS).chars().collect();
+                    chars.splice(pos..pos + 1, token);
+                }
+                _ => {
+                    let token: Vec<char> = self.rng.pick(&MUTATE_TOKENS).chars().collect();
+                    chars.splice(pos..pos, token);
+                }
+            }
+        }
+        chars.into_iter().collect()
+    }
+}
+
+// Parse the script and run it on a few bars if the script is valid.
+// It returns true if the script is valid and runs successfully, the fuzzing target only cares
+// whether this function panics.
+pub fn check_script(src: &str) -> bool {
+    let callback = NoneCallback();
+    let mut script = PineScript::new(Some(&callback));
+    if script.parse_src(String::from(src)).is_err() {
+        return false;
+    }
+    let data = vec![
+        (
+            "close",
+            AnySeries::from_float_vec(vec![Some(1f64), Some(3f64), None, Some(2f64)]),
+        ),
+        (
+            "open",
+            AnySeries::from_float_vec(vec![Some(2f64), None, Some(1f64), Some(4f64)]),
+        ),
+        (
+            "high",
+            AnySeries::from_float_vec(vec![Some(3f64), Some(4f64), Some(2f64), Some(5f64)]),
+        ),
+        (
+            "low",
+            AnySeries::from_float_vec(vec![Some(0f64), Some(1f64), None, Some(1f64)]),
+        ),
+    ];
+    script.run_with_data(data, None).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_test() {
+        let mut rng1 = Rng::new(10);
+        let mut rng2 = Rng::new(10);
+        let v1: Vec<_> = (0..10).map(|_| rng1.below(100)).collect();
+        let v2: Vec<_> = (0..10).map(|_| rng2.below(100)).collect();
+        assert_eq!(v1, v2);
+        assert!(v1.iter().all(|&v| v < 100));
+    }
+
+    #[test]
+    fn gen_script_test() {
+        let mut gen = ScriptGen::new(1, GenConfig::default());
+        let scripts: Vec<_> = (0..100).map(|_| gen.gen_script()).collect();
+        // Most of the generated scripts should pass the syntax checking.
+        let valid_count = scripts.iter().filter(|s| check_script(s)).count();
+        assert!(valid_count > 0);
+    }
+
+    #[test]
+    fn regression_scripts_test() {
+        // The specific function definition must not be reused across contexts.
+        assert!(check_script(
+            "v1(x) => high\nm = v1(1)\nfor v5 = 0 to 1\n    v6 = v1(v5)\nplot(m)"
+        ));
+        // The parser must restore the context when the nested block contains errors.
+        assert!(!check_script("if close\n    a = b\nc = close\nplot(c)"));
+        assert!(!check_script("f(x) => m + x\nplot(f(1))"));
+    }
+
+    #[test]
+    fn gen_mutated_script_test() {
+        let mut gen = ScriptGen::new(2, GenConfig::default());
+        for _ in 0..100 {
+            check_script(&gen.gen_mutated_script());
+        }
+    }
+}
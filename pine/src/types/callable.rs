@@ -305,6 +305,15 @@ impl<'a> Callable<'a> {
         }
     }
 
+    fn init_param_names(&mut self, func_type: &FunctionType<'a>) {
+        if self.param_names.is_none() {
+            self.param_names = Some(func_type.arg_names());
+            if let Some(ref mut caller) = self.caller {
+                caller.init_param_len(self.param_names.as_ref().unwrap().len());
+            }
+        }
+    }
+
     pub fn call(
         &mut self,
         context: &mut dyn Ctx<'a>,
@@ -312,14 +321,28 @@ impl<'a> Callable<'a> {
         dict_args: Vec<(&'a str, PineRef<'a>)>,
         func_type: FunctionType<'a>,
     ) -> Result<PineRef<'a>, RuntimeErr> {
-        if self.param_names.is_none() {
-            self.param_names = Some(func_type.arg_names());
-            if let Some(ref mut caller) = self.caller {
-                caller.init_param_len(self.param_names.as_ref().unwrap().len());
+        self.init_param_names(&func_type);
+        let param_names = self.param_names.as_ref().unwrap();
+        let mut index_args = Vec::with_capacity(dict_args.len());
+        for (name, val) in dict_args.into_iter() {
+            match param_names.iter().position(|&v| name == v) {
+                None => return Err(RuntimeErr::NotValidParam),
+                Some(pos) => index_args.push((pos, val)),
             }
         }
-        let param_names = self.param_names.as_ref().unwrap();
-        let param_len = param_names.len();
+        self.call_with_indexs(context, pos_args, index_args, func_type)
+    }
+
+    // Call with the dict arguments that have been resolved to the parameter positions.
+    pub fn call_with_indexs(
+        &mut self,
+        context: &mut dyn Ctx<'a>,
+        pos_args: Vec<PineRef<'a>>,
+        dict_args: Vec<(usize, PineRef<'a>)>,
+        func_type: FunctionType<'a>,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        self.init_param_names(&func_type);
+        let param_len = self.param_names.as_ref().unwrap().len();
         if pos_args.len() > param_len {
             return Err(RuntimeErr::NotValidParam);
         }
@@ -329,13 +352,11 @@ impl<'a> Callable<'a> {
         for (i, val) in pos_args.into_iter().enumerate() {
             all_args[i] = Some(val);
         }
-        for (name, val) in dict_args.into_iter() {
-            match param_names.iter().position(|&v| name == v) {
-                None => return Err(RuntimeErr::NotValidParam),
-                Some(pos) => {
-                    all_args[pos] = Some(val);
-                }
+        for (pos, val) in dict_args.into_iter() {
+            if pos >= param_len {
+                return Err(RuntimeErr::NotValidParam);
             }
+            all_args[pos] = Some(val);
         }
         if let Some(func) = self.func {
             func(context, all_args, func_type)
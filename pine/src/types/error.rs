@@ -18,4 +18,8 @@ pub enum RuntimeErr {
     Break,
 
     ForRangeIndexIsNA, // The index of for-range is na
+
+    Cancelled(i32), // The execution is cancelled, carry the index of the last completed bar.
+
+    NegativeHistoryIndex(i64), // The history reference like close[n] is negative.
 }
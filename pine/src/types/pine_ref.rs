@@ -74,6 +74,23 @@ impl<'a> PineRef<'a> {
             PineRef::Rc(ref item) => item.borrow().copy(),
         }
     }
+
+    // Check if the Rc object is referred by other PineRef.
+    pub fn is_shared(&self) -> bool {
+        match *self {
+            PineRef::Box(_) => false,
+            PineRef::Rc(ref item) => Rc::strong_count(item) > 1,
+        }
+    }
+
+    // Copy on write, the inner object is only copied if it is shared with other references.
+    pub fn into_unique(self) -> PineRef<'a> {
+        if self.is_shared() {
+            self.copy_inner()
+        } else {
+            self
+        }
+    }
 }
 
 impl<'a> PineType<'a> for PineRef<'a> {
@@ -111,3 +128,30 @@ impl<'a> DerefMut for PineRef<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Float, Series};
+
+    #[test]
+    fn into_unique_test() {
+        let mut series: Series<Float> = Series::from(Some(1f64));
+        series.commit();
+        let val = PineRef::new_rc(series);
+        assert!(!val.is_shared());
+        let ptr = val.as_ptr();
+
+        // The unique value is moved without copying.
+        let val = val.into_unique();
+        assert_eq!(val.as_ptr(), ptr);
+
+        // The shared value is copied.
+        let shared = val.clone();
+        assert!(val.is_shared());
+        let copied = shared.into_unique();
+        assert_ne!(copied.as_ptr(), ptr);
+        assert!(!val.is_shared());
+        assert!(!copied.is_shared());
+    }
+}
@@ -7,6 +7,7 @@ use super::traits::{
     Arithmetic, Category, Comparator, ComplexType, DataType, Negative, PineClass, PineFrom,
     PineStaticType, PineType, SecondType, SimpleType,
 };
+use std::borrow::Cow;
 
 // pine int type
 pub type Int = Option<i64>;
@@ -20,6 +21,36 @@ impl Negative<Int> for Int {
     }
 }
 
+impl Comparator for Int {
+    fn gt(self, other: Self) -> bool {
+        match (self, other) {
+            (Some(v1), Some(v2)) => v1 > v2,
+            _ => false,
+        }
+    }
+
+    fn ge(self, other: Self) -> bool {
+        match (self, other) {
+            (Some(v1), Some(v2)) => v1 >= v2,
+            _ => false,
+        }
+    }
+
+    fn lt(self, other: Self) -> bool {
+        match (self, other) {
+            (Some(v1), Some(v2)) => v1 < v2,
+            _ => false,
+        }
+    }
+
+    fn le(self, other: Self) -> bool {
+        match (self, other) {
+            (Some(v1), Some(v2)) => v1 <= v2,
+            _ => false,
+        }
+    }
+}
+
 impl Arithmetic for Int {
     fn add(self, other: Self) -> Self {
         match (self, other) {
@@ -346,9 +377,40 @@ impl<'a> PineFrom<'a, Bool> for Bool {
 
 impl SimpleType for Bool {}
 
-// pine color type
+// pine color type, the color literals borrow the source and the computed colors own the value.
 #[derive(Debug, PartialEq, Clone, Default)]
-pub struct Color<'a>(pub &'a str);
+pub struct Color<'a>(pub Cow<'a, str>);
+
+impl<'a> Color<'a> {
+    pub fn new(s: &'a str) -> Color<'a> {
+        Color(Cow::Borrowed(s))
+    }
+
+    pub fn new_owned(s: String) -> Color<'a> {
+        Color(Cow::Owned(s))
+    }
+
+    // Parse the `#RRGGBB` or `#RRGGBBAA` color to the rgba channels, `None` for the na color.
+    pub fn to_rgba(&self) -> Option<[u8; 4]> {
+        let hex = self.0.strip_prefix('#')?;
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+        let alpha = if hex.len() == 8 { channel(3)? } else { 255 };
+        Some([channel(0)?, channel(1)?, channel(2)?, alpha])
+    }
+
+    // Generate the color from the rgba channels, the alpha is omitted when the color is opaque.
+    pub fn from_rgba(rgba: [u8; 4]) -> Color<'a> {
+        let [r, g, b, a] = rgba;
+        if a == 255 {
+            Color::new_owned(format!("#{:02X}{:02X}{:02X}", r, g, b))
+        } else {
+            Color::new_owned(format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a))
+        }
+    }
+}
 impl<'a> PineStaticType for Color<'a> {
     fn static_type() -> (DataType, SecondType) {
         (DataType::Color, SecondType::Simple)
@@ -372,7 +434,7 @@ impl<'a> PineFrom<'a, Color<'a>> for Color<'a> {
                 let f: RefData<Series<Color>> = downcast_pf::<Series<Color>>(t).unwrap();
                 Ok(RefData::new_box(f.get_current()))
             }
-            (DataType::NA, _) => Ok(RefData::new_box(Color(""))),
+            (DataType::NA, _) => Ok(RefData::new_box(Color::new(""))),
             _ => Err(RuntimeErr::UnknownRuntimeErr),
         }
     }
@@ -683,8 +745,23 @@ mod tests {
     fn color_test() {
         assert_eq!(Color::static_type(), (DataType::Color, SecondType::Simple));
         assert_eq!(
-            Color::get_type(&Color("")),
+            Color::get_type(&Color::new("")),
             (DataType::Color, SecondType::Simple)
         );
     }
+
+    #[test]
+    fn color_rgba_test() {
+        assert_eq!(Color::new("#FF8000").to_rgba(), Some([255, 128, 0, 255]));
+        assert_eq!(Color::new("#ff800080").to_rgba(), Some([255, 128, 0, 128]));
+        assert_eq!(Color::new("").to_rgba(), None);
+        assert_eq!(Color::new("#FF80").to_rgba(), None);
+        assert_eq!(Color::new("#GG8000").to_rgba(), None);
+
+        assert_eq!(Color::from_rgba([255, 128, 0, 255]), Color::new("#FF8000"));
+        assert_eq!(
+            Color::from_rgba([255, 128, 0, 128]),
+            Color::new("#FF800080")
+        );
+    }
 }
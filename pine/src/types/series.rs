@@ -4,8 +4,8 @@ use super::pine_ref::PineRef;
 use super::primitive::{Bool, Color, Float, Int, NA};
 use super::ref_data::RefData;
 use super::traits::{
-    Arithmetic, Category, ComplexType, DataType, Negative, PineFrom, PineStaticType, PineType,
-    SecondType,
+    Arithmetic, Category, Comparator, ComplexType, DataType, Negative, PineFrom, PineStaticType,
+    PineType, SecondType,
 };
 use std::cmp::{Ordering, PartialEq, PartialOrd};
 use std::convert::{From, Into};
@@ -282,6 +282,24 @@ impl<'a, D: Clone + Negative<D> + Debug + 'a> Negative<Series<'a, D>> for Series
     }
 }
 
+impl<'a, D: Clone + Comparator + Debug + 'a> Comparator for Series<'a, D> {
+    fn gt(self, other: Self) -> bool {
+        self.current.gt(other.current)
+    }
+
+    fn ge(self, other: Self) -> bool {
+        self.current.ge(other.current)
+    }
+
+    fn lt(self, other: Self) -> bool {
+        self.current.lt(other.current)
+    }
+
+    fn le(self, other: Self) -> bool {
+        self.current.le(other.current)
+    }
+}
+
 impl<'a, D: Clone + Arithmetic + Debug + 'a> Arithmetic for Series<'a, D> {
     fn add(mut self, other: Self) -> Self {
         self.current = self.current.add(other.current);
@@ -52,6 +52,14 @@ impl<'a> SimpleCallableObject<'a> {
         self.obj.get(context, name)
     }
 
+    pub fn get_obj(
+        &self,
+        context: &mut dyn Ctx<'a>,
+        name: &str,
+    ) -> Result<PineRef<'a>, RuntimeErr> {
+        self.obj.get_obj(context, name)
+    }
+
     pub fn set(&self, name: &str, property: PineRef<'a>) -> Result<(), RuntimeErr> {
         self.obj.set(name, property)
     }
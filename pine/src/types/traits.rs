@@ -22,6 +22,8 @@ pub enum DataType {
     String,
     Line,
     Label,
+    Polyline,
+    ChartPoint,
     NA,
     PineVar,
     Tuple,
@@ -150,6 +152,12 @@ pub trait PineClass<'a> {
 
     fn get(&self, context: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr>;
 
+    // Get the property that is the object of the next prefix, e.g. `b` of `a.b.c`. The property
+    // like `strategy.opentrades` is a value, and also an object when it's followed by a prefix.
+    fn get_obj(&self, context: &mut dyn Ctx<'a>, name: &str) -> Result<PineRef<'a>, RuntimeErr> {
+        self.get(context, name)
+    }
+
     fn set(&self, _name: &str, _property: PineRef<'a>) -> Result<(), RuntimeErr> {
         Err(RuntimeErr::NotSupportOperator)
     }
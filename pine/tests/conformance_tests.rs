@@ -0,0 +1,210 @@
+#![cfg(feature = "runtime")]
+
+// Golden-file conformance tests.
+//
+// Every sub directory of `tests/conformance` is one test case that contains:
+// - `script.pine`: the script to run.
+// - `input.csv`: the input bars, the header names the sources(time, open, high, low, close, volume).
+// - `expected.csv`: the expected outputs, one column for each output series in the order of the
+//   plot calls. An empty cell or `na` means the value is na.
+//
+// The values are compared within the tolerance `1e-6`, the script can change it by the comment
+// `//@tolerance=<value>`.
+extern crate pine;
+use pine::runtime::data_src::NoneCallback;
+use pine::runtime::AnySeries;
+use pine::PineScript;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+struct Csv {
+    header: Vec<String>,
+    rows: Vec<Vec<Option<f64>>>,
+}
+
+fn parse_cell(cell: &str, path: &Path) -> Option<f64> {
+    let cell = cell.trim();
+    if cell.is_empty() || cell == "na" || cell == "NaN" {
+        None
+    } else {
+        match cell.parse::<f64>() {
+            Ok(v) => Some(v),
+            Err(_) => panic!("{}: invalid number {:?}", path.display(), cell),
+        }
+    }
+}
+
+fn read_csv(path: &Path) -> Csv {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("{}: cannot read the file, {}", path.display(), e));
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = match lines.next() {
+        Some(line) => line.split(',').map(|s| String::from(s.trim())).collect(),
+        None => panic!("{}: the header is missing", path.display()),
+    };
+    let rows: Vec<Vec<Option<f64>>> = lines
+        .map(|line| line.split(',').map(|c| parse_cell(c, path)).collect())
+        .collect();
+    for (i, row) in rows.iter().enumerate() {
+        assert_eq!(
+            row.len(),
+            header.len(),
+            "{}: row {} has {} cells but the header has {}",
+            path.display(),
+            i + 1,
+            row.len(),
+            header.len()
+        );
+    }
+    Csv { header, rows }
+}
+
+fn column(csv: &Csv, index: usize) -> Vec<Option<f64>> {
+    csv.rows.iter().map(|row| row[index]).collect()
+}
+
+fn input_name(name: &str) -> &'static str {
+    match name {
+        "time" => "time",
+        "open" => "open",
+        "high" => "high",
+        "low" => "low",
+        "close" => "close",
+        "volume" => "volume",
+        _ => panic!("Unsupported input column {:?}", name),
+    }
+}
+
+fn gen_input(csv: &Csv) -> Vec<(&'static str, AnySeries)> {
+    csv.header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let name = input_name(name);
+            let values = column(csv, i);
+            let series = match name {
+                "time" | "volume" => AnySeries::from_int_vec(
+                    values.into_iter().map(|v| v.map(|v| v as i64)).collect(),
+                ),
+                _ => AnySeries::from_float_vec(values),
+            };
+            (name, series)
+        })
+        .collect()
+}
+
+fn parse_tolerance(script: &str) -> f64 {
+    script
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("//@tolerance="))
+        .map(|v| v.trim().parse::<f64>().expect("Invalid tolerance"))
+        .next()
+        .unwrap_or(DEFAULT_TOLERANCE)
+}
+
+fn value_eq(actual: Option<f64>, expected: Option<f64>, tolerance: f64) -> bool {
+    match (actual, expected) {
+        (None, None) => true,
+        (Some(a), None) | (None, Some(a)) => a.is_nan(),
+        (Some(a), Some(e)) => (a - e).abs() <= tolerance * e.abs().max(1f64),
+    }
+}
+
+// Run one test case and return the mismatch messages.
+fn run_case(dir: &Path) -> Vec<String> {
+    let script = fs::read_to_string(dir.join("script.pine"))
+        .unwrap_or_else(|e| panic!("{}: cannot read script.pine, {}", dir.display(), e));
+    let input = read_csv(&dir.join("input.csv"));
+    let expected = read_csv(&dir.join("expected.csv"));
+    let tolerance = parse_tolerance(&script);
+
+    let callback = NoneCallback();
+    let mut pine = PineScript::new(Some(&callback));
+    if let Err(errs) = pine.parse_src(script) {
+        return vec![format!("parse errors {:?}", errs)];
+    }
+    let output = match pine.run_with_data(gen_input(&input), None) {
+        Ok(output) => output,
+        Err(err) => return vec![format!("runtime error {:?}", err)],
+    };
+
+    let actual: Vec<Vec<Option<f64>>> = output
+        .data_list
+        .into_iter()
+        .flatten()
+        .flat_map(|d| d.series)
+        .collect();
+    let mut errs = vec![];
+    if actual.len() != expected.header.len() {
+        errs.push(format!(
+            "expected {} output series but got {}",
+            expected.header.len(),
+            actual.len()
+        ));
+    }
+    for (i, (name, series)) in expected.header.iter().zip(actual.iter()).enumerate() {
+        let expected_series = column(&expected, i);
+        if series.len() != expected_series.len() {
+            errs.push(format!(
+                "{}: expected {} values but got {}",
+                name,
+                expected_series.len(),
+                series.len()
+            ));
+            continue;
+        }
+        for (bar, (a, e)) in series.iter().zip(expected_series.iter()).enumerate() {
+            if !value_eq(*a, *e, tolerance) {
+                errs.push(format!(
+                    "{}: bar {} expected {:?} but got {:?}",
+                    name, bar, e, a
+                ));
+            }
+        }
+    }
+    errs
+}
+
+fn case_dirs() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&root)
+        .unwrap()
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+#[test]
+fn conformance_test() {
+    let dirs = case_dirs();
+    assert!(!dirs.is_empty());
+
+    let failures: Vec<String> = dirs
+        .iter()
+        .flat_map(|dir| {
+            let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+            run_case(dir)
+                .into_iter()
+                .map(move |err| format!("[{}] {}", name, err))
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "Conformance failures:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn value_eq_test() {
+    assert!(value_eq(None, None, DEFAULT_TOLERANCE));
+    assert!(value_eq(Some(f64::NAN), None, DEFAULT_TOLERANCE));
+    assert!(value_eq(Some(1.0000001), Some(1f64), DEFAULT_TOLERANCE));
+    assert!(!value_eq(Some(1.1), Some(1f64), DEFAULT_TOLERANCE));
+    assert!(!value_eq(Some(1f64), None, DEFAULT_TOLERANCE));
+    assert_eq!(parse_tolerance("//@tolerance=0.01\nplot(close)"), 0.01);
+}
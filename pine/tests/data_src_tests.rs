@@ -1,5 +1,9 @@
+#![cfg(feature = "runtime")]
+
 extern crate pine;
 use pine::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
+use pine::libs::array;
+use pine::libs::nz;
 use pine::libs::plot;
 use pine::libs::print;
 use pine::runtime::data_src::{Callback, DataSrc, NoneCallback};
@@ -88,6 +92,47 @@ fn func_call_test() {
     );
 }
 
+const TYPED_FUNC_SCRIPT: &str = "
+f(float x, series int n) => x / 2 + n
+plot(f(1, 1))
+plot(f(close, 2))
+";
+
+#[test]
+fn typed_func_call_test() {
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    parser.parse_src(String::from(TYPED_FUNC_SCRIPT)).unwrap();
+    let data = vec![(
+        "close",
+        AnySeries::from_float_vec(vec![Some(2f64), Some(4f64)]),
+    )];
+    let out_data = parser.run_with_data(data, None).unwrap();
+    assert_eq!(
+        out_data.data_list,
+        vec![
+            Some(OutputData::new(vec![vec![Some(1.5f64), Some(1.5f64)]])),
+            Some(OutputData::new(vec![vec![Some(3f64), Some(4f64)]])),
+        ]
+    );
+
+    // The series argument can't be passed to the simple parameter.
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    assert!(parser
+        .parse_src(String::from(
+            "f(simple float x) => x
+plot(f(close))"
+        ))
+        .is_err());
+}
+
 const IF_ELSE_SCRIPT: &str = "
 m = if close > open
     s = close
@@ -169,6 +214,150 @@ fn for_range_test() {
     assert!(parser.run_with_data(data, None).is_ok());
 }
 
+const FOR_IN_SCRIPT: &str = "
+float total = 0
+for x in [1.0, 2.0, close]
+    if x == 2
+        continue
+    if x > 2
+        break
+    total := total + x
+plot(total)
+
+int index_sum = 0
+for [i, s] in [\"a\", \"b\"]
+    index_sum := index_sum + i
+plot(index_sum)
+
+last = for [i, x] in [close, 1.0]
+    i * x
+plot(last)
+
+float window_sum = 0
+for x in array.from_series(close, 2)
+    window_sum := window_sum + nz(x)
+plot(window_sum)
+";
+
+#[test]
+fn for_in_test() {
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var(), nz::declare_var(), array::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    parser.parse_src(String::from(FOR_IN_SCRIPT)).unwrap();
+    let data = vec![(
+        "close",
+        AnySeries::from_float_vec(vec![Some(1f64), Some(3f64)]),
+    )];
+    let out_data = parser.run_with_data(data, None).unwrap();
+    assert_eq!(
+        out_data.data_list,
+        vec![
+            Some(OutputData::new(vec![vec![Some(2f64), Some(1f64)]])),
+            Some(OutputData::new(vec![vec![Some(1f64), Some(1f64)]])),
+            Some(OutputData::new(vec![vec![Some(1f64), Some(1f64)]])),
+            Some(OutputData::new(vec![vec![Some(1f64), Some(4f64)]])),
+        ]
+    );
+
+    // Only the arrays can be iterated.
+    let lib_info = pine::LibInfo::new(vec![], vec![]);
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    assert!(parser
+        .parse_src(String::from("for x in 1\n    x\n"))
+        .is_err());
+}
+
+const LOOP_VALUE_SCRIPT: &str = "
+v1 = for i = 1 to 5
+    if i == 3
+        break
+    i * 2
+plot(v1)
+
+v2 = for i = 1 to 5
+    if i == 2 or i == 4
+        continue
+    i
+plot(v2)
+
+v3 = for i = 0 to 10 by -1
+    i
+plot(v3)
+
+float sum = 0
+v4 = for i = 1 to 5
+    if i == 3
+        break
+    sum := sum + i
+plot(v4)
+plot(sum)
+";
+
+#[test]
+fn loop_value_test() {
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    parser.parse_src(String::from(LOOP_VALUE_SCRIPT)).unwrap();
+    let data = vec![("close", AnySeries::from_float_vec(vec![Some(1f64)]))];
+    let out_data = parser.run_with_data(data, None).unwrap();
+    // The loop returns the value of the last completed iteration, or na if there is none.
+    assert_eq!(
+        out_data.data_list,
+        vec![
+            Some(OutputData::new(vec![vec![Some(4f64)]])),
+            Some(OutputData::new(vec![vec![Some(5f64)]])),
+            Some(OutputData::new(vec![vec![None]])),
+            Some(OutputData::new(vec![vec![Some(3f64)]])),
+            Some(OutputData::new(vec![vec![Some(3f64)]])),
+        ]
+    );
+}
+
+const VAR_NA_SCRIPT: &str = "
+var float sum = na
+sum := nz(sum) + close
+var int count = na
+count := nz(count) + 1
+plot(sum)
+plot(count)
+";
+
+#[test]
+fn var_na_test() {
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var(), nz::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    parser.parse_src(String::from(VAR_NA_SCRIPT)).unwrap();
+    let data = vec![(
+        "close",
+        AnySeries::from_float_vec(vec![Some(1f64), Some(3f64), Some(5f64)]),
+    )];
+    let out_data = parser.run_with_data(data, None).unwrap();
+    assert_eq!(
+        out_data.data_list,
+        vec![
+            Some(OutputData::new(vec![vec![
+                Some(1f64),
+                Some(4f64),
+                Some(9f64)
+            ]])),
+            Some(OutputData::new(vec![vec![
+                Some(1f64),
+                Some(2f64),
+                Some(3f64)
+            ]])),
+        ]
+    );
+}
+
 const EMA_SCRIPT: &str = "
 pine_ema(x, y) =>
     alpha = 2 / (y + 1)
@@ -599,15 +788,15 @@ fn kc_test() {
     let data = vec![
         (
             "close",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64), Some(15f64), Some(30f64)]),
         ),
         (
             "high",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64), Some(15f64), Some(30f64)]),
         ),
         (
             "low",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64), Some(15f64), Some(30f64)]),
         ),
     ];
 
@@ -666,15 +855,36 @@ fn kcw_test() {
     let data = vec![
         (
             "close",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![
+                Some(10f64),
+                Some(20f64),
+                Some(15f64),
+                Some(30f64),
+                Some(25f64),
+                Some(40f64),
+            ]),
         ),
         (
             "high",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![
+                Some(10f64),
+                Some(20f64),
+                Some(15f64),
+                Some(30f64),
+                Some(25f64),
+                Some(40f64),
+            ]),
         ),
         (
             "low",
-            AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
+            AnySeries::from_float_vec(vec![
+                Some(10f64),
+                Some(20f64),
+                Some(15f64),
+                Some(30f64),
+                Some(25f64),
+                Some(40f64),
+            ]),
         ),
     ];
 
@@ -713,10 +923,9 @@ fn macd_example_test() {
     );
     let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
     parser.parse_src(String::from(MACD_EXAMPLE_SCRIPT)).unwrap();
-    let data = vec![(
-        "close",
-        AnySeries::from_float_vec(vec![Some(10f64), Some(20f64)]),
-    )];
+    // The signal line starts after the 26 bars of the slow ema and the 9 bars of the signal.
+    let closes: Vec<_> = (0..40).map(|i| Some(10f64 + (i % 7) as f64)).collect();
+    let data = vec![("close", AnySeries::from_float_vec(closes))];
 
     assert!(parser.run_with_data(data, None).is_ok());
 
@@ -1379,6 +1588,7 @@ plot(close, title='Histogram',
 
 #[test]
 fn myplot_test() {
+    use pine::libs::nz;
     use pine::libs::plot;
     use pine::runtime::NoneCallback;
 
@@ -1519,6 +1729,7 @@ plot(volume)
 
 #[test]
 fn volume_test() {
+    use pine::libs::nz;
     use pine::libs::plot;
     use pine::runtime::output::{OutputData, ScriptPurpose, StudyScript};
     use pine::runtime::NoneCallback;
@@ -1604,6 +1815,79 @@ fn dayofweek_test() {
     );
 }
 
+const TIME_CLOSE_SCRIPT: &'static str = r#"
+plot(time_close)
+plot(time_tradingday)
+"#;
+
+#[test]
+fn time_close_test() {
+    use pine::runtime::output::SymbolInfo;
+    use std::rc::Rc;
+
+    let mut parser = pine::PineScript::new(Some(&NoneCallback()));
+    parser.parse_src(String::from(TIME_CLOSE_SCRIPT)).unwrap();
+    let hour = 3600_000i64;
+    // The hourly bars of the session 17:00-16:00 Chicago time from 14:00 to 18:00 on 2020-01-20.
+    let start = 1579550400000i64;
+    let times = vec![start, start + hour, start + 3 * hour, start + 4 * hour];
+    let syminfo = Rc::new(SymbolInfo {
+        symbol_type: String::from("futures"),
+        timezone: String::from("America/Chicago"),
+        ticker: String::from("CME:ES1!"),
+        session: String::from("regular"),
+        trade_start: String::from("17:00"),
+        trade_end: String::from("16:00"),
+        root: Some(String::from("ES")),
+        currency: String::from("USD"),
+        description: String::from("des"),
+        mintick: 0.25,
+    });
+    let result = parser.run_with_data(
+        vec![(
+            "_time",
+            AnySeries::from_int_vec(times.iter().map(|t| Some(*t)).collect()),
+        )],
+        Some(syminfo),
+    );
+    let (day1, day2) = (1579478400000f64, 1579564800000f64);
+    // The bar before the session break closes at 16:00 and the last bar closes after one hour.
+    let closes = vec![
+        start + hour,
+        start + 2 * hour,
+        start + 4 * hour,
+        start + 5 * hour,
+    ];
+    assert_eq!(
+        result.unwrap().data_list,
+        vec![
+            Some(OutputData::new(vec![closes
+                .iter()
+                .map(|t| Some(*t as f64))
+                .collect()])),
+            Some(OutputData::new(vec![vec![
+                Some(day1),
+                Some(day1),
+                Some(day2),
+                Some(day2)
+            ]])),
+        ]
+    );
+
+    // The realtime bar keeps the interval of the history bars.
+    let result = parser.update(vec![(
+        "_time",
+        AnySeries::from_int_vec(vec![Some(start + 4 * hour), Some(start + 5 * hour)]),
+    )]);
+    assert_eq!(
+        result.unwrap().data_list[0],
+        Some(OutputData::new(vec![vec![
+            Some((start + 5 * hour) as f64),
+            Some((start + 6 * hour) as f64)
+        ]]))
+    );
+}
+
 const RUN1_SCRIPT: &'static str = r#"
 study(title="VWAPG", shorttitle="VWAPG")
 src = (high + low + open)/3
@@ -1865,7 +2149,7 @@ fn chaikin_test() {
     assert!(out_data.as_ref().unwrap().data_list[0]
         .as_ref()
         .unwrap()
-        .series[0][0]
+        .series[0][1]
         .is_some());
 }
 
@@ -1968,3 +2252,47 @@ fn alma_time_test() {
             .is_some());
     }
 }
+
+const LAZY_CONDITION_SCRIPT: &str = "g(v) =>
+    s = v + 1
+    s[1]
+m = close > 0 ? 1.0 : array.indexof(array.new<float>(-1), 1.0)
+n = close > 1 ? g(close) : 0.0
+plot(m)
+plot(n)
+";
+
+#[test]
+fn condition_lazy_test() {
+    let lib_info = pine::LibInfo::new(
+        vec![plot::declare_var(), array::declare_var()],
+        vec![("close", SyntaxType::Series(SimpleSyntaxType::Float))],
+    );
+    let mut parser = pine::PineScript::new_with_libinfo(lib_info, Some(&NoneCallback()));
+    parser
+        .parse_src(String::from(LAZY_CONDITION_SCRIPT))
+        .unwrap();
+    let data = vec![(
+        "close",
+        AnySeries::from_float_vec(vec![Some(2f64), Some(1f64), Some(3f64)]),
+    )];
+    // The untaken branch is not evaluated, so the invalid array size never raises the error,
+    // and the history of `g` only contains the values of the bars that call it.
+    let out_data = parser.run_with_data(data, None).unwrap();
+    assert_eq!(
+        out_data.data_list,
+        vec![
+            Some(OutputData::new(vec![vec![
+                Some(1f64),
+                Some(1f64),
+                Some(1f64)
+            ]])),
+            Some(OutputData::new(vec![vec![None, Some(0f64), Some(3f64)]])),
+        ]
+    );
+
+    // Both branches are still type-checked.
+    assert!(parser
+        .parse_src(String::from("m = close > 0 ? 1.0 : nothing\nplot(m)"))
+        .is_err());
+}
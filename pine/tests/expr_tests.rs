@@ -139,6 +139,40 @@ fn var_assign_test() {
     );
 }
 
+const INVALID_VAR_ASSIGN: &str = "[a, b] := f()
+a.b := 1
+a[1] := 2
+c = 3
+";
+
+#[test]
+fn invalid_var_assign_test() {
+    use pine::ast::error::PineErrorKind;
+    use pine::ast::state::PineInputError;
+
+    let res = pine::parse_ast(INVALID_VAR_ASSIGN);
+    let (blk, errs) = res.unwrap_err();
+    assert_eq!(
+        errs,
+        vec![
+            PineInputError::new(
+                PineErrorKind::TupleVarAssign,
+                StrRange::from_start("[a, b]", Position::new(0, 0))
+            ),
+            PineInputError::new(
+                PineErrorKind::InvalidVarAssignTarget,
+                StrRange::from_start("a.b", Position::new(1, 0))
+            ),
+            PineInputError::new(
+                PineErrorKind::InvalidVarAssignTarget,
+                StrRange::from_start("a[1]", Position::new(2, 0))
+            ),
+        ]
+    );
+    // The statements after the invalid reassignment are still parsed.
+    assert_eq!(blk.unwrap().stmts.len(), 1);
+}
+
 const VAR_DECLARATION: &str = "m = true
 bool m = true
 var m = true
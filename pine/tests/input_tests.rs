@@ -1,3 +1,5 @@
+#![cfg(feature = "runtime")]
+
 extern crate pine;
 use pine::ast::syntax_type::{SimpleSyntaxType, SyntaxType};
 use pine::libs::input;